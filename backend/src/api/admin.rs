@@ -2,27 +2,116 @@ use actix_multipart::Multipart;
 use actix_web::{delete, get, post, put, web, HttpRequest, HttpResponse, Responder};
 use calamine::Reader;
 use futures_util::StreamExt;
+use sqlx::PgPool;
 use uuid::Uuid;
 
+use crate::config::Config;
 use crate::db::AppState;
-use crate::models::CurrentUser;
+use crate::models::{
+    AssignRoleRequest, CreateRoleRequest, CurrentUser, Permission, PermissionInfo,
+    UpdateRoleRequest, UserPermissions,
+};
 use crate::models::{
     BatchDeleteCoursesRequest, BatchDeleteTeachersRequest, BatchImportCourseItem,
-    BatchImportCoursesRequest, BatchImportTeacherItem, BatchImportTeachersRequest, CourseListQuery,
-    CreateCourseRequest, CreateTeacherRequest, TeacherListQuery, UpdateCourseRequest,
+    BatchImportCoursesRequest, BatchImportTeacherItem, BatchImportTeachersRequest, ClaimListQuery,
+    CourseListQuery, CreateCourseRequest, CreateStorageMigrationRequest, CreateTeacherRequest,
+    ReportAction, ReportListQuery, ReportTargetType, ResolveReportRequest, ReviewClaimRequest,
+    ReviewVerificationRequest, StorageMigrationJobResponse, TeacherListQuery, UpdateCourseRequest,
     UpdateCourseStatusRequest, UpdateTeacherRequest, UpdateTeacherStatusRequest,
+    VerificationListQuery,
 };
 use crate::services::{
-    AdminError, AdminService, AuditLogQuery, AuditLogService, AuditResourceRequest, CourseError,
-    CourseService, FavoriteService, ResourceError, ResourceService, TeacherError, TeacherService,
-    UpdateUserStatusRequest,
+    AdminError, AdminService, AuditAction, AuditLogQuery, AuditLogService, AuditResourceRequest,
+    ClaimService, CourseError, CourseService, FavoriteService, PermissionError, PermissionService,
+    ReportError, ReportService, ResourceError, ResourceService, StorageMigrationService,
+    TeacherError, TeacherService, UpdateUserStatusRequest, VerificationError, VerificationService,
 };
+use crate::tasks;
 use crate::utils::{bad_request, conflict, forbidden, internal_error, no_content, not_found};
 
-/// 检查用户是否是管理员
-fn check_admin(current_user: &CurrentUser) -> Result<(), AdminError> {
-    if !matches!(current_user.role, crate::models::UserRole::Admin) {
-        return Err(AdminError::Forbidden("需要管理员权限".to_string()));
+/// 检查是否具有全站范围的权限
+fn check_permission(
+    permissions: &UserPermissions,
+    permission: Permission,
+) -> Result<(), AdminError> {
+    if !permissions.has(permission) {
+        return Err(AdminError::Forbidden(format!(
+            "需要 {} 权限",
+            permission.as_str()
+        )));
+    }
+    Ok(())
+}
+
+/// 检查是否具有任意范围的权限，列表接口再按课程范围筛选
+fn check_any_permission(
+    permissions: &UserPermissions,
+    permission: Permission,
+) -> Result<(), AdminError> {
+    if !permissions.has_any(permission) {
+        return Err(AdminError::Forbidden(format!(
+            "需要 {} 权限",
+            permission.as_str()
+        )));
+    }
+    Ok(())
+}
+
+/// 检查对课程的权限：全站权限或该课程的课程范围权限
+fn check_course_permission(
+    permissions: &UserPermissions,
+    permission: Permission,
+    course_sn: i64,
+) -> Result<(), AdminError> {
+    if !permissions.has_for_courses(permission, &[course_sn]) {
+        return Err(AdminError::Forbidden(format!(
+            "需要该课程的 {} 权限",
+            permission.as_str()
+        )));
+    }
+    Ok(())
+}
+
+/// 检查对资源的权限：全站权限或资源关联课程的课程范围权限
+async fn check_resource_permission(
+    pool: &PgPool,
+    permissions: &UserPermissions,
+    permission: Permission,
+    resource_id: Uuid,
+) -> Result<(), AdminError> {
+    if permissions.has(permission) {
+        return Ok(());
+    }
+    let course_sns = PermissionService::resource_course_sns(pool, resource_id)
+        .await
+        .map_err(|e| AdminError::DatabaseError(e.to_string()))?;
+    if !permissions.has_for_courses(permission, &course_sns) {
+        return Err(AdminError::Forbidden(format!(
+            "需要该资源所属课程的 {} 权限",
+            permission.as_str()
+        )));
+    }
+    Ok(())
+}
+
+/// 检查对评论的权限：全站权限或评论所属资源关联课程的课程范围权限
+async fn check_comment_permission(
+    pool: &PgPool,
+    permissions: &UserPermissions,
+    permission: Permission,
+    comment_id: Uuid,
+) -> Result<(), AdminError> {
+    if permissions.has(permission) {
+        return Ok(());
+    }
+    let course_sns = PermissionService::comment_course_sns(pool, comment_id)
+        .await
+        .map_err(|e| AdminError::DatabaseError(e.to_string()))?;
+    if !permissions.has_for_courses(permission, &course_sns) {
+        return Err(AdminError::Forbidden(format!(
+            "需要该评论所属课程的 {} 权限",
+            permission.as_str()
+        )));
     }
     Ok(())
 }
@@ -79,16 +168,57 @@ fn handle_resource_error(err: ResourceError) -> HttpResponse {
     }
 }
 
+/// 将VerificationError转换为HttpResponse
+fn handle_verification_error(err: VerificationError) -> HttpResponse {
+    match err {
+        VerificationError::NotFound(msg) => not_found(&msg),
+        VerificationError::ValidationError(msg) => bad_request(&msg),
+        VerificationError::Conflict(msg) => conflict(&msg),
+        VerificationError::DatabaseError(msg) => {
+            log::error!("[Admin] 实名认证服务数据库错误 | error={}", msg);
+            internal_error("服务器内部错误")
+        }
+    }
+}
+
+/// 将ReportError转换为HttpResponse
+fn handle_report_error(err: ReportError) -> HttpResponse {
+    match err {
+        ReportError::NotFound(msg) => not_found(&msg),
+        ReportError::ValidationError(msg) => bad_request(&msg),
+        ReportError::Conflict(msg) => conflict(&msg),
+        ReportError::DatabaseError(msg) => {
+            log::error!("[Admin] 举报服务数据库错误 | error={}", msg);
+            internal_error("服务器内部错误")
+        }
+    }
+}
+
+/// 将PermissionError转换为HttpResponse
+fn handle_permission_error(err: PermissionError) -> HttpResponse {
+    match err {
+        PermissionError::NotFound(msg) => not_found(&msg),
+        PermissionError::ValidationError(msg) => bad_request(&msg),
+        PermissionError::Forbidden(msg) => forbidden(&msg),
+        PermissionError::Conflict(msg) => conflict(&msg),
+        PermissionError::DatabaseError(msg) => {
+            log::error!("[Admin] 权限服务数据库错误 | error={}", msg);
+            internal_error("服务器内部错误")
+        }
+    }
+}
+
 /// 获取仪表盘统计数据
 #[get("/admin/dashboard")]
 async fn get_dashboard(
     data: web::Data<AppState>,
     current_user: actix_web::web::ReqData<CurrentUser>,
+    permissions: web::ReqData<UserPermissions>,
 ) -> impl Responder {
     let user = current_user.into_inner();
     log::info!("[Admin] 获取仪表盘数据 | admin_id={}", user.id);
 
-    if let Err(e) = check_admin(&user) {
+    if let Err(e) = check_permission(&permissions, Permission::DashboardView) {
         return handle_admin_error(e);
     }
 
@@ -103,12 +233,13 @@ async fn get_dashboard(
 async fn get_user_list(
     data: web::Data<AppState>,
     current_user: actix_web::web::ReqData<CurrentUser>,
+    permissions: web::ReqData<UserPermissions>,
     query: web::Query<std::collections::HashMap<String, String>>,
 ) -> impl Responder {
     let user = current_user.into_inner();
     log::info!("[Admin] 获取用户列表 | admin_id={}", user.id);
 
-    if let Err(e) = check_admin(&user) {
+    if let Err(e) = check_permission(&permissions, Permission::UserRead) {
         return handle_admin_error(e);
     }
 
@@ -132,13 +263,14 @@ async fn get_user_list(
 async fn update_user_status(
     data: web::Data<AppState>,
     current_user: actix_web::web::ReqData<CurrentUser>,
+    permissions: web::ReqData<UserPermissions>,
     path: web::Path<Uuid>,
     req: web::Json<UpdateUserStatusRequest>,
     http_req: HttpRequest,
 ) -> impl Responder {
     let user = current_user.into_inner();
 
-    if let Err(e) = check_admin(&user) {
+    if let Err(e) = check_permission(&permissions, Permission::UserManage) {
         return handle_admin_error(e);
     }
 
@@ -196,11 +328,13 @@ async fn update_user_status(
 async fn get_user_real_info(
     data: web::Data<AppState>,
     current_user: actix_web::web::ReqData<CurrentUser>,
+    permissions: web::ReqData<UserPermissions>,
     path: web::Path<Uuid>,
+    http_req: HttpRequest,
 ) -> impl Responder {
     let user = current_user.into_inner();
 
-    if let Err(e) = check_admin(&user) {
+    if let Err(e) = check_permission(&permissions, Permission::UserRealInfoRead) {
         return handle_admin_error(e);
     }
 
@@ -211,7 +345,16 @@ async fn get_user_real_info(
         user_id
     );
 
-    match AdminService::get_user_real_info(&data.pool, user_id).await {
+    let ip_address = http_req.peer_addr().map(|addr| addr.ip().to_string());
+    match AdminService::get_user_real_info(
+        &data.pool,
+        data.real_info_cipher.as_deref(),
+        user_id,
+        user.id,
+        ip_address.as_deref(),
+    )
+    .await
+    {
         Ok(real_info) => {
             log::info!(
                 "[Admin] 获取用户实名信息成功 | admin_id={}, target_user_id={}",
@@ -237,12 +380,13 @@ async fn get_user_real_info(
 async fn get_pending_resources(
     data: web::Data<AppState>,
     current_user: actix_web::web::ReqData<CurrentUser>,
+    permissions: web::ReqData<UserPermissions>,
     query: web::Query<std::collections::HashMap<String, String>>,
 ) -> impl Responder {
     let user = current_user.into_inner();
     log::info!("[Admin] 获取待审核资源列表 | admin_id={}", user.id);
 
-    if let Err(e) = check_admin(&user) {
+    if let Err(e) = check_any_permission(&permissions, Permission::ResourceAudit) {
         return handle_admin_error(e);
     }
 
@@ -255,7 +399,10 @@ async fn get_pending_resources(
         .and_then(|p| p.parse::<i32>().ok())
         .unwrap_or(20);
 
-    match AdminService::get_pending_resources(&data.pool, page, per_page).await {
+    let course_scope = permissions.course_scope(Permission::ResourceAudit);
+    match AdminService::get_pending_resources(&data.pool, page, per_page, course_scope.as_deref())
+        .await
+    {
         Ok(response) => HttpResponse::Ok().json(response),
         Err(e) => handle_admin_error(e),
     }
@@ -266,16 +413,24 @@ async fn get_pending_resources(
 async fn audit_resource(
     data: web::Data<AppState>,
     current_user: actix_web::web::ReqData<CurrentUser>,
+    permissions: web::ReqData<UserPermissions>,
     path: web::Path<Uuid>,
     req: web::Json<AuditResourceRequest>,
 ) -> impl Responder {
     let user = current_user.into_inner();
+    let resource_id = path.into_inner();
 
-    if let Err(e) = check_admin(&user) {
+    if let Err(e) = check_resource_permission(
+        &data.pool,
+        &permissions,
+        Permission::ResourceAudit,
+        resource_id,
+    )
+    .await
+    {
         return handle_admin_error(e);
     }
 
-    let resource_id = path.into_inner();
     log::info!(
         "[Admin] 审核资源 | admin_id={}, resource_id={}, status={}",
         user.id,
@@ -310,12 +465,13 @@ async fn audit_resource(
 async fn get_comment_list(
     data: web::Data<AppState>,
     current_user: actix_web::web::ReqData<CurrentUser>,
+    permissions: web::ReqData<UserPermissions>,
     query: web::Query<std::collections::HashMap<String, String>>,
 ) -> impl Responder {
     let user = current_user.into_inner();
     log::info!("[Admin] 获取评论列表 | admin_id={}", user.id);
 
-    if let Err(e) = check_admin(&user) {
+    if let Err(e) = check_any_permission(&permissions, Permission::CommentAudit) {
         return handle_admin_error(e);
     }
 
@@ -329,7 +485,16 @@ async fn get_comment_list(
         .unwrap_or(20);
     let audit_status = query.get("auditStatus").cloned();
 
-    match AdminService::get_comment_list(&data.pool, page, per_page, audit_status).await {
+    let course_scope = permissions.course_scope(Permission::CommentAudit);
+    match AdminService::get_comment_list(
+        &data.pool,
+        page,
+        per_page,
+        audit_status,
+        course_scope.as_deref(),
+    )
+    .await
+    {
         Ok(response) => HttpResponse::Ok().json(response),
         Err(e) => handle_admin_error(e),
     }
@@ -340,16 +505,24 @@ async fn get_comment_list(
 async fn delete_comment(
     data: web::Data<AppState>,
     current_user: actix_web::web::ReqData<CurrentUser>,
+    permissions: web::ReqData<UserPermissions>,
     path: web::Path<Uuid>,
     req: HttpRequest,
 ) -> impl Responder {
     let user = current_user.into_inner();
+    let comment_id = path.into_inner();
 
-    if let Err(e) = check_admin(&user) {
+    if let Err(e) = check_comment_permission(
+        &data.pool,
+        &permissions,
+        Permission::CommentDelete,
+        comment_id,
+    )
+    .await
+    {
         return handle_admin_error(e);
     }
 
-    let comment_id = path.into_inner();
     log::info!(
         "[Admin] 删除评论 | admin_id={}, comment_id={}",
         user.id,
@@ -394,16 +567,24 @@ async fn delete_comment(
 async fn audit_comment(
     data: web::Data<AppState>,
     current_user: actix_web::web::ReqData<CurrentUser>,
+    permissions: web::ReqData<UserPermissions>,
     path: web::Path<Uuid>,
     req: web::Json<std::collections::HashMap<String, String>>,
 ) -> impl Responder {
     let user = current_user.into_inner();
+    let comment_id = path.into_inner();
 
-    if let Err(e) = check_admin(&user) {
+    if let Err(e) = check_comment_permission(
+        &data.pool,
+        &permissions,
+        Permission::CommentAudit,
+        comment_id,
+    )
+    .await
+    {
         return handle_admin_error(e);
     }
 
-    let comment_id = path.into_inner();
     let status = req.get("status").cloned().unwrap_or_default();
     log::info!(
         "[Admin] 审核评论 | admin_id={}, comment_id={}, status={}",
@@ -432,6 +613,7 @@ async fn audit_comment(
 async fn send_notification(
     data: web::Data<AppState>,
     current_user: actix_web::web::ReqData<CurrentUser>,
+    permissions: web::ReqData<UserPermissions>,
     req: web::Json<crate::services::SendNotificationRequest>,
     http_req: HttpRequest,
 ) -> impl Responder {
@@ -442,7 +624,7 @@ async fn send_notification(
         req.title
     );
 
-    if let Err(e) = check_admin(&user) {
+    if let Err(e) = check_permission(&permissions, Permission::NotificationSend) {
         return handle_admin_error(e);
     }
 
@@ -496,11 +678,12 @@ async fn send_notification(
 async fn get_detailed_stats(
     data: web::Data<AppState>,
     current_user: actix_web::web::ReqData<CurrentUser>,
+    permissions: web::ReqData<UserPermissions>,
 ) -> impl Responder {
     let user = current_user.into_inner();
     log::info!("[Admin] 获取详细统计数据 | admin_id={}", user.id);
 
-    if let Err(e) = check_admin(&user) {
+    if let Err(e) = check_permission(&permissions, Permission::DashboardView) {
         return handle_admin_error(e);
     }
 
@@ -515,12 +698,13 @@ async fn get_detailed_stats(
 async fn get_audit_logs(
     data: web::Data<AppState>,
     current_user: actix_web::web::ReqData<CurrentUser>,
+    permissions: web::ReqData<UserPermissions>,
     query: web::Query<AuditLogQuery>,
 ) -> impl Responder {
     let user = current_user.into_inner();
     log::info!("[Admin] 获取审计日志 | admin_id={}", user.id);
 
-    if let Err(e) = check_admin(&user) {
+    if let Err(e) = check_permission(&permissions, Permission::AuditLogRead) {
         return handle_admin_error(e);
     }
 
@@ -546,12 +730,13 @@ async fn get_audit_logs(
 async fn get_teacher_list(
     data: web::Data<AppState>,
     current_user: actix_web::web::ReqData<CurrentUser>,
+    permissions: web::ReqData<UserPermissions>,
     query: web::Query<TeacherListQuery>,
 ) -> impl Responder {
     let user = current_user.into_inner();
     log::info!("[Admin] 获取教师列表 | admin_id={}", user.id);
 
-    if let Err(e) = check_admin(&user) {
+    if let Err(e) = check_permission(&permissions, Permission::TeacherManage) {
         return handle_admin_error(e);
     }
 
@@ -566,12 +751,13 @@ async fn get_teacher_list(
 async fn create_teacher(
     data: web::Data<AppState>,
     current_user: actix_web::web::ReqData<CurrentUser>,
+    permissions: web::ReqData<UserPermissions>,
     req: web::Json<CreateTeacherRequest>,
 ) -> impl Responder {
     let user = current_user.into_inner();
     log::info!("[Admin] 添加教师 | admin_id={}", user.id);
 
-    if let Err(e) = check_admin(&user) {
+    if let Err(e) = check_permission(&permissions, Permission::TeacherManage) {
         return handle_admin_error(e);
     }
 
@@ -586,6 +772,7 @@ async fn create_teacher(
 async fn update_teacher(
     data: web::Data<AppState>,
     current_user: actix_web::web::ReqData<CurrentUser>,
+    permissions: web::ReqData<UserPermissions>,
     path: web::Path<i64>,
     req: web::Json<UpdateTeacherRequest>,
 ) -> impl Responder {
@@ -597,7 +784,7 @@ async fn update_teacher(
         sn
     );
 
-    if let Err(e) = check_admin(&user) {
+    if let Err(e) = check_permission(&permissions, Permission::TeacherManage) {
         return handle_admin_error(e);
     }
 
@@ -612,6 +799,7 @@ async fn update_teacher(
 async fn update_teacher_status(
     data: web::Data<AppState>,
     current_user: actix_web::web::ReqData<CurrentUser>,
+    permissions: web::ReqData<UserPermissions>,
     path: web::Path<i64>,
     req: web::Json<UpdateTeacherStatusRequest>,
 ) -> impl Responder {
@@ -624,7 +812,7 @@ async fn update_teacher_status(
         req.is_active
     );
 
-    if let Err(e) = check_admin(&user) {
+    if let Err(e) = check_permission(&permissions, Permission::TeacherManage) {
         return handle_admin_error(e);
     }
 
@@ -639,13 +827,14 @@ async fn update_teacher_status(
 async fn delete_teacher(
     data: web::Data<AppState>,
     current_user: actix_web::web::ReqData<CurrentUser>,
+    permissions: web::ReqData<UserPermissions>,
     path: web::Path<i64>,
 ) -> impl Responder {
     let user = current_user.into_inner();
     let sn = path.into_inner();
     log::info!("[Admin] 删除教师 | admin_id={}, teacher_sn={}", user.id, sn);
 
-    if let Err(e) = check_admin(&user) {
+    if let Err(e) = check_permission(&permissions, Permission::TeacherManage) {
         return handle_admin_error(e);
     }
 
@@ -662,12 +851,13 @@ async fn delete_teacher(
 async fn get_course_list(
     data: web::Data<AppState>,
     current_user: actix_web::web::ReqData<CurrentUser>,
+    permissions: web::ReqData<UserPermissions>,
     query: web::Query<CourseListQuery>,
 ) -> impl Responder {
     let user = current_user.into_inner();
     log::info!("[Admin] 获取课程列表 | admin_id={}", user.id);
 
-    if let Err(e) = check_admin(&user) {
+    if let Err(e) = check_any_permission(&permissions, Permission::CourseManage) {
         return handle_admin_error(e);
     }
 
@@ -682,12 +872,13 @@ async fn get_course_list(
 async fn create_course(
     data: web::Data<AppState>,
     current_user: actix_web::web::ReqData<CurrentUser>,
+    permissions: web::ReqData<UserPermissions>,
     req: web::Json<CreateCourseRequest>,
 ) -> impl Responder {
     let user = current_user.into_inner();
     log::info!("[Admin] 添加课程 | admin_id={}", user.id);
 
-    if let Err(e) = check_admin(&user) {
+    if let Err(e) = check_permission(&permissions, Permission::CourseManage) {
         return handle_admin_error(e);
     }
 
@@ -702,6 +893,7 @@ async fn create_course(
 async fn update_course(
     data: web::Data<AppState>,
     current_user: actix_web::web::ReqData<CurrentUser>,
+    permissions: web::ReqData<UserPermissions>,
     path: web::Path<i64>,
     req: web::Json<UpdateCourseRequest>,
 ) -> impl Responder {
@@ -713,7 +905,7 @@ async fn update_course(
         sn
     );
 
-    if let Err(e) = check_admin(&user) {
+    if let Err(e) = check_course_permission(&permissions, Permission::CourseManage, sn) {
         return handle_admin_error(e);
     }
 
@@ -728,6 +920,7 @@ async fn update_course(
 async fn update_course_status(
     data: web::Data<AppState>,
     current_user: actix_web::web::ReqData<CurrentUser>,
+    permissions: web::ReqData<UserPermissions>,
     path: web::Path<i64>,
     req: web::Json<UpdateCourseStatusRequest>,
 ) -> impl Responder {
@@ -740,7 +933,7 @@ async fn update_course_status(
         req.is_active
     );
 
-    if let Err(e) = check_admin(&user) {
+    if let Err(e) = check_course_permission(&permissions, Permission::CourseManage, sn) {
         return handle_admin_error(e);
     }
 
@@ -755,13 +948,14 @@ async fn update_course_status(
 async fn delete_course(
     data: web::Data<AppState>,
     current_user: actix_web::web::ReqData<CurrentUser>,
+    permissions: web::ReqData<UserPermissions>,
     path: web::Path<i64>,
 ) -> impl Responder {
     let user = current_user.into_inner();
     let sn = path.into_inner();
     log::info!("[Admin] 删除课程 | admin_id={}, course_sn={}", user.id, sn);
 
-    if let Err(e) = check_admin(&user) {
+    if let Err(e) = check_permission(&permissions, Permission::CourseManage) {
         return handle_admin_error(e);
     }
 
@@ -776,12 +970,13 @@ async fn delete_course(
 async fn batch_import_teachers(
     data: web::Data<AppState>,
     current_user: actix_web::web::ReqData<CurrentUser>,
+    permissions: web::ReqData<UserPermissions>,
     req: web::Json<BatchImportTeachersRequest>,
 ) -> impl Responder {
     let user = current_user.into_inner();
     log::info!("[Admin] 批量导入教师 | admin_id={}, count={}", user.id, req.teachers.len());
 
-    if let Err(e) = check_admin(&user) {
+    if let Err(e) = check_permission(&permissions, Permission::TeacherManage) {
         return handle_admin_error(e);
     }
 
@@ -808,12 +1003,13 @@ async fn batch_import_teachers(
 async fn batch_import_courses(
     data: web::Data<AppState>,
     current_user: actix_web::web::ReqData<CurrentUser>,
+    permissions: web::ReqData<UserPermissions>,
     req: web::Json<BatchImportCoursesRequest>,
 ) -> impl Responder {
     let user = current_user.into_inner();
     log::info!("[Admin] 批量导入课程 | admin_id={}, count={}", user.id, req.courses.len());
 
-    if let Err(e) = check_admin(&user) {
+    if let Err(e) = check_permission(&permissions, Permission::CourseManage) {
         return handle_admin_error(e);
     }
 
@@ -978,12 +1174,13 @@ fn parse_courses_from_bytes(
 async fn batch_import_teachers_from_file(
     data: web::Data<AppState>,
     current_user: actix_web::web::ReqData<CurrentUser>,
+    permissions: web::ReqData<UserPermissions>,
     mut payload: Multipart,
 ) -> impl Responder {
     let user = current_user.into_inner();
     log::info!("[Admin] 开始从文件批量导入教师 | admin_id={}", user.id);
 
-    if let Err(e) = check_admin(&user) {
+    if let Err(e) = check_permission(&permissions, Permission::TeacherManage) {
         return handle_admin_error(e);
     }
 
@@ -1071,12 +1268,13 @@ async fn batch_import_teachers_from_file(
 async fn batch_import_courses_from_file(
     data: web::Data<AppState>,
     current_user: actix_web::web::ReqData<CurrentUser>,
+    permissions: web::ReqData<UserPermissions>,
     mut payload: Multipart,
 ) -> impl Responder {
     let user = current_user.into_inner();
     log::info!("[Admin] 开始从文件批量导入课程 | admin_id={}", user.id);
 
-    if let Err(e) = check_admin(&user) {
+    if let Err(e) = check_permission(&permissions, Permission::CourseManage) {
         return handle_admin_error(e);
     }
 
@@ -1164,6 +1362,7 @@ async fn batch_import_courses_from_file(
 async fn batch_delete_teachers(
     data: web::Data<AppState>,
     current_user: actix_web::web::ReqData<CurrentUser>,
+    permissions: web::ReqData<UserPermissions>,
     req: web::Json<BatchDeleteTeachersRequest>,
 ) -> impl Responder {
     let user = current_user.into_inner();
@@ -1173,7 +1372,7 @@ async fn batch_delete_teachers(
         req.sns
     );
 
-    if let Err(e) = check_admin(&user) {
+    if let Err(e) = check_permission(&permissions, Permission::TeacherManage) {
         return handle_admin_error(e);
     }
 
@@ -1201,6 +1400,7 @@ async fn batch_delete_teachers(
 async fn batch_delete_courses(
     data: web::Data<AppState>,
     current_user: actix_web::web::ReqData<CurrentUser>,
+    permissions: web::ReqData<UserPermissions>,
     req: web::Json<BatchDeleteCoursesRequest>,
 ) -> impl Responder {
     let user = current_user.into_inner();
@@ -1210,7 +1410,7 @@ async fn batch_delete_courses(
         req.sns
     );
 
-    if let Err(e) = check_admin(&user) {
+    if let Err(e) = check_permission(&permissions, Permission::CourseManage) {
         return handle_admin_error(e);
     }
 
@@ -1240,12 +1440,13 @@ async fn batch_delete_courses(
 async fn get_all_resources(
     data: web::Data<AppState>,
     current_user: actix_web::web::ReqData<CurrentUser>,
+    permissions: web::ReqData<UserPermissions>,
     query: web::Query<std::collections::HashMap<String, String>>,
 ) -> impl Responder {
     let user = current_user.into_inner();
     log::info!("[Admin] 获取所有资源列表 | admin_id={}", user.id);
 
-    if let Err(e) = check_admin(&user) {
+    if let Err(e) = check_any_permission(&permissions, Permission::ResourceManage) {
         return handle_admin_error(e);
     }
 
@@ -1259,7 +1460,48 @@ async fn get_all_resources(
         .unwrap_or(20);
     let keyword = query.get("keyword").cloned();
 
-    match AdminService::get_all_resources(&data.pool, page, per_page, keyword).await {
+    let course_scope = permissions.course_scope(Permission::ResourceManage);
+    match AdminService::get_all_resources(
+        &data.pool,
+        page,
+        per_page,
+        keyword,
+        course_scope.as_deref(),
+    )
+    .await
+    {
+        Ok(response) => HttpResponse::Ok().json(response),
+        Err(e) => handle_admin_error(e),
+    }
+}
+
+/// 获取重复资源分组（文件内容完全相同的资源）
+#[get("/admin/resources/duplicates")]
+async fn get_duplicate_resources(
+    data: web::Data<AppState>,
+    current_user: actix_web::web::ReqData<CurrentUser>,
+    permissions: web::ReqData<UserPermissions>,
+    query: web::Query<std::collections::HashMap<String, String>>,
+) -> impl Responder {
+    let user = current_user.into_inner();
+    log::info!("[Admin] 获取重复资源分组 | admin_id={}", user.id);
+
+    if let Err(e) = check_permission(&permissions, Permission::ResourceManage) {
+        return handle_admin_error(e);
+    }
+
+    let page = query
+        .get("page")
+        .and_then(|p| p.parse::<i32>().ok())
+        .unwrap_or(1)
+        .max(1);
+    let per_page = query
+        .get("perPage")
+        .and_then(|p| p.parse::<i32>().ok())
+        .unwrap_or(20)
+        .clamp(1, 100);
+
+    match AdminService::get_duplicate_resource_clusters(&data.pool, page, per_page).await {
         Ok(response) => HttpResponse::Ok().json(response),
         Err(e) => handle_admin_error(e),
     }
@@ -1270,23 +1512,31 @@ async fn get_all_resources(
 async fn admin_delete_resource(
     data: web::Data<AppState>,
     current_user: actix_web::web::ReqData<CurrentUser>,
+    permissions: web::ReqData<UserPermissions>,
     path: web::Path<Uuid>,
     req: HttpRequest,
 ) -> impl Responder {
     let user = current_user.into_inner();
+    let resource_id = path.into_inner();
 
-    if let Err(e) = check_admin(&user) {
+    if let Err(e) = check_resource_permission(
+        &data.pool,
+        &permissions,
+        Permission::ResourceManage,
+        resource_id,
+    )
+    .await
+    {
         return handle_admin_error(e);
     }
 
-    let resource_id = path.into_inner();
     log::info!(
         "[Admin] 管理员删除资源 | admin_id={}, resource_id={}",
         user.id,
         resource_id
     );
 
-    match ResourceService::delete_resource(&data.pool, &user, &data.storage, resource_id).await {
+    match ResourceService::force_delete_resource(&data.pool, &data.storage, resource_id).await {
         Ok(title) => {
             log::info!(
                 "[Admin] 资源删除成功 | admin_id={}, resource_id={}, title={}",
@@ -1325,16 +1575,24 @@ async fn admin_delete_resource(
 async fn admin_recalculate_resource_hash(
     data: web::Data<AppState>,
     current_user: actix_web::web::ReqData<CurrentUser>,
+    permissions: web::ReqData<UserPermissions>,
     path: web::Path<Uuid>,
     req: HttpRequest,
 ) -> impl Responder {
     let user = current_user.into_inner();
+    let resource_id = path.into_inner();
 
-    if let Err(e) = check_admin(&user) {
+    if let Err(e) = check_resource_permission(
+        &data.pool,
+        &permissions,
+        Permission::ResourceManage,
+        resource_id,
+    )
+    .await
+    {
         return handle_admin_error(e);
     }
 
-    let resource_id = path.into_inner();
     log::info!(
         "[Admin] 管理员重新计算资源hash | admin_id={}, resource_id={}",
         user.id, resource_id
@@ -1389,11 +1647,12 @@ async fn admin_recalculate_resource_hash(
 async fn get_admin_favorites(
     data: web::Data<AppState>,
     current_user: actix_web::web::ReqData<CurrentUser>,
+    permissions: web::ReqData<UserPermissions>,
 ) -> impl Responder {
     let user = current_user.into_inner();
     log::info!("[Admin] 获取收藏夹列表 | admin_id={}", user.id);
 
-    if let Err(e) = check_admin(&user) {
+    if let Err(e) = check_permission(&permissions, Permission::ResourceManage) {
         return handle_admin_error(e);
     }
 
@@ -1408,12 +1667,13 @@ async fn get_admin_favorites(
 async fn delete_all_favorite_resources(
     data: web::Data<AppState>,
     current_user: actix_web::web::ReqData<CurrentUser>,
+    permissions: web::ReqData<UserPermissions>,
     path: web::Path<Uuid>,
     req: HttpRequest,
 ) -> impl Responder {
     let user = current_user.into_inner();
 
-    if let Err(e) = check_admin(&user) {
+    if let Err(e) = check_permission(&permissions, Permission::ResourceManage) {
         return handle_admin_error(e);
     }
 
@@ -1463,6 +1723,818 @@ async fn delete_all_favorite_resources(
     }
 }
 
+// ==================== 存储迁移接口 ====================
+
+/// 创建存储迁移任务
+/// 迁移在后台执行，返回任务 ID 供管理员轮询进度
+#[post("/admin/storage-migrations")]
+async fn create_storage_migration(
+    data: web::Data<AppState>,
+    current_user: actix_web::web::ReqData<CurrentUser>,
+    permissions: web::ReqData<UserPermissions>,
+    body: web::Json<CreateStorageMigrationRequest>,
+    req: HttpRequest,
+) -> impl Responder {
+    let user = current_user.into_inner();
+
+    if let Err(e) = check_permission(&permissions, Permission::StorageMigrate) {
+        return handle_admin_error(e);
+    }
+
+    let config = Config::from_env();
+    let request = body.into_inner();
+    match StorageMigrationService::create_job(&data.pool, &data.storage, &config, user.id, &request)
+        .await
+    {
+        Ok(job) => {
+            log::info!(
+                "[Admin] 创建存储迁移任务 | admin_id={}, job_id={}, {}->{}, files={}",
+                user.id,
+                job.id,
+                job.source_type,
+                job.target_type,
+                job.total_items
+            );
+
+            // 记录审计日志
+            let ip_address = req.peer_addr().map(|addr| addr.ip().to_string());
+            if let Err(e) = AuditLogService::log_action(
+                &data.pool,
+                user.id,
+                "create_storage_migration",
+                Some("storage_migration"),
+                Some(job.id),
+                Some(serde_json::json!({
+                    "source_type": job.source_type,
+                    "target_type": job.target_type,
+                    "include_images": job.include_images,
+                    "delete_source": job.delete_source,
+                    "total_items": job.total_items,
+                })),
+                ip_address.as_deref(),
+            )
+            .await
+            {
+                log::warn!(
+                    "[Audit] 记录存储迁移日志失败 | admin_id={}, job_id={}, error={}",
+                    user.id,
+                    job.id,
+                    e
+                );
+            }
+
+            let response = StorageMigrationJobResponse::from(job.clone());
+            tasks::storage_migration_task::spawn_storage_migration_job(
+                data.pool.clone(),
+                data.storage.clone(),
+                config,
+                job,
+            );
+
+            HttpResponse::Accepted().json(response)
+        }
+        Err(e) => handle_resource_error(e),
+    }
+}
+
+/// 获取最近的存储迁移任务
+#[get("/admin/storage-migrations")]
+async fn get_storage_migrations(
+    data: web::Data<AppState>,
+    permissions: web::ReqData<UserPermissions>,
+) -> impl Responder {
+    if let Err(e) = check_permission(&permissions, Permission::StorageMigrate) {
+        return handle_admin_error(e);
+    }
+
+    match StorageMigrationService::get_jobs(&data.pool).await {
+        Ok(response) => HttpResponse::Ok().json(response),
+        Err(e) => handle_resource_error(e),
+    }
+}
+
+/// 获取存储迁移任务进度
+#[get("/admin/storage-migrations/{job_id}")]
+async fn get_storage_migration(
+    data: web::Data<AppState>,
+    permissions: web::ReqData<UserPermissions>,
+    path: web::Path<Uuid>,
+) -> impl Responder {
+    if let Err(e) = check_permission(&permissions, Permission::StorageMigrate) {
+        return handle_admin_error(e);
+    }
+
+    match StorageMigrationService::get_job(&data.pool, path.into_inner()).await {
+        Ok(response) => HttpResponse::Ok().json(response),
+        Err(e) => handle_resource_error(e),
+    }
+}
+
+// ==================== 资源申领审核接口 ====================
+
+/// 获取申领列表
+#[get("/admin/claims")]
+async fn get_claim_list(
+    data: web::Data<AppState>,
+    current_user: actix_web::web::ReqData<CurrentUser>,
+    permissions: web::ReqData<UserPermissions>,
+    query: web::Query<ClaimListQuery>,
+) -> impl Responder {
+    let user = current_user.into_inner();
+    log::info!("[Admin] 获取申领列表 | admin_id={}", user.id);
+
+    if let Err(e) = check_permission(&permissions, Permission::ClaimReview) {
+        return handle_admin_error(e);
+    }
+
+    let config = Config::from_env();
+    match ClaimService::get_claim_list(&data.pool, query.into_inner(), &config.image_base_url)
+        .await
+    {
+        Ok(response) => HttpResponse::Ok().json(response),
+        Err(e) => handle_resource_error(e),
+    }
+}
+
+/// 审核申领（通过时设置资源作者）
+#[put("/admin/claims/{claim_id}/review")]
+async fn review_claim(
+    data: web::Data<AppState>,
+    current_user: actix_web::web::ReqData<CurrentUser>,
+    permissions: web::ReqData<UserPermissions>,
+    path: web::Path<Uuid>,
+    req: web::Json<ReviewClaimRequest>,
+    http_req: HttpRequest,
+) -> impl Responder {
+    let user = current_user.into_inner();
+
+    if let Err(e) = check_permission(&permissions, Permission::ClaimReview) {
+        return handle_admin_error(e);
+    }
+
+    let claim_id = path.into_inner();
+    log::info!(
+        "[Admin] 审核申领 | admin_id={}, claim_id={}, status={}",
+        user.id,
+        claim_id,
+        req.status
+    );
+
+    let config = Config::from_env();
+    match ClaimService::review_claim(
+        &data.pool,
+        claim_id,
+        user.id,
+        req.into_inner(),
+        &config.image_base_url,
+    )
+    .await
+    {
+        Ok(response) => {
+            log::info!(
+                "[Admin] 申领审核完成 | admin_id={}, claim_id={}, status={}",
+                user.id,
+                claim_id,
+                response.status
+            );
+
+            // 记录审计日志
+            let ip_address = http_req.peer_addr().map(|addr| addr.ip().to_string());
+            if let Err(e) = AuditLogService::log_action(
+                &data.pool,
+                user.id,
+                "review_claim",
+                Some("claim"),
+                Some(claim_id),
+                Some(serde_json::json!({
+                    "resource_id": response.resource_id,
+                    "applicant_id": response.applicant_id,
+                    "status": response.status,
+                })),
+                ip_address.as_deref(),
+            )
+            .await
+            {
+                log::warn!(
+                    "[Audit] 记录审核申领日志失败 | admin_id={}, claim_id={}, error={}",
+                    user.id,
+                    claim_id,
+                    e
+                );
+            }
+
+            HttpResponse::Ok().json(response)
+        }
+        Err(e) => handle_resource_error(e),
+    }
+}
+
+// ==================== 实名认证审核接口 ====================
+
+/// 获取实名认证申请列表
+#[get("/admin/verifications")]
+async fn get_verification_list(
+    data: web::Data<AppState>,
+    current_user: actix_web::web::ReqData<CurrentUser>,
+    permissions: web::ReqData<UserPermissions>,
+    query: web::Query<VerificationListQuery>,
+) -> impl Responder {
+    let user = current_user.into_inner();
+    log::info!("[Admin] 获取实名认证申请列表 | admin_id={}", user.id);
+
+    if let Err(e) = check_permission(&permissions, Permission::VerificationReview) {
+        return handle_admin_error(e);
+    }
+
+    let config = Config::from_env();
+    match VerificationService::get_verification_list(
+        &data.pool,
+        query.into_inner(),
+        &config.image_base_url,
+    )
+    .await
+    {
+        Ok(response) => HttpResponse::Ok().json(response),
+        Err(e) => handle_verification_error(e),
+    }
+}
+
+/// 获取实名认证申请详情（包含解密后的实名信息，记录审计日志）
+#[get("/admin/verifications/{verification_id}")]
+async fn get_verification_detail(
+    data: web::Data<AppState>,
+    current_user: actix_web::web::ReqData<CurrentUser>,
+    permissions: web::ReqData<UserPermissions>,
+    path: web::Path<Uuid>,
+    http_req: HttpRequest,
+) -> impl Responder {
+    let user = current_user.into_inner();
+
+    if let Err(e) = check_permission(&permissions, Permission::VerificationReview) {
+        return handle_admin_error(e);
+    }
+
+    let verification_id = path.into_inner();
+    log::info!(
+        "[Admin] 获取实名认证申请详情 | admin_id={}, verification_id={}",
+        user.id,
+        verification_id
+    );
+
+    let config = Config::from_env();
+    let ip_address = http_req.peer_addr().map(|addr| addr.ip().to_string());
+    match AdminService::get_verification_detail(
+        &data.pool,
+        data.real_info_cipher.as_deref(),
+        verification_id,
+        user.id,
+        ip_address.as_deref(),
+        &config.image_base_url,
+    )
+    .await
+    {
+        Ok(detail) => HttpResponse::Ok().json(detail),
+        Err(e) => {
+            log::warn!(
+                "[Admin] 获取实名认证申请详情失败 | admin_id={}, verification_id={}, error={}",
+                user.id,
+                verification_id,
+                e
+            );
+            handle_admin_error(e)
+        }
+    }
+}
+
+/// 审核实名认证申请（通过时设置用户实名状态）
+#[put("/admin/verifications/{verification_id}/review")]
+async fn review_verification(
+    data: web::Data<AppState>,
+    current_user: actix_web::web::ReqData<CurrentUser>,
+    permissions: web::ReqData<UserPermissions>,
+    path: web::Path<Uuid>,
+    req: web::Json<ReviewVerificationRequest>,
+    http_req: HttpRequest,
+) -> impl Responder {
+    let user = current_user.into_inner();
+
+    if let Err(e) = check_permission(&permissions, Permission::VerificationReview) {
+        return handle_admin_error(e);
+    }
+
+    let verification_id = path.into_inner();
+    log::info!(
+        "[Admin] 审核实名认证申请 | admin_id={}, verification_id={}, status={}",
+        user.id,
+        verification_id,
+        req.status
+    );
+
+    let config = Config::from_env();
+    match VerificationService::review(
+        &data.pool,
+        verification_id,
+        user.id,
+        req.into_inner(),
+        &config.image_base_url,
+    )
+    .await
+    {
+        Ok(response) => {
+            // 记录审计日志
+            let ip_address = http_req.peer_addr().map(|addr| addr.ip().to_string());
+            if let Err(e) = AuditLogService::log_action(
+                &data.pool,
+                user.id,
+                "review_verification",
+                Some("verification"),
+                Some(verification_id),
+                Some(serde_json::json!({
+                    "user_id": response.user_id,
+                    "status": response.status,
+                    "reason": response.review_reason,
+                })),
+                ip_address.as_deref(),
+            )
+            .await
+            {
+                log::warn!(
+                    "[Audit] 记录审核实名认证日志失败 | admin_id={}, verification_id={}, error={}",
+                    user.id,
+                    verification_id,
+                    e
+                );
+            }
+
+            HttpResponse::Ok().json(response)
+        }
+        Err(e) => handle_verification_error(e),
+    }
+}
+
+// ==================== 举报处理接口 ====================
+
+/// 解析举报对象类型路径参数
+fn parse_report_target_type(target_type: &str) -> Result<ReportTargetType, AdminError> {
+    ReportTargetType::from_str(target_type)
+        .ok_or_else(|| AdminError::ValidationError(format!("无效的举报对象类型: {}", target_type)))
+}
+
+/// 隐藏和删除被举报对象还需要对应的资源或评论管理权限
+async fn check_report_action_permission(
+    pool: &PgPool,
+    permissions: &UserPermissions,
+    target_type: ReportTargetType,
+    target_id: Uuid,
+    action: Option<ReportAction>,
+) -> Result<(), AdminError> {
+    match (action, target_type) {
+        (Some(ReportAction::Hide), ReportTargetType::Resource) => {
+            check_resource_permission(pool, permissions, Permission::ResourceAudit, target_id).await
+        }
+        (Some(ReportAction::Delete), ReportTargetType::Resource) => {
+            check_resource_permission(pool, permissions, Permission::ResourceManage, target_id)
+                .await
+        }
+        (Some(ReportAction::Hide), ReportTargetType::Comment) => {
+            check_comment_permission(pool, permissions, Permission::CommentAudit, target_id).await
+        }
+        (Some(ReportAction::Delete), ReportTargetType::Comment) => {
+            check_comment_permission(pool, permissions, Permission::CommentDelete, target_id).await
+        }
+        _ => Ok(()),
+    }
+}
+
+/// 获取举报队列（同一对象的举报合并为一条）
+#[get("/admin/reports")]
+async fn get_report_queue(
+    data: web::Data<AppState>,
+    current_user: actix_web::web::ReqData<CurrentUser>,
+    permissions: web::ReqData<UserPermissions>,
+    query: web::Query<ReportListQuery>,
+) -> impl Responder {
+    let user = current_user.into_inner();
+    log::info!("[Admin] 获取举报队列 | admin_id={}", user.id);
+
+    if let Err(e) = check_permission(&permissions, Permission::ReportReview) {
+        return handle_admin_error(e);
+    }
+
+    match ReportService::get_report_queue(&data.pool, query.into_inner()).await {
+        Ok(response) => HttpResponse::Ok().json(response),
+        Err(e) => handle_report_error(e),
+    }
+}
+
+/// 获取对象收到的全部举报
+#[get("/admin/reports/{target_type}/{target_id}")]
+async fn get_target_reports(
+    data: web::Data<AppState>,
+    current_user: actix_web::web::ReqData<CurrentUser>,
+    permissions: web::ReqData<UserPermissions>,
+    path: web::Path<(String, Uuid)>,
+) -> impl Responder {
+    let user = current_user.into_inner();
+
+    if let Err(e) = check_permission(&permissions, Permission::ReportReview) {
+        return handle_admin_error(e);
+    }
+
+    let (target_type, target_id) = path.into_inner();
+    let target_type = match parse_report_target_type(&target_type) {
+        Ok(t) => t,
+        Err(e) => return handle_admin_error(e),
+    };
+    log::info!(
+        "[Admin] 获取举报详情 | admin_id={}, target_type={}, target_id={}",
+        user.id,
+        target_type.as_str(),
+        target_id
+    );
+
+    match ReportService::get_target_reports(&data.pool, target_type, target_id).await {
+        Ok(response) => HttpResponse::Ok().json(response),
+        Err(e) => handle_report_error(e),
+    }
+}
+
+/// 处理对象的全部待处理举报（驳回、隐藏、删除或警告发布者）
+#[put("/admin/reports/{target_type}/{target_id}/resolve")]
+async fn resolve_reports(
+    data: web::Data<AppState>,
+    current_user: actix_web::web::ReqData<CurrentUser>,
+    permissions: web::ReqData<UserPermissions>,
+    path: web::Path<(String, Uuid)>,
+    req: web::Json<ResolveReportRequest>,
+    http_req: HttpRequest,
+) -> impl Responder {
+    let user = current_user.into_inner();
+
+    if let Err(e) = check_permission(&permissions, Permission::ReportReview) {
+        return handle_admin_error(e);
+    }
+
+    let (target_type, target_id) = path.into_inner();
+    let target_type = match parse_report_target_type(&target_type) {
+        Ok(t) => t,
+        Err(e) => return handle_admin_error(e),
+    };
+    if let Err(e) = check_report_action_permission(
+        &data.pool,
+        &permissions,
+        target_type,
+        target_id,
+        ReportAction::from_str(&req.action),
+    )
+    .await
+    {
+        return handle_admin_error(e);
+    }
+
+    log::info!(
+        "[Admin] 处理举报 | admin_id={}, target_type={}, target_id={}, action={}",
+        user.id,
+        target_type.as_str(),
+        target_id,
+        req.action
+    );
+
+    match ReportService::resolve_reports(
+        &data.pool,
+        &data.storage,
+        target_type,
+        target_id,
+        user.id,
+        req.into_inner(),
+    )
+    .await
+    {
+        Ok(response) => {
+            // 记录审计日志
+            let ip_address = http_req.peer_addr().map(|addr| addr.ip().to_string());
+            if let Err(e) = AuditLogService::log_action(
+                &data.pool,
+                user.id,
+                "resolve_report",
+                Some(target_type.as_str()),
+                Some(target_id),
+                Some(serde_json::json!({
+                    "action": response.action,
+                    "resolved_count": response.resolved_count,
+                })),
+                ip_address.as_deref(),
+            )
+            .await
+            {
+                log::warn!(
+                    "[Audit] 记录处理举报日志失败 | admin_id={}, target_id={}, error={}",
+                    user.id,
+                    target_id,
+                    e
+                );
+            }
+
+            HttpResponse::Ok().json(response)
+        }
+        Err(e) => handle_report_error(e),
+    }
+}
+
+// ==================== 角色与权限接口 ====================
+
+/// 记录角色管理操作的审计日志
+async fn log_role_action(
+    pool: &PgPool,
+    user_id: Uuid,
+    action: AuditAction,
+    target_type: &str,
+    target_id: Uuid,
+    details: serde_json::Value,
+    http_req: &HttpRequest,
+) {
+    let ip_address = http_req.peer_addr().map(|addr| addr.ip().to_string());
+    if let Err(e) = AuditLogService::log(
+        pool,
+        Some(user_id),
+        action,
+        Some(target_type),
+        Some(target_id),
+        Some(details),
+        ip_address.as_deref(),
+    )
+    .await
+    {
+        log::warn!(
+            "[Audit] 记录角色管理日志失败 | admin_id={}, target_id={}, error={}",
+            user_id,
+            target_id,
+            e
+        );
+    }
+}
+
+/// 获取所有可分配的权限
+#[get("/admin/permissions")]
+async fn get_permission_list(permissions: web::ReqData<UserPermissions>) -> impl Responder {
+    if let Err(e) = check_permission(&permissions, Permission::RoleManage) {
+        return handle_admin_error(e);
+    }
+
+    let list: Vec<PermissionInfo> = Permission::ALL.into_iter().map(Into::into).collect();
+    HttpResponse::Ok().json(list)
+}
+
+/// 获取角色列表
+#[get("/admin/roles")]
+async fn get_role_list(
+    data: web::Data<AppState>,
+    permissions: web::ReqData<UserPermissions>,
+) -> impl Responder {
+    if let Err(e) = check_permission(&permissions, Permission::RoleManage) {
+        return handle_admin_error(e);
+    }
+
+    match PermissionService::list_roles(&data.pool).await {
+        Ok(roles) => HttpResponse::Ok().json(roles),
+        Err(e) => handle_permission_error(e),
+    }
+}
+
+/// 创建角色
+#[post("/admin/roles")]
+async fn create_role(
+    data: web::Data<AppState>,
+    current_user: actix_web::web::ReqData<CurrentUser>,
+    permissions: web::ReqData<UserPermissions>,
+    req: web::Json<CreateRoleRequest>,
+    http_req: HttpRequest,
+) -> impl Responder {
+    let user = current_user.into_inner();
+    log::info!("[Admin] 创建角色 | admin_id={}, name={}", user.id, req.name);
+
+    if let Err(e) = check_permission(&permissions, Permission::RoleManage) {
+        return handle_admin_error(e);
+    }
+
+    match PermissionService::create_role(&data.pool, &permissions, &req).await {
+        Ok(role) => {
+            log_role_action(
+                &data.pool,
+                user.id,
+                AuditAction::RoleCreated,
+                "role",
+                role.id,
+                serde_json::json!({
+                    "name": role.name,
+                    "permissions": role.permissions,
+                }),
+                &http_req,
+            )
+            .await;
+            HttpResponse::Created().json(role)
+        }
+        Err(e) => handle_permission_error(e),
+    }
+}
+
+/// 更新角色
+#[put("/admin/roles/{role_id}")]
+async fn update_role(
+    data: web::Data<AppState>,
+    current_user: actix_web::web::ReqData<CurrentUser>,
+    permissions: web::ReqData<UserPermissions>,
+    path: web::Path<Uuid>,
+    req: web::Json<UpdateRoleRequest>,
+    http_req: HttpRequest,
+) -> impl Responder {
+    let user = current_user.into_inner();
+    let role_id = path.into_inner();
+    log::info!(
+        "[Admin] 更新角色 | admin_id={}, role_id={}",
+        user.id,
+        role_id
+    );
+
+    if let Err(e) = check_permission(&permissions, Permission::RoleManage) {
+        return handle_admin_error(e);
+    }
+
+    match PermissionService::update_role(&data.pool, &permissions, role_id, &req).await {
+        Ok((before, role)) => {
+            log_role_action(
+                &data.pool,
+                user.id,
+                AuditAction::RoleUpdated,
+                "role",
+                role.id,
+                serde_json::json!({
+                    "name": role.name,
+                    "old_permissions": before.permissions,
+                    "new_permissions": role.permissions,
+                }),
+                &http_req,
+            )
+            .await;
+            HttpResponse::Ok().json(role)
+        }
+        Err(e) => handle_permission_error(e),
+    }
+}
+
+/// 删除角色
+#[delete("/admin/roles/{role_id}")]
+async fn delete_role(
+    data: web::Data<AppState>,
+    current_user: actix_web::web::ReqData<CurrentUser>,
+    permissions: web::ReqData<UserPermissions>,
+    path: web::Path<Uuid>,
+    http_req: HttpRequest,
+) -> impl Responder {
+    let user = current_user.into_inner();
+    let role_id = path.into_inner();
+    log::info!(
+        "[Admin] 删除角色 | admin_id={}, role_id={}",
+        user.id,
+        role_id
+    );
+
+    if let Err(e) = check_permission(&permissions, Permission::RoleManage) {
+        return handle_admin_error(e);
+    }
+
+    match PermissionService::delete_role(&data.pool, &permissions, role_id).await {
+        Ok(role) => {
+            log_role_action(
+                &data.pool,
+                user.id,
+                AuditAction::RoleDeleted,
+                "role",
+                role.id,
+                serde_json::json!({
+                    "name": role.name,
+                    "permissions": role.permissions,
+                }),
+                &http_req,
+            )
+            .await;
+            no_content()
+        }
+        Err(e) => handle_permission_error(e),
+    }
+}
+
+/// 获取用户的角色分配
+#[get("/admin/users/{user_id}/roles")]
+async fn get_user_roles(
+    data: web::Data<AppState>,
+    permissions: web::ReqData<UserPermissions>,
+    path: web::Path<Uuid>,
+) -> impl Responder {
+    if let Err(e) = check_permission(&permissions, Permission::RoleManage) {
+        return handle_admin_error(e);
+    }
+
+    match PermissionService::list_user_roles(&data.pool, path.into_inner()).await {
+        Ok(assignments) => HttpResponse::Ok().json(assignments),
+        Err(e) => handle_permission_error(e),
+    }
+}
+
+/// 为用户分配角色
+#[post("/admin/users/{user_id}/roles")]
+async fn assign_user_role(
+    data: web::Data<AppState>,
+    current_user: actix_web::web::ReqData<CurrentUser>,
+    permissions: web::ReqData<UserPermissions>,
+    path: web::Path<Uuid>,
+    req: web::Json<AssignRoleRequest>,
+    http_req: HttpRequest,
+) -> impl Responder {
+    let user = current_user.into_inner();
+    let target_user_id = path.into_inner();
+    log::info!(
+        "[Admin] 分配角色 | admin_id={}, user_id={}, role_id={}, course_sn={:?}",
+        user.id,
+        target_user_id,
+        req.role_id,
+        req.course_sn
+    );
+
+    if let Err(e) = check_permission(&permissions, Permission::RoleManage) {
+        return handle_admin_error(e);
+    }
+
+    match PermissionService::assign_role(&data.pool, &permissions, target_user_id, &req, user.id)
+        .await
+    {
+        Ok(assignment) => {
+            log_role_action(
+                &data.pool,
+                user.id,
+                AuditAction::RoleAssigned,
+                "user",
+                target_user_id,
+                serde_json::json!({
+                    "assignment_id": assignment.id,
+                    "role_name": assignment.role_name,
+                    "course_sn": assignment.course_sn,
+                }),
+                &http_req,
+            )
+            .await;
+            HttpResponse::Created().json(assignment)
+        }
+        Err(e) => handle_permission_error(e),
+    }
+}
+
+/// 取消用户的角色分配
+#[delete("/admin/users/{user_id}/roles/{assignment_id}")]
+async fn revoke_user_role(
+    data: web::Data<AppState>,
+    current_user: actix_web::web::ReqData<CurrentUser>,
+    permissions: web::ReqData<UserPermissions>,
+    path: web::Path<(Uuid, Uuid)>,
+    http_req: HttpRequest,
+) -> impl Responder {
+    let user = current_user.into_inner();
+    let (target_user_id, assignment_id) = path.into_inner();
+    log::info!(
+        "[Admin] 取消角色 | admin_id={}, user_id={}, assignment_id={}",
+        user.id,
+        target_user_id,
+        assignment_id
+    );
+
+    if let Err(e) = check_permission(&permissions, Permission::RoleManage) {
+        return handle_admin_error(e);
+    }
+
+    match PermissionService::revoke_role(&data.pool, &permissions, target_user_id, assignment_id)
+        .await
+    {
+        Ok(assignment) => {
+            log_role_action(
+                &data.pool,
+                user.id,
+                AuditAction::RoleRevoked,
+                "user",
+                target_user_id,
+                serde_json::json!({
+                    "assignment_id": assignment.id,
+                    "role_name": assignment.role_name,
+                    "course_sn": assignment.course_sn,
+                }),
+                &http_req,
+            )
+            .await;
+            no_content()
+        }
+        Err(e) => handle_permission_error(e),
+    }
+}
+
 /// 配置管理后台路由
 pub fn config(cfg: &mut web::ServiceConfig) {
     cfg.service(get_dashboard)
@@ -1500,8 +2572,33 @@ pub fn config(cfg: &mut web::ServiceConfig) {
         .service(batch_delete_courses)
         // 资料管理
         .service(get_all_resources)
+        .service(get_duplicate_resources)
         .service(admin_delete_resource)
         .service(admin_recalculate_resource_hash)
         .service(get_admin_favorites)
-        .service(delete_all_favorite_resources);
+        .service(delete_all_favorite_resources)
+        // 存储迁移
+        .service(create_storage_migration)
+        .service(get_storage_migrations)
+        .service(get_storage_migration)
+        // 资源申领审核
+        .service(get_claim_list)
+        .service(review_claim)
+        // 实名认证审核
+        .service(get_verification_list)
+        .service(get_verification_detail)
+        .service(review_verification)
+        // 举报处理
+        .service(get_report_queue)
+        .service(get_target_reports)
+        .service(resolve_reports)
+        // 角色与权限
+        .service(get_permission_list)
+        .service(get_role_list)
+        .service(create_role)
+        .service(update_role)
+        .service(delete_role)
+        .service(get_user_roles)
+        .service(assign_user_role)
+        .service(revoke_user_role);
 }
@@ -1,16 +1,26 @@
+use crate::config::Config;
 use crate::db::AppState;
-use crate::models::{LoginRequest, RegisterRequest};
-use crate::services::{AuditLogService, AuthError, AuthService};
-use crate::utils::{bad_request, conflict, internal_error, unauthorized};
+use crate::models::{
+    AuthResponse, CurrentUser, ForgotPasswordRequest, LoginRequest, LoginTwoFactorRequest,
+    RegisterRequest, ResetPasswordRequest, SessionClientInfo, SessionListResponse, SessionResponse,
+    SessionRevokeReason, VerifyEmailRequest,
+};
+use crate::services::{
+    AuditAction, AuditLogService, AuthError, AuthService, LoginLimits, LoginResult, SessionService,
+};
+use crate::utils::{
+    bad_request, conflict, internal_error, not_found, too_many_requests, unauthorized, verify_token,
+};
 use actix_web::cookie::{time::Duration as CookieDuration, Cookie, SameSite};
-use actix_web::{post, web, HttpRequest, HttpResponse, Responder};
+use actix_web::{delete, get, post, web, HttpMessage, HttpRequest, HttpResponse, Responder};
+use uuid::Uuid;
 
 /// Cookie 名称常量
-const ACCESS_TOKEN_COOKIE: &str = "access_token";
-const REFRESH_TOKEN_COOKIE: &str = "refresh_token";
+pub const ACCESS_TOKEN_COOKIE: &str = "access_token";
+pub const REFRESH_TOKEN_COOKIE: &str = "refresh_token";
 
 /// 构建 HttpOnly Cookie
-fn build_auth_cookie<'a>(
+pub fn build_auth_cookie<'a>(
     name: &'a str,
     value: &'a str,
     max_age_days: i64,
@@ -36,6 +46,18 @@ fn clear_auth_cookie<'a>(name: &'a str, secure: bool) -> Cookie<'a> {
         .finish()
 }
 
+/// 从请求中提取会话的客户端信息（User-Agent 和 IP）
+pub fn session_client_info(req: &HttpRequest) -> SessionClientInfo {
+    SessionClientInfo {
+        user_agent: req
+            .headers()
+            .get(actix_web::http::header::USER_AGENT)
+            .and_then(|value| value.to_str().ok())
+            .map(|value| value.to_string()),
+        ip_address: req.peer_addr().map(|addr| addr.ip().to_string()),
+    }
+}
+
 /// 注册
 #[post("/auth/register")]
 pub async fn register(
@@ -46,7 +68,16 @@ pub async fn register(
     let username = req.username.clone();
     log::info!("[Auth] 用户注册请求 | username={}", username);
 
-    match AuthService::register(&state.pool, &state.jwt_secret, req.into_inner(), state.require_email_on_register).await {
+    let client = session_client_info(&http_req);
+    match AuthService::register(
+        &state.pool,
+        &state.jwt_secret,
+        req.into_inner(),
+        state.require_email_on_register,
+        &client,
+    )
+    .await
+    {
         Ok(response) => {
             log::info!(
                 "[Auth] 用户注册成功 | user_id={}, username={}",
@@ -66,6 +97,24 @@ pub async fn register(
             )
             .await;
 
+            // 填写了邮箱时发送验证邮件，发送失败不影响注册
+            if response.user.email.is_some() {
+                if let Err(e) = AuthService::send_email_verification(
+                    &state.pool,
+                    &Config::from_env(),
+                    &state.mailer,
+                    response.user.id,
+                )
+                .await
+                {
+                    log::warn!(
+                        "[Auth] 发送邮箱验证邮件失败 | user_id={}, error={}",
+                        response.user.id,
+                        e
+                    );
+                }
+            }
+
             // 设置 HttpOnly Cookies
             let access_cookie = build_auth_cookie(
                 ACCESS_TOKEN_COOKIE,
@@ -98,6 +147,8 @@ pub async fn register(
 }
 
 /// 登录
+///
+/// 用户启用了两步验证时返回临时令牌，需要再调用 `/auth/login/2fa` 提交验证码
 #[post("/auth/login")]
 pub async fn login(
     state: web::Data<AppState>,
@@ -107,17 +158,27 @@ pub async fn login(
     let username = req.username.clone();
     log::info!("[Auth] 用户登录请求 | username={}", username);
 
-    match AuthService::login(&state.pool, &state.jwt_secret, req.into_inner()).await {
-        Ok(response) => {
+    let client = session_client_info(&http_req);
+    let limits = LoginLimits::from_config(&Config::from_env());
+    // 获取 IP 地址
+    let ip_address = http_req.peer_addr().map(|addr| addr.ip().to_string());
+
+    match AuthService::login(
+        &state.pool,
+        &state.jwt_secret,
+        req.into_inner(),
+        &client,
+        limits.as_ref(),
+    )
+    .await
+    {
+        Ok(LoginResult::Authenticated(response)) => {
             log::info!(
                 "[Auth] 用户登录成功 | user_id={}, username={}",
                 response.user.id,
                 response.user.username
             );
 
-            // 获取 IP 地址
-            let ip_address = http_req.peer_addr().map(|addr| addr.ip().to_string());
-
             // 记录审计日志
             let _ = AuditLogService::log_login(
                 &state.pool,
@@ -127,39 +188,176 @@ pub async fn login(
             )
             .await;
 
-            // 设置 HttpOnly Cookies
-            let access_cookie = build_auth_cookie(
-                ACCESS_TOKEN_COOKIE,
-                &response.tokens.access_token,
-                1, // 1天
-                state.cookie_secure,
+            login_success_response(&state, response)
+        }
+        Ok(LoginResult::TwoFactorRequired { user_id, challenge }) => {
+            log::info!(
+                "[Auth] 用户登录需要两步验证 | user_id={}, username={}",
+                user_id,
+                username
             );
-            let refresh_cookie = build_auth_cookie(
-                REFRESH_TOKEN_COOKIE,
-                &response.tokens.refresh_token,
-                7, // 7天
-                state.cookie_secure,
+            HttpResponse::Ok().json(challenge)
+        }
+        Err(e) => {
+            log::warn!("[Auth] 用户登录失败 | username={}, error={}", username, e);
+            login_error_response(
+                &state,
+                e,
+                &username,
+                "invalid_credentials",
+                ip_address.as_deref(),
+            )
+            .await
+        }
+    }
+}
+
+/// 登录第二步：提交两步验证码或恢复码
+#[post("/auth/login/2fa")]
+pub async fn login_two_factor(
+    state: web::Data<AppState>,
+    req: web::Json<LoginTwoFactorRequest>,
+    http_req: HttpRequest,
+) -> impl Responder {
+    // 临时令牌无效时用户名为空，只用于日志
+    let username = verify_token(&req.mfa_token, &state.jwt_secret, Some("mfa"))
+        .map(|claims| claims.username)
+        .unwrap_or_default();
+    log::info!("[Auth] 两步验证登录请求 | username={}", username);
+
+    let client = session_client_info(&http_req);
+    let limits = LoginLimits::from_config(&Config::from_env());
+    let ip_address = http_req.peer_addr().map(|addr| addr.ip().to_string());
+
+    match AuthService::login_two_factor(
+        &state.pool,
+        &state.jwt_secret,
+        state.totp_cipher.as_deref(),
+        req.into_inner(),
+        &client,
+        limits.as_ref(),
+    )
+    .await
+    {
+        Ok((response, method)) => {
+            log::info!(
+                "[Auth] 两步验证登录成功 | user_id={}, username={}, method={}",
+                response.user.id,
+                response.user.username,
+                method.as_str()
             );
 
-            // 返回用户信息（不包含token），直接返回用户对象（符合API规范）
-            HttpResponse::Ok()
-                .cookie(access_cookie)
-                .cookie(refresh_cookie)
-                .json(response.user)
+            let details = serde_json::json!({
+                "username": response.user.username,
+                "mfa_method": method.as_str(),
+            });
+            let _ = AuditLogService::log(
+                &state.pool,
+                Some(response.user.id),
+                AuditAction::Login,
+                Some("user"),
+                Some(response.user.id),
+                Some(details),
+                ip_address.as_deref(),
+            )
+            .await;
+
+            login_success_response(&state, response)
         }
         Err(e) => {
-            log::warn!("[Auth] 用户登录失败 | username={}, error={}", username, e);
+            log::warn!(
+                "[Auth] 两步验证登录失败 | username={}, error={}",
+                username,
+                e
+            );
             match e {
-                // 使用 400 而不是 401，因为 401 会触发前端刷新 token 逻辑
-                // 密码错误是业务错误，不是认证状态问题
-                AuthError::InvalidCredentials(msg) => bad_request(&msg),
-                AuthError::ValidationError(msg) => bad_request(&msg),
-                _ => internal_error("登录失败"),
+                AuthError::TokenInvalid(msg) => bad_request(&msg),
+                e => {
+                    login_error_response(
+                        &state,
+                        e,
+                        &username,
+                        "invalid_2fa_code",
+                        ip_address.as_deref(),
+                    )
+                    .await
+                }
             }
         }
     }
 }
 
+/// 登录成功：设置 Cookie 并返回用户信息
+fn login_success_response(state: &AppState, response: AuthResponse) -> HttpResponse {
+    // 设置 HttpOnly Cookies
+    let access_cookie = build_auth_cookie(
+        ACCESS_TOKEN_COOKIE,
+        &response.tokens.access_token,
+        1, // 1天
+        state.cookie_secure,
+    );
+    let refresh_cookie = build_auth_cookie(
+        REFRESH_TOKEN_COOKIE,
+        &response.tokens.refresh_token,
+        7, // 7天
+        state.cookie_secure,
+    );
+
+    // 返回用户信息（不包含token），直接返回用户对象（符合API规范）
+    HttpResponse::Ok()
+        .cookie(access_cookie)
+        .cookie(refresh_cookie)
+        .json(response.user)
+}
+
+/// 登录失败的响应，密码错误和验证码错误时以 `failure_reason` 记录审计日志
+async fn login_error_response(
+    state: &AppState,
+    e: AuthError,
+    username: &str,
+    failure_reason: &str,
+    ip_address: Option<&str>,
+) -> HttpResponse {
+    match e {
+        // 使用 400 而不是 401，因为 401 会触发前端刷新 token 逻辑
+        // 密码错误是业务错误，不是认证状态问题
+        AuthError::InvalidCredentials(msg) => {
+            let _ = AuditLogService::log_login_failed(
+                &state.pool,
+                None,
+                username,
+                failure_reason,
+                ip_address,
+            )
+            .await;
+            bad_request(&msg)
+        }
+        AuthError::TooManyAttempts(retry_after) => too_many_requests(
+            &format!("登录尝试过于频繁，请 {} 秒后重试", retry_after),
+            retry_after,
+        ),
+        AuthError::AccountLocked(user_id, lockout_secs) => {
+            let _ = AuditLogService::log_account_locked(
+                &state.pool,
+                user_id,
+                username,
+                lockout_secs,
+                ip_address,
+            )
+            .await;
+            too_many_requests(
+                &format!(
+                    "登录失败次数过多，账号已临时锁定，请 {} 秒后重试",
+                    lockout_secs
+                ),
+                lockout_secs,
+            )
+        }
+        AuthError::ValidationError(msg) => bad_request(&msg),
+        _ => internal_error("登录失败"),
+    }
+}
+
 /// 刷新 Token
 #[post("/auth/refresh")]
 pub async fn refresh(state: web::Data<AppState>, req: HttpRequest) -> impl Responder {
@@ -177,7 +375,8 @@ pub async fn refresh(state: web::Data<AppState>, req: HttpRequest) -> impl Respo
 
     let refresh_token = refresh_token.unwrap();
 
-    match AuthService::refresh_token(&state.pool, &state.jwt_secret, refresh_token).await {
+    let client = session_client_info(&req);
+    match AuthService::refresh_token(&state.pool, &state.jwt_secret, refresh_token, &client).await {
         Ok(tokens) => {
             log::info!("[Auth] Token刷新成功");
 
@@ -206,6 +405,21 @@ pub async fn refresh(state: web::Data<AppState>, req: HttpRequest) -> impl Respo
             log::warn!("[Auth] Token刷新失败 | error={}", e);
             match e {
                 AuthError::TokenInvalid(msg) => unauthorized(&msg),
+                AuthError::TokenReused(user_id) => {
+                    // 记录审计日志
+                    let ip_address = req.peer_addr().map(|addr| addr.ip().to_string());
+                    let _ = AuditLogService::log_action(
+                        &state.pool,
+                        user_id,
+                        "refresh_token_reuse_detected",
+                        Some("session"),
+                        None,
+                        None,
+                        ip_address.as_deref(),
+                    )
+                    .await;
+                    unauthorized("登录状态异常，所有会话已退出，请重新登录")
+                }
                 _ => internal_error("刷新失败"),
             }
         }
@@ -214,9 +428,25 @@ pub async fn refresh(state: web::Data<AppState>, req: HttpRequest) -> impl Respo
 
 /// 登出
 #[post("/auth/logout")]
-pub async fn logout(state: web::Data<AppState>) -> impl Responder {
+pub async fn logout(state: web::Data<AppState>, req: HttpRequest) -> impl Responder {
     log::info!("[Auth] 用户登出");
 
+    // 吊销当前会话，失败不影响清除 Cookie
+    let refresh_token = req
+        .cookie(REFRESH_TOKEN_COOKIE)
+        .map(|c| c.value().to_string());
+    let current_user = req.extensions().get::<CurrentUser>().cloned();
+    if let Err(e) = AuthService::logout(
+        &state.pool,
+        &state.jwt_secret,
+        refresh_token.as_deref(),
+        current_user.as_ref(),
+    )
+    .await
+    {
+        log::warn!("[Auth] 吊销登录会话失败 | error={}", e);
+    }
+
     // 清除 Cookies
     let access_cookie = clear_auth_cookie(ACCESS_TOKEN_COOKIE, state.cookie_secure);
     let refresh_cookie = clear_auth_cookie(REFRESH_TOKEN_COOKIE, state.cookie_secure);
@@ -229,10 +459,275 @@ pub async fn logout(state: web::Data<AppState>) -> impl Responder {
         }))
 }
 
+/// 验证邮箱
+#[post("/auth/email/verify")]
+pub async fn verify_email(
+    state: web::Data<AppState>,
+    req: web::Json<VerifyEmailRequest>,
+    http_req: HttpRequest,
+) -> impl Responder {
+    log::info!("[Auth] 邮箱验证请求");
+
+    match AuthService::verify_email(&state.pool, &state.jwt_secret, &req.token).await {
+        Ok(user_id) => {
+            log::info!("[Auth] 邮箱验证成功 | user_id={}", user_id);
+
+            let ip_address = http_req.peer_addr().map(|addr| addr.ip().to_string());
+            let _ = AuditLogService::log(
+                &state.pool,
+                Some(user_id),
+                AuditAction::EmailVerified,
+                Some("user"),
+                Some(user_id),
+                None,
+                ip_address.as_deref(),
+            )
+            .await;
+
+            HttpResponse::Ok().json(serde_json::json!({
+                "message": "邮箱验证成功"
+            }))
+        }
+        Err(e) => {
+            log::warn!("[Auth] 邮箱验证失败 | error={}", e);
+            match e {
+                // 使用 400 而不是 401，避免触发前端刷新 token 逻辑
+                AuthError::TokenInvalid(msg) => bad_request(&msg),
+                _ => internal_error("邮箱验证失败"),
+            }
+        }
+    }
+}
+
+/// 申请重置密码（忘记密码）
+#[post("/auth/password/forgot")]
+pub async fn forgot_password(
+    state: web::Data<AppState>,
+    req: web::Json<ForgotPasswordRequest>,
+    http_req: HttpRequest,
+) -> impl Responder {
+    log::info!("[Auth] 申请重置密码");
+
+    let config = Config::from_env();
+    match AuthService::request_password_reset(&state.pool, &config, &state.mailer, req.into_inner())
+        .await
+    {
+        Ok(user_id) => {
+            if let Some(user_id) = user_id {
+                let ip_address = http_req.peer_addr().map(|addr| addr.ip().to_string());
+                let _ = AuditLogService::log(
+                    &state.pool,
+                    Some(user_id),
+                    AuditAction::PasswordResetRequested,
+                    Some("user"),
+                    Some(user_id),
+                    None,
+                    ip_address.as_deref(),
+                )
+                .await;
+            }
+
+            // 无论邮箱是否存在都返回相同结果
+            HttpResponse::Ok().json(serde_json::json!({
+                "message": "如果该邮箱已绑定并完成验证，重置密码邮件将很快送达"
+            }))
+        }
+        Err(e) => {
+            log::warn!("[Auth] 申请重置密码失败 | error={}", e);
+            match e {
+                AuthError::ValidationError(msg) => bad_request(&msg),
+                _ => internal_error("申请重置密码失败"),
+            }
+        }
+    }
+}
+
+/// 重置密码
+#[post("/auth/password/reset")]
+pub async fn reset_password(
+    state: web::Data<AppState>,
+    req: web::Json<ResetPasswordRequest>,
+    http_req: HttpRequest,
+) -> impl Responder {
+    log::info!("[Auth] 重置密码请求");
+
+    match AuthService::reset_password(&state.pool, &state.jwt_secret, req.into_inner()).await {
+        Ok(user_id) => {
+            log::info!("[Auth] 密码重置成功 | user_id={}", user_id);
+
+            let ip_address = http_req.peer_addr().map(|addr| addr.ip().to_string());
+            let _ = AuditLogService::log(
+                &state.pool,
+                Some(user_id),
+                AuditAction::PasswordReset,
+                Some("user"),
+                Some(user_id),
+                None,
+                ip_address.as_deref(),
+            )
+            .await;
+
+            // 所有会话已吊销，同时清除当前浏览器的 Cookie
+            let access_cookie = clear_auth_cookie(ACCESS_TOKEN_COOKIE, state.cookie_secure);
+            let refresh_cookie = clear_auth_cookie(REFRESH_TOKEN_COOKIE, state.cookie_secure);
+
+            HttpResponse::Ok()
+                .cookie(access_cookie)
+                .cookie(refresh_cookie)
+                .json(serde_json::json!({
+                    "message": "密码已重置，请使用新密码登录"
+                }))
+        }
+        Err(e) => {
+            log::warn!("[Auth] 重置密码失败 | error={}", e);
+            match e {
+                AuthError::TokenInvalid(msg) => bad_request(&msg),
+                AuthError::ValidationError(msg) => bad_request(&msg),
+                _ => internal_error("重置密码失败"),
+            }
+        }
+    }
+}
+
+/// 获取当前用户的登录会话列表
+#[get("/auth/sessions")]
+pub async fn list_sessions(
+    state: web::Data<AppState>,
+    current_user: web::ReqData<CurrentUser>,
+) -> impl Responder {
+    let user = current_user.into_inner();
+
+    match SessionService::get_active_sessions(&state.pool, user.id).await {
+        Ok(sessions) => HttpResponse::Ok().json(SessionListResponse {
+            sessions: sessions
+                .into_iter()
+                .map(|session| SessionResponse::from_session(session, user.session_id))
+                .collect(),
+        }),
+        Err(e) => {
+            log::error!("[Auth] 获取登录会话失败 | user_id={}, error={}", user.id, e);
+            internal_error("获取登录会话失败")
+        }
+    }
+}
+
+/// 吊销指定登录会话
+#[delete("/auth/sessions/{session_id}")]
+pub async fn revoke_session(
+    state: web::Data<AppState>,
+    current_user: web::ReqData<CurrentUser>,
+    path: web::Path<Uuid>,
+    http_req: HttpRequest,
+) -> impl Responder {
+    let user = current_user.into_inner();
+    let session_id = path.into_inner();
+    log::info!(
+        "[Auth] 吊销登录会话 | user_id={}, session_id={}",
+        user.id,
+        session_id
+    );
+
+    match SessionService::revoke_session(
+        &state.pool,
+        user.id,
+        session_id,
+        SessionRevokeReason::Revoked,
+    )
+    .await
+    {
+        Ok(true) => {
+            // 记录审计日志
+            let ip_address = http_req.peer_addr().map(|addr| addr.ip().to_string());
+            let _ = AuditLogService::log_action(
+                &state.pool,
+                user.id,
+                "revoke_session",
+                Some("session"),
+                Some(session_id),
+                None,
+                ip_address.as_deref(),
+            )
+            .await;
+
+            // 吊销的是当前会话时同时清除 Cookie
+            let mut response = HttpResponse::NoContent();
+            if user.session_id == Some(session_id) {
+                response
+                    .cookie(clear_auth_cookie(ACCESS_TOKEN_COOKIE, state.cookie_secure))
+                    .cookie(clear_auth_cookie(REFRESH_TOKEN_COOKIE, state.cookie_secure));
+            }
+            response.finish()
+        }
+        Ok(false) => not_found("登录会话不存在或已失效"),
+        Err(e) => {
+            log::error!(
+                "[Auth] 吊销登录会话失败 | user_id={}, session_id={}, error={}",
+                user.id,
+                session_id,
+                e
+            );
+            internal_error("吊销登录会话失败")
+        }
+    }
+}
+
+/// 吊销当前用户的全部登录会话（包括当前会话）
+#[delete("/auth/sessions")]
+pub async fn revoke_all_sessions(
+    state: web::Data<AppState>,
+    current_user: web::ReqData<CurrentUser>,
+    http_req: HttpRequest,
+) -> impl Responder {
+    let user = current_user.into_inner();
+    log::info!("[Auth] 吊销全部登录会话 | user_id={}", user.id);
+
+    match SessionService::revoke_all_sessions(&state.pool, user.id, SessionRevokeReason::RevokeAll)
+        .await
+    {
+        Ok(revoked) => {
+            // 记录审计日志
+            let ip_address = http_req.peer_addr().map(|addr| addr.ip().to_string());
+            let _ = AuditLogService::log_action(
+                &state.pool,
+                user.id,
+                "revoke_all_sessions",
+                Some("session"),
+                None,
+                Some(serde_json::json!({ "revoked_count": revoked })),
+                ip_address.as_deref(),
+            )
+            .await;
+
+            HttpResponse::Ok()
+                .cookie(clear_auth_cookie(ACCESS_TOKEN_COOKIE, state.cookie_secure))
+                .cookie(clear_auth_cookie(REFRESH_TOKEN_COOKIE, state.cookie_secure))
+                .json(serde_json::json!({
+                    "message": "已退出全部登录会话",
+                    "revokedCount": revoked
+                }))
+        }
+        Err(e) => {
+            log::error!(
+                "[Auth] 吊销全部登录会话失败 | user_id={}, error={}",
+                user.id,
+                e
+            );
+            internal_error("吊销登录会话失败")
+        }
+    }
+}
+
 /// 配置认证路由
 pub fn config(cfg: &mut web::ServiceConfig) {
     cfg.service(register)
         .service(login)
+        .service(login_two_factor)
         .service(refresh)
-        .service(logout);
+        .service(logout)
+        .service(verify_email)
+        .service(forgot_password)
+        .service(reset_password)
+        .service(list_sessions)
+        .service(revoke_all_sessions)
+        .service(revoke_session);
 }
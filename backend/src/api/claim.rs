@@ -0,0 +1,155 @@
+use actix_web::{get, post, web, HttpRequest, HttpResponse, Responder};
+use uuid::Uuid;
+
+use crate::config::Config;
+use crate::db::AppState;
+use crate::models::{ClaimListQuery, CreateClaimRequest, CurrentUser, Permission};
+use crate::services::{AuditLogService, ClaimService, PermissionService, ResourceError};
+use crate::utils::{bad_request, conflict, created, forbidden, internal_error, not_found};
+
+/// 将ResourceError转换为HttpResponse
+fn handle_claim_error(err: ResourceError, fallback: &str) -> HttpResponse {
+    match err {
+        ResourceError::ValidationError(msg) => bad_request(&msg),
+        ResourceError::NotFound(msg) => not_found(&msg),
+        ResourceError::Unauthorized(msg) => forbidden(&msg),
+        ResourceError::Conflict(msg) => conflict(&msg),
+        _ => internal_error(fallback),
+    }
+}
+
+/// 提交资源申领
+#[post("/resources/{resource_id}/claims")]
+pub async fn create_claim(
+    state: web::Data<AppState>,
+    user: web::ReqData<CurrentUser>,
+    path: web::Path<Uuid>,
+    request: web::Json<CreateClaimRequest>,
+    req: HttpRequest,
+) -> impl Responder {
+    let resource_id = path.into_inner();
+
+    log::info!(
+        "[Claim] 提交资源申领 | resource_id={}, user_id={}",
+        resource_id,
+        user.id
+    );
+
+    let config = Config::from_env();
+    match ClaimService::create_claim(
+        &state.pool,
+        resource_id,
+        user.id,
+        request.into_inner(),
+        &config.image_base_url,
+    )
+    .await
+    {
+        Ok(response) => {
+            // 记录审计日志
+            let ip_address = req.peer_addr().map(|addr| addr.ip().to_string());
+            if let Err(e) = AuditLogService::log_action(
+                &state.pool,
+                user.id,
+                "create_claim",
+                Some("claim"),
+                Some(response.id),
+                Some(serde_json::json!({
+                    "resource_id": resource_id,
+                    "claim_type": response.claim_type,
+                })),
+                ip_address.as_deref(),
+            )
+            .await
+            {
+                log::warn!(
+                    "[Audit] 记录提交申领日志失败 | claim_id={}, error={}",
+                    response.id,
+                    e
+                );
+            }
+
+            created(response)
+        }
+        Err(e) => {
+            log::warn!(
+                "[Claim] 提交资源申领失败 | resource_id={}, user_id={}, error={}",
+                resource_id,
+                user.id,
+                e
+            );
+            handle_claim_error(e, "提交申领失败")
+        }
+    }
+}
+
+/// 获取我的申领列表
+#[get("/claims/my")]
+pub async fn get_my_claims(
+    state: web::Data<AppState>,
+    user: web::ReqData<CurrentUser>,
+    query: web::Query<ClaimListQuery>,
+) -> impl Responder {
+    log::debug!("[Claim] 获取我的申领列表 | user_id={}", user.id);
+
+    let config = Config::from_env();
+    match ClaimService::get_user_claims(
+        &state.pool,
+        user.id,
+        query.into_inner(),
+        &config.image_base_url,
+    )
+    .await
+    {
+        Ok(response) => HttpResponse::Ok().json(response),
+        Err(e) => {
+            log::warn!(
+                "[Claim] 获取我的申领列表失败 | user_id={}, error={}",
+                user.id,
+                e
+            );
+            handle_claim_error(e, "获取申领列表失败")
+        }
+    }
+}
+
+/// 获取申领详情（仅申领人或有申领审核权限的管理员可查看）
+#[get("/claims/{claim_id}")]
+pub async fn get_claim_detail(
+    state: web::Data<AppState>,
+    user: web::ReqData<CurrentUser>,
+    path: web::Path<Uuid>,
+) -> impl Responder {
+    let claim_id = path.into_inner();
+
+    let config = Config::from_env();
+    match ClaimService::get_claim(&state.pool, claim_id, &config.image_base_url).await {
+        Ok(response) => {
+            if response.applicant_id != user.id {
+                match PermissionService::load_user_permissions(&state.pool, user.id, &user.role)
+                    .await
+                {
+                    Ok(permissions) if permissions.has(Permission::ClaimReview) => {}
+                    Ok(_) => return forbidden("无权查看该申领"),
+                    Err(e) => {
+                        log::error!(
+                            "[Claim] 加载用户权限失败 | user_id={}, error={}",
+                            user.id,
+                            e
+                        );
+                        return internal_error("获取申领详情失败");
+                    }
+                }
+            }
+            HttpResponse::Ok().json(response)
+        }
+        Err(e) => handle_claim_error(e, "获取申领详情失败"),
+    }
+}
+
+/// 配置申领路由
+pub fn config(cfg: &mut web::ServiceConfig) {
+    cfg.service(create_claim)
+        .service(get_my_claims)
+        .service(get_claim_detail);
+}
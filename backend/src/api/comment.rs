@@ -1,10 +1,177 @@
-use actix_web::{delete, web, HttpRequest, HttpResponse, Responder};
+use actix_web::{delete, get, put, web, HttpRequest, HttpResponse, Responder};
 use uuid::Uuid;
 
 use crate::db::AppState;
-use crate::models::{CurrentUser, UserRole};
+use crate::models::{
+    CommentListQuery, CurrentUser, MarkHelpfulRequest, UpdateCommentRequest, UserRole,
+    VoteCommentRequest,
+};
 use crate::services::{AuditLogService, CommentService, ResourceError};
-use crate::utils::{forbidden, internal_error, not_found};
+use crate::utils::{bad_request, forbidden, internal_error, not_found};
+
+/// 获取楼层回复列表（公开接口，不需要登录）
+#[get("/comments/{comment_id}/replies")]
+pub async fn get_replies(
+    state: web::Data<AppState>,
+    user: Option<web::ReqData<CurrentUser>>,
+    path: web::Path<Uuid>,
+    query: web::Query<CommentListQuery>,
+) -> impl Responder {
+    let comment_id = path.into_inner();
+    let viewer_id = user.map(|u| u.id);
+
+    match CommentService::get_replies(&state.pool, comment_id, viewer_id, query.into_inner()).await
+    {
+        Ok(replies) => HttpResponse::Ok().json(replies),
+        Err(e) => {
+            log::warn!(
+                "[Comment] 获取回复失败 | comment_id={}, error={}",
+                comment_id,
+                e
+            );
+            match e {
+                ResourceError::NotFound(msg) => not_found(&msg),
+                ResourceError::ValidationError(msg) => bad_request(&msg),
+                _ => internal_error("获取回复失败"),
+            }
+        }
+    }
+}
+
+/// 获取评论编辑历史（公开接口，不需要登录）
+#[get("/comments/{comment_id}/history")]
+pub async fn get_edit_history(state: web::Data<AppState>, path: web::Path<Uuid>) -> impl Responder {
+    let comment_id = path.into_inner();
+
+    match CommentService::get_edit_history(&state.pool, comment_id).await {
+        Ok(history) => HttpResponse::Ok().json(history),
+        Err(ResourceError::NotFound(msg)) => not_found(&msg),
+        Err(e) => {
+            log::warn!(
+                "[Comment] 获取编辑历史失败 | comment_id={}, error={}",
+                comment_id,
+                e
+            );
+            internal_error("获取编辑历史失败")
+        }
+    }
+}
+
+/// 编辑评论
+#[put("/comments/{comment_id}")]
+pub async fn update_comment(
+    state: web::Data<AppState>,
+    user: web::ReqData<CurrentUser>,
+    path: web::Path<Uuid>,
+    request: web::Json<UpdateCommentRequest>,
+    req: HttpRequest,
+) -> impl Responder {
+    let comment_id = path.into_inner();
+
+    match CommentService::update_comment(
+        &state.pool,
+        &state.moderation,
+        comment_id,
+        user.id,
+        request.into_inner(),
+    )
+    .await
+    {
+        Ok(comment) => {
+            // 记录审计日志
+            let ip_address = req.peer_addr().map(|addr| addr.ip().to_string());
+            if let Err(e) = AuditLogService::log_update_comment(
+                &state.pool,
+                user.id,
+                comment_id,
+                ip_address.as_deref(),
+            )
+            .await
+            {
+                log::warn!(
+                    "[Audit] 记录编辑评论日志失败 | comment_id={}, error={}",
+                    comment_id,
+                    e
+                );
+            }
+
+            HttpResponse::Ok().json(comment)
+        }
+        Err(e) => {
+            log::warn!(
+                "[Comment] 编辑评论失败 | comment_id={}, user_id={}, error={}",
+                comment_id,
+                user.id,
+                e
+            );
+            match e {
+                ResourceError::NotFound(msg) => not_found(&msg),
+                ResourceError::ValidationError(msg) => bad_request(&msg),
+                ResourceError::Unauthorized(msg) => forbidden(&msg),
+                _ => internal_error("编辑失败"),
+            }
+        }
+    }
+}
+
+/// 对评论投票（赞同、反对或取消）
+#[put("/comments/{comment_id}/vote")]
+pub async fn vote_comment(
+    state: web::Data<AppState>,
+    user: web::ReqData<CurrentUser>,
+    path: web::Path<Uuid>,
+    request: web::Json<VoteCommentRequest>,
+) -> impl Responder {
+    let comment_id = path.into_inner();
+
+    match CommentService::vote_comment(&state.pool, comment_id, user.id, request.into_inner()).await
+    {
+        Ok(vote) => HttpResponse::Ok().json(vote),
+        Err(e) => {
+            log::warn!(
+                "[Comment] 评论投票失败 | comment_id={}, user_id={}, error={}",
+                comment_id,
+                user.id,
+                e
+            );
+            match e {
+                ResourceError::NotFound(msg) => not_found(&msg),
+                ResourceError::ValidationError(msg) => bad_request(&msg),
+                _ => internal_error("投票失败"),
+            }
+        }
+    }
+}
+
+/// 标记或取消有帮助的评论（资源上传者）
+#[put("/comments/{comment_id}/helpful")]
+pub async fn mark_helpful(
+    state: web::Data<AppState>,
+    user: web::ReqData<CurrentUser>,
+    path: web::Path<Uuid>,
+    request: web::Json<MarkHelpfulRequest>,
+) -> impl Responder {
+    let comment_id = path.into_inner();
+
+    match CommentService::mark_helpful(&state.pool, comment_id, user.id, request.into_inner()).await
+    {
+        Ok(comment) => HttpResponse::Ok().json(comment),
+        Err(e) => {
+            log::warn!(
+                "[Comment] 标记有帮助评论失败 | comment_id={}, user_id={}, error={}",
+                comment_id,
+                user.id,
+                e
+            );
+            match e {
+                ResourceError::NotFound(msg) => not_found(&msg),
+                ResourceError::ValidationError(msg) => bad_request(&msg),
+                ResourceError::Unauthorized(msg) => forbidden(&msg),
+                _ => internal_error("操作失败"),
+            }
+        }
+    }
+}
 
 /// 删除评论
 #[delete("/comments/{comment_id}")]
@@ -50,5 +217,10 @@ pub async fn delete_comment(
 
 /// 配置评论路由
 pub fn config(cfg: &mut web::ServiceConfig) {
-    cfg.service(delete_comment);
+    cfg.service(get_replies)
+        .service(get_edit_history)
+        .service(update_comment)
+        .service(vote_comment)
+        .service(mark_helpful)
+        .service(delete_comment);
 }
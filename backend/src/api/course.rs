@@ -40,7 +40,19 @@ async fn get_courses(data: web::Data<AppState>) -> impl Responder {
     }
 }
 
+/// 获取课程详情（公开API）
+#[get("/courses/{sn}")]
+async fn get_course_detail(data: web::Data<AppState>, path: web::Path<i64>) -> impl Responder {
+    let sn = path.into_inner();
+    log::info!("[Course] 获取课程详情 | sn={}", sn);
+
+    match CourseService::get_course_detail(&data.pool, sn).await {
+        Ok(response) => HttpResponse::Ok().json(response),
+        Err(e) => handle_course_error(e),
+    }
+}
+
 /// 配置课程路由
 pub fn config(cfg: &mut web::ServiceConfig) {
-    cfg.service(get_courses);
+    cfg.service(get_courses).service(get_course_detail);
 }
@@ -1,13 +1,22 @@
 use actix_web::{delete, get, post, put, web, HttpRequest, HttpResponse, Responder};
+use futures_util::StreamExt;
 use uuid::Uuid;
 
 use crate::config::Config;
 use crate::db::AppState;
 use crate::models::{
-    AddToFavoriteRequest, CreateFavoriteRequest, CurrentUser, UpdateFavoriteRequest,
+    AddToFavoriteRequest, CreateFavoriteRequest, CreatePackJobResponse, CurrentUser,
+    PackDownloadQuery, UpdateFavoriteRequest,
+};
+use crate::services::{
+    resolve_pack_storage, AuditLogService, FavoriteService, PackJobService, ResourceError,
+    StorageError,
+};
+use crate::tasks;
+use crate::utils::{
+    bad_request, build_content_disposition, conflict, forbidden, internal_error, not_found,
+    stream_file,
 };
-use crate::services::{AuditLogService, FavoriteService, ResourceError};
-use crate::utils::{bad_request, build_content_disposition, conflict, forbidden, internal_error, not_found};
 
 /// 创建收藏夹
 #[post("/favorites")]
@@ -343,7 +352,6 @@ pub async fn download_favorite(
         };
 
     let favorite_name = favorite_detail.name.clone();
-    let resource_count = favorite_detail.resource_count as usize;
 
     // 打包下载
     // 加载配置用于创建存储后端（支持混合存储）
@@ -358,36 +366,42 @@ pub async fn download_favorite(
     )
     .await
     {
-        Ok((zip_data, filename)) => {
-            let download_size = zip_data.len() as i64;
-
-            // 记录审计日志
+        Ok(pack) => {
+            // 打包结束后记录审计日志（ZIP 边生成边发送，结束时才知道实际大小）
+            let pool = state.pool.clone();
+            let user_id = user.id;
             let ip_address = req.peer_addr().map(|addr| addr.ip().to_string());
-            if let Err(e) = AuditLogService::log_pack_download(
-                &state.pool,
-                user.id,
-                favorite_id,
-                &favorite_name,
-                download_size,
-                resource_count,
-                ip_address.as_deref(),
-            )
-            .await
-            {
-                log::warn!(
-                    "[Audit] 记录打包下载日志失败 | favorite_id={}, error={}",
+            let summary = pack.summary;
+            actix_web::rt::spawn(async move {
+                let Ok(summary) = summary.await else {
+                    return;
+                };
+                if let Err(e) = AuditLogService::log_pack_download(
+                    &pool,
+                    user_id,
                     favorite_id,
-                    e
-                );
-            }
+                    &favorite_name,
+                    summary.total_bytes as i64,
+                    summary.file_count,
+                    ip_address.as_deref(),
+                )
+                .await
+                {
+                    log::warn!(
+                        "[Audit] 记录打包下载日志失败 | favorite_id={}, error={}",
+                        favorite_id,
+                        e
+                    );
+                }
+            });
 
             // 构建 Content-Disposition 头，支持中文文件名
-            let content_disposition = build_content_disposition(&filename);
+            let content_disposition = build_content_disposition(&pack.filename);
 
             HttpResponse::Ok()
                 .content_type("application/zip")
                 .append_header(("Content-Disposition", content_disposition))
-                .body(zip_data)
+                .streaming(pack.stream.map(|chunk| chunk.map(web::Bytes::from)))
         }
         Err(e) => match e {
             ResourceError::ValidationError(msg) => bad_request(&msg),
@@ -399,6 +413,158 @@ pub async fn download_favorite(
     }
 }
 
+/// 创建打包下载任务
+/// 打包在后台执行，返回任务 ID 供客户端轮询进度
+#[post("/favorites/{favorite_id}/pack-jobs")]
+pub async fn create_pack_job(
+    state: web::Data<AppState>,
+    user: web::ReqData<CurrentUser>,
+    path: web::Path<Uuid>,
+    req: HttpRequest,
+) -> impl Responder {
+    let favorite_id = path.into_inner();
+    let config = Config::from_env();
+
+    match PackJobService::create_job(&state.pool, &state.storage, &config, favorite_id, user.id)
+        .await
+    {
+        Ok((job, task)) => {
+            log::info!(
+                "[Favorite] 创建打包任务 | job_id={}, favorite_id={}, user_id={}, files={}",
+                job.id,
+                favorite_id,
+                user.id,
+                job.total_files
+            );
+
+            let response = CreatePackJobResponse::from(&job);
+            let ip_address = req.peer_addr().map(|addr| addr.ip().to_string());
+            tasks::pack_job_task::spawn_pack_job(
+                state.pool.clone(),
+                state.storage.clone(),
+                config,
+                job,
+                task,
+                ip_address,
+            );
+
+            HttpResponse::Accepted().json(response)
+        }
+        Err(e) => match e {
+            ResourceError::ValidationError(msg) => bad_request(&msg),
+            ResourceError::NotFound(msg) => not_found(&msg),
+            ResourceError::Unauthorized(msg) => forbidden(&msg),
+            _ => internal_error("创建打包任务失败"),
+        },
+    }
+}
+
+/// 获取我的打包任务列表
+#[get("/pack-jobs")]
+pub async fn get_my_pack_jobs(
+    state: web::Data<AppState>,
+    user: web::ReqData<CurrentUser>,
+) -> impl Responder {
+    let config = Config::from_env();
+    match PackJobService::get_user_jobs(&state.pool, &state.storage, &config, user.id).await {
+        Ok(response) => HttpResponse::Ok().json(response),
+        Err(_) => internal_error("获取打包任务失败"),
+    }
+}
+
+/// 获取打包任务进度（完成后返回签名下载链接）
+#[get("/pack-jobs/{job_id}")]
+pub async fn get_pack_job(
+    state: web::Data<AppState>,
+    user: web::ReqData<CurrentUser>,
+    path: web::Path<Uuid>,
+) -> impl Responder {
+    let job_id = path.into_inner();
+    let config = Config::from_env();
+
+    match PackJobService::get_job(&state.pool, &state.storage, &config, job_id, user.id).await {
+        Ok(response) => HttpResponse::Ok().json(response),
+        Err(ResourceError::NotFound(msg)) => not_found(&msg),
+        Err(_) => internal_error("获取打包任务失败"),
+    }
+}
+
+/// 下载打包文件（公开接口，通过签名校验）
+#[get("/pack-downloads/{job_id}")]
+pub async fn download_pack(
+    state: web::Data<AppState>,
+    path: web::Path<Uuid>,
+    query: web::Query<PackDownloadQuery>,
+) -> impl Responder {
+    let job_id = path.into_inner();
+    let config = Config::from_env();
+
+    let job = match PackJobService::get_download_job(&state.pool, &config, job_id, &query).await {
+        Ok(job) => job,
+        Err(ResourceError::Unauthorized(msg)) => return forbidden(&msg),
+        Err(ResourceError::NotFound(msg)) => return not_found(&msg),
+        Err(_) => return internal_error("下载打包文件失败"),
+    };
+
+    let storage_key = job.storage_key.as_deref().unwrap_or_default();
+    let filename = job.file_name.as_deref().unwrap_or("download.zip");
+    let storage = match resolve_pack_storage(
+        &state.storage,
+        &config,
+        job.storage_type.as_deref().unwrap_or("local"),
+    ) {
+        Ok(storage) => storage,
+        Err(e) => {
+            log::error!(
+                "[Favorite] 无法访问打包文件存储 | job_id={}, error={}",
+                job_id,
+                e
+            );
+            return internal_error("无法访问存储");
+        }
+    };
+
+    if storage.backend_type().is_object_storage() {
+        // 对象存储：跳转到签名下载 URL
+        let expires_secs = storage.default_signed_url_expiry();
+        return match storage
+            .get_download_url(storage_key, filename, expires_secs)
+            .await
+        {
+            Ok(download_url) => HttpResponse::Found()
+                .insert_header(("Location", download_url))
+                .finish(),
+            Err(e) => {
+                log::warn!(
+                    "[Favorite] 生成打包文件下载链接失败 | job_id={}, error={}",
+                    job_id,
+                    e
+                );
+                internal_error("生成下载链接失败")
+            }
+        };
+    }
+
+    match storage.read_stream(storage_key, None).await {
+        Ok(file) => {
+            let mut builder = HttpResponse::Ok();
+            builder
+                .content_type("application/zip")
+                .append_header(("Content-Disposition", build_content_disposition(filename)));
+            stream_file(builder, file)
+        }
+        Err(StorageError::NotFound(_)) => not_found("打包文件不存在或已过期"),
+        Err(e) => {
+            log::warn!(
+                "[Favorite] 读取打包文件失败 | job_id={}, error={}",
+                job_id,
+                e
+            );
+            internal_error("文件读取失败")
+        }
+    }
+}
+
 /// 配置收藏夹路由
 pub fn config(cfg: &mut web::ServiceConfig) {
     cfg.service(create_favorite)
@@ -409,5 +575,9 @@ pub fn config(cfg: &mut web::ServiceConfig) {
         .service(add_resource_to_favorite)
         .service(remove_resource_from_favorite)
         .service(check_resource_in_favorite)
-        .service(download_favorite);
+        .service(download_favorite)
+        .service(create_pack_job)
+        .service(get_my_pack_jobs)
+        .service(get_pack_job)
+        .service(download_pack);
 }
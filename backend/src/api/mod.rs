@@ -1,11 +1,15 @@
 pub mod admin;
 pub mod auth;
+pub mod claim;
 pub mod comment;
 pub mod course;
 pub mod favorite;
 pub mod image_host;
 pub mod notification;
+pub mod oidc;
 pub mod oss;
+pub mod report;
 pub mod resource;
 pub mod teacher;
+pub mod two_factor;
 pub mod user;
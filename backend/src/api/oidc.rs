@@ -0,0 +1,390 @@
+use super::auth::{
+    build_auth_cookie, session_client_info, ACCESS_TOKEN_COOKIE, REFRESH_TOKEN_COOKIE,
+};
+use crate::config::Config;
+use crate::db::AppState;
+use crate::models::{
+    CurrentUser, OidcAuthorizeQuery, OidcCallbackQuery, OidcLoginState, UserIdentityListResponse,
+    UserIdentityResponse,
+};
+use crate::services::{
+    oidc_error_redirect, oidc_two_factor_redirect, sanitize_redirect_path, AuditAction,
+    AuditLogService, AuthService, LoginResult, OidcClaims, OidcClient, OidcError, OidcService,
+};
+use crate::utils::{
+    bad_request, error_response, internal_error, no_content, not_found, unauthorized,
+};
+use actix_web::http::header;
+use actix_web::{delete, get, web, HttpMessage, HttpRequest, HttpResponse, Responder};
+use uuid::Uuid;
+
+/// 302 跳转
+fn redirect_to(location: &str) -> HttpResponse {
+    HttpResponse::Found()
+        .insert_header((header::LOCATION, location))
+        .finish()
+}
+
+/// 单点登录失败时展示给用户的提示
+fn oidc_error_message(err: &OidcError) -> String {
+    match err {
+        OidcError::IdentityConflict(msg)
+        | OidcError::RegistrationDisabled(msg)
+        | OidcError::AccountDisabled(msg)
+        | OidcError::ValidationError(msg) => msg.clone(),
+        OidcError::InvalidState(_) => "登录请求已过期，请重新登录".to_string(),
+        OidcError::ProviderError(_) | OidcError::InvalidToken(_) => {
+            "统一身份认证失败，请重试".to_string()
+        }
+        _ => "登录失败".to_string(),
+    }
+}
+
+/// 发起单点登录，跳转到身份提供方
+///
+/// `link=true` 时将外部身份绑定到当前登录的账号
+#[get("/auth/oidc/authorize")]
+pub async fn oidc_authorize(
+    state: web::Data<AppState>,
+    query: web::Query<OidcAuthorizeQuery>,
+    http_req: HttpRequest,
+) -> impl Responder {
+    let Some(client) = state.oidc.as_ref() else {
+        return not_found("未启用单点登录");
+    };
+
+    let link_user_id = if query.link {
+        match http_req.extensions().get::<CurrentUser>() {
+            Some(user) => Some(user.id),
+            None => return unauthorized("请先登录后再绑定外部身份"),
+        }
+    } else {
+        None
+    };
+    let redirect_path = sanitize_redirect_path(query.redirect.as_deref());
+    log::info!(
+        "[OIDC] 发起单点登录 | link_user_id={:?}, redirect={}",
+        link_user_id,
+        redirect_path
+    );
+
+    match OidcService::begin_login(&state.pool, client, link_user_id, &redirect_path).await {
+        Ok(url) => redirect_to(&url),
+        Err(e) => {
+            log::error!("[OIDC] 发起单点登录失败 | error={}", e);
+            match e {
+                OidcError::ProviderError(_) => {
+                    error_response(502, "无法连接统一身份认证服务，请稍后重试")
+                }
+                _ => internal_error("发起单点登录失败"),
+            }
+        }
+    }
+}
+
+/// 身份提供方回调：校验授权码后登录或绑定外部身份，再跳转回前端
+#[get("/auth/oidc/callback")]
+pub async fn oidc_callback(
+    state: web::Data<AppState>,
+    query: web::Query<OidcCallbackQuery>,
+    http_req: HttpRequest,
+) -> impl Responder {
+    let Some(client) = state.oidc.clone() else {
+        return not_found("未启用单点登录");
+    };
+    let frontend_base_url = Config::from_env().frontend_base_url;
+
+    if let Some(ref error) = query.error {
+        log::warn!(
+            "[OIDC] 身份提供方返回错误 | error={}, description={:?}",
+            error,
+            query.error_description
+        );
+        // 用户拒绝授权等情况同样需要使 state 失效
+        if let Some(ref login_state) = query.state {
+            let _ = OidcService::take_state(&state.pool, login_state).await;
+        }
+        return redirect_to(&oidc_error_redirect(
+            &frontend_base_url,
+            "统一身份认证未完成，请重试",
+        ));
+    }
+
+    let result = match (query.code.as_deref(), query.state.as_deref()) {
+        (Some(code), Some(login_state)) => {
+            handle_callback(&state, &client, code, login_state, &http_req).await
+        }
+        _ => Err(OidcError::InvalidState("缺少 code 或 state 参数".into())),
+    };
+
+    match result {
+        Ok(response) => response,
+        Err(e) => {
+            log::warn!("[OIDC] 单点登录失败 | error={}", e);
+            redirect_to(&oidc_error_redirect(
+                &frontend_base_url,
+                &oidc_error_message(&e),
+            ))
+        }
+    }
+}
+
+async fn handle_callback(
+    state: &AppState,
+    client: &OidcClient,
+    code: &str,
+    login_state: &str,
+    http_req: &HttpRequest,
+) -> Result<HttpResponse, OidcError> {
+    let login_state = OidcService::take_state(&state.pool, login_state)
+        .await?
+        .ok_or_else(|| OidcError::InvalidState("state 无效、已使用或已过期".into()))?;
+    let claims = client
+        .authenticate(code, &login_state.code_verifier, &login_state.nonce)
+        .await?;
+
+    match login_state.link_user_id {
+        Some(user_id) => {
+            link_callback(state, client, user_id, &claims, &login_state, http_req).await
+        }
+        None => login_callback(state, client, &claims, &login_state, http_req).await,
+    }
+}
+
+/// 绑定外部身份到已登录账号
+async fn link_callback(
+    state: &AppState,
+    client: &OidcClient,
+    user_id: Uuid,
+    claims: &OidcClaims,
+    login_state: &OidcLoginState,
+    http_req: &HttpRequest,
+) -> Result<HttpResponse, OidcError> {
+    let outcome = OidcService::link_identity(
+        &state.pool,
+        client.settings(),
+        state.real_info_cipher.as_deref(),
+        user_id,
+        claims,
+    )
+    .await?;
+    log::info!(
+        "[OIDC] 外部身份绑定成功 | user_id={}, provider={}, linked={}, verified={}",
+        user_id,
+        claims.issuer,
+        outcome.linked,
+        outcome.verified
+    );
+
+    if outcome.linked {
+        let ip_address = http_req.peer_addr().map(|addr| addr.ip().to_string());
+        let _ = AuditLogService::log(
+            &state.pool,
+            Some(user_id),
+            AuditAction::IdentityLinked,
+            Some("user"),
+            Some(user_id),
+            Some(serde_json::json!({
+                "provider": claims.issuer,
+                "subject": claims.subject,
+                "verified_by_claims": outcome.verified,
+            })),
+            ip_address.as_deref(),
+        )
+        .await;
+    }
+
+    let frontend_base_url = Config::from_env().frontend_base_url;
+    Ok(redirect_to(&format!(
+        "{}{}",
+        frontend_base_url, login_state.redirect_path
+    )))
+}
+
+/// 使用外部身份登录，未绑定时按配置自动注册
+async fn login_callback(
+    state: &AppState,
+    client: &OidcClient,
+    claims: &OidcClaims,
+    login_state: &OidcLoginState,
+    http_req: &HttpRequest,
+) -> Result<HttpResponse, OidcError> {
+    let outcome = OidcService::resolve_login(
+        &state.pool,
+        client.settings(),
+        state.real_info_cipher.as_deref(),
+        claims,
+    )
+    .await?;
+    let ip_address = http_req.peer_addr().map(|addr| addr.ip().to_string());
+
+    if outcome.registered {
+        let _ = AuditLogService::log(
+            &state.pool,
+            Some(outcome.user_id),
+            AuditAction::Register,
+            Some("user"),
+            Some(outcome.user_id),
+            Some(serde_json::json!({
+                "method": "oidc",
+                "provider": claims.issuer,
+                "subject": claims.subject,
+            })),
+            ip_address.as_deref(),
+        )
+        .await;
+    }
+
+    let client_info = session_client_info(http_req);
+    let frontend_base_url = Config::from_env().frontend_base_url;
+    match AuthService::login_external(
+        &state.pool,
+        &state.jwt_secret,
+        outcome.user_id,
+        &client_info,
+    )
+    .await?
+    {
+        LoginResult::Authenticated(response) => {
+            log::info!(
+                "[OIDC] 单点登录成功 | user_id={}, username={}, registered={}, verified={}",
+                response.user.id,
+                response.user.username,
+                outcome.registered,
+                outcome.verified
+            );
+
+            let _ = AuditLogService::log(
+                &state.pool,
+                Some(response.user.id),
+                AuditAction::Login,
+                Some("user"),
+                Some(response.user.id),
+                Some(serde_json::json!({
+                    "username": response.user.username,
+                    "method": "oidc",
+                    "provider": claims.issuer,
+                    "verified_by_claims": outcome.verified,
+                })),
+                ip_address.as_deref(),
+            )
+            .await;
+
+            let access_cookie = build_auth_cookie(
+                ACCESS_TOKEN_COOKIE,
+                &response.tokens.access_token,
+                1, // 1天
+                state.cookie_secure,
+            );
+            let refresh_cookie = build_auth_cookie(
+                REFRESH_TOKEN_COOKIE,
+                &response.tokens.refresh_token,
+                7, // 7天
+                state.cookie_secure,
+            );
+
+            Ok(HttpResponse::Found()
+                .cookie(access_cookie)
+                .cookie(refresh_cookie)
+                .insert_header((
+                    header::LOCATION,
+                    format!("{}{}", frontend_base_url, login_state.redirect_path),
+                ))
+                .finish())
+        }
+        LoginResult::TwoFactorRequired { user_id, challenge } => {
+            log::info!("[OIDC] 单点登录需要两步验证 | user_id={}", user_id);
+            Ok(redirect_to(&oidc_two_factor_redirect(
+                &frontend_base_url,
+                &challenge.mfa_token,
+                &login_state.redirect_path,
+            )))
+        }
+    }
+}
+
+/// 获取当前用户绑定的外部身份
+#[get("/users/me/identities")]
+pub async fn list_identities(
+    state: web::Data<AppState>,
+    user: web::ReqData<CurrentUser>,
+) -> impl Responder {
+    match OidcService::list_identities(&state.pool, user.id).await {
+        Ok((identities, has_password)) => {
+            let settings = state.oidc.as_ref().map(|client| client.settings());
+            let identities = identities
+                .into_iter()
+                .map(|identity| {
+                    // 身份提供方更换后，旧的绑定记录显示其 Issuer
+                    let provider_name = settings
+                        .filter(|settings| settings.issuer == identity.provider)
+                        .map(|settings| settings.provider_name.clone())
+                        .unwrap_or_else(|| identity.provider.clone());
+                    UserIdentityResponse::from_identity(identity, &provider_name)
+                })
+                .collect();
+            HttpResponse::Ok().json(UserIdentityListResponse {
+                identities,
+                has_password,
+            })
+        }
+        Err(e) => {
+            log::error!("[OIDC] 获取外部身份失败 | user_id={}, error={}", user.id, e);
+            internal_error("获取外部身份失败")
+        }
+    }
+}
+
+/// 解除外部身份绑定
+#[delete("/users/me/identities/{identity_id}")]
+pub async fn unlink_identity(
+    state: web::Data<AppState>,
+    user: web::ReqData<CurrentUser>,
+    path: web::Path<Uuid>,
+    http_req: HttpRequest,
+) -> impl Responder {
+    let identity_id = path.into_inner();
+    log::info!(
+        "[OIDC] 解除外部身份绑定 | user_id={}, identity_id={}",
+        user.id,
+        identity_id
+    );
+
+    match OidcService::unlink_identity(&state.pool, user.id, identity_id).await {
+        Ok(()) => {
+            let ip_address = http_req.peer_addr().map(|addr| addr.ip().to_string());
+            let _ = AuditLogService::log(
+                &state.pool,
+                Some(user.id),
+                AuditAction::IdentityUnlinked,
+                Some("user"),
+                Some(user.id),
+                Some(serde_json::json!({ "identity_id": identity_id })),
+                ip_address.as_deref(),
+            )
+            .await;
+            no_content()
+        }
+        Err(e) => {
+            log::warn!(
+                "[OIDC] 解除外部身份绑定失败 | user_id={}, identity_id={}, error={}",
+                user.id,
+                identity_id,
+                e
+            );
+            match e {
+                OidcError::NotFound(msg) => not_found(&msg),
+                OidcError::ValidationError(msg) => bad_request(&msg),
+                _ => internal_error("解除绑定失败"),
+            }
+        }
+    }
+}
+
+/// 配置单点登录路由
+pub fn config(cfg: &mut web::ServiceConfig) {
+    cfg.service(oidc_authorize)
+        .service(oidc_callback)
+        .service(list_identities)
+        .service(unlink_identity);
+}
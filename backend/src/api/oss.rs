@@ -10,7 +10,7 @@ use crate::services::{
     AuditLogService, FileService, ImageError, ImageService, ResourceError, ResourceService,
     StorageBackendType, StorageFileMetadata,
 };
-use crate::utils::{bad_request, created, forbidden, internal_error};
+use crate::utils::{bad_request, conflict, created, forbidden, internal_error};
 
 #[derive(Debug, Serialize)]
 #[serde(rename_all = "camelCase")]
@@ -67,6 +67,8 @@ struct ResourceUploadCallbackRequest {
     teacher_sns: Option<Vec<i64>>,
     course_sns: Option<Vec<i64>>,
     related_resource_ids: Option<Vec<Uuid>>,
+    #[serde(default)]
+    allow_duplicate: bool,
 }
 
 #[derive(Debug, Deserialize)]
@@ -92,8 +94,8 @@ async fn get_sts_token(
     user: web::ReqData<CurrentUser>,
     payload: web::Json<OssStsTokenRequest>,
 ) -> impl Responder {
-    if state.storage.backend_type() != StorageBackendType::Oss {
-        return bad_request("当前不是 OSS 存储模式，无法申请直传凭证");
+    if !state.storage.backend_type().is_object_storage() {
+        return bad_request("当前不是对象存储模式，无法申请直传凭证");
     }
 
     let (folder, max_size) = match payload.file_type.as_str() {
@@ -136,7 +138,7 @@ async fn get_sts_token(
                     upload_mode: "sts".to_string(),
                     upload_key: credentials.upload_key,
                     expires_in: credentials.expires_in,
-                    storage_backend: "oss".to_string(),
+                    storage_backend: state.storage.backend_type().as_str().to_string(),
                     upload_url: None,
                     access_key_id: Some(credentials.access_key_id),
                     access_key_secret: Some(credentials.access_key_secret),
@@ -193,7 +195,7 @@ async fn get_sts_token(
         upload_mode: "signed_url".to_string(),
         upload_key,
         expires_in,
-        storage_backend: "oss".to_string(),
+        storage_backend: state.storage.backend_type().as_str().to_string(),
         upload_url: Some(upload_url),
         access_key_id: None,
         access_key_secret: None,
@@ -212,8 +214,8 @@ async fn resource_upload_callback(
     req: HttpRequest,
     payload: web::Json<ResourceUploadCallbackRequest>,
 ) -> impl Responder {
-    if state.storage.backend_type() != StorageBackendType::Oss {
-        return internal_error("当前未启用OSS存储模式");
+    if !state.storage.backend_type().is_object_storage() {
+        return internal_error("当前未启用对象存储模式");
     }
 
     if !key_in_scope(&payload.oss_key, "resources") {
@@ -235,12 +237,14 @@ async fn resource_upload_callback(
         teacher_sns: payload.teacher_sns.clone(),
         course_sns: payload.course_sns.clone(),
         related_resource_ids: payload.related_resource_ids.clone(),
+        allow_duplicate: payload.allow_duplicate,
     };
 
     match ResourceService::create_resource_from_oss_callback(
         &state.pool,
         &user,
         &state.storage,
+        &state.moderation,
         upload_request,
         &payload.oss_key,
         metadata,
@@ -263,6 +267,7 @@ async fn resource_upload_callback(
         Err(e) => match e {
             ResourceError::ValidationError(msg) => bad_request(&msg),
             ResourceError::Unauthorized(msg) => forbidden(&msg),
+            ResourceError::Conflict(msg) => conflict(&msg),
             _ => {
                 log::error!(
                     "[OSS] 资源回调处理失败 | user_id={}, key={}, error={}",
@@ -282,8 +287,8 @@ async fn image_upload_callback(
     user: web::ReqData<CurrentUser>,
     payload: web::Json<ImageUploadCallbackRequest>,
 ) -> impl Responder {
-    if state.storage.backend_type() != StorageBackendType::Oss {
-        return internal_error("当前未启用OSS存储模式");
+    if !state.storage.backend_type().is_object_storage() {
+        return internal_error("当前未启用对象存储模式");
     }
 
     if !key_in_scope(&payload.oss_key, "images") {
@@ -0,0 +1,66 @@
+use actix_web::{get, post, web, HttpResponse, Responder};
+
+use crate::db::AppState;
+use crate::models::{CreateReportRequest, CurrentUser, ReportListQuery};
+use crate::services::{ReportError, ReportService};
+use crate::utils::{bad_request, conflict, created, internal_error, not_found};
+
+/// 将ReportError转换为HttpResponse
+fn handle_report_error(err: ReportError, fallback: &str) -> HttpResponse {
+    match err {
+        ReportError::ValidationError(msg) => bad_request(&msg),
+        ReportError::NotFound(msg) => not_found(&msg),
+        ReportError::Conflict(msg) => conflict(&msg),
+        ReportError::DatabaseError(_) => internal_error(fallback),
+    }
+}
+
+/// 举报资源或评论
+#[post("/reports")]
+pub async fn create_report(
+    state: web::Data<AppState>,
+    user: web::ReqData<CurrentUser>,
+    request: web::Json<CreateReportRequest>,
+) -> impl Responder {
+    log::info!(
+        "[Report] 提交举报 | user_id={}, target_type={}, target_id={}",
+        user.id,
+        request.target_type,
+        request.target_id
+    );
+
+    match ReportService::create_report(&state.pool, user.id, request.into_inner()).await {
+        Ok(response) => created(response),
+        Err(e) => {
+            log::warn!("[Report] 提交举报失败 | user_id={}, error={}", user.id, e);
+            handle_report_error(e, "提交举报失败")
+        }
+    }
+}
+
+/// 获取我的举报列表
+#[get("/reports/my")]
+pub async fn get_my_reports(
+    state: web::Data<AppState>,
+    user: web::ReqData<CurrentUser>,
+    query: web::Query<ReportListQuery>,
+) -> impl Responder {
+    log::debug!("[Report] 获取我的举报列表 | user_id={}", user.id);
+
+    match ReportService::get_my_reports(&state.pool, user.id, query.into_inner()).await {
+        Ok(response) => HttpResponse::Ok().json(response),
+        Err(e) => {
+            log::warn!(
+                "[Report] 获取我的举报列表失败 | user_id={}, error={}",
+                user.id,
+                e
+            );
+            handle_report_error(e, "获取举报列表失败")
+        }
+    }
+}
+
+/// 配置举报路由
+pub fn config(cfg: &mut web::ServiceConfig) {
+    cfg.service(create_report).service(get_my_reports);
+}
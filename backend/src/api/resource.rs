@@ -6,13 +6,18 @@ use uuid::Uuid;
 use crate::db::AppState;
 use crate::models::{
     resource::*, CommentListQuery, CreateCommentRequest, CreateRatingRequest, CurrentUser,
-    UpdateResourceContentRequest, UpdateResourceDescriptionRequest, UpdateResourceRelationsRequest,
+    ResourceVersionDiffQuery, UpdateResourceContentRequest, UpdateResourceDescriptionRequest,
+    UpdateResourceRelationsRequest,
 };
 use crate::services::{
-    AuditLogService, CommentService, LikeService, RatingService, ResourceError, ResourceService,
-    StorageBackendType, StorageError,
+    is_object_storage_type, storage_for_type, AuditLogService, CommentService, LikeService,
+    RatingService, RecommendationService, ResourceError, ResourceService, ResourceVersionService,
+    StorageError,
+};
+use crate::utils::{
+    bad_request, conflict, forbidden, internal_error, not_found, not_modified,
+    range_not_satisfiable, stream_file, unauthorized, ConditionalOutcome, FileValidators,
 };
-use crate::utils::{bad_request, conflict, forbidden, internal_error, not_found};
 
 /// 上传资源
 #[post("/resources")]
@@ -129,6 +134,7 @@ pub async fn upload_resource(
         &state.pool,
         &user,
         &state.storage,
+        &state.moderation,
         metadata,
         &filename,
         data,
@@ -321,6 +327,35 @@ pub async fn get_my_resources(
     }
 }
 
+/// 获取为当前用户推荐的资源
+#[get("/resources/recommended")]
+pub async fn get_recommended_resources(
+    state: web::Data<AppState>,
+    user: web::ReqData<CurrentUser>,
+    query: web::Query<RecommendedResourcesQuery>,
+) -> impl Responder {
+    let limit = query.limit.unwrap_or(10);
+    let resource_type = query
+        .resource_type
+        .as_deref()
+        .map(str::trim)
+        .filter(|t| !t.is_empty());
+
+    match RecommendationService::recommend_resources(&state.pool, user.id, resource_type, limit)
+        .await
+    {
+        Ok(resources) => HttpResponse::Ok().json(resources),
+        Err(e) => {
+            log::warn!(
+                "[Resource] 获取推荐资源失败 | user_id={}, error={}",
+                user.id,
+                e
+            );
+            internal_error("获取推荐资源失败")
+        }
+    }
+}
+
 /// 下载资源
 /// 支持未登录用户（游客）下载
 #[get("/resources/{resource_id}/download")]
@@ -335,7 +370,7 @@ pub async fn download_resource(
 
     // 获取资源文件路径和存储类型（带权限检查）
     match ResourceService::get_resource_file_path(&state.pool, resource_id, current_user.as_ref()).await {
-        Ok((file_path, resource_type, title, storage_type)) => {
+        Ok((file_path, resource_type, title, storage_type, file_hash, updated_at)) => {
             let user_id = current_user.map(|u| u.id);
             let content_type = crate::services::FileService::get_mime_type_by_type(&resource_type);
             let extension = crate::services::FileService::get_extension_by_type(&resource_type);
@@ -343,13 +378,25 @@ pub async fn download_resource(
             let content_disposition = build_content_disposition(&filename);
 
             // 根据资源实际的存储类型决定读取方式
-            let is_oss = storage_type.as_deref() == Some("oss");
+            let is_oss = is_object_storage_type(storage_type.as_deref());
 
             if is_oss {
-                // OSS 存储：生成签名下载 URL
-                let expires_secs = state.storage.default_signed_url_expiry();
-                match state
-                    .storage
+                // 对象存储：生成签名下载 URL
+                let config = crate::config::Config::from_env();
+                let object_storage =
+                    match storage_for_type(&state.storage, &config, storage_type.as_deref()) {
+                        Ok(object_storage) => object_storage,
+                        Err(e) => {
+                            log::error!(
+                                "[Resource] 无法访问资源所在的存储 | resource_id={}, error={}",
+                                resource_id,
+                                e
+                            );
+                            return internal_error("无法访问存储");
+                        }
+                    };
+                let expires_secs = object_storage.default_signed_url_expiry();
+                match object_storage
                     .get_download_url(&file_path, &filename, expires_secs)
                     .await
                 {
@@ -362,7 +409,7 @@ pub async fn download_resource(
                     }
                     Err(e) => {
                         log::warn!(
-                            "[Resource] 生成 OSS 下载链接失败 | resource_id={}, path={}, error={}",
+                            "[Resource] 生成签名下载链接失败 | resource_id={}, path={}, error={}",
                             resource_id,
                             file_path,
                             e
@@ -372,23 +419,39 @@ pub async fn download_resource(
                 }
             } else {
                 // 本地存储：需要创建本地存储实例来读取文件
+                // 支持断点续传：ETag 使用文件哈希
+                let validators = FileValidators::new(file_hash.as_deref(), Some(updated_at));
+                let range = match validators.evaluate(req.headers()) {
+                    ConditionalOutcome::NotModified => return not_modified(&validators),
+                    ConditionalOutcome::Content(range) => range,
+                };
+
                 let config = crate::config::Config::from_env();
                 match crate::services::create_local_storage(&config) {
                     Ok(local_storage) => {
-                        match local_storage.read_file(&file_path).await {
-                            Ok(file_content) => {
-                                record_download_events(&state, resource_id, user_id, &title, &req).await;
+                        match local_storage.read_stream(&file_path, range).await {
+                            Ok(file) => {
+                                // 续传请求不重复计入下载次数
+                                if file.range.is_none_or(|(start, _)| start == 0) {
+                                    record_download_events(&state, resource_id, user_id, &title, &req).await;
+                                }
 
                                 log::info!(
-                                    "[Resource] 资源下载成功 | resource_id={}, user_id={:?}, storage=local",
+                                    "[Resource] 资源下载成功 | resource_id={}, user_id={:?}, storage=local, range={:?}",
                                     resource_id,
-                                    user_id
+                                    user_id,
+                                    file.range
                                 );
 
-                                HttpResponse::Ok()
+                                let mut builder = HttpResponse::Ok();
+                                builder
                                     .content_type(content_type)
-                                    .insert_header(("Content-Disposition", content_disposition))
-                                    .body(file_content)
+                                    .insert_header(("Content-Disposition", content_disposition));
+                                validators.insert_headers(&mut builder);
+                                stream_file(builder, file)
+                            }
+                            Err(StorageError::RangeNotSatisfiable(total_size)) => {
+                                range_not_satisfiable(total_size)
                             }
                             Err(StorageError::NotFound(_)) => {
                                 log::warn!(
@@ -535,24 +598,39 @@ pub async fn get_resource_preview_url(
 
     // 获取资源文件路径和存储类型（带权限检查）
     match ResourceService::get_resource_file_path_for_preview(&state.pool, resource_id, current_user.as_ref()).await {
-        Ok((file_path, resource_type, storage_type, updated_at)) => {
-            let is_oss = storage_type.as_deref() == Some("oss");
+        Ok((file_path, resource_type, storage_type, updated_at, _)) => {
+            let is_oss = is_object_storage_type(storage_type.as_deref());
 
             // 将 updated_at 格式化为 ISO 8601 字符串
             let updated_at_str = updated_at.format("%Y-%m-%dT%H:%M:%S%.3fZ").to_string();
 
             if is_oss {
-                // OSS 存储：生成签名 URL，前端直接从 OSS 获取
-                let expires_secs = state.storage.default_signed_url_expiry();
-                match state.storage.get_file_url(&file_path, expires_secs).await {
+                // 对象存储：生成签名 URL，前端直接从 OSS / S3 获取
+                let config = crate::config::Config::from_env();
+                let object_storage =
+                    match storage_for_type(&state.storage, &config, storage_type.as_deref()) {
+                        Ok(object_storage) => object_storage,
+                        Err(e) => {
+                            log::error!(
+                                "[Resource] 无法访问资源所在的存储 | resource_id={}, error={}",
+                                resource_id,
+                                e
+                            );
+                            return internal_error("无法访问存储");
+                        }
+                    };
+                let storage_name = object_storage.backend_type().as_str();
+                let expires_secs = object_storage.default_signed_url_expiry();
+                match object_storage.get_file_url(&file_path, expires_secs).await {
                     Ok(preview_url) => {
                         log::debug!(
-                            "[Resource] 生成 OSS 预览 URL | resource_id={}, storage=oss",
-                            resource_id
+                            "[Resource] 生成签名预览 URL | resource_id={}, storage={}",
+                            resource_id,
+                            storage_name
                         );
                         HttpResponse::Ok().json(serde_json::json!({
                             "previewUrl": preview_url,
-                            "storageType": "oss",
+                            "storageType": storage_name,
                             "resourceType": resource_type,
                             "directAccess": true,
                             "updatedAt": updated_at_str
@@ -560,7 +638,7 @@ pub async fn get_resource_preview_url(
                     }
                     Err(e) => {
                         log::warn!(
-                            "[Resource] 生成 OSS 预览 URL 失败 | resource_id={}, error={}",
+                            "[Resource] 生成签名预览 URL 失败 | resource_id={}, error={}",
                             resource_id,
                             e
                         );
@@ -600,65 +678,49 @@ pub async fn get_resource_preview_url(
 /// 获取资源文件内容（用于预览）
 /// 使用后端代理模式读取文件，本地存储和OSS兜底场景使用
 /// 支持未登录用户（游客）预览
+/// 支持 Range 请求，便于 PDF.js 按需分段加载
 #[get("/resources/{resource_id}/content")]
 pub async fn get_resource_content(
     state: web::Data<AppState>,
     user: Option<web::ReqData<CurrentUser>>,
     path: web::Path<Uuid>,
+    req: HttpRequest,
 ) -> impl Responder {
     let resource_id = path.into_inner();
     let current_user = user.map(|u| u.into_inner());
 
     // 获取资源文件路径和存储类型（带权限检查）
     match ResourceService::get_resource_file_path_for_preview(&state.pool, resource_id, current_user.as_ref()).await {
-        Ok((file_path, resource_type, storage_type, updated_at)) => {
+        Ok((file_path, resource_type, storage_type, updated_at, file_hash)) => {
             // 根据资源实际的存储类型选择正确的存储后端读取文件
             // 使用后端代理模式，避免浏览器直接访问 OSS 产生 CORS 问题
-            let is_oss = storage_type.as_deref() == Some("oss");
 
             // 将 updated_at 格式化为 ISO 8601 字符串
             let updated_at_str = updated_at.format("%Y-%m-%dT%H:%M:%S%.3fZ").to_string();
 
-            let read_result = if is_oss {
-                // OSS 存储：使用主 storage（如果是 OSS 模式）或创建 OSS 存储实例
-                if state.storage.backend_type() == StorageBackendType::Oss {
-                    state.storage.read_file(&file_path).await
-                } else {
-                    // 当前是 local 模式，但需要读取 OSS 文件
-                    // 创建临时 OSS 存储实例
-                    let config = crate::config::Config::from_env();
-                    match crate::services::create_storage_backend(&config) {
-                        Ok(oss_storage) if oss_storage.backend_type() == StorageBackendType::Oss => {
-                            oss_storage.read_file(&file_path).await
-                        }
-                        _ => {
-                            log::warn!(
-                                "[Resource] 无法创建 OSS 存储实例来读取资源 | resource_id={}",
-                                resource_id
-                            );
-                            return internal_error("无法读取 OSS 资源");
-                        }
-                    }
-                }
-            } else {
-                // 本地存储：使用主 storage（如果是 Local 模式）或创建本地存储实例
-                if state.storage.backend_type() == StorageBackendType::Local {
-                    state.storage.read_file(&file_path).await
-                } else {
-                    // 当前是 OSS 模式，但需要读取本地文件
-                    let config = crate::config::Config::from_env();
-                    match crate::services::create_local_storage(&config) {
-                        Ok(local_storage) => local_storage.read_file(&file_path).await,
-                        Err(e) => {
-                            log::error!("[Resource] 创建本地存储失败 | error={}", e);
-                            return internal_error("无法访问本地存储");
-                        }
-                    }
-                }
+            let validators = FileValidators::new(file_hash.as_deref(), Some(updated_at));
+            let range = match validators.evaluate(req.headers()) {
+                ConditionalOutcome::NotModified => return not_modified(&validators),
+                ConditionalOutcome::Content(range) => range,
             };
 
+            // 使用主 storage（类型一致时）或创建资源所在的存储实例
+            let config = crate::config::Config::from_env();
+            let read_result =
+                match storage_for_type(&state.storage, &config, storage_type.as_deref()) {
+                    Ok(storage) => storage.read_stream(&file_path, range).await,
+                    Err(e) => {
+                        log::error!(
+                            "[Resource] 无法访问资源所在的存储 | resource_id={}, error={}",
+                            resource_id,
+                            e
+                        );
+                        return internal_error("无法访问存储");
+                    }
+                };
+
             match read_result {
-                Ok(file_content) => {
+                Ok(file) => {
                     // 获取 MIME 类型 - 优先使用 resource_type，因为它更准确
                     let content_type =
                         crate::services::FileService::get_mime_type_by_type(&resource_type);
@@ -669,15 +731,20 @@ pub async fn get_resource_content(
                         file_path,
                         resource_type,
                         content_type,
-                        if is_oss { "oss" } else { "local" }
+                        storage_type.as_deref().unwrap_or("local")
                     );
 
                     // 返回文件内容（inline 显示，不是下载）
-                    HttpResponse::Ok()
+                    let mut builder = HttpResponse::Ok();
+                    builder
                         .content_type(content_type)
                         .insert_header(("Cache-Control", "public, max-age=3600"))
-                        .insert_header(("X-Resource-Updated-At", updated_at_str.as_str()))
-                        .body(file_content)
+                        .insert_header(("X-Resource-Updated-At", updated_at_str.as_str()));
+                    validators.insert_headers(&mut builder);
+                    stream_file(builder, file)
+                }
+                Err(StorageError::RangeNotSatisfiable(total_size)) => {
+                    range_not_satisfiable(total_size)
                 }
                 Err(StorageError::NotFound(_)) => {
                     log::warn!(
@@ -781,6 +848,7 @@ pub async fn update_resource_content(
         &state.pool,
         &user,
         &state.storage,
+        &state.moderation,
         resource_id,
         request.content.clone(),
     )
@@ -827,8 +895,22 @@ pub async fn get_hot_resources(
     query: web::Query<crate::models::HotResourcesQuery>,
 ) -> impl Responder {
     let limit = query.limit.unwrap_or(10);
+    let window = match query.window.as_deref() {
+        None => TrendingWindow::Week,
+        Some(w) => match TrendingWindow::from_str(w) {
+            Some(window) => window,
+            None => return bad_request("时间窗口必须是 day、week 或 semester"),
+        },
+    };
+    let category = query
+        .category
+        .as_deref()
+        .map(str::trim)
+        .filter(|c| !c.is_empty());
 
-    match ResourceService::get_hot_resources(&state.pool, limit).await {
+    match ResourceService::get_hot_resources(&state.pool, window, query.course_sn, category, limit)
+        .await
+    {
         Ok(resources) => HttpResponse::Ok().json(resources),
         Err(e) => {
             log::warn!("获取热门资源失败: {}", e);
@@ -1240,12 +1322,16 @@ pub async fn get_like_status(
 #[get("/resources/{resource_id}/comments")]
 pub async fn get_comments(
     state: web::Data<AppState>,
+    user: Option<web::ReqData<CurrentUser>>,
     path: web::Path<Uuid>,
     query: web::Query<CommentListQuery>,
 ) -> impl Responder {
     let resource_id = path.into_inner();
+    let viewer_id = user.map(|u| u.id);
 
-    match CommentService::get_comments(&state.pool, resource_id, query.into_inner()).await {
+    match CommentService::get_comments(&state.pool, resource_id, viewer_id, query.into_inner())
+        .await
+    {
         Ok(comments) => HttpResponse::Ok().json(comments),
         Err(e) => {
             log::warn!(
@@ -1255,6 +1341,7 @@ pub async fn get_comments(
             );
             match e {
                 ResourceError::NotFound(msg) => not_found(&msg),
+                ResourceError::ValidationError(msg) => bad_request(&msg),
                 _ => internal_error("获取评论失败"),
             }
         }
@@ -1290,8 +1377,14 @@ pub async fn create_comment(
         }
     };
 
-    match CommentService::create_comment(&state.pool, resource_id, user.id, request.into_inner())
-        .await
+    match CommentService::create_comment(
+        &state.pool,
+        &state.moderation,
+        resource_id,
+        user.id,
+        request.into_inner(),
+    )
+    .await
     {
         Ok(comment) => {
             // 记录审计日志
@@ -1494,6 +1587,240 @@ pub async fn update_resource_description(
     }
 }
 
+/// 获取资源历史版本列表（仅上传者或管理员）
+#[get("/resources/{resource_id}/versions")]
+pub async fn get_resource_versions(
+    state: web::Data<AppState>,
+    user: Option<web::ReqData<CurrentUser>>,
+    path: web::Path<Uuid>,
+) -> impl Responder {
+    let resource_id = path.into_inner();
+    let Some(user) = user else {
+        return unauthorized("请先登录");
+    };
+
+    match ResourceVersionService::list_versions(&state.pool, &user, resource_id).await {
+        Ok(response) => HttpResponse::Ok().json(response),
+        Err(e) => {
+            log::warn!(
+                "[Resource] 获取资源历史版本失败 | resource_id={}, user_id={}, error={}",
+                resource_id,
+                user.id,
+                e
+            );
+            match e {
+                ResourceError::NotFound(msg) => not_found(&msg),
+                ResourceError::Unauthorized(msg) => forbidden(&msg),
+                _ => internal_error("获取历史版本失败"),
+            }
+        }
+    }
+}
+
+/// 对比资源的两个历史版本（Markdown 按行对比）
+#[get("/resources/{resource_id}/versions/diff")]
+pub async fn diff_resource_versions(
+    state: web::Data<AppState>,
+    user: Option<web::ReqData<CurrentUser>>,
+    path: web::Path<Uuid>,
+    query: web::Query<ResourceVersionDiffQuery>,
+) -> impl Responder {
+    let resource_id = path.into_inner();
+    let Some(user) = user else {
+        return unauthorized("请先登录");
+    };
+
+    match ResourceVersionService::diff_versions(
+        &state.pool,
+        &state.storage,
+        &user,
+        resource_id,
+        &query,
+    )
+    .await
+    {
+        Ok(response) => HttpResponse::Ok().json(response),
+        Err(e) => {
+            log::warn!(
+                "[Resource] 对比资源版本失败 | resource_id={}, user_id={}, error={}",
+                resource_id,
+                user.id,
+                e
+            );
+            match e {
+                ResourceError::NotFound(msg) => not_found(&msg),
+                ResourceError::Unauthorized(msg) => forbidden(&msg),
+                ResourceError::ValidationError(msg) => bad_request(&msg),
+                _ => internal_error("对比版本失败"),
+            }
+        }
+    }
+}
+
+/// 下载资源的指定历史版本
+#[get("/resources/{resource_id}/versions/{version_number}/download")]
+pub async fn download_resource_version(
+    state: web::Data<AppState>,
+    user: Option<web::ReqData<CurrentUser>>,
+    path: web::Path<(Uuid, i32)>,
+) -> impl Responder {
+    let (resource_id, version_number) = path.into_inner();
+    let Some(user) = user else {
+        return unauthorized("请先登录");
+    };
+
+    let (version, title) = match ResourceVersionService::get_version_for_download(
+        &state.pool,
+        &user,
+        resource_id,
+        version_number,
+    )
+    .await
+    {
+        Ok(result) => result,
+        Err(e) => {
+            log::warn!(
+                "[Resource] 获取资源版本失败(下载) | resource_id={}, version={}, error={}",
+                resource_id,
+                version_number,
+                e
+            );
+            return match e {
+                ResourceError::NotFound(msg) => not_found(&msg),
+                ResourceError::Unauthorized(msg) => forbidden(&msg),
+                _ => internal_error("获取版本失败"),
+            };
+        }
+    };
+
+    let filename = format!("{}_v{}.md", sanitize_filename(&title), version_number);
+    let backend = match ResourceVersionService::resolve_storage(
+        &state.storage,
+        version.storage_type.as_deref(),
+    ) {
+        Ok(backend) => backend,
+        Err(e) => {
+            log::error!("[Resource] 无法访问版本文件存储 | error={}", e);
+            return internal_error("无法访问存储");
+        }
+    };
+
+    if backend.backend_type().is_object_storage() {
+        // 对象存储：生成签名下载 URL
+        let expires_secs = backend.default_signed_url_expiry();
+        return match backend
+            .get_download_url(&version.storage_key, &filename, expires_secs)
+            .await
+        {
+            Ok(download_url) => HttpResponse::Found()
+                .insert_header(("Location", download_url))
+                .finish(),
+            Err(e) => {
+                log::warn!(
+                    "[Resource] 生成版本下载链接失败 | resource_id={}, version={}, error={}",
+                    resource_id,
+                    version_number,
+                    e
+                );
+                internal_error("生成下载链接失败")
+            }
+        };
+    }
+
+    match backend.read_stream(&version.storage_key, None).await {
+        Ok(file) => {
+            let mut builder = HttpResponse::Ok();
+            builder
+                .content_type("text/markdown; charset=utf-8")
+                .insert_header(("Content-Disposition", build_content_disposition(&filename)));
+            stream_file(builder, file)
+        }
+        Err(StorageError::NotFound(_)) => not_found("版本文件不存在"),
+        Err(e) => {
+            log::warn!(
+                "[Resource] 读取版本文件失败 | resource_id={}, version={}, error={}",
+                resource_id,
+                version_number,
+                e
+            );
+            internal_error("文件读取失败")
+        }
+    }
+}
+
+/// 回滚资源到指定历史版本（上传者或管理员）
+#[post("/resources/{resource_id}/versions/{version_number}/rollback")]
+pub async fn rollback_resource_version(
+    state: web::Data<AppState>,
+    user: web::ReqData<CurrentUser>,
+    path: web::Path<(Uuid, i32)>,
+    req: HttpRequest,
+) -> impl Responder {
+    let (resource_id, version_number) = path.into_inner();
+
+    match ResourceVersionService::rollback_to_version(
+        &state.pool,
+        &state.storage,
+        &state.moderation,
+        &user,
+        resource_id,
+        version_number,
+    )
+    .await
+    {
+        Ok(response) => {
+            log::info!(
+                "[Resource] 资源版本回滚成功 | resource_id={}, from_version={}, new_version={:?}, user_id={}",
+                resource_id,
+                version_number,
+                response.version_number,
+                user.id
+            );
+
+            // 记录审计日志
+            let ip_address = req.peer_addr().map(|addr| addr.ip().to_string());
+            if let Err(e) = AuditLogService::log_action(
+                &state.pool,
+                user.id,
+                "rollback_resource_version",
+                Some("resource"),
+                Some(resource_id),
+                Some(serde_json::json!({
+                    "rolled_back_from": version_number,
+                    "new_version": response.version_number,
+                })),
+                ip_address.as_deref(),
+            )
+            .await
+            {
+                log::warn!(
+                    "[Audit] 记录资源版本回滚日志失败 | resource_id={}, error={}",
+                    resource_id,
+                    e
+                );
+            }
+
+            HttpResponse::Ok().json(response)
+        }
+        Err(e) => {
+            log::warn!(
+                "[Resource] 资源版本回滚失败 | resource_id={}, version={}, user_id={}, error={}",
+                resource_id,
+                version_number,
+                user.id,
+                e
+            );
+            match e {
+                ResourceError::NotFound(msg) => not_found(&msg),
+                ResourceError::Unauthorized(msg) => forbidden(&msg),
+                ResourceError::ValidationError(msg) => bad_request(&msg),
+                ResourceError::Conflict(msg) => conflict(&msg),
+                _ => internal_error("回滚版本失败"),
+            }
+        }
+    }
+}
+
 /// 获取 PDF 预览检测配置
 #[get("/resources/pdf-preview-challenge/config")]
 pub async fn get_pdf_preview_challenge_config(
@@ -1556,6 +1883,7 @@ pub fn config(cfg: &mut web::ServiceConfig) {
     cfg.service(upload_resource)
         .service(delete_resource)
         .service(get_my_resources)
+        .service(get_recommended_resources) // /resources/recommended
         .service(rate_resource)
         .service(get_my_rating)
         .service(delete_rating)
@@ -1564,5 +1892,9 @@ pub fn config(cfg: &mut web::ServiceConfig) {
         .service(update_resource_content)
         .service(get_resource_raw_content)
         .service(update_resource_relations)
-        .service(update_resource_description);
+        .service(update_resource_description)
+        .service(diff_resource_versions) // /resources/{id}/versions/diff（先于 {version_number} 注册）
+        .service(get_resource_versions)
+        .service(download_resource_version)
+        .service(rollback_resource_version);
 }
@@ -0,0 +1,273 @@
+use crate::config::Config;
+use crate::db::AppState;
+use crate::models::{
+    CurrentUser, RecoveryCodesResponse, TotpCodeRequest, TotpDisableRequest, TotpSetupRequest,
+    UserRole,
+};
+use crate::services::{
+    AuditAction, AuditLogService, AuthError, AuthService, SessionService, TotpError, TotpService,
+};
+use crate::utils::{bad_request, forbidden, internal_error, not_found};
+use actix_web::{get, post, web, HttpRequest, HttpResponse, Responder};
+
+/// 站点是否要求该用户启用两步验证
+fn is_two_factor_required(config: &Config, user: &CurrentUser) -> bool {
+    config.admin_require_2fa && user.role == UserRole::Admin
+}
+
+/// 将 TotpError 转换为 HttpResponse
+fn handle_totp_error(err: TotpError) -> HttpResponse {
+    match err {
+        TotpError::UserNotFound(msg) => not_found(&msg),
+        TotpError::AlreadyEnabled(msg)
+        | TotpError::NotEnabled(msg)
+        | TotpError::InvalidCode(msg) => bad_request(&msg),
+        TotpError::EncryptionError(msg) => {
+            log::error!("[TwoFactor] 密钥加密错误 | error={}", msg);
+            internal_error("服务器内部错误")
+        }
+        TotpError::DatabaseError(msg) => {
+            log::error!("[TwoFactor] 数据库错误 | error={}", msg);
+            internal_error("服务器内部错误")
+        }
+    }
+}
+
+/// 再次确认密码失败时的响应
+fn handle_password_error(err: AuthError) -> HttpResponse {
+    match err {
+        // 使用 400 而不是 401，避免触发前端刷新 token 逻辑
+        AuthError::InvalidCredentials(msg) => bad_request(&msg),
+        AuthError::UserNotFound(msg) => not_found(&msg),
+        e => {
+            log::error!("[TwoFactor] 校验密码失败 | error={}", e);
+            internal_error("服务器内部错误")
+        }
+    }
+}
+
+/// 获取两步验证状态
+#[get("/users/me/2fa")]
+pub async fn get_two_factor_status(
+    state: web::Data<AppState>,
+    user: web::ReqData<CurrentUser>,
+) -> impl Responder {
+    let required = is_two_factor_required(&Config::from_env(), &user);
+
+    match TotpService::get_status(&state.pool, user.id, user.session_id, required).await {
+        Ok(status) => HttpResponse::Ok().json(status),
+        Err(e) => {
+            log::warn!(
+                "[TwoFactor] 获取两步验证状态失败 | user_id={}, error={}",
+                user.id,
+                e
+            );
+            handle_totp_error(e)
+        }
+    }
+}
+
+/// 开始启用两步验证：确认密码后生成密钥
+#[post("/users/me/2fa/setup")]
+pub async fn setup_two_factor(
+    state: web::Data<AppState>,
+    user: web::ReqData<CurrentUser>,
+    req: web::Json<TotpSetupRequest>,
+) -> impl Responder {
+    log::info!("[TwoFactor] 用户开始绑定两步验证 | user_id={}", user.id);
+
+    if let Err(e) = AuthService::confirm_password(&state.pool, user.id, &req.password).await {
+        log::warn!(
+            "[TwoFactor] 绑定两步验证前校验密码失败 | user_id={}, error={}",
+            user.id,
+            e
+        );
+        return handle_password_error(e);
+    }
+
+    let config = Config::from_env();
+    match TotpService::begin_setup(
+        &state.pool,
+        state.totp_cipher.as_deref(),
+        user.id,
+        &config.totp_issuer,
+    )
+    .await
+    {
+        Ok(response) => HttpResponse::Ok().json(response),
+        Err(e) => {
+            log::warn!(
+                "[TwoFactor] 生成两步验证密钥失败 | user_id={}, error={}",
+                user.id,
+                e
+            );
+            handle_totp_error(e)
+        }
+    }
+}
+
+/// 确认启用两步验证，返回恢复码
+///
+/// 当前会话同时视为已完成两步验证，无需重新登录
+#[post("/users/me/2fa/enable")]
+pub async fn enable_two_factor(
+    state: web::Data<AppState>,
+    user: web::ReqData<CurrentUser>,
+    req: web::Json<TotpCodeRequest>,
+    http_req: HttpRequest,
+) -> impl Responder {
+    log::info!("[TwoFactor] 用户确认启用两步验证 | user_id={}", user.id);
+
+    let recovery_codes = match TotpService::enable(
+        &state.pool,
+        state.totp_cipher.as_deref(),
+        user.id,
+        &req.code,
+    )
+    .await
+    {
+        Ok(codes) => codes,
+        Err(e) => {
+            log::warn!(
+                "[TwoFactor] 启用两步验证失败 | user_id={}, error={}",
+                user.id,
+                e
+            );
+            return handle_totp_error(e);
+        }
+    };
+
+    if let Some(session_id) = user.session_id {
+        if let Err(e) = SessionService::mark_mfa_verified(&state.pool, user.id, session_id).await {
+            log::warn!(
+                "[TwoFactor] 标记会话两步验证状态失败 | user_id={}, error={}",
+                user.id,
+                e
+            );
+        }
+    }
+
+    let ip_address = http_req.peer_addr().map(|addr| addr.ip().to_string());
+    let _ = AuditLogService::log(
+        &state.pool,
+        Some(user.id),
+        AuditAction::TwoFactorEnabled,
+        Some("user"),
+        Some(user.id),
+        None,
+        ip_address.as_deref(),
+    )
+    .await;
+
+    HttpResponse::Ok().json(RecoveryCodesResponse { recovery_codes })
+}
+
+/// 关闭两步验证（需要密码和验证码）
+#[post("/users/me/2fa/disable")]
+pub async fn disable_two_factor(
+    state: web::Data<AppState>,
+    user: web::ReqData<CurrentUser>,
+    req: web::Json<TotpDisableRequest>,
+    http_req: HttpRequest,
+) -> impl Responder {
+    log::info!("[TwoFactor] 用户关闭两步验证 | user_id={}", user.id);
+
+    if is_two_factor_required(&Config::from_env(), &user) {
+        log::warn!("[TwoFactor] 管理员不能关闭两步验证 | user_id={}", user.id);
+        return forbidden("本站点要求管理员启用两步验证，无法关闭");
+    }
+
+    if let Err(e) = AuthService::confirm_password(&state.pool, user.id, &req.password).await {
+        log::warn!(
+            "[TwoFactor] 关闭两步验证前校验密码失败 | user_id={}, error={}",
+            user.id,
+            e
+        );
+        return handle_password_error(e);
+    }
+
+    if let Err(e) = TotpService::disable(
+        &state.pool,
+        state.totp_cipher.as_deref(),
+        user.id,
+        &req.code,
+    )
+    .await
+    {
+        log::warn!(
+            "[TwoFactor] 关闭两步验证失败 | user_id={}, error={}",
+            user.id,
+            e
+        );
+        return handle_totp_error(e);
+    }
+
+    let ip_address = http_req.peer_addr().map(|addr| addr.ip().to_string());
+    let _ = AuditLogService::log(
+        &state.pool,
+        Some(user.id),
+        AuditAction::TwoFactorDisabled,
+        Some("user"),
+        Some(user.id),
+        None,
+        ip_address.as_deref(),
+    )
+    .await;
+
+    HttpResponse::Ok().json(serde_json::json!({
+        "message": "两步验证已关闭"
+    }))
+}
+
+/// 重新生成恢复码，之前的恢复码全部失效
+#[post("/users/me/2fa/recovery-codes")]
+pub async fn regenerate_recovery_codes(
+    state: web::Data<AppState>,
+    user: web::ReqData<CurrentUser>,
+    req: web::Json<TotpCodeRequest>,
+    http_req: HttpRequest,
+) -> impl Responder {
+    log::info!("[TwoFactor] 用户重新生成恢复码 | user_id={}", user.id);
+
+    match TotpService::regenerate_recovery_codes(
+        &state.pool,
+        state.totp_cipher.as_deref(),
+        user.id,
+        &req.code,
+    )
+    .await
+    {
+        Ok(recovery_codes) => {
+            let ip_address = http_req.peer_addr().map(|addr| addr.ip().to_string());
+            let _ = AuditLogService::log(
+                &state.pool,
+                Some(user.id),
+                AuditAction::RecoveryCodesRenewed,
+                Some("user"),
+                Some(user.id),
+                None,
+                ip_address.as_deref(),
+            )
+            .await;
+
+            HttpResponse::Ok().json(RecoveryCodesResponse { recovery_codes })
+        }
+        Err(e) => {
+            log::warn!(
+                "[TwoFactor] 重新生成恢复码失败 | user_id={}, error={}",
+                user.id,
+                e
+            );
+            handle_totp_error(e)
+        }
+    }
+}
+
+/// 配置两步验证路由
+pub fn config(cfg: &mut web::ServiceConfig) {
+    cfg.service(get_two_factor_status)
+        .service(setup_two_factor)
+        .service(enable_two_factor)
+        .service(disable_two_factor)
+        .service(regenerate_recovery_codes);
+}
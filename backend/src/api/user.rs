@@ -1,12 +1,16 @@
+use crate::api::auth::session_client_info;
+use crate::config::Config;
 use crate::db::AppState;
 use crate::models::{
     ChangePasswordRequest, CurrentUser, LeaderboardQuery, UpdateProfileRequest, UserHomepageQuery,
-    UserRole, VerificationRequest,
+    VerificationRequest,
+};
+use crate::services::{
+    AuditLogService, AuthError, AuthService, PermissionService, UserError, UserService,
+    VerificationError, VerificationService,
 };
-use crate::services::{AuditLogService, UserError, UserService};
 use crate::utils::{
-    bad_request, forbidden, generate_access_token, generate_refresh_token, internal_error,
-    not_found, unauthorized,
+    bad_request, conflict, created, forbidden, internal_error, not_found, unauthorized,
 };
 use actix_web::cookie::{time::Duration as CookieDuration, Cookie, SameSite};
 use actix_web::{get, post, put, web, HttpRequest, HttpResponse, Responder};
@@ -23,6 +27,10 @@ pub struct SiteConfigResponse {
     pub allow_username_change: bool,
     /// 是否允许用户修改邮箱
     pub allow_email_change: bool,
+    /// 是否启用单点登录
+    pub oidc_enabled: bool,
+    /// 单点登录按钮上显示的身份提供方名称
+    pub oidc_provider_name: Option<String>,
 }
 
 /// Cookie 名称常量
@@ -76,6 +84,21 @@ pub async fn get_current_user(
     }
 }
 
+/// 获取当前用户的管理后台权限，前端据此显示管理入口
+#[get("/users/me/permissions")]
+pub async fn get_my_permissions(
+    state: web::Data<AppState>,
+    user: web::ReqData<CurrentUser>,
+) -> impl Responder {
+    match PermissionService::load_user_permissions(&state.pool, user.id, &user.role).await {
+        Ok(permissions) => HttpResponse::Ok().json(permissions.to_response()),
+        Err(e) => {
+            log::error!("[User] 获取权限失败 | user_id={}, error={}", user.id, e);
+            internal_error("获取权限失败")
+        }
+    }
+}
+
 /// 更新当前用户资料
 #[put("/users/me")]
 pub async fn update_profile(
@@ -95,6 +118,12 @@ pub async fn update_profile(
         return forbidden("实名认证后才可修改个人简介");
     }
 
+    // 站点要求绑定邮箱时不能清空邮箱
+    let requested_email = req.email.as_ref().map(|e| e.trim().to_string());
+    if state.require_email_on_register && requested_email.as_deref() == Some("") {
+        return bad_request("本站点要求绑定邮箱，不能清空邮箱");
+    }
+
     log::info!("[User] 更新用户资料 | user_id={}", user.id);
 
     match UserService::update_profile(
@@ -127,6 +156,24 @@ pub async fn update_profile(
                 );
             }
 
+            // 修改了邮箱时需要重新验证
+            if requested_email.is_some_and(|e| !e.is_empty()) && !user_info.email_verified {
+                if let Err(e) = AuthService::send_email_verification(
+                    &state.pool,
+                    &Config::from_env(),
+                    &state.mailer,
+                    user.id,
+                )
+                .await
+                {
+                    log::warn!(
+                        "[User] 发送邮箱验证邮件失败 | user_id={}, error={}",
+                        user.id,
+                        e
+                    );
+                }
+            }
+
             HttpResponse::Ok().json(user_info)
         }
         Err(e) => {
@@ -144,7 +191,17 @@ pub async fn update_profile(
     }
 }
 
-/// 实名认证
+/// 将实名认证错误转换为 HTTP 响应
+fn handle_verification_error(e: VerificationError) -> HttpResponse {
+    match e {
+        VerificationError::NotFound(msg) => not_found(&msg),
+        VerificationError::ValidationError(msg) => bad_request(&msg),
+        VerificationError::Conflict(msg) => conflict(&msg),
+        VerificationError::DatabaseError(_) => internal_error("操作失败"),
+    }
+}
+
+/// 提交实名认证申请（进入待审核队列，管理员通过后生效）
 #[post("/users/verify")]
 pub async fn verify_user(
     state: web::Data<AppState>,
@@ -156,80 +213,92 @@ pub async fn verify_user(
         return bad_request("用户已完成实名认证");
     }
 
-    match UserService::verify_user(&state.pool, user.id, req.into_inner()).await {
-        Ok(user_info) => {
-            // 实名认证成功，生成新的 Token（保持原有角色）
-            let user_role = match user_info.role.as_str() {
-                "admin" => UserRole::Admin,
-                "verified" => UserRole::Verified,
-                "user" => UserRole::User,
-                _ => UserRole::Guest,
-            };
-            let access_token = match generate_access_token(
-                user_info.id,
-                user_info.username.clone(),
-                user_role.clone(),
-                user_info.is_verified,
-                &state.jwt_secret,
-            ) {
-                Ok(token) => token,
-                Err(e) => {
-                    log::error!(
-                        "[Auth] 生成访问令牌失败 | user_id={}, error={}",
-                        user_info.id,
-                        e
-                    );
-                    return internal_error("认证成功但生成令牌失败，请重新登录");
-                }
-            };
-
-            let refresh_token = match generate_refresh_token(
-                user_info.id,
-                user_info.username.clone(),
-                user_role,
-                user_info.is_verified,
-                &state.jwt_secret,
-            ) {
-                Ok(token) => token,
-                Err(e) => {
-                    log::error!(
-                        "[Auth] 生成刷新令牌失败 | user_id={}, error={}",
-                        user_info.id,
-                        e
-                    );
-                    return internal_error("认证成功但生成令牌失败，请重新登录");
-                }
-            };
-
-            // 设置 HttpOnly Cookies
-            let access_cookie = build_auth_cookie(
-                ACCESS_TOKEN_COOKIE,
-                &access_token,
-                1, // 1天
-                state.cookie_secure,
-            );
-            let refresh_cookie = build_auth_cookie(
-                REFRESH_TOKEN_COOKIE,
-                &refresh_token,
-                7, // 7天
-                state.cookie_secure,
-            );
-
-            // 返回用户信息（不包含token），直接返回用户对象（符合API规范）
-            HttpResponse::Ok()
-                .cookie(access_cookie)
-                .cookie(refresh_cookie)
-                .json(user_info)
+    let config = Config::from_env();
+    match VerificationService::submit(
+        &state.pool,
+        state.real_info_cipher.as_deref(),
+        user.id,
+        req.into_inner(),
+        &config.image_base_url,
+    )
+    .await
+    {
+        Ok(response) => created(response),
+        Err(e) => {
+            log::warn!("[User] 提交实名认证失败 | user_id={}, error={}", user.id, e);
+            handle_verification_error(e)
         }
+    }
+}
+
+/// 获取当前用户的实名认证状态
+///
+/// 申请已通过但当前令牌仍为未实名状态时，为当前会话重新签发 Token
+#[get("/users/me/verification")]
+pub async fn get_my_verification(
+    state: web::Data<AppState>,
+    user: web::ReqData<CurrentUser>,
+    http_req: HttpRequest,
+) -> impl Responder {
+    let config = Config::from_env();
+    let response = match VerificationService::get_my_verification(
+        &state.pool,
+        user.id,
+        &config.image_base_url,
+    )
+    .await
+    {
+        Ok(response) => response,
         Err(e) => {
-            log::warn!("[User] 实名认证失败 | user_id={}, error={}", user.id, e);
-            match e {
-                UserError::UserNotFound(msg) => not_found(&msg),
-                UserError::ValidationError(msg) => bad_request(&msg),
-                _ => internal_error("认证失败"),
-            }
+            log::warn!(
+                "[User] 获取实名认证状态失败 | user_id={}, error={}",
+                user.id,
+                e
+            );
+            return handle_verification_error(e);
         }
+    };
+
+    if !response.is_verified || user.is_verified {
+        return HttpResponse::Ok().json(response);
     }
+
+    // 实名认证已通过，为当前会话重新签发 Token（保持原有角色）
+    let client = session_client_info(&http_req);
+    let tokens = match AuthService::reissue_tokens(
+        &state.pool,
+        &state.jwt_secret,
+        user.id,
+        user.session_id,
+        &client,
+    )
+    .await
+    {
+        Ok(tokens) => tokens,
+        Err(e) => {
+            log::error!("[Auth] 重新签发令牌失败 | user_id={}, error={}", user.id, e);
+            return internal_error("认证已通过但生成令牌失败，请重新登录");
+        }
+    };
+
+    // 设置 HttpOnly Cookies
+    let access_cookie = build_auth_cookie(
+        ACCESS_TOKEN_COOKIE,
+        &tokens.access_token,
+        1, // 1天
+        state.cookie_secure,
+    );
+    let refresh_cookie = build_auth_cookie(
+        REFRESH_TOKEN_COOKIE,
+        &tokens.refresh_token,
+        7, // 7天
+        state.cookie_secure,
+    );
+
+    HttpResponse::Ok()
+        .cookie(access_cookie)
+        .cookie(refresh_cookie)
+        .json(response)
 }
 
 /// 获取用户公开资料（公开接口，任何人都可以访问）
@@ -271,6 +340,34 @@ pub async fn get_user_homepage(
     }
 }
 
+/// 重新发送邮箱验证邮件
+#[post("/users/me/email/verification")]
+pub async fn send_email_verification(
+    state: web::Data<AppState>,
+    user: web::ReqData<CurrentUser>,
+) -> impl Responder {
+    log::info!("[User] 发送邮箱验证邮件 | user_id={}", user.id);
+
+    let config = Config::from_env();
+    match AuthService::send_email_verification(&state.pool, &config, &state.mailer, user.id).await {
+        Ok(email) => HttpResponse::Ok().json(serde_json::json!({
+            "message": format!("验证邮件已发送至 {}", email)
+        })),
+        Err(e) => {
+            log::warn!(
+                "[User] 发送邮箱验证邮件失败 | user_id={}, error={}",
+                user.id,
+                e
+            );
+            match e {
+                AuthError::UserNotFound(msg) => not_found(&msg),
+                AuthError::ValidationError(msg) => bad_request(&msg),
+                _ => internal_error("发送验证邮件失败"),
+            }
+        }
+    }
+}
+
 /// 获取站点公开配置（公开接口，无需认证）
 #[get("/config")]
 pub async fn get_site_config(state: web::Data<AppState>) -> impl Responder {
@@ -278,6 +375,11 @@ pub async fn get_site_config(state: web::Data<AppState>) -> impl Responder {
         require_email_on_register: state.require_email_on_register,
         allow_username_change: state.allow_username_change,
         allow_email_change: state.allow_email_change,
+        oidc_enabled: state.oidc.is_some(),
+        oidc_provider_name: state
+            .oidc
+            .as_ref()
+            .map(|client| client.settings().provider_name.clone()),
     })
 }
 
@@ -365,8 +467,11 @@ pub async fn get_leaderboard(
 /// 配置用户路由
 pub fn config(cfg: &mut web::ServiceConfig) {
     cfg.service(get_current_user)
+        .service(get_my_permissions)
         .service(update_profile)
+        .service(send_email_verification)
         .service(verify_user)
+        .service(get_my_verification)
         .service(get_leaderboard) // 必须在 get_user_profile 之前注册，避免被解析为 user_id
         .service(get_user_homepage) // 必须在 get_user_profile 之前注册
         .service(get_user_profile)
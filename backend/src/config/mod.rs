@@ -50,6 +50,18 @@ pub struct Config {
     pub oss_sts_session_duration: u64,
     pub oss_key_prefix: String,
     pub oss_signed_url_expiry: u64,
+    /// S3 兼容存储（STORAGE_BACKEND=s3，如 MinIO）
+    pub s3_endpoint: Option<String>,
+    pub s3_bucket: Option<String>,
+    pub s3_region: String,
+    pub s3_access_key_id: Option<String>,
+    pub s3_secret_access_key: Option<String>,
+    /// 是否使用路径风格地址（endpoint/bucket/key），MinIO 需要开启
+    pub s3_force_path_style: bool,
+    pub s3_key_prefix: String,
+    pub s3_signed_url_expiry: u64,
+    /// 分片上传的分片大小（字节），流式写入超过该大小时使用分片上传
+    pub s3_multipart_part_size: u64,
     /// 注册时是否强制要求邮箱
     pub require_email_on_register: bool,
     /// 是否允许用户修改用户名
@@ -62,6 +74,100 @@ pub struct Config {
     pub pdf_preview_challenge_uuid: Option<String>,
     /// PDF 预览检测验证码
     pub pdf_preview_challenge_code: Option<String>,
+    /// 内容审核提供方：noop / rule / openai
+    pub moderation_provider: String,
+    /// OpenAI 兼容接口地址
+    pub moderation_api_base: String,
+    /// OpenAI 兼容接口密钥
+    pub moderation_api_key: Option<String>,
+    /// 审核使用的模型
+    pub moderation_model: String,
+    /// 审核请求超时时间（秒）
+    pub moderation_timeout_secs: u64,
+    /// 单次审核提交的最大字符数
+    pub moderation_max_chars: usize,
+    /// 规则审核关键词（逗号分隔）
+    pub moderation_keywords: Vec<String>,
+    /// 规则审核规则文件路径
+    pub moderation_rules_file: Option<String>,
+    /// 打包下载文件保留时间（秒）
+    pub pack_job_ttl_secs: u64,
+    /// 过期打包文件清理间隔（秒）
+    pub pack_job_gc_interval_secs: u64,
+    /// 每个用户同时进行的打包任务上限
+    pub pack_job_max_active_per_user: i64,
+    /// 是否启用登录、注册等接口的限流与账号锁定
+    pub rate_limit_enabled: bool,
+    /// 是否信任反向代理的 X-Forwarded-For / Forwarded 头获取客户端 IP
+    pub rate_limit_trust_proxy: bool,
+    /// 同一 IP 在退避前允许的失败次数
+    pub rate_limit_ip_free_attempts: u32,
+    /// 同一用户名在退避前允许的登录失败次数
+    pub rate_limit_username_free_attempts: u32,
+    /// 退避基础等待时间（秒），之后每次失败翻倍
+    pub rate_limit_backoff_base_secs: u64,
+    /// 退避最长等待时间（秒）
+    pub rate_limit_backoff_max_secs: u64,
+    /// IP 失败记录的保留时间（秒），超过后重新计数
+    pub rate_limit_window_secs: u64,
+    /// 连续登录失败多少次后临时锁定账号，0 表示不锁定
+    pub login_lockout_threshold: i32,
+    /// 账号临时锁定时长（秒）
+    pub login_lockout_secs: u64,
+    /// 邮件发送方式：log / file / smtp
+    pub mail_transport: String,
+    /// 发件人（如 `ShareUSTC <noreply@example.com>`）
+    pub mail_from: String,
+    /// file 方式下邮件保存目录
+    pub mail_file_dir: String,
+    pub smtp_host: Option<String>,
+    pub smtp_port: u16,
+    pub smtp_username: Option<String>,
+    pub smtp_password: Option<String>,
+    /// SMTP 加密方式：starttls / tls / none
+    pub smtp_security: String,
+    /// SMTP 连接和每条命令的超时时间（秒）
+    pub smtp_timeout_secs: u64,
+    /// 前端站点地址，用于生成邮件中的链接
+    pub frontend_base_url: String,
+    /// 邮箱验证链接有效期（秒）
+    pub email_verification_ttl_secs: u64,
+    /// 密码重置链接有效期（秒）
+    pub password_reset_ttl_secs: u64,
+    /// 是否要求管理员启用两步验证，未通过两步验证的管理员会话只能访问登录和两步验证相关接口
+    pub admin_require_2fa: bool,
+    /// 认证器应用中显示的签发方名称
+    pub totp_issuer: String,
+    /// 是否启用 OIDC 单点登录
+    pub oidc_enabled: bool,
+    /// 身份提供方的 Issuer，发现文档地址为 `{issuer}/.well-known/openid-configuration`
+    pub oidc_issuer: Option<String>,
+    pub oidc_client_id: Option<String>,
+    /// 客户端密钥，公开客户端（只使用 PKCE）可以不填
+    pub oidc_client_secret: Option<String>,
+    /// 身份提供方登录完成后的回调地址，需要在身份提供方登记
+    pub oidc_redirect_uri: String,
+    pub oidc_scopes: String,
+    /// 登录按钮上显示的身份提供方名称
+    pub oidc_provider_name: String,
+    /// 自动注册时用作用户名的声明
+    pub oidc_username_claim: String,
+    /// 外部身份未绑定账号时是否自动创建账号
+    pub oidc_auto_register: bool,
+    /// 用于判断实名认证的可信声明，为空时不通过单点登录设置实名状态
+    pub oidc_verified_claim: Option<String>,
+    /// 可信声明的取值列表，为空时声明为 true 或非空即视为已实名
+    pub oidc_verified_values: Vec<String>,
+    /// 实名信息中的姓名声明
+    pub oidc_real_name_claim: String,
+    /// 实名信息中的学号声明
+    pub oidc_student_id_claim: Option<String>,
+    /// 请求身份提供方的超时时间（秒）
+    pub oidc_timeout_secs: u64,
+    /// 实名信息加密密钥（`密钥编号:Base64 编码的 32 字节密钥`），第一个用于加密，其余只用于解密
+    pub real_info_encryption_keys: Vec<String>,
+    /// 两步验证密钥加密密钥，格式与轮换方式同实名信息加密密钥
+    pub totp_encryption_keys: Vec<String>,
 }
 
 impl Config {
@@ -100,6 +206,7 @@ impl Config {
             .as_str()
         {
             "oss" => "oss".to_string(),
+            "s3" => "s3".to_string(),
             _ => "local".to_string(),
         };
 
@@ -151,6 +258,23 @@ impl Config {
                 .ok()
                 .and_then(|value| value.parse::<u64>().ok())
                 .unwrap_or(600),
+            s3_endpoint: optional_env("S3_ENDPOINT"),
+            s3_bucket: optional_env("S3_BUCKET"),
+            s3_region: optional_env("S3_REGION").unwrap_or_else(|| "us-east-1".to_string()),
+            s3_access_key_id: optional_env("S3_ACCESS_KEY_ID"),
+            s3_secret_access_key: optional_env("S3_SECRET_ACCESS_KEY"),
+            s3_force_path_style: env::var("S3_FORCE_PATH_STYLE")
+                .map(|v| v != "false" && v != "0")
+                .unwrap_or(true),
+            s3_key_prefix: env::var("S3_KEY_PREFIX").unwrap_or_default(),
+            s3_signed_url_expiry: env::var("S3_SIGNED_URL_EXPIRY")
+                .ok()
+                .and_then(|value| value.parse::<u64>().ok())
+                .unwrap_or(600),
+            s3_multipart_part_size: env::var("S3_MULTIPART_PART_SIZE")
+                .ok()
+                .and_then(|value| value.parse::<u64>().ok())
+                .unwrap_or(8 * 1024 * 1024),
             // 用户配置项
             require_email_on_register: env::var("REGISTER_REQUIRE_EMAIL")
                 .map(|v| v == "true" || v == "1")
@@ -166,6 +290,169 @@ impl Config {
             // PDF 预览检测配置
             pdf_preview_challenge_uuid: optional_env("PDF_PREVIEW_CHALLENGE_UUID"),
             pdf_preview_challenge_code: optional_env("PDF_PREVIEW_CHALLENGE_CODE"),
+            // 内容审核配置
+            moderation_provider: env::var("MODERATION_PROVIDER")
+                .map(|v| v.trim().to_lowercase())
+                .unwrap_or_else(|_| "noop".to_string()),
+            moderation_api_base: optional_env("MODERATION_API_BASE")
+                .unwrap_or_else(|| "https://api.openai.com/v1".to_string()),
+            moderation_api_key: optional_env("MODERATION_API_KEY"),
+            moderation_model: optional_env("MODERATION_MODEL")
+                .unwrap_or_else(|| "gpt-4o-mini".to_string()),
+            moderation_timeout_secs: env::var("MODERATION_TIMEOUT_SECS")
+                .ok()
+                .and_then(|value| value.parse::<u64>().ok())
+                .unwrap_or(15),
+            moderation_max_chars: env::var("MODERATION_MAX_CHARS")
+                .ok()
+                .and_then(|value| value.parse::<usize>().ok())
+                .unwrap_or(8000),
+            moderation_keywords: env::var("MODERATION_KEYWORDS")
+                .unwrap_or_default()
+                .split(',')
+                .map(|s| s.trim().to_string())
+                .filter(|s| !s.is_empty())
+                .collect(),
+            moderation_rules_file: optional_env("MODERATION_RULES_FILE"),
+            // 打包下载任务配置
+            pack_job_ttl_secs: env::var("PACK_JOB_TTL_SECS")
+                .ok()
+                .and_then(|value| value.parse::<u64>().ok())
+                .unwrap_or(24 * 60 * 60),
+            pack_job_gc_interval_secs: env::var("PACK_JOB_GC_INTERVAL_SECS")
+                .ok()
+                .and_then(|value| value.parse::<u64>().ok())
+                .filter(|value| *value > 0)
+                .unwrap_or(600),
+            pack_job_max_active_per_user: env::var("PACK_JOB_MAX_ACTIVE_PER_USER")
+                .ok()
+                .and_then(|value| value.parse::<i64>().ok())
+                .unwrap_or(2),
+            // 限流与账号锁定配置
+            rate_limit_enabled: env::var("RATE_LIMIT_ENABLED")
+                .map(|v| v.to_lowercase() != "false")
+                .unwrap_or(true),
+            rate_limit_trust_proxy: env::var("RATE_LIMIT_TRUST_PROXY")
+                .map(|v| v.to_lowercase() == "true")
+                .unwrap_or(false),
+            rate_limit_ip_free_attempts: env::var("RATE_LIMIT_IP_FREE_ATTEMPTS")
+                .ok()
+                .and_then(|value| value.parse::<u32>().ok())
+                .unwrap_or(20),
+            rate_limit_username_free_attempts: env::var("RATE_LIMIT_USERNAME_FREE_ATTEMPTS")
+                .ok()
+                .and_then(|value| value.parse::<u32>().ok())
+                .unwrap_or(5),
+            rate_limit_backoff_base_secs: env::var("RATE_LIMIT_BACKOFF_BASE_SECS")
+                .ok()
+                .and_then(|value| value.parse::<u64>().ok())
+                .filter(|value| *value > 0)
+                .unwrap_or(2),
+            rate_limit_backoff_max_secs: env::var("RATE_LIMIT_BACKOFF_MAX_SECS")
+                .ok()
+                .and_then(|value| value.parse::<u64>().ok())
+                .filter(|value| *value > 0)
+                .unwrap_or(900),
+            rate_limit_window_secs: env::var("RATE_LIMIT_WINDOW_SECS")
+                .ok()
+                .and_then(|value| value.parse::<u64>().ok())
+                .filter(|value| *value > 0)
+                .unwrap_or(3600),
+            login_lockout_threshold: env::var("LOGIN_LOCKOUT_THRESHOLD")
+                .ok()
+                .and_then(|value| value.parse::<i32>().ok())
+                .unwrap_or(10),
+            login_lockout_secs: env::var("LOGIN_LOCKOUT_SECS")
+                .ok()
+                .and_then(|value| value.parse::<u64>().ok())
+                .filter(|value| *value > 0)
+                .unwrap_or(1800),
+            // 邮件配置
+            mail_transport: env::var("MAIL_TRANSPORT")
+                .map(|v| v.trim().to_lowercase())
+                .unwrap_or_else(|_| "log".to_string()),
+            mail_from: optional_env("MAIL_FROM")
+                .unwrap_or_else(|| "ShareUSTC <noreply@localhost>".to_string()),
+            mail_file_dir: optional_env("MAIL_FILE_DIR").unwrap_or_else(|| "./mail".to_string()),
+            smtp_host: optional_env("SMTP_HOST"),
+            smtp_port: env::var("SMTP_PORT")
+                .ok()
+                .and_then(|value| value.parse::<u16>().ok())
+                .unwrap_or(587),
+            smtp_username: optional_env("SMTP_USERNAME"),
+            smtp_password: optional_env("SMTP_PASSWORD"),
+            smtp_security: env::var("SMTP_SECURITY")
+                .map(|v| v.trim().to_lowercase())
+                .unwrap_or_else(|_| "starttls".to_string()),
+            smtp_timeout_secs: env::var("SMTP_TIMEOUT_SECS")
+                .ok()
+                .and_then(|value| value.parse::<u64>().ok())
+                .filter(|value| *value > 0)
+                .unwrap_or(15),
+            frontend_base_url: optional_env("FRONTEND_BASE_URL")
+                .map(|url| url.trim_end_matches('/').to_string())
+                .unwrap_or_else(|| "http://localhost:5173".to_string()),
+            email_verification_ttl_secs: env::var("EMAIL_VERIFICATION_TTL_SECS")
+                .ok()
+                .and_then(|value| value.parse::<u64>().ok())
+                .filter(|value| *value > 0)
+                .unwrap_or(24 * 60 * 60),
+            password_reset_ttl_secs: env::var("PASSWORD_RESET_TTL_SECS")
+                .ok()
+                .and_then(|value| value.parse::<u64>().ok())
+                .filter(|value| *value > 0)
+                .unwrap_or(30 * 60),
+            // 两步验证配置
+            admin_require_2fa: env::var("ADMIN_REQUIRE_2FA")
+                .map(|v| v.to_lowercase() == "true")
+                .unwrap_or(false),
+            totp_issuer: optional_env("TOTP_ISSUER").unwrap_or_else(|| "ShareUSTC".to_string()),
+            // OIDC 单点登录配置
+            oidc_enabled: env::var("OIDC_ENABLED")
+                .map(|v| v.to_lowercase() == "true")
+                .unwrap_or(false),
+            oidc_issuer: optional_env("OIDC_ISSUER")
+                .map(|issuer| issuer.trim_end_matches('/').to_string()),
+            oidc_client_id: optional_env("OIDC_CLIENT_ID"),
+            oidc_client_secret: optional_env("OIDC_CLIENT_SECRET"),
+            oidc_redirect_uri: optional_env("OIDC_REDIRECT_URI")
+                .unwrap_or_else(|| "http://localhost:8080/api/auth/oidc/callback".to_string()),
+            oidc_scopes: optional_env("OIDC_SCOPES")
+                .unwrap_or_else(|| "openid profile email".to_string()),
+            oidc_provider_name: optional_env("OIDC_PROVIDER_NAME")
+                .unwrap_or_else(|| "统一身份认证".to_string()),
+            oidc_username_claim: optional_env("OIDC_USERNAME_CLAIM")
+                .unwrap_or_else(|| "preferred_username".to_string()),
+            oidc_auto_register: env::var("OIDC_AUTO_REGISTER")
+                .map(|v| v != "false" && v != "0")
+                .unwrap_or(true),
+            oidc_verified_claim: optional_env("OIDC_VERIFIED_CLAIM"),
+            oidc_verified_values: env::var("OIDC_VERIFIED_VALUES")
+                .unwrap_or_default()
+                .split(',')
+                .map(|s| s.trim().to_string())
+                .filter(|s| !s.is_empty())
+                .collect(),
+            oidc_real_name_claim: optional_env("OIDC_REAL_NAME_CLAIM")
+                .unwrap_or_else(|| "name".to_string()),
+            oidc_student_id_claim: optional_env("OIDC_STUDENT_ID_CLAIM"),
+            oidc_timeout_secs: env::var("OIDC_TIMEOUT_SECS")
+                .ok()
+                .and_then(|value| value.parse::<u64>().ok())
+                .filter(|value| *value > 0)
+                .unwrap_or(10),
+            real_info_encryption_keys: env::var("REAL_INFO_ENCRYPTION_KEYS")
+                .unwrap_or_default()
+                .split(',')
+                .map(|s| s.trim().to_string())
+                .filter(|s| !s.is_empty())
+                .collect(),
+            totp_encryption_keys: env::var("TOTP_ENCRYPTION_KEYS")
+                .unwrap_or_default()
+                .split(',')
+                .map(|s| s.trim().to_string())
+                .filter(|s| !s.is_empty())
+                .collect(),
         }
     }
 }
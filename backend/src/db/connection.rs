@@ -3,7 +3,8 @@ use std::sync::Arc;
 use std::time::Duration;
 
 use crate::config::BrandConfig;
-use crate::services::StorageBackend;
+use crate::services::{Mailer, ModerationProvider, OidcClient, RealInfoCipher, StorageBackend};
+use crate::utils::FieldCipher;
 
 /// 创建数据库连接池
 ///
@@ -37,6 +38,16 @@ pub struct AppState {
     pub jwt_secret: String,
     pub cookie_secure: bool,
     pub storage: Arc<dyn StorageBackend>,
+    /// 内容审核提供方
+    pub moderation: Arc<dyn ModerationProvider>,
+    /// 邮件发送方
+    pub mailer: Arc<dyn Mailer>,
+    /// OIDC 单点登录客户端，未启用时为 None
+    pub oidc: Option<Arc<OidcClient>>,
+    /// 实名信息加密器，未配置密钥时为 None（明文存储）
+    pub real_info_cipher: Option<Arc<RealInfoCipher>>,
+    /// 两步验证密钥加密器，未配置密钥时为 None（明文存储）
+    pub totp_cipher: Option<Arc<FieldCipher>>,
     /// 注册时是否强制要求邮箱
     pub require_email_on_register: bool,
     /// 是否允许用户修改用户名
@@ -57,6 +68,11 @@ impl AppState {
         jwt_secret: String,
         cookie_secure: bool,
         storage: Arc<dyn StorageBackend>,
+        moderation: Arc<dyn ModerationProvider>,
+        mailer: Arc<dyn Mailer>,
+        oidc: Option<Arc<OidcClient>>,
+        real_info_cipher: Option<Arc<RealInfoCipher>>,
+        totp_cipher: Option<Arc<FieldCipher>>,
         require_email_on_register: bool,
         allow_username_change: bool,
         allow_email_change: bool,
@@ -69,6 +85,11 @@ impl AppState {
             jwt_secret,
             cookie_secure,
             storage,
+            moderation,
+            mailer,
+            oidc,
+            real_info_cipher,
+            totp_cipher,
             require_email_on_register,
             allow_username_change,
             allow_email_change,
@@ -119,6 +140,11 @@ mod tests {
             jwt_secret: String,
             cookie_secure: bool,
             storage: Arc<dyn StorageBackend>,
+            moderation: Arc<dyn ModerationProvider>,
+            mailer: Arc<dyn Mailer>,
+            oidc: Option<Arc<OidcClient>>,
+            real_info_cipher: Option<Arc<RealInfoCipher>>,
+            totp_cipher: Option<Arc<FieldCipher>>,
             require_email_on_register: bool,
             allow_username_change: bool,
             allow_email_change: bool,
@@ -126,11 +152,11 @@ mod tests {
             pdf_preview_challenge_uuid: Option<String>,
             pdf_preview_challenge_code: Option<String>,
         ) -> AppState {
-            AppState::new(pool, jwt_secret, cookie_secure, storage, require_email_on_register, allow_username_change, allow_email_change, brand, pdf_preview_challenge_uuid, pdf_preview_challenge_code)
+            AppState::new(pool, jwt_secret, cookie_secure, storage, moderation, mailer, oidc, real_info_cipher, totp_cipher, require_email_on_register, allow_username_change, allow_email_change, brand, pdf_preview_challenge_uuid, pdf_preview_challenge_code)
         }
 
         // 验证函数指针类型
-        let _: fn(PgPool, String, bool, Arc<dyn StorageBackend>, bool, bool, bool, BrandConfig, Option<String>, Option<String>) -> AppState = _check_app_state_new_signature;
+        let _: fn(PgPool, String, bool, Arc<dyn StorageBackend>, Arc<dyn ModerationProvider>, Arc<dyn Mailer>, Option<Arc<OidcClient>>, Option<Arc<RealInfoCipher>>, Option<Arc<FieldCipher>>, bool, bool, bool, BrandConfig, Option<String>, Option<String>) -> AppState = _check_app_state_new_signature;
 
         // 测试通过，类型检查完成
         assert!(true);
@@ -143,10 +169,7 @@ mod tests {
     async fn test_create_pool_with_invalid_url() {
         let invalid_url = "invalid_url_format";
         let result = create_pool(invalid_url).await;
-        assert!(
-            result.is_err(),
-            "无效的数据库 URL 应该返回错误"
-        );
+        assert!(result.is_err(), "无效的数据库 URL 应该返回错误");
     }
 
     /// 测试数据库连接超时
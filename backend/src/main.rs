@@ -12,12 +12,14 @@ mod middleware;
 mod models;
 mod services;
 mod tasks;
+#[cfg(test)]
+mod test_support;
 mod utils;
 
 use crate::utils::{internal_error, not_found};
 use config::Config;
 use db::AppState;
-use middleware::{JwtAuth, PublicPathRule};
+use middleware::{JwtAuth, PublicPathRule, RateLimit, RateLimitRule, RequireRole};
 
 #[derive(Serialize)]
 #[serde(rename_all = "camelCase")]
@@ -55,8 +57,6 @@ async fn health_check(config: web::Data<AppState>) -> impl Responder {
 /// 使用后端代理模式读取文件，避免浏览器直接访问 OSS 产生 CORS 问题
 #[get("/images/{image_id}")]
 async fn serve_image(data: web::Data<AppState>, path: web::Path<Uuid>) -> impl Responder {
-    use crate::services::StorageBackendType;
-
     let image_id = path.into_inner();
 
     // 从数据库获取图片路径和存储类型
@@ -64,44 +64,22 @@ async fn serve_image(data: web::Data<AppState>, path: web::Path<Uuid>) -> impl R
         Ok((file_path, mime_type, storage_type)) => {
             // 根据图片实际的存储类型选择正确的存储后端读取文件
             // 使用后端代理模式，避免浏览器直接访问 OSS 产生 CORS 问题
-            let is_oss = storage_type.as_deref() == Some("oss");
-
-            let read_result = if is_oss {
-                // OSS 存储：使用主 storage（如果是 OSS 模式）或创建 OSS 存储实例
-                if data.storage.backend_type() == StorageBackendType::Oss {
-                    data.storage.read_file(&file_path).await
-                } else {
-                    // 当前是 local 模式，但需要读取 OSS 文件
-                    let config = config::Config::from_env();
-                    match services::create_storage_backend(&config) {
-                        Ok(oss_storage) if oss_storage.backend_type() == StorageBackendType::Oss => {
-                            oss_storage.read_file(&file_path).await
-                        }
-                        _ => {
-                            log::warn!(
-                                "[Image] 无法创建 OSS 存储实例来读取图片 | image_id={}",
-                                image_id
-                            );
-                            return internal_error("无法读取 OSS 图片");
-                        }
-                    }
-                }
-            } else {
-                // 本地存储：使用主 storage（如果是 Local 模式）或创建本地存储实例
-                if data.storage.backend_type() == StorageBackendType::Local {
-                    data.storage.read_file(&file_path).await
-                } else {
-                    // 当前是 OSS 模式，但需要读取本地文件
-                    let config = config::Config::from_env();
-                    match services::create_local_storage(&config) {
-                        Ok(local_storage) => local_storage.read_file(&file_path).await,
-                        Err(e) => {
-                            log::error!("[Image] 创建本地存储失败 | error={}", e);
-                            return internal_error("无法访问本地存储");
-                        }
+            let storage_name = storage_type.as_deref().unwrap_or("local");
+
+            let config = config::Config::from_env();
+            let read_result =
+                match services::storage_for_type(&data.storage, &config, storage_type.as_deref()) {
+                    Ok(storage) => storage.read_file(&file_path).await,
+                    Err(e) => {
+                        log::warn!(
+                            "[Image] 无法访问图片所在的存储 | image_id={}, storage={}, error={}",
+                            image_id,
+                            storage_name,
+                            e
+                        );
+                        return internal_error("无法访问图片存储");
                     }
-                }
-            };
+                };
 
             match read_result {
                 Ok(file_content) => {
@@ -120,7 +98,7 @@ async fn serve_image(data: web::Data<AppState>, path: web::Path<Uuid>) -> impl R
                         "[Image] 读取图片文件失败 | image_id={}, path={}, storage={}, error={}",
                         image_id,
                         file_path,
-                        if is_oss { "oss" } else { "local" },
+                        storage_name,
                         e
                     );
                     not_found("图片不存在")
@@ -188,6 +166,16 @@ async fn main() -> std::io::Result<()> {
         }
     };
 
+    // 命令行子命令：加密已有的实名信息后退出，不启动服务
+    if std::env::args().nth(1).as_deref() == Some("encrypt-real-info") {
+        std::process::exit(encrypt_real_info(&pool, &config).await);
+    }
+
+    // 命令行子命令：加密已有的两步验证密钥后退出，不启动服务
+    if std::env::args().nth(1).as_deref() == Some("encrypt-totp-secrets") {
+        std::process::exit(encrypt_totp_secrets(&pool, &config).await);
+    }
+
     // 同步管理员权限（根据环境变量配置）
     if !config.admin_usernames.is_empty() {
         log::info!(
@@ -238,12 +226,87 @@ async fn main() -> std::io::Result<()> {
         storage.backend_type().as_str()
     );
 
+    // 初始化内容审核提供方
+    let moderation = match services::create_moderation_provider(&config) {
+        Ok(moderation) => moderation,
+        Err(e) => {
+            log::error!("[System] 初始化内容审核失败 | error={}", e);
+            std::process::exit(1);
+        }
+    };
+    log::info!(
+        "[System] Moderation provider: {}",
+        moderation.provider_type().as_str()
+    );
+
+    // 初始化邮件发送方
+    let mailer = match services::create_mailer(&config) {
+        Ok(mailer) => mailer,
+        Err(e) => {
+            log::error!("[System] 初始化邮件发送失败 | error={}", e);
+            std::process::exit(1);
+        }
+    };
+    log::info!(
+        "[System] Mail transport: {}",
+        mailer.transport_type().as_str()
+    );
+
+    // 初始化 OIDC 单点登录
+    let oidc = match services::create_oidc_client(&config) {
+        Ok(oidc) => oidc,
+        Err(e) => {
+            log::error!("[System] 初始化单点登录失败 | error={}", e);
+            std::process::exit(1);
+        }
+    };
+    if let Some(ref oidc) = oidc {
+        log::info!("[System] OIDC enabled: issuer={}", oidc.settings().issuer);
+    }
+
+    // 初始化实名信息加密
+    let real_info_cipher = match services::create_real_info_cipher(&config) {
+        Ok(cipher) => cipher,
+        Err(e) => {
+            log::error!("[System] 初始化实名信息加密失败 | error={}", e);
+            std::process::exit(1);
+        }
+    };
+    match real_info_cipher {
+        Some(ref cipher) => log::info!(
+            "[System] Real info encryption enabled: key_id={}",
+            cipher.current_key_id()
+        ),
+        None => log::warn!("[System] 未配置 REAL_INFO_ENCRYPTION_KEYS，实名信息将以明文存储"),
+    }
+
+    // 初始化两步验证密钥加密
+    let totp_cipher = match utils::FieldCipher::from_keys(&config.totp_encryption_keys) {
+        Ok(cipher) => cipher.map(std::sync::Arc::new),
+        Err(e) => {
+            log::error!("[System] 初始化两步验证密钥加密失败 | error={}", e);
+            std::process::exit(1);
+        }
+    };
+    match totp_cipher {
+        Some(ref cipher) => log::info!(
+            "[System] TOTP secret encryption enabled: key_id={}",
+            cipher.current_key_id()
+        ),
+        None => log::warn!("[System] 未配置 TOTP_ENCRYPTION_KEYS，两步验证密钥将以明文存储"),
+    }
+
     // 创建应用状态
     let app_state = web::Data::new(AppState::new(
         pool.clone(),
         config.jwt_secret.clone(),
         config.cookie_secure,
         storage.clone(),
+        moderation,
+        mailer,
+        oidc,
+        real_info_cipher,
+        totp_cipher,
         config.require_email_on_register,
         config.allow_username_change,
         config.allow_email_change,
@@ -253,7 +316,22 @@ async fn main() -> std::io::Result<()> {
     ));
 
     // 启动文件哈希计算后台任务
-    tasks::file_hash_task::start_file_hash_task(pool, storage).await;
+    tasks::file_hash_task::start_file_hash_task(pool.clone(), storage.clone()).await;
+
+    // 上次运行中断的存储迁移任务无法继续，标记为失败
+    tasks::storage_migration_task::fail_interrupted_migrations(&pool).await;
+
+    // 启动登录会话清理后台任务
+    tasks::session_cleanup_task::start_session_cleanup_task(pool.clone()).await;
+
+    // 启动资源推荐后台任务
+    tasks::recommendation_task::start_recommendation_task(pool.clone()).await;
+
+    // 启动资源热度后台任务
+    tasks::trending_task::start_trending_task(pool.clone()).await;
+
+    // 启动打包文件清理后台任务
+    tasks::pack_job_task::start_pack_job_gc_task(pool, storage, config.clone()).await;
 
     log::info!("[System] Server starting at http://{}", server_addr);
     log::debug!("[System] Debug logging enabled");
@@ -262,9 +340,13 @@ async fn main() -> std::io::Result<()> {
     log::debug!("[System]   POST /api/auth/login    - 用户登录");
     log::debug!("[System]   POST /api/auth/refresh  - 刷新Token");
     log::debug!("[System]   POST /api/auth/logout   - 用户登出");
+    log::debug!("[System]   GET  /api/auth/sessions - 获取登录会话列表");
+    log::debug!("[System]   DEL  /api/auth/sessions - 退出全部登录会话");
+    log::debug!("[System]   DEL  /api/auth/sessions/{{id}} - 退出指定登录会话");
     log::debug!("[System]   GET  /api/users/me      - 获取当前用户");
     log::debug!("[System]   PUT  /api/users/me      - 更新用户资料");
-    log::debug!("[System]   POST /api/users/verify  - 实名认证");
+    log::debug!("[System]   POST /api/users/verify  - 提交实名认证申请");
+    log::debug!("[System]   GET  /api/users/me/verification - 获取实名认证状态");
     log::debug!("[System]   GET  /api/users/{{user_id}} - 获取用户资料");
     log::debug!("[System]   POST /api/images/upload - 上传图片");
     log::debug!("[System]   GET  /api/images        - 获取我的图片列表");
@@ -275,9 +357,15 @@ async fn main() -> std::io::Result<()> {
     log::debug!("[System]   GET  /api/resources     - 获取资源列表");
     log::debug!("[System]   GET  /api/resources/search - 搜索资源");
     log::debug!("[System]   GET  /api/resources/my  - 获取我的资源列表");
+    log::debug!("[System]   GET  /api/resources/recommended - 获取推荐资源");
     log::debug!("[System]   GET  /api/resources/{{id}} - 获取资源详情");
     log::debug!("[System]   GET  /api/resources/{{id}}/download - 下载资源");
     log::debug!("[System]   DEL  /api/resources/{{id}} - 删除资源");
+    log::debug!("[System]   POST /api/resources/{{id}}/claims - 提交资源申领");
+    log::debug!("[System]   GET  /api/claims/my     - 获取我的申领列表");
+    log::debug!("[System]   GET  /api/claims/{{id}}   - 获取申领详情");
+    log::debug!("[System]   POST /api/reports       - 举报资源或评论");
+    log::debug!("[System]   GET  /api/reports/my    - 获取我的举报列表");
     log::debug!("[System]   POST /api/favorites     - 创建收藏夹");
     log::debug!("[System]   GET  /api/favorites     - 获取我的收藏夹列表");
     log::debug!("[System]   GET  /api/favorites/{{id}} - 获取收藏夹详情");
@@ -286,12 +374,47 @@ async fn main() -> std::io::Result<()> {
     log::debug!("[System]   POST /api/favorites/{{id}}/resources - 添加资源到收藏夹");
     log::debug!("[System]   DEL  /api/favorites/{{id}}/resources/{{rid}} - 从收藏夹移除资源");
     log::debug!("[System]   GET  /api/favorites/check/{{rid}} - 检查资源收藏状态");
+    log::debug!("[System]   POST /api/favorites/{{id}}/pack-jobs - 创建打包下载任务");
+    log::debug!("[System]   GET  /api/pack-jobs     - 获取我的打包任务");
+    log::debug!("[System]   GET  /api/pack-jobs/{{id}} - 获取打包任务进度");
+    log::debug!("[System]   GET  /api/pack-downloads/{{id}} - 下载打包文件（签名链接）");
+    log::debug!("[System]   POST /api/auth/email/verify - 验证邮箱");
+    log::debug!("[System]   POST /api/auth/password/forgot - 申请重置密码");
+    log::debug!("[System]   POST /api/auth/password/reset - 重置密码");
+    log::debug!("[System]   POST /api/users/me/email/verification - 重新发送邮箱验证邮件");
+    log::debug!("[System]   POST /api/auth/login/2fa - 提交两步验证码登录");
+    log::debug!("[System]   GET  /api/users/me/2fa  - 获取两步验证状态");
+    log::debug!("[System]   POST /api/users/me/2fa/setup - 生成两步验证密钥");
+    log::debug!("[System]   POST /api/users/me/2fa/enable - 启用两步验证");
+    log::debug!("[System]   POST /api/users/me/2fa/disable - 关闭两步验证");
+    log::debug!("[System]   POST /api/users/me/2fa/recovery-codes - 重新生成恢复码");
+    log::debug!("[System]   GET  /api/auth/oidc/authorize - 发起单点登录");
+    log::debug!("[System]   GET  /api/auth/oidc/callback - 单点登录回调");
+    log::debug!("[System]   GET  /api/users/me/identities - 获取绑定的外部身份");
+    log::debug!("[System]   DEL  /api/users/me/identities/{{id}} - 解除外部身份绑定");
     log::debug!("[System]   GET  /api/health        - 健康检查");
     log::debug!("[System]   GET  /api/hello         - 测试接口");
 
     // 克隆配置数据用于闭包
     let jwt_secret = config.jwt_secret.clone();
     let cors_origins = config.cors_allowed_origins.clone();
+    let rate_limit_enabled = config.rate_limit_enabled;
+    let rate_limit_trust_proxy = config.rate_limit_trust_proxy;
+    let admin_require_2fa = config.admin_require_2fa;
+    if admin_require_2fa {
+        log::info!("[System] Admin two-factor authentication required");
+    }
+
+    // 限流计数在所有 worker 线程间共享
+    let rate_limiter = std::sync::Arc::new(services::RateLimiter::from_config(&config));
+    if rate_limit_enabled {
+        log::info!(
+            "[System] Rate limit enabled: ip_free_attempts={}, username_free_attempts={}, lockout_threshold={}",
+            config.rate_limit_ip_free_attempts,
+            config.rate_limit_username_free_attempts,
+            config.login_lockout_threshold
+        );
+    }
 
     // 记录 CORS 配置信息
     log::info!("[System] CORS allowed origins: {:?}", cors_origins);
@@ -302,11 +425,15 @@ async fn main() -> std::io::Result<()> {
 
         // 配置公开路径规则
         let public_rules = vec![
-            // /api/auth 全部公开
-            PublicPathRule::all_methods("/api/auth"),
+            // /api/auth 全部公开，登录会话管理除外
+            PublicPathRule::all_methods("/api/auth").exclude(vec!["/api/auth/sessions"]),
             // /api/resources GET 方法公开（列表、搜索、详情、下载），但排除需要登录的接口
             PublicPathRule::with_methods("/api/resources", vec![Method::GET])
-                .exclude(vec!["/api/resources/my", "/api/resources/{id}/rate"]),
+                .exclude(vec![
+                    "/api/resources/my",
+                    "/api/resources/recommended",
+                    "/api/resources/{id}/rate",
+                ]),
             // /api/resources/pdf-preview-challenge 全部公开（支持未登录用户检测）
             PublicPathRule::all_methods("/api/resources/pdf-preview-challenge"),
             // /api/users/{user_id} 和 /api/users/{user_id}/homepage GET 方法公开
@@ -318,9 +445,67 @@ async fn main() -> std::io::Result<()> {
             // /api/teachers 和 /api/courses GET 方法公开（供游客筛选资源）
             PublicPathRule::with_methods("/api/teachers", vec![Method::GET]),
             PublicPathRule::with_methods("/api/courses", vec![Method::GET]),
+            // /api/comments GET 方法公开（楼层回复、编辑历史）
+            PublicPathRule::with_methods("/api/comments", vec![Method::GET]),
+            // /api/pack-downloads 通过签名链接下载打包文件，无需登录
+            PublicPathRule::with_methods("/api/pack-downloads", vec![Method::GET]),
         ];
 
-        let jwt_auth = JwtAuth::new(jwt_secret.clone()).with_public_rules(public_rules);
+        let jwt_auth = JwtAuth::new(jwt_secret.clone())
+            .with_public_rules(public_rules)
+            .with_session_check(app_state.pool.clone());
+
+        // 管理后台接口需要管理员角色或分配的角色权限，具体权限由各接口检查；
+        // 要求两步验证时，管理员未完成两步验证只能访问登录和个人设置接口
+        let require_admin = RequireRole::admin()
+            .for_paths(vec!["/api/admin"])
+            .with_permission_grants(app_state.pool.clone());
+        let require_admin = if admin_require_2fa {
+            require_admin.with_mfa_check(app_state.pool.clone(), vec!["/api/auth", "/api/users/me"])
+        } else {
+            require_admin
+        };
+
+        // 配置限流规则（登录、注册、单点登录、实名验证、邮件验证与找回密码、两步验证、PDF 预览挑战验证）
+        let rate_limit_rules = if rate_limit_enabled {
+            vec![
+                RateLimitRule::failures("login", Method::POST, "/api/auth/login"),
+                RateLimitRule::failures("login_2fa", Method::POST, "/api/auth/login/2fa"),
+                RateLimitRule::attempts("register", Method::POST, "/api/auth/register"),
+                RateLimitRule::attempts("oidc_authorize", Method::GET, "/api/auth/oidc/authorize"),
+                RateLimitRule::failures("verify", Method::POST, "/api/users/verify"),
+                RateLimitRule::failures("email_verify", Method::POST, "/api/auth/email/verify"),
+                RateLimitRule::attempts(
+                    "email_send",
+                    Method::POST,
+                    "/api/users/me/email/verification",
+                ),
+                RateLimitRule::attempts(
+                    "password_forgot",
+                    Method::POST,
+                    "/api/auth/password/forgot",
+                ),
+                RateLimitRule::failures("password_reset", Method::POST, "/api/auth/password/reset"),
+                RateLimitRule::failures("2fa_setup", Method::POST, "/api/users/me/2fa/setup"),
+                RateLimitRule::failures("2fa_enable", Method::POST, "/api/users/me/2fa/enable"),
+                RateLimitRule::failures("2fa_disable", Method::POST, "/api/users/me/2fa/disable"),
+                RateLimitRule::failures(
+                    "2fa_recovery_codes",
+                    Method::POST,
+                    "/api/users/me/2fa/recovery-codes",
+                ),
+                RateLimitRule::attempts(
+                    "pdf_challenge",
+                    Method::POST,
+                    "/api/resources/pdf-preview-challenge/verify",
+                ),
+            ]
+        } else {
+            Vec::new()
+        };
+        let rate_limit = RateLimit::new(rate_limiter.clone(), rate_limit_rules)
+            .trust_proxy(rate_limit_trust_proxy)
+            .with_audit(app_state.pool.clone());
 
         // 构建 CORS 配置
         // 注意：使用 Cookie 认证必须设置 supports_credentials(true)
@@ -358,12 +543,18 @@ async fn main() -> std::io::Result<()> {
             // 注意：config 必须在 config_public 之前注册，否则 /resources/my 会被 /resources/{id} 匹配
             .service(
                 web::scope("/api")
+                    .wrap(require_admin) // 角色检查在认证之后执行
                     .wrap(jwt_auth)
+                    .wrap(rate_limit) // 限流在认证之前执行
                     .configure(api::auth::config)
                     .configure(api::user::config)
+                    .configure(api::two_factor::config) // 两步验证路由
+                    .configure(api::oidc::config) // 单点登录路由
                     .configure(api::oss::config)
                     .configure(api::image_host::config)
                     .configure(api::comment::config) // 评论路由
+                    .configure(api::claim::config) // 资源申领路由
+                    .configure(api::report::config) // 举报路由
                     .configure(api::notification::config) // 通知路由
                     .configure(api::admin::config) // 管理后台路由
                     .configure(api::favorite::config) // 收藏夹路由
@@ -433,3 +624,86 @@ async fn initialize_user_sn(pool: &sqlx::PgPool) -> Result<usize, sqlx::Error> {
 
     Ok(assigned)
 }
+
+/// `encrypt-real-info` 子命令：用当前密钥加密已有的实名信息，返回进程退出码
+///
+/// 轮换密钥时先把新密钥放在 REAL_INFO_ENCRYPTION_KEYS 最前面并保留旧密钥，
+/// 执行完成且没有失败记录后即可移除旧密钥
+async fn encrypt_real_info(pool: &sqlx::PgPool, config: &Config) -> i32 {
+    let cipher = match services::RealInfoCipher::from_config(config) {
+        Ok(Some(cipher)) => cipher,
+        Ok(None) => {
+            log::error!("[RealInfo] 未配置 REAL_INFO_ENCRYPTION_KEYS，无法加密实名信息");
+            return 1;
+        }
+        Err(e) => {
+            log::error!("[RealInfo] 加密密钥配置错误 | error={}", e);
+            return 1;
+        }
+    };
+
+    log::info!(
+        "[RealInfo] 开始加密实名信息 | key_id={}",
+        cipher.current_key_id()
+    );
+    match services::RealInfoService::encrypt_existing(pool, &cipher).await {
+        Ok(stats) => {
+            log::info!(
+                "[RealInfo] 实名信息加密完成 | scanned={}, updated={}, failed={}",
+                stats.scanned,
+                stats.updated,
+                stats.failed
+            );
+            if stats.failed > 0 {
+                1
+            } else {
+                0
+            }
+        }
+        Err(e) => {
+            log::error!("[RealInfo] 实名信息加密失败 | error={}", e);
+            1
+        }
+    }
+}
+
+/// `encrypt-totp-secrets` 子命令：用当前密钥加密已有的两步验证密钥，返回进程退出码
+///
+/// 轮换密钥的方式与 `encrypt-real-info` 相同，使用 TOTP_ENCRYPTION_KEYS
+async fn encrypt_totp_secrets(pool: &sqlx::PgPool, config: &Config) -> i32 {
+    let cipher = match utils::FieldCipher::from_keys(&config.totp_encryption_keys) {
+        Ok(Some(cipher)) => cipher,
+        Ok(None) => {
+            log::error!("[Totp] 未配置 TOTP_ENCRYPTION_KEYS，无法加密两步验证密钥");
+            return 1;
+        }
+        Err(e) => {
+            log::error!("[Totp] 加密密钥配置错误 | error={}", e);
+            return 1;
+        }
+    };
+
+    log::info!(
+        "[Totp] 开始加密两步验证密钥 | key_id={}",
+        cipher.current_key_id()
+    );
+    match services::TotpService::encrypt_existing_secrets(pool, &cipher).await {
+        Ok(stats) => {
+            log::info!(
+                "[Totp] 两步验证密钥加密完成 | scanned={}, updated={}, failed={}",
+                stats.scanned,
+                stats.updated,
+                stats.failed
+            );
+            if stats.failed > 0 {
+                1
+            } else {
+                0
+            }
+        }
+        Err(e) => {
+            log::error!("[Totp] 两步验证密钥加密失败 | error={}", e);
+            1
+        }
+    }
+}
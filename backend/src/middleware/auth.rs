@@ -1,19 +1,21 @@
 use actix_web::{
     body::MessageBody,
     dev::{Service, ServiceRequest, ServiceResponse, Transform},
-    error::ErrorUnauthorized,
+    error::{ErrorInternalServerError, ErrorUnauthorized, InternalError},
     http::{header, Method},
     Error, HttpMessage,
 };
 use futures_util::future::LocalBoxFuture;
+use sqlx::PgPool;
 use std::{
     future::{ready, Ready},
     rc::Rc,
     task::{Context, Poll},
 };
 
-use crate::models::CurrentUser;
-use crate::utils::{extract_current_user, verify_token};
+use crate::models::{CurrentUser, UserRole};
+use crate::services::{PermissionService, SessionService};
+use crate::utils::{extract_current_user, forbidden, verify_token};
 
 /// Cookie 名称常量
 const ACCESS_TOKEN_COOKIE: &str = "access_token";
@@ -73,6 +75,8 @@ impl PublicPathRule {
 pub struct JwtAuth {
     jwt_secret: String,
     public_paths: Vec<PublicPathRule>,
+    /// 用于校验会话是否被吊销，未设置时只校验 Token 签名
+    session_pool: Option<PgPool>,
 }
 
 impl JwtAuth {
@@ -80,6 +84,7 @@ impl JwtAuth {
         Self {
             jwt_secret,
             public_paths: Vec::new(),
+            session_pool: None,
         }
     }
 
@@ -89,6 +94,11 @@ impl JwtAuth {
         self
     }
 
+    /// 启用会话校验：用户被禁用、令牌版本变化或会话被吊销后 Token 立即失效
+    pub fn with_session_check(mut self, pool: PgPool) -> Self {
+        self.session_pool = Some(pool);
+        self
+    }
 }
 
 impl<S, B> Transform<S, ServiceRequest> for JwtAuth
@@ -108,6 +118,7 @@ where
             service: Rc::new(service),
             jwt_secret: self.jwt_secret.clone(),
             public_paths: self.public_paths.clone(),
+            session_pool: self.session_pool.clone(),
         }))
     }
 }
@@ -116,6 +127,7 @@ pub struct JwtAuthMiddleware<S> {
     service: Rc<S>,
     jwt_secret: String,
     public_paths: Vec<PublicPathRule>,
+    session_pool: Option<PgPool>,
 }
 
 impl<S, B> Service<ServiceRequest> for JwtAuthMiddleware<S>
@@ -136,6 +148,7 @@ where
         let service = self.service.clone();
         let jwt_secret = self.jwt_secret.clone();
         let public_paths = self.public_paths.clone();
+        let session_pool = self.session_pool.clone();
 
         Box::pin(async move {
             let path = req.path().to_string();
@@ -158,8 +171,47 @@ where
                 // 验证Token
                 match verify_token(&token, &jwt_secret, Some("access")) {
                     Ok(claims) => {
+                        let token_version = claims.ver;
                         match extract_current_user(claims) {
                             Ok(current_user) => {
+                                // 校验用户状态、令牌版本和会话是否仍然有效
+                                if let Some(pool) = &session_pool {
+                                    match SessionService::is_access_valid(
+                                        pool,
+                                        current_user.id,
+                                        current_user.session_id,
+                                        token_version,
+                                    )
+                                    .await
+                                    {
+                                        Ok(true) => {}
+                                        Ok(false) => {
+                                            log::info!(
+                                                "[Auth] 登录会话已失效 | user_id={}, session_id={:?}",
+                                                current_user.id,
+                                                current_user.session_id
+                                            );
+                                            if !is_public {
+                                                return Err(ErrorUnauthorized("登录会话已失效"));
+                                            }
+                                            return service.call(req).await;
+                                        }
+                                        Err(e) => {
+                                            log::error!(
+                                                "[Auth] 校验登录会话失败 | user_id={}, error={}",
+                                                current_user.id,
+                                                e
+                                            );
+                                            if !is_public {
+                                                return Err(ErrorInternalServerError(
+                                                    "服务器内部错误",
+                                                ));
+                                            }
+                                            return service.call(req).await;
+                                        }
+                                    }
+                                }
+
                                 log::debug!(
                                     "用户认证成功: {}, 角色: {:?}",
                                     current_user.username,
@@ -236,26 +288,200 @@ pub fn get_current_user(req: &ServiceRequest) -> Option<CurrentUser> {
     req.extensions().get::<CurrentUser>().cloned()
 }
 
-/// 角色检查中间件工厂（预留接口）
-#[allow(dead_code)]
+/// 角色检查中间件
+///
+/// 访问 `for_paths` 指定前缀的接口需要具有对应角色；启用 `with_permission_grants` 后，
+/// 在数据库中分配了任一角色权限的用户同样可以访问，其有效权限（`UserPermissions`）会写入请求扩展，
+/// 由处理函数检查具体权限。启用 `with_mfa_check` 后，具有该角色的用户，以及通过角色权限授予访问受限路径的用户，
+/// 还必须在当前会话中完成两步验证，否则除豁免路径外的请求都会被拒绝。
+/// 依赖 JwtAuth 写入的用户信息，需要在 JwtAuth 之后执行。
+#[derive(Clone)]
 pub struct RequireRole {
-    role: String,
+    role: UserRole,
+    path_prefixes: Vec<String>,
+    /// 用于加载用户的角色权限，为空时只按用户角色判断
+    permission_pool: Option<PgPool>,
+    /// 用于查询会话的两步验证状态，为空时不要求两步验证
+    mfa_pool: Option<PgPool>,
+    mfa_exempt_paths: Vec<String>,
 }
 
 impl RequireRole {
-    #[allow(dead_code)]
     pub fn admin() -> Self {
         Self {
-            role: "admin".to_string(),
+            role: UserRole::Admin,
+            path_prefixes: Vec::new(),
+            permission_pool: None,
+            mfa_pool: None,
+            mfa_exempt_paths: Vec::new(),
         }
     }
 
     #[allow(dead_code)]
     pub fn verified() -> Self {
         Self {
-            role: "verified".to_string(),
+            role: UserRole::Verified,
+            ..Self::admin()
         }
     }
+
+    /// 设置需要该角色才能访问的路径前缀
+    pub fn for_paths(mut self, paths: Vec<&str>) -> Self {
+        self.path_prefixes = paths.into_iter().map(|p| p.to_string()).collect();
+        self
+    }
+
+    /// 允许具有角色权限授予的用户访问，并将其有效权限写入请求扩展
+    pub fn with_permission_grants(mut self, pool: PgPool) -> Self {
+        self.permission_pool = Some(pool);
+        self
+    }
+
+    /// 要求具有该角色或通过角色权限授予访问的用户完成两步验证，`exempt_paths` 下的接口（登录、启用两步验证等）不受限制
+    pub fn with_mfa_check(mut self, pool: PgPool, exempt_paths: Vec<&str>) -> Self {
+        self.mfa_pool = Some(pool);
+        self.mfa_exempt_paths = exempt_paths.into_iter().map(|p| p.to_string()).collect();
+        self
+    }
+
+    fn has_role(&self, user: &CurrentUser) -> bool {
+        match self.role {
+            UserRole::Admin => user.role == UserRole::Admin,
+            UserRole::Verified => user.role == UserRole::Admin || user.is_verified,
+            UserRole::User | UserRole::Guest => true,
+        }
+    }
+
+    /// 是否需要检查两步验证：具有该角色的用户在所有非豁免路径上都需要；
+    /// 通过角色权限授予进入受限路径的用户同样需要，避免绕过管理后台的两步验证要求
+    fn mfa_applies(&self, path: &str, has_role: bool, admitted_by_grants: bool) -> bool {
+        let is_exempt = self.mfa_exempt_paths.iter().any(|p| path.starts_with(p));
+        (has_role || admitted_by_grants) && !is_exempt
+    }
+}
+
+impl<S, B> Transform<S, ServiceRequest> for RequireRole
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    S::Future: 'static,
+    B: MessageBody + 'static,
+{
+    type Response = ServiceResponse<B>;
+    type Error = Error;
+    type Transform = RequireRoleMiddleware<S>;
+    type InitError = ();
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        ready(Ok(RequireRoleMiddleware {
+            service: Rc::new(service),
+            rule: Rc::new(self.clone()),
+        }))
+    }
+}
+
+pub struct RequireRoleMiddleware<S> {
+    service: Rc<S>,
+    rule: Rc<RequireRole>,
+}
+
+impl<S, B> Service<ServiceRequest> for RequireRoleMiddleware<S>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    S::Future: 'static,
+    B: MessageBody + 'static,
+{
+    type Response = ServiceResponse<B>;
+    type Error = Error;
+    type Future = LocalBoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    fn poll_ready(&self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.service.poll_ready(cx)
+    }
+
+    fn call(&self, req: ServiceRequest) -> Self::Future {
+        let service = self.service.clone();
+        let rule = self.rule.clone();
+        let path = req.path().to_string();
+        let requires_role = rule.path_prefixes.iter().any(|p| path.starts_with(p));
+        let current_user = req.extensions().get::<CurrentUser>().cloned();
+
+        let Some(user) = current_user else {
+            if requires_role {
+                return Box::pin(async { Err(ErrorUnauthorized("需要登录")) });
+            }
+            return Box::pin(service.call(req));
+        };
+
+        Box::pin(async move {
+            let has_role = rule.has_role(&user);
+            let mut admitted_by_grants = false;
+
+            if requires_role {
+                if let Some(pool) = &rule.permission_pool {
+                    let permissions =
+                        match PermissionService::load_user_permissions(pool, user.id, &user.role)
+                            .await
+                        {
+                            Ok(permissions) => permissions,
+                            Err(e) => {
+                                log::error!(
+                                    "[Auth] 加载用户权限失败 | user_id={}, error={}",
+                                    user.id,
+                                    e
+                                );
+                                return Err(ErrorInternalServerError("服务器内部错误"));
+                            }
+                        };
+                    let has_grants = !permissions.is_empty();
+                    req.extensions_mut().insert(permissions);
+                    if !has_role && !has_grants {
+                        return Err(permission_denied(&user, &path));
+                    }
+                    admitted_by_grants = !has_role;
+                } else if !has_role {
+                    return Err(permission_denied(&user, &path));
+                }
+            }
+
+            let mfa_pool = match &rule.mfa_pool {
+                Some(pool) if rule.mfa_applies(&path, has_role, admitted_by_grants) => pool,
+                _ => return service.call(req).await,
+            };
+
+            match SessionService::is_mfa_verified(mfa_pool, user.id, user.session_id).await {
+                Ok(true) => service.call(req).await,
+                Ok(false) => {
+                    log::info!(
+                        "[Auth] 会话未完成两步验证 | user_id={}, session_id={:?}, path={}",
+                        user.id,
+                        user.session_id,
+                        path
+                    );
+                    let response = forbidden("该账号需要启用两步验证，并在登录时完成验证");
+                    Err(InternalError::from_response("需要两步验证", response).into())
+                }
+                Err(e) => {
+                    log::error!(
+                        "[Auth] 查询两步验证状态失败 | user_id={}, error={}",
+                        user.id,
+                        e
+                    );
+                    Err(ErrorInternalServerError("服务器内部错误"))
+                }
+            }
+        })
+    }
+}
+
+fn permission_denied(user: &CurrentUser, path: &str) -> Error {
+    log::warn!(
+        "[Auth] 角色权限不足 | user_id={}, role={:?}, path={}",
+        user.id,
+        user.role,
+        path
+    );
+    InternalError::from_response("权限不足", forbidden("权限不足")).into()
 }
 
 /// 需要认证的处理函数包装器（预留接口）
@@ -273,3 +499,34 @@ where
     }
     f(req).await
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn admin_gate() -> RequireRole {
+        RequireRole {
+            mfa_exempt_paths: vec!["/api/auth".to_string(), "/api/users/me".to_string()],
+            ..RequireRole::admin().for_paths(vec!["/api/admin"])
+        }
+    }
+
+    #[test]
+    fn test_mfa_applies_to_role_holders() {
+        let gate = admin_gate();
+        assert!(gate.mfa_applies("/api/admin/users", true, false));
+        assert!(gate.mfa_applies("/api/resources", true, false));
+        assert!(!gate.mfa_applies("/api/auth/2fa/verify", true, false));
+        assert!(!gate.mfa_applies("/api/users/me/2fa", true, false));
+    }
+
+    #[test]
+    fn test_mfa_applies_to_grant_only_users() {
+        let gate = admin_gate();
+        // 通过角色权限授予进入管理后台的用户同样需要两步验证
+        assert!(gate.mfa_applies("/api/admin/users/real-info", false, true));
+        assert!(!gate.mfa_applies("/api/auth/login", false, true));
+        // 普通用户访问非受限路径不需要
+        assert!(!gate.mfa_applies("/api/resources", false, false));
+    }
+}
@@ -1,7 +1,10 @@
 // 中间件模块
 
 pub mod auth;
+pub mod rate_limit;
 
 // JwtAuth 和 PublicPathRule 在主程序中使用
 pub use auth::JwtAuth;
 pub use auth::PublicPathRule;
+pub use auth::RequireRole;
+pub use rate_limit::{RateLimit, RateLimitRule};
@@ -0,0 +1,329 @@
+use actix_web::{
+    body::MessageBody,
+    dev::{Service, ServiceRequest, ServiceResponse, Transform},
+    error::InternalError,
+    http::Method,
+    Error,
+};
+use futures_util::future::LocalBoxFuture;
+use sqlx::PgPool;
+use std::{
+    future::{ready, Ready},
+    rc::Rc,
+    sync::Arc,
+    task::{Context, Poll},
+};
+
+use crate::services::{AuditAction, AuditLogService, RateLimiter};
+use crate::utils::too_many_requests;
+
+/// 限流规则
+#[derive(Clone)]
+pub struct RateLimitRule {
+    /// 规则名称，作为计数键的一部分并写入审计日志
+    pub name: String,
+    pub path: String,
+    pub method: Method,
+    /// 是否每次请求都计数（注册等接口），否则只统计 4xx 失败
+    pub count_all: bool,
+}
+
+impl RateLimitRule {
+    /// 只统计失败请求的规则
+    pub fn failures(name: &str, method: Method, path: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            path: path.to_string(),
+            method,
+            count_all: false,
+        }
+    }
+
+    /// 统计所有请求的规则
+    pub fn attempts(name: &str, method: Method, path: &str) -> Self {
+        Self {
+            count_all: true,
+            ..Self::failures(name, method, path)
+        }
+    }
+
+    fn matches(&self, path: &str, method: &Method) -> bool {
+        self.path == path && self.method == *method
+    }
+}
+
+/// 按客户端 IP 限流的中间件，超出次数后指数退避，返回 429
+pub struct RateLimit {
+    limiter: Arc<RateLimiter>,
+    rules: Vec<RateLimitRule>,
+    trust_proxy: bool,
+    pool: Option<PgPool>,
+}
+
+impl RateLimit {
+    pub fn new(limiter: Arc<RateLimiter>, rules: Vec<RateLimitRule>) -> Self {
+        Self {
+            limiter,
+            rules,
+            trust_proxy: false,
+            pool: None,
+        }
+    }
+
+    /// 从反向代理头获取客户端 IP
+    pub fn trust_proxy(mut self, trust_proxy: bool) -> Self {
+        self.trust_proxy = trust_proxy;
+        self
+    }
+
+    /// 开始退避时写入审计日志
+    pub fn with_audit(mut self, pool: PgPool) -> Self {
+        self.pool = Some(pool);
+        self
+    }
+}
+
+impl<S, B> Transform<S, ServiceRequest> for RateLimit
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    S::Future: 'static,
+    B: MessageBody + 'static,
+{
+    type Response = ServiceResponse<B>;
+    type Error = Error;
+    type Transform = RateLimitMiddleware<S>;
+    type InitError = ();
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        ready(Ok(RateLimitMiddleware {
+            service: Rc::new(service),
+            limiter: self.limiter.clone(),
+            rules: Rc::new(self.rules.clone()),
+            trust_proxy: self.trust_proxy,
+            pool: self.pool.clone(),
+        }))
+    }
+}
+
+pub struct RateLimitMiddleware<S> {
+    service: Rc<S>,
+    limiter: Arc<RateLimiter>,
+    rules: Rc<Vec<RateLimitRule>>,
+    trust_proxy: bool,
+    pool: Option<PgPool>,
+}
+
+impl<S, B> Service<ServiceRequest> for RateLimitMiddleware<S>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    S::Future: 'static,
+    B: MessageBody + 'static,
+{
+    type Response = ServiceResponse<B>;
+    type Error = Error;
+    type Future = LocalBoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    fn poll_ready(&self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.service.poll_ready(cx)
+    }
+
+    fn call(&self, req: ServiceRequest) -> Self::Future {
+        let service = self.service.clone();
+        let rule = self
+            .rules
+            .iter()
+            .find(|rule| rule.matches(req.path(), req.method()))
+            .cloned();
+
+        // 不受限流的接口直接放行
+        let Some(rule) = rule else {
+            return Box::pin(service.call(req));
+        };
+
+        let limiter = self.limiter.clone();
+        let pool = self.pool.clone();
+        let ip = client_ip(&req, self.trust_proxy);
+        let key = format!("{}:{}", rule.name, ip.as_deref().unwrap_or("unknown"));
+
+        Box::pin(async move {
+            if let Some(retry_after) = limiter.check(&key) {
+                log::info!(
+                    "[RateLimit] 请求过于频繁 | rule={}, ip={:?}, retry_after={}s",
+                    rule.name,
+                    ip,
+                    retry_after
+                );
+                let response = too_many_requests(
+                    &format!("操作过于频繁，请 {} 秒后重试", retry_after),
+                    retry_after,
+                );
+                return Err(InternalError::from_response("请求过于频繁", response).into());
+            }
+
+            let res = service.call(req).await?;
+
+            let status = res.status();
+            let failed = status.is_client_error();
+            if rule.count_all || failed {
+                if let Some(delay) = limiter.record_failure(&key) {
+                    log::warn!(
+                        "[RateLimit] 开始退避 | rule={}, ip={:?}, status={}, delay={}s",
+                        rule.name,
+                        ip,
+                        status.as_u16(),
+                        delay
+                    );
+                    if let Some(pool) = pool {
+                        let details = serde_json::json!({
+                            "rule": rule.name,
+                            "path": rule.path,
+                            "retry_after": delay,
+                        });
+                        // 审计日志写入失败不影响请求
+                        actix_web::rt::spawn(async move {
+                            if let Err(e) = AuditLogService::log(
+                                &pool,
+                                None,
+                                AuditAction::RateLimited,
+                                Some("rate_limit"),
+                                None,
+                                Some(details),
+                                ip.as_deref(),
+                            )
+                            .await
+                            {
+                                log::warn!("[Audit] 记录限流日志失败 | error={}", e);
+                            }
+                        });
+                    }
+                }
+            }
+
+            Ok(res)
+        })
+    }
+}
+
+/// 获取客户端 IP，信任代理时优先使用 X-Forwarded-For / Forwarded
+fn client_ip(req: &ServiceRequest, trust_proxy: bool) -> Option<String> {
+    let ip = if trust_proxy {
+        req.connection_info()
+            .realip_remote_addr()
+            .map(|addr| addr.to_string())
+    } else {
+        req.peer_addr().map(|addr| addr.ip().to_string())
+    }?;
+    Some(strip_port(&ip))
+}
+
+/// 去掉地址中的端口（realip_remote_addr 可能带端口）
+fn strip_port(addr: &str) -> String {
+    if let Ok(socket) = addr.parse::<std::net::SocketAddr>() {
+        return socket.ip().to_string();
+    }
+    addr.trim_matches(|c| c == '[' || c == ']').to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::services::BackoffPolicy;
+    use actix_web::{http::StatusCode, test as actix_test, web, App, HttpResponse};
+    use std::time::Duration;
+
+    fn limiter(free_attempts: u32) -> Arc<RateLimiter> {
+        Arc::new(RateLimiter::new(
+            BackoffPolicy {
+                free_attempts,
+                base_delay_secs: 60,
+                max_delay_secs: 600,
+            },
+            Duration::from_secs(600),
+        ))
+    }
+
+    #[test]
+    fn test_strip_port() {
+        assert_eq!(strip_port("10.0.0.1:5000"), "10.0.0.1");
+        assert_eq!(strip_port("10.0.0.1"), "10.0.0.1");
+        assert_eq!(strip_port("[::1]:80"), "::1");
+        assert_eq!(strip_port("::1"), "::1");
+    }
+
+    #[actix_web::test]
+    async fn test_failures_trigger_backoff() {
+        let rules = vec![RateLimitRule::failures("login", Method::POST, "/login")];
+        let app = actix_test::init_service(
+            App::new()
+                .wrap(RateLimit::new(limiter(2), rules))
+                .route(
+                    "/login",
+                    web::post().to(|| async { HttpResponse::BadRequest().finish() }),
+                )
+                .route(
+                    "/other",
+                    web::post().to(|| async { HttpResponse::BadRequest().finish() }),
+                ),
+        )
+        .await;
+
+        for _ in 0..2 {
+            let req = actix_test::TestRequest::post().uri("/login").to_request();
+            let res = actix_test::call_service(&app, req).await;
+            assert_eq!(res.status(), StatusCode::BAD_REQUEST);
+        }
+
+        let req = actix_test::TestRequest::post().uri("/login").to_request();
+        let err = actix_test::try_call_service(&app, req).await.unwrap_err();
+        let res = err.error_response();
+        assert_eq!(res.status(), StatusCode::TOO_MANY_REQUESTS);
+        assert_eq!(res.headers().get("Retry-After").unwrap(), "60");
+
+        // 未配置规则的接口不受影响
+        let req = actix_test::TestRequest::post().uri("/other").to_request();
+        let res = actix_test::call_service(&app, req).await;
+        assert_eq!(res.status(), StatusCode::BAD_REQUEST);
+    }
+
+    #[actix_web::test]
+    async fn test_success_not_counted_for_failure_rule() {
+        let rules = vec![
+            RateLimitRule::failures("login", Method::POST, "/login"),
+            RateLimitRule::attempts("register", Method::POST, "/register"),
+        ];
+        let app = actix_test::init_service(
+            App::new()
+                .wrap(RateLimit::new(limiter(1), rules))
+                .route(
+                    "/login",
+                    web::post().to(|| async { HttpResponse::Ok().finish() }),
+                )
+                .route(
+                    "/register",
+                    web::post().to(|| async { HttpResponse::Ok().finish() }),
+                ),
+        )
+        .await;
+
+        for _ in 0..3 {
+            let req = actix_test::TestRequest::post().uri("/login").to_request();
+            let res = actix_test::call_service(&app, req).await;
+            assert_eq!(res.status(), StatusCode::OK);
+        }
+
+        // 注册接口成功请求也计数
+        let req = actix_test::TestRequest::post()
+            .uri("/register")
+            .to_request();
+        assert_eq!(
+            actix_test::call_service(&app, req).await.status(),
+            StatusCode::OK
+        );
+        let req = actix_test::TestRequest::post()
+            .uri("/register")
+            .to_request();
+        let err = actix_test::try_call_service(&app, req).await.unwrap_err();
+        assert_eq!(err.error_response().status(), StatusCode::TOO_MANY_REQUESTS);
+    }
+}
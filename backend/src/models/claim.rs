@@ -0,0 +1,335 @@
+use chrono::NaiveDateTime;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+/// 申领理由最大长度
+pub const MAX_CLAIM_REASON_LENGTH: usize = 1000;
+/// 单次申领最多可附带的证明图片数量
+pub const MAX_CLAIM_PROOF_IMAGES: usize = 9;
+
+/// 申领实体（对应数据库 claims 表）
+#[derive(Debug, Clone, sqlx::FromRow)]
+pub struct Claim {
+    pub id: Uuid,
+    pub resource_id: Uuid,
+    pub applicant_id: Uuid,
+    pub claim_type: Option<String>,
+    pub reason: String,
+    pub proof_files: Option<serde_json::Value>,
+    pub status: Option<String>,
+    pub reviewer_id: Option<Uuid>,
+    pub reviewed_at: Option<NaiveDateTime>,
+    pub review_comment: Option<String>,
+    pub created_at: NaiveDateTime,
+}
+
+impl Claim {
+    /// 从 proof_files（JSON 数组）中解析证明图片 ID
+    pub fn proof_image_ids(&self) -> Vec<Uuid> {
+        self.proof_files
+            .as_ref()
+            .and_then(|v| v.as_array())
+            .map(|arr| {
+                arr.iter()
+                    .filter_map(|item| item.as_str())
+                    .filter_map(|s| Uuid::parse_str(s).ok())
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+}
+
+/// 申领类型
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ClaimType {
+    /// 申领为资源作者
+    Author,
+}
+
+impl ClaimType {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ClaimType::Author => "author",
+        }
+    }
+
+    pub fn from_str(s: &str) -> Option<Self> {
+        match s {
+            "author" => Some(ClaimType::Author),
+            _ => None,
+        }
+    }
+}
+
+/// 申领状态
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ClaimStatus {
+    Pending,
+    Approved,
+    Rejected,
+}
+
+impl ClaimStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ClaimStatus::Pending => "pending",
+            ClaimStatus::Approved => "approved",
+            ClaimStatus::Rejected => "rejected",
+        }
+    }
+
+    pub fn from_str(s: &str) -> Option<Self> {
+        match s {
+            "pending" => Some(ClaimStatus::Pending),
+            "approved" => Some(ClaimStatus::Approved),
+            "rejected" => Some(ClaimStatus::Rejected),
+            _ => None,
+        }
+    }
+}
+
+/// 提交申领请求
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CreateClaimRequest {
+    /// 申领类型，默认 author
+    pub claim_type: Option<String>,
+    pub reason: String,
+    /// 证明图片 ID（来自图床）
+    #[serde(default)]
+    pub proof_image_ids: Vec<Uuid>,
+}
+
+impl CreateClaimRequest {
+    /// 验证请求数据
+    pub fn validate(&self) -> Result<(), String> {
+        if let Some(ref claim_type) = self.claim_type {
+            if ClaimType::from_str(claim_type).is_none() {
+                return Err(format!("无效的申领类型: {}", claim_type));
+            }
+        }
+
+        let reason = self.reason.trim();
+        if reason.is_empty() {
+            return Err("申领理由不能为空".to_string());
+        }
+        if reason.chars().count() > MAX_CLAIM_REASON_LENGTH {
+            return Err(format!("申领理由不能超过{}个字符", MAX_CLAIM_REASON_LENGTH));
+        }
+
+        if self.proof_image_ids.is_empty() {
+            return Err("请至少上传一张证明图片".to_string());
+        }
+        if self.proof_image_ids.len() > MAX_CLAIM_PROOF_IMAGES {
+            return Err(format!("证明图片不能超过{}张", MAX_CLAIM_PROOF_IMAGES));
+        }
+
+        Ok(())
+    }
+
+    /// 获取申领类型（未指定时默认为作者申领）
+    pub fn claim_type(&self) -> ClaimType {
+        self.claim_type
+            .as_deref()
+            .and_then(ClaimType::from_str)
+            .unwrap_or(ClaimType::Author)
+    }
+}
+
+/// 审核申领请求
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ReviewClaimRequest {
+    pub status: String, // approved, rejected
+    pub comment: Option<String>,
+}
+
+/// 申领列表查询
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ClaimListQuery {
+    pub page: Option<i64>,
+    pub per_page: Option<i64>,
+    pub status: Option<String>,
+}
+
+/// 申领证明图片
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ClaimProofImage {
+    pub id: Uuid,
+    pub url: String,
+}
+
+/// 申领响应
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ClaimResponse {
+    pub id: Uuid,
+    pub resource_id: Uuid,
+    pub resource_title: Option<String>,
+    pub applicant_id: Uuid,
+    pub applicant_name: Option<String>,
+    pub claim_type: String,
+    pub reason: String,
+    pub proof_images: Vec<ClaimProofImage>,
+    pub status: String,
+    pub reviewer_id: Option<Uuid>,
+    pub review_comment: Option<String>,
+    pub reviewed_at: Option<String>,
+    pub created_at: String,
+}
+
+/// 申领列表响应
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ClaimListResponse {
+    pub claims: Vec<ClaimResponse>,
+    pub total: i64,
+    pub page: i64,
+    pub per_page: i64,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn make_request(reason: &str, images: usize) -> CreateClaimRequest {
+        CreateClaimRequest {
+            claim_type: None,
+            reason: reason.to_string(),
+            proof_image_ids: (0..images).map(|_| Uuid::new_v4()).collect(),
+        }
+    }
+
+    mod create_claim_request_tests {
+        use super::*;
+
+        #[test]
+        fn test_create_claim_request_deserialization() {
+            let id = Uuid::new_v4();
+            let json = format!(
+                r#"{{"claimType": "author", "reason": "我是原作者", "proofImageIds": ["{}"]}}"#,
+                id
+            );
+            let req: CreateClaimRequest = serde_json::from_str(&json).unwrap();
+            assert_eq!(req.claim_type.as_deref(), Some("author"));
+            assert_eq!(req.proof_image_ids, vec![id]);
+            assert!(req.validate().is_ok());
+        }
+
+        #[test]
+        fn test_create_claim_request_defaults() {
+            let json = r#"{"reason": "我是原作者"}"#;
+            let req: CreateClaimRequest = serde_json::from_str(json).unwrap();
+            assert!(req.proof_image_ids.is_empty());
+            assert_eq!(req.claim_type(), ClaimType::Author);
+        }
+
+        #[test]
+        fn test_validate_empty_reason() {
+            assert!(make_request("   ", 1).validate().is_err());
+        }
+
+        #[test]
+        fn test_validate_reason_too_long() {
+            let reason = "申".repeat(MAX_CLAIM_REASON_LENGTH + 1);
+            assert!(make_request(&reason, 1).validate().is_err());
+
+            let reason = "申".repeat(MAX_CLAIM_REASON_LENGTH);
+            assert!(make_request(&reason, 1).validate().is_ok());
+        }
+
+        #[test]
+        fn test_validate_proof_images_count() {
+            assert!(make_request("理由", 0).validate().is_err());
+            assert!(make_request("理由", MAX_CLAIM_PROOF_IMAGES)
+                .validate()
+                .is_ok());
+            assert!(make_request("理由", MAX_CLAIM_PROOF_IMAGES + 1)
+                .validate()
+                .is_err());
+        }
+
+        #[test]
+        fn test_validate_invalid_claim_type() {
+            let mut req = make_request("理由", 1);
+            req.claim_type = Some("owner".to_string());
+            assert!(req.validate().is_err());
+        }
+    }
+
+    mod claim_status_tests {
+        use super::*;
+
+        #[test]
+        fn test_claim_status_roundtrip() {
+            for status in [
+                ClaimStatus::Pending,
+                ClaimStatus::Approved,
+                ClaimStatus::Rejected,
+            ] {
+                assert_eq!(ClaimStatus::from_str(status.as_str()), Some(status));
+            }
+            assert_eq!(ClaimStatus::from_str("unknown"), None);
+        }
+    }
+
+    mod claim_tests {
+        use super::*;
+
+        #[test]
+        fn test_proof_image_ids_parsing() {
+            let id = Uuid::new_v4();
+            let claim = Claim {
+                id: Uuid::new_v4(),
+                resource_id: Uuid::new_v4(),
+                applicant_id: Uuid::new_v4(),
+                claim_type: Some("author".to_string()),
+                reason: "理由".to_string(),
+                proof_files: Some(serde_json::json!([id.to_string(), "not-a-uuid", 1])),
+                status: Some("pending".to_string()),
+                reviewer_id: None,
+                reviewed_at: None,
+                review_comment: None,
+                created_at: chrono::Utc::now().naive_utc(),
+            };
+            assert_eq!(claim.proof_image_ids(), vec![id]);
+        }
+    }
+
+    mod claim_response_tests {
+        use super::*;
+
+        #[test]
+        fn test_claim_list_response_serialization() {
+            let response = ClaimListResponse {
+                claims: vec![ClaimResponse {
+                    id: Uuid::new_v4(),
+                    resource_id: Uuid::new_v4(),
+                    resource_title: Some("高数期末".to_string()),
+                    applicant_id: Uuid::new_v4(),
+                    applicant_name: Some("test_user".to_string()),
+                    claim_type: "author".to_string(),
+                    reason: "理由".to_string(),
+                    proof_images: vec![],
+                    status: "pending".to_string(),
+                    reviewer_id: None,
+                    review_comment: None,
+                    reviewed_at: None,
+                    created_at: "2024-01-01T00:00:00.000Z".to_string(),
+                }],
+                total: 1,
+                page: 1,
+                per_page: 20,
+            };
+
+            let json = serde_json::to_string(&response).unwrap();
+            assert!(json.contains("resourceTitle"));
+            assert!(json.contains("applicantName"));
+            assert!(json.contains("proofImages"));
+            assert!(json.contains("reviewComment"));
+            assert!(json.contains("perPage"));
+        }
+    }
+}
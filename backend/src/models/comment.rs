@@ -2,14 +2,31 @@ use chrono::NaiveDateTime;
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
+/// 单条评论最多提醒的用户数
+pub const MAX_MENTIONS_PER_COMMENT: usize = 10;
+/// 评论列表中每条顶层评论默认预览的回复数
+pub const DEFAULT_REPLY_PREVIEW: i64 = 3;
+/// 评论列表中每条顶层评论最多预览的回复数
+pub const MAX_REPLY_PREVIEW: i64 = 10;
+
 /// 评论实体
 #[derive(Debug, sqlx::FromRow, Serialize)]
 pub struct Comment {
     pub id: Uuid,
     pub resource_id: Uuid,
     pub user_id: Uuid,
+    /// 直接回复的评论，顶层评论为 None
+    pub parent_id: Option<Uuid>,
+    /// 所属楼层的顶层评论，顶层评论为 None
+    pub root_id: Option<Uuid>,
     pub content: String,
     pub audit_status: String,
+    pub is_deleted: bool,
+    pub edited_at: Option<NaiveDateTime>,
+    pub upvotes: i32,
+    pub downvotes: i32,
+    /// 资源上传者标记为有帮助（置顶）
+    pub is_helpful: bool,
     pub created_at: NaiveDateTime,
     pub updated_at: NaiveDateTime,
 }
@@ -19,6 +36,15 @@ pub struct Comment {
 #[serde(rename_all = "camelCase")]
 pub struct CreateCommentRequest {
     pub content: String,
+    /// 回复的评论 ID，为空时发表顶层评论
+    pub parent_id: Option<Uuid>,
+}
+
+/// 编辑评论请求
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UpdateCommentRequest {
+    pub content: String,
 }
 
 /// 评论响应（包含用户信息）
@@ -30,8 +56,24 @@ pub struct CommentResponse {
     pub user_id: Uuid,
     pub user_name: String,
     pub user_avatar: Option<String>,
+    /// 已删除的评论内容为空
     pub content: String,
+    pub parent_id: Option<Uuid>,
+    pub root_id: Option<Uuid>,
+    /// 被回复评论的作者（仅回复）
+    pub reply_to_user_name: Option<String>,
+    pub is_deleted: bool,
+    pub edited_at: Option<String>,
+    pub upvotes: i32,
+    pub downvotes: i32,
+    /// 当前用户的投票（1 赞同，-1 反对），未登录或未投票为 None
+    pub my_vote: Option<i16>,
+    pub is_helpful: bool,
     pub created_at: String, // 使用 String 类型，在构造时格式化为 ISO 8601 格式
+    /// 楼层内的回复总数（仅顶层评论）
+    pub reply_count: i64,
+    /// 楼层内最早的几条回复（仅顶层评论），更多回复通过回复列表接口分页获取
+    pub replies: Vec<CommentResponse>,
 }
 
 /// 评论列表查询
@@ -40,6 +82,70 @@ pub struct CommentResponse {
 pub struct CommentListQuery {
     pub page: Option<i64>,
     pub per_page: Option<i64>,
+    /// 每条顶层评论预览的回复数
+    pub replies_per_comment: Option<i64>,
+    /// 顶层评论排序方式：newest（默认）、oldest、top、pinned
+    pub sort: Option<String>,
+}
+
+/// 评论排序方式
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CommentSortMode {
+    /// 最新发表在前（默认）
+    Newest,
+    /// 最早发表在前
+    Oldest,
+    /// 得分（赞同减反对）最高在前
+    Top,
+    /// 有帮助的评论置顶，其余最新在前
+    PinnedFirst,
+}
+
+impl CommentSortMode {
+    #[allow(dead_code)]
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            CommentSortMode::Newest => "newest",
+            CommentSortMode::Oldest => "oldest",
+            CommentSortMode::Top => "top",
+            CommentSortMode::PinnedFirst => "pinned",
+        }
+    }
+
+    pub fn from_str(s: &str) -> Option<Self> {
+        match s {
+            "newest" => Some(CommentSortMode::Newest),
+            "oldest" => Some(CommentSortMode::Oldest),
+            "top" => Some(CommentSortMode::Top),
+            "pinned" => Some(CommentSortMode::PinnedFirst),
+            _ => None,
+        }
+    }
+}
+
+/// 评论投票请求
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct VoteCommentRequest {
+    /// 1 赞同，-1 反对，0 取消投票
+    pub value: i16,
+}
+
+/// 评论投票响应
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CommentVoteResponse {
+    pub comment_id: Uuid,
+    pub my_vote: Option<i16>,
+    pub upvotes: i32,
+    pub downvotes: i32,
+}
+
+/// 标记有帮助评论请求
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MarkHelpfulRequest {
+    pub helpful: bool,
 }
 
 /// 评论列表响应
@@ -52,6 +158,54 @@ pub struct CommentListResponse {
     pub per_page: i64,
 }
 
+/// 评论编辑历史项（编辑前的内容）
+#[derive(Debug, Serialize, sqlx::FromRow)]
+#[serde(rename_all = "camelCase")]
+pub struct CommentEditItem {
+    pub id: Uuid,
+    pub content: String,
+    /// 被替换的时间
+    pub edited_at: NaiveDateTime,
+}
+
+/// 评论编辑历史响应
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CommentEditHistoryResponse {
+    pub comment_id: Uuid,
+    pub edits: Vec<CommentEditItem>,
+}
+
+/// 提取评论中 @ 提醒的用户名（去重，保持出现顺序）
+///
+/// 用户名规则与注册时一致：3-50 个字母、数字或下划线；@ 前为字母数字时（如邮箱）不视为提醒
+pub fn extract_mentions(content: &str) -> Vec<String> {
+    let is_name_char = |c: char| c.is_ascii_alphanumeric() || c == '_';
+    let chars: Vec<char> = content.chars().collect();
+    let mut mentions: Vec<String> = Vec::new();
+
+    let mut i = 0;
+    while i < chars.len() {
+        if chars[i] != '@' || (i > 0 && is_name_char(chars[i - 1])) {
+            i += 1;
+            continue;
+        }
+        let name: String = chars[i + 1..]
+            .iter()
+            .take_while(|c| is_name_char(**c))
+            .collect();
+        i += 1 + name.chars().count();
+
+        if (3..=50).contains(&name.len()) && !mentions.contains(&name) {
+            mentions.push(name);
+            if mentions.len() >= MAX_MENTIONS_PER_COMMENT {
+                break;
+            }
+        }
+    }
+    mentions
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -85,6 +239,26 @@ mod tests {
             assert_eq!(query.page, Some(5));
             assert_eq!(query.per_page, None);
         }
+
+        #[test]
+        fn test_comment_sort_mode() {
+            let json = r#"{"sort": "top"}"#;
+            let query: CommentListQuery = serde_json::from_str(json).unwrap();
+            assert_eq!(
+                query.sort.as_deref().and_then(CommentSortMode::from_str),
+                Some(CommentSortMode::Top)
+            );
+
+            for mode in [
+                CommentSortMode::Newest,
+                CommentSortMode::Oldest,
+                CommentSortMode::Top,
+                CommentSortMode::PinnedFirst,
+            ] {
+                assert_eq!(CommentSortMode::from_str(mode.as_str()), Some(mode));
+            }
+            assert_eq!(CommentSortMode::from_str("hot"), None);
+        }
     }
 
     mod create_comment_request_tests {
@@ -95,6 +269,15 @@ mod tests {
             let json = r#"{"content": "This is a test comment"}"#;
             let req: CreateCommentRequest = serde_json::from_str(json).unwrap();
             assert_eq!(req.content, "This is a test comment");
+            assert!(req.parent_id.is_none());
+        }
+
+        #[test]
+        fn test_create_comment_request_with_parent() {
+            let parent_id = Uuid::new_v4();
+            let json = format!(r#"{{"content": "reply", "parentId": "{}"}}"#, parent_id);
+            let req: CreateCommentRequest = serde_json::from_str(&json).unwrap();
+            assert_eq!(req.parent_id, Some(parent_id));
         }
 
         #[test]
@@ -129,7 +312,18 @@ mod tests {
                 user_name: "test_user".to_string(),
                 user_avatar: Some("http://example.com/avatar.png".to_string()),
                 content: "Test content".to_string(),
+                parent_id: None,
+                root_id: None,
+                reply_to_user_name: None,
+                is_deleted: false,
+                edited_at: None,
+                upvotes: 0,
+                downvotes: 0,
+                my_vote: None,
+                is_helpful: false,
                 created_at: "2024-01-01T00:00:00Z".to_string(),
+                reply_count: 0,
+                replies: vec![],
             };
 
             let json = serde_json::to_string(&response).unwrap();
@@ -154,7 +348,18 @@ mod tests {
                 user_name: "test_user".to_string(),
                 user_avatar: None,
                 content: "Test content".to_string(),
+                parent_id: None,
+                root_id: None,
+                reply_to_user_name: None,
+                is_deleted: false,
+                edited_at: None,
+                upvotes: 0,
+                downvotes: 0,
+                my_vote: None,
+                is_helpful: false,
                 created_at: "2024-01-01T00:00:00Z".to_string(),
+                reply_count: 0,
+                replies: vec![],
             };
 
             let json = serde_json::to_string(&response).unwrap();
@@ -178,7 +383,18 @@ mod tests {
                     user_name: "test_user".to_string(),
                     user_avatar: None,
                     content: "Test".to_string(),
+                    parent_id: None,
+                    root_id: None,
+                    reply_to_user_name: None,
+                    is_deleted: false,
+                    edited_at: None,
+                    upvotes: 0,
+                    downvotes: 0,
+                    my_vote: None,
+                    is_helpful: false,
                     created_at: "2024-01-01T00:00:00Z".to_string(),
+                    reply_count: 0,
+                    replies: vec![],
                 }],
                 total: 1,
                 page: 1,
@@ -206,4 +422,37 @@ mod tests {
             assert!(json.contains("\"total\":0"));
         }
     }
+
+    mod mention_tests {
+        use super::*;
+
+        #[test]
+        fn test_extract_mentions() {
+            assert_eq!(
+                extract_mentions("@alice 说得对，@bob_2 也看看"),
+                vec!["alice", "bob_2"]
+            );
+            assert_eq!(extract_mentions("@alice @alice"), vec!["alice"]);
+            assert!(extract_mentions("没有提醒").is_empty());
+        }
+
+        #[test]
+        fn test_extract_mentions_ignores_invalid() {
+            // 邮箱地址、过短或过长的用户名不视为提醒
+            assert!(extract_mentions("联系 me@example.com").is_empty());
+            assert!(extract_mentions("@ab @").is_empty());
+            assert!(extract_mentions(&format!("@{}", "a".repeat(51))).is_empty());
+            // 用户名后紧跟中文标点
+            assert_eq!(extract_mentions("（@carol）"), vec!["carol"]);
+        }
+
+        #[test]
+        fn test_extract_mentions_limit() {
+            let content = (0..MAX_MENTIONS_PER_COMMENT + 5)
+                .map(|i| format!("@user{:03}", i))
+                .collect::<Vec<_>>()
+                .join(" ");
+            assert_eq!(extract_mentions(&content).len(), MAX_MENTIONS_PER_COMMENT);
+        }
+    }
 }
@@ -178,3 +178,135 @@ pub struct FailedCourseDeleteItem {
     pub sn: i64,
     pub reason: String,
 }
+
+/// 课程关联教师（由课程资源关联的教师推导）
+#[derive(Debug, Serialize, FromRow)]
+#[serde(rename_all = "camelCase")]
+pub struct CourseTeacherInfo {
+    pub sn: i64,
+    pub name: String,
+    pub department: Option<String>,
+    /// 该教师在本课程下关联的资源数
+    pub resource_count: i64,
+}
+
+/// 课程资源分类统计
+#[derive(Debug, Serialize, FromRow)]
+#[serde(rename_all = "camelCase")]
+pub struct CourseCategoryCount {
+    pub category: String,
+    pub count: i64,
+}
+
+/// 课程资源评分汇总（按评分次数加权的各维度平均分）
+#[derive(Debug, Serialize, FromRow)]
+#[serde(rename_all = "camelCase")]
+pub struct CourseRatingSummary {
+    pub avg_difficulty: Option<f64>,
+    pub avg_overall_quality: Option<f64>,
+    pub avg_answer_quality: Option<f64>,
+    pub avg_format_quality: Option<f64>,
+    pub avg_detail_level: Option<f64>,
+    /// 评分总数
+    pub rating_count: i64,
+}
+
+/// 课程资源列表项
+#[derive(Debug, Serialize, FromRow)]
+#[serde(rename_all = "camelCase")]
+pub struct CourseResourceItem {
+    pub id: Uuid,
+    pub title: String,
+    pub resource_type: String,
+    pub category: String,
+    pub uploader_name: Option<String>,
+    pub downloads: i32,
+    pub views: i32,
+    pub likes: i32,
+    pub avg_overall_quality: Option<f64>,
+    pub created_at: NaiveDateTime,
+}
+
+/// 课程详情响应 DTO
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CourseDetailResponse {
+    pub sn: i64,
+    pub name: String,
+    pub semester: Option<String>,
+    pub credits: Option<f64>,
+    pub teachers: Vec<CourseTeacherInfo>,
+    /// 已审核通过的资源总数
+    pub resource_count: i64,
+    pub category_counts: Vec<CourseCategoryCount>,
+    pub ratings: CourseRatingSummary,
+    /// 热门资源（按下载量和点赞数排序）
+    pub top_resources: Vec<CourseResourceItem>,
+    /// 最近上传的资源
+    pub recent_resources: Vec<CourseResourceItem>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn resource_item(title: &str) -> CourseResourceItem {
+        CourseResourceItem {
+            id: Uuid::nil(),
+            title: title.to_string(),
+            resource_type: "pdf".to_string(),
+            category: "past_paper".to_string(),
+            uploader_name: Some("alice".to_string()),
+            downloads: 12,
+            views: 30,
+            likes: 4,
+            avg_overall_quality: Some(8.5),
+            created_at: NaiveDateTime::default(),
+        }
+    }
+
+    #[test]
+    fn test_course_detail_serialization() {
+        let detail = CourseDetailResponse {
+            sn: 2,
+            name: "线性代数".to_string(),
+            semester: Some("2024秋".to_string()),
+            credits: Some(3.0),
+            teachers: vec![CourseTeacherInfo {
+                sn: 1,
+                name: "王老师".to_string(),
+                department: None,
+                resource_count: 3,
+            }],
+            resource_count: 3,
+            category_counts: vec![CourseCategoryCount {
+                category: "past_paper".to_string(),
+                count: 3,
+            }],
+            ratings: CourseRatingSummary {
+                avg_difficulty: Some(5.0),
+                avg_overall_quality: None,
+                avg_answer_quality: None,
+                avg_format_quality: None,
+                avg_detail_level: None,
+                rating_count: 2,
+            },
+            top_resources: vec![resource_item("期末试卷")],
+            recent_resources: Vec::new(),
+        };
+
+        let value = serde_json::to_value(&detail).unwrap();
+        assert_eq!(value["resourceCount"], 3);
+        assert_eq!(value["teachers"][0]["resourceCount"], 3);
+        assert_eq!(value["categoryCounts"][0]["category"], "past_paper");
+        assert_eq!(value["ratings"]["avgDifficulty"], 5.0);
+        assert_eq!(
+            value["ratings"]["avgOverallQuality"],
+            serde_json::Value::Null
+        );
+        assert_eq!(value["ratings"]["ratingCount"], 2);
+        assert_eq!(value["topResources"][0]["uploaderName"], "alice");
+        assert_eq!(value["topResources"][0]["avgOverallQuality"], 8.5);
+        assert_eq!(value["recentResources"], serde_json::json!([]));
+    }
+}
@@ -0,0 +1,120 @@
+use serde::Deserialize;
+use uuid::Uuid;
+
+/// 邮件令牌用途
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EmailTokenPurpose {
+    /// 验证邮箱
+    VerifyEmail,
+    /// 重置密码
+    ResetPassword,
+}
+
+impl EmailTokenPurpose {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            EmailTokenPurpose::VerifyEmail => "verify_email",
+            EmailTokenPurpose::ResetPassword => "reset_password",
+        }
+    }
+
+    #[allow(dead_code)]
+    pub fn from_str(s: &str) -> Option<Self> {
+        match s {
+            "verify_email" => Some(EmailTokenPurpose::VerifyEmail),
+            "reset_password" => Some(EmailTokenPurpose::ResetPassword),
+            _ => None,
+        }
+    }
+}
+
+/// 已使用的邮件令牌信息
+#[derive(Debug, Clone, sqlx::FromRow)]
+pub struct EmailTokenUse {
+    pub user_id: Uuid,
+    /// 令牌发送到的邮箱
+    pub email: String,
+}
+
+/// 验证邮箱请求 DTO
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct VerifyEmailRequest {
+    pub token: String,
+}
+
+/// 找回密码请求 DTO
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ForgotPasswordRequest {
+    pub email: String,
+}
+
+impl ForgotPasswordRequest {
+    /// 验证找回密码请求
+    pub fn validate(&self) -> Result<(), String> {
+        if !self.email.contains('@') {
+            return Err("邮箱格式不正确".to_string());
+        }
+        Ok(())
+    }
+}
+
+/// 重置密码请求 DTO
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ResetPasswordRequest {
+    pub token: String,
+    pub new_password: String,
+}
+
+impl ResetPasswordRequest {
+    /// 验证重置密码请求
+    pub fn validate(&self) -> Result<(), String> {
+        if self.token.is_empty() {
+            return Err("重置链接无效".to_string());
+        }
+        if self.new_password.len() < 6 {
+            return Err("新密码长度至少为6个字符".to_string());
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_email_token_purpose_round_trip() {
+        for purpose in [
+            EmailTokenPurpose::VerifyEmail,
+            EmailTokenPurpose::ResetPassword,
+        ] {
+            assert_eq!(EmailTokenPurpose::from_str(purpose.as_str()), Some(purpose));
+        }
+        assert_eq!(EmailTokenPurpose::from_str("unknown"), None);
+    }
+
+    #[test]
+    fn test_reset_password_request_validation() {
+        let req = ResetPasswordRequest {
+            token: "token".to_string(),
+            new_password: "12345".to_string(),
+        };
+        assert!(req.validate().is_err());
+
+        let req = ResetPasswordRequest {
+            token: "token".to_string(),
+            new_password: "123456".to_string(),
+        };
+        assert!(req.validate().is_ok());
+    }
+
+    #[test]
+    fn test_forgot_password_request_validation() {
+        let req: ForgotPasswordRequest =
+            serde_json::from_str(r#"{"email": "invalid-email"}"#).unwrap();
+        assert!(req.validate().is_err());
+    }
+}
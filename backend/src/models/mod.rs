@@ -1,22 +1,37 @@
 // 数据模型层模块
 
+pub mod claim;
 pub mod comment;
 pub mod course;
+pub mod email_token;
 pub mod favorite;
 pub mod image;
 pub mod like;
 pub mod notification;
+pub mod oidc;
+pub mod pack_job;
+pub mod permission;
 pub mod rating;
+pub mod report;
 pub mod resource;
+pub mod resource_version;
+pub mod session;
+pub mod storage_migration;
 pub mod teacher;
+pub mod totp;
 pub mod user;
+pub mod verification;
 
 // 模型导出供其他模块使用
 #[allow(unused_imports)]
+pub use claim::*;
+#[allow(unused_imports)]
 pub use comment::*;
 #[allow(unused_imports)]
 pub use course::*;
 #[allow(unused_imports)]
+pub use email_token::*;
+#[allow(unused_imports)]
 pub use favorite::*;
 #[allow(unused_imports)]
 pub use image::*;
@@ -25,10 +40,28 @@ pub use like::*;
 #[allow(unused_imports)]
 pub use notification::*;
 #[allow(unused_imports)]
+pub use oidc::*;
+#[allow(unused_imports)]
+pub use pack_job::*;
+#[allow(unused_imports)]
+pub use permission::*;
+#[allow(unused_imports)]
 pub use rating::*;
 #[allow(unused_imports)]
+pub use report::*;
+#[allow(unused_imports)]
 pub use resource::*;
 #[allow(unused_imports)]
+pub use resource_version::*;
+#[allow(unused_imports)]
+pub use session::*;
+#[allow(unused_imports)]
+pub use storage_migration::*;
+#[allow(unused_imports)]
 pub use teacher::*;
 #[allow(unused_imports)]
+pub use totp::*;
+#[allow(unused_imports)]
 pub use user::*;
+#[allow(unused_imports)]
+pub use verification::*;
@@ -20,9 +20,9 @@ pub struct Notification {
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 #[allow(dead_code)]
 pub enum NotificationType {
-    /// 审核结果（预留）
+    /// 审核结果
     AuditResult,
-    /// 申领结果（预留）
+    /// 申领结果
     ClaimResult,
     /// 评论回复
     CommentReply,
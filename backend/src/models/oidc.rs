@@ -0,0 +1,82 @@
+use chrono::NaiveDateTime;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+/// 发起 OIDC 登录的查询参数
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OidcAuthorizeQuery {
+    /// 登录完成后跳转的前端路径，只允许站内路径
+    pub redirect: Option<String>,
+    /// 为 true 时将外部身份绑定到当前登录的账号
+    #[serde(default)]
+    pub link: bool,
+}
+
+/// 身份提供方回调的查询参数
+#[derive(Debug, Deserialize)]
+pub struct OidcCallbackQuery {
+    pub code: Option<String>,
+    pub state: Option<String>,
+    /// 用户拒绝授权等情况下身份提供方返回的错误
+    pub error: Option<String>,
+    pub error_description: Option<String>,
+}
+
+/// 发起登录时保存的状态，回调时取出并删除
+#[derive(Debug, sqlx::FromRow)]
+pub struct OidcLoginState {
+    pub code_verifier: String,
+    pub nonce: String,
+    pub link_user_id: Option<Uuid>,
+    pub redirect_path: String,
+}
+
+/// 外部身份绑定记录
+#[derive(Debug, sqlx::FromRow)]
+pub struct UserIdentity {
+    pub id: Uuid,
+    pub user_id: Uuid,
+    pub provider: String,
+    pub subject: String,
+    pub email: Option<String>,
+    pub created_at: NaiveDateTime,
+    pub last_login_at: Option<NaiveDateTime>,
+}
+
+/// 外部身份绑定响应
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UserIdentityResponse {
+    pub id: Uuid,
+    pub provider: String,
+    /// 身份提供方的显示名称
+    pub provider_name: String,
+    pub subject: String,
+    pub email: Option<String>,
+    pub created_at: NaiveDateTime,
+    pub last_login_at: Option<NaiveDateTime>,
+}
+
+impl UserIdentityResponse {
+    pub fn from_identity(identity: UserIdentity, provider_name: &str) -> Self {
+        Self {
+            id: identity.id,
+            provider: identity.provider,
+            provider_name: provider_name.to_string(),
+            subject: identity.subject,
+            email: identity.email,
+            created_at: identity.created_at,
+            last_login_at: identity.last_login_at,
+        }
+    }
+}
+
+/// 外部身份列表响应
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UserIdentityListResponse {
+    pub identities: Vec<UserIdentityResponse>,
+    /// 账号是否设置了密码，未设置密码时不能解除最后一个外部身份
+    pub has_password: bool,
+}
@@ -0,0 +1,146 @@
+use chrono::NaiveDateTime;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+/// 打包下载任务实体（对应数据库 pack_jobs 表）
+#[derive(Debug, Clone, sqlx::FromRow)]
+pub struct PackJob {
+    pub id: Uuid,
+    pub user_id: Uuid,
+    pub favorite_id: Option<Uuid>,
+    pub favorite_name: String,
+    pub status: String,
+    pub total_files: i32,
+    pub processed_files: i32,
+    pub skipped_files: i32,
+    pub bytes_written: i64,
+    pub storage_key: Option<String>,
+    pub storage_type: Option<String>,
+    pub file_name: Option<String>,
+    pub error_message: Option<String>,
+    pub completed_at: Option<NaiveDateTime>,
+    pub expires_at: Option<NaiveDateTime>,
+    pub created_at: NaiveDateTime,
+}
+
+/// 打包任务状态
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PackJobStatus {
+    /// 排队等待执行
+    Pending,
+    /// 打包中
+    Running,
+    /// 已完成，可下载
+    Completed,
+    Failed,
+    /// 文件已过期清理
+    Expired,
+}
+
+impl PackJobStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            PackJobStatus::Pending => "pending",
+            PackJobStatus::Running => "running",
+            PackJobStatus::Completed => "completed",
+            PackJobStatus::Failed => "failed",
+            PackJobStatus::Expired => "expired",
+        }
+    }
+
+    pub fn from_str(s: &str) -> Option<Self> {
+        match s {
+            "pending" => Some(PackJobStatus::Pending),
+            "running" => Some(PackJobStatus::Running),
+            "completed" => Some(PackJobStatus::Completed),
+            "failed" => Some(PackJobStatus::Failed),
+            "expired" => Some(PackJobStatus::Expired),
+            _ => None,
+        }
+    }
+}
+
+/// 创建打包任务响应
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CreatePackJobResponse {
+    pub job_id: Uuid,
+    pub status: String,
+    pub total_files: i32,
+}
+
+impl From<&PackJob> for CreatePackJobResponse {
+    fn from(job: &PackJob) -> Self {
+        CreatePackJobResponse {
+            job_id: job.id,
+            status: job.status.clone(),
+            total_files: job.total_files,
+        }
+    }
+}
+
+/// 打包任务响应
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PackJobResponse {
+    pub id: Uuid,
+    pub favorite_id: Option<Uuid>,
+    pub favorite_name: String,
+    pub status: String,
+    pub total_files: i32,
+    /// 已处理（打包或跳过）的文件数
+    pub processed_files: i32,
+    pub skipped_files: i32,
+    /// 已写出的 ZIP 字节数
+    pub bytes_written: i64,
+    /// 下载文件名（完成后返回）
+    pub file_name: Option<String>,
+    /// 带签名的下载地址（完成后返回，过期前有效）
+    pub download_url: Option<String>,
+    pub error_message: Option<String>,
+    pub expires_at: Option<String>,
+    pub completed_at: Option<String>,
+    pub created_at: String,
+}
+
+/// 打包任务列表响应
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PackJobListResponse {
+    pub jobs: Vec<PackJobResponse>,
+}
+
+/// 打包文件下载查询参数（签名链接）
+#[derive(Debug, Deserialize)]
+pub struct PackDownloadQuery {
+    /// 链接过期时间（Unix 时间戳，秒）
+    pub expires: i64,
+    pub signature: String,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pack_job_status_round_trip() {
+        for status in [
+            PackJobStatus::Pending,
+            PackJobStatus::Running,
+            PackJobStatus::Completed,
+            PackJobStatus::Failed,
+            PackJobStatus::Expired,
+        ] {
+            assert_eq!(PackJobStatus::from_str(status.as_str()), Some(status));
+        }
+        assert_eq!(PackJobStatus::from_str("unknown"), None);
+    }
+
+    #[test]
+    fn test_pack_download_query_deserialization() {
+        let query: PackDownloadQuery =
+            serde_json::from_str(r#"{"expires": 1700000000, "signature": "abc"}"#).unwrap();
+        assert_eq!(query.expires, 1700000000);
+        assert_eq!(query.signature, "abc");
+    }
+}
@@ -0,0 +1,418 @@
+use chrono::NaiveDateTime;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use uuid::Uuid;
+
+/// 内置管理员角色名，对应 users.role = 'admin'，不能作为自定义角色名
+pub const RESERVED_ROLE_NAME: &str = "admin";
+
+/// 管理后台权限
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Permission {
+    /// 查看仪表盘和统计数据
+    DashboardView,
+    /// 查看用户列表
+    UserRead,
+    /// 启用/禁用用户
+    UserManage,
+    /// 查看用户实名信息
+    UserRealInfoRead,
+    /// 审核实名认证申请
+    VerificationReview,
+    /// 审核资源
+    ResourceAudit,
+    /// 管理资源（查看全部资源、删除、重新计算哈希）
+    ResourceManage,
+    /// 审核评论
+    CommentAudit,
+    /// 删除评论
+    CommentDelete,
+    /// 发送系统通知
+    NotificationSend,
+    /// 查看审计日志
+    AuditLogRead,
+    /// 管理授课教师
+    TeacherManage,
+    /// 管理课程
+    CourseManage,
+    /// 处理资源申领
+    ClaimReview,
+    /// 处理用户举报
+    ReportReview,
+    /// 存储迁移
+    StorageMigrate,
+    /// 管理角色和用户角色分配
+    RoleManage,
+}
+
+impl Permission {
+    /// 全部权限，按管理后台菜单顺序排列
+    pub const ALL: [Permission; 17] = [
+        Permission::DashboardView,
+        Permission::UserRead,
+        Permission::UserManage,
+        Permission::UserRealInfoRead,
+        Permission::VerificationReview,
+        Permission::ResourceAudit,
+        Permission::ResourceManage,
+        Permission::CommentAudit,
+        Permission::CommentDelete,
+        Permission::NotificationSend,
+        Permission::AuditLogRead,
+        Permission::TeacherManage,
+        Permission::CourseManage,
+        Permission::ClaimReview,
+        Permission::ReportReview,
+        Permission::StorageMigrate,
+        Permission::RoleManage,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Permission::DashboardView => "dashboard.view",
+            Permission::UserRead => "user.read",
+            Permission::UserManage => "user.manage",
+            Permission::UserRealInfoRead => "user.real_info.read",
+            Permission::VerificationReview => "verification.review",
+            Permission::ResourceAudit => "resource.audit",
+            Permission::ResourceManage => "resource.manage",
+            Permission::CommentAudit => "comment.audit",
+            Permission::CommentDelete => "comment.delete",
+            Permission::NotificationSend => "notification.send",
+            Permission::AuditLogRead => "audit_log.read",
+            Permission::TeacherManage => "teacher.manage",
+            Permission::CourseManage => "course.manage",
+            Permission::ClaimReview => "claim.review",
+            Permission::ReportReview => "report.review",
+            Permission::StorageMigrate => "storage.migrate",
+            Permission::RoleManage => "role.manage",
+        }
+    }
+
+    pub fn from_str(s: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|p| p.as_str() == s)
+    }
+
+    pub fn description(&self) -> &'static str {
+        match self {
+            Permission::DashboardView => "查看仪表盘和统计数据",
+            Permission::UserRead => "查看用户列表",
+            Permission::UserManage => "启用或禁用用户",
+            Permission::UserRealInfoRead => "查看用户实名信息",
+            Permission::VerificationReview => "审核实名认证申请",
+            Permission::ResourceAudit => "审核资源",
+            Permission::ResourceManage => "管理和删除资源",
+            Permission::CommentAudit => "审核评论",
+            Permission::CommentDelete => "删除评论",
+            Permission::NotificationSend => "发送系统通知",
+            Permission::AuditLogRead => "查看审计日志",
+            Permission::TeacherManage => "管理授课教师",
+            Permission::CourseManage => "管理课程",
+            Permission::ClaimReview => "处理资源申领",
+            Permission::ReportReview => "处理用户举报",
+            Permission::StorageMigrate => "执行存储迁移",
+            Permission::RoleManage => "管理角色和权限分配",
+        }
+    }
+
+    /// 是否可以只授予指定课程范围（课程版主）
+    pub fn is_course_scopable(&self) -> bool {
+        matches!(
+            self,
+            Permission::ResourceAudit
+                | Permission::ResourceManage
+                | Permission::CommentAudit
+                | Permission::CommentDelete
+                | Permission::CourseManage
+        )
+    }
+}
+
+/// 用户在管理后台的有效权限
+///
+/// 由内置管理员角色和数据库中分配的角色合并得到，全站授予优先于课程范围授予
+#[derive(Debug, Clone, Default)]
+pub struct UserPermissions {
+    /// 内置管理员，拥有全部权限
+    is_admin: bool,
+    global: HashSet<Permission>,
+    /// 权限 -> 授权的课程 sn
+    course_scoped: HashMap<Permission, HashSet<i64>>,
+}
+
+impl UserPermissions {
+    /// 内置管理员的权限
+    pub fn admin() -> Self {
+        Self {
+            is_admin: true,
+            ..Self::default()
+        }
+    }
+
+    /// 添加一条授予，`course_sn` 为空表示全站范围；不可按课程授予的权限忽略课程范围授予
+    pub fn grant(&mut self, permission: Permission, course_sn: Option<i64>) {
+        match course_sn {
+            None => {
+                self.global.insert(permission);
+            }
+            Some(sn) if permission.is_course_scopable() => {
+                self.course_scoped.entry(permission).or_default().insert(sn);
+            }
+            Some(_) => {}
+        }
+    }
+
+    /// 没有任何权限（普通用户）
+    pub fn is_empty(&self) -> bool {
+        !self.is_admin && self.global.is_empty() && self.course_scoped.is_empty()
+    }
+
+    /// 是否具有全站范围的权限
+    pub fn has(&self, permission: Permission) -> bool {
+        self.is_admin || self.global.contains(&permission)
+    }
+
+    /// 是否具有任意范围的权限（全站或至少一门课程）
+    pub fn has_any(&self, permission: Permission) -> bool {
+        self.has(permission) || self.course_scoped.contains_key(&permission)
+    }
+
+    /// 是否可以对关联了 `course_sns` 中任一课程的对象行使权限
+    pub fn has_for_courses(&self, permission: Permission, course_sns: &[i64]) -> bool {
+        self.has(permission)
+            || self
+                .course_scoped
+                .get(&permission)
+                .is_some_and(|scoped| course_sns.iter().any(|sn| scoped.contains(sn)))
+    }
+
+    /// 列表查询的课程范围：None 表示不限制，Some 为允许的课程 sn（可能为空）
+    pub fn course_scope(&self, permission: Permission) -> Option<Vec<i64>> {
+        if self.has(permission) {
+            return None;
+        }
+        let mut sns: Vec<i64> = self
+            .course_scoped
+            .get(&permission)
+            .map(|scoped| scoped.iter().copied().collect())
+            .unwrap_or_default();
+        sns.sort_unstable();
+        Some(sns)
+    }
+
+    /// 供前端展示菜单使用的权限摘要
+    pub fn to_response(&self) -> MyPermissionsResponse {
+        let course_scoped = Permission::ALL
+            .into_iter()
+            .filter_map(|p| {
+                self.course_scope(p)
+                    .filter(|sns| !sns.is_empty())
+                    .map(|course_sns| ScopedPermission {
+                        permission: p.as_str().to_string(),
+                        course_sns,
+                    })
+            })
+            .collect();
+
+        MyPermissionsResponse {
+            is_admin: self.is_admin,
+            permissions: Permission::ALL
+                .into_iter()
+                .filter(|p| self.has(*p))
+                .map(|p| p.as_str().to_string())
+                .collect(),
+            course_scoped,
+        }
+    }
+}
+
+/// 角色（对应数据库 roles 表）
+#[derive(Debug, Clone, sqlx::FromRow)]
+pub struct Role {
+    pub id: Uuid,
+    pub name: String,
+    pub display_name: String,
+    pub description: Option<String>,
+    pub permissions: Vec<String>,
+    pub is_system: bool,
+    pub created_at: NaiveDateTime,
+    pub updated_at: NaiveDateTime,
+}
+
+/// 角色响应
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RoleResponse {
+    pub id: Uuid,
+    pub name: String,
+    pub display_name: String,
+    pub description: Option<String>,
+    pub permissions: Vec<String>,
+    /// 内置角色，不能删除
+    pub is_system: bool,
+    /// 拥有该角色的用户数
+    pub user_count: i64,
+    pub created_at: NaiveDateTime,
+    pub updated_at: NaiveDateTime,
+}
+
+impl RoleResponse {
+    pub fn from_role(role: Role, user_count: i64) -> Self {
+        Self {
+            id: role.id,
+            name: role.name,
+            display_name: role.display_name,
+            description: role.description,
+            permissions: role.permissions,
+            is_system: role.is_system,
+            user_count,
+            created_at: role.created_at,
+            updated_at: role.updated_at,
+        }
+    }
+}
+
+/// 创建角色请求 DTO
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CreateRoleRequest {
+    /// 角色标识，小写字母开头，只包含小写字母、数字和下划线
+    pub name: String,
+    pub display_name: String,
+    pub description: Option<String>,
+    pub permissions: Vec<String>,
+}
+
+/// 更新角色请求 DTO
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UpdateRoleRequest {
+    pub display_name: Option<String>,
+    pub description: Option<String>,
+    pub permissions: Option<Vec<String>>,
+}
+
+/// 为用户分配角色请求 DTO
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AssignRoleRequest {
+    pub role_id: Uuid,
+    /// 只在该课程范围内生效，为空表示全站
+    pub course_sn: Option<i64>,
+}
+
+/// 用户的角色分配
+#[derive(Debug, Serialize, sqlx::FromRow)]
+#[serde(rename_all = "camelCase")]
+pub struct UserRoleAssignment {
+    pub id: Uuid,
+    pub role_id: Uuid,
+    pub role_name: String,
+    pub role_display_name: String,
+    pub course_sn: Option<i64>,
+    pub course_name: Option<String>,
+    pub granted_by: Option<Uuid>,
+    pub created_at: NaiveDateTime,
+}
+
+/// 权限说明
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PermissionInfo {
+    pub name: &'static str,
+    pub description: &'static str,
+    /// 是否可以只授予指定课程范围
+    pub course_scopable: bool,
+}
+
+impl From<Permission> for PermissionInfo {
+    fn from(permission: Permission) -> Self {
+        Self {
+            name: permission.as_str(),
+            description: permission.description(),
+            course_scopable: permission.is_course_scopable(),
+        }
+    }
+}
+
+/// 课程范围内的权限
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ScopedPermission {
+    pub permission: String,
+    pub course_sns: Vec<i64>,
+}
+
+/// 当前用户的管理后台权限响应
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MyPermissionsResponse {
+    pub is_admin: bool,
+    /// 全站范围的权限
+    pub permissions: Vec<String>,
+    /// 只在部分课程内生效的权限
+    pub course_scoped: Vec<ScopedPermission>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_permission_names_round_trip() {
+        for permission in Permission::ALL {
+            assert_eq!(Permission::from_str(permission.as_str()), Some(permission));
+        }
+        assert_eq!(Permission::from_str("resource.unknown"), None);
+        assert_eq!(Permission::from_str(""), None);
+    }
+
+    #[test]
+    fn test_admin_has_all_permissions() {
+        let permissions = UserPermissions::admin();
+        assert!(!permissions.is_empty());
+        for permission in Permission::ALL {
+            assert!(permissions.has(permission));
+            assert_eq!(permissions.course_scope(permission), None);
+        }
+        assert_eq!(permissions.to_response().permissions.len(), Permission::ALL.len());
+    }
+
+    #[test]
+    fn test_course_scoped_grants() {
+        let mut permissions = UserPermissions::default();
+        assert!(permissions.is_empty());
+
+        permissions.grant(Permission::ResourceAudit, Some(3));
+        permissions.grant(Permission::ResourceAudit, Some(1));
+        permissions.grant(Permission::DashboardView, None);
+        // 不可按课程授予的权限忽略课程范围授予
+        permissions.grant(Permission::UserRealInfoRead, Some(1));
+
+        assert!(permissions.has(Permission::DashboardView));
+        assert!(!permissions.has(Permission::ResourceAudit));
+        assert!(permissions.has_any(Permission::ResourceAudit));
+        assert!(!permissions.has_any(Permission::UserRealInfoRead));
+        assert!(permissions.has_for_courses(Permission::ResourceAudit, &[2, 3]));
+        assert!(!permissions.has_for_courses(Permission::ResourceAudit, &[2]));
+        assert!(!permissions.has_for_courses(Permission::ResourceAudit, &[]));
+        assert!(!permissions.has_for_courses(Permission::CommentAudit, &[1]));
+
+        assert_eq!(
+            permissions.course_scope(Permission::ResourceAudit),
+            Some(vec![1, 3])
+        );
+        assert_eq!(permissions.course_scope(Permission::CommentAudit), Some(vec![]));
+        assert_eq!(permissions.course_scope(Permission::DashboardView), None);
+
+        // 同时具有全站授予时不再限制课程范围
+        permissions.grant(Permission::ResourceAudit, None);
+        assert!(permissions.has_for_courses(Permission::ResourceAudit, &[]));
+        assert_eq!(permissions.course_scope(Permission::ResourceAudit), None);
+
+        let response = permissions.to_response();
+        assert!(!response.is_admin);
+        assert_eq!(response.permissions, vec!["dashboard.view", "resource.audit"]);
+        assert!(response.course_scoped.is_empty());
+    }
+}
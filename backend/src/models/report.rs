@@ -0,0 +1,438 @@
+use chrono::NaiveDateTime;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+/// 举报说明最大长度
+pub const MAX_REPORT_DESCRIPTION_LENGTH: usize = 1000;
+/// 处理备注最大长度
+pub const MAX_RESOLUTION_NOTE_LENGTH: usize = 500;
+
+/// 举报实体（对应数据库 reports 表）
+#[derive(Debug, Clone, sqlx::FromRow)]
+pub struct Report {
+    pub id: Uuid,
+    pub target_type: String,
+    pub target_id: Uuid,
+    pub reporter_id: Uuid,
+    pub reason: String,
+    pub description: Option<String>,
+    pub status: String,
+    pub action: Option<String>,
+    pub handled_by: Option<Uuid>,
+    pub resolution_note: Option<String>,
+    pub resolved_at: Option<NaiveDateTime>,
+    pub created_at: NaiveDateTime,
+}
+
+/// 举报对象类型
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ReportTargetType {
+    Resource,
+    Comment,
+}
+
+impl ReportTargetType {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ReportTargetType::Resource => "resource",
+            ReportTargetType::Comment => "comment",
+        }
+    }
+
+    pub fn from_str(s: &str) -> Option<Self> {
+        match s {
+            "resource" => Some(ReportTargetType::Resource),
+            "comment" => Some(ReportTargetType::Comment),
+            _ => None,
+        }
+    }
+
+    /// 用于提示信息的中文名称
+    pub fn label(&self) -> &'static str {
+        match self {
+            ReportTargetType::Resource => "资源",
+            ReportTargetType::Comment => "评论",
+        }
+    }
+}
+
+/// 举报理由分类
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReportReason {
+    /// 侵犯版权
+    Copyright,
+    /// 内容错误
+    Incorrect,
+    /// 辱骂、骚扰等不当内容
+    Abusive,
+    /// 垃圾广告
+    Spam,
+    /// 其他（需填写说明）
+    Other,
+}
+
+impl ReportReason {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ReportReason::Copyright => "copyright",
+            ReportReason::Incorrect => "incorrect",
+            ReportReason::Abusive => "abusive",
+            ReportReason::Spam => "spam",
+            ReportReason::Other => "other",
+        }
+    }
+
+    pub fn from_str(s: &str) -> Option<Self> {
+        match s {
+            "copyright" => Some(ReportReason::Copyright),
+            "incorrect" => Some(ReportReason::Incorrect),
+            "abusive" => Some(ReportReason::Abusive),
+            "spam" => Some(ReportReason::Spam),
+            "other" => Some(ReportReason::Other),
+            _ => None,
+        }
+    }
+
+    /// 用于通知内容的中文名称
+    pub fn label(&self) -> &'static str {
+        match self {
+            ReportReason::Copyright => "侵犯版权",
+            ReportReason::Incorrect => "内容错误",
+            ReportReason::Abusive => "不当内容",
+            ReportReason::Spam => "垃圾广告",
+            ReportReason::Other => "其他",
+        }
+    }
+}
+
+/// 举报状态
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReportStatus {
+    Pending,
+    /// 已处理（隐藏、删除或警告）
+    Resolved,
+    /// 已驳回（举报不成立）
+    Dismissed,
+}
+
+impl ReportStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ReportStatus::Pending => "pending",
+            ReportStatus::Resolved => "resolved",
+            ReportStatus::Dismissed => "dismissed",
+        }
+    }
+
+    pub fn from_str(s: &str) -> Option<Self> {
+        match s {
+            "pending" => Some(ReportStatus::Pending),
+            "resolved" => Some(ReportStatus::Resolved),
+            "dismissed" => Some(ReportStatus::Dismissed),
+            _ => None,
+        }
+    }
+}
+
+/// 管理员对被举报对象的处理方式
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReportAction {
+    /// 驳回举报，不处理对象
+    Dismiss,
+    /// 隐藏对象（审核状态改为 rejected）
+    Hide,
+    /// 删除对象
+    Delete,
+    /// 警告发布者
+    WarnUploader,
+}
+
+impl ReportAction {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ReportAction::Dismiss => "dismiss",
+            ReportAction::Hide => "hide",
+            ReportAction::Delete => "delete",
+            ReportAction::WarnUploader => "warn_uploader",
+        }
+    }
+
+    pub fn from_str(s: &str) -> Option<Self> {
+        match s {
+            "dismiss" => Some(ReportAction::Dismiss),
+            "hide" => Some(ReportAction::Hide),
+            "delete" => Some(ReportAction::Delete),
+            "warn_uploader" => Some(ReportAction::WarnUploader),
+            _ => None,
+        }
+    }
+
+    /// 处理后举报的状态
+    pub fn report_status(&self) -> ReportStatus {
+        match self {
+            ReportAction::Dismiss => ReportStatus::Dismissed,
+            _ => ReportStatus::Resolved,
+        }
+    }
+}
+
+/// 提交举报请求
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CreateReportRequest {
+    pub target_type: String, // resource, comment
+    pub target_id: Uuid,
+    pub reason: String,
+    pub description: Option<String>,
+}
+
+impl CreateReportRequest {
+    /// 验证请求数据，返回对象类型、理由和整理后的说明
+    pub fn validate(&self) -> Result<(ReportTargetType, ReportReason, Option<String>), String> {
+        let target_type = ReportTargetType::from_str(&self.target_type)
+            .ok_or_else(|| format!("无效的举报对象类型: {}", self.target_type))?;
+        let reason = ReportReason::from_str(&self.reason)
+            .ok_or_else(|| format!("无效的举报理由: {}", self.reason))?;
+
+        let description = self
+            .description
+            .as_deref()
+            .map(str::trim)
+            .filter(|d| !d.is_empty())
+            .map(str::to_string);
+        if reason == ReportReason::Other && description.is_none() {
+            return Err("选择其他理由时必须填写举报说明".to_string());
+        }
+        if let Some(ref description) = description {
+            if description.chars().count() > MAX_REPORT_DESCRIPTION_LENGTH {
+                return Err(format!(
+                    "举报说明不能超过{}个字符",
+                    MAX_REPORT_DESCRIPTION_LENGTH
+                ));
+            }
+        }
+
+        Ok((target_type, reason, description))
+    }
+}
+
+/// 处理举报请求
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ResolveReportRequest {
+    pub action: String, // dismiss, hide, delete, warn_uploader
+    /// 处理备注，会发送给举报人；警告发布者时作为警告内容
+    pub note: Option<String>,
+}
+
+impl ResolveReportRequest {
+    /// 验证请求数据，返回处理方式和整理后的备注
+    pub fn validate(&self) -> Result<(ReportAction, Option<String>), String> {
+        let action = ReportAction::from_str(&self.action)
+            .ok_or_else(|| format!("无效的处理方式: {}", self.action))?;
+
+        let note = self
+            .note
+            .as_deref()
+            .map(str::trim)
+            .filter(|n| !n.is_empty())
+            .map(str::to_string);
+        if let Some(ref note) = note {
+            if note.chars().count() > MAX_RESOLUTION_NOTE_LENGTH {
+                return Err(format!(
+                    "处理备注不能超过{}个字符",
+                    MAX_RESOLUTION_NOTE_LENGTH
+                ));
+            }
+        }
+
+        Ok((action, note))
+    }
+}
+
+/// 举报列表查询
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ReportListQuery {
+    pub page: Option<i64>,
+    pub per_page: Option<i64>,
+    pub status: Option<String>,
+    /// 按对象类型筛选（仅管理员举报队列）
+    pub target_type: Option<String>,
+}
+
+/// 举报响应
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ReportResponse {
+    pub id: Uuid,
+    pub target_type: String,
+    pub target_id: Uuid,
+    pub reporter_id: Uuid,
+    pub reporter_name: Option<String>,
+    pub reason: String,
+    pub description: Option<String>,
+    pub status: String,
+    pub action: Option<String>,
+    pub handled_by: Option<Uuid>,
+    pub resolution_note: Option<String>,
+    pub resolved_at: Option<NaiveDateTime>,
+    pub created_at: NaiveDateTime,
+}
+
+/// 我的举报列表响应
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ReportListResponse {
+    pub reports: Vec<ReportResponse>,
+    pub total: i64,
+    pub page: i64,
+    pub per_page: i64,
+}
+
+/// 被举报对象的摘要信息（对象已被删除时为空）
+#[derive(Debug, Clone, Serialize, sqlx::FromRow)]
+#[serde(rename_all = "camelCase")]
+pub struct ReportTargetInfo {
+    /// 资源标题或评论内容
+    pub title: String,
+    /// 所属资源（评论为评论所在资源）
+    pub resource_id: Uuid,
+    pub resource_title: Option<String>,
+    /// 发布者（资源上传者或评论作者）
+    pub owner_id: Uuid,
+    pub owner_name: Option<String>,
+    pub audit_status: Option<String>,
+    pub is_deleted: bool,
+}
+
+/// 举报队列条目：同一对象的举报合并为一条
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ReportTargetGroup {
+    pub target_type: String,
+    pub target_id: Uuid,
+    pub target: Option<ReportTargetInfo>,
+    pub report_count: i64,
+    /// 各举报理由的数量
+    pub reason_counts: serde_json::Value,
+    pub first_reported_at: NaiveDateTime,
+    pub last_reported_at: NaiveDateTime,
+}
+
+/// 举报队列响应
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ReportQueueResponse {
+    pub targets: Vec<ReportTargetGroup>,
+    pub total: i64,
+    pub page: i64,
+    pub per_page: i64,
+}
+
+/// 单个对象的举报详情
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ReportTargetDetailResponse {
+    pub target_type: String,
+    pub target_id: Uuid,
+    pub target: Option<ReportTargetInfo>,
+    pub reports: Vec<ReportResponse>,
+}
+
+/// 处理举报响应
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ResolveReportResponse {
+    pub target_type: String,
+    pub target_id: Uuid,
+    pub action: String,
+    /// 本次处理的举报数量
+    pub resolved_count: i64,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn report(target_type: &str, reason: &str, description: Option<&str>) -> CreateReportRequest {
+        CreateReportRequest {
+            target_type: target_type.to_string(),
+            target_id: Uuid::new_v4(),
+            reason: reason.to_string(),
+            description: description.map(str::to_string),
+        }
+    }
+
+    fn resolve(action: &str, note: Option<&str>) -> ResolveReportRequest {
+        ResolveReportRequest {
+            action: action.to_string(),
+            note: note.map(str::to_string),
+        }
+    }
+
+    #[test]
+    fn test_create_report_validation() {
+        assert_eq!(
+            report("resource", "copyright", None).validate(),
+            Ok((ReportTargetType::Resource, ReportReason::Copyright, None))
+        );
+        assert_eq!(
+            report("comment", "other", Some("  刷屏  ")).validate(),
+            Ok((
+                ReportTargetType::Comment,
+                ReportReason::Other,
+                Some("刷屏".to_string())
+            ))
+        );
+        assert!(report("user", "spam", None).validate().is_err());
+        assert!(report("resource", "boring", None).validate().is_err());
+        assert!(report("resource", "other", Some("   ")).validate().is_err());
+        assert!(report(
+            "resource",
+            "incorrect",
+            Some(&"错".repeat(MAX_REPORT_DESCRIPTION_LENGTH + 1))
+        )
+        .validate()
+        .is_err());
+
+        let json = r#"{"targetType": "comment", "targetId": "00000000-0000-0000-0000-000000000001", "reason": "abusive"}"#;
+        let req: CreateReportRequest = serde_json::from_str(json).unwrap();
+        assert!(req.description.is_none());
+        assert!(req.validate().is_ok());
+    }
+
+    #[test]
+    fn test_resolve_report_validation() {
+        assert_eq!(
+            resolve("dismiss", None).validate(),
+            Ok((ReportAction::Dismiss, None))
+        );
+        assert_eq!(
+            resolve("warn_uploader", Some(" 请注明出处 ")).validate(),
+            Ok((ReportAction::WarnUploader, Some("请注明出处".to_string())))
+        );
+        assert!(resolve("ban", None).validate().is_err());
+        assert!(
+            resolve("hide", Some(&"备".repeat(MAX_RESOLUTION_NOTE_LENGTH + 1)))
+                .validate()
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn test_report_action_status() {
+        assert_eq!(
+            ReportAction::Dismiss.report_status(),
+            ReportStatus::Dismissed
+        );
+        for action in [
+            ReportAction::Hide,
+            ReportAction::Delete,
+            ReportAction::WarnUploader,
+        ] {
+            assert_eq!(action.report_status(), ReportStatus::Resolved);
+            assert_eq!(ReportAction::from_str(action.as_str()), Some(action));
+        }
+    }
+}
@@ -448,6 +448,9 @@ pub struct UploadResourceRequest {
     pub course_sns: Option<Vec<i64>>,
     /// 关联资源ID列表（可选）
     pub related_resource_ids: Option<Vec<Uuid>>,
+    /// 已存在相同文件的资源时仍然上传（需要 resource.manage 权限）
+    #[serde(default)]
+    pub allow_duplicate: bool,
 }
 
 impl UploadResourceRequest {
@@ -514,6 +517,8 @@ pub struct ResourceDetailResponse {
     pub courses: Vec<CourseInfo>,
     /// 关联的资源列表（该资源主动关联的其他资源）
     pub related_resources: Vec<RelatedResourceInfo>,
+    /// 下载过该资源的用户还下载了
+    pub also_downloaded: Vec<RelatedResourceInfo>,
     /// 存储类型：local 或 oss
     pub storage_type: String,
 }
@@ -565,6 +570,23 @@ pub struct ResourceListItem {
     pub uploader_name: Option<String>,
     /// 存储类型：local 或 oss
     pub storage_type: String,
+    /// 搜索相关度得分（仅搜索接口返回）
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub search_rank: Option<f64>,
+    /// 搜索高亮片段（仅搜索接口返回）
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub highlight: Option<ResourceSearchHighlight>,
+}
+
+/// 搜索结果高亮片段 DTO
+/// 匹配的检索词使用 `<mark>` 包裹，其余内容已做 HTML 转义
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ResourceSearchHighlight {
+    /// 高亮后的标题（标题未命中时为 None）
+    pub title: Option<String>,
+    /// 描述或标签中命中的片段
+    pub snippet: Option<String>,
 }
 
 /// 资源列表查询参数
@@ -689,6 +711,8 @@ impl UpdateResourceContentRequest {
 #[serde(rename_all = "camelCase")]
 pub struct UpdateResourceContentResponse {
     pub id: Uuid,
+    /// 本次编辑生成的版本序号（版本记录保存失败时为 None）
+    pub version_number: Option<i32>,
     pub updated_at: chrono::NaiveDateTime,
 }
 
@@ -697,10 +721,72 @@ pub struct UpdateResourceContentResponse {
 #[serde(rename_all = "camelCase")]
 pub struct HotResourcesQuery {
     pub limit: Option<i32>,
+    /// 时间窗口：day / week / semester，默认 week
+    pub window: Option<String>,
+    /// 按课程筛选（课程编号）
+    pub course_sn: Option<i64>,
+    /// 按分类筛选
+    pub category: Option<String>,
+}
+
+/// 热度统计时间窗口
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TrendingWindow {
+    Day,
+    Week,
+    Semester,
+}
+
+impl TrendingWindow {
+    pub const ALL: [TrendingWindow; 3] = [
+        TrendingWindow::Day,
+        TrendingWindow::Week,
+        TrendingWindow::Semester,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            TrendingWindow::Day => "day",
+            TrendingWindow::Week => "week",
+            TrendingWindow::Semester => "semester",
+        }
+    }
+
+    pub fn from_str(s: &str) -> Option<Self> {
+        match s {
+            "day" => Some(TrendingWindow::Day),
+            "week" => Some(TrendingWindow::Week),
+            "semester" => Some(TrendingWindow::Semester),
+            _ => None,
+        }
+    }
+
+    /// 窗口长度（天），窗口外的行为不计入热度
+    pub fn days(&self) -> i32 {
+        match self {
+            TrendingWindow::Day => 1,
+            TrendingWindow::Week => 7,
+            TrendingWindow::Semester => 120,
+        }
+    }
+
+    /// 热度半衰期（小时）：行为发生后经过该时长，其贡献衰减为一半
+    pub fn half_life_hours(&self) -> f64 {
+        match self {
+            TrendingWindow::Day => 6.0,
+            TrendingWindow::Week => 48.0,
+            TrendingWindow::Semester => 30.0 * 24.0,
+        }
+    }
+
+    /// 指数衰减系数（每小时）
+    pub fn decay_per_hour(&self) -> f64 {
+        std::f64::consts::LN_2 / self.half_life_hours()
+    }
 }
 
 /// 热门资源列表项 DTO
-#[derive(Debug, Clone, Serialize)]
+#[derive(Debug, Clone, Serialize, FromRow)]
 #[serde(rename_all = "camelCase")]
 pub struct HotResourceItem {
     pub id: Uuid,
@@ -710,6 +796,75 @@ pub struct HotResourceItem {
     pub downloads: i32,
     pub views: i32,
     pub likes: i32,
+    /// 所选时间窗口内的热度分，来源为 stats 的资源没有热度分，固定为 0
+    pub score: f64,
+    /// 来源：trending / stats
+    pub source: String,
+}
+
+/// 热门资源来源
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HotResourceSource {
+    /// 时间窗口内的热度分
+    Trending,
+    /// 热度分不足时按累计浏览、下载量补足
+    Stats,
+}
+
+impl HotResourceSource {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            HotResourceSource::Trending => "trending",
+            HotResourceSource::Stats => "stats",
+        }
+    }
+}
+
+/// 推荐资源查询参数
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RecommendedResourcesQuery {
+    pub limit: Option<i32>,
+    /// 按文件类型过滤（如 pdf）
+    pub resource_type: Option<String>,
+}
+
+/// 推荐来源
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RecommendationSource {
+    /// 基于物品的协同过滤
+    Collaborative,
+    /// 同课程 / 同教师的内容推荐（冷启动）
+    Content,
+    /// 热门资源兜底
+    Popular,
+}
+
+impl RecommendationSource {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            RecommendationSource::Collaborative => "collaborative",
+            RecommendationSource::Content => "content",
+            RecommendationSource::Popular => "popular",
+        }
+    }
+}
+
+/// 推荐资源列表项 DTO
+#[derive(Debug, Clone, Serialize, FromRow)]
+#[serde(rename_all = "camelCase")]
+pub struct RecommendedResourceItem {
+    pub id: Uuid,
+    pub title: String,
+    pub course_name: Option<String>,
+    pub resource_type: String,
+    pub downloads: i32,
+    pub views: i32,
+    pub likes: i32,
+    /// 推荐得分（不同来源的得分不可直接比较）
+    pub score: f64,
+    /// 推荐来源：collaborative / content / popular
+    pub source: String,
 }
 
 #[cfg(test)]
@@ -942,6 +1097,7 @@ mod tests {
                 teacher_sns: None,
                 course_sns: None,
                 related_resource_ids: None,
+                allow_duplicate: false,
             }
         }
 
@@ -1151,4 +1307,25 @@ mod tests {
             assert!(req.validate().is_ok());
         }
     }
+
+    mod trending_window_tests {
+        use super::*;
+
+        #[test]
+        fn test_from_str_roundtrip() {
+            for window in TrendingWindow::ALL {
+                assert_eq!(TrendingWindow::from_str(window.as_str()), Some(window));
+            }
+            assert_eq!(TrendingWindow::from_str("month"), None);
+        }
+
+        #[test]
+        fn test_decay_halves_after_half_life() {
+            for window in TrendingWindow::ALL {
+                let factor = (-window.decay_per_hour() * window.half_life_hours()).exp();
+                assert!((factor - 0.5).abs() < 1e-9);
+                assert!(window.half_life_hours() < window.days() as f64 * 24.0);
+            }
+        }
+    }
 }
@@ -0,0 +1,178 @@
+use chrono::NaiveDateTime;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+/// 资源版本实体（对应数据库 resource_versions 表）
+#[derive(Debug, Clone, sqlx::FromRow)]
+pub struct ResourceVersion {
+    pub id: Uuid,
+    #[allow(dead_code)]
+    pub resource_id: Uuid,
+    pub version_number: i32,
+    pub storage_key: String,
+    pub storage_type: Option<String>,
+    pub file_hash: Option<String>,
+    pub file_size: Option<i64>,
+    pub editor_id: Option<Uuid>,
+    pub rollback_from: Option<i32>,
+    pub created_at: NaiveDateTime,
+}
+
+/// 资源版本响应
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ResourceVersionResponse {
+    pub id: Uuid,
+    pub version_number: i32,
+    pub file_hash: Option<String>,
+    pub file_size: Option<i64>,
+    pub editor_id: Option<Uuid>,
+    pub editor_name: Option<String>,
+    /// 若该版本由回滚产生，记录回滚来源的版本序号
+    pub rollback_from: Option<i32>,
+    /// 是否为资源当前内容对应的版本
+    pub is_current: bool,
+    pub created_at: String,
+}
+
+/// 资源版本列表响应
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ResourceVersionListResponse {
+    pub resource_id: Uuid,
+    pub current_version: Option<i32>,
+    pub versions: Vec<ResourceVersionResponse>,
+}
+
+/// 版本对比查询参数
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ResourceVersionDiffQuery {
+    /// 旧版本序号
+    pub from: i32,
+    /// 新版本序号（不传时与最新版本对比）
+    pub to: Option<i32>,
+}
+
+impl ResourceVersionDiffQuery {
+    /// 验证查询参数
+    pub fn validate(&self) -> Result<(), String> {
+        if self.from < 1 {
+            return Err("版本序号必须大于 0".to_string());
+        }
+        if let Some(to) = self.to {
+            if to < 1 {
+                return Err("版本序号必须大于 0".to_string());
+            }
+            if to == self.from {
+                return Err("不能与同一版本进行对比".to_string());
+            }
+        }
+        Ok(())
+    }
+}
+
+/// 差异行类型
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DiffLineKind {
+    Equal,
+    Insert,
+    Delete,
+}
+
+/// 差异行
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DiffLine {
+    pub kind: DiffLineKind,
+    /// 在旧版本中的行号（从 1 开始，新增行为 None）
+    pub old_line: Option<usize>,
+    /// 在新版本中的行号（从 1 开始，删除行为 None）
+    pub new_line: Option<usize>,
+    pub content: String,
+}
+
+/// 差异块（包含变更行及其上下文）
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DiffHunk {
+    pub old_start: usize,
+    pub old_lines: usize,
+    pub new_start: usize,
+    pub new_lines: usize,
+    pub lines: Vec<DiffLine>,
+}
+
+/// 版本对比响应
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ResourceVersionDiffResponse {
+    pub resource_id: Uuid,
+    pub from_version: i32,
+    pub to_version: i32,
+    pub additions: usize,
+    pub deletions: usize,
+    pub hunks: Vec<DiffHunk>,
+}
+
+/// 版本回滚响应
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RollbackResourceVersionResponse {
+    pub id: Uuid,
+    /// 回滚后生成的新版本序号
+    pub version_number: Option<i32>,
+    pub rolled_back_from: i32,
+    pub updated_at: NaiveDateTime,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_diff_query_validate() {
+        let query = ResourceVersionDiffQuery {
+            from: 1,
+            to: Some(2),
+        };
+        assert!(query.validate().is_ok());
+
+        let query = ResourceVersionDiffQuery { from: 1, to: None };
+        assert!(query.validate().is_ok());
+
+        let query = ResourceVersionDiffQuery {
+            from: 0,
+            to: Some(2),
+        };
+        assert!(query.validate().is_err());
+
+        let query = ResourceVersionDiffQuery {
+            from: 2,
+            to: Some(2),
+        };
+        assert!(query.validate().is_err());
+    }
+
+    #[test]
+    fn test_diff_query_deserialization() {
+        let query: ResourceVersionDiffQuery = serde_json::from_str(r#"{"from": 3}"#).unwrap();
+        assert_eq!(query.from, 3);
+        assert!(query.to.is_none());
+    }
+
+    #[test]
+    fn test_diff_line_serialization() {
+        let line = DiffLine {
+            kind: DiffLineKind::Insert,
+            old_line: None,
+            new_line: Some(3),
+            content: "新增内容".to_string(),
+        };
+        let json = serde_json::to_string(&line).unwrap();
+        assert!(json.contains(r#""kind":"insert""#));
+        assert!(json.contains(r#""newLine":3"#));
+        assert!(json.contains(r#""oldLine":null"#));
+    }
+}
@@ -0,0 +1,98 @@
+use chrono::NaiveDateTime;
+use serde::Serialize;
+use uuid::Uuid;
+
+/// 登录会话实体（对应数据库 user_sessions 表，不含 Token 哈希）
+#[derive(Debug, Clone, sqlx::FromRow)]
+pub struct UserSession {
+    pub id: Uuid,
+    pub user_agent: Option<String>,
+    pub ip_address: Option<String>,
+    pub created_at: NaiveDateTime,
+    pub last_used_at: NaiveDateTime,
+    pub expires_at: NaiveDateTime,
+}
+
+/// 刷新 Token 时读取的会话状态
+#[derive(Debug, Clone, sqlx::FromRow)]
+pub struct SessionTokenState {
+    pub user_id: Uuid,
+    pub refresh_token_hash: String,
+    pub previous_token_hash: Option<String>,
+    /// 未吊销且未过期
+    pub is_active: bool,
+    /// 距上次轮换的秒数
+    pub seconds_since_rotation: i64,
+}
+
+/// 会话吊销原因
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SessionRevokeReason {
+    /// 用户登出
+    Logout,
+    /// 用户手动吊销单个会话
+    Revoked,
+    /// 用户吊销全部会话
+    RevokeAll,
+    /// 检测到已轮换的 Refresh Token 被重复使用
+    ReuseDetected,
+    /// 管理员禁用了用户
+    UserDisabled,
+    /// 用户通过邮件重置了密码
+    PasswordReset,
+}
+
+impl SessionRevokeReason {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SessionRevokeReason::Logout => "logout",
+            SessionRevokeReason::Revoked => "revoked",
+            SessionRevokeReason::RevokeAll => "revoke_all",
+            SessionRevokeReason::ReuseDetected => "reuse_detected",
+            SessionRevokeReason::UserDisabled => "user_disabled",
+            SessionRevokeReason::PasswordReset => "password_reset",
+        }
+    }
+}
+
+/// 创建会话时记录的客户端信息
+#[derive(Debug, Clone, Default)]
+pub struct SessionClientInfo {
+    pub user_agent: Option<String>,
+    pub ip_address: Option<String>,
+}
+
+/// 会话响应
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SessionResponse {
+    pub id: Uuid,
+    pub user_agent: Option<String>,
+    pub ip_address: Option<String>,
+    pub created_at: String,
+    pub last_used_at: String,
+    pub expires_at: String,
+    /// 是否为发起请求的会话
+    pub is_current: bool,
+}
+
+impl SessionResponse {
+    pub fn from_session(session: UserSession, current_session_id: Option<Uuid>) -> Self {
+        SessionResponse {
+            id: session.id,
+            user_agent: session.user_agent,
+            ip_address: session.ip_address,
+            created_at: session.created_at.format("%Y-%m-%d %H:%M:%S").to_string(),
+            last_used_at: session.last_used_at.format("%Y-%m-%d %H:%M:%S").to_string(),
+            expires_at: session.expires_at.format("%Y-%m-%d %H:%M:%S").to_string(),
+            is_current: current_session_id == Some(session.id),
+        }
+    }
+}
+
+/// 会话列表响应
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SessionListResponse {
+    pub sessions: Vec<SessionResponse>,
+}
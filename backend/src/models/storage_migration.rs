@@ -0,0 +1,203 @@
+use chrono::NaiveDateTime;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+/// 存储迁移任务实体（对应数据库 storage_migration_jobs 表）
+#[derive(Debug, Clone, sqlx::FromRow)]
+pub struct StorageMigrationJob {
+    pub id: Uuid,
+    pub admin_id: Uuid,
+    pub source_type: String,
+    pub target_type: String,
+    pub include_images: bool,
+    pub delete_source: bool,
+    pub status: String,
+    pub total_items: i32,
+    pub processed_items: i32,
+    pub migrated_items: i32,
+    pub failed_items: i32,
+    pub bytes_copied: i64,
+    pub failures: serde_json::Value,
+    pub error_message: Option<String>,
+    pub started_at: Option<NaiveDateTime>,
+    pub completed_at: Option<NaiveDateTime>,
+    pub created_at: NaiveDateTime,
+}
+
+/// 存储迁移任务状态
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StorageMigrationStatus {
+    /// 排队等待执行
+    Pending,
+    /// 迁移中
+    Running,
+    /// 已完成（部分文件可能迁移失败，见 failures）
+    Completed,
+    /// 任务中断或无法执行
+    Failed,
+}
+
+impl StorageMigrationStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            StorageMigrationStatus::Pending => "pending",
+            StorageMigrationStatus::Running => "running",
+            StorageMigrationStatus::Completed => "completed",
+            StorageMigrationStatus::Failed => "failed",
+        }
+    }
+
+    #[allow(dead_code)]
+    pub fn from_str(s: &str) -> Option<Self> {
+        match s {
+            "pending" => Some(StorageMigrationStatus::Pending),
+            "running" => Some(StorageMigrationStatus::Running),
+            "completed" => Some(StorageMigrationStatus::Completed),
+            "failed" => Some(StorageMigrationStatus::Failed),
+            _ => None,
+        }
+    }
+}
+
+/// 迁移对象类型
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum StorageMigrationItemKind {
+    /// 资源当前文件
+    Resource,
+    /// 资源历史版本文件
+    ResourceVersion,
+    Image,
+}
+
+/// 单个文件的迁移失败记录
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StorageMigrationFailure {
+    pub kind: StorageMigrationItemKind,
+    pub id: Uuid,
+    pub file_path: String,
+    pub error: String,
+}
+
+/// 创建存储迁移任务请求
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CreateStorageMigrationRequest {
+    /// 源存储类型（local / oss / s3）
+    pub source_type: String,
+    /// 目标存储类型（local / oss / s3）
+    pub target_type: String,
+    /// 是否同时迁移图片，默认迁移
+    #[serde(default = "default_include_images")]
+    pub include_images: bool,
+    /// 迁移成功后是否删除源文件，默认保留
+    #[serde(default)]
+    pub delete_source: bool,
+}
+
+fn default_include_images() -> bool {
+    true
+}
+
+/// 存储迁移任务响应
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StorageMigrationJobResponse {
+    pub id: Uuid,
+    pub admin_id: Uuid,
+    pub source_type: String,
+    pub target_type: String,
+    pub include_images: bool,
+    pub delete_source: bool,
+    pub status: String,
+    pub total_items: i32,
+    /// 已处理（迁移成功或失败）的文件数
+    pub processed_items: i32,
+    pub migrated_items: i32,
+    pub failed_items: i32,
+    pub bytes_copied: i64,
+    /// 迁移失败的文件（最多保留前若干条）
+    pub failures: serde_json::Value,
+    pub error_message: Option<String>,
+    pub started_at: Option<String>,
+    pub completed_at: Option<String>,
+    pub created_at: String,
+}
+
+impl From<StorageMigrationJob> for StorageMigrationJobResponse {
+    fn from(job: StorageMigrationJob) -> Self {
+        StorageMigrationJobResponse {
+            id: job.id,
+            admin_id: job.admin_id,
+            source_type: job.source_type,
+            target_type: job.target_type,
+            include_images: job.include_images,
+            delete_source: job.delete_source,
+            status: job.status,
+            total_items: job.total_items,
+            processed_items: job.processed_items,
+            migrated_items: job.migrated_items,
+            failed_items: job.failed_items,
+            bytes_copied: job.bytes_copied,
+            failures: job.failures,
+            error_message: job.error_message,
+            started_at: job
+                .started_at
+                .map(|t| t.format("%Y-%m-%d %H:%M:%S").to_string()),
+            completed_at: job
+                .completed_at
+                .map(|t| t.format("%Y-%m-%d %H:%M:%S").to_string()),
+            created_at: job.created_at.format("%Y-%m-%d %H:%M:%S").to_string(),
+        }
+    }
+}
+
+/// 存储迁移任务列表响应
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StorageMigrationJobListResponse {
+    pub jobs: Vec<StorageMigrationJobResponse>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_storage_migration_status_round_trip() {
+        for status in [
+            StorageMigrationStatus::Pending,
+            StorageMigrationStatus::Running,
+            StorageMigrationStatus::Completed,
+            StorageMigrationStatus::Failed,
+        ] {
+            assert_eq!(
+                StorageMigrationStatus::from_str(status.as_str()),
+                Some(status)
+            );
+        }
+        assert_eq!(StorageMigrationStatus::from_str("unknown"), None);
+    }
+
+    #[test]
+    fn test_create_request_defaults() {
+        let request: CreateStorageMigrationRequest =
+            serde_json::from_str(r#"{"sourceType": "local", "targetType": "oss"}"#).unwrap();
+        assert!(request.include_images);
+        assert!(!request.delete_source);
+    }
+
+    #[test]
+    fn test_failure_serialization() {
+        let failure = StorageMigrationFailure {
+            kind: StorageMigrationItemKind::ResourceVersion,
+            id: Uuid::nil(),
+            file_path: "resources/a.pdf".to_string(),
+            error: "哈希不一致".to_string(),
+        };
+        let value = serde_json::to_value(&failure).unwrap();
+        assert_eq!(value["kind"], "resource_version");
+        assert_eq!(value["filePath"], "resources/a.pdf");
+    }
+}
@@ -0,0 +1,87 @@
+use serde::{Deserialize, Serialize};
+
+/// 开始启用两步验证请求 DTO（需要再次输入密码）
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TotpSetupRequest {
+    pub password: String,
+}
+
+/// 开始启用两步验证响应，密钥在确认启用前不会生效
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TotpSetupResponse {
+    /// Base32 编码的密钥，供无法扫码时手动输入
+    pub secret: String,
+    /// 认证器应用扫码使用的 otpauth:// 链接
+    pub otpauth_uri: String,
+}
+
+/// 提交验证码请求 DTO（确认启用、重新生成恢复码）
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TotpCodeRequest {
+    /// 6 位验证码或恢复码
+    pub code: String,
+}
+
+/// 关闭两步验证请求 DTO
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TotpDisableRequest {
+    pub password: String,
+    pub code: String,
+}
+
+/// 两步验证状态响应
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TotpStatusResponse {
+    pub enabled: bool,
+    /// 站点是否要求当前用户启用两步验证
+    pub required: bool,
+    /// 当前会话是否已完成两步验证
+    pub session_verified: bool,
+    /// 剩余未使用的恢复码数量
+    pub recovery_codes_remaining: i64,
+}
+
+/// 恢复码响应，明文只返回这一次
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RecoveryCodesResponse {
+    pub recovery_codes: Vec<String>,
+}
+
+/// 登录第二步请求 DTO
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LoginTwoFactorRequest {
+    /// 第一步登录返回的临时令牌
+    pub mfa_token: String,
+    /// 6 位验证码或恢复码
+    pub code: String,
+}
+
+impl LoginTwoFactorRequest {
+    /// 验证登录第二步请求
+    pub fn validate(&self) -> Result<(), String> {
+        if self.mfa_token.is_empty() {
+            return Err("登录已过期，请重新登录".to_string());
+        }
+        if self.code.trim().is_empty() {
+            return Err("请输入验证码".to_string());
+        }
+        Ok(())
+    }
+}
+
+/// 密码校验通过但需要两步验证时的登录响应
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TwoFactorChallengeResponse {
+    pub mfa_required: bool,
+    pub mfa_token: String,
+    /// 临时令牌有效期（秒）
+    pub expires_in: i64,
+}
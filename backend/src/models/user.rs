@@ -44,6 +44,8 @@ pub struct User {
     #[serde(skip_serializing)]
     pub password_hash: String,
     pub email: Option<String>,
+    /// 邮箱是否已验证
+    pub email_verified: bool,
     pub role: String,
     pub bio: Option<String>,
     pub social_links: Option<serde_json::Value>,
@@ -62,6 +64,8 @@ pub struct UserInfo {
     pub sn: Option<i64>,
     pub username: String,
     pub email: Option<String>,
+    /// 邮箱是否已验证
+    pub email_verified: bool,
     pub role: String,
     pub bio: Option<String>,
     pub is_verified: bool,
@@ -75,6 +79,7 @@ impl From<User> for UserInfo {
             sn: user.sn,
             username: user.username,
             email: user.email,
+            email_verified: user.email_verified,
             role: user.role,
             bio: user.bio,
             is_verified: user.is_verified,
@@ -128,6 +133,12 @@ pub struct Claims {
     pub exp: i64,           // 过期时间
     pub iat: i64,           // 签发时间
     pub token_type: String, // token 类型: access | refresh
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sid: Option<Uuid>, // 登录会话ID
+    #[serde(default)]
+    pub ver: i32, // 用户令牌版本，与 users.token_version 不一致时失效
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub jti: Option<String>, // Token 唯一标识，保证每次轮换得到不同的 Refresh Token
 }
 
 /// 当前用户信息（从 JWT 中提取）
@@ -137,6 +148,8 @@ pub struct CurrentUser {
     pub username: String,
     pub role: UserRole,
     pub is_verified: bool,
+    /// 当前登录会话ID（旧版 Token 中没有）
+    pub session_id: Option<Uuid>,
 }
 
 impl RegisterRequest {
@@ -193,16 +206,6 @@ pub struct UpdateProfileRequest {
     pub social_links: Option<serde_json::Value>,
 }
 
-/// 实名认证请求 DTO
-#[derive(Debug, Deserialize)]
-#[serde(rename_all = "camelCase")]
-pub struct VerificationRequest {
-    pub real_name: Option<String>,
-    pub student_id: Option<String>,
-    pub major: Option<String>,
-    pub grade: Option<String>,
-}
-
 /// 修改密码请求 DTO
 #[derive(Debug, Deserialize)]
 #[serde(rename_all = "camelCase")]
@@ -0,0 +1,279 @@
+use chrono::NaiveDateTime;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+/// 姓名最大长度
+pub const MAX_REAL_NAME_LENGTH: usize = 50;
+/// 学号最大长度
+pub const MAX_STUDENT_ID_LENGTH: usize = 32;
+/// 专业最大长度
+pub const MAX_MAJOR_LENGTH: usize = 100;
+/// 年级最大长度
+pub const MAX_GRADE_LENGTH: usize = 20;
+/// 审核理由最大长度
+pub const MAX_REVIEW_REASON_LENGTH: usize = 500;
+
+/// 实名认证请求 DTO
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct VerificationRequest {
+    pub real_name: Option<String>,
+    pub student_id: Option<String>,
+    pub major: Option<String>,
+    pub grade: Option<String>,
+    /// 证明材料图片 ID（如学生证照片，来自图床），可选
+    pub proof_image_id: Option<Uuid>,
+}
+
+impl VerificationRequest {
+    /// 验证请求数据：姓名和学号必填
+    pub fn validate(&self) -> Result<(), String> {
+        let fields = [
+            ("姓名", &self.real_name, MAX_REAL_NAME_LENGTH, true),
+            ("学号", &self.student_id, MAX_STUDENT_ID_LENGTH, true),
+            ("专业", &self.major, MAX_MAJOR_LENGTH, false),
+            ("年级", &self.grade, MAX_GRADE_LENGTH, false),
+        ];
+        for (label, value, max_length, required) in fields {
+            let value = value.as_deref().map(str::trim).unwrap_or_default();
+            if required && value.is_empty() {
+                return Err(format!("{}不能为空", label));
+            }
+            if value.chars().count() > max_length {
+                return Err(format!("{}不能超过{}个字符", label, max_length));
+            }
+        }
+        Ok(())
+    }
+
+    /// 生成实名信息 JSON（与 users.real_info 格式相同）
+    pub fn real_info(&self) -> serde_json::Value {
+        let field = |value: &Option<String>| {
+            value
+                .as_deref()
+                .map(str::trim)
+                .filter(|v| !v.is_empty())
+                .map(str::to_string)
+        };
+        serde_json::json!({
+            "real_name": field(&self.real_name),
+            "student_id": field(&self.student_id),
+            "major": field(&self.major),
+            "grade": field(&self.grade),
+        })
+    }
+}
+
+/// 实名认证申请实体（对应数据库 verification_requests 表）
+#[derive(Debug, Clone, sqlx::FromRow)]
+pub struct VerificationRecord {
+    pub id: Uuid,
+    pub user_id: Uuid,
+    /// 提交的实名信息，配置密钥时敏感字段为密文；审核后清空
+    pub real_info: serde_json::Value,
+    pub proof_image_id: Option<Uuid>,
+    pub status: String,
+    pub reviewer_id: Option<Uuid>,
+    pub review_reason: Option<String>,
+    pub reviewed_at: Option<NaiveDateTime>,
+    pub created_at: NaiveDateTime,
+}
+
+/// 实名认证申请状态
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VerificationStatus {
+    Pending,
+    Approved,
+    Rejected,
+}
+
+impl VerificationStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            VerificationStatus::Pending => "pending",
+            VerificationStatus::Approved => "approved",
+            VerificationStatus::Rejected => "rejected",
+        }
+    }
+
+    pub fn from_str(s: &str) -> Option<Self> {
+        match s {
+            "pending" => Some(VerificationStatus::Pending),
+            "approved" => Some(VerificationStatus::Approved),
+            "rejected" => Some(VerificationStatus::Rejected),
+            _ => None,
+        }
+    }
+}
+
+/// 审核实名认证申请请求
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ReviewVerificationRequest {
+    pub status: String, // approved, rejected
+    /// 审核理由，驳回时必填
+    pub reason: Option<String>,
+}
+
+impl ReviewVerificationRequest {
+    /// 验证请求数据，返回审核结果和整理后的理由
+    pub fn validate(&self) -> Result<(VerificationStatus, Option<String>), String> {
+        let status = match VerificationStatus::from_str(&self.status) {
+            Some(s @ (VerificationStatus::Approved | VerificationStatus::Rejected)) => s,
+            _ => return Err("状态必须是 approved 或 rejected".to_string()),
+        };
+
+        let reason = self
+            .reason
+            .as_deref()
+            .map(str::trim)
+            .filter(|r| !r.is_empty())
+            .map(str::to_string);
+        if status == VerificationStatus::Rejected && reason.is_none() {
+            return Err("驳回时必须填写理由".to_string());
+        }
+        if let Some(ref reason) = reason {
+            if reason.chars().count() > MAX_REVIEW_REASON_LENGTH {
+                return Err(format!(
+                    "审核理由不能超过{}个字符",
+                    MAX_REVIEW_REASON_LENGTH
+                ));
+            }
+        }
+
+        Ok((status, reason))
+    }
+}
+
+/// 实名认证申请列表查询
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct VerificationListQuery {
+    pub page: Option<i64>,
+    pub per_page: Option<i64>,
+    pub status: Option<String>,
+}
+
+/// 实名认证证明图片
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct VerificationProofImage {
+    pub id: Uuid,
+    pub url: String,
+}
+
+/// 实名认证申请响应（不包含实名信息）
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct VerificationResponse {
+    pub id: Uuid,
+    pub user_id: Uuid,
+    pub username: Option<String>,
+    pub proof_image: Option<VerificationProofImage>,
+    pub status: String,
+    pub reviewer_id: Option<Uuid>,
+    pub review_reason: Option<String>,
+    pub reviewed_at: Option<NaiveDateTime>,
+    pub created_at: NaiveDateTime,
+}
+
+/// 实名认证申请详情（管理员审核时查看，包含解密后的实名信息）
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct VerificationDetailResponse {
+    #[serde(flatten)]
+    pub verification: VerificationResponse,
+    pub real_name: Option<String>,
+    pub student_id: Option<String>,
+    pub major: Option<String>,
+    pub grade: Option<String>,
+}
+
+/// 实名认证申请列表响应
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct VerificationListResponse {
+    pub verifications: Vec<VerificationResponse>,
+    pub total: i64,
+    pub page: i64,
+    pub per_page: i64,
+}
+
+/// 当前用户的实名认证状态
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MyVerificationResponse {
+    pub is_verified: bool,
+    /// 最近一次提交的申请
+    pub latest: Option<VerificationResponse>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn review(status: &str, reason: Option<&str>) -> ReviewVerificationRequest {
+        ReviewVerificationRequest {
+            status: status.to_string(),
+            reason: reason.map(str::to_string),
+        }
+    }
+
+    fn verification(real_name: &str, student_id: &str) -> VerificationRequest {
+        VerificationRequest {
+            real_name: Some(real_name.to_string()),
+            student_id: Some(student_id.to_string()),
+            major: Some(" 数学 ".to_string()),
+            grade: Some(String::new()),
+            proof_image_id: None,
+        }
+    }
+
+    #[test]
+    fn test_verification_request_validation() {
+        assert!(verification("张三", "PB21000001").validate().is_ok());
+        assert!(verification("  ", "PB21000001").validate().is_err());
+        assert!(verification("张三", "").validate().is_err());
+        assert!(
+            verification(&"名".repeat(MAX_REAL_NAME_LENGTH + 1), "PB21000001")
+                .validate()
+                .is_err()
+        );
+
+        let json = r#"{"realName": "张三", "studentId": "PB21000001"}"#;
+        let req: VerificationRequest = serde_json::from_str(json).unwrap();
+        assert!(req.proof_image_id.is_none());
+        assert!(req.validate().is_ok());
+    }
+
+    #[test]
+    fn test_verification_request_real_info() {
+        let info = verification(" 张三 ", "PB21000001").real_info();
+        assert_eq!(info["real_name"], "张三");
+        assert_eq!(info["major"], "数学");
+        assert_eq!(info["grade"], serde_json::Value::Null);
+    }
+
+    #[test]
+    fn test_review_request_validation() {
+        assert_eq!(
+            review("approved", None).validate(),
+            Ok((VerificationStatus::Approved, None))
+        );
+        assert_eq!(
+            review("rejected", Some("  学号与证件不符 ")).validate(),
+            Ok((
+                VerificationStatus::Rejected,
+                Some("学号与证件不符".to_string())
+            ))
+        );
+        assert!(review("rejected", None).validate().is_err());
+        assert!(review("rejected", Some("   ")).validate().is_err());
+        assert!(review("pending", None).validate().is_err());
+        assert!(
+            review("approved", Some(&"理".repeat(MAX_REVIEW_REASON_LENGTH + 1)))
+                .validate()
+                .is_err()
+        );
+    }
+}
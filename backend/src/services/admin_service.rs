@@ -5,7 +5,11 @@ use serde::{Deserialize, Serialize};
 use sqlx::PgPool;
 use uuid::Uuid;
 
-use crate::models::CurrentUser;
+use crate::models::{CurrentUser, SessionRevokeReason, VerificationDetailResponse};
+use crate::services::{
+    AuditAction, AuditLogService, RealInfoCipher, RealInfoFields, RealInfoService, SessionService,
+    VerificationError, VerificationService,
+};
 
 /// 管理员服务错误类型
 #[derive(Debug)]
@@ -130,6 +134,8 @@ pub struct AdminCommentItem {
     pub user_name: Option<String>,
     pub content: String,
     pub audit_status: String,
+    pub parent_id: Option<Uuid>,
+    pub is_deleted: bool,
     pub created_at: NaiveDateTime,
 }
 
@@ -243,11 +249,12 @@ impl AdminService {
                 .map_err(|e| AdminError::DatabaseError(e.to_string()))?;
 
         // 待审核评论数
-        let pending_comments: i64 =
-            sqlx::query_scalar("SELECT COUNT(*) FROM comments WHERE audit_status = 'pending'")
-                .fetch_one(pool)
-                .await
-                .map_err(|e| AdminError::DatabaseError(e.to_string()))?;
+        let pending_comments: i64 = sqlx::query_scalar(
+            "SELECT COUNT(*) FROM comments WHERE audit_status = 'pending' AND is_deleted = false",
+        )
+        .fetch_one(pool)
+        .await
+        .map_err(|e| AdminError::DatabaseError(e.to_string()))?;
 
         // 今日新增用户
         let today_new_users: i64 =
@@ -338,14 +345,33 @@ impl AdminService {
             return Err(AdminError::NotFound("用户不存在".to_string()));
         }
 
+        // 禁用用户时吊销其全部登录会话，已签发的 Token 立即失效
+        if !is_active {
+            let revoked = SessionService::revoke_all_sessions(
+                pool,
+                user_id,
+                SessionRevokeReason::UserDisabled,
+            )
+            .await
+            .map_err(|e| AdminError::DatabaseError(e.to_string()))?;
+            log::info!(
+                "[Admin] 已吊销被禁用用户的登录会话 | user_id={}, revoked={}",
+                user_id,
+                revoked
+            );
+        }
+
         Ok(())
     }
 
     /// 获取待审核资源列表
+    ///
+    /// `course_scope` 不为空时只返回关联了其中课程的资源（课程版主）
     pub async fn get_pending_resources(
         pool: &PgPool,
         page: i32,
         per_page: i32,
+        course_scope: Option<&[i64]>,
     ) -> Result<PendingResourceListResponse, AdminError> {
         let offset = (page - 1) * per_page;
 
@@ -365,22 +391,36 @@ impl AdminService {
             FROM resources r
             JOIN users u ON r.uploader_id = u.id
             WHERE r.audit_status = 'pending'
+              AND ($3::BIGINT[] IS NULL OR EXISTS (
+                  SELECT 1 FROM resource_courses rc
+                  WHERE rc.resource_id = r.id AND rc.course_sn = ANY($3)
+              ))
             ORDER BY r.created_at DESC
             LIMIT $1 OFFSET $2
             "#,
         )
         .bind(per_page as i64)
         .bind(offset as i64)
+        .bind(course_scope)
         .fetch_all(pool)
         .await
         .map_err(|e| AdminError::DatabaseError(e.to_string()))?;
 
         // 获取总数
-        let total: i64 =
-            sqlx::query_scalar("SELECT COUNT(*) FROM resources WHERE audit_status = 'pending'")
-                .fetch_one(pool)
-                .await
-                .map_err(|e| AdminError::DatabaseError(e.to_string()))?;
+        let total: i64 = sqlx::query_scalar(
+            r#"
+            SELECT COUNT(*) FROM resources r
+            WHERE r.audit_status = 'pending'
+              AND ($1::BIGINT[] IS NULL OR EXISTS (
+                  SELECT 1 FROM resource_courses rc
+                  WHERE rc.resource_id = r.id AND rc.course_sn = ANY($1)
+              ))
+            "#,
+        )
+        .bind(course_scope)
+        .fetch_one(pool)
+        .await
+        .map_err(|e| AdminError::DatabaseError(e.to_string()))?;
 
         Ok(PendingResourceListResponse {
             resources,
@@ -429,15 +469,19 @@ impl AdminService {
     }
 
     /// 获取评论列表
+    ///
+    /// `course_scope` 不为空时只返回关联了其中课程的资源下的评论（课程版主）
     pub async fn get_comment_list(
         pool: &PgPool,
         page: i32,
         per_page: i32,
         audit_status: Option<String>,
+        course_scope: Option<&[i64]>,
     ) -> Result<AdminCommentListResponse, AdminError> {
         let offset = (page - 1) * per_page;
 
-        let mut query = String::from(
+        // 审核状态和课程范围为空时不筛选
+        let comments: Vec<AdminCommentItem> = sqlx::query_as(
             r#"
             SELECT
                 c.id,
@@ -447,55 +491,45 @@ impl AdminService {
                 u.username as user_name,
                 c.content,
                 c.audit_status,
+                c.parent_id,
+                c.is_deleted,
                 c.created_at
             FROM comments c
             JOIN users u ON c.user_id = u.id
             JOIN resources r ON c.resource_id = r.id
-            WHERE 1=1
+            WHERE ($3::VARCHAR IS NULL OR c.audit_status = $3)
+              AND ($4::BIGINT[] IS NULL OR EXISTS (
+                  SELECT 1 FROM resource_courses rc
+                  WHERE rc.resource_id = c.resource_id AND rc.course_sn = ANY($4)
+              ))
+            ORDER BY c.created_at DESC
+            LIMIT $1 OFFSET $2
             "#,
-        );
-
-        let mut count_query = String::from("SELECT COUNT(*) FROM comments c WHERE 1=1");
-
-        // 添加审核状态筛选
-        if let Some(ref _status) = audit_status {
-            query.push_str(" AND c.audit_status = $3");
-            count_query.push_str(" AND c.audit_status = $1");
-        }
-
-        query.push_str(" ORDER BY c.created_at DESC LIMIT $1 OFFSET $2");
-
-        // 执行查询
-        let comments: Vec<AdminCommentItem> = if let Some(ref status) = audit_status {
-            sqlx::query_as(&query)
-                .bind(per_page as i64)
-                .bind(offset as i64)
-                .bind(status)
-                .fetch_all(pool)
-                .await
-                .map_err(|e| AdminError::DatabaseError(e.to_string()))?
-        } else {
-            sqlx::query_as(&query)
-                .bind(per_page as i64)
-                .bind(offset as i64)
-                .fetch_all(pool)
-                .await
-                .map_err(|e| AdminError::DatabaseError(e.to_string()))?
-        };
+        )
+        .bind(per_page as i64)
+        .bind(offset as i64)
+        .bind(audit_status.as_deref())
+        .bind(course_scope)
+        .fetch_all(pool)
+        .await
+        .map_err(|e| AdminError::DatabaseError(e.to_string()))?;
 
         // 获取总数
-        let total: i64 = if let Some(ref status) = audit_status {
-            sqlx::query_scalar(&count_query)
-                .bind(status)
-                .fetch_one(pool)
-                .await
-                .map_err(|e| AdminError::DatabaseError(e.to_string()))?
-        } else {
-            sqlx::query_scalar(&count_query)
-                .fetch_one(pool)
-                .await
-                .map_err(|e| AdminError::DatabaseError(e.to_string()))?
-        };
+        let total: i64 = sqlx::query_scalar(
+            r#"
+            SELECT COUNT(*) FROM comments c
+            WHERE ($1::VARCHAR IS NULL OR c.audit_status = $1)
+              AND ($2::BIGINT[] IS NULL OR EXISTS (
+                  SELECT 1 FROM resource_courses rc
+                  WHERE rc.resource_id = c.resource_id AND rc.course_sn = ANY($2)
+              ))
+            "#,
+        )
+        .bind(audit_status.as_deref())
+        .bind(course_scope)
+        .fetch_one(pool)
+        .await
+        .map_err(|e| AdminError::DatabaseError(e.to_string()))?;
 
         Ok(AdminCommentListResponse {
             comments,
@@ -505,9 +539,11 @@ impl AdminService {
         })
     }
 
-    /// 删除评论
+    /// 删除评论（软删除，保留楼层结构）
     pub async fn delete_comment(pool: &PgPool, comment_id: Uuid) -> Result<(), AdminError> {
-        let result = sqlx::query("DELETE FROM comments WHERE id = $1")
+        let result = sqlx::query(
+            "UPDATE comments SET is_deleted = true, deleted_at = NOW() WHERE id = $1 AND is_deleted = false",
+        )
             .bind(comment_id)
             .execute(pool)
             .await
@@ -730,10 +766,11 @@ impl AdminService {
         .map_err(|e| AdminError::DatabaseError(e.to_string()))?;
 
         // 互动统计
-        let total_comments: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM comments")
-            .fetch_one(pool)
-            .await
-            .map_err(|e| AdminError::DatabaseError(e.to_string()))?;
+        let total_comments: i64 =
+            sqlx::query_scalar("SELECT COUNT(*) FROM comments WHERE is_deleted = false")
+                .fetch_one(pool)
+                .await
+                .map_err(|e| AdminError::DatabaseError(e.to_string()))?;
 
         let total_ratings: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM ratings")
             .fetch_one(pool)
@@ -1339,11 +1376,14 @@ impl AdminService {
     }
 
     /// 获取所有资源列表（支持关键词搜索）
+    ///
+    /// `course_scope` 不为空时只返回关联了其中课程的资源（课程版主）
     pub async fn get_all_resources(
         pool: &PgPool,
         page: i32,
         per_page: i32,
         keyword: Option<String>,
+        course_scope: Option<&[i64]>,
     ) -> Result<AdminResourceListResponse, AdminError> {
         let offset = (page - 1) * per_page;
 
@@ -1360,21 +1400,33 @@ impl AdminService {
             JOIN users u ON r.uploader_id = u.id
             LEFT JOIN users a ON r.author_id = a.id
             LEFT JOIN resource_stats rs ON r.id = rs.resource_id
-            WHERE 1=1
+            WHERE ($3::BIGINT[] IS NULL OR EXISTS (
+                SELECT 1 FROM resource_courses rc
+                WHERE rc.resource_id = r.id AND rc.course_sn = ANY($3)
+            ))
             "#,
         );
 
-        let mut count_query = String::from("SELECT COUNT(*) FROM resources r WHERE 1=1");
+        let mut count_query = String::from(
+            r#"
+            SELECT COUNT(*) FROM resources r
+            WHERE ($1::BIGINT[] IS NULL OR EXISTS (
+                SELECT 1 FROM resource_courses rc
+                WHERE rc.resource_id = r.id AND rc.course_sn = ANY($1)
+            ))
+            "#,
+        );
 
         // 添加关键词搜索条件
         if let Some(ref kw) = keyword {
             if !kw.is_empty() {
                 let search_pattern = format!("%{}%", kw);
-                query.push_str(" AND (r.title ILIKE $3 OR r.course_name ILIKE $3)");
-                count_query.push_str(" AND (r.title ILIKE $1 OR r.course_name ILIKE $1)");
+                query.push_str(" AND (r.title ILIKE $4 OR r.course_name ILIKE $4)");
+                count_query.push_str(" AND (r.title ILIKE $2 OR r.course_name ILIKE $2)");
 
                 // 获取总数（带关键词）
                 let total: i64 = sqlx::query_scalar(&count_query)
+                    .bind(course_scope)
                     .bind(&search_pattern)
                     .fetch_one(pool)
                     .await
@@ -1387,6 +1439,7 @@ impl AdminService {
                 ))
                 .bind(per_page as i64)
                 .bind(offset as i64)
+                .bind(course_scope)
                 .bind(search_pattern)
                 .fetch_all(pool)
                 .await
@@ -1403,6 +1456,7 @@ impl AdminService {
 
         // 获取总数（不带关键词）
         let total: i64 = sqlx::query_scalar(&count_query)
+            .bind(course_scope)
             .fetch_one(pool)
             .await
             .map_err(|e| AdminError::DatabaseError(e.to_string()))?;
@@ -1414,6 +1468,7 @@ impl AdminService {
         ))
         .bind(per_page as i64)
         .bind(offset as i64)
+        .bind(course_scope)
         .fetch_all(pool)
         .await
         .map_err(|e| AdminError::DatabaseError(e.to_string()))?;
@@ -1426,7 +1481,93 @@ impl AdminService {
         })
     }
 
+    /// 获取重复资源分组（文件哈希相同的资源），资源数多的分组排在前面
+    pub async fn get_duplicate_resource_clusters(
+        pool: &PgPool,
+        page: i32,
+        per_page: i32,
+    ) -> Result<DuplicateResourceClusterListResponse, AdminError> {
+        let offset = (page - 1) * per_page;
+
+        let total: i64 = sqlx::query_scalar(
+            r#"
+            SELECT COUNT(*) FROM (
+                SELECT 1 FROM resources
+                WHERE file_hash IS NOT NULL
+                GROUP BY file_hash
+                HAVING COUNT(*) > 1
+            ) d
+            "#,
+        )
+        .fetch_one(pool)
+        .await
+        .map_err(|e| AdminError::DatabaseError(e.to_string()))?;
+
+        let hashes: Vec<(String, i64)> = sqlx::query_as(
+            r#"
+            SELECT file_hash, COUNT(*) AS resource_count
+            FROM resources
+            WHERE file_hash IS NOT NULL
+            GROUP BY file_hash
+            HAVING COUNT(*) > 1
+            ORDER BY COUNT(*) DESC, MIN(created_at) ASC
+            LIMIT $1 OFFSET $2
+            "#,
+        )
+        .bind(per_page as i64)
+        .bind(offset as i64)
+        .fetch_all(pool)
+        .await
+        .map_err(|e| AdminError::DatabaseError(e.to_string()))?;
+
+        let hash_list: Vec<&str> = hashes.iter().map(|(hash, _)| hash.as_str()).collect();
+        let rows: Vec<DuplicateResourceRow> = sqlx::query_as(
+            r#"
+            SELECT
+                r.file_hash,
+                r.id, r.title, r.course_name, r.resource_type, r.category,
+                r.uploader_id, u.username as uploader_name,
+                r.author_id, a.username as author_name,
+                r.audit_status, r.file_size, r.created_at,
+                rs.views, rs.downloads, rs.likes
+            FROM resources r
+            JOIN users u ON r.uploader_id = u.id
+            LEFT JOIN users a ON r.author_id = a.id
+            LEFT JOIN resource_stats rs ON r.id = rs.resource_id
+            WHERE r.file_hash = ANY($1)
+            ORDER BY r.created_at ASC
+            "#,
+        )
+        .bind(&hash_list)
+        .fetch_all(pool)
+        .await
+        .map_err(|e| AdminError::DatabaseError(e.to_string()))?;
+
+        let mut clusters: Vec<DuplicateResourceCluster> = hashes
+            .into_iter()
+            .map(|(file_hash, resource_count)| DuplicateResourceCluster {
+                file_hash,
+                resource_count,
+                resources: Vec::new(),
+            })
+            .collect();
+        for row in rows {
+            if let Some(cluster) = clusters.iter_mut().find(|c| c.file_hash == row.file_hash) {
+                cluster.resources.push(row.resource);
+            }
+        }
+
+        Ok(DuplicateResourceClusterListResponse {
+            clusters,
+            total,
+            page,
+            per_page,
+        })
+    }
+
     /// 删除收藏夹内的所有资源
+    ///
+    /// 不检查资源上传者，调用方需要具有全站范围的 resource.manage 权限
     pub async fn delete_all_favorite_resources(
         pool: &PgPool,
         user: &CurrentUser,
@@ -1465,7 +1606,7 @@ impl AdminService {
 
         // 逐个删除资源
         for resource_id in resource_ids {
-            match ResourceService::delete_resource(pool, user, storage, resource_id).await {
+            match ResourceService::force_delete_resource(pool, storage, resource_id).await {
                 Ok(_) => {
                     deleted_count += 1;
                 }
@@ -1500,7 +1641,7 @@ impl AdminService {
         storage: &Arc<dyn crate::services::StorageBackend>,
         resource_id: Uuid,
     ) -> Result<RecalculateHashResult, AdminError> {
-        use crate::services::{FileService, StorageBackendType};
+        use crate::services::FileService;
         use crate::config::Config;
 
         // 获取资源信息
@@ -1515,38 +1656,20 @@ impl AdminService {
 
         let old_hash = resource.file_hash.clone();
         let file_path = resource.file_path.clone();
-        let is_oss = resource.storage_type.as_deref() == Some("oss");
+        let storage_type = resource.storage_type.as_deref().unwrap_or("local");
 
         log::info!(
             "[AdminService] 开始重新计算资源hash | resource_id={}, storage_type={}",
             resource_id,
-            if is_oss { "oss" } else { "local" }
+            storage_type
         );
 
         // 根据存储类型选择正确的存储后端
-        let storage_to_use: Arc<dyn crate::services::StorageBackend> = if is_oss {
-            if storage.backend_type() == StorageBackendType::Oss {
-                storage.clone()
-            } else {
-                // 当前是 local 模式，但需要读取 OSS 文件
-                let config = Config::from_env();
-                match crate::services::create_storage_backend(&config) {
-                    Ok(oss_storage) if oss_storage.backend_type() == StorageBackendType::Oss => oss_storage,
-                    _ => return Err(AdminError::ValidationError("无法访问 OSS 存储".to_string())),
-                }
-            }
-        } else {
-            if storage.backend_type() == StorageBackendType::Local {
-                storage.clone()
-            } else {
-                // 当前是 OSS 模式，但需要读取本地文件
-                let config = Config::from_env();
-                match crate::services::create_local_storage(&config) {
-                    Ok(local_storage) => local_storage,
-                    Err(e) => return Err(AdminError::ValidationError(format!("无法访问本地存储: {}", e))),
-                }
-            }
-        };
+        let config = Config::from_env();
+        let storage_to_use =
+            crate::services::storage_for_type(storage, &config, Some(storage_type)).map_err(
+                |e| AdminError::ValidationError(format!("无法访问 {} 存储: {}", storage_type, e)),
+            )?;
 
         // 读取文件内容
         let file_data = match storage_to_use.read_file(&file_path).await {
@@ -1597,9 +1720,14 @@ impl AdminService {
     }
 
     /// 获取用户实名信息
+    ///
+    /// 每次返回实名信息前都会记录审计日志，审计失败时不返回数据
     pub async fn get_user_real_info(
         pool: &PgPool,
+        cipher: Option<&RealInfoCipher>,
         user_id: Uuid,
+        admin_id: Uuid,
+        ip_address: Option<&str>,
     ) -> Result<UserRealInfoResponse, AdminError> {
         // 获取用户基本信息和实名信息
         let row: (String, bool, Option<serde_json::Value>) = sqlx::query_as(
@@ -1613,18 +1741,32 @@ impl AdminService {
 
         let (username, is_verified, real_info_json) = row;
 
-        // 解析实名信息
-        let (real_name, student_id, major, grade) = if let Some(info) = real_info_json {
-            (
-                info.get("real_name").and_then(|v| v.as_str()).map(|s| s.to_string()),
-                info.get("student_id").and_then(|v| v.as_str()).map(|s| s.to_string()),
-                info.get("major").and_then(|v| v.as_str()).map(|s| s.to_string()),
-                info.get("grade").and_then(|v| v.as_str()).map(|s| s.to_string()),
-            )
-        } else {
-            (None, None, None, None)
+        // 解密实名信息
+        let fields = match real_info_json {
+            Some(ref info) => {
+                Self::open_real_info(
+                    pool,
+                    cipher,
+                    user_id,
+                    info,
+                    admin_id,
+                    "user",
+                    user_id,
+                    serde_json::json!({ "username": username }),
+                    ip_address,
+                )
+                .await?
+            }
+            None => RealInfoFields::default(),
         };
 
+        let RealInfoFields {
+            real_name,
+            student_id,
+            major,
+            grade,
+        } = fields;
+
         Ok(UserRealInfoResponse {
             user_id,
             username,
@@ -1635,6 +1777,92 @@ impl AdminService {
             grade,
         })
     }
+
+    /// 获取实名认证申请详情（包含解密后的实名信息）
+    ///
+    /// 与 get_user_real_info 相同，返回实名信息前记录审计日志，审计失败时不返回数据
+    pub async fn get_verification_detail(
+        pool: &PgPool,
+        cipher: Option<&RealInfoCipher>,
+        verification_id: Uuid,
+        admin_id: Uuid,
+        ip_address: Option<&str>,
+        image_base_url: &str,
+    ) -> Result<VerificationDetailResponse, AdminError> {
+        let (record, verification) =
+            VerificationService::get_verification(pool, verification_id, image_base_url)
+                .await
+                .map_err(|e| match e {
+                    VerificationError::NotFound(msg) => AdminError::NotFound(msg),
+                    VerificationError::ValidationError(msg) => AdminError::ValidationError(msg),
+                    e => AdminError::DatabaseError(e.to_string()),
+                })?;
+
+        // 已审核的申请实名信息已清空，解密结果为空时不记录审计日志
+        let RealInfoFields {
+            real_name,
+            student_id,
+            major,
+            grade,
+        } = Self::open_real_info(
+            pool,
+            cipher,
+            record.user_id,
+            &record.real_info,
+            admin_id,
+            "verification",
+            verification_id,
+            serde_json::json!({
+                "user_id": record.user_id,
+                "username": verification.username,
+            }),
+            ip_address,
+        )
+        .await?;
+
+        Ok(VerificationDetailResponse {
+            verification,
+            real_name,
+            student_id,
+            major,
+            grade,
+        })
+    }
+
+    /// 解密实名信息并记录审计日志
+    ///
+    /// 管理员查看实名信息的唯一解密入口，解密结果非空时审计失败则不返回数据
+    #[allow(clippy::too_many_arguments)]
+    async fn open_real_info(
+        pool: &PgPool,
+        cipher: Option<&RealInfoCipher>,
+        user_id: Uuid,
+        real_info: &serde_json::Value,
+        admin_id: Uuid,
+        target_type: &str,
+        target_id: Uuid,
+        details: serde_json::Value,
+        ip_address: Option<&str>,
+    ) -> Result<RealInfoFields, AdminError> {
+        let fields = RealInfoService::open(cipher, user_id, real_info)
+            .map_err(|e| AdminError::DatabaseError(e.to_string()))?;
+
+        if fields != RealInfoFields::default() {
+            AuditLogService::log(
+                pool,
+                Some(admin_id),
+                AuditAction::RealInfoDecrypted,
+                Some(target_type),
+                Some(target_id),
+                Some(details),
+                ip_address,
+            )
+            .await
+            .map_err(|e| AdminError::DatabaseError(format!("记录实名信息审计日志失败: {}", e)))?;
+        }
+
+        Ok(fields)
+    }
 }
 
 /// Hash重新计算结果
@@ -1862,6 +2090,33 @@ pub struct AdminResourceListResponse {
     pub per_page: i32,
 }
 
+/// 重复资源查询结果
+#[derive(Debug, sqlx::FromRow)]
+struct DuplicateResourceRow {
+    file_hash: String,
+    #[sqlx(flatten)]
+    resource: AdminResourceListItem,
+}
+
+/// 重复资源分组：文件内容完全相同的资源，按上传时间先后排列
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DuplicateResourceCluster {
+    pub file_hash: String,
+    pub resource_count: i64,
+    pub resources: Vec<AdminResourceListItem>,
+}
+
+/// 重复资源分组列表响应
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DuplicateResourceClusterListResponse {
+    pub clusters: Vec<DuplicateResourceCluster>,
+    pub total: i64,
+    pub page: i32,
+    pub per_page: i32,
+}
+
 /// 删除收藏夹资源结果
 #[derive(Debug, Serialize)]
 #[serde(rename_all = "camelCase")]
@@ -1,11 +1,13 @@
 use crate::models::resource::AiAuditResult;
 
+use super::moderation_service::{ModerationProvider, ModerationRequest, ModerationTarget};
+
 #[derive(Debug)]
+#[allow(clippy::enum_variant_names)]
 pub enum AiError {
-    #[allow(dead_code)]
     ApiError(String),
-    #[allow(dead_code)]
     TimeoutError(String),
+    ConfigError(String),
 }
 
 impl std::fmt::Display for AiError {
@@ -13,6 +15,7 @@ impl std::fmt::Display for AiError {
         match self {
             AiError::ApiError(msg) => write!(f, "AI API 错误: {}", msg),
             AiError::TimeoutError(msg) => write!(f, "AI 请求超时: {}", msg),
+            AiError::ConfigError(msg) => write!(f, "AI 配置错误: {}", msg),
         }
     }
 }
@@ -24,41 +27,64 @@ pub struct AiService;
 impl AiService {
     /// 审核资源内容
     ///
-    /// 当前阶段：默认返回通过
-    /// 后续阶段：接入真实 AI API
+    /// 审核服务不可用（超时、接口错误）时不阻塞上传，返回未通过并转人工审核
     pub async fn audit_resource(
-        _title: &str,
-        _description: Option<&str>,
-        _file_data: Option<&[u8]>,
+        moderation: &dyn ModerationProvider,
+        title: &str,
+        description: Option<&str>,
+        file_data: Option<&[u8]>,
     ) -> Result<AiAuditResult, AiError> {
-        // TODO: 接入真实 AI 审核服务
-        // 当前阶段默认返回通过
-
-        log::debug!("AI 审核资源: 标题={}, 描述={:?}", _title, _description);
-
-        // 模拟 AI 审核（直接通过）
-        Ok(AiAuditResult {
-            passed: true,
-            reason: None,
-            accuracy_score: Some(0.95), // 模拟高准确度评分
-        })
+        log::debug!(
+            "AI 审核资源: provider={}, 标题={}, 描述={:?}",
+            moderation.provider_type().as_str(),
+            title,
+            description
+        );
+
+        let request = ModerationRequest {
+            target: ModerationTarget::Resource,
+            title: Some(title),
+            text: description,
+            file_data,
+        };
+        Ok(Self::degrade_on_error(moderation.moderate(request).await))
     }
 
-    /// 审核评论内容（预留接口）
+    /// 审核评论内容
     ///
-    /// 当前阶段：默认返回通过
-    #[allow(dead_code)]
-    pub async fn audit_comment(_content: &str) -> Result<AiAuditResult, AiError> {
-        // TODO: 接入真实 AI 评论审核服务
-        // 当前阶段默认返回通过
-
-        log::debug!("AI 审核评论: 内容长度={}", _content.len());
+    /// 审核服务不可用时返回未通过，评论转人工审核
+    pub async fn audit_comment(
+        moderation: &dyn ModerationProvider,
+        content: &str,
+    ) -> Result<AiAuditResult, AiError> {
+        log::debug!(
+            "AI 审核评论: provider={}, 内容长度={}",
+            moderation.provider_type().as_str(),
+            content.len()
+        );
+
+        let request = ModerationRequest {
+            target: ModerationTarget::Comment,
+            title: None,
+            text: Some(content),
+            file_data: None,
+        };
+        Ok(Self::degrade_on_error(moderation.moderate(request).await))
+    }
 
-        Ok(AiAuditResult {
-            passed: true,
-            reason: None,
-            accuracy_score: None,
-        })
+    /// 审核服务出错时降级为人工审核
+    fn degrade_on_error(result: Result<AiAuditResult, AiError>) -> AiAuditResult {
+        match result {
+            Ok(result) => result,
+            Err(e) => {
+                log::warn!("[AI] 内容审核失败，转人工审核 | error={}", e);
+                AiAuditResult {
+                    passed: false,
+                    reason: Some(format!("AI 审核失败，转人工审核: {}", e)),
+                    accuracy_score: None,
+                }
+            }
+        }
     }
 
     /// 获取 AI 不通过原因（用于人工审核参考）（预留接口）
@@ -70,31 +96,40 @@ impl AiService {
         // 当前阶段返回通用原因
         "AI 审核检测到此资源可能包含不合适的内容，建议检查资源内容是否符合社区规范。".to_string()
     }
-
-    /// 推荐资源（预留接口）
-    ///
-    /// 当前阶段：返回空列表
-    /// 后续阶段：接入真实 AI 推荐服务
-    #[allow(dead_code)]
-    pub async fn recommend_resources(
-        _user_id: uuid::Uuid,
-        _resource_type: Option<&str>,
-        _limit: i32,
-    ) -> Result<Vec<uuid::Uuid>, AiError> {
-        // TODO: 接入真实 AI 推荐服务
-        // 当前阶段返回空列表
-        log::debug!("AI 推荐资源: user_id={}", _user_id);
-        Ok(vec![])
-    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::services::moderation_service::{
+        ModerationFuture, ModerationProviderType, NoopModerationProvider,
+    };
+
+    /// 返回固定结果的审核提供方
+    struct FixedModerationProvider(Result<AiAuditResult, fn() -> AiError>);
+
+    impl ModerationProvider for FixedModerationProvider {
+        fn moderate<'a>(&'a self, _request: ModerationRequest<'a>) -> ModerationFuture<'a> {
+            let result = match &self.0 {
+                Ok(result) => Ok(result.clone()),
+                Err(make_error) => Err(make_error()),
+            };
+            Box::pin(async move { result })
+        }
+
+        fn provider_type(&self) -> ModerationProviderType {
+            ModerationProviderType::OpenAi
+        }
+    }
 
     #[tokio::test]
     async fn test_audit_resource_passes() {
-        let result = AiService::audit_resource("测试资源", None, None).await;
+        let provider = FixedModerationProvider(Ok(AiAuditResult {
+            passed: true,
+            reason: None,
+            accuracy_score: Some(0.95),
+        }));
+        let result = AiService::audit_resource(&provider, "测试资源", None, None).await;
         assert!(result.is_ok());
 
         let audit_result = result.unwrap();
@@ -104,7 +139,7 @@ mod tests {
 
     #[tokio::test]
     async fn test_audit_comment_passes() {
-        let result = AiService::audit_comment("这是一条测试评论").await;
+        let result = AiService::audit_comment(&NoopModerationProvider, "这是一条测试评论").await;
         assert!(result.is_ok());
 
         let audit_result = result.unwrap();
@@ -112,12 +147,15 @@ mod tests {
     }
 
     #[tokio::test]
-    async fn test_recommend_resources_empty() {
-        let user_id = uuid::Uuid::new_v4();
-        let result = AiService::recommend_resources(user_id, None, 10).await;
-        assert!(result.is_ok());
-
-        let recommendations = result.unwrap();
-        assert!(recommendations.is_empty());
+    async fn test_audit_timeout_degrades_to_manual_review() {
+        let provider = FixedModerationProvider(Err(|| AiError::TimeoutError("timeout".to_string())));
+        let result = AiService::audit_resource(&provider, "测试资源", None, None)
+            .await
+            .unwrap();
+        assert!(!result.passed);
+        assert!(result.reason.unwrap().contains("AI 请求超时"));
+
+        let result = AiService::audit_comment(&provider, "评论").await.unwrap();
+        assert!(!result.passed);
     }
 }
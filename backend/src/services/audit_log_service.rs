@@ -21,7 +21,25 @@ pub enum AuditAction {
     CreateFavorite,
     UpdateProfile,
     AdminAction,
-    PackDownload, // 打包下载收藏夹
+    PackDownload,           // 打包下载收藏夹
+    LoginFailed,            // 登录失败
+    AccountLocked,          // 连续登录失败导致账号临时锁定
+    RateLimited,            // 请求过于频繁触发限流
+    EmailVerified,          // 邮箱验证成功
+    PasswordResetRequested, // 申请通过邮件重置密码
+    PasswordReset,          // 通过邮件重置密码
+    TwoFactorEnabled,       // 启用两步验证
+    TwoFactorDisabled,      // 关闭两步验证
+    RecoveryCodesRenewed,   // 重新生成两步验证恢复码
+    IdentityLinked,         // 绑定外部身份（单点登录）
+    IdentityUnlinked,       // 解除外部身份绑定
+    RoleCreated,            // 创建角色
+    RoleUpdated,            // 修改角色权限
+    RoleDeleted,            // 删除角色
+    RoleAssigned,           // 为用户分配角色
+    RoleRevoked,            // 取消用户的角色
+    RealInfoDecrypted,      // 管理员查看（解密）用户实名信息
+    UpdateComment,          // 编辑评论
 }
 
 impl ToString for AuditAction {
@@ -42,6 +60,24 @@ impl ToString for AuditAction {
             AuditAction::UpdateProfile => "update_profile".to_string(),
             AuditAction::AdminAction => "admin_action".to_string(),
             AuditAction::PackDownload => "pack_download".to_string(),
+            AuditAction::LoginFailed => "login_failed".to_string(),
+            AuditAction::AccountLocked => "account_locked".to_string(),
+            AuditAction::RateLimited => "rate_limited".to_string(),
+            AuditAction::EmailVerified => "email_verified".to_string(),
+            AuditAction::PasswordResetRequested => "password_reset_requested".to_string(),
+            AuditAction::PasswordReset => "password_reset".to_string(),
+            AuditAction::TwoFactorEnabled => "two_factor_enabled".to_string(),
+            AuditAction::TwoFactorDisabled => "two_factor_disabled".to_string(),
+            AuditAction::RecoveryCodesRenewed => "recovery_codes_regenerated".to_string(),
+            AuditAction::IdentityLinked => "identity_linked".to_string(),
+            AuditAction::IdentityUnlinked => "identity_unlinked".to_string(),
+            AuditAction::RoleCreated => "role_created".to_string(),
+            AuditAction::RoleUpdated => "role_updated".to_string(),
+            AuditAction::RoleDeleted => "role_deleted".to_string(),
+            AuditAction::RoleAssigned => "role_assigned".to_string(),
+            AuditAction::RoleRevoked => "role_revoked".to_string(),
+            AuditAction::RealInfoDecrypted => "real_info_decrypted".to_string(),
+            AuditAction::UpdateComment => "update_comment".to_string(),
         }
     }
 }
@@ -102,6 +138,56 @@ impl AuditLogService {
         .await
     }
 
+    /// 记录登录失败日志（用户名不存在时 user_id 为空）
+    pub async fn log_login_failed(
+        pool: &PgPool,
+        user_id: Option<Uuid>,
+        username: &str,
+        reason: &str,
+        ip_address: Option<&str>,
+    ) -> Result<(), sqlx::Error> {
+        let details = serde_json::json!({
+            "username": username,
+            "reason": reason,
+        });
+
+        Self::log(
+            pool,
+            user_id,
+            AuditAction::LoginFailed,
+            Some("user"),
+            user_id,
+            Some(details),
+            ip_address,
+        )
+        .await
+    }
+
+    /// 记录账号锁定日志
+    pub async fn log_account_locked(
+        pool: &PgPool,
+        user_id: Uuid,
+        username: &str,
+        lockout_secs: u64,
+        ip_address: Option<&str>,
+    ) -> Result<(), sqlx::Error> {
+        let details = serde_json::json!({
+            "username": username,
+            "lockout_secs": lockout_secs,
+        });
+
+        Self::log(
+            pool,
+            Some(user_id),
+            AuditAction::AccountLocked,
+            Some("user"),
+            Some(user_id),
+            Some(details),
+            ip_address,
+        )
+        .await
+    }
+
     /// 记录注册日志
     pub async fn log_register(
         pool: &PgPool,
@@ -382,6 +468,25 @@ impl AuditLogService {
         .await
     }
 
+    /// 记录编辑评论日志
+    pub async fn log_update_comment(
+        pool: &PgPool,
+        user_id: Uuid,
+        comment_id: Uuid,
+        ip_address: Option<&str>,
+    ) -> Result<(), sqlx::Error> {
+        Self::log(
+            pool,
+            Some(user_id),
+            AuditAction::UpdateComment,
+            Some("comment"),
+            Some(comment_id),
+            None,
+            ip_address,
+        )
+        .await
+    }
+
     /// 记录删除评论日志
     pub async fn log_delete_comment(
         pool: &PgPool,
@@ -1,10 +1,20 @@
+use crate::config::Config;
 use crate::models::{
-    AuthResponse, LoginRequest, RegisterRequest, TokenResponse, User, UserInfo, UserRole,
+    AuthResponse, CurrentUser, EmailTokenPurpose, ForgotPasswordRequest, LoginRequest,
+    LoginTwoFactorRequest, RegisterRequest, ResetPasswordRequest, SessionClientInfo,
+    SessionRevokeReason, TokenResponse, TwoFactorChallengeResponse, User, UserInfo, UserRole,
+};
+use crate::services::{
+    check_refresh_token, send_mail_in_background, sender_name, EmailTokenService,
+    LoginAttemptService, LoginLimits, MailMessage, Mailer, MfaMethod, RefreshTokenCheck,
+    SessionService, TotpError, TotpService,
 };
 use crate::utils::{
-    generate_access_token, generate_refresh_token, hash_password, verify_password, verify_token,
+    generate_access_token, generate_mfa_token, generate_refresh_token, hash_password,
+    verify_password, verify_token, FieldCipher, MFA_TOKEN_EXPIRE_MINUTES,
 };
 use sqlx::PgPool;
+use std::sync::Arc;
 use uuid::Uuid;
 
 /// 认证错误类型
@@ -12,9 +22,16 @@ use uuid::Uuid;
 pub enum AuthError {
     InvalidCredentials(String),
     UserExists(String),
-    #[allow(dead_code)]
     UserNotFound(String),
     TokenInvalid(String),
+    /// 已轮换的 Refresh Token 被重复使用，携带用户ID，该用户的全部会话已被吊销
+    TokenReused(Uuid),
+    /// 登录失败次数过多或账号已锁定，携带需要等待的秒数
+    TooManyAttempts(u64),
+    /// 本次登录失败触发了账号锁定，携带用户ID和锁定秒数
+    AccountLocked(Uuid, u64),
+    /// 两步验证密钥加密或解密失败
+    EncryptionError(String),
     DatabaseError(String),
     ValidationError(String),
 }
@@ -26,6 +43,16 @@ impl std::fmt::Display for AuthError {
             AuthError::UserExists(msg) => write!(f, "用户已存在: {}", msg),
             AuthError::UserNotFound(msg) => write!(f, "用户不存在: {}", msg),
             AuthError::TokenInvalid(msg) => write!(f, "Token无效: {}", msg),
+            AuthError::TokenReused(user_id) => {
+                write!(f, "Refresh Token 被重复使用: user_id={}", user_id)
+            }
+            AuthError::TooManyAttempts(secs) => {
+                write!(f, "登录尝试过于频繁，请 {} 秒后重试", secs)
+            }
+            AuthError::AccountLocked(_, secs) => {
+                write!(f, "登录失败次数过多，账号已锁定 {} 秒", secs)
+            }
+            AuthError::EncryptionError(msg) => write!(f, "密钥加密错误: {}", msg),
             AuthError::DatabaseError(msg) => write!(f, "数据库错误: {}", msg),
             AuthError::ValidationError(msg) => write!(f, "验证错误: {}", msg),
         }
@@ -34,6 +61,56 @@ impl std::fmt::Display for AuthError {
 
 impl std::error::Error for AuthError {}
 
+impl From<sqlx::Error> for AuthError {
+    fn from(err: sqlx::Error) -> Self {
+        AuthError::DatabaseError(err.to_string())
+    }
+}
+
+impl From<TotpError> for AuthError {
+    fn from(err: TotpError) -> Self {
+        match err {
+            TotpError::UserNotFound(msg) => AuthError::UserNotFound(msg),
+            TotpError::InvalidCode(msg) => AuthError::InvalidCredentials(msg),
+            TotpError::AlreadyEnabled(msg) | TotpError::NotEnabled(msg) => {
+                AuthError::ValidationError(msg)
+            }
+            TotpError::EncryptionError(msg) => AuthError::EncryptionError(msg),
+            TotpError::DatabaseError(msg) => AuthError::DatabaseError(msg),
+        }
+    }
+}
+
+/// 登录第一步的结果
+#[derive(Debug)]
+pub enum LoginResult {
+    /// 登录成功，已创建会话
+    Authenticated(AuthResponse),
+    /// 密码正确，但用户启用了两步验证，需要提交验证码
+    TwoFactorRequired {
+        user_id: Uuid,
+        challenge: TwoFactorChallengeResponse,
+    },
+}
+
+/// 签发 Token 时从数据库读取的用户状态
+#[derive(Debug, sqlx::FromRow)]
+struct TokenUser {
+    id: Uuid,
+    username: String,
+    role: String,
+    is_verified: bool,
+    is_active: bool,
+    token_version: i32,
+}
+
+/// 轮换已有会话时的参数
+struct SessionRotation<'a> {
+    session_id: Uuid,
+    /// 当前 Refresh Token 的哈希，不为空时轮换前校验
+    expected_hash: Option<&'a str>,
+}
+
 /// 认证服务
 pub struct AuthService;
 
@@ -44,6 +121,7 @@ impl AuthService {
         jwt_secret: &str,
         req: RegisterRequest,
         require_email: bool,
+        client: &SessionClientInfo,
     ) -> Result<AuthResponse, AuthError> {
         // 检查是否强制要求邮箱
         if require_email && req.email.is_none() {
@@ -72,65 +150,20 @@ impl AuthService {
             hash_password(&req.password).map_err(|e| AuthError::ValidationError(e))?;
 
         // 创建用户
-        let user_id = Uuid::new_v4();
-        // 检查用户名是否在ADMIN_USERNAMES环境变量列表中，如果是则赋予admin角色
-        let admin_usernames = std::env::var("ADMIN_USERNAMES").unwrap_or_default();
-        let admin_list: Vec<String> = admin_usernames
-            .split(',')
-            .map(|s| s.trim().to_string())
-            .filter(|s| !s.is_empty())
-            .collect();
-        let role = if admin_list.contains(&req.username) {
-            "admin"
-        } else {
-            "user"
-        };
-
-        // 获取下一个 sn 值
-        let sn: i64 = sqlx::query_scalar("SELECT nextval('user_sn_seq')")
-            .fetch_one(pool)
-            .await
-            .map_err(|e| AuthError::DatabaseError(format!("获取用户编号失败: {}", e)))?;
-
-        sqlx::query(
-            r#"
-            INSERT INTO users (id, sn, username, password_hash, email, role, is_active, created_at, updated_at)
-            VALUES ($1, $2, $3, $4, $5, $6, true, NOW(), NOW())
-            "#
+        let (user_id, sn, role) = Self::create_user(
+            pool,
+            &req.username,
+            &password_hash,
+            req.email.as_deref(),
+            false,
         )
-        .bind(user_id)
-        .bind(sn)
-        .bind(&req.username)
-        .bind(&password_hash)
-        .bind(&req.email)
-        .bind(role)
-        .execute(pool)
-        .await
-        .map_err(|e| AuthError::DatabaseError(format!("创建用户失败: {}", e)))?;
+        .await?;
 
         log::info!("用户注册成功: {}, 角色: {}", req.username, role);
 
-        // 解析角色用于Token生成
-        let user_role = match role {
-            "admin" => UserRole::Admin,
-            "verified" => UserRole::Verified,
-            "user" => UserRole::User,
-            _ => UserRole::Guest,
-        };
-
-        // 生成 Token
-        let access_token = generate_access_token(
-            user_id,
-            req.username.clone(),
-            user_role.clone(),
-            false, // 新注册用户默认未实名认证
-            jwt_secret,
-        )
-        .map_err(|e| AuthError::TokenInvalid(e))?;
-
-        let refresh_token =
-            generate_refresh_token(user_id, req.username.clone(), user_role, false, jwt_secret)
-                .map_err(|e| AuthError::TokenInvalid(e))?;
+        // 创建登录会话并生成 Token
+        let token_user = Self::load_token_user(pool, user_id).await?;
+        let tokens = Self::issue_tokens(pool, jwt_secret, &token_user, None, client, false).await?;
 
         Ok(AuthResponse {
             user: UserInfo {
@@ -138,32 +171,42 @@ impl AuthService {
                 sn: Some(sn),
                 username: req.username,
                 email: req.email,
+                email_verified: false,
                 role: role.to_string(),
                 bio: None,
                 is_verified: false,
                 created_at: chrono::Local::now().naive_local(),
             },
-            tokens: TokenResponse {
-                access_token,
-                refresh_token,
-                token_type: "Bearer".to_string(),
-                expires_in: 60 * 60, // 60分钟
-            },
+            tokens,
         })
     }
 
     /// 用户登录
+    ///
+    /// 用户启用了两步验证时，密码正确后不创建会话，返回临时令牌等待提交验证码
     pub async fn login(
         pool: &PgPool,
         jwt_secret: &str,
         req: LoginRequest,
-    ) -> Result<AuthResponse, AuthError> {
+        client: &SessionClientInfo,
+        limits: Option<&LoginLimits>,
+    ) -> Result<LoginResult, AuthError> {
         // 验证请求
         req.validate().map_err(|e| AuthError::ValidationError(e))?;
 
+        // 账号锁定或处于退避中时，不再校验密码
+        if let Some(limits) = limits {
+            if let Some(retry_after) =
+                LoginAttemptService::check(pool, &req.username, limits).await?
+            {
+                log::warn!("登录被限制: {}, 需等待 {} 秒", req.username, retry_after);
+                return Err(AuthError::TooManyAttempts(retry_after));
+            }
+        }
+
         // 查询用户
         let user: User = sqlx::query_as::<_, User>(
-            "SELECT id, sn, username, password_hash, email, role, bio,
+            "SELECT id, sn, username, password_hash, email, email_verified, role, bio,
                     CASE WHEN social_links = '{}'::jsonb THEN NULL ELSE social_links END as social_links,
                     CASE WHEN real_info = '{}'::jsonb THEN NULL ELSE real_info END as real_info,
                     is_verified, is_active, created_at, updated_at
@@ -181,68 +224,161 @@ impl AuthService {
 
         if !valid {
             log::warn!("登录失败，密码错误: {}", req.username);
+            if let Some(limits) = limits {
+                if let Some(lockout_secs) =
+                    LoginAttemptService::record_failure(pool, user.id, limits).await?
+                {
+                    log::warn!("账号已锁定: {}, {} 秒", req.username, lockout_secs);
+                    return Err(AuthError::AccountLocked(user.id, lockout_secs));
+                }
+            }
             return Err(AuthError::InvalidCredentials(
                 "用户名或密码错误".to_string(),
             ));
         }
 
+        let token_user = Self::load_token_user(pool, user.id).await?;
+
+        // 失败计数在第二步通过后才清零，避免反复提交正确密码绕过验证码的尝试次数限制
+        if TotpService::is_enabled(pool, user.id).await? {
+            log::info!("用户 {} 密码校验通过，等待两步验证", req.username);
+            return Self::two_factor_challenge(&token_user, jwt_secret);
+        }
+
+        if limits.is_some() {
+            LoginAttemptService::reset(pool, user.id).await?;
+        }
+
         log::info!("用户登录成功: {}, 角色: {}", req.username, user.role);
 
-        // 解析角色
-        let role = match user.role.as_str() {
-            "admin" => {
-                log::info!("用户 {} 以管理员身份登录", req.username);
-                UserRole::Admin
+        if user.role == "admin" {
+            log::info!("用户 {} 以管理员身份登录", req.username);
+        }
+
+        // 创建登录会话并生成 Token
+        let tokens = Self::issue_tokens(pool, jwt_secret, &token_user, None, client, false).await?;
+
+        Ok(LoginResult::Authenticated(AuthResponse {
+            user: UserInfo::from(user),
+            tokens,
+        }))
+    }
+
+    /// 登录第二步：校验两步验证码或恢复码，通过后创建已完成两步验证的会话
+    pub async fn login_two_factor(
+        pool: &PgPool,
+        jwt_secret: &str,
+        cipher: Option<&FieldCipher>,
+        req: LoginTwoFactorRequest,
+        client: &SessionClientInfo,
+        limits: Option<&LoginLimits>,
+    ) -> Result<(AuthResponse, MfaMethod), AuthError> {
+        req.validate().map_err(AuthError::ValidationError)?;
+
+        let claims = verify_token(&req.mfa_token, jwt_secret, Some("mfa"))
+            .map_err(|_| AuthError::TokenInvalid("登录已过期，请重新登录".to_string()))?;
+        let user_id = Uuid::parse_str(&claims.sub)
+            .map_err(|_| AuthError::TokenInvalid("无效的用户ID".to_string()))?;
+
+        // 第一步之后用户被禁用或令牌版本变化（如重置了密码）时需要重新登录
+        let token_user = Self::load_token_user(pool, user_id).await?;
+        if !token_user.is_active || token_user.token_version != claims.ver {
+            return Err(AuthError::TokenInvalid(
+                "登录已过期，请重新登录".to_string(),
+            ));
+        }
+
+        if let Some(limits) = limits {
+            if let Some(retry_after) =
+                LoginAttemptService::check(pool, &token_user.username, limits).await?
+            {
+                log::warn!(
+                    "两步验证被限制: {}, 需等待 {} 秒",
+                    token_user.username,
+                    retry_after
+                );
+                return Err(AuthError::TooManyAttempts(retry_after));
             }
-            "verified" => UserRole::Verified,
-            "user" => UserRole::User,
-            _ => UserRole::Guest,
+        }
+
+        let Some(method) = TotpService::verify(pool, cipher, user_id, &req.code).await? else {
+            log::warn!("两步验证失败，验证码错误: {}", token_user.username);
+            if let Some(limits) = limits {
+                if let Some(lockout_secs) =
+                    LoginAttemptService::record_failure(pool, user_id, limits).await?
+                {
+                    log::warn!("账号已锁定: {}, {} 秒", token_user.username, lockout_secs);
+                    return Err(AuthError::AccountLocked(user_id, lockout_secs));
+                }
+            }
+            return Err(AuthError::InvalidCredentials("验证码错误".to_string()));
         };
 
-        // 生成 Token
-        let access_token = generate_access_token(
-            user.id,
-            user.username.clone(),
-            role.clone(),
-            user.is_verified,
-            jwt_secret,
+        if limits.is_some() {
+            LoginAttemptService::reset(pool, user_id).await?;
+        }
+
+        let user: User = sqlx::query_as::<_, User>(
+            "SELECT id, sn, username, password_hash, email, email_verified, role, bio,
+                    CASE WHEN social_links = '{}'::jsonb THEN NULL ELSE social_links END as social_links,
+                    CASE WHEN real_info = '{}'::jsonb THEN NULL ELSE real_info END as real_info,
+                    is_verified, is_active, created_at, updated_at
+             FROM users WHERE id = $1 AND is_active = true"
         )
-        .map_err(|e| AuthError::TokenInvalid(e))?;
+        .bind(user_id)
+        .fetch_optional(pool)
+        .await?
+        .ok_or_else(|| AuthError::TokenInvalid("登录已过期，请重新登录".to_string()))?;
+
+        log::info!(
+            "用户登录成功: {}, 角色: {}, 两步验证方式: {}",
+            user.username,
+            user.role,
+            method.as_str()
+        );
+
+        let tokens = Self::issue_tokens(pool, jwt_secret, &token_user, None, client, true).await?;
+
+        Ok((
+            AuthResponse {
+                user: UserInfo::from(user),
+                tokens,
+            },
+            method,
+        ))
+    }
 
-        let refresh_token = generate_refresh_token(
-            user.id,
-            user.username.clone(),
-            role.clone(),
-            user.is_verified,
-            jwt_secret,
+    /// 校验用户密码（启用、关闭两步验证等敏感操作前再次确认身份）
+    pub async fn confirm_password(
+        pool: &PgPool,
+        user_id: Uuid,
+        password: &str,
+    ) -> Result<(), AuthError> {
+        let password_hash: String = sqlx::query_scalar(
+            "SELECT password_hash FROM users WHERE id = $1 AND is_active = true",
         )
-        .map_err(|e| AuthError::TokenInvalid(e))?;
+        .bind(user_id)
+        .fetch_optional(pool)
+        .await?
+        .ok_or_else(|| AuthError::UserNotFound("用户不存在".to_string()))?;
 
-        Ok(AuthResponse {
-            user: UserInfo {
-                id: user.id,
-                sn: user.sn,
-                username: user.username,
-                email: user.email,
-                role: user.role,
-                bio: user.bio,
-                is_verified: user.is_verified,
-                created_at: user.created_at,
-            },
-            tokens: TokenResponse {
-                access_token,
-                refresh_token,
-                token_type: "Bearer".to_string(),
-                expires_in: 60 * 60, // 60分钟
-            },
-        })
+        let valid = verify_password(password, &password_hash)
+            .map_err(|_| AuthError::InvalidCredentials("密码错误".to_string()))?;
+        if !valid {
+            return Err(AuthError::InvalidCredentials("密码错误".to_string()));
+        }
+        Ok(())
     }
 
     /// 刷新 Token
+    ///
+    /// 每次刷新都会轮换 Refresh Token；已轮换的旧 Token 再次使用时视为泄露，
+    /// 吊销该用户的全部会话。
     pub async fn refresh_token(
-        _pool: &PgPool,
+        pool: &PgPool,
         jwt_secret: &str,
         refresh_token: String,
+        client: &SessionClientInfo,
     ) -> Result<TokenResponse, AuthError> {
         // 验证 Refresh Token
         let claims = verify_token(&refresh_token, jwt_secret, Some("refresh"))
@@ -252,39 +388,500 @@ impl AuthService {
         let user_id = Uuid::parse_str(&claims.sub)
             .map_err(|_| AuthError::TokenInvalid("无效的用户ID".to_string()))?;
 
-        let role = match claims.role.as_str() {
+        // 旧版 Token 没有会话信息，需要重新登录
+        let session_id = claims
+            .sid
+            .ok_or_else(|| AuthError::TokenInvalid("登录会话已失效，请重新登录".to_string()))?;
+
+        let state = SessionService::get_token_state(pool, session_id)
+            .await?
+            .filter(|state| state.user_id == user_id)
+            .ok_or_else(|| AuthError::TokenInvalid("登录会话不存在".to_string()))?;
+
+        let token_hash = SessionService::hash_refresh_token(&refresh_token);
+        match check_refresh_token(&state, &token_hash) {
+            RefreshTokenCheck::Valid => {}
+            RefreshTokenCheck::Inactive => {
+                return Err(AuthError::TokenInvalid("登录会话已失效".to_string()));
+            }
+            RefreshTokenCheck::RecentlyRotated => {
+                return Err(AuthError::TokenInvalid("Token已被刷新".to_string()));
+            }
+            RefreshTokenCheck::Reused => {
+                let revoked = SessionService::revoke_all_sessions(
+                    pool,
+                    user_id,
+                    SessionRevokeReason::ReuseDetected,
+                )
+                .await?;
+                log::warn!(
+                    "检测到 Refresh Token 重复使用，已吊销全部会话: user_id={}, session_id={}, revoked={}",
+                    user_id,
+                    session_id,
+                    revoked
+                );
+                return Err(AuthError::TokenReused(user_id));
+            }
+        }
+
+        // 使用数据库中的最新用户状态签发，角色和认证状态变更可以及时生效
+        let token_user = Self::load_token_user(pool, user_id).await?;
+        if !token_user.is_active {
+            SessionService::revoke_session(
+                pool,
+                user_id,
+                session_id,
+                SessionRevokeReason::UserDisabled,
+            )
+            .await?;
+            return Err(AuthError::TokenInvalid("账号已被禁用".to_string()));
+        }
+        if token_user.token_version != claims.ver {
+            return Err(AuthError::TokenInvalid("登录会话已失效".to_string()));
+        }
+
+        log::info!("刷新 Token: {}", token_user.username);
+
+        let rotation = SessionRotation {
+            session_id,
+            expected_hash: Some(&token_hash),
+        };
+        Self::issue_tokens(pool, jwt_secret, &token_user, Some(rotation), client, false).await
+    }
+
+    /// 为当前会话重新签发 Token（用户角色或认证状态变化后调用）
+    ///
+    /// 旧版 Token 没有会话ID时创建新会话
+    pub async fn reissue_tokens(
+        pool: &PgPool,
+        jwt_secret: &str,
+        user_id: Uuid,
+        session_id: Option<Uuid>,
+        client: &SessionClientInfo,
+    ) -> Result<TokenResponse, AuthError> {
+        let token_user = Self::load_token_user(pool, user_id).await?;
+        let rotation = session_id.map(|session_id| SessionRotation {
+            session_id,
+            expected_hash: None,
+        });
+        Self::issue_tokens(pool, jwt_secret, &token_user, rotation, client, false).await
+    }
+
+    /// 登出：吊销当前会话
+    ///
+    /// 优先使用 Refresh Token 中的会话ID，Refresh Token 无效时使用 Access Token 中的会话ID
+    pub async fn logout(
+        pool: &PgPool,
+        jwt_secret: &str,
+        refresh_token: Option<&str>,
+        current_user: Option<&CurrentUser>,
+    ) -> Result<(), AuthError> {
+        let from_refresh = refresh_token
+            .and_then(|token| verify_token(token, jwt_secret, Some("refresh")).ok())
+            .and_then(|claims| {
+                let user_id = Uuid::parse_str(&claims.sub).ok()?;
+                Some((user_id, claims.sid?))
+            });
+        let from_access =
+            current_user.and_then(|user| user.session_id.map(|session_id| (user.id, session_id)));
+
+        if let Some((user_id, session_id)) = from_refresh.or(from_access) {
+            SessionService::revoke_session(pool, user_id, session_id, SessionRevokeReason::Logout)
+                .await?;
+            log::info!("会话已吊销: user_id={}, session_id={}", user_id, session_id);
+        }
+
+        Ok(())
+    }
+
+    /// 发送邮箱验证邮件，返回收件邮箱
+    ///
+    /// 重新发送时之前的验证链接失效
+    pub async fn send_email_verification(
+        pool: &PgPool,
+        config: &Config,
+        mailer: &Arc<dyn Mailer>,
+        user_id: Uuid,
+    ) -> Result<String, AuthError> {
+        let (email, email_verified): (Option<String>, bool) = sqlx::query_as(
+            "SELECT email, email_verified FROM users WHERE id = $1 AND is_active = true",
+        )
+        .bind(user_id)
+        .fetch_optional(pool)
+        .await?
+        .ok_or_else(|| AuthError::UserNotFound("用户不存在".to_string()))?;
+
+        let email = email
+            .filter(|e| !e.trim().is_empty())
+            .ok_or_else(|| AuthError::ValidationError("尚未绑定邮箱".to_string()))?;
+        if email_verified {
+            return Err(AuthError::ValidationError("邮箱已验证".to_string()));
+        }
+
+        let token = EmailTokenService::issue(
+            pool,
+            &config.jwt_secret,
+            user_id,
+            EmailTokenPurpose::VerifyEmail,
+            &email,
+            config.email_verification_ttl_secs,
+        )
+        .await?;
+        let link = format!("{}/verify-email?token={}", config.frontend_base_url, token);
+        let message = MailMessage::email_verification(
+            sender_name(&config.mail_from),
+            &email,
+            &link,
+            config.email_verification_ttl_secs,
+        );
+        send_mail_in_background(mailer.clone(), message);
+
+        log::info!("已发送邮箱验证邮件: user_id={}", user_id);
+        Ok(email)
+    }
+
+    /// 通过邮件中的链接验证邮箱，返回用户ID
+    pub async fn verify_email(
+        pool: &PgPool,
+        jwt_secret: &str,
+        token: &str,
+    ) -> Result<Uuid, AuthError> {
+        let mut tx = pool.begin().await?;
+
+        let used =
+            EmailTokenService::consume(&mut *tx, jwt_secret, token, EmailTokenPurpose::VerifyEmail)
+                .await?
+                .ok_or_else(|| AuthError::TokenInvalid("验证链接无效或已过期".to_string()))?;
+
+        // 发送验证邮件后又修改了邮箱，旧链接不能验证新邮箱
+        let result = sqlx::query(
+            r#"
+            UPDATE users
+            SET email_verified = true, email_verified_at = NOW(), updated_at = NOW()
+            WHERE id = $1 AND is_active = true AND LOWER(email) = LOWER($2)
+            "#,
+        )
+        .bind(used.user_id)
+        .bind(&used.email)
+        .execute(&mut *tx)
+        .await?;
+        if result.rows_affected() == 0 {
+            return Err(AuthError::TokenInvalid(
+                "邮箱已变更，请重新发送验证邮件".to_string(),
+            ));
+        }
+
+        tx.commit().await?;
+        log::info!("邮箱验证成功: user_id={}", used.user_id);
+        Ok(used.user_id)
+    }
+
+    /// 申请重置密码
+    ///
+    /// 只向已验证的邮箱发送重置邮件。邮箱不存在时同样返回成功，避免泄露注册信息；
+    /// 返回 Some(用户ID) 表示已发送邮件。
+    pub async fn request_password_reset(
+        pool: &PgPool,
+        config: &Config,
+        mailer: &Arc<dyn Mailer>,
+        req: ForgotPasswordRequest,
+    ) -> Result<Option<Uuid>, AuthError> {
+        req.validate().map_err(AuthError::ValidationError)?;
+
+        let user: Option<(Uuid, String, String)> = sqlx::query_as(
+            r#"
+            SELECT id, username, email
+            FROM users
+            WHERE LOWER(email) = LOWER($1) AND email_verified = true AND is_active = true
+            ORDER BY email_verified_at DESC
+            LIMIT 1
+            "#,
+        )
+        .bind(req.email.trim())
+        .fetch_optional(pool)
+        .await?;
+
+        let Some((user_id, username, email)) = user else {
+            log::info!("申请重置密码的邮箱未绑定或未验证");
+            return Ok(None);
+        };
+
+        let token = EmailTokenService::issue(
+            pool,
+            &config.jwt_secret,
+            user_id,
+            EmailTokenPurpose::ResetPassword,
+            &email,
+            config.password_reset_ttl_secs,
+        )
+        .await?;
+        let link = format!(
+            "{}/reset-password?token={}",
+            config.frontend_base_url, token
+        );
+        let message = MailMessage::password_reset(
+            sender_name(&config.mail_from),
+            &email,
+            &username,
+            &link,
+            config.password_reset_ttl_secs,
+        );
+        send_mail_in_background(mailer.clone(), message);
+
+        log::info!("已发送密码重置邮件: user_id={}", user_id);
+        Ok(Some(user_id))
+    }
+
+    /// 通过邮件中的链接重置密码，返回用户ID
+    ///
+    /// 重置后清除登录失败记录，并吊销该用户的全部会话
+    pub async fn reset_password(
+        pool: &PgPool,
+        jwt_secret: &str,
+        req: ResetPasswordRequest,
+    ) -> Result<Uuid, AuthError> {
+        req.validate().map_err(AuthError::ValidationError)?;
+        let password_hash = hash_password(&req.new_password).map_err(AuthError::ValidationError)?;
+
+        let mut tx = pool.begin().await?;
+
+        let used = EmailTokenService::consume(
+            &mut *tx,
+            jwt_secret,
+            &req.token,
+            EmailTokenPurpose::ResetPassword,
+        )
+        .await?
+        .ok_or_else(|| AuthError::TokenInvalid("重置链接无效或已过期".to_string()))?;
+
+        let result = sqlx::query(
+            r#"
+            UPDATE users
+            SET password_hash = $1,
+                failed_login_attempts = 0,
+                last_failed_login_at = NULL,
+                locked_until = NULL,
+                updated_at = NOW()
+            WHERE id = $2 AND is_active = true AND LOWER(email) = LOWER($3)
+            "#,
+        )
+        .bind(&password_hash)
+        .bind(used.user_id)
+        .bind(&used.email)
+        .execute(&mut *tx)
+        .await?;
+        if result.rows_affected() == 0 {
+            return Err(AuthError::TokenInvalid("重置链接无效或已过期".to_string()));
+        }
+
+        tx.commit().await?;
+
+        SessionService::revoke_all_sessions(pool, used.user_id, SessionRevokeReason::PasswordReset)
+            .await?;
+
+        log::info!("密码已通过邮件重置: user_id={}", used.user_id);
+        Ok(used.user_id)
+    }
+
+    /// 创建用户，返回用户ID、编号和角色
+    ///
+    /// 用户名在 ADMIN_USERNAMES 列表中时赋予管理员角色；`password_hash` 为空表示未设置密码
+    /// （单点登录自动注册的账号），此时只能通过外部身份或重置密码登录
+    pub async fn create_user(
+        pool: &PgPool,
+        username: &str,
+        password_hash: &str,
+        email: Option<&str>,
+        email_verified: bool,
+    ) -> Result<(Uuid, i64, &'static str), AuthError> {
+        let user_id = Uuid::new_v4();
+        // 检查用户名是否在ADMIN_USERNAMES环境变量列表中，如果是则赋予admin角色
+        let admin_usernames = std::env::var("ADMIN_USERNAMES").unwrap_or_default();
+        let admin_list: Vec<String> = admin_usernames
+            .split(',')
+            .map(|s| s.trim().to_string())
+            .filter(|s| !s.is_empty())
+            .collect();
+        let role = if admin_list.iter().any(|admin| admin == username) {
+            "admin"
+        } else {
+            "user"
+        };
+
+        // 获取下一个 sn 值
+        let sn: i64 = sqlx::query_scalar("SELECT nextval('user_sn_seq')")
+            .fetch_one(pool)
+            .await
+            .map_err(|e| AuthError::DatabaseError(format!("获取用户编号失败: {}", e)))?;
+
+        sqlx::query(
+            r#"
+            INSERT INTO users (id, sn, username, password_hash, email, email_verified, email_verified_at, role, is_active, created_at, updated_at)
+            VALUES ($1, $2, $3, $4, $5, $6, CASE WHEN $6 THEN NOW() END, $7, true, NOW(), NOW())
+            "#
+        )
+        .bind(user_id)
+        .bind(sn)
+        .bind(username)
+        .bind(password_hash)
+        .bind(email)
+        .bind(email_verified)
+        .bind(role)
+        .execute(pool)
+        .await
+        .map_err(|e| AuthError::DatabaseError(format!("创建用户失败: {}", e)))?;
+
+        Ok((user_id, sn, role))
+    }
+
+    /// 外部身份（单点登录）认证通过后登录
+    ///
+    /// 与密码登录相同，用户启用了两步验证时需要继续提交验证码
+    pub async fn login_external(
+        pool: &PgPool,
+        jwt_secret: &str,
+        user_id: Uuid,
+        client: &SessionClientInfo,
+    ) -> Result<LoginResult, AuthError> {
+        let user: User = sqlx::query_as::<_, User>(
+            "SELECT id, sn, username, password_hash, email, email_verified, role, bio,
+                    CASE WHEN social_links = '{}'::jsonb THEN NULL ELSE social_links END as social_links,
+                    CASE WHEN real_info = '{}'::jsonb THEN NULL ELSE real_info END as real_info,
+                    is_verified, is_active, created_at, updated_at
+             FROM users WHERE id = $1 AND is_active = true"
+        )
+        .bind(user_id)
+        .fetch_optional(pool)
+        .await?
+        .ok_or_else(|| AuthError::UserNotFound("用户不存在或已被禁用".to_string()))?;
+
+        let token_user = Self::load_token_user(pool, user_id).await?;
+        if TotpService::is_enabled(pool, user_id).await? {
+            log::info!("用户 {} 单点登录通过，等待两步验证", user.username);
+            return Self::two_factor_challenge(&token_user, jwt_secret);
+        }
+
+        log::info!("用户单点登录成功: {}, 角色: {}", user.username, user.role);
+
+        let tokens = Self::issue_tokens(pool, jwt_secret, &token_user, None, client, false).await?;
+        Ok(LoginResult::Authenticated(AuthResponse {
+            user: UserInfo::from(user),
+            tokens,
+        }))
+    }
+
+    /// 第一步认证通过，签发两步验证临时令牌
+    fn two_factor_challenge(
+        token_user: &TokenUser,
+        jwt_secret: &str,
+    ) -> Result<LoginResult, AuthError> {
+        let mfa_token = generate_mfa_token(
+            token_user.id,
+            token_user.username.clone(),
+            token_user.token_version,
+            jwt_secret,
+        )
+        .map_err(AuthError::TokenInvalid)?;
+        Ok(LoginResult::TwoFactorRequired {
+            user_id: token_user.id,
+            challenge: TwoFactorChallengeResponse {
+                mfa_required: true,
+                mfa_token,
+                expires_in: MFA_TOKEN_EXPIRE_MINUTES * 60,
+            },
+        })
+    }
+
+    /// 读取签发 Token 所需的用户状态
+    async fn load_token_user(pool: &PgPool, user_id: Uuid) -> Result<TokenUser, AuthError> {
+        sqlx::query_as::<_, TokenUser>(
+            "SELECT id, username, role, is_verified, is_active, token_version FROM users WHERE id = $1",
+        )
+        .bind(user_id)
+        .fetch_optional(pool)
+        .await?
+        .ok_or_else(|| AuthError::TokenInvalid("用户不存在".to_string()))
+    }
+
+    /// 生成 Token 对，并创建新会话或轮换已有会话
+    ///
+    /// `mfa_verified` 只在创建新会话时使用，轮换时保留会话原有的两步验证状态
+    async fn issue_tokens(
+        pool: &PgPool,
+        jwt_secret: &str,
+        user: &TokenUser,
+        rotation: Option<SessionRotation<'_>>,
+        client: &SessionClientInfo,
+        mfa_verified: bool,
+    ) -> Result<TokenResponse, AuthError> {
+        let role = match user.role.as_str() {
             "admin" => UserRole::Admin,
             "verified" => UserRole::Verified,
             "user" => UserRole::User,
             _ => UserRole::Guest,
         };
-
-        log::info!("刷新 Token: {}", claims.username);
+        let session_id = rotation
+            .as_ref()
+            .map(|rotation| rotation.session_id)
+            .unwrap_or_else(Uuid::new_v4);
 
         // 生成新的 Token 对
         let access_token = generate_access_token(
-            user_id,
-            claims.username.clone(),
+            user.id,
+            user.username.clone(),
             role.clone(),
-            claims.is_verified,
+            user.is_verified,
+            session_id,
+            user.token_version,
             jwt_secret,
         )
-        .map_err(|e| AuthError::TokenInvalid(e))?;
+        .map_err(AuthError::TokenInvalid)?;
 
         let refresh_token = generate_refresh_token(
-            user_id,
-            claims.username,
+            user.id,
+            user.username.clone(),
             role,
-            claims.is_verified,
+            user.is_verified,
+            session_id,
+            user.token_version,
             jwt_secret,
         )
-        .map_err(|e| AuthError::TokenInvalid(e))?;
+        .map_err(AuthError::TokenInvalid)?;
+
+        let token_hash = SessionService::hash_refresh_token(&refresh_token);
+        match rotation {
+            Some(rotation) => {
+                let rotated = SessionService::rotate_session(
+                    pool,
+                    session_id,
+                    user.id,
+                    rotation.expected_hash,
+                    &token_hash,
+                    client,
+                )
+                .await?;
+                if !rotated {
+                    return Err(AuthError::TokenInvalid("登录会话已失效".to_string()));
+                }
+            }
+            None => {
+                SessionService::create_session(
+                    pool,
+                    session_id,
+                    user.id,
+                    &token_hash,
+                    client,
+                    mfa_verified,
+                )
+                .await?;
+            }
+        }
 
         Ok(TokenResponse {
             access_token,
             refresh_token,
             token_type: "Bearer".to_string(),
-            expires_in: 15 * 60, // 15分钟
+            expires_in: 60 * 60, // 60分钟
         })
     }
 }
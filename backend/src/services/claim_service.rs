@@ -0,0 +1,486 @@
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::models::{
+    Claim, ClaimListQuery, ClaimListResponse, ClaimProofImage, ClaimResponse, ClaimStatus,
+    CreateClaimRequest, ReviewClaimRequest,
+};
+use crate::services::{NotificationService, ResourceError};
+
+/// 申领查询结果（包含资源标题和申领人用户名）
+#[derive(Debug, sqlx::FromRow)]
+struct ClaimRow {
+    #[sqlx(flatten)]
+    claim: Claim,
+    resource_title: Option<String>,
+    applicant_name: Option<String>,
+}
+
+/// 申领列表查询字段
+const CLAIM_SELECT_SQL: &str = r#"
+    SELECT
+        c.id, c.resource_id, c.applicant_id, c.claim_type, c.reason, c.proof_files,
+        c.status, c.reviewer_id, c.reviewed_at, c.review_comment, c.created_at,
+        r.title AS resource_title,
+        u.username AS applicant_name
+    FROM claims c
+    LEFT JOIN resources r ON c.resource_id = r.id
+    LEFT JOIN users u ON c.applicant_id = u.id
+"#;
+
+pub struct ClaimService;
+
+impl ClaimService {
+    /// 提交资源申领
+    pub async fn create_claim(
+        pool: &PgPool,
+        resource_id: Uuid,
+        applicant_id: Uuid,
+        request: CreateClaimRequest,
+        image_base_url: &str,
+    ) -> Result<ClaimResponse, ResourceError> {
+        request.validate().map_err(ResourceError::ValidationError)?;
+
+        // 检查资源是否存在
+        let resource = sqlx::query_as::<_, (Uuid, Option<Uuid>, String)>(
+            "SELECT uploader_id, author_id, audit_status FROM resources WHERE id = $1",
+        )
+        .bind(resource_id)
+        .fetch_optional(pool)
+        .await?;
+
+        let (uploader_id, author_id, audit_status) =
+            resource.ok_or_else(|| ResourceError::NotFound("资源不存在".to_string()))?;
+
+        if audit_status != "approved" {
+            return Err(ResourceError::ValidationError(
+                "资源尚未通过审核，暂不可申领".to_string(),
+            ));
+        }
+
+        // 作者为空时上传者即视为作者
+        if author_id.unwrap_or(uploader_id) == applicant_id {
+            return Err(ResourceError::Conflict("您已是该资源的作者".to_string()));
+        }
+
+        // 同一用户对同一资源只能有一个待审核申领
+        let has_pending = sqlx::query_scalar::<_, bool>(
+            r#"
+            SELECT EXISTS(
+                SELECT 1 FROM claims
+                WHERE resource_id = $1 AND applicant_id = $2 AND status = 'pending'
+            )
+            "#,
+        )
+        .bind(resource_id)
+        .bind(applicant_id)
+        .fetch_one(pool)
+        .await?;
+
+        if has_pending {
+            return Err(ResourceError::Conflict(
+                "您已提交过该资源的申领，请等待审核".to_string(),
+            ));
+        }
+
+        // 证明图片必须是申领人自己在图床上传的图片
+        let mut proof_image_ids = request.proof_image_ids.clone();
+        proof_image_ids.sort();
+        proof_image_ids.dedup();
+
+        let owned_count = sqlx::query_scalar::<_, i64>(
+            "SELECT COUNT(*) FROM images WHERE id = ANY($1) AND uploader_id = $2",
+        )
+        .bind(&proof_image_ids)
+        .bind(applicant_id)
+        .fetch_one(pool)
+        .await?;
+
+        if owned_count != proof_image_ids.len() as i64 {
+            return Err(ResourceError::ValidationError(
+                "证明图片不存在或不属于当前用户".to_string(),
+            ));
+        }
+
+        // 保持用户提交时的图片顺序
+        let mut ordered_ids: Vec<Uuid> = Vec::with_capacity(proof_image_ids.len());
+        for id in &request.proof_image_ids {
+            if !ordered_ids.contains(id) {
+                ordered_ids.push(*id);
+            }
+        }
+        let proof_files = serde_json::Value::Array(
+            ordered_ids
+                .iter()
+                .map(|id| serde_json::Value::String(id.to_string()))
+                .collect(),
+        );
+
+        let claim = sqlx::query_as::<_, Claim>(
+            r#"
+            INSERT INTO claims (resource_id, applicant_id, claim_type, reason, proof_files, status)
+            VALUES ($1, $2, $3, $4, $5, 'pending')
+            RETURNING id, resource_id, applicant_id, claim_type, reason, proof_files,
+                      status, reviewer_id, reviewed_at, review_comment, created_at
+            "#,
+        )
+        .bind(resource_id)
+        .bind(applicant_id)
+        .bind(request.claim_type().as_str())
+        .bind(request.reason.trim())
+        .bind(proof_files)
+        .fetch_one(pool)
+        .await
+        .map_err(|e| match e {
+            // 并发提交时由待审核申领唯一索引兜底
+            sqlx::Error::Database(ref db) if db.is_unique_violation() => {
+                ResourceError::Conflict("您已提交过该资源的申领，请等待审核".to_string())
+            }
+            e => ResourceError::DatabaseError(e.to_string()),
+        })?;
+
+        log::info!(
+            "[ClaimService] 申领已提交: claim_id={}, resource_id={}, applicant_id={}",
+            claim.id,
+            resource_id,
+            applicant_id
+        );
+
+        Self::get_claim(pool, claim.id, image_base_url).await
+    }
+
+    /// 获取单个申领详情
+    pub async fn get_claim(
+        pool: &PgPool,
+        claim_id: Uuid,
+        image_base_url: &str,
+    ) -> Result<ClaimResponse, ResourceError> {
+        let sql = format!("{} WHERE c.id = $1", CLAIM_SELECT_SQL);
+        let row = sqlx::query_as::<_, ClaimRow>(&sql)
+            .bind(claim_id)
+            .fetch_optional(pool)
+            .await?
+            .ok_or_else(|| ResourceError::NotFound("申领不存在".to_string()))?;
+
+        Ok(Self::build_response(row, image_base_url))
+    }
+
+    /// 获取当前用户提交的申领列表
+    pub async fn get_user_claims(
+        pool: &PgPool,
+        applicant_id: Uuid,
+        query: ClaimListQuery,
+        image_base_url: &str,
+    ) -> Result<ClaimListResponse, ResourceError> {
+        Self::list_claims(pool, Some(applicant_id), query, image_base_url).await
+    }
+
+    /// 获取全部申领列表（管理员）
+    pub async fn get_claim_list(
+        pool: &PgPool,
+        query: ClaimListQuery,
+        image_base_url: &str,
+    ) -> Result<ClaimListResponse, ResourceError> {
+        Self::list_claims(pool, None, query, image_base_url).await
+    }
+
+    async fn list_claims(
+        pool: &PgPool,
+        applicant_id: Option<Uuid>,
+        query: ClaimListQuery,
+        image_base_url: &str,
+    ) -> Result<ClaimListResponse, ResourceError> {
+        let page = query.page.unwrap_or(1).max(1);
+        let per_page = query.per_page.unwrap_or(20).clamp(1, 100);
+        let offset = (page - 1) * per_page;
+
+        let status = match query.status.as_deref() {
+            Some(s) if !s.is_empty() => Some(
+                ClaimStatus::from_str(s)
+                    .ok_or_else(|| {
+                        ResourceError::ValidationError(format!("无效的申领状态: {}", s))
+                    })?
+                    .as_str(),
+            ),
+            _ => None,
+        };
+
+        let total = sqlx::query_scalar::<_, i64>(
+            r#"
+            SELECT COUNT(*) FROM claims c
+            WHERE ($1::uuid IS NULL OR c.applicant_id = $1)
+                AND ($2::varchar IS NULL OR c.status = $2)
+            "#,
+        )
+        .bind(applicant_id)
+        .bind(status)
+        .fetch_one(pool)
+        .await?;
+
+        let sql = format!(
+            r#"{}
+            WHERE ($1::uuid IS NULL OR c.applicant_id = $1)
+                AND ($2::varchar IS NULL OR c.status = $2)
+            ORDER BY c.created_at DESC
+            LIMIT $3 OFFSET $4
+            "#,
+            CLAIM_SELECT_SQL
+        );
+        let rows = sqlx::query_as::<_, ClaimRow>(&sql)
+            .bind(applicant_id)
+            .bind(status)
+            .bind(per_page)
+            .bind(offset)
+            .fetch_all(pool)
+            .await?;
+
+        let claims = rows
+            .into_iter()
+            .map(|row| Self::build_response(row, image_base_url))
+            .collect();
+
+        Ok(ClaimListResponse {
+            claims,
+            total,
+            page,
+            per_page,
+        })
+    }
+
+    /// 审核申领（管理员）
+    /// 通过时将资源作者设置为申领人，并自动驳回该资源的其他待审核申领
+    pub async fn review_claim(
+        pool: &PgPool,
+        claim_id: Uuid,
+        reviewer_id: Uuid,
+        request: ReviewClaimRequest,
+        image_base_url: &str,
+    ) -> Result<ClaimResponse, ResourceError> {
+        let status = match ClaimStatus::from_str(&request.status) {
+            Some(s @ (ClaimStatus::Approved | ClaimStatus::Rejected)) => s,
+            _ => {
+                return Err(ResourceError::ValidationError(
+                    "状态必须是 approved 或 rejected".to_string(),
+                ))
+            }
+        };
+        let comment = request
+            .comment
+            .as_deref()
+            .map(str::trim)
+            .filter(|c| !c.is_empty())
+            .map(str::to_string);
+
+        let mut tx = pool.begin().await?;
+
+        // 锁定申领记录，防止并发审核
+        let claim = sqlx::query_as::<_, Claim>(
+            r#"
+            SELECT id, resource_id, applicant_id, claim_type, reason, proof_files,
+                   status, reviewer_id, reviewed_at, review_comment, created_at
+            FROM claims
+            WHERE id = $1
+            FOR UPDATE
+            "#,
+        )
+        .bind(claim_id)
+        .fetch_optional(&mut *tx)
+        .await?
+        .ok_or_else(|| ResourceError::NotFound("申领不存在".to_string()))?;
+
+        if claim.status.as_deref() != Some(ClaimStatus::Pending.as_str()) {
+            return Err(ResourceError::Conflict("该申领已被处理".to_string()));
+        }
+
+        sqlx::query(
+            r#"
+            UPDATE claims
+            SET status = $1, reviewer_id = $2, reviewed_at = NOW(), review_comment = $3
+            WHERE id = $4
+            "#,
+        )
+        .bind(status.as_str())
+        .bind(reviewer_id)
+        .bind(&comment)
+        .bind(claim_id)
+        .execute(&mut *tx)
+        .await?;
+
+        // 被连带驳回的其他申领人
+        let mut superseded_applicants: Vec<Uuid> = Vec::new();
+
+        if status == ClaimStatus::Approved {
+            let result = sqlx::query(
+                "UPDATE resources SET author_id = $1, updated_at = NOW() WHERE id = $2",
+            )
+            .bind(claim.applicant_id)
+            .bind(claim.resource_id)
+            .execute(&mut *tx)
+            .await?;
+
+            if result.rows_affected() == 0 {
+                return Err(ResourceError::NotFound("资源不存在".to_string()));
+            }
+
+            superseded_applicants = sqlx::query_scalar::<_, Uuid>(
+                r#"
+                UPDATE claims
+                SET status = 'rejected', reviewer_id = $1, reviewed_at = NOW(),
+                    review_comment = '该资源已被其他用户申领成功'
+                WHERE resource_id = $2 AND status = 'pending' AND id <> $3
+                RETURNING applicant_id
+                "#,
+            )
+            .bind(reviewer_id)
+            .bind(claim.resource_id)
+            .bind(claim_id)
+            .fetch_all(&mut *tx)
+            .await?;
+        }
+
+        tx.commit().await?;
+
+        log::info!(
+            "[ClaimService] 申领审核完成: claim_id={}, resource_id={}, status={}",
+            claim_id,
+            claim.resource_id,
+            status.as_str()
+        );
+
+        let response = Self::get_claim(pool, claim_id, image_base_url).await?;
+        let resource_title = response.resource_title.clone().unwrap_or_default();
+
+        // 通知申领人审核结果
+        if let Err(e) = NotificationService::create_claim_result_notification(
+            pool,
+            claim.resource_id,
+            &resource_title,
+            claim.applicant_id,
+            status == ClaimStatus::Approved,
+            comment.as_deref(),
+        )
+        .await
+        {
+            log::warn!("[ClaimService] 发送申领结果通知失败: {}", e);
+        }
+
+        for applicant_id in superseded_applicants {
+            if let Err(e) = NotificationService::create_claim_result_notification(
+                pool,
+                claim.resource_id,
+                &resource_title,
+                applicant_id,
+                false,
+                Some("该资源已被其他用户申领成功"),
+            )
+            .await
+            {
+                log::warn!("[ClaimService] 发送申领结果通知失败: {}", e);
+            }
+        }
+
+        Ok(response)
+    }
+
+    fn build_response(row: ClaimRow, image_base_url: &str) -> ClaimResponse {
+        let base_url = image_base_url.trim_end_matches('/');
+        let proof_images = row
+            .claim
+            .proof_image_ids()
+            .into_iter()
+            .map(|id| ClaimProofImage {
+                id,
+                url: format!("{}/images/{}", base_url, id),
+            })
+            .collect();
+
+        let claim = row.claim;
+        ClaimResponse {
+            id: claim.id,
+            resource_id: claim.resource_id,
+            resource_title: row.resource_title,
+            applicant_id: claim.applicant_id,
+            applicant_name: row.applicant_name,
+            claim_type: claim.claim_type.unwrap_or_else(|| "author".to_string()),
+            reason: claim.reason,
+            proof_images,
+            status: claim.status.unwrap_or_else(|| "pending".to_string()),
+            reviewer_id: claim.reviewer_id,
+            review_comment: claim.review_comment,
+            reviewed_at: claim
+                .reviewed_at
+                .map(|dt| dt.format("%Y-%m-%dT%H:%M:%S%.3fZ").to_string()),
+            created_at: claim
+                .created_at
+                .format("%Y-%m-%dT%H:%M:%S%.3fZ")
+                .to_string(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{create_resource, create_user, init_schema};
+
+    #[sqlx::test(migrations = false)]
+    async fn test_concurrent_claims_keep_single_pending(pool: PgPool) {
+        init_schema(&pool).await;
+        let uploader = create_user(&pool, "alice").await;
+        let applicant = create_user(&pool, "bob").await;
+        let resource_id = create_resource(&pool, uploader, "高数笔记").await;
+        let image_id: Uuid =
+            sqlx::query_scalar("INSERT INTO images (uploader_id) VALUES ($1) RETURNING id")
+                .bind(applicant)
+                .fetch_one(&pool)
+                .await
+                .unwrap();
+
+        let tasks: Vec<_> = (0..6)
+            .map(|_| {
+                let pool = pool.clone();
+                tokio::spawn(async move {
+                    let request = CreateClaimRequest {
+                        claim_type: None,
+                        reason: "我是原作者".to_string(),
+                        proof_image_ids: vec![image_id],
+                    };
+                    ClaimService::create_claim(&pool, resource_id, applicant, request, "").await
+                })
+            })
+            .collect();
+
+        let mut created = 0;
+        for task in tasks {
+            match task.await.unwrap() {
+                Ok(_) => created += 1,
+                Err(ResourceError::Conflict(_)) => {}
+                Err(e) => panic!("unexpected error: {}", e),
+            }
+        }
+        assert_eq!(created, 1);
+
+        let pending: i64 =
+            sqlx::query_scalar("SELECT COUNT(*) FROM claims WHERE status = 'pending'")
+                .fetch_one(&pool)
+                .await
+                .unwrap();
+        assert_eq!(pending, 1);
+
+        // 待审核申领处理后可以再次申领
+        sqlx::query("UPDATE claims SET status = 'rejected'")
+            .execute(&pool)
+            .await
+            .unwrap();
+        let request = CreateClaimRequest {
+            claim_type: None,
+            reason: "补充证明".to_string(),
+            proof_image_ids: vec![image_id],
+        };
+        assert!(
+            ClaimService::create_claim(&pool, resource_id, applicant, request, "")
+                .await
+                .is_ok()
+        );
+    }
+}
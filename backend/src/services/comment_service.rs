@@ -1,13 +1,122 @@
+use std::collections::HashSet;
+use std::sync::Arc;
+
+use chrono::NaiveDateTime;
 use sqlx::PgPool;
 use uuid::Uuid;
 
 use crate::models::{
-    Comment, CommentListQuery, CommentListResponse, CommentResponse, CreateCommentRequest,
+    extract_mentions, Comment, CommentEditHistoryResponse, CommentEditItem, CommentListQuery,
+    CommentListResponse, CommentResponse, CommentSortMode, CommentVoteResponse,
+    CreateCommentRequest, MarkHelpfulRequest, UpdateCommentRequest, VoteCommentRequest,
+    DEFAULT_REPLY_PREVIEW, MAX_REPLY_PREVIEW,
 };
-use crate::services::{NotificationService, ResourceError};
+use crate::services::{AiService, ModerationProvider, NotificationService, ResourceError};
 
 pub struct CommentService;
 
+/// 评论查询字段（包含作者、被回复者和楼层回复数）
+const COMMENT_COLUMNS: &str = r#"
+    c.id, c.resource_id, c.user_id, c.parent_id, c.root_id, c.content, c.is_deleted,
+    c.edited_at, c.upvotes, c.downvotes, c.is_helpful, c.created_at,
+    u.username AS user_name,
+    u.avatar_url AS user_avatar,
+    pu.username AS reply_to_user_name,
+    (SELECT COUNT(*) FROM comments r
+     WHERE r.root_id = c.id AND r.audit_status = 'approved' AND r.is_deleted = false) AS reply_count
+"#;
+
+/// 评论查询关联表
+const COMMENT_JOINS: &str = r#"
+    FROM comments c
+    JOIN users u ON c.user_id = u.id
+    LEFT JOIN comments p ON c.parent_id = p.id
+    LEFT JOIN users pu ON p.user_id = pu.id
+"#;
+
+/// 公开可见的顶层评论：已删除的评论仅在楼层内仍有回复时保留占位
+const VISIBLE_ROOT_CONDITION: &str = r#"
+    c.parent_id IS NULL AND c.audit_status = 'approved'
+    AND (c.is_deleted = false OR EXISTS (
+        SELECT 1 FROM comments r
+        WHERE r.root_id = c.id AND r.audit_status = 'approved' AND r.is_deleted = false
+    ))
+"#;
+
+/// 评论查询结果
+#[derive(Debug, sqlx::FromRow)]
+struct CommentRow {
+    id: Uuid,
+    resource_id: Uuid,
+    user_id: Uuid,
+    parent_id: Option<Uuid>,
+    root_id: Option<Uuid>,
+    content: String,
+    is_deleted: bool,
+    edited_at: Option<NaiveDateTime>,
+    upvotes: i32,
+    downvotes: i32,
+    is_helpful: bool,
+    created_at: Option<NaiveDateTime>,
+    user_name: String,
+    user_avatar: Option<String>,
+    reply_to_user_name: Option<String>,
+    reply_count: i64,
+}
+
+/// 顶层评论排序子句
+fn order_clause(sort: CommentSortMode) -> &'static str {
+    match sort {
+        CommentSortMode::Newest => "c.created_at DESC",
+        CommentSortMode::Oldest => "c.created_at ASC",
+        CommentSortMode::Top => "(c.upvotes - c.downvotes) DESC, c.upvotes DESC, c.created_at DESC",
+        CommentSortMode::PinnedFirst => {
+            "c.is_helpful DESC, c.helpful_at DESC NULLS LAST, c.created_at DESC"
+        }
+    }
+}
+
+fn format_time(dt: NaiveDateTime) -> String {
+    dt.format("%Y-%m-%dT%H:%M:%S%.3fZ").to_string()
+}
+
+impl From<CommentRow> for CommentResponse {
+    fn from(row: CommentRow) -> Self {
+        CommentResponse {
+            id: row.id,
+            resource_id: row.resource_id,
+            user_id: row.user_id,
+            user_name: row.user_name,
+            user_avatar: row.user_avatar,
+            // 已删除的评论只保留楼层结构，不再展示内容
+            content: if row.is_deleted {
+                String::new()
+            } else {
+                row.content
+            },
+            parent_id: row.parent_id,
+            root_id: row.root_id,
+            reply_to_user_name: row.reply_to_user_name,
+            is_deleted: row.is_deleted,
+            edited_at: row.edited_at.map(format_time),
+            upvotes: row.upvotes,
+            downvotes: row.downvotes,
+            my_vote: None,
+            is_helpful: row.is_helpful,
+            created_at: row
+                .created_at
+                .map(format_time)
+                .unwrap_or_else(|| format_time(chrono::Local::now().naive_local())),
+            reply_count: if row.parent_id.is_none() {
+                row.reply_count
+            } else {
+                0
+            },
+            replies: Vec::new(),
+        }
+    }
+}
+
 /// HTML 转义，防止 XSS 攻击
 /// 将特殊字符转换为 HTML 实体
 fn escape_html(input: &str) -> String {
@@ -26,26 +135,44 @@ fn escape_html(input: &str) -> String {
     result
 }
 
+/// 验证评论内容，返回去除首尾空白后的内容
+fn validate_content(content: &str) -> Result<&str, ResourceError> {
+    let content = content.trim();
+    if content.is_empty() {
+        return Err(ResourceError::ValidationError(
+            "评论内容不能为空".to_string(),
+        ));
+    }
+    if content.len() > 1000 {
+        return Err(ResourceError::ValidationError(
+            "评论内容不能超过1000字".to_string(),
+        ));
+    }
+    Ok(content)
+}
+
 impl CommentService {
-    /// 创建评论
+    /// 创建评论（`parent_id` 不为空时回复该评论）
     pub async fn create_comment(
         pool: &PgPool,
+        moderation: &Arc<dyn ModerationProvider>,
         resource_id: Uuid,
         user_id: Uuid,
         request: CreateCommentRequest,
     ) -> Result<CommentResponse, ResourceError> {
         // 验证评论内容
-        let content = request.content.trim();
-        if content.is_empty() {
-            return Err(ResourceError::ValidationError(
-                "评论内容不能为空".to_string(),
-            ));
-        }
-        if content.len() > 1000 {
-            return Err(ResourceError::ValidationError(
-                "评论内容不能超过1000字".to_string(),
-            ));
-        }
+        let content = validate_content(&request.content)?;
+        let mentions = extract_mentions(content);
+
+        // AI 审核（审核原文，未通过的评论转人工审核）
+        let ai_result = AiService::audit_comment(moderation.as_ref(), content)
+            .await
+            .map_err(|e| ResourceError::AiError(e.to_string()))?;
+        let audit_status = if ai_result.passed {
+            "approved"
+        } else {
+            "pending"
+        };
 
         // HTML 转义，防止 XSS 攻击
         let content = escape_html(content);
@@ -65,24 +192,55 @@ impl CommentService {
             )));
         }
 
+        // 回复评论时，楼层统一挂在顶层评论下
+        let (parent_id, root_id, parent_user_id) = match request.parent_id {
+            Some(parent_id) => {
+                let parent = Self::find_comment(pool, parent_id)
+                    .await?
+                    .filter(|p| p.resource_id == resource_id)
+                    .ok_or_else(|| ResourceError::NotFound("回复的评论不存在".to_string()))?;
+                if parent.is_deleted || parent.audit_status != "approved" {
+                    return Err(ResourceError::ValidationError(
+                        "无法回复已删除或未通过审核的评论".to_string(),
+                    ));
+                }
+                (
+                    Some(parent.id),
+                    Some(parent.root_id.unwrap_or(parent.id)),
+                    Some(parent.user_id),
+                )
+            }
+            None => (None, None, None),
+        };
+
         log::debug!(
-            "[CommentService] 开始创建评论: resource_id={}, user_id={}, content={}",
+            "[CommentService] 开始创建评论: resource_id={}, user_id={}, parent_id={:?}, content={}",
             resource_id,
             user_id,
+            parent_id,
             content
         );
 
         // 直接插入不使用事务（简化排查）
         let comment = sqlx::query_as::<_, Comment>(
             r#"
-            INSERT INTO comments (resource_id, user_id, content)
-            VALUES ($1, $2, $3)
-            RETURNING id, resource_id, user_id, content, audit_status, created_at, updated_at
+            INSERT INTO comments (resource_id, user_id, parent_id, root_id, content, audit_status, ai_reject_reason)
+            VALUES ($1, $2, $3, $4, $5, $6, $7)
+            RETURNING id, resource_id, user_id, parent_id, root_id, content, audit_status,
+                      is_deleted, edited_at, upvotes, downvotes, is_helpful, created_at, updated_at
             "#,
         )
         .bind(resource_id)
         .bind(user_id)
+        .bind(parent_id)
+        .bind(root_id)
         .bind(content)
+        .bind(audit_status)
+        .bind(if ai_result.passed {
+            None
+        } else {
+            ai_result.reason.as_deref()
+        })
         .fetch_one(pool)
         .await
         .map_err(|e| {
@@ -92,50 +250,40 @@ impl CommentService {
 
         log::debug!("[CommentService] 评论插入成功: comment_id={}", comment.id);
 
-        // 获取用户信息
-        let (user_name, user_avatar) = match sqlx::query_as::<_, (String, Option<String>)>(
-            "SELECT username, avatar_url FROM users WHERE id = $1",
-        )
-        .bind(user_id)
-        .fetch_one(pool)
-        .await
-        {
-            Ok((name, avatar)) => (name, avatar),
-            Err(e) => {
-                log::warn!("[CommentService] 获取用户信息失败: {}", e);
-                ("未知用户".to_string(), None)
-            }
-        };
+        let response = Self::get_comment_response(pool, comment.id).await?;
 
-        log::debug!(
-            "[CommentService] 评论创建完成: comment_id={}, user_name={}",
-            comment.id,
-            user_name
-        );
+        // 发送通知（回复、@ 提醒和资源上传者），待审核的评论暂不通知
+        if ai_result.passed {
+            Self::notify_on_comment(
+                pool,
+                resource_id,
+                user_id,
+                &response.user_name,
+                parent_user_id,
+                &mentions,
+            )
+            .await;
+        } else {
+            log::info!(
+                "[CommentService] 评论未通过 AI 审核，等待人工审核: comment_id={}, reason={:?}",
+                comment.id,
+                ai_result.reason
+            );
+        }
 
-        // 发送通知给资源上传者（如果不是评论自己的资源）
-        Self::notify_uploader_on_comment(pool, resource_id, user_id, &user_name).await;
-
-        Ok(CommentResponse {
-            id: comment.id,
-            resource_id: comment.resource_id,
-            user_id: comment.user_id,
-            user_name,
-            user_avatar,
-            content: comment.content,
-            created_at: comment
-                .created_at
-                .format("%Y-%m-%dT%H:%M:%S%.3fZ")
-                .to_string(),
-        })
+        Ok(response)
     }
 
-    /// 评论时通知资源上传者
-    async fn notify_uploader_on_comment(
+    /// 评论后发送通知
+    ///
+    /// 依次通知被回复的评论作者、被 @ 提醒的用户和资源作者（或上传者），每个用户最多收到一条，不给自己发通知
+    async fn notify_on_comment(
         pool: &PgPool,
         resource_id: Uuid,
         commenter_id: Uuid,
         commenter_name: &str,
+        parent_user_id: Option<Uuid>,
+        mentions: &[String],
     ) {
         // 获取资源上传者信息
         let result = sqlx::query_as::<_, (Uuid, String, Option<Uuid>)>(
@@ -145,87 +293,232 @@ impl CommentService {
         .fetch_optional(pool)
         .await;
 
-        if let Ok(Some((uploader_id, resource_title, author_id))) = result {
-            // 优先通知作者（如果存在），否则通知上传者
-            let notify_user_id = author_id.unwrap_or(uploader_id);
+        let (uploader_id, resource_title, author_id) = match result {
+            Ok(Some(resource)) => resource,
+            Ok(None) => return,
+            Err(e) => {
+                log::warn!("[CommentService] 获取资源信息失败: {}", e);
+                return;
+            }
+        };
+
+        let mut notified = HashSet::from([commenter_id]);
+
+        if let Some(parent_user_id) = parent_user_id {
+            if notified.insert(parent_user_id) {
+                if let Err(e) = NotificationService::create_comment_reply_notification(
+                    pool,
+                    resource_id,
+                    &resource_title,
+                    parent_user_id,
+                    commenter_name,
+                )
+                .await
+                {
+                    log::warn!("[CommentService] 发送回复通知失败: {}", e);
+                }
+            }
+        }
 
-            // 不给自己发通知
-            if notify_user_id != commenter_id {
-                if let Err(e) = NotificationService::create_comment_notification(
+        for user_id in Self::resolve_mentions(pool, mentions).await {
+            if notified.insert(user_id) {
+                if let Err(e) = NotificationService::create_mention_notification(
                     pool,
                     resource_id,
                     &resource_title,
-                    notify_user_id,
+                    user_id,
                     commenter_name,
                 )
                 .await
                 {
-                    log::warn!("[CommentService] 发送评论通知失败: {}", e);
+                    log::warn!("[CommentService] 发送提醒通知失败: {}", e);
                 }
             }
         }
+
+        // 优先通知作者（如果存在），否则通知上传者
+        let notify_user_id = author_id.unwrap_or(uploader_id);
+        if notified.insert(notify_user_id) {
+            if let Err(e) = NotificationService::create_comment_notification(
+                pool,
+                resource_id,
+                &resource_title,
+                notify_user_id,
+                commenter_name,
+            )
+            .await
+            {
+                log::warn!("[CommentService] 发送评论通知失败: {}", e);
+            }
+        }
+    }
+
+    /// 将被 @ 提醒的用户名解析为用户 ID（忽略不存在或已禁用的用户）
+    async fn resolve_mentions(pool: &PgPool, mentions: &[String]) -> Vec<Uuid> {
+        if mentions.is_empty() {
+            return Vec::new();
+        }
+
+        sqlx::query_scalar("SELECT id FROM users WHERE username = ANY($1) AND is_active = true")
+            .bind(mentions)
+            .fetch_all(pool)
+            .await
+            .unwrap_or_else(|e| {
+                log::warn!("[CommentService] 查询被提醒的用户失败: {}", e);
+                Vec::new()
+            })
     }
 
     /// 获取评论列表
+    ///
+    /// 按顶层评论分页，每条顶层评论附带最早的几条回复和回复总数；`viewer_id` 为当前登录用户，用于返回其投票
     pub async fn get_comments(
         pool: &PgPool,
         resource_id: Uuid,
+        viewer_id: Option<Uuid>,
         query: CommentListQuery,
     ) -> Result<CommentListResponse, ResourceError> {
+        let sort = match query.sort.as_deref() {
+            Some(s) if !s.is_empty() => CommentSortMode::from_str(s)
+                .ok_or_else(|| ResourceError::ValidationError(format!("无效的排序方式: {}", s)))?,
+            _ => CommentSortMode::Newest,
+        };
         let page = query.page.unwrap_or(1).max(1);
         let per_page = query.per_page.unwrap_or(20).min(100);
         let offset = (page - 1) * per_page;
+        let reply_preview = query
+            .replies_per_comment
+            .unwrap_or(DEFAULT_REPLY_PREVIEW)
+            .clamp(0, MAX_REPLY_PREVIEW);
 
-        // 获取评论总数
-        let total = sqlx::query_scalar::<_, i64>(
-            "SELECT COUNT(*) FROM comments WHERE resource_id = $1 AND audit_status = 'approved'",
-        )
+        // 获取顶层评论总数
+        let total = sqlx::query_scalar::<_, i64>(&format!(
+            "SELECT COUNT(*) FROM comments c WHERE c.resource_id = $1 AND {}",
+            VISIBLE_ROOT_CONDITION
+        ))
         .bind(resource_id)
         .fetch_one(pool)
         .await?;
 
-        // 获取评论列表
-        let rows = sqlx::query!(
+        // 获取顶层评论列表
+        let rows = sqlx::query_as::<_, CommentRow>(&format!(
             r#"
-            SELECT
-                c.id,
-                c.resource_id,
-                c.user_id,
-                c.content,
-                c.created_at,
-                u.username as user_name
-            FROM comments c
-            JOIN users u ON c.user_id = u.id
-            WHERE c.resource_id = $1 AND c.audit_status = 'approved'
-            ORDER BY c.created_at DESC
+            SELECT {} {}
+            WHERE c.resource_id = $1 AND {}
+            ORDER BY {}
             LIMIT $2 OFFSET $3
             "#,
-            resource_id,
+            COMMENT_COLUMNS,
+            COMMENT_JOINS,
+            VISIBLE_ROOT_CONDITION,
+            order_clause(sort)
+        ))
+        .bind(resource_id)
+        .bind(per_page)
+        .bind(offset)
+        .fetch_all(pool)
+        .await?;
+
+        let mut comments: Vec<CommentResponse> =
+            rows.into_iter().map(CommentResponse::from).collect();
+
+        // 获取每个楼层最早的几条回复
+        if reply_preview > 0 {
+            let root_ids: Vec<Uuid> = comments
+                .iter()
+                .filter(|c| c.reply_count > 0)
+                .map(|c| c.id)
+                .collect();
+            if !root_ids.is_empty() {
+                let replies = sqlx::query_as::<_, CommentRow>(&format!(
+                    r#"
+                    SELECT * FROM (
+                        SELECT {},
+                            ROW_NUMBER() OVER (PARTITION BY c.root_id ORDER BY c.created_at ASC) AS rn
+                        {}
+                        WHERE c.root_id = ANY($1) AND c.audit_status = 'approved' AND c.is_deleted = false
+                    ) t
+                    WHERE t.rn <= $2
+                    ORDER BY t.created_at ASC
+                    "#,
+                    COMMENT_COLUMNS, COMMENT_JOINS
+                ))
+                .bind(&root_ids)
+                .bind(reply_preview)
+                .fetch_all(pool)
+                .await?;
+
+                for reply in replies {
+                    if let Some(root) = comments.iter_mut().find(|c| Some(c.id) == reply.root_id) {
+                        root.replies.push(CommentResponse::from(reply));
+                    }
+                }
+            }
+        }
+
+        if let Some(viewer_id) = viewer_id {
+            Self::fill_my_votes(pool, viewer_id, &mut comments).await?;
+        }
+
+        Ok(CommentListResponse {
+            comments,
+            total,
+            page,
             per_page,
-            offset
+        })
+    }
+
+    /// 获取楼层内的回复列表（按时间正序分页）
+    pub async fn get_replies(
+        pool: &PgPool,
+        comment_id: Uuid,
+        viewer_id: Option<Uuid>,
+        query: CommentListQuery,
+    ) -> Result<CommentListResponse, ResourceError> {
+        let page = query.page.unwrap_or(1).max(1);
+        let per_page = query.per_page.unwrap_or(20).min(100);
+        let offset = (page - 1) * per_page;
+
+        let root = Self::find_comment(pool, comment_id)
+            .await?
+            .filter(|c| c.audit_status == "approved")
+            .ok_or_else(|| ResourceError::NotFound("评论不存在".to_string()))?;
+        if root.parent_id.is_some() {
+            return Err(ResourceError::ValidationError(
+                "只能获取顶层评论的回复".to_string(),
+            ));
+        }
+
+        let total = sqlx::query_scalar::<_, i64>(
+            r#"
+            SELECT COUNT(*) FROM comments
+            WHERE root_id = $1 AND audit_status = 'approved' AND is_deleted = false
+            "#,
         )
+        .bind(comment_id)
+        .fetch_one(pool)
+        .await?;
+
+        let rows = sqlx::query_as::<_, CommentRow>(&format!(
+            r#"
+            SELECT {} {}
+            WHERE c.root_id = $1 AND c.audit_status = 'approved' AND c.is_deleted = false
+            ORDER BY c.created_at ASC
+            LIMIT $2 OFFSET $3
+            "#,
+            COMMENT_COLUMNS, COMMENT_JOINS
+        ))
+        .bind(comment_id)
+        .bind(per_page)
+        .bind(offset)
         .fetch_all(pool)
         .await?;
 
-        let comments = rows
-            .into_iter()
-            .map(|row| CommentResponse {
-                id: row.id,
-                resource_id: row.resource_id,
-                user_id: row.user_id,
-                user_name: row.user_name,
-                user_avatar: None,
-                content: row.content,
-                created_at: row
-                    .created_at
-                    .map(|dt| dt.format("%Y-%m-%dT%H:%M:%S%.3fZ").to_string())
-                    .unwrap_or_else(|| {
-                        chrono::Local::now()
-                            .format("%Y-%m-%dT%H:%M:%S%.3fZ")
-                            .to_string()
-                    }),
-            })
-            .collect();
+        let mut comments: Vec<CommentResponse> =
+            rows.into_iter().map(CommentResponse::from).collect();
+        if let Some(viewer_id) = viewer_id {
+            Self::fill_my_votes(pool, viewer_id, &mut comments).await?;
+        }
 
         Ok(CommentListResponse {
             comments,
@@ -235,7 +528,333 @@ impl CommentService {
         })
     }
 
-    /// 删除评论
+    /// 填充当前用户对评论（含预览回复）的投票
+    async fn fill_my_votes(
+        pool: &PgPool,
+        viewer_id: Uuid,
+        comments: &mut [CommentResponse],
+    ) -> Result<(), ResourceError> {
+        let ids: Vec<Uuid> = comments
+            .iter()
+            .flat_map(|c| std::iter::once(c.id).chain(c.replies.iter().map(|r| r.id)))
+            .collect();
+        if ids.is_empty() {
+            return Ok(());
+        }
+
+        let votes: Vec<(Uuid, i16)> = sqlx::query_as(
+            "SELECT comment_id, value FROM comment_votes WHERE user_id = $1 AND comment_id = ANY($2)",
+        )
+        .bind(viewer_id)
+        .bind(&ids)
+        .fetch_all(pool)
+        .await?;
+        let votes: std::collections::HashMap<Uuid, i16> = votes.into_iter().collect();
+
+        for comment in comments.iter_mut() {
+            comment.my_vote = votes.get(&comment.id).copied();
+            for reply in comment.replies.iter_mut() {
+                reply.my_vote = votes.get(&reply.id).copied();
+            }
+        }
+        Ok(())
+    }
+
+    /// 对评论投票（赞同、反对或取消），不能给自己的评论投票
+    pub async fn vote_comment(
+        pool: &PgPool,
+        comment_id: Uuid,
+        user_id: Uuid,
+        request: VoteCommentRequest,
+    ) -> Result<CommentVoteResponse, ResourceError> {
+        if !matches!(request.value, -1..=1) {
+            return Err(ResourceError::ValidationError(
+                "投票值必须是 1、-1 或 0".to_string(),
+            ));
+        }
+
+        let mut tx = pool.begin().await?;
+
+        // 锁定评论，保证票数汇总与投票记录一致
+        let author_id: Uuid = sqlx::query_scalar(
+            r#"
+            SELECT user_id FROM comments
+            WHERE id = $1 AND audit_status = 'approved' AND is_deleted = false
+            FOR UPDATE
+            "#,
+        )
+        .bind(comment_id)
+        .fetch_optional(&mut *tx)
+        .await?
+        .ok_or_else(|| ResourceError::NotFound("评论不存在".to_string()))?;
+
+        if author_id == user_id {
+            return Err(ResourceError::ValidationError(
+                "不能给自己的评论投票".to_string(),
+            ));
+        }
+
+        if request.value == 0 {
+            sqlx::query("DELETE FROM comment_votes WHERE comment_id = $1 AND user_id = $2")
+                .bind(comment_id)
+                .bind(user_id)
+                .execute(&mut *tx)
+                .await?;
+        } else {
+            sqlx::query(
+                r#"
+                INSERT INTO comment_votes (comment_id, user_id, value)
+                VALUES ($1, $2, $3)
+                ON CONFLICT (comment_id, user_id) DO UPDATE SET value = EXCLUDED.value
+                "#,
+            )
+            .bind(comment_id)
+            .bind(user_id)
+            .bind(request.value)
+            .execute(&mut *tx)
+            .await?;
+        }
+
+        // 更新评论的票数汇总
+        let (upvotes, downvotes): (i32, i32) = sqlx::query_as(
+            r#"
+            UPDATE comments SET
+                upvotes = (SELECT COUNT(*) FROM comment_votes WHERE comment_id = $1 AND value = 1),
+                downvotes = (SELECT COUNT(*) FROM comment_votes WHERE comment_id = $1 AND value = -1)
+            WHERE id = $1
+            RETURNING upvotes, downvotes
+            "#,
+        )
+        .bind(comment_id)
+        .fetch_one(&mut *tx)
+        .await?;
+
+        tx.commit().await?;
+
+        Ok(CommentVoteResponse {
+            comment_id,
+            my_vote: (request.value != 0).then_some(request.value),
+            upvotes,
+            downvotes,
+        })
+    }
+
+    /// 标记或取消有帮助的评论（仅资源上传者或作者，仅顶层评论）
+    pub async fn mark_helpful(
+        pool: &PgPool,
+        comment_id: Uuid,
+        user_id: Uuid,
+        request: MarkHelpfulRequest,
+    ) -> Result<CommentResponse, ResourceError> {
+        let comment = Self::find_comment(pool, comment_id)
+            .await?
+            .filter(|c| !c.is_deleted && c.audit_status == "approved")
+            .ok_or_else(|| ResourceError::NotFound("评论不存在".to_string()))?;
+
+        if comment.parent_id.is_some() {
+            return Err(ResourceError::ValidationError(
+                "只能标记顶层评论".to_string(),
+            ));
+        }
+
+        let (uploader_id, author_id): (Uuid, Option<Uuid>) =
+            sqlx::query_as("SELECT uploader_id, author_id FROM resources WHERE id = $1")
+                .bind(comment.resource_id)
+                .fetch_optional(pool)
+                .await?
+                .ok_or_else(|| ResourceError::NotFound("资源不存在".to_string()))?;
+
+        if user_id != uploader_id && Some(user_id) != author_id {
+            return Err(ResourceError::Unauthorized(
+                "只有资源上传者可以标记有帮助的评论".to_string(),
+            ));
+        }
+
+        sqlx::query(
+            r#"
+            UPDATE comments
+            SET is_helpful = $1, helpful_at = CASE WHEN $1 THEN NOW() ELSE NULL END
+            WHERE id = $2
+            "#,
+        )
+        .bind(request.helpful)
+        .bind(comment_id)
+        .execute(pool)
+        .await?;
+
+        log::info!(
+            "[CommentService] 评论有帮助标记已更新: comment_id={}, helpful={}",
+            comment_id,
+            request.helpful
+        );
+
+        Self::get_comment_response(pool, comment_id).await
+    }
+
+    /// 编辑评论（仅评论作者），编辑前的内容记入编辑历史
+    pub async fn update_comment(
+        pool: &PgPool,
+        moderation: &Arc<dyn ModerationProvider>,
+        comment_id: Uuid,
+        user_id: Uuid,
+        request: UpdateCommentRequest,
+    ) -> Result<CommentResponse, ResourceError> {
+        let comment = Self::find_comment(pool, comment_id)
+            .await?
+            .filter(|c| !c.is_deleted)
+            .ok_or_else(|| ResourceError::NotFound("评论不存在".to_string()))?;
+
+        if comment.user_id != user_id {
+            return Err(ResourceError::Unauthorized(
+                "只能编辑自己的评论".to_string(),
+            ));
+        }
+        // 被驳回的评论不能通过编辑重新进入审核
+        if comment.audit_status == "rejected" {
+            return Err(ResourceError::ValidationError(
+                "未通过审核的评论不能编辑".to_string(),
+            ));
+        }
+
+        let content = validate_content(&request.content)?;
+        let escaped = escape_html(content);
+        if escaped == comment.content {
+            return Self::get_comment_response(pool, comment_id).await;
+        }
+
+        // AI 审核（审核原文，未通过的评论转人工审核）
+        let ai_result = AiService::audit_comment(moderation.as_ref(), content)
+            .await
+            .map_err(|e| ResourceError::AiError(e.to_string()))?;
+        let audit_status = if ai_result.passed {
+            "approved"
+        } else {
+            "pending"
+        };
+
+        let mut tx = pool.begin().await?;
+
+        sqlx::query("INSERT INTO comment_edits (comment_id, content) VALUES ($1, $2)")
+            .bind(comment_id)
+            .bind(&comment.content)
+            .execute(&mut *tx)
+            .await?;
+
+        sqlx::query(
+            r#"
+            UPDATE comments
+            SET content = $1, audit_status = $2, ai_reject_reason = $3, edited_at = NOW()
+            WHERE id = $4
+            "#,
+        )
+        .bind(&escaped)
+        .bind(audit_status)
+        .bind(if ai_result.passed {
+            None
+        } else {
+            ai_result.reason.as_deref()
+        })
+        .bind(comment_id)
+        .execute(&mut *tx)
+        .await?;
+
+        tx.commit().await?;
+
+        log::info!(
+            "[CommentService] 评论已编辑: comment_id={}, audit_status={}",
+            comment_id,
+            audit_status
+        );
+
+        let response = Self::get_comment_response(pool, comment_id).await?;
+
+        // 只提醒编辑后新增的 @ 用户，待审核的评论暂不通知
+        if ai_result.passed {
+            let old_mentions = extract_mentions(&comment.content);
+            let new_mentions: Vec<String> = extract_mentions(content)
+                .into_iter()
+                .filter(|m| !old_mentions.contains(m))
+                .collect();
+            if !new_mentions.is_empty() {
+                Self::notify_mentions(
+                    pool,
+                    comment.resource_id,
+                    user_id,
+                    &response.user_name,
+                    &new_mentions,
+                )
+                .await;
+            }
+        }
+
+        Ok(response)
+    }
+
+    /// 编辑评论新增 @ 提醒时通知被提醒的用户
+    async fn notify_mentions(
+        pool: &PgPool,
+        resource_id: Uuid,
+        commenter_id: Uuid,
+        commenter_name: &str,
+        mentions: &[String],
+    ) {
+        let resource_title: String =
+            match sqlx::query_scalar("SELECT title FROM resources WHERE id = $1")
+                .bind(resource_id)
+                .fetch_one(pool)
+                .await
+            {
+                Ok(title) => title,
+                Err(e) => {
+                    log::warn!("[CommentService] 获取资源信息失败: {}", e);
+                    return;
+                }
+            };
+
+        for user_id in Self::resolve_mentions(pool, mentions).await {
+            if user_id == commenter_id {
+                continue;
+            }
+            if let Err(e) = NotificationService::create_mention_notification(
+                pool,
+                resource_id,
+                &resource_title,
+                user_id,
+                commenter_name,
+            )
+            .await
+            {
+                log::warn!("[CommentService] 发送提醒通知失败: {}", e);
+            }
+        }
+    }
+
+    /// 获取评论编辑历史（公开，仅限已通过审核且未删除的评论）
+    pub async fn get_edit_history(
+        pool: &PgPool,
+        comment_id: Uuid,
+    ) -> Result<CommentEditHistoryResponse, ResourceError> {
+        Self::find_comment(pool, comment_id)
+            .await?
+            .filter(|c| !c.is_deleted && c.audit_status == "approved")
+            .ok_or_else(|| ResourceError::NotFound("评论不存在".to_string()))?;
+
+        let edits = sqlx::query_as::<_, CommentEditItem>(
+            r#"
+            SELECT id, content, created_at AS edited_at
+            FROM comment_edits
+            WHERE comment_id = $1
+            ORDER BY created_at DESC
+            "#,
+        )
+        .bind(comment_id)
+        .fetch_all(pool)
+        .await?;
+
+        Ok(CommentEditHistoryResponse { comment_id, edits })
+    }
+
+    /// 删除评论（软删除，保留楼层结构）
     pub async fn delete_comment(
         pool: &PgPool,
         comment_id: Uuid,
@@ -243,14 +862,9 @@ impl CommentService {
         is_admin: bool,
     ) -> Result<bool, ResourceError> {
         // 检查评论是否存在且属于该用户（或用户是管理员）
-        let comment = sqlx::query_as::<_, Comment>("SELECT * FROM comments WHERE id = $1")
-            .bind(comment_id)
-            .fetch_optional(pool)
-            .await?;
-
-        let comment = match comment {
-            Some(c) => c,
-            None => return Ok(false),
+        let comment = match Self::find_comment(pool, comment_id).await? {
+            Some(c) if !c.is_deleted => c,
+            _ => return Ok(false),
         };
 
         // 检查权限
@@ -258,12 +872,206 @@ impl CommentService {
             return Ok(false);
         }
 
-        // 删除评论
-        sqlx::query("DELETE FROM comments WHERE id = $1")
+        // 标记删除
+        sqlx::query("UPDATE comments SET is_deleted = true, deleted_at = NOW() WHERE id = $1")
             .bind(comment_id)
             .execute(pool)
             .await?;
 
         Ok(true)
     }
+
+    async fn find_comment(
+        pool: &PgPool,
+        comment_id: Uuid,
+    ) -> Result<Option<Comment>, ResourceError> {
+        let comment = sqlx::query_as::<_, Comment>("SELECT * FROM comments WHERE id = $1")
+            .bind(comment_id)
+            .fetch_optional(pool)
+            .await?;
+        Ok(comment)
+    }
+
+    async fn get_comment_response(
+        pool: &PgPool,
+        comment_id: Uuid,
+    ) -> Result<CommentResponse, ResourceError> {
+        let row = sqlx::query_as::<_, CommentRow>(&format!(
+            "SELECT {} {} WHERE c.id = $1",
+            COMMENT_COLUMNS, COMMENT_JOINS
+        ))
+        .bind(comment_id)
+        .fetch_optional(pool)
+        .await?
+        .ok_or_else(|| ResourceError::NotFound("评论不存在".to_string()))?;
+        Ok(CommentResponse::from(row))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{create_resource, create_user, init_schema};
+
+    async fn create_comment(pool: &PgPool, resource_id: Uuid, user_id: Uuid) -> Uuid {
+        sqlx::query_scalar(
+            "INSERT INTO comments (resource_id, user_id, content) VALUES ($1, $2, '写得很好') RETURNING id",
+        )
+        .bind(resource_id)
+        .bind(user_id)
+        .fetch_one(pool)
+        .await
+        .unwrap()
+    }
+
+    async fn vote(
+        pool: &PgPool,
+        comment_id: Uuid,
+        user_id: Uuid,
+        value: i16,
+    ) -> Result<CommentVoteResponse, ResourceError> {
+        CommentService::vote_comment(pool, comment_id, user_id, VoteCommentRequest { value }).await
+    }
+
+    #[sqlx::test(migrations = false)]
+    async fn test_vote_upsert_recounts_totals(pool: PgPool) {
+        init_schema(&pool).await;
+        let uploader = create_user(&pool, "alice").await;
+        let author = create_user(&pool, "carol").await;
+        let bob = create_user(&pool, "bob").await;
+        let dave = create_user(&pool, "dave").await;
+        let resource_id = create_resource(&pool, uploader, "高数笔记").await;
+        let comment_id = create_comment(&pool, resource_id, author).await;
+
+        let response = vote(&pool, comment_id, bob, 1).await.unwrap();
+        assert_eq!((response.upvotes, response.downvotes), (1, 0));
+        assert_eq!(response.my_vote, Some(1));
+        let response = vote(&pool, comment_id, dave, 1).await.unwrap();
+        assert_eq!((response.upvotes, response.downvotes), (2, 0));
+
+        // 改投反对票更新原有记录
+        let response = vote(&pool, comment_id, bob, -1).await.unwrap();
+        assert_eq!((response.upvotes, response.downvotes), (1, 1));
+        let vote_rows: i64 =
+            sqlx::query_scalar("SELECT COUNT(*) FROM comment_votes WHERE comment_id = $1")
+                .bind(comment_id)
+                .fetch_one(&pool)
+                .await
+                .unwrap();
+        assert_eq!(vote_rows, 2);
+
+        let response = vote(&pool, comment_id, bob, 0).await.unwrap();
+        assert_eq!((response.upvotes, response.downvotes), (1, 0));
+        assert_eq!(response.my_vote, None);
+
+        let stored: (i32, i32) =
+            sqlx::query_as("SELECT upvotes, downvotes FROM comments WHERE id = $1")
+                .bind(comment_id)
+                .fetch_one(&pool)
+                .await
+                .unwrap();
+        assert_eq!(stored, (1, 0));
+
+        assert!(matches!(
+            vote(&pool, comment_id, author, 1).await,
+            Err(ResourceError::ValidationError(_))
+        ));
+        assert!(matches!(
+            vote(&pool, comment_id, dave, 2).await,
+            Err(ResourceError::ValidationError(_))
+        ));
+    }
+
+    #[sqlx::test(migrations = false)]
+    async fn test_mark_helpful_toggle(pool: PgPool) {
+        init_schema(&pool).await;
+        let uploader = create_user(&pool, "alice").await;
+        let resource_author = create_user(&pool, "erin").await;
+        let commenter = create_user(&pool, "carol").await;
+        let bob = create_user(&pool, "bob").await;
+        let resource_id = create_resource(&pool, uploader, "高数笔记").await;
+        sqlx::query("UPDATE resources SET author_id = $1 WHERE id = $2")
+            .bind(resource_author)
+            .bind(resource_id)
+            .execute(&pool)
+            .await
+            .unwrap();
+        let comment_id = create_comment(&pool, resource_id, commenter).await;
+
+        let mark = |user_id: Uuid, helpful: bool| {
+            let pool = pool.clone();
+            async move {
+                CommentService::mark_helpful(
+                    &pool,
+                    comment_id,
+                    user_id,
+                    MarkHelpfulRequest { helpful },
+                )
+                .await
+            }
+        };
+        let helpful_at = || async {
+            sqlx::query_scalar::<_, Option<NaiveDateTime>>(
+                "SELECT helpful_at FROM comments WHERE id = $1",
+            )
+            .bind(comment_id)
+            .fetch_one(&pool)
+            .await
+            .unwrap()
+        };
+
+        assert!(matches!(
+            mark(bob, true).await,
+            Err(ResourceError::Unauthorized(_))
+        ));
+
+        // 资源作者可以标记，上传者可以取消
+        assert!(mark(resource_author, true).await.unwrap().is_helpful);
+        assert!(helpful_at().await.is_some());
+        assert!(!mark(uploader, false).await.unwrap().is_helpful);
+        assert!(helpful_at().await.is_none());
+
+        // 回复不能被标记
+        let reply_id: Uuid = sqlx::query_scalar(
+            r#"
+            INSERT INTO comments (resource_id, user_id, content, parent_id, root_id)
+            VALUES ($1, $2, '同意', $3, $3)
+            RETURNING id
+            "#,
+        )
+        .bind(resource_id)
+        .bind(bob)
+        .bind(comment_id)
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+        assert!(matches!(
+            CommentService::mark_helpful(
+                &pool,
+                reply_id,
+                uploader,
+                MarkHelpfulRequest { helpful: true }
+            )
+            .await,
+            Err(ResourceError::ValidationError(_))
+        ));
+    }
+
+    #[test]
+    fn test_order_clause() {
+        assert_eq!(order_clause(CommentSortMode::Newest), "c.created_at DESC");
+        assert_eq!(order_clause(CommentSortMode::Oldest), "c.created_at ASC");
+        assert!(order_clause(CommentSortMode::Top).starts_with("(c.upvotes - c.downvotes) DESC"));
+        assert!(order_clause(CommentSortMode::PinnedFirst).starts_with("c.is_helpful DESC"));
+
+        // 各排序方式都以创建时间兜底，保证分页顺序稳定
+        for sort in [
+            CommentSortMode::Newest,
+            CommentSortMode::Oldest,
+            CommentSortMode::Top,
+            CommentSortMode::PinnedFirst,
+        ] {
+            assert!(order_clause(sort).contains("c.created_at"));
+        }
+    }
 }
@@ -2,10 +2,14 @@ use sqlx::PgPool;
 
 use crate::models::{
     BatchDeleteCoursesResult, BatchImportCourseItem, BatchImportCoursesResult, Course,
-    CourseListQuery, CourseListResponse, CreateCourseRequest, FailedCourseDeleteItem,
-    FailedCourseImportItem, UpdateCourseRequest, UpdateCourseStatusRequest,
+    CourseCategoryCount, CourseDetailResponse, CourseListQuery, CourseListResponse,
+    CourseRatingSummary, CourseResourceItem, CourseTeacherInfo, CreateCourseRequest,
+    FailedCourseDeleteItem, FailedCourseImportItem, UpdateCourseRequest, UpdateCourseStatusRequest,
 };
 
+/// 课程详情中热门资源、最近上传资源的展示数量
+const COURSE_DETAIL_RESOURCE_LIMIT: i64 = 6;
+
 /// 课程服务错误类型
 #[derive(Debug)]
 pub enum CourseError {
@@ -142,6 +146,132 @@ impl CourseService {
         Ok(courses)
     }
 
+    /// 获取课程详情（公开）
+    ///
+    /// 汇总课程下已审核通过的资源：关联教师（由资源关联的教师推导）、分类统计、
+    /// 各维度平均评分、热门资源和最近上传的资源
+    pub async fn get_course_detail(
+        pool: &PgPool,
+        sn: i64,
+    ) -> Result<CourseDetailResponse, CourseError> {
+        let course = Self::get_course_by_sn(pool, sn).await?;
+        if !course.is_active {
+            return Err(CourseError::NotFound(format!("课程编号 {} 不存在", sn)));
+        }
+
+        let teachers = sqlx::query_as::<_, CourseTeacherInfo>(
+            r#"
+            SELECT t.sn, t.name, t.department, COUNT(DISTINCT r.id) AS resource_count
+            FROM resource_courses rc
+            INNER JOIN resources r ON r.id = rc.resource_id AND r.audit_status = 'approved'
+            INNER JOIN resource_teachers rt ON rt.resource_id = r.id
+            INNER JOIN teachers t ON t.sn = rt.teacher_sn AND t.is_active = true
+            WHERE rc.course_sn = $1
+            GROUP BY t.sn, t.name, t.department
+            ORDER BY resource_count DESC, t.sn ASC
+            "#,
+        )
+        .bind(sn)
+        .fetch_all(pool)
+        .await
+        .map_err(|e| CourseError::DatabaseError(e.to_string()))?;
+
+        let category_counts = sqlx::query_as::<_, CourseCategoryCount>(
+            r#"
+            SELECT COALESCE(r.category, 'other') AS category, COUNT(*) AS count
+            FROM resource_courses rc
+            INNER JOIN resources r ON r.id = rc.resource_id AND r.audit_status = 'approved'
+            WHERE rc.course_sn = $1
+            GROUP BY COALESCE(r.category, 'other')
+            ORDER BY count DESC, category ASC
+            "#,
+        )
+        .bind(sn)
+        .fetch_all(pool)
+        .await
+        .map_err(|e| CourseError::DatabaseError(e.to_string()))?;
+        let resource_count = category_counts.iter().map(|c| c.count).sum();
+
+        let ratings = sqlx::query_as::<_, CourseRatingSummary>(
+            r#"
+            SELECT
+                SUM(rs.difficulty_total)::float8 / NULLIF(SUM(rs.difficulty_count), 0) AS avg_difficulty,
+                SUM(rs.overall_quality_total)::float8 / NULLIF(SUM(rs.overall_quality_count), 0) AS avg_overall_quality,
+                SUM(rs.answer_quality_total)::float8 / NULLIF(SUM(rs.answer_quality_count), 0) AS avg_answer_quality,
+                SUM(rs.format_quality_total)::float8 / NULLIF(SUM(rs.format_quality_count), 0) AS avg_format_quality,
+                SUM(rs.detail_level_total)::float8 / NULLIF(SUM(rs.detail_level_count), 0) AS avg_detail_level,
+                (SELECT COUNT(*) FROM ratings ra
+                 INNER JOIN resource_courses rc2 ON rc2.resource_id = ra.resource_id
+                 INNER JOIN resources r2 ON r2.id = ra.resource_id AND r2.audit_status = 'approved'
+                 WHERE rc2.course_sn = $1) AS rating_count
+            FROM resource_courses rc
+            INNER JOIN resources r ON r.id = rc.resource_id AND r.audit_status = 'approved'
+            INNER JOIN resource_stats rs ON rs.resource_id = r.id
+            WHERE rc.course_sn = $1
+            "#,
+        )
+        .bind(sn)
+        .fetch_one(pool)
+        .await
+        .map_err(|e| CourseError::DatabaseError(e.to_string()))?;
+
+        let top_resources = Self::get_course_resources(
+            pool,
+            sn,
+            "COALESCE(rs.downloads, 0) + 2 * COALESCE(rs.likes, 0) DESC, r.created_at DESC",
+        )
+        .await?;
+        let recent_resources = Self::get_course_resources(pool, sn, "r.created_at DESC").await?;
+
+        Ok(CourseDetailResponse {
+            sn: course.sn,
+            name: course.name,
+            semester: course.semester,
+            credits: course.credits,
+            teachers,
+            resource_count,
+            category_counts,
+            ratings,
+            top_resources,
+            recent_resources,
+        })
+    }
+
+    /// 按指定排序获取课程下已审核通过的资源
+    async fn get_course_resources(
+        pool: &PgPool,
+        sn: i64,
+        order_by: &'static str,
+    ) -> Result<Vec<CourseResourceItem>, CourseError> {
+        sqlx::query_as::<_, CourseResourceItem>(&format!(
+            r#"
+            SELECT
+                r.id, r.title,
+                COALESCE(r.resource_type, 'other') AS resource_type,
+                COALESCE(r.category, 'other') AS category,
+                u.username AS uploader_name,
+                COALESCE(rs.downloads, 0) AS downloads,
+                COALESCE(rs.views, 0) AS views,
+                COALESCE(rs.likes, 0) AS likes,
+                rs.overall_quality_total::float8 / NULLIF(rs.overall_quality_count, 0) AS avg_overall_quality,
+                r.created_at
+            FROM resource_courses rc
+            INNER JOIN resources r ON r.id = rc.resource_id AND r.audit_status = 'approved'
+            LEFT JOIN resource_stats rs ON rs.resource_id = r.id
+            LEFT JOIN users u ON u.id = r.uploader_id
+            WHERE rc.course_sn = $1
+            ORDER BY {}
+            LIMIT $2
+            "#,
+            order_by
+        ))
+        .bind(sn)
+        .bind(COURSE_DETAIL_RESOURCE_LIMIT)
+        .fetch_all(pool)
+        .await
+        .map_err(|e| CourseError::DatabaseError(e.to_string()))
+    }
+
     /// 更新课程信息
     pub async fn update_course(
         pool: &PgPool,
@@ -455,3 +585,155 @@ impl CourseService {
         })
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{create_resource, create_user, init_schema};
+    use uuid::Uuid;
+
+    async fn create_course(pool: &PgPool, name: &str) -> i64 {
+        sqlx::query_scalar("INSERT INTO courses (name) VALUES ($1) RETURNING sn")
+            .bind(name)
+            .fetch_one(pool)
+            .await
+            .unwrap()
+    }
+
+    async fn link_course(pool: &PgPool, resource_id: Uuid, course_sn: i64) {
+        sqlx::query("INSERT INTO resource_courses (resource_id, course_sn) VALUES ($1, $2)")
+            .bind(resource_id)
+            .bind(course_sn)
+            .execute(pool)
+            .await
+            .unwrap();
+    }
+
+    #[sqlx::test(migrations = false)]
+    async fn test_course_detail_without_teachers_or_ratings(pool: PgPool) {
+        init_schema(&pool).await;
+        let uploader = create_user(&pool, "alice").await;
+        let sn = create_course(&pool, "线性代数").await;
+
+        // 早期数据没有分类、类型和统计行
+        let legacy: Uuid = sqlx::query_scalar(
+            "INSERT INTO resources (title, uploader_id, audit_status) VALUES ('旧讲义', $1, 'approved') RETURNING id",
+        )
+        .bind(uploader)
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+        link_course(&pool, legacy, sn).await;
+
+        // 未审核通过的资源不计入
+        let pending = create_resource(&pool, uploader, "待审核").await;
+        sqlx::query("UPDATE resources SET audit_status = 'pending' WHERE id = $1")
+            .bind(pending)
+            .execute(&pool)
+            .await
+            .unwrap();
+        link_course(&pool, pending, sn).await;
+
+        let detail = CourseService::get_course_detail(&pool, sn).await.unwrap();
+        assert!(detail.teachers.is_empty());
+        assert_eq!(detail.resource_count, 1);
+        assert_eq!(detail.category_counts.len(), 1);
+        assert_eq!(detail.category_counts[0].category, "other");
+        assert_eq!(detail.ratings.rating_count, 0);
+        assert_eq!(detail.ratings.avg_difficulty, None);
+        assert_eq!(detail.ratings.avg_overall_quality, None);
+
+        for resources in [&detail.top_resources, &detail.recent_resources] {
+            assert_eq!(resources.len(), 1);
+            let item = &resources[0];
+            assert_eq!(item.id, legacy);
+            assert_eq!(item.category, "other");
+            assert_eq!(item.resource_type, "other");
+            assert_eq!(item.uploader_name.as_deref(), Some("alice"));
+            assert_eq!((item.downloads, item.views, item.likes), (0, 0, 0));
+            assert_eq!(item.avg_overall_quality, None);
+        }
+    }
+
+    #[sqlx::test(migrations = false)]
+    async fn test_course_detail_aggregates_teachers_and_ratings(pool: PgPool) {
+        init_schema(&pool).await;
+        let uploader = create_user(&pool, "alice").await;
+        let rater = create_user(&pool, "bob").await;
+        let sn = create_course(&pool, "数学分析").await;
+        let teacher_sn: i64 =
+            sqlx::query_scalar("INSERT INTO teachers (name) VALUES ('王老师') RETURNING sn")
+                .fetch_one(&pool)
+                .await
+                .unwrap();
+
+        let notes = create_resource(&pool, uploader, "笔记").await;
+        let paper = create_resource(&pool, uploader, "试卷").await;
+        for (id, downloads, likes, quality_total, quality_count) in
+            [(notes, 5, 0, 16, 2), (paper, 1, 4, 6, 1)]
+        {
+            link_course(&pool, id, sn).await;
+            sqlx::query("INSERT INTO resource_teachers (resource_id, teacher_sn) VALUES ($1, $2)")
+                .bind(id)
+                .bind(teacher_sn)
+                .execute(&pool)
+                .await
+                .unwrap();
+            sqlx::query(
+                r#"
+                UPDATE resource_stats
+                SET downloads = $2, likes = $3, overall_quality_total = $4, overall_quality_count = $5
+                WHERE resource_id = $1
+                "#,
+            )
+            .bind(id)
+            .bind(downloads)
+            .bind(likes)
+            .bind(quality_total)
+            .bind(quality_count)
+            .execute(&pool)
+            .await
+            .unwrap();
+        }
+        sqlx::query(
+            "INSERT INTO ratings (resource_id, user_id, overall_quality) VALUES ($1, $2, 6)",
+        )
+        .bind(paper)
+        .bind(rater)
+        .execute(&pool)
+        .await
+        .unwrap();
+
+        let detail = CourseService::get_course_detail(&pool, sn).await.unwrap();
+        assert_eq!(detail.teachers.len(), 1);
+        assert_eq!(detail.teachers[0].name, "王老师");
+        assert_eq!(detail.teachers[0].resource_count, 2);
+        assert_eq!(detail.resource_count, 2);
+        assert_eq!(detail.category_counts[0].category, "lecture");
+        assert_eq!(detail.category_counts[0].count, 2);
+        // 按各资源评分总和加权：(16 + 6) / (2 + 1)
+        assert_eq!(detail.ratings.avg_overall_quality, Some(22.0 / 3.0));
+        assert_eq!(detail.ratings.avg_difficulty, None);
+        assert_eq!(detail.ratings.rating_count, 1);
+        // 热门排序为 下载量 + 2 * 点赞数
+        let top: Vec<Uuid> = detail.top_resources.iter().map(|r| r.id).collect();
+        assert_eq!(top, vec![paper, notes]);
+        assert_eq!(detail.top_resources[1].avg_overall_quality, Some(8.0));
+    }
+
+    #[sqlx::test(migrations = false)]
+    async fn test_inactive_course_detail_not_found(pool: PgPool) {
+        init_schema(&pool).await;
+        let sn = create_course(&pool, "停开课程").await;
+        sqlx::query("UPDATE courses SET is_active = false WHERE sn = $1")
+            .bind(sn)
+            .execute(&pool)
+            .await
+            .unwrap();
+
+        assert!(matches!(
+            CourseService::get_course_detail(&pool, sn).await,
+            Err(CourseError::NotFound(_))
+        ));
+    }
+}
@@ -0,0 +1,201 @@
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::models::{EmailTokenPurpose, EmailTokenUse};
+
+/// 邮件令牌服务
+///
+/// 令牌格式为 `{id}.{expires}.{signature}`，签名为
+/// HMAC-SHA256(secret, "email:{purpose}:{id}:{expires}")。
+/// 数据库只记录令牌ID，使用后标记 used_at，保证每个令牌只能使用一次。
+pub struct EmailTokenService;
+
+impl EmailTokenService {
+    /// 签发令牌，同一用户同一用途之前未使用的令牌全部失效
+    pub async fn issue(
+        pool: &PgPool,
+        secret: &str,
+        user_id: Uuid,
+        purpose: EmailTokenPurpose,
+        email: &str,
+        ttl_secs: u64,
+    ) -> Result<String, sqlx::Error> {
+        let token_id = Uuid::new_v4();
+        let expires = chrono::Utc::now().timestamp() + ttl_secs as i64;
+
+        let mut tx = pool.begin().await?;
+
+        sqlx::query(
+            r#"
+            UPDATE email_tokens
+            SET used_at = NOW()
+            WHERE user_id = $1 AND purpose = $2 AND used_at IS NULL
+            "#,
+        )
+        .bind(user_id)
+        .bind(purpose.as_str())
+        .execute(&mut *tx)
+        .await?;
+
+        sqlx::query(
+            r#"
+            INSERT INTO email_tokens (id, user_id, purpose, email, expires_at, created_at)
+            VALUES ($1, $2, $3, $4, NOW() + make_interval(secs => $5), NOW())
+            "#,
+        )
+        .bind(token_id)
+        .bind(user_id)
+        .bind(purpose.as_str())
+        .bind(email)
+        .bind(ttl_secs as f64)
+        .execute(&mut *tx)
+        .await?;
+
+        tx.commit().await?;
+        Ok(sign_token(secret, token_id, purpose, expires))
+    }
+
+    /// 使用令牌，签名错误、已过期或已使用时返回 None
+    pub async fn consume<'e, E>(
+        executor: E,
+        secret: &str,
+        token: &str,
+        purpose: EmailTokenPurpose,
+    ) -> Result<Option<EmailTokenUse>, sqlx::Error>
+    where
+        E: sqlx::PgExecutor<'e>,
+    {
+        let Some(token_id) = verify_token(secret, token, purpose, chrono::Utc::now().timestamp())
+        else {
+            return Ok(None);
+        };
+
+        sqlx::query_as::<_, EmailTokenUse>(
+            r#"
+            UPDATE email_tokens
+            SET used_at = NOW()
+            WHERE id = $1 AND purpose = $2 AND used_at IS NULL AND expires_at > NOW()
+            RETURNING user_id, email
+            "#,
+        )
+        .bind(token_id)
+        .bind(purpose.as_str())
+        .fetch_optional(executor)
+        .await
+    }
+}
+
+/// 生成带签名的令牌
+fn sign_token(secret: &str, token_id: Uuid, purpose: EmailTokenPurpose, expires: i64) -> String {
+    format!(
+        "{}.{}.{}",
+        token_id.simple(),
+        expires,
+        token_signature(secret, token_id, purpose, expires)
+    )
+}
+
+fn token_signature(
+    secret: &str,
+    token_id: Uuid,
+    purpose: EmailTokenPurpose,
+    expires: i64,
+) -> String {
+    let mut mac =
+        Hmac::<Sha256>::new_from_slice(secret.as_bytes()).expect("HMAC 可以接受任意长度的密钥");
+    mac.update(format!("email:{}:{}:{}", purpose.as_str(), token_id, expires).as_bytes());
+    mac.finalize()
+        .into_bytes()
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect()
+}
+
+/// 校验令牌签名和有效期，返回令牌ID
+fn verify_token(secret: &str, token: &str, purpose: EmailTokenPurpose, now: i64) -> Option<Uuid> {
+    let mut parts = token.trim().splitn(3, '.');
+    let token_id = Uuid::parse_str(parts.next()?).ok()?;
+    let expires = parts.next()?.parse::<i64>().ok()?;
+    let signature = parts.next()?;
+
+    if expires <= now {
+        return None;
+    }
+
+    // 常量时间比较
+    let expected = token_signature(secret, token_id, purpose, expires);
+    if expected.len() != signature.len() {
+        return None;
+    }
+    let diff = expected
+        .bytes()
+        .zip(signature.bytes())
+        .fold(0u8, |acc, (a, b)| acc | (a ^ b));
+    (diff == 0).then_some(token_id)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const NOW: i64 = 1_700_000_000;
+
+    #[test]
+    fn test_token_round_trip() {
+        let token_id = Uuid::new_v4();
+        let token = sign_token("secret", token_id, EmailTokenPurpose::VerifyEmail, NOW + 60);
+
+        assert_eq!(
+            verify_token("secret", &token, EmailTokenPurpose::VerifyEmail, NOW),
+            Some(token_id)
+        );
+    }
+
+    #[test]
+    fn test_token_rejects_other_purpose_and_secret() {
+        let token = sign_token(
+            "secret",
+            Uuid::new_v4(),
+            EmailTokenPurpose::VerifyEmail,
+            NOW + 60,
+        );
+
+        // 邮箱验证令牌不能用于重置密码
+        assert_eq!(
+            verify_token("secret", &token, EmailTokenPurpose::ResetPassword, NOW),
+            None
+        );
+        assert_eq!(
+            verify_token("other", &token, EmailTokenPurpose::VerifyEmail, NOW),
+            None
+        );
+    }
+
+    #[test]
+    fn test_token_rejects_expired_and_tampered() {
+        let token_id = Uuid::new_v4();
+        let token = sign_token(
+            "secret",
+            token_id,
+            EmailTokenPurpose::ResetPassword,
+            NOW + 60,
+        );
+        assert_eq!(
+            verify_token("secret", &token, EmailTokenPurpose::ResetPassword, NOW + 60),
+            None
+        );
+
+        // 修改有效期后签名不再匹配
+        let tampered = token.replacen(&(NOW + 60).to_string(), &(NOW + 6000).to_string(), 1);
+        assert_eq!(
+            verify_token("secret", &tampered, EmailTokenPurpose::ResetPassword, NOW),
+            None
+        );
+        assert_eq!(
+            verify_token("secret", "garbage", EmailTokenPurpose::ResetPassword, NOW),
+            None
+        );
+    }
+}
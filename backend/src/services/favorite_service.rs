@@ -1,10 +1,17 @@
+use futures_util::{Stream, StreamExt};
 use sqlx::PgPool;
-use std::io::Write;
+use std::collections::HashMap;
+use std::future::Future;
+use std::pin::Pin;
 use std::sync::Arc;
+use tokio::sync::{mpsc, oneshot};
 use uuid::Uuid;
 
 use crate::config::Config;
-use crate::services::{create_local_storage, ResourceService, StorageBackend, StorageBackendType};
+use crate::services::{
+    storage_for_type, ResourceService, StorageBackend, StorageError,
+};
+use crate::utils::{ZipStreamError, ZipStreamWriter, MAX_ZIP_ENTRY_SIZE};
 
 /// 计算平均分辅助函数
 fn calc_avg(total: Option<i32>, count: Option<i32>) -> Option<f64> {
@@ -532,18 +539,17 @@ impl FavoriteService {
         Ok(rows)
     }
 
-    /// 打包下载收藏夹资源
-    /// 返回 ZIP 文件的字节数据
-    /// 限制：最多 100 个文件，总大小不超过 500MB
-    /// 支持混合存储：根据每个资源的实际 storage_type 选择存储后端
-    pub async fn pack_favorite_resources(
+    /// 准备打包收藏夹资源
+    /// 检查收藏夹归属与文件数量限制，生成归档内的文件名
+    /// 限制：最多 100 个文件
+    pub async fn prepare_pack(
         pool: &PgPool,
         storage: &Arc<dyn StorageBackend>,
         config: &Config,
         favorite_id: Uuid,
         user_id: Uuid,
         favorite_name: &str,
-    ) -> Result<(Vec<u8>, String), ResourceError> {
+    ) -> Result<PackTask, ResourceError> {
         // 获取资源文件信息（包含存储类型）
         let resources = Self::get_favorite_resource_paths(pool, favorite_id, user_id).await?;
 
@@ -552,196 +558,553 @@ impl FavoriteService {
         }
 
         // 检查文件数量限制
-        const MAX_FILES: usize = 100;
-        if resources.len() > MAX_FILES {
+        if resources.len() > MAX_PACK_FILES {
             return Err(ResourceError::ValidationError(format!(
                 "收藏夹资源数量超过限制，最多支持 {} 个文件",
-                MAX_FILES
+                MAX_PACK_FILES
             )));
         }
 
-        // 计算总文件大小并检查限制
-        const MAX_TOTAL_SIZE: i64 = 500 * 1024 * 1024; // 500MB
-        let total_size: i64 = resources.iter().map(|(_, _, _, _, size, _)| *size).sum();
-        if total_size > MAX_TOTAL_SIZE {
-            return Err(ResourceError::ValidationError(format!(
-                "收藏夹资源总大小超过限制，最大支持 500MB，当前 {:.2}MB",
-                total_size as f64 / 1024.0 / 1024.0
-            )));
-        }
+        let timestamp = chrono::Local::now();
+        Ok(PackTask {
+            pool: pool.clone(),
+            storage: storage.clone(),
+            config: config.clone(),
+            favorite_name: favorite_name.to_string(),
+            filename: format!(
+                "{}_{}.zip",
+                sanitize_file_name(favorite_name),
+                timestamp.format("%Y%m%d_%H%M%S")
+            ),
+            created_at: timestamp.format("%Y-%m-%d %H:%M:%S").to_string(),
+            items: Self::build_pack_items(resources),
+        })
+    }
 
-        // 在内存中创建 ZIP 文件
-        let mut zip_buffer = Vec::new();
-        {
-            let mut zip_writer = zip::ZipWriter::new(std::io::Cursor::new(&mut zip_buffer));
-            let options = zip::write::FileOptions::default()
-                .compression_method(zip::CompressionMethod::Deflated)
-                .unix_permissions(0o644);
-
-            // 用于检测文件名冲突
-            let mut file_names: std::collections::HashMap<String, usize> =
-                std::collections::HashMap::new();
-
-            for (resource_id, title, file_path, resource_type, _, storage_type) in &resources {
-                // 根据存储类型选择正确的存储后端读取文件
-                let file_content = if storage_type == "oss" {
-                    // OSS 存储：使用主 storage（如果是 OSS 模式）或创建 OSS 存储
-                    if storage.backend_type() == StorageBackendType::Oss {
-                        match storage.read_file(file_path).await {
-                            Ok(content) => content,
-                            Err(e) => {
-                                log::warn!(
-                                    "读取 OSS 资源文件失败: resource_id={}, path={}, error={}",
-                                    resource_id, file_path, e
-                                );
-                                continue; // 跳过无法读取的文件
-                            }
-                        }
+    /// 打包下载收藏夹资源
+    /// 返回流式生成的 ZIP 数据，边从存储读取边输出，内存占用与归档大小无关
+    /// 无法读取的文件会被跳过，并在归档内的清单中列出原因
+    /// 支持混合存储：根据每个资源的实际 storage_type 选择存储后端
+    pub async fn pack_favorite_resources(
+        pool: &PgPool,
+        storage: &Arc<dyn StorageBackend>,
+        config: &Config,
+        favorite_id: Uuid,
+        user_id: Uuid,
+        favorite_name: &str,
+    ) -> Result<FavoritePack, ResourceError> {
+        let task =
+            Self::prepare_pack(pool, storage, config, favorite_id, user_id, favorite_name).await?;
+        let filename = task.filename().to_string();
+
+        let (chunk_tx, chunk_rx) = mpsc::channel(PACK_CHANNEL_CAPACITY);
+        let (summary_tx, summary_rx) = oneshot::channel();
+
+        tokio::spawn(async move {
+            let mut output = ChannelPackOutput(chunk_tx);
+            let mut summary = FavoritePackSummary::default();
+            match task.run(&mut output, &mut summary).await {
+                Ok(()) => {
+                    log::info!(
+                        "打包下载完成: {}, 文件数: {}, 跳过: {}, 文件大小: {} bytes",
+                        task.filename(),
+                        summary.file_count,
+                        summary.skipped.len(),
+                        summary.total_bytes
+                    );
+                }
+                Err(PackAbort::Disconnected) => {
+                    log::warn!(
+                        "打包下载中断，客户端已断开: {}, 已发送 {} bytes",
+                        task.filename(),
+                        summary.total_bytes
+                    );
+                }
+                Err(e) => {
+                    log::error!("打包下载失败: {}, error={}", task.filename(), e);
+                    let _ = output
+                        .0
+                        .send(Err(std::io::Error::other(e.to_string())))
+                        .await;
+                }
+            }
+            let _ = summary_tx.send(summary);
+        });
+
+        let stream = futures_util::stream::unfold(chunk_rx, |mut rx| async move {
+            rx.recv().await.map(|chunk| (chunk, rx))
+        });
+
+        Ok(FavoritePack {
+            filename,
+            stream: Box::pin(stream),
+            summary: summary_rx,
+        })
+    }
+
+    /// 为收藏夹资源生成归档内的文件名（同名文件自动编号，避开清单文件名）
+    fn build_pack_items(
+        resources: Vec<(Uuid, String, String, String, i64, String)>,
+    ) -> Vec<PackItem> {
+        // 用于检测文件名冲突
+        let mut file_names: HashMap<String, usize> = HashMap::new();
+        file_names.insert(PACK_MANIFEST_NAME.to_string(), 1);
+
+        resources
+            .into_iter()
+            .map(
+                |(resource_id, title, file_path, resource_type, _, storage_type)| {
+                    let safe_title = sanitize_file_name(&title);
+
+                    // 确定文件扩展名
+                    let ext = match resource_type.as_str() {
+                        "web_markdown" => "md",
+                        "pdf" => "pdf",
+                        "ppt" => "ppt",
+                        "pptx" => "pptx",
+                        "doc" => "doc",
+                        "docx" => "docx",
+                        "txt" => "txt",
+                        "zip" => "zip",
+                        _ => "bin",
+                    };
+
+                    // 生成唯一的文件名
+                    let base_name = format!("{}.{}", safe_title, ext);
+                    let entry_name = if let Some(count) = file_names.get(&base_name) {
+                        let new_count = count + 1;
+                        file_names.insert(base_name.clone(), new_count);
+                        format!("{}_{}.{}", safe_title, new_count, ext)
                     } else {
-                        // 当前是 local 模式，但需要读取 OSS 文件
-                        // 创建临时 OSS 存储实例
-                        match crate::services::create_storage_backend(config) {
-                            Ok(oss_storage) if oss_storage.backend_type() == StorageBackendType::Oss => {
-                                match oss_storage.read_file(file_path).await {
-                                    Ok(content) => content,
-                                    Err(e) => {
-                                        log::warn!(
-                                            "读取 OSS 资源文件失败: resource_id={}, path={}, error={}",
-                                            resource_id, file_path, e
-                                        );
-                                        continue;
-                                    }
-                                }
-                            }
-                            _ => {
-                                log::warn!(
-                                    "无法创建 OSS 存储实例来读取资源: resource_id={}",
-                                    resource_id
-                                );
-                                continue;
-                            }
-                        }
-                    }
-                } else {
-                    // 本地存储：使用本地存储实例读取
-                    match create_local_storage(config) {
-                        Ok(local_storage) => match local_storage.read_file(file_path).await {
-                            Ok(content) => content,
-                            Err(e) => {
-                                log::warn!(
-                                    "读取本地资源文件失败: resource_id={}, path={}, error={}",
-                                    resource_id, file_path, e
-                                );
-                                continue; // 跳过无法读取的文件
-                            }
-                        },
-                        Err(e) => {
-                            log::error!("创建本地存储失败: error={}", e);
-                            continue;
-                        }
+                        file_names.insert(base_name.clone(), 1);
+                        base_name
+                    };
+
+                    PackItem {
+                        resource_id,
+                        title,
+                        file_path,
+                        storage_type,
+                        entry_name,
                     }
-                };
+                },
+            )
+            .collect()
+    }
+}
 
-                // 生成安全的文件名 - 保留 Unicode 字符（包括中文），只替换文件系统不安全字符
-                let safe_title = title
-                    .chars()
-                    .map(|c| {
-                        // 文件系统不安全的字符: / \ ? % * : | " < > 和控制字符
-                        if c.is_control()
-                            || matches!(
-                                c,
-                                '/' | '\\' | '?' | '%' | '*' | ':' | '|' | '"' | '<' | '>'
-                            )
-                        {
-                            '_'
-                        } else {
-                            c
-                        }
-                    })
-                    .collect::<String>();
-
-                // 确定文件扩展名
-                let ext = match resource_type.as_str() {
-                    "web_markdown" => "md",
-                    "pdf" => "pdf",
-                    "ppt" => "ppt",
-                    "pptx" => "pptx",
-                    "doc" => "doc",
-                    "docx" => "docx",
-                    "txt" => "txt",
-                    "zip" => "zip",
-                    _ => "bin",
-                };
+/// 打包下载最多支持的文件数
+const MAX_PACK_FILES: usize = 100;
+/// 打包数据通道容量（以数据块计），限制生成速度超过发送速度时的内存占用
+const PACK_CHANNEL_CAPACITY: usize = 4;
+/// 归档内清单文件名
+pub const PACK_MANIFEST_NAME: &str = "下载清单.txt";
+
+/// 打包下载的数据流
+pub type FavoritePackStream = Pin<Box<dyn Stream<Item = Result<Vec<u8>, std::io::Error>> + Send>>;
+
+/// 流式打包下载
+pub struct FavoritePack {
+    /// 下载文件名
+    pub filename: String,
+    /// ZIP 数据流
+    pub stream: FavoritePackStream,
+    /// 打包结束（完成或客户端断开）后的统计信息
+    pub summary: oneshot::Receiver<FavoritePackSummary>,
+}
 
-                // 生成唯一的文件名
-                let base_name = format!("{}.{}", safe_title, ext);
-                let file_name = if let Some(count) = file_names.get(&base_name) {
-                    let new_count = count + 1;
-                    file_names.insert(base_name.clone(), new_count);
-                    format!("{}_{}.{}", safe_title, new_count, ext)
-                } else {
-                    file_names.insert(base_name.clone(), 1);
-                    base_name
-                };
+/// 被跳过的文件
+#[derive(Debug, Clone)]
+pub struct SkippedPackFile {
+    pub resource_id: Uuid,
+    pub title: String,
+    pub reason: String,
+}
 
-                // 添加到 ZIP
-                if let Err(e) = zip_writer.start_file(&file_name, options) {
-                    log::warn!("添加文件到ZIP失败: {}, error={}", file_name, e);
-                    continue;
-                }
+/// 打包统计信息
+#[derive(Debug, Clone, Default)]
+pub struct FavoritePackSummary {
+    /// 待打包的资源总数
+    pub total_files: usize,
+    /// 成功打包的文件数
+    pub file_count: usize,
+    pub skipped: Vec<SkippedPackFile>,
+    /// 已输出的 ZIP 字节数
+    pub total_bytes: u64,
+    /// 是否完整输出（客户端中途断开时为 false）
+    pub completed: bool,
+}
 
-                if let Err(e) = zip_writer.write_all(&file_content) {
-                    log::warn!("写入文件内容到ZIP失败: {}, error={}", file_name, e);
-                    continue;
-                }
+impl FavoritePackSummary {
+    /// 已处理（打包或跳过）的资源数
+    pub fn processed_files(&self) -> usize {
+        self.file_count + self.skipped.len()
+    }
+}
 
-                log::debug!(
-                    "已添加文件到ZIP: {} ({} bytes)",
-                    file_name,
-                    file_content.len()
-                );
+/// 打包过程中的中断原因
+#[derive(Debug)]
+pub enum PackAbort {
+    /// 客户端断开连接
+    Disconnected,
+    Zip(ZipStreamError),
+    /// 写出 ZIP 数据失败
+    Output(String),
+    /// 读取资源文件中途失败
+    Storage(String),
+}
 
-                // 增加资源下载计数
-                if let Err(e) = ResourceService::increment_downloads(pool, *resource_id).await {
+impl std::fmt::Display for PackAbort {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PackAbort::Disconnected => write!(f, "客户端已断开"),
+            PackAbort::Zip(e) => write!(f, "{}", e),
+            PackAbort::Output(msg) => write!(f, "写出 ZIP 数据失败: {}", msg),
+            PackAbort::Storage(msg) => write!(f, "读取资源文件失败: {}", msg),
+        }
+    }
+}
+
+impl From<ZipStreamError> for PackAbort {
+    fn from(err: ZipStreamError) -> Self {
+        PackAbort::Zip(err)
+    }
+}
+
+pub type PackFuture<'a> = Pin<Box<dyn Future<Output = Result<(), PackAbort>> + Send + 'a>>;
+
+/// ZIP 数据的输出目标（HTTP 响应流或后台任务的临时文件）
+pub trait PackOutput: Send {
+    /// 写出一段 ZIP 数据
+    fn write_chunk(&mut self, chunk: Vec<u8>) -> PackFuture<'_>;
+
+    /// 每处理完一个资源（打包或跳过）后回调，用于汇报进度
+    fn report_progress<'a>(&'a mut self, _summary: &'a FavoritePackSummary) -> PackFuture<'a> {
+        Box::pin(async { Ok(()) })
+    }
+}
+
+/// 输出到 HTTP 响应流
+struct ChannelPackOutput(mpsc::Sender<Result<Vec<u8>, std::io::Error>>);
+
+impl PackOutput for ChannelPackOutput {
+    fn write_chunk(&mut self, chunk: Vec<u8>) -> PackFuture<'_> {
+        Box::pin(async move {
+            self.0
+                .send(Ok(chunk))
+                .await
+                .map_err(|_| PackAbort::Disconnected)
+        })
+    }
+}
+
+/// 待打包的资源
+struct PackItem {
+    resource_id: Uuid,
+    title: String,
+    file_path: String,
+    storage_type: String,
+    /// 归档内的文件名
+    entry_name: String,
+}
+
+/// 收藏夹打包任务
+pub struct PackTask {
+    pool: PgPool,
+    storage: Arc<dyn StorageBackend>,
+    config: Config,
+    favorite_name: String,
+    filename: String,
+    created_at: String,
+    items: Vec<PackItem>,
+}
+
+impl PackTask {
+    /// 下载文件名
+    pub fn filename(&self) -> &str {
+        &self.filename
+    }
+
+    /// 待打包的资源总数
+    pub fn total_files(&self) -> usize {
+        self.items.len()
+    }
+
+    /// 生成 ZIP 并写出到 output，summary 随打包进度更新
+    pub async fn run(
+        &self,
+        output: &mut dyn PackOutput,
+        summary: &mut FavoritePackSummary,
+    ) -> Result<(), PackAbort> {
+        let mut writer = ZipStreamWriter::new();
+        summary.total_files = self.items.len();
+
+        let result = self.write_archive(&mut writer, output, summary).await;
+        summary.total_bytes = writer.bytes_written();
+        summary.completed = result.is_ok();
+        result
+    }
+
+    async fn write_archive(
+        &self,
+        writer: &mut ZipStreamWriter,
+        output: &mut dyn PackOutput,
+        summary: &mut FavoritePackSummary,
+    ) -> Result<(), PackAbort> {
+        // 同一次打包中按存储类型复用存储后端
+        let mut storages: HashMap<String, Arc<dyn StorageBackend>> = HashMap::new();
+        let mut packed: Vec<&str> = Vec::new();
+
+        for item in &self.items {
+            if self
+                .pack_item(item, &mut storages, writer, output, summary)
+                .await?
+            {
+                packed.push(&item.entry_name);
+            }
+            summary.total_bytes = writer.bytes_written();
+            output.report_progress(summary).await?;
+        }
+
+        // 写入清单并完成 ZIP 文件
+        let manifest = build_pack_manifest(
+            &self.favorite_name,
+            &self.created_at,
+            self.items.len(),
+            &packed,
+            &summary.skipped,
+        );
+        writer.add_file(PACK_MANIFEST_NAME, manifest.as_bytes())?;
+        writer.finish()?;
+        flush_output(writer, output).await
+    }
+
+    /// 打包单个资源，返回是否成功添加到 ZIP（无法读取的文件记录到 summary.skipped）
+    async fn pack_item(
+        &self,
+        item: &PackItem,
+        storages: &mut HashMap<String, Arc<dyn StorageBackend>>,
+        writer: &mut ZipStreamWriter,
+        output: &mut dyn PackOutput,
+        summary: &mut FavoritePackSummary,
+    ) -> Result<bool, PackAbort> {
+        let storage = match storages.get(&item.storage_type) {
+            Some(storage) => storage.clone(),
+            None => match resolve_pack_storage(&self.storage, &self.config, &item.storage_type) {
+                Ok(storage) => {
+                    storages.insert(item.storage_type.clone(), storage.clone());
+                    storage
+                }
+                Err(e) => {
                     log::warn!(
-                        "增加资源下载计数失败: resource_id={}, error={}",
-                        resource_id, e
+                        "无法访问资源存储: resource_id={}, storage={}, error={}",
+                        item.resource_id,
+                        item.storage_type,
+                        e
                     );
+                    summary.skipped.push(item.skipped("存储不可用".to_string()));
+                    return Ok(false);
                 }
-            }
+            },
+        };
 
-            // 完成 ZIP 文件
-            if let Err(e) = zip_writer.finish() {
-                return Err(ResourceError::FileError(format!("创建ZIP文件失败: {}", e)));
+        let mut file = match storage.read_stream(&item.file_path, None).await {
+            Ok(file) => file,
+            Err(e) => {
+                log::warn!(
+                    "读取资源文件失败: resource_id={}, path={}, storage={}, error={}",
+                    item.resource_id,
+                    item.file_path,
+                    item.storage_type,
+                    e
+                );
+                // 清单对用户可见，不暴露存储路径等内部细节
+                let reason = match e {
+                    StorageError::NotFound(_) => "文件不存在",
+                    _ => "读取文件失败",
+                };
+                summary.skipped.push(item.skipped(reason.to_string()));
+                return Ok(false);
             }
+        };
+
+        if file.content_length.unwrap_or(0) > MAX_ZIP_ENTRY_SIZE {
+            summary
+                .skipped
+                .push(item.skipped("文件超过 4GB，无法打包".to_string()));
+            return Ok(false);
         }
 
-        // 生成下载文件名 - 保留 Unicode 字符（包括中文），只替换文件系统不安全字符
-        let safe_favorite_name = favorite_name
-            .chars()
-            .map(|c| {
-                // 文件系统不安全的字符: / \ ? % * : | " < > 和控制字符
-                if c.is_control()
-                    || matches!(
-                        c,
-                        '/' | '\\' | '?' | '%' | '*' | ':' | '|' | '"' | '<' | '>'
-                    )
-                {
-                    '_'
-                } else {
-                    c
-                }
-            })
-            .collect::<String>();
-        let timestamp = chrono::Local::now().format("%Y%m%d_%H%M%S");
-        let download_filename = format!("{}_{}.zip", safe_favorite_name, timestamp);
-
-        log::info!(
-            "打包下载完成: {}, 文件大小: {} bytes",
-            download_filename,
-            zip_buffer.len()
+        // 添加到 ZIP，边读取边压缩并写出
+        writer.start_file(&item.entry_name)?;
+        let mut file_size: u64 = 0;
+        while let Some(chunk) = file.stream.next().await {
+            // 文件已部分写入 ZIP，读取中途出错时无法跳过，只能中止打包
+            let chunk = chunk.map_err(|e| PackAbort::Storage(format!("{}: {}", item.title, e)))?;
+            writer.write_data(&chunk)?;
+            file_size += chunk.len() as u64;
+            flush_output(writer, output).await?;
+        }
+        writer.finish_file()?;
+
+        log::debug!("已添加文件到ZIP: {} ({} bytes)", item.entry_name, file_size);
+        summary.file_count += 1;
+
+        // 增加资源下载计数
+        if let Err(e) = ResourceService::increment_downloads(&self.pool, item.resource_id).await {
+            log::warn!(
+                "增加资源下载计数失败: resource_id={}, error={}",
+                item.resource_id,
+                e
+            );
+        }
+        Ok(true)
+    }
+}
+
+impl PackItem {
+    fn skipped(&self, reason: String) -> SkippedPackFile {
+        SkippedPackFile {
+            resource_id: self.resource_id,
+            title: self.title.clone(),
+            reason,
+        }
+    }
+}
+
+/// 将已生成的 ZIP 数据写出到输出目标
+async fn flush_output(
+    writer: &mut ZipStreamWriter,
+    output: &mut dyn PackOutput,
+) -> Result<(), PackAbort> {
+    let chunk = writer.take_output();
+    if chunk.is_empty() {
+        return Ok(());
+    }
+    output.write_chunk(chunk).await
+}
+
+/// 根据存储类型选择存储后端
+pub fn resolve_pack_storage(
+    storage: &Arc<dyn StorageBackend>,
+    config: &Config,
+    storage_type: &str,
+) -> Result<Arc<dyn StorageBackend>, String> {
+    storage_for_type(storage, config, Some(storage_type))
+        .map_err(|e| format!("无法访问 {} 存储: {}", storage_type, e))
+}
+
+/// 生成归档内的清单文本
+fn build_pack_manifest(
+    favorite_name: &str,
+    created_at: &str,
+    total: usize,
+    packed: &[&str],
+    skipped: &[SkippedPackFile],
+) -> String {
+    let mut manifest = format!(
+        "收藏夹：{}\n打包时间：{}\n共 {} 个资源，成功打包 {} 个，跳过 {} 个\n",
+        favorite_name,
+        created_at,
+        total,
+        packed.len(),
+        skipped.len()
+    );
+
+    manifest.push_str("\n已打包的文件：\n");
+    if packed.is_empty() {
+        manifest.push_str("  （无）\n");
+    }
+    for name in packed {
+        manifest.push_str(&format!("  - {}\n", name));
+    }
+
+    if !skipped.is_empty() {
+        manifest.push_str("\n跳过的文件：\n");
+        for file in skipped {
+            manifest.push_str(&format!(
+                "  - {}（资源 ID: {}）：{}\n",
+                file.title, file.resource_id, file.reason
+            ));
+        }
+    }
+    manifest
+}
+
+/// 生成安全的文件名 - 保留 Unicode 字符（包括中文），只替换文件系统不安全字符
+fn sanitize_file_name(name: &str) -> String {
+    name.chars()
+        .map(|c| {
+            // 文件系统不安全的字符: / \ ? % * : | " < > 和控制字符
+            if c.is_control()
+                || matches!(
+                    c,
+                    '/' | '\\' | '?' | '%' | '*' | ':' | '|' | '"' | '<' | '>'
+                )
+            {
+                '_'
+            } else {
+                c
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn resource(title: &str, resource_type: &str) -> (Uuid, String, String, String, i64, String) {
+        (
+            Uuid::new_v4(),
+            title.to_string(),
+            format!("resources/{}", Uuid::new_v4()),
+            resource_type.to_string(),
+            1024,
+            "local".to_string(),
+        )
+    }
+
+    #[test]
+    fn test_sanitize_file_name() {
+        assert_eq!(sanitize_file_name("高数/期末:试卷?"), "高数_期末_试卷_");
+        assert_eq!(sanitize_file_name("线性代数 笔记"), "线性代数 笔记");
+    }
+
+    #[test]
+    fn test_build_pack_items_deduplicates_names() {
+        let items = FavoriteService::build_pack_items(vec![
+            resource("笔记", "pdf"),
+            resource("笔记", "pdf"),
+            resource("笔记", "web_markdown"),
+            resource("下载清单", "txt"),
+        ]);
+        let names: Vec<&str> = items.iter().map(|i| i.entry_name.as_str()).collect();
+        assert_eq!(
+            names,
+            vec!["笔记.pdf", "笔记_2.pdf", "笔记.md", "下载清单_2.txt"]
+        );
+    }
+
+    #[test]
+    fn test_build_pack_manifest_lists_skipped_files() {
+        let skipped = vec![SkippedPackFile {
+            resource_id: Uuid::nil(),
+            title: "丢失的文件".to_string(),
+            reason: "文件不存在".to_string(),
+        }];
+        let manifest = build_pack_manifest(
+            "期末复习",
+            "2024-01-01 00:00:00",
+            2,
+            &["笔记.pdf"],
+            &skipped,
         );
 
-        Ok((zip_buffer, download_filename))
+        assert!(manifest.contains("收藏夹：期末复习"));
+        assert!(manifest.contains("成功打包 1 个，跳过 1 个"));
+        assert!(manifest.contains("  - 笔记.pdf"));
+        assert!(manifest
+            .contains("丢失的文件（资源 ID: 00000000-0000-0000-0000-000000000000）：文件不存在"));
     }
 }
@@ -1,4 +1,6 @@
 use crate::models::resource::ResourceType;
+use crate::services::{StorageByteStream, StorageError};
+use futures_util::StreamExt;
 use sha2::{Digest, Sha256};
 use std::io;
 use std::path::Path;
@@ -41,6 +43,7 @@ impl FileService {
     /// # Returns
     /// * `Ok(String)` - SHA-256 哈希值（十六进制字符串）
     /// * `Err(io::Error)` - 读取错误
+    #[allow(dead_code)]
     pub async fn calculate_hash_streaming<R>(
         reader: &mut R,
         buffer_size: Option<usize>,
@@ -63,6 +66,17 @@ impl FileService {
         Ok(format!("{:x}", hasher.finalize()))
     }
 
+    /// 从存储字节流计算文件 SHA-256 哈希（边读取边计算，不把整个文件读入内存）
+    pub async fn calculate_hash_from_stream(
+        mut stream: StorageByteStream,
+    ) -> Result<String, StorageError> {
+        let mut hasher = Sha256::new();
+        while let Some(chunk) = stream.next().await {
+            hasher.update(&chunk?);
+        }
+        Ok(format!("{:x}", hasher.finalize()))
+    }
+
     /// 验证资源文件
     pub fn validate_resource_file(
         file_name: &str,
@@ -177,6 +191,25 @@ mod tests {
         assert_eq!(hash, FileService::calculate_hash(data));
     }
 
+    #[tokio::test]
+    async fn test_calculate_hash_from_stream() {
+        let chunks = vec![Ok(b"hello ".to_vec()), Ok(b"world".to_vec())];
+        let hash =
+            FileService::calculate_hash_from_stream(Box::pin(futures_util::stream::iter(chunks)))
+                .await
+                .unwrap();
+        assert_eq!(hash, FileService::calculate_hash(b"hello world"));
+
+        let chunks = vec![
+            Ok(b"hello".to_vec()),
+            Err(StorageError::Io("读取失败".to_string())),
+        ];
+        let result =
+            FileService::calculate_hash_from_stream(Box::pin(futures_util::stream::iter(chunks)))
+                .await;
+        assert!(result.is_err());
+    }
+
     #[test]
     fn test_validate_resource_file_empty() {
         let result = FileService::validate_resource_file("test.pdf", &[], Some("application/pdf"));
@@ -41,6 +41,9 @@ impl From<super::storage_service::StorageError> for ImageError {
             super::storage_service::StorageError::NotFound(msg) => ImageError::NotFound(msg),
             super::storage_service::StorageError::Io(msg) => ImageError::FileError(msg),
             super::storage_service::StorageError::Backend(msg) => ImageError::FileError(msg),
+            err @ super::storage_service::StorageError::RangeNotSatisfiable(_) => {
+                ImageError::ValidationError(err.to_string())
+            }
         }
     }
 }
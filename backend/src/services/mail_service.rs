@@ -0,0 +1,799 @@
+use std::future::Future;
+use std::path::PathBuf;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::time::Duration;
+
+use base64::{engine::general_purpose::STANDARD, Engine as _};
+use tokio::io::{AsyncBufReadExt, AsyncRead, AsyncWrite, AsyncWriteExt, BufReader};
+use tokio::net::TcpStream;
+use tokio_rustls::rustls;
+use uuid::Uuid;
+
+use crate::config::Config;
+
+/// 邮件错误类型
+#[derive(Debug)]
+pub enum MailError {
+    ConfigError(String),
+    InvalidMessage(String),
+    IoError(String),
+    SmtpError(String),
+}
+
+impl std::fmt::Display for MailError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            MailError::ConfigError(msg) => write!(f, "邮件配置错误: {}", msg),
+            MailError::InvalidMessage(msg) => write!(f, "邮件内容无效: {}", msg),
+            MailError::IoError(msg) => write!(f, "邮件写入失败: {}", msg),
+            MailError::SmtpError(msg) => write!(f, "SMTP 错误: {}", msg),
+        }
+    }
+}
+
+impl std::error::Error for MailError {}
+
+pub type MailFuture<'a> = Pin<Box<dyn Future<Output = Result<(), MailError>> + Send + 'a>>;
+
+/// 邮件发送方式
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MailTransportType {
+    /// 只写入日志（开发环境）
+    Log,
+    /// 保存为 .eml 文件（测试环境）
+    File,
+    /// 通过 SMTP 服务器发送
+    Smtp,
+}
+
+impl MailTransportType {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Log => "log",
+            Self::File => "file",
+            Self::Smtp => "smtp",
+        }
+    }
+
+    pub fn from_str(s: &str) -> Option<Self> {
+        match s {
+            "log" | "none" => Some(Self::Log),
+            "file" => Some(Self::File),
+            "smtp" => Some(Self::Smtp),
+            _ => None,
+        }
+    }
+}
+
+/// SMTP 加密方式
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SmtpSecurity {
+    /// 明文连接后通过 STARTTLS 升级
+    StartTls,
+    /// 直接建立 TLS 连接（通常为 465 端口）
+    Tls,
+    /// 不加密（仅限本地测试）
+    None,
+}
+
+impl SmtpSecurity {
+    pub fn from_str(s: &str) -> Option<Self> {
+        match s {
+            "starttls" => Some(Self::StartTls),
+            "tls" | "ssl" => Some(Self::Tls),
+            "none" | "plain" => Some(Self::None),
+            _ => None,
+        }
+    }
+}
+
+/// 待发送的纯文本邮件
+#[derive(Debug, Clone)]
+pub struct MailMessage {
+    pub to: String,
+    pub subject: String,
+    pub body: String,
+}
+
+impl MailMessage {
+    /// 邮箱验证邮件
+    pub fn email_verification(site_name: &str, to: &str, link: &str, ttl_secs: u64) -> Self {
+        MailMessage {
+            to: to.to_string(),
+            subject: format!("【{}】请验证你的邮箱", site_name),
+            body: format!(
+                "你好！\n\n请点击下面的链接验证你在 {} 绑定的邮箱：\n\n{}\n\n链接 {}内有效，且只能使用一次。\n如果这不是你本人的操作，请忽略本邮件。\n",
+                site_name,
+                link,
+                format_duration(ttl_secs)
+            ),
+        }
+    }
+
+    /// 密码重置邮件
+    pub fn password_reset(
+        site_name: &str,
+        to: &str,
+        username: &str,
+        link: &str,
+        ttl_secs: u64,
+    ) -> Self {
+        MailMessage {
+            to: to.to_string(),
+            subject: format!("【{}】重置密码", site_name),
+            body: format!(
+                "{}，你好！\n\n我们收到了重置你在 {} 账号密码的请求，请点击下面的链接设置新密码：\n\n{}\n\n链接 {}内有效，且只能使用一次。重置后所有已登录的设备都需要重新登录。\n如果这不是你本人的操作，请忽略本邮件，你的密码不会改变。\n",
+                username,
+                site_name,
+                link,
+                format_duration(ttl_secs)
+            ),
+        }
+    }
+
+    /// 校验收件人和标题，防止邮件头注入
+    fn validate(&self) -> Result<(), MailError> {
+        if !is_valid_address(&self.to) {
+            return Err(MailError::InvalidMessage(format!(
+                "收件人地址无效: {}",
+                self.to
+            )));
+        }
+        if self.subject.contains(['\r', '\n']) {
+            return Err(MailError::InvalidMessage("邮件标题包含换行符".to_string()));
+        }
+        Ok(())
+    }
+
+    /// 生成完整的 RFC 5322 邮件内容（正文使用 base64 编码）
+    fn render(&self, from: &str) -> String {
+        let domain = address_domain(mailbox_address(from)).unwrap_or("localhost");
+        let encoded_body = STANDARD.encode(self.body.replace('\n', "\r\n").as_bytes());
+        let body_lines: Vec<&str> = encoded_body
+            .as_bytes()
+            .chunks(76)
+            .map(|chunk| std::str::from_utf8(chunk).unwrap_or_default())
+            .collect();
+
+        format!(
+            "From: {}\r\nTo: {}\r\nSubject: {}\r\nDate: {}\r\nMessage-ID: <{}@{}>\r\nMIME-Version: 1.0\r\nContent-Type: text/plain; charset=UTF-8\r\nContent-Transfer-Encoding: base64\r\n\r\n{}\r\n",
+            encode_mailbox(from),
+            self.to,
+            encode_header_word(&self.subject),
+            chrono::Utc::now().to_rfc2822(),
+            Uuid::new_v4().simple(),
+            domain,
+            body_lines.join("\r\n")
+        )
+    }
+}
+
+/// 邮件发送方
+pub trait Mailer: Send + Sync {
+    fn send<'a>(&'a self, message: &'a MailMessage) -> MailFuture<'a>;
+
+    fn transport_type(&self) -> MailTransportType;
+}
+
+/// 只把邮件写入日志的发送方
+#[derive(Debug, Clone)]
+pub struct LogMailer {
+    from: String,
+}
+
+impl Mailer for LogMailer {
+    fn send<'a>(&'a self, message: &'a MailMessage) -> MailFuture<'a> {
+        Box::pin(async move {
+            message.validate()?;
+            log::info!(
+                "[Mail] 邮件未实际发送（MAIL_TRANSPORT=log） | from={}, to={}, subject={}\n{}",
+                self.from,
+                message.to,
+                message.subject,
+                message.body
+            );
+            Ok(())
+        })
+    }
+
+    fn transport_type(&self) -> MailTransportType {
+        MailTransportType::Log
+    }
+}
+
+/// 把邮件保存为 .eml 文件的发送方
+#[derive(Debug, Clone)]
+pub struct FileMailer {
+    from: String,
+    dir: PathBuf,
+}
+
+impl Mailer for FileMailer {
+    fn send<'a>(&'a self, message: &'a MailMessage) -> MailFuture<'a> {
+        Box::pin(async move {
+            message.validate()?;
+            tokio::fs::create_dir_all(&self.dir)
+                .await
+                .map_err(|e| MailError::IoError(e.to_string()))?;
+
+            let path = self.dir.join(format!(
+                "{}_{}.eml",
+                chrono::Utc::now().format("%Y%m%d%H%M%S"),
+                Uuid::new_v4().simple()
+            ));
+            tokio::fs::write(&path, message.render(&self.from))
+                .await
+                .map_err(|e| MailError::IoError(e.to_string()))?;
+
+            log::info!(
+                "[Mail] 邮件已保存 | to={}, path={}",
+                message.to,
+                path.display()
+            );
+            Ok(())
+        })
+    }
+
+    fn transport_type(&self) -> MailTransportType {
+        MailTransportType::File
+    }
+}
+
+/// 通过 SMTP 发送邮件
+#[derive(Debug, Clone)]
+pub struct SmtpMailer {
+    from: String,
+    host: String,
+    port: u16,
+    security: SmtpSecurity,
+    credentials: Option<(String, String)>,
+    timeout: Duration,
+}
+
+impl SmtpMailer {
+    pub fn from_config(config: &Config) -> Result<Self, MailError> {
+        let host = config.smtp_host.clone().ok_or_else(|| {
+            MailError::ConfigError("MAIL_TRANSPORT=smtp 时必须配置 SMTP_HOST".to_string())
+        })?;
+        let security = SmtpSecurity::from_str(&config.smtp_security).ok_or_else(|| {
+            MailError::ConfigError(format!(
+                "不支持的 SMTP 加密方式: {}（可选 starttls、tls、none）",
+                config.smtp_security
+            ))
+        })?;
+        let credentials = match (&config.smtp_username, &config.smtp_password) {
+            (Some(username), Some(password)) => Some((username.clone(), password.clone())),
+            (None, None) => None,
+            _ => {
+                return Err(MailError::ConfigError(
+                    "SMTP_USERNAME 和 SMTP_PASSWORD 需要同时配置".to_string(),
+                ))
+            }
+        };
+
+        Ok(SmtpMailer {
+            from: config.mail_from.clone(),
+            host,
+            port: config.smtp_port,
+            security,
+            credentials,
+            timeout: Duration::from_secs(config.smtp_timeout_secs),
+        })
+    }
+
+    async fn deliver(&self, message: &MailMessage) -> Result<(), MailError> {
+        let tcp = tokio::time::timeout(
+            self.timeout,
+            TcpStream::connect((self.host.as_str(), self.port)),
+        )
+        .await
+        .map_err(|_| MailError::SmtpError("连接超时".to_string()))?
+        .map_err(|e| MailError::SmtpError(format!("连接失败: {}", e)))?;
+
+        match self.security {
+            SmtpSecurity::Tls => {
+                let tls = self.tls_connect(tcp).await?;
+                let mut conn = SmtpConnection::new(tls, self.timeout);
+                conn.expect_reply(220).await?;
+                let capabilities = conn.ehlo().await?;
+                self.transaction(&mut conn, &capabilities, message).await
+            }
+            SmtpSecurity::StartTls => {
+                let mut conn = SmtpConnection::new(tcp, self.timeout);
+                conn.expect_reply(220).await?;
+                let capabilities = conn.ehlo().await?;
+                if !has_capability(&capabilities, "STARTTLS") {
+                    return Err(MailError::SmtpError("服务器不支持 STARTTLS".to_string()));
+                }
+                conn.command("STARTTLS", 220).await?;
+
+                let tls = self.tls_connect(conn.into_inner()).await?;
+                let mut conn = SmtpConnection::new(tls, self.timeout);
+                let capabilities = conn.ehlo().await?;
+                self.transaction(&mut conn, &capabilities, message).await
+            }
+            SmtpSecurity::None => {
+                let mut conn = SmtpConnection::new(tcp, self.timeout);
+                conn.expect_reply(220).await?;
+                let capabilities = conn.ehlo().await?;
+                self.transaction(&mut conn, &capabilities, message).await
+            }
+        }
+    }
+
+    async fn tls_connect(
+        &self,
+        tcp: TcpStream,
+    ) -> Result<tokio_rustls::client::TlsStream<TcpStream>, MailError> {
+        let mut roots = rustls::RootCertStore::empty();
+        roots.add_trust_anchors(webpki_roots::TLS_SERVER_ROOTS.iter().map(|anchor| {
+            rustls::OwnedTrustAnchor::from_subject_spki_name_constraints(
+                anchor.subject,
+                anchor.spki,
+                anchor.name_constraints,
+            )
+        }));
+        let tls_config = rustls::ClientConfig::builder()
+            .with_safe_defaults()
+            .with_root_certificates(roots)
+            .with_no_client_auth();
+        let server_name = rustls::ServerName::try_from(self.host.as_str())
+            .map_err(|e| MailError::ConfigError(format!("SMTP_HOST 无效: {}", e)))?;
+
+        let connector = tokio_rustls::TlsConnector::from(Arc::new(tls_config));
+        tokio::time::timeout(self.timeout, connector.connect(server_name, tcp))
+            .await
+            .map_err(|_| MailError::SmtpError("TLS 握手超时".to_string()))?
+            .map_err(|e| MailError::SmtpError(format!("TLS 握手失败: {}", e)))
+    }
+
+    /// 认证并发送一封邮件
+    async fn transaction<S: AsyncRead + AsyncWrite + Unpin>(
+        &self,
+        conn: &mut SmtpConnection<S>,
+        capabilities: &[String],
+        message: &MailMessage,
+    ) -> Result<(), MailError> {
+        if let Some((username, password)) = &self.credentials {
+            if auth_mechanisms(capabilities).iter().any(|m| m == "PLAIN") {
+                let credentials = STANDARD.encode(format!("\0{}\0{}", username, password));
+                conn.command(&format!("AUTH PLAIN {}", credentials), 235)
+                    .await?;
+            } else {
+                conn.command("AUTH LOGIN", 334).await?;
+                conn.command(&STANDARD.encode(username), 334).await?;
+                conn.command(&STANDARD.encode(password), 235).await?;
+            }
+        }
+
+        conn.command(&format!("MAIL FROM:<{}>", mailbox_address(&self.from)), 250)
+            .await?;
+        conn.command(&format!("RCPT TO:<{}>", message.to), 250)
+            .await?;
+        conn.command("DATA", 354).await?;
+        // 正文为 base64 编码，不会出现以 "." 开头的行，无需转义
+        conn.command(&format!("{}.", message.render(&self.from)), 250)
+            .await?;
+        // 邮件已被接收，QUIT 失败不影响结果
+        let _ = conn.command("QUIT", 221).await;
+        Ok(())
+    }
+}
+
+impl Mailer for SmtpMailer {
+    fn send<'a>(&'a self, message: &'a MailMessage) -> MailFuture<'a> {
+        Box::pin(async move {
+            message.validate()?;
+            self.deliver(message).await?;
+            log::info!(
+                "[Mail] 邮件已发送 | to={}, subject={}",
+                message.to,
+                message.subject
+            );
+            Ok(())
+        })
+    }
+
+    fn transport_type(&self) -> MailTransportType {
+        MailTransportType::Smtp
+    }
+}
+
+/// SMTP 命令/响应连接
+struct SmtpConnection<S> {
+    stream: BufReader<S>,
+    timeout: Duration,
+}
+
+impl<S: AsyncRead + AsyncWrite + Unpin> SmtpConnection<S> {
+    fn new(stream: S, timeout: Duration) -> Self {
+        Self {
+            stream: BufReader::new(stream),
+            timeout,
+        }
+    }
+
+    fn into_inner(self) -> S {
+        self.stream.into_inner()
+    }
+
+    /// 读取一条（可能多行的）响应，返回状态码和各行文本
+    async fn read_reply(&mut self) -> Result<(u16, Vec<String>), MailError> {
+        let mut lines = Vec::new();
+        loop {
+            let mut line = String::new();
+            let read = tokio::time::timeout(self.timeout, self.stream.read_line(&mut line))
+                .await
+                .map_err(|_| MailError::SmtpError("等待服务器响应超时".to_string()))?
+                .map_err(|e| MailError::SmtpError(format!("读取响应失败: {}", e)))?;
+            if read == 0 {
+                return Err(MailError::SmtpError("服务器关闭了连接".to_string()));
+            }
+
+            let line = line.trim_end();
+            let code = line
+                .get(..3)
+                .and_then(|code| code.parse::<u16>().ok())
+                .ok_or_else(|| MailError::SmtpError(format!("无法解析响应: {}", line)))?;
+            let is_last = line.as_bytes().get(3) != Some(&b'-');
+            lines.push(line.get(4..).unwrap_or_default().to_string());
+            if is_last {
+                return Ok((code, lines));
+            }
+        }
+    }
+
+    async fn expect_reply(&mut self, expected: u16) -> Result<Vec<String>, MailError> {
+        let (code, lines) = self.read_reply().await?;
+        if code != expected {
+            return Err(MailError::SmtpError(format!(
+                "期望 {}，服务器返回 {} {}",
+                expected,
+                code,
+                lines.join(" ")
+            )));
+        }
+        Ok(lines)
+    }
+
+    async fn command(&mut self, command: &str, expected: u16) -> Result<Vec<String>, MailError> {
+        let data = format!("{}\r\n", command);
+        tokio::time::timeout(
+            self.timeout,
+            self.stream.get_mut().write_all(data.as_bytes()),
+        )
+        .await
+        .map_err(|_| MailError::SmtpError("发送命令超时".to_string()))?
+        .map_err(|e| MailError::SmtpError(format!("发送命令失败: {}", e)))?;
+        self.expect_reply(expected).await
+    }
+
+    /// 发送 EHLO 并返回服务器支持的扩展
+    async fn ehlo(&mut self) -> Result<Vec<String>, MailError> {
+        self.command("EHLO localhost", 250).await
+    }
+}
+
+/// 根据配置创建邮件发送方
+pub fn create_mailer(config: &Config) -> Result<Arc<dyn Mailer>, MailError> {
+    let transport = MailTransportType::from_str(&config.mail_transport).ok_or_else(|| {
+        MailError::ConfigError(format!(
+            "不支持的邮件发送方式: {}（可选 log、file、smtp）",
+            config.mail_transport
+        ))
+    })?;
+    if !is_valid_address(mailbox_address(&config.mail_from)) {
+        return Err(MailError::ConfigError(format!(
+            "MAIL_FROM 无效: {}",
+            config.mail_from
+        )));
+    }
+
+    let mailer: Arc<dyn Mailer> = match transport {
+        MailTransportType::Log => Arc::new(LogMailer {
+            from: config.mail_from.clone(),
+        }),
+        MailTransportType::File => Arc::new(FileMailer {
+            from: config.mail_from.clone(),
+            dir: PathBuf::from(&config.mail_file_dir),
+        }),
+        MailTransportType::Smtp => Arc::new(SmtpMailer::from_config(config)?),
+    };
+    Ok(mailer)
+}
+
+/// 在后台发送邮件，发送失败只记录日志
+pub fn send_mail_in_background(mailer: Arc<dyn Mailer>, message: MailMessage) {
+    actix_web::rt::spawn(async move {
+        if let Err(e) = mailer.send(&message).await {
+            log::error!("[Mail] 邮件发送失败 | to={}, error={}", message.to, e);
+        }
+    });
+}
+
+/// 发件人的显示名称，用作邮件中的站点名称
+pub fn sender_name(from: &str) -> &str {
+    match from.find('<') {
+        Some(idx) if !from[..idx].trim().is_empty() => from[..idx].trim().trim_matches('"'),
+        _ => "ShareUSTC",
+    }
+}
+
+/// 从 `名称 <地址>` 中取出地址
+fn mailbox_address(mailbox: &str) -> &str {
+    match (mailbox.find('<'), mailbox.rfind('>')) {
+        (Some(start), Some(end)) if start < end => mailbox[start + 1..end].trim(),
+        _ => mailbox.trim(),
+    }
+}
+
+fn address_domain(address: &str) -> Option<&str> {
+    address.rsplit_once('@').map(|(_, domain)| domain)
+}
+
+/// 简单校验邮箱地址，拒绝可能造成命令或邮件头注入的字符
+fn is_valid_address(address: &str) -> bool {
+    match address.split_once('@') {
+        Some((local, domain)) => {
+            !local.is_empty()
+                && !domain.is_empty()
+                && !address
+                    .chars()
+                    .any(|c| c.is_whitespace() || c.is_control() || matches!(c, '<' | '>' | ','))
+        }
+        None => false,
+    }
+}
+
+/// 非 ASCII 的邮件头按 RFC 2047 编码
+fn encode_header_word(value: &str) -> String {
+    if value.is_ascii() {
+        value.to_string()
+    } else {
+        format!("=?UTF-8?B?{}?=", STANDARD.encode(value.as_bytes()))
+    }
+}
+
+fn encode_mailbox(mailbox: &str) -> String {
+    match mailbox.find('<') {
+        Some(idx) if !mailbox[..idx].trim().is_empty() => format!(
+            "{} <{}>",
+            encode_header_word(mailbox[..idx].trim().trim_matches('"')),
+            mailbox_address(mailbox)
+        ),
+        _ => mailbox_address(mailbox).to_string(),
+    }
+}
+
+fn has_capability(capabilities: &[String], name: &str) -> bool {
+    capabilities.iter().any(|line| {
+        line.split_whitespace()
+            .next()
+            .map(|s| s.eq_ignore_ascii_case(name))
+            == Some(true)
+    })
+}
+
+/// 解析 EHLO 响应中的 AUTH 扩展
+fn auth_mechanisms(capabilities: &[String]) -> Vec<String> {
+    capabilities
+        .iter()
+        .filter_map(|line| {
+            let mut parts = line.split_whitespace();
+            match parts.next() {
+                Some(keyword) if keyword.eq_ignore_ascii_case("AUTH") => {
+                    Some(parts.map(|m| m.to_uppercase()).collect::<Vec<_>>())
+                }
+                _ => None,
+            }
+        })
+        .flatten()
+        .collect()
+}
+
+fn format_duration(secs: u64) -> String {
+    if secs >= 3600 && secs.is_multiple_of(3600) {
+        format!("{} 小时", secs / 3600)
+    } else if secs >= 60 {
+        format!("{} 分钟", secs / 60)
+    } else {
+        format!("{} 秒", secs)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::io::AsyncReadExt;
+    use tokio::net::TcpListener;
+    use tokio::sync::oneshot;
+
+    fn message() -> MailMessage {
+        MailMessage::email_verification(
+            "ShareUSTC",
+            "alice@example.com",
+            "http://localhost:5173/verify-email?token=abc",
+            86400,
+        )
+    }
+
+    #[test]
+    fn test_mailbox_helpers() {
+        assert_eq!(
+            mailbox_address("ShareUSTC <noreply@example.com>"),
+            "noreply@example.com"
+        );
+        assert_eq!(
+            mailbox_address("noreply@example.com"),
+            "noreply@example.com"
+        );
+        assert_eq!(
+            sender_name("\"Share USTC\" <noreply@example.com>"),
+            "Share USTC"
+        );
+        assert_eq!(sender_name("noreply@example.com"), "ShareUSTC");
+        assert_eq!(
+            encode_mailbox("资料站 <noreply@example.com>"),
+            format!(
+                "=?UTF-8?B?{}?= <noreply@example.com>",
+                STANDARD.encode("资料站")
+            )
+        );
+    }
+
+    #[test]
+    fn test_invalid_recipient_rejected() {
+        let mut msg = message();
+        msg.to = "alice@example.com\r\nBcc: bob@example.com".to_string();
+        assert!(matches!(msg.validate(), Err(MailError::InvalidMessage(_))));
+        msg.to = "not-an-address".to_string();
+        assert!(msg.validate().is_err());
+        assert!(message().validate().is_ok());
+    }
+
+    #[test]
+    fn test_render_message() {
+        let rendered = message().render("ShareUSTC <noreply@example.com>");
+        let (headers, body) = rendered.split_once("\r\n\r\n").unwrap();
+
+        assert!(headers.contains("To: alice@example.com"));
+        assert!(headers.contains("Subject: =?UTF-8?B?"));
+        assert!(headers.contains("@example.com>"));
+        assert!(body.lines().all(|line| line.len() <= 76));
+
+        let decoded = STANDARD.decode(body.replace("\r\n", "")).unwrap();
+        let text = String::from_utf8(decoded).unwrap();
+        assert!(text.contains("http://localhost:5173/verify-email?token=abc"));
+        assert!(text.contains("24 小时"));
+    }
+
+    #[test]
+    fn test_auth_mechanisms() {
+        let capabilities = vec![
+            "smtp.example.com".to_string(),
+            "AUTH LOGIN plain".to_string(),
+            "STARTTLS".to_string(),
+        ];
+        assert_eq!(auth_mechanisms(&capabilities), vec!["LOGIN", "PLAIN"]);
+        assert!(has_capability(&capabilities, "starttls"));
+        assert!(!has_capability(&capabilities, "SMTPUTF8"));
+    }
+
+    #[tokio::test]
+    async fn test_file_mailer_writes_eml() {
+        let dir = std::env::temp_dir().join(format!("mail_test_{}", Uuid::new_v4().simple()));
+        let mailer = FileMailer {
+            from: "ShareUSTC <noreply@example.com>".to_string(),
+            dir: dir.clone(),
+        };
+        mailer.send(&message()).await.unwrap();
+
+        let mut entries = std::fs::read_dir(&dir).unwrap();
+        let path = entries.next().unwrap().unwrap().path();
+        assert_eq!(path.extension().unwrap(), "eml");
+        let content = std::fs::read_to_string(&path).unwrap();
+        assert!(content.starts_with("From: ShareUSTC <noreply@example.com>\r\n"));
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    /// 启动一个只处理一次会话的本地 SMTP 服务，返回 (端口, 收到的命令)
+    async fn spawn_mock_smtp() -> (u16, oneshot::Receiver<Vec<String>>) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let (tx, rx) = oneshot::channel();
+
+        tokio::spawn(async move {
+            let (socket, _) = listener.accept().await.unwrap();
+            let mut stream = BufReader::new(socket);
+            let mut received = Vec::new();
+            stream
+                .get_mut()
+                .write_all(b"220 mock ESMTP\r\n")
+                .await
+                .unwrap();
+
+            let mut in_data = false;
+            loop {
+                let mut line = String::new();
+                if stream.read_line(&mut line).await.unwrap() == 0 {
+                    break;
+                }
+                let line = line.trim_end().to_string();
+                if in_data {
+                    if line == "." {
+                        in_data = false;
+                        stream.get_mut().write_all(b"250 queued\r\n").await.unwrap();
+                    }
+                    received.push(line);
+                    continue;
+                }
+
+                let reply: &[u8] = if line.starts_with("EHLO") {
+                    b"250-mock\r\n250-AUTH PLAIN LOGIN\r\n250 8BITMIME\r\n"
+                } else if line.starts_with("AUTH PLAIN") {
+                    b"235 ok\r\n"
+                } else if line == "DATA" {
+                    in_data = true;
+                    b"354 go ahead\r\n"
+                } else if line == "QUIT" {
+                    b"221 bye\r\n"
+                } else {
+                    b"250 ok\r\n"
+                };
+                received.push(line.clone());
+                stream.get_mut().write_all(reply).await.unwrap();
+                if line == "QUIT" {
+                    break;
+                }
+            }
+
+            let mut rest = Vec::new();
+            let _ = stream.read_to_end(&mut rest).await;
+            let _ = tx.send(received);
+        });
+
+        (port, rx)
+    }
+
+    #[tokio::test]
+    async fn test_smtp_mailer_plain_session() {
+        let (port, rx) = spawn_mock_smtp().await;
+        let mailer = SmtpMailer {
+            from: "ShareUSTC <noreply@example.com>".to_string(),
+            host: "127.0.0.1".to_string(),
+            port,
+            security: SmtpSecurity::None,
+            credentials: Some(("user".to_string(), "secret".to_string())),
+            timeout: Duration::from_secs(5),
+        };
+
+        mailer.send(&message()).await.unwrap();
+
+        let received = rx.await.unwrap();
+        assert_eq!(received[0], "EHLO localhost");
+        assert_eq!(
+            received[1],
+            format!("AUTH PLAIN {}", STANDARD.encode("\0user\0secret"))
+        );
+        assert_eq!(received[2], "MAIL FROM:<noreply@example.com>");
+        assert_eq!(received[3], "RCPT TO:<alice@example.com>");
+        assert_eq!(received[4], "DATA");
+        assert!(received.contains(&"To: alice@example.com".to_string()));
+        assert_eq!(received.last().unwrap(), "QUIT");
+    }
+
+    #[test]
+    fn test_create_mailer_rejects_unknown_transport() {
+        let mut config = Config::from_env();
+        config.mail_transport = "carrier-pigeon".to_string();
+        assert!(matches!(
+            create_mailer(&config),
+            Err(MailError::ConfigError(_))
+        ));
+
+        config.mail_transport = "smtp".to_string();
+        config.smtp_host = None;
+        assert!(create_mailer(&config).is_err());
+    }
+}
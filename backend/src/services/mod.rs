@@ -4,36 +4,69 @@ pub mod admin_service;
 pub mod ai_service;
 pub mod audit_log_service;
 pub mod auth_service;
+pub mod claim_service;
 pub mod comment_service;
 pub mod course_service;
+pub mod email_token_service;
 pub mod favorite_service;
 pub mod file_service;
 pub mod image_service;
 pub mod like_service;
+pub mod mail_service;
+pub mod moderation_service;
 pub mod notification_service;
+pub mod oidc_service;
 pub mod oss_service;
+pub mod pack_job_service;
+pub mod permission_service;
+pub mod rate_limit_service;
 pub mod rating_service;
+pub mod real_info_service;
+pub mod recommendation_service;
+pub mod report_service;
 pub mod resource_service;
+pub mod resource_version_service;
+pub mod s3_service;
+pub mod session_service;
+pub mod storage_migration_service;
 pub mod storage_service;
 pub mod teacher_service;
+pub mod totp_service;
 pub mod user_service;
+pub mod verification_service;
 
 pub use admin_service::*;
 pub use ai_service::*;
 pub use audit_log_service::*;
 pub use auth_service::*;
+pub use claim_service::*;
 pub use comment_service::*;
 pub use course_service::*;
+pub use email_token_service::*;
 pub use favorite_service::*;
 pub use file_service::*;
 pub use image_service::*;
 pub use like_service::*;
+pub use mail_service::*;
+pub use moderation_service::*;
 pub use notification_service::*;
+pub use oidc_service::*;
+pub use pack_job_service::*;
+pub use permission_service::*;
+pub use rate_limit_service::*;
 pub use rating_service::*;
+pub use real_info_service::*;
+pub use recommendation_service::*;
+pub use report_service::*;
 pub use resource_service::*;
+pub use resource_version_service::*;
+pub use session_service::*;
+pub use storage_migration_service::*;
 pub use storage_service::*;
 pub use teacher_service::*;
+pub use totp_service::*;
 pub use user_service::*;
+pub use verification_service::*;
 
 // 从 resource_service 重新导出关联信息结构体
 pub use crate::models::resource::{CourseInfo, RelatedResourceInfo, TeacherInfo};
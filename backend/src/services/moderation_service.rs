@@ -0,0 +1,683 @@
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::time::Duration;
+
+use regex::Regex;
+use serde::Deserialize;
+
+use crate::config::Config;
+use crate::models::resource::AiAuditResult;
+
+use super::ai_service::AiError;
+
+pub type ModerationFuture<'a> =
+    Pin<Box<dyn Future<Output = Result<AiAuditResult, AiError>> + Send + 'a>>;
+
+/// 审核提供方类型
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ModerationProviderType {
+    /// 不审核，全部通过
+    Noop,
+    /// 本地关键词/正则规则
+    Rule,
+    /// OpenAI 兼容的 Chat Completions 接口
+    OpenAi,
+}
+
+impl ModerationProviderType {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Noop => "noop",
+            Self::Rule => "rule",
+            Self::OpenAi => "openai",
+        }
+    }
+
+    pub fn from_str(s: &str) -> Option<Self> {
+        match s {
+            "noop" | "none" => Some(Self::Noop),
+            "rule" | "keyword" => Some(Self::Rule),
+            "openai" => Some(Self::OpenAi),
+            _ => None,
+        }
+    }
+}
+
+/// 审核对象
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ModerationTarget {
+    Resource,
+    Comment,
+}
+
+impl ModerationTarget {
+    fn label(&self) -> &'static str {
+        match self {
+            Self::Resource => "学习资料",
+            Self::Comment => "评论",
+        }
+    }
+}
+
+/// 审核请求
+#[derive(Debug, Clone, Copy)]
+pub struct ModerationRequest<'a> {
+    pub target: ModerationTarget,
+    pub title: Option<&'a str>,
+    pub text: Option<&'a str>,
+    /// 文件内容（仅 UTF-8 文本文件会参与审核）
+    pub file_data: Option<&'a [u8]>,
+}
+
+impl ModerationRequest<'_> {
+    /// 拼接参与审核的文本，最多保留 max_chars 个字符
+    pub fn combined_text(&self, max_chars: usize) -> String {
+        let mut parts: Vec<String> = Vec::new();
+        if let Some(title) = self.title.filter(|t| !t.trim().is_empty()) {
+            parts.push(format!("标题：{}", title.trim()));
+        }
+        if let Some(text) = self.text.filter(|t| !t.trim().is_empty()) {
+            parts.push(format!("正文：{}", text.trim()));
+        }
+        if let Some(file_text) = self
+            .file_data
+            .and_then(|data| std::str::from_utf8(data).ok())
+            .filter(|t| !t.trim().is_empty())
+        {
+            // 文件内容与正文相同时（如 Markdown 在线编辑）不重复提交
+            if self.text.map(str::trim) != Some(file_text.trim()) {
+                parts.push(format!("文件内容：{}", file_text.trim()));
+            }
+        }
+
+        let combined = parts.join("\n");
+        if combined.chars().count() > max_chars {
+            combined.chars().take(max_chars).collect()
+        } else {
+            combined
+        }
+    }
+}
+
+/// 内容审核提供方
+pub trait ModerationProvider: Send + Sync {
+    fn moderate<'a>(&'a self, request: ModerationRequest<'a>) -> ModerationFuture<'a>;
+
+    fn provider_type(&self) -> ModerationProviderType;
+}
+
+/// 不做任何审核的提供方（全部通过）
+#[derive(Debug, Clone, Default)]
+pub struct NoopModerationProvider;
+
+impl ModerationProvider for NoopModerationProvider {
+    fn moderate<'a>(&'a self, _request: ModerationRequest<'a>) -> ModerationFuture<'a> {
+        Box::pin(async move {
+            Ok(AiAuditResult {
+                passed: true,
+                reason: None,
+                accuracy_score: None,
+            })
+        })
+    }
+
+    fn provider_type(&self) -> ModerationProviderType {
+        ModerationProviderType::Noop
+    }
+}
+
+/// 基于关键词/正则规则的本地审核
+#[derive(Debug, Clone)]
+pub struct RuleModerationProvider {
+    /// 关键词（已转为小写，匹配时忽略大小写）
+    keywords: Vec<String>,
+    patterns: Vec<Regex>,
+    max_chars: usize,
+}
+
+/// 单次审核结果中最多列出的命中规则数
+const MAX_REPORTED_RULE_HITS: usize = 5;
+
+impl RuleModerationProvider {
+    pub fn new(keywords: Vec<String>, patterns: Vec<Regex>, max_chars: usize) -> Self {
+        Self {
+            keywords: keywords
+                .into_iter()
+                .map(|k| k.trim().to_lowercase())
+                .filter(|k| !k.is_empty())
+                .collect(),
+            patterns,
+            max_chars,
+        }
+    }
+
+    /// 解析规则文本
+    ///
+    /// 每行一条规则：普通行为关键词，`re:` 开头为正则表达式，`#` 开头为注释
+    pub fn parse_rules(content: &str) -> Result<(Vec<String>, Vec<Regex>), AiError> {
+        let mut keywords = Vec::new();
+        let mut patterns = Vec::new();
+        for (index, line) in content.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            if let Some(pattern) = line.strip_prefix("re:") {
+                let regex = Regex::new(&format!("(?i){}", pattern.trim())).map_err(|e| {
+                    AiError::ConfigError(format!("第 {} 行正则表达式无效: {}", index + 1, e))
+                })?;
+                patterns.push(regex);
+            } else {
+                keywords.push(line.to_string());
+            }
+        }
+        Ok((keywords, patterns))
+    }
+
+    pub fn from_config(config: &Config) -> Result<Self, AiError> {
+        let mut keywords = config.moderation_keywords.clone();
+        let mut patterns = Vec::new();
+
+        if let Some(ref path) = config.moderation_rules_file {
+            let content = std::fs::read_to_string(path).map_err(|e| {
+                AiError::ConfigError(format!("读取审核规则文件失败 {}: {}", path, e))
+            })?;
+            let (file_keywords, file_patterns) = Self::parse_rules(&content)?;
+            keywords.extend(file_keywords);
+            patterns.extend(file_patterns);
+        }
+
+        Ok(Self::new(keywords, patterns, config.moderation_max_chars))
+    }
+
+    fn check(&self, text: &str) -> AiAuditResult {
+        let lower = text.to_lowercase();
+        let mut hits: Vec<String> = self
+            .keywords
+            .iter()
+            .filter(|keyword| lower.contains(keyword.as_str()))
+            .cloned()
+            .collect();
+        hits.extend(
+            self.patterns
+                .iter()
+                .filter_map(|pattern| pattern.find(text).map(|m| m.as_str().to_string())),
+        );
+        hits.dedup();
+
+        if hits.is_empty() {
+            AiAuditResult {
+                passed: true,
+                reason: None,
+                accuracy_score: None,
+            }
+        } else {
+            hits.truncate(MAX_REPORTED_RULE_HITS);
+            AiAuditResult {
+                passed: false,
+                reason: Some(format!("内容命中审核规则: {}", hits.join("、"))),
+                accuracy_score: None,
+            }
+        }
+    }
+}
+
+impl ModerationProvider for RuleModerationProvider {
+    fn moderate<'a>(&'a self, request: ModerationRequest<'a>) -> ModerationFuture<'a> {
+        Box::pin(async move { Ok(self.check(&request.combined_text(self.max_chars))) })
+    }
+
+    fn provider_type(&self) -> ModerationProviderType {
+        ModerationProviderType::Rule
+    }
+}
+
+/// OpenAI 兼容接口审核配置
+#[derive(Debug, Clone)]
+pub struct OpenAiModerationConfig {
+    /// 接口地址（如 https://api.openai.com/v1）
+    pub api_base: String,
+    pub api_key: Option<String>,
+    pub model: String,
+    pub timeout: Duration,
+    pub max_chars: usize,
+}
+
+/// 基于 OpenAI 兼容 Chat Completions 接口的审核
+#[derive(Debug, Clone)]
+pub struct OpenAiModerationProvider {
+    config: OpenAiModerationConfig,
+    client: reqwest::Client,
+}
+
+/// 模型返回的审核结论
+#[derive(Debug, Deserialize)]
+struct ModerationVerdict {
+    passed: bool,
+    reason: Option<String>,
+    accuracy: Option<f64>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ChatCompletionResponse {
+    choices: Vec<ChatCompletionChoice>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ChatCompletionChoice {
+    message: ChatCompletionMessage,
+}
+
+#[derive(Debug, Deserialize)]
+struct ChatCompletionMessage {
+    content: Option<String>,
+}
+
+const OPENAI_SYSTEM_PROMPT: &str = "你是高校学习资料分享平台的内容审核员。\
+请判断用户提交的内容是否适合公开分享：包含色情、暴力、违法信息、广告引流、人身攻击、他人隐私或与学习无关的垃圾内容时不通过。\
+只输出一个 JSON 对象，不要输出其他内容，格式为：\
+{\"passed\": true 或 false, \"reason\": \"不通过的原因，通过时为 null\", \"accuracy\": 0 到 1 之间的数字，表示内容作为学习资料的准确度与质量}";
+
+impl OpenAiModerationProvider {
+    pub fn new(config: OpenAiModerationConfig) -> Result<Self, AiError> {
+        let client = reqwest::Client::builder()
+            .timeout(config.timeout)
+            .build()
+            .map_err(|e| AiError::ConfigError(format!("初始化审核 HTTP 客户端失败: {}", e)))?;
+
+        Ok(Self { config, client })
+    }
+
+    pub fn from_config(config: &Config) -> Result<Self, AiError> {
+        Self::new(OpenAiModerationConfig {
+            api_base: config.moderation_api_base.clone(),
+            api_key: config.moderation_api_key.clone(),
+            model: config.moderation_model.clone(),
+            timeout: Duration::from_secs(config.moderation_timeout_secs),
+            max_chars: config.moderation_max_chars,
+        })
+    }
+
+    fn endpoint(&self) -> String {
+        format!(
+            "{}/chat/completions",
+            self.config.api_base.trim_end_matches('/')
+        )
+    }
+
+    /// 从模型输出中解析审核结论（兼容 ```json 代码块包裹）
+    fn parse_verdict(content: &str) -> Result<AiAuditResult, AiError> {
+        let start = content.find('{');
+        let end = content.rfind('}');
+        let json = match (start, end) {
+            (Some(start), Some(end)) if start < end => &content[start..=end],
+            _ => {
+                return Err(AiError::ApiError(format!(
+                    "审核结果不是有效的 JSON: {}",
+                    content
+                )))
+            }
+        };
+
+        let verdict: ModerationVerdict = serde_json::from_str(json)
+            .map_err(|e| AiError::ApiError(format!("解析审核结果失败: {}", e)))?;
+
+        Ok(AiAuditResult {
+            passed: verdict.passed,
+            reason: if verdict.passed {
+                None
+            } else {
+                Some(
+                    verdict
+                        .reason
+                        .filter(|r| !r.trim().is_empty())
+                        .unwrap_or_else(|| "AI 审核未通过".to_string()),
+                )
+            },
+            accuracy_score: verdict.accuracy.map(|a| a.clamp(0.0, 1.0)),
+        })
+    }
+
+    async fn request(&self, request: ModerationRequest<'_>) -> Result<AiAuditResult, AiError> {
+        let user_content = format!(
+            "审核对象：{}\n{}",
+            request.target.label(),
+            request.combined_text(self.config.max_chars)
+        );
+        let body = serde_json::json!({
+            "model": self.config.model,
+            "temperature": 0,
+            "messages": [
+                { "role": "system", "content": OPENAI_SYSTEM_PROMPT },
+                { "role": "user", "content": user_content },
+            ],
+        });
+        let body = serde_json::to_vec(&body)
+            .map_err(|e| AiError::ApiError(format!("序列化审核请求失败: {}", e)))?;
+
+        let mut builder = self
+            .client
+            .post(self.endpoint())
+            .header(reqwest::header::CONTENT_TYPE, "application/json")
+            .body(body);
+        if let Some(ref api_key) = self.config.api_key {
+            builder = builder.bearer_auth(api_key);
+        }
+
+        let response = builder.send().await.map_err(map_reqwest_error)?;
+        let status = response.status();
+        let bytes = response.bytes().await.map_err(map_reqwest_error)?;
+
+        if !status.is_success() {
+            return Err(AiError::ApiError(format!(
+                "审核接口返回错误状态 {}: {}",
+                status,
+                String::from_utf8_lossy(&bytes)
+            )));
+        }
+
+        let completion: ChatCompletionResponse = serde_json::from_slice(&bytes)
+            .map_err(|e| AiError::ApiError(format!("解析审核接口响应失败: {}", e)))?;
+        let content = completion
+            .choices
+            .into_iter()
+            .next()
+            .and_then(|choice| choice.message.content)
+            .ok_or_else(|| AiError::ApiError("审核接口响应缺少内容".to_string()))?;
+
+        Self::parse_verdict(&content)
+    }
+}
+
+impl ModerationProvider for OpenAiModerationProvider {
+    fn moderate<'a>(&'a self, request: ModerationRequest<'a>) -> ModerationFuture<'a> {
+        Box::pin(self.request(request))
+    }
+
+    fn provider_type(&self) -> ModerationProviderType {
+        ModerationProviderType::OpenAi
+    }
+}
+
+fn map_reqwest_error(err: reqwest::Error) -> AiError {
+    if err.is_timeout() {
+        AiError::TimeoutError(err.to_string())
+    } else {
+        AiError::ApiError(err.to_string())
+    }
+}
+
+/// 根据配置创建审核提供方
+pub fn create_moderation_provider(config: &Config) -> Result<Arc<dyn ModerationProvider>, AiError> {
+    let provider_type =
+        ModerationProviderType::from_str(&config.moderation_provider).ok_or_else(|| {
+            AiError::ConfigError(format!(
+                "不支持的审核提供方: {}（可选 noop、rule、openai）",
+                config.moderation_provider
+            ))
+        })?;
+
+    let provider: Arc<dyn ModerationProvider> = match provider_type {
+        ModerationProviderType::Noop => Arc::new(NoopModerationProvider),
+        ModerationProviderType::Rule => Arc::new(RuleModerationProvider::from_config(config)?),
+        ModerationProviderType::OpenAi => Arc::new(OpenAiModerationProvider::from_config(config)?),
+    };
+    Ok(provider)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::TcpListener;
+    use tokio::sync::oneshot;
+
+    fn resource_request<'a>(title: &'a str, text: Option<&'a str>) -> ModerationRequest<'a> {
+        ModerationRequest {
+            target: ModerationTarget::Resource,
+            title: Some(title),
+            text,
+            file_data: None,
+        }
+    }
+
+    /// 启动一个只处理一次请求的本地 HTTP 服务，返回 (base_url, 收到的原始请求)
+    async fn spawn_mock_server(
+        status: u16,
+        body: String,
+        delay: Duration,
+    ) -> (String, oneshot::Receiver<String>) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let (tx, rx) = oneshot::channel();
+
+        tokio::spawn(async move {
+            let (mut socket, _) = listener.accept().await.unwrap();
+            let mut buf = Vec::new();
+            let mut chunk = [0u8; 4096];
+            loop {
+                let n = socket.read(&mut chunk).await.unwrap();
+                if n == 0 {
+                    break;
+                }
+                buf.extend_from_slice(&chunk[..n]);
+                let text = String::from_utf8_lossy(&buf).to_string();
+                if let Some(header_end) = text.find("\r\n\r\n") {
+                    let content_length = text[..header_end]
+                        .lines()
+                        .find_map(|line| {
+                            line.to_lowercase()
+                                .strip_prefix("content-length:")
+                                .map(|v| v.trim().parse::<usize>().unwrap_or(0))
+                        })
+                        .unwrap_or(0);
+                    if buf.len() >= header_end + 4 + content_length {
+                        break;
+                    }
+                }
+            }
+            let _ = tx.send(String::from_utf8_lossy(&buf).to_string());
+
+            tokio::time::sleep(delay).await;
+            let response = format!(
+                "HTTP/1.1 {} OK\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                status,
+                body.len(),
+                body
+            );
+            let _ = socket.write_all(response.as_bytes()).await;
+        });
+
+        (format!("http://{}/v1", addr), rx)
+    }
+
+    fn completion_body(content: &str) -> String {
+        serde_json::json!({
+            "choices": [{ "message": { "role": "assistant", "content": content } }]
+        })
+        .to_string()
+    }
+
+    fn openai_provider(api_base: String, timeout: Duration) -> OpenAiModerationProvider {
+        OpenAiModerationProvider::new(OpenAiModerationConfig {
+            api_base,
+            api_key: Some("test-key".to_string()),
+            model: "test-model".to_string(),
+            timeout,
+            max_chars: 1000,
+        })
+        .unwrap()
+    }
+
+    mod request_tests {
+        use super::*;
+
+        #[test]
+        fn test_combined_text_truncates() {
+            let text = "很".repeat(100);
+            let request = resource_request("标题", Some(&text));
+            assert_eq!(request.combined_text(10).chars().count(), 10);
+        }
+
+        #[test]
+        fn test_combined_text_skips_binary_and_duplicate_file() {
+            let binary = [0xffu8, 0xfe, 0x00];
+            let request = ModerationRequest {
+                target: ModerationTarget::Resource,
+                title: Some("标题"),
+                text: None,
+                file_data: Some(&binary),
+            };
+            assert_eq!(request.combined_text(100), "标题：标题");
+
+            let content = "# 笔记";
+            let request = ModerationRequest {
+                target: ModerationTarget::Resource,
+                title: None,
+                text: Some(content),
+                file_data: Some(content.as_bytes()),
+            };
+            assert_eq!(request.combined_text(100), "正文：# 笔记");
+        }
+    }
+
+    mod rule_provider_tests {
+        use super::*;
+
+        #[test]
+        fn test_parse_rules() {
+            let (keywords, patterns) =
+                RuleModerationProvider::parse_rules("# 注释\n代写\n\nre:加\\s*微信\n").unwrap();
+            assert_eq!(keywords, vec!["代写"]);
+            assert_eq!(patterns.len(), 1);
+        }
+
+        #[test]
+        fn test_parse_rules_invalid_regex() {
+            let result = RuleModerationProvider::parse_rules("re:(unclosed");
+            assert!(matches!(result, Err(AiError::ConfigError(_))));
+        }
+
+        #[tokio::test]
+        async fn test_rule_provider_keyword_and_regex() {
+            let (_, patterns) = RuleModerationProvider::parse_rules("re:加\\s*微信").unwrap();
+            let provider = RuleModerationProvider::new(vec!["Cheat".to_string()], patterns, 1000);
+
+            let result = provider
+                .moderate(resource_request("高数笔记", Some("期末复习")))
+                .await
+                .unwrap();
+            assert!(result.passed);
+
+            let result = provider
+                .moderate(resource_request("代考 CHEAT", Some("请加 微信")))
+                .await
+                .unwrap();
+            assert!(!result.passed);
+            let reason = result.reason.unwrap();
+            assert!(reason.contains("cheat"));
+            assert!(reason.contains("加 微信"));
+        }
+    }
+
+    mod openai_provider_tests {
+        use super::*;
+
+        #[test]
+        fn test_parse_verdict_with_code_fence() {
+            let result = OpenAiModerationProvider::parse_verdict(
+                "```json\n{\"passed\": false, \"reason\": \"广告\", \"accuracy\": 1.5}\n```",
+            )
+            .unwrap();
+            assert!(!result.passed);
+            assert_eq!(result.reason.as_deref(), Some("广告"));
+            assert_eq!(result.accuracy_score, Some(1.0));
+        }
+
+        #[test]
+        fn test_parse_verdict_invalid() {
+            assert!(OpenAiModerationProvider::parse_verdict("我认为没问题").is_err());
+        }
+
+        #[tokio::test]
+        async fn test_openai_provider_passed() {
+            let body = completion_body(r#"{"passed": true, "reason": null, "accuracy": 0.87}"#);
+            let (api_base, request_rx) =
+                spawn_mock_server(200, body, Duration::from_millis(0)).await;
+            let provider = openai_provider(api_base, Duration::from_secs(5));
+
+            let result = provider
+                .moderate(resource_request("高数笔记", Some("极限与连续")))
+                .await
+                .unwrap();
+            assert!(result.passed);
+            assert!(result.reason.is_none());
+            assert_eq!(result.accuracy_score, Some(0.87));
+
+            let raw_request = request_rx.await.unwrap();
+            assert!(raw_request.starts_with("POST /v1/chat/completions"));
+            assert!(raw_request
+                .to_lowercase()
+                .contains("authorization: bearer test-key"));
+            assert!(raw_request.contains("test-model"));
+            assert!(raw_request.contains("极限与连续"));
+        }
+
+        #[tokio::test]
+        async fn test_openai_provider_rejected() {
+            let body =
+                completion_body(r#"{"passed": false, "reason": "包含广告引流", "accuracy": 0.2}"#);
+            let (api_base, _) = spawn_mock_server(200, body, Duration::from_millis(0)).await;
+            let provider = openai_provider(api_base, Duration::from_secs(5));
+
+            let result = provider
+                .moderate(resource_request("加群领资料", None))
+                .await
+                .unwrap();
+            assert!(!result.passed);
+            assert_eq!(result.reason.as_deref(), Some("包含广告引流"));
+        }
+
+        #[tokio::test]
+        async fn test_openai_provider_http_error() {
+            let (api_base, _) = spawn_mock_server(
+                500,
+                r#"{"error": "internal"}"#.to_string(),
+                Duration::from_millis(0),
+            )
+            .await;
+            let provider = openai_provider(api_base, Duration::from_secs(5));
+
+            let result = provider.moderate(resource_request("标题", None)).await;
+            assert!(matches!(result, Err(AiError::ApiError(_))));
+        }
+
+        #[tokio::test]
+        async fn test_openai_provider_timeout() {
+            let body = completion_body(r#"{"passed": true}"#);
+            let (api_base, _) = spawn_mock_server(200, body, Duration::from_secs(3)).await;
+            let provider = openai_provider(api_base, Duration::from_millis(200));
+
+            let result = provider.moderate(resource_request("标题", None)).await;
+            assert!(matches!(result, Err(AiError::TimeoutError(_))));
+        }
+    }
+
+    #[test]
+    fn test_provider_type_from_str() {
+        assert_eq!(
+            ModerationProviderType::from_str("openai"),
+            Some(ModerationProviderType::OpenAi)
+        );
+        assert_eq!(
+            ModerationProviderType::from_str("rule"),
+            Some(ModerationProviderType::Rule)
+        );
+        assert_eq!(
+            ModerationProviderType::from_str("noop"),
+            Some(ModerationProviderType::Noop)
+        );
+        assert_eq!(ModerationProviderType::from_str("unknown"), None);
+    }
+}
@@ -3,7 +3,8 @@ use uuid::Uuid;
 
 use crate::models::{
     CreateNotificationRequest, Notification, NotificationListQuery, NotificationListResponse,
-    NotificationPriority, NotificationResponse, NotificationType, UnreadCountResponse,
+    NotificationPriority, NotificationResponse, NotificationType, ReportAction, ReportReason,
+    UnreadCountResponse,
 };
 use crate::services::ResourceError;
 use chrono::NaiveDateTime;
@@ -449,6 +450,54 @@ impl NotificationService {
         Ok(())
     }
 
+    /// 创建评论回复通知（评论被回复时通知评论作者）
+    pub async fn create_comment_reply_notification(
+        pool: &PgPool,
+        resource_id: Uuid,
+        resource_title: &str,
+        recipient_id: Uuid,
+        replier_name: &str,
+    ) -> Result<(), ResourceError> {
+        let request = CreateNotificationRequest {
+            recipient_id: Some(recipient_id),
+            title: "您的评论收到新回复".to_string(),
+            content: format!(
+                "用户 {} 回复了您在资源《{}》下的评论",
+                replier_name, resource_title
+            ),
+            notification_type: NotificationType::CommentReply,
+            priority: NotificationPriority::Normal,
+            link_url: Some(format!("/resources/{}", resource_id)),
+        };
+
+        Self::create_notification(pool, request).await?;
+        Ok(())
+    }
+
+    /// 创建评论提醒通知（评论中 @ 用户时通知被提醒的用户）
+    pub async fn create_mention_notification(
+        pool: &PgPool,
+        resource_id: Uuid,
+        resource_title: &str,
+        recipient_id: Uuid,
+        commenter_name: &str,
+    ) -> Result<(), ResourceError> {
+        let request = CreateNotificationRequest {
+            recipient_id: Some(recipient_id),
+            title: "有人在评论中提到了您".to_string(),
+            content: format!(
+                "用户 {} 在资源《{}》的评论中提到了您",
+                commenter_name, resource_title
+            ),
+            notification_type: NotificationType::CommentReply,
+            priority: NotificationPriority::Normal,
+            link_url: Some(format!("/resources/{}", resource_id)),
+        };
+
+        Self::create_notification(pool, request).await?;
+        Ok(())
+    }
+
     /// 创建评分通知（资源被评分时通知上传者）
     pub async fn create_rating_notification(
         pool: &PgPool,
@@ -469,4 +518,151 @@ impl NotificationService {
         Self::create_notification(pool, request).await?;
         Ok(())
     }
+
+    /// 创建申领结果通知（管理员审核申领后通知申领人）
+    pub async fn create_claim_result_notification(
+        pool: &PgPool,
+        resource_id: Uuid,
+        resource_title: &str,
+        applicant_id: Uuid,
+        approved: bool,
+        comment: Option<&str>,
+    ) -> Result<(), ResourceError> {
+        let (title, mut content) = if approved {
+            (
+                "您的资源申领已通过".to_string(),
+                format!("您对资源《{}》的作者申领已通过审核", resource_title),
+            )
+        } else {
+            (
+                "您的资源申领未通过".to_string(),
+                format!("您对资源《{}》的作者申领未通过审核", resource_title),
+            )
+        };
+        if let Some(comment) = comment {
+            content.push_str(&format!("，审核意见：{}", comment));
+        }
+
+        let request = CreateNotificationRequest {
+            recipient_id: Some(applicant_id),
+            title,
+            content,
+            notification_type: NotificationType::ClaimResult,
+            priority: NotificationPriority::Normal,
+            link_url: Some(format!("/resources/{}", resource_id)),
+        };
+
+        Self::create_notification(pool, request).await?;
+        Ok(())
+    }
+
+    /// 创建实名认证结果通知（管理员审核实名认证申请后通知申请人）
+    pub async fn create_verification_result_notification(
+        pool: &PgPool,
+        user_id: Uuid,
+        approved: bool,
+        reason: Option<&str>,
+    ) -> Result<(), ResourceError> {
+        let (title, mut content) = if approved {
+            (
+                "您的实名认证已通过".to_string(),
+                "您提交的实名认证申请已通过审核，重新登录或刷新认证状态后生效".to_string(),
+            )
+        } else {
+            (
+                "您的实名认证未通过".to_string(),
+                "您提交的实名认证申请未通过审核".to_string(),
+            )
+        };
+        if let Some(reason) = reason {
+            content.push_str(&format!("，审核意见：{}", reason));
+        }
+
+        let request = CreateNotificationRequest {
+            recipient_id: Some(user_id),
+            title,
+            content,
+            notification_type: NotificationType::AuditResult,
+            priority: NotificationPriority::Normal,
+            link_url: Some("/verification".to_string()),
+        };
+
+        Self::create_notification(pool, request).await?;
+        Ok(())
+    }
+
+    /// 创建举报处理结果通知（管理员处理举报后通知举报人）
+    pub async fn create_report_result_notification(
+        pool: &PgPool,
+        reporter_id: Uuid,
+        target_desc: &str,
+        action: ReportAction,
+        note: Option<&str>,
+    ) -> Result<(), ResourceError> {
+        let (title, mut content) = match action {
+            ReportAction::Dismiss => (
+                "您的举报未被采纳".to_string(),
+                format!("您对{}的举报经核实后未被采纳", target_desc),
+            ),
+            ReportAction::Hide => (
+                "您的举报已处理".to_string(),
+                format!("您举报的{}已被隐藏，感谢您的反馈", target_desc),
+            ),
+            ReportAction::Delete => (
+                "您的举报已处理".to_string(),
+                format!("您举报的{}已被删除，感谢您的反馈", target_desc),
+            ),
+            ReportAction::WarnUploader => (
+                "您的举报已处理".to_string(),
+                format!("您举报的{}的发布者已被警告，感谢您的反馈", target_desc),
+            ),
+        };
+        if let Some(note) = note {
+            content.push_str(&format!("，处理意见：{}", note));
+        }
+
+        let request = CreateNotificationRequest {
+            recipient_id: Some(reporter_id),
+            title,
+            content,
+            notification_type: NotificationType::AuditResult,
+            priority: NotificationPriority::Normal,
+            link_url: Some("/reports".to_string()),
+        };
+
+        Self::create_notification(pool, request).await?;
+        Ok(())
+    }
+
+    /// 创建举报警告通知（管理员处理举报时通知被举报内容的发布者）
+    pub async fn create_report_warning_notification(
+        pool: &PgPool,
+        owner_id: Uuid,
+        resource_id: Uuid,
+        target_desc: &str,
+        reasons: &[ReportReason],
+        note: Option<&str>,
+    ) -> Result<(), ResourceError> {
+        let reasons: Vec<&str> = reasons.iter().map(|r| r.label()).collect();
+        let mut content = format!(
+            "您发布的{}因{}被用户举报，请遵守社区规范",
+            target_desc,
+            reasons.join("、")
+        );
+        if let Some(note) = note {
+            content.push_str(&format!("，管理员留言：{}", note));
+        }
+
+        let request = CreateNotificationRequest {
+            recipient_id: Some(owner_id),
+            title: "您发布的内容收到举报警告".to_string(),
+            content,
+            notification_type: NotificationType::AdminMessage,
+            priority: NotificationPriority::Normal,
+            link_url: Some(format!("/resources/{}", resource_id)),
+        };
+
+        Self::create_notification(pool, request).await?;
+        Ok(())
+    }
 }
@@ -1,7 +1,8 @@
 use crate::config::Config;
-use crate::utils::build_content_disposition;
+use crate::utils::{build_content_disposition, percent_encode};
 use base64::{engine::general_purpose::STANDARD as BASE64_STANDARD, Engine as _};
 use chrono::Utc;
+use futures_util::StreamExt;
 use hmac::{Hmac, Mac};
 use reqwest::StatusCode;
 use serde::Deserialize;
@@ -11,8 +12,8 @@ use std::collections::BTreeMap;
 use uuid::Uuid;
 
 use super::storage_service::{
-    StorageBackend, StorageBackendType, StorageError, StorageFileMetadata, StorageFuture,
-    StorageStsCredentials,
+    ByteRange, StorageBackend, StorageBackendType, StorageByteStream, StorageError,
+    StorageFileMetadata, StorageFuture, StorageReadStream, StorageStsCredentials,
 };
 
 #[derive(Debug, Clone)]
@@ -320,7 +321,8 @@ impl OssStorage {
     async fn put_object(
         &self,
         key: &str,
-        data: Vec<u8>,
+        body: reqwest::Body,
+        content_length: Option<u64>,
         content_type: Option<&str>,
     ) -> Result<String, StorageError> {
         let normalized_key = self.normalize_key(key)?;
@@ -339,9 +341,12 @@ impl OssStorage {
         if let Some(content_type) = content_type {
             request = request.header("Content-Type", content_type);
         }
+        if let Some(content_length) = content_length {
+            request = request.header("Content-Length", content_length);
+        }
 
         let response = request
-            .body(data)
+            .body(body)
             .send()
             .await
             .map_err(|e| StorageError::Backend(format!("OSS 写入请求失败: {}", e)))?;
@@ -395,7 +400,11 @@ impl StorageBackend for OssStorage {
         data: Vec<u8>,
         content_type: Option<&'a str>,
     ) -> StorageFuture<'a, String> {
-        Box::pin(async move { self.put_object(key, data, content_type).await })
+        Box::pin(async move {
+            let content_length = data.len() as u64;
+            self.put_object(key, data.into(), Some(content_length), content_type)
+                .await
+        })
     }
 
     fn read_file<'a>(&'a self, key: &'a str) -> StorageFuture<'a, Vec<u8>> {
@@ -429,6 +438,42 @@ impl StorageBackend for OssStorage {
         })
     }
 
+    fn read_stream<'a>(
+        &'a self,
+        key: &'a str,
+        range: Option<ByteRange>,
+    ) -> StorageFuture<'a, StorageReadStream> {
+        Box::pin(async move {
+            let signed_url =
+                self.build_presigned_url("GET", key, self.config.signed_url_expiry, None, None)?;
+
+            let mut request = self.client.get(&signed_url);
+            if let Some(range) = range {
+                request = request.header("Range", range.to_header_value());
+            }
+            let response = request
+                .send()
+                .await
+                .map_err(|e| StorageError::Backend(format!("OSS 读取请求失败: {}", e)))?;
+
+            read_stream_from_response(response, key, "OSS")
+        })
+    }
+
+    fn write_stream<'a>(
+        &'a self,
+        key: &'a str,
+        stream: StorageByteStream,
+        content_length: Option<u64>,
+        content_type: Option<&'a str>,
+    ) -> StorageFuture<'a, String> {
+        Box::pin(async move {
+            let body = reqwest::Body::wrap_stream(stream);
+            self.put_object(key, body, content_length, content_type)
+                .await
+        })
+    }
+
     fn write_file<'a>(
         &'a self,
         key: &'a str,
@@ -436,7 +481,9 @@ impl StorageBackend for OssStorage {
         content_type: Option<&'a str>,
     ) -> StorageFuture<'a, ()> {
         Box::pin(async move {
-            self.put_object(key, data, content_type).await?;
+            let content_length = data.len() as u64;
+            self.put_object(key, data.into(), Some(content_length), content_type)
+                .await?;
             Ok(())
         })
     }
@@ -623,14 +670,14 @@ fn sign_sts_rpc_query(
     Ok(signature)
 }
 
-fn required(value: Option<&str>, env_name: &str) -> Result<String, StorageError> {
+pub(super) fn required(value: Option<&str>, env_name: &str) -> Result<String, StorageError> {
     value
         .map(|v| v.trim().to_string())
         .filter(|v| !v.is_empty())
         .ok_or_else(|| StorageError::Config(format!("缺少配置: {}", env_name)))
 }
 
-fn canonical_query_string(params: &BTreeMap<String, String>) -> String {
+pub(super) fn canonical_query_string(params: &BTreeMap<String, String>) -> String {
     params
         .iter()
         .map(|(k, v)| format!("{}={}", percent_encode(k, true), percent_encode(v, true)))
@@ -638,7 +685,7 @@ fn canonical_query_string(params: &BTreeMap<String, String>) -> String {
         .join("&")
 }
 
-fn parse_storage_metadata_from_headers(
+pub(super) fn parse_storage_metadata_from_headers(
     headers: &reqwest::header::HeaderMap,
     content_length_override: Option<u64>,
 ) -> StorageFileMetadata {
@@ -664,7 +711,7 @@ fn parse_storage_metadata_from_headers(
     }
 }
 
-fn parse_total_length_from_content_range(content_range: Option<&str>) -> Option<u64> {
+pub(super) fn parse_total_length_from_content_range(content_range: Option<&str>) -> Option<u64> {
     let value = content_range?;
     let total = value.rsplit('/').next()?;
     if total == "*" {
@@ -673,30 +720,81 @@ fn parse_total_length_from_content_range(content_range: Option<&str>) -> Option<
     total.parse::<u64>().ok()
 }
 
-fn percent_encode(input: &str, encode_slash: bool) -> String {
-    let mut encoded = String::new();
-    for &byte in input.as_bytes() {
-        let is_unreserved =
-            byte.is_ascii_alphanumeric() || matches!(byte, b'-' | b'_' | b'.' | b'~');
-        let is_slash = byte == b'/';
-        if is_unreserved || (!encode_slash && is_slash) {
-            encoded.push(byte as char);
-        } else {
-            encoded.push('%');
-            encoded.push_str(&format!("{:02X}", byte));
+/// 从 Content-Range（如 "bytes 0-99/1000"）中解析实际返回的区间
+pub(super) fn parse_range_from_content_range(content_range: Option<&str>) -> Option<(u64, u64)> {
+    let value = content_range?.strip_prefix("bytes ")?;
+    let (range, _) = value.split_once('/')?;
+    let (start, end) = range.split_once('-')?;
+    Some((start.trim().parse().ok()?, end.trim().parse().ok()?))
+}
+
+/// 将对象存储的 GET 响应转换为流式读取结果（OSS 与 S3 共用）
+pub(super) fn read_stream_from_response(
+    response: reqwest::Response,
+    key: &str,
+    backend_name: &'static str,
+) -> Result<StorageReadStream, StorageError> {
+    let content_range = response
+        .headers()
+        .get(reqwest::header::CONTENT_RANGE)
+        .and_then(|v| v.to_str().ok())
+        .map(|v| v.to_string());
+    let content_length = response
+        .headers()
+        .get(reqwest::header::CONTENT_LENGTH)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.parse::<u64>().ok());
+
+    let (total_size, resolved) = match response.status() {
+        StatusCode::OK => (content_length, None),
+        StatusCode::PARTIAL_CONTENT => {
+            let resolved = parse_range_from_content_range(content_range.as_deref());
+            (
+                parse_total_length_from_content_range(content_range.as_deref()),
+                resolved,
+            )
         }
-    }
-    encoded
+        StatusCode::NOT_FOUND => {
+            return Err(StorageError::NotFound(format!(
+                "{} 文件不存在: {}",
+                backend_name, key
+            )))
+        }
+        StatusCode::RANGE_NOT_SATISFIABLE => {
+            return Err(StorageError::RangeNotSatisfiable(
+                parse_total_length_from_content_range(content_range.as_deref()).unwrap_or(0),
+            ))
+        }
+        status => {
+            return Err(StorageError::Backend(format!(
+                "{} 读取失败，HTTP 状态码: {}",
+                backend_name, status
+            )))
+        }
+    };
+
+    let stream = response.bytes_stream().map(move |chunk| {
+        chunk
+            .map(|bytes| bytes.to_vec())
+            .map_err(|e| StorageError::Backend(format!("{} 响应读取失败: {}", backend_name, e)))
+    });
+
+    Ok(StorageReadStream {
+        stream: Box::pin(stream),
+        content_length,
+        total_size,
+        range: resolved,
+    })
 }
 
-fn hex_lower(bytes: &[u8]) -> String {
+pub(super) fn hex_lower(bytes: &[u8]) -> String {
     bytes
         .iter()
         .map(|b| format!("{:02x}", b))
         .collect::<String>()
 }
 
-fn hmac_sha256(key: &[u8], message: &[u8]) -> [u8; 32] {
+pub(super) fn hmac_sha256(key: &[u8], message: &[u8]) -> [u8; 32] {
     const BLOCK_SIZE: usize = 64;
     let mut key_block = [0u8; BLOCK_SIZE];
 
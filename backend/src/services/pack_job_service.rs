@@ -0,0 +1,512 @@
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+use sqlx::PgPool;
+use std::sync::Arc;
+use uuid::Uuid;
+
+use crate::config::Config;
+use crate::models::{
+    PackDownloadQuery, PackJob, PackJobListResponse, PackJobResponse, PackJobStatus,
+};
+use crate::services::{
+    is_object_storage_type, resolve_pack_storage, FavoritePackSummary, FavoriteService, PackTask,
+    ResourceError, StorageBackend, StorageBackendType,
+};
+
+/// 打包文件在存储后端中的目录
+pub const PACK_ARTIFACT_PREFIX: &str = "tmp/packs";
+/// 下载链接有效期（秒），客户端轮询任务状态时会拿到新的链接
+const DOWNLOAD_URL_EXPIRY_SECS: i64 = 600;
+/// 我的打包任务列表最多返回的条数
+const MAX_LIST_JOBS: i64 = 20;
+
+const PACK_JOB_COLUMNS: &str = r#"
+    id, user_id, favorite_id, favorite_name, status, total_files, processed_files,
+    skipped_files, bytes_written, storage_key, storage_type, file_name, error_message,
+    completed_at, expires_at, created_at
+"#;
+
+pub struct PackJobService;
+
+impl PackJobService {
+    /// 创建打包任务
+    /// 校验收藏夹归属、文件数量与用户进行中的任务数，返回任务记录和待执行的打包任务
+    pub async fn create_job(
+        pool: &PgPool,
+        storage: &Arc<dyn StorageBackend>,
+        config: &Config,
+        favorite_id: Uuid,
+        user_id: Uuid,
+    ) -> Result<(PackJob, PackTask), ResourceError> {
+        let favorite_name = sqlx::query_scalar::<_, String>(
+            "SELECT name FROM favorites WHERE id = $1 AND user_id = $2",
+        )
+        .bind(favorite_id)
+        .bind(user_id)
+        .fetch_optional(pool)
+        .await?
+        .ok_or_else(|| ResourceError::NotFound("收藏夹不存在".to_string()))?;
+
+        // 限制每个用户同时进行的任务数
+        let active_jobs = sqlx::query_scalar::<_, i64>(
+            "SELECT COUNT(*) FROM pack_jobs WHERE user_id = $1 AND status IN ('pending', 'running')",
+        )
+        .bind(user_id)
+        .fetch_one(pool)
+        .await?;
+
+        if active_jobs >= config.pack_job_max_active_per_user {
+            return Err(ResourceError::ValidationError(format!(
+                "最多同时进行 {} 个打包任务，请等待已有任务完成",
+                config.pack_job_max_active_per_user
+            )));
+        }
+
+        let task = FavoriteService::prepare_pack(
+            pool,
+            storage,
+            config,
+            favorite_id,
+            user_id,
+            &favorite_name,
+        )
+        .await?;
+
+        let job = sqlx::query_as::<_, PackJob>(&format!(
+            r#"
+            INSERT INTO pack_jobs (user_id, favorite_id, favorite_name, status, total_files, file_name)
+            VALUES ($1, $2, $3, $4, $5, $6)
+            RETURNING {}
+            "#,
+            PACK_JOB_COLUMNS
+        ))
+        .bind(user_id)
+        .bind(favorite_id)
+        .bind(&favorite_name)
+        .bind(PackJobStatus::Pending.as_str())
+        .bind(task.total_files() as i32)
+        .bind(task.filename())
+        .fetch_one(pool)
+        .await?;
+
+        Ok((job, task))
+    }
+
+    /// 获取打包任务详情（仅任务创建者可见）
+    pub async fn get_job(
+        pool: &PgPool,
+        storage: &Arc<dyn StorageBackend>,
+        config: &Config,
+        job_id: Uuid,
+        user_id: Uuid,
+    ) -> Result<PackJobResponse, ResourceError> {
+        let job = Self::find_job(pool, job_id)
+            .await?
+            .filter(|job| job.user_id == user_id)
+            .ok_or_else(|| ResourceError::NotFound("打包任务不存在".to_string()))?;
+
+        Ok(Self::build_response(job, storage, config).await)
+    }
+
+    /// 获取我最近的打包任务
+    pub async fn get_user_jobs(
+        pool: &PgPool,
+        storage: &Arc<dyn StorageBackend>,
+        config: &Config,
+        user_id: Uuid,
+    ) -> Result<PackJobListResponse, ResourceError> {
+        let jobs = sqlx::query_as::<_, PackJob>(&format!(
+            "SELECT {} FROM pack_jobs WHERE user_id = $1 ORDER BY created_at DESC LIMIT $2",
+            PACK_JOB_COLUMNS
+        ))
+        .bind(user_id)
+        .bind(MAX_LIST_JOBS)
+        .fetch_all(pool)
+        .await?;
+
+        let mut responses = Vec::with_capacity(jobs.len());
+        for job in jobs {
+            responses.push(Self::build_response(job, storage, config).await);
+        }
+
+        Ok(PackJobListResponse { jobs: responses })
+    }
+
+    /// 校验签名下载链接，返回可下载的任务
+    pub async fn get_download_job(
+        pool: &PgPool,
+        config: &Config,
+        job_id: Uuid,
+        query: &PackDownloadQuery,
+    ) -> Result<PackJob, ResourceError> {
+        if !verify_download_signature(&config.jwt_secret, job_id, query.expires, &query.signature) {
+            return Err(ResourceError::Unauthorized("下载链接无效".to_string()));
+        }
+
+        if query.expires < chrono::Utc::now().timestamp() {
+            return Err(ResourceError::Unauthorized("下载链接已过期".to_string()));
+        }
+
+        let job = Self::find_job(pool, job_id)
+            .await?
+            .ok_or_else(|| ResourceError::NotFound("打包任务不存在".to_string()))?;
+
+        if PackJobStatus::from_str(&job.status) != Some(PackJobStatus::Completed)
+            || job.storage_key.is_none()
+        {
+            return Err(ResourceError::NotFound(
+                "打包文件不存在或已过期".to_string(),
+            ));
+        }
+
+        Ok(job)
+    }
+
+    async fn find_job(pool: &PgPool, job_id: Uuid) -> Result<Option<PackJob>, ResourceError> {
+        let job = sqlx::query_as::<_, PackJob>(&format!(
+            "SELECT {} FROM pack_jobs WHERE id = $1",
+            PACK_JOB_COLUMNS
+        ))
+        .bind(job_id)
+        .fetch_optional(pool)
+        .await?;
+
+        Ok(job)
+    }
+
+    /// 标记任务开始执行
+    pub async fn mark_running(pool: &PgPool, job_id: Uuid) -> Result<(), ResourceError> {
+        sqlx::query(
+            "UPDATE pack_jobs SET status = $2, updated_at = CURRENT_TIMESTAMP WHERE id = $1",
+        )
+        .bind(job_id)
+        .bind(PackJobStatus::Running.as_str())
+        .execute(pool)
+        .await?;
+
+        Ok(())
+    }
+
+    /// 更新任务进度
+    pub async fn update_progress(
+        pool: &PgPool,
+        job_id: Uuid,
+        summary: &FavoritePackSummary,
+    ) -> Result<(), ResourceError> {
+        sqlx::query(
+            r#"
+            UPDATE pack_jobs
+            SET processed_files = $2, skipped_files = $3, bytes_written = $4,
+                updated_at = CURRENT_TIMESTAMP
+            WHERE id = $1
+            "#,
+        )
+        .bind(job_id)
+        .bind(summary.processed_files() as i32)
+        .bind(summary.skipped.len() as i32)
+        .bind(summary.total_bytes as i64)
+        .execute(pool)
+        .await?;
+
+        Ok(())
+    }
+
+    /// 标记任务完成，记录打包文件位置并设置过期时间
+    pub async fn mark_completed(
+        pool: &PgPool,
+        job_id: Uuid,
+        summary: &FavoritePackSummary,
+        storage_key: &str,
+        storage_type: StorageBackendType,
+        ttl_secs: u64,
+    ) -> Result<(), ResourceError> {
+        sqlx::query(
+            r#"
+            UPDATE pack_jobs
+            SET status = $2, processed_files = $3, skipped_files = $4, bytes_written = $5,
+                storage_key = $6, storage_type = $7,
+                completed_at = CURRENT_TIMESTAMP,
+                expires_at = CURRENT_TIMESTAMP + make_interval(secs => $8),
+                updated_at = CURRENT_TIMESTAMP
+            WHERE id = $1
+            "#,
+        )
+        .bind(job_id)
+        .bind(PackJobStatus::Completed.as_str())
+        .bind(summary.processed_files() as i32)
+        .bind(summary.skipped.len() as i32)
+        .bind(summary.total_bytes as i64)
+        .bind(storage_key)
+        .bind(storage_type.as_str())
+        .bind(ttl_secs as f64)
+        .execute(pool)
+        .await?;
+
+        Ok(())
+    }
+
+    /// 标记任务失败
+    pub async fn mark_failed(
+        pool: &PgPool,
+        job_id: Uuid,
+        error_message: &str,
+    ) -> Result<(), ResourceError> {
+        sqlx::query(
+            r#"
+            UPDATE pack_jobs
+            SET status = $2, error_message = $3, updated_at = CURRENT_TIMESTAMP
+            WHERE id = $1
+            "#,
+        )
+        .bind(job_id)
+        .bind(PackJobStatus::Failed.as_str())
+        .bind(error_message)
+        .execute(pool)
+        .await?;
+
+        Ok(())
+    }
+
+    /// 将未完成的任务标记为失败（服务重启后任务无法继续执行）
+    pub async fn fail_interrupted_jobs(pool: &PgPool) -> Result<u64, ResourceError> {
+        let result = sqlx::query(
+            r#"
+            UPDATE pack_jobs
+            SET status = 'failed', error_message = '服务重启，任务中断',
+                updated_at = CURRENT_TIMESTAMP
+            WHERE status IN ('pending', 'running')
+            "#,
+        )
+        .execute(pool)
+        .await?;
+
+        Ok(result.rows_affected())
+    }
+
+    /// 获取已过期但打包文件尚未清理的任务
+    pub async fn get_expired_jobs(
+        pool: &PgPool,
+        limit: i64,
+    ) -> Result<Vec<PackJob>, ResourceError> {
+        let jobs = sqlx::query_as::<_, PackJob>(&format!(
+            r#"
+            SELECT {} FROM pack_jobs
+            WHERE status = 'completed' AND expires_at < CURRENT_TIMESTAMP
+            ORDER BY expires_at
+            LIMIT $1
+            "#,
+            PACK_JOB_COLUMNS
+        ))
+        .bind(limit)
+        .fetch_all(pool)
+        .await?;
+
+        Ok(jobs)
+    }
+
+    /// 标记任务的打包文件已清理
+    pub async fn mark_expired(pool: &PgPool, job_id: Uuid) -> Result<(), ResourceError> {
+        sqlx::query(
+            r#"
+            UPDATE pack_jobs
+            SET status = $2, storage_key = NULL, updated_at = CURRENT_TIMESTAMP
+            WHERE id = $1
+            "#,
+        )
+        .bind(job_id)
+        .bind(PackJobStatus::Expired.as_str())
+        .execute(pool)
+        .await?;
+
+        Ok(())
+    }
+
+    /// 删除过期或失败超过保留期的任务记录
+    pub async fn purge_finished_jobs(
+        pool: &PgPool,
+        retention_secs: u64,
+    ) -> Result<u64, ResourceError> {
+        let result = sqlx::query(
+            r#"
+            DELETE FROM pack_jobs
+            WHERE status IN ('failed', 'expired')
+              AND created_at < CURRENT_TIMESTAMP - make_interval(secs => $1)
+            "#,
+        )
+        .bind(retention_secs as f64)
+        .execute(pool)
+        .await?;
+
+        Ok(result.rows_affected())
+    }
+
+    /// 构建任务响应，已完成且未过期的任务附带下载链接
+    async fn build_response(
+        job: PackJob,
+        storage: &Arc<dyn StorageBackend>,
+        config: &Config,
+    ) -> PackJobResponse {
+        let download_url = match Self::build_download_url(&job, storage, config).await {
+            Ok(url) => url,
+            Err(e) => {
+                log::warn!(
+                    "[PackJob] 生成下载链接失败 | job_id={}, error={}",
+                    job.id,
+                    e
+                );
+                None
+            }
+        };
+
+        PackJobResponse {
+            id: job.id,
+            favorite_id: job.favorite_id,
+            favorite_name: job.favorite_name,
+            status: job.status,
+            total_files: job.total_files,
+            processed_files: job.processed_files,
+            skipped_files: job.skipped_files,
+            bytes_written: job.bytes_written,
+            file_name: job.file_name,
+            download_url,
+            error_message: job.error_message,
+            expires_at: job
+                .expires_at
+                .map(|dt| dt.format("%Y-%m-%dT%H:%M:%S%.3fZ").to_string()),
+            completed_at: job
+                .completed_at
+                .map(|dt| dt.format("%Y-%m-%dT%H:%M:%S%.3fZ").to_string()),
+            created_at: job.created_at.format("%Y-%m-%dT%H:%M:%S%.3fZ").to_string(),
+        }
+    }
+
+    /// 生成下载链接
+    /// OSS 存储直接返回预签名地址，本地存储返回带 HMAC 签名的下载接口地址
+    /// 链接有效期不超过打包文件的过期时间
+    async fn build_download_url(
+        job: &PackJob,
+        storage: &Arc<dyn StorageBackend>,
+        config: &Config,
+    ) -> Result<Option<String>, String> {
+        if PackJobStatus::from_str(&job.status) != Some(PackJobStatus::Completed) {
+            return Ok(None);
+        }
+        let (Some(storage_key), Some(expires_at)) = (&job.storage_key, job.expires_at) else {
+            return Ok(None);
+        };
+
+        let remaining_secs = (expires_at - chrono::Local::now().naive_local()).num_seconds();
+        if remaining_secs <= 0 {
+            return Ok(None);
+        }
+        let expiry_secs = remaining_secs.min(DOWNLOAD_URL_EXPIRY_SECS);
+
+        let storage_type = job.storage_type.as_deref().unwrap_or("local");
+        if is_object_storage_type(Some(storage_type)) {
+            let object_storage = resolve_pack_storage(storage, config, storage_type)?;
+            let filename = job.file_name.as_deref().unwrap_or("download.zip");
+            let url = object_storage
+                .get_download_url(storage_key, filename, expiry_secs as u64)
+                .await
+                .map_err(|e| e.to_string())?;
+            return Ok(Some(url));
+        }
+
+        let expires = chrono::Utc::now().timestamp() + expiry_secs;
+        let signature = sign_download(&config.jwt_secret, job.id, expires);
+        Ok(Some(format!(
+            "/api/pack-downloads/{}?expires={}&signature={}",
+            job.id, expires, signature
+        )))
+    }
+}
+
+/// 打包文件在存储后端中的 key
+pub fn pack_artifact_key(job_id: Uuid) -> String {
+    format!("{}/{}.zip", PACK_ARTIFACT_PREFIX, job_id)
+}
+
+/// 计算下载链接签名：HMAC-SHA256(secret, "pack:{job_id}:{expires}")
+fn sign_download(secret: &str, job_id: Uuid, expires: i64) -> String {
+    let mut mac =
+        Hmac::<Sha256>::new_from_slice(secret.as_bytes()).expect("HMAC 可以接受任意长度的密钥");
+    mac.update(format!("pack:{}:{}", job_id, expires).as_bytes());
+    mac.finalize()
+        .into_bytes()
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect()
+}
+
+/// 校验下载链接签名（常量时间比较）
+fn verify_download_signature(secret: &str, job_id: Uuid, expires: i64, signature: &str) -> bool {
+    let expected = sign_download(secret, job_id, expires);
+    if expected.len() != signature.len() {
+        return false;
+    }
+    expected
+        .bytes()
+        .zip(signature.bytes())
+        .fold(0u8, |acc, (a, b)| acc | (a ^ b))
+        == 0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_download_signature_round_trip() {
+        let job_id = Uuid::new_v4();
+        let signature = sign_download("secret", job_id, 1_700_000_000);
+
+        assert_eq!(signature.len(), 64);
+        assert!(verify_download_signature(
+            "secret",
+            job_id,
+            1_700_000_000,
+            &signature
+        ));
+    }
+
+    #[test]
+    fn test_download_signature_rejects_tampering() {
+        let job_id = Uuid::new_v4();
+        let signature = sign_download("secret", job_id, 1_700_000_000);
+
+        // 修改过期时间、任务 ID 或密钥后签名失效
+        assert!(!verify_download_signature(
+            "secret",
+            job_id,
+            1_700_000_001,
+            &signature
+        ));
+        assert!(!verify_download_signature(
+            "secret",
+            Uuid::new_v4(),
+            1_700_000_000,
+            &signature
+        ));
+        assert!(!verify_download_signature(
+            "other",
+            job_id,
+            1_700_000_000,
+            &signature
+        ));
+        assert!(!verify_download_signature(
+            "secret",
+            job_id,
+            1_700_000_000,
+            &signature[..32]
+        ));
+    }
+
+    #[test]
+    fn test_pack_artifact_key() {
+        let job_id = Uuid::nil();
+        assert_eq!(
+            pack_artifact_key(job_id),
+            "tmp/packs/00000000-0000-0000-0000-000000000000.zip"
+        );
+    }
+}
@@ -0,0 +1,555 @@
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::models::{
+    AssignRoleRequest, CreateRoleRequest, Permission, Role, RoleResponse, UpdateRoleRequest,
+    UserPermissions, UserRole, UserRoleAssignment, RESERVED_ROLE_NAME,
+};
+
+/// 角色显示名称最大长度
+const MAX_ROLE_DISPLAY_NAME_LENGTH: usize = 100;
+/// 角色说明最大长度
+const MAX_ROLE_DESCRIPTION_LENGTH: usize = 500;
+
+/// 权限服务错误类型
+#[derive(Debug)]
+pub enum PermissionError {
+    DatabaseError(String),
+    NotFound(String),
+    ValidationError(String),
+    Forbidden(String),
+    Conflict(String),
+}
+
+impl std::fmt::Display for PermissionError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PermissionError::DatabaseError(msg) => write!(f, "数据库错误: {}", msg),
+            PermissionError::NotFound(msg) => write!(f, "未找到: {}", msg),
+            PermissionError::ValidationError(msg) => write!(f, "验证错误: {}", msg),
+            PermissionError::Forbidden(msg) => write!(f, "权限不足: {}", msg),
+            PermissionError::Conflict(msg) => write!(f, "冲突: {}", msg),
+        }
+    }
+}
+
+impl std::error::Error for PermissionError {}
+
+impl From<sqlx::Error> for PermissionError {
+    fn from(err: sqlx::Error) -> Self {
+        PermissionError::DatabaseError(err.to_string())
+    }
+}
+
+/// 角色分配查询字段
+const ASSIGNMENT_SELECT_SQL: &str = r#"
+    SELECT
+        ur.id, ur.role_id, r.name AS role_name, r.display_name AS role_display_name,
+        ur.course_sn, c.name AS course_name, ur.granted_by, ur.created_at
+    FROM user_roles ur
+    JOIN roles r ON ur.role_id = r.id
+    LEFT JOIN courses c ON ur.course_sn = c.sn
+"#;
+
+/// 权限服务
+pub struct PermissionService;
+
+impl PermissionService {
+    /// 加载用户在管理后台的有效权限
+    ///
+    /// 内置管理员拥有全部权限，其他用户的权限来自分配的角色
+    pub async fn load_user_permissions(
+        pool: &PgPool,
+        user_id: Uuid,
+        role: &UserRole,
+    ) -> Result<UserPermissions, sqlx::Error> {
+        if *role == UserRole::Admin {
+            return Ok(UserPermissions::admin());
+        }
+
+        let grants: Vec<(Vec<String>, Option<i64>)> = sqlx::query_as(
+            r#"
+            SELECT r.permissions, ur.course_sn
+            FROM user_roles ur
+            JOIN roles r ON ur.role_id = r.id
+            WHERE ur.user_id = $1
+            "#,
+        )
+        .bind(user_id)
+        .fetch_all(pool)
+        .await?;
+
+        let mut permissions = UserPermissions::default();
+        for (names, course_sn) in grants {
+            // 角色中已不再支持的权限名直接忽略
+            for permission in names.iter().filter_map(|name| Permission::from_str(name)) {
+                permissions.grant(permission, course_sn);
+            }
+        }
+        Ok(permissions)
+    }
+
+    /// 获取所有角色
+    pub async fn list_roles(pool: &PgPool) -> Result<Vec<RoleResponse>, PermissionError> {
+        let rows: Vec<(Uuid, i64)> = sqlx::query_as(
+            "SELECT role_id, COUNT(DISTINCT user_id) FROM user_roles GROUP BY role_id",
+        )
+        .fetch_all(pool)
+        .await?;
+        let user_counts: std::collections::HashMap<Uuid, i64> = rows.into_iter().collect();
+
+        let roles: Vec<Role> =
+            sqlx::query_as("SELECT * FROM roles ORDER BY is_system DESC, created_at ASC")
+                .fetch_all(pool)
+                .await?;
+
+        Ok(roles
+            .into_iter()
+            .map(|role| {
+                let user_count = user_counts.get(&role.id).copied().unwrap_or(0);
+                RoleResponse::from_role(role, user_count)
+            })
+            .collect())
+    }
+
+    /// 创建角色，`actor` 只能授予自己在全站范围内拥有的权限
+    pub async fn create_role(
+        pool: &PgPool,
+        actor: &UserPermissions,
+        req: &CreateRoleRequest,
+    ) -> Result<RoleResponse, PermissionError> {
+        let name = req.name.trim();
+        validate_role_name(name).map_err(PermissionError::ValidationError)?;
+        let display_name = validate_display_name(&req.display_name)?;
+        let description = normalize_description(req.description.as_deref())?;
+        let permissions =
+            normalize_permissions(&req.permissions).map_err(PermissionError::ValidationError)?;
+        check_grantable(actor, &permissions)?;
+
+        let role: Role = sqlx::query_as(
+            r#"
+            INSERT INTO roles (name, display_name, description, permissions)
+            VALUES ($1, $2, $3, $4)
+            RETURNING *
+            "#,
+        )
+        .bind(name)
+        .bind(display_name)
+        .bind(description)
+        .bind(&permissions)
+        .fetch_one(pool)
+        .await
+        .map_err(|e| match e {
+            sqlx::Error::Database(ref db) if db.is_unique_violation() => {
+                PermissionError::Conflict(format!("角色 {} 已存在", name))
+            }
+            e => e.into(),
+        })?;
+
+        Ok(RoleResponse::from_role(role, 0))
+    }
+
+    /// 更新角色，修改后的权限对已分配该角色的用户立即生效
+    ///
+    /// 返回更新前后的角色
+    pub async fn update_role(
+        pool: &PgPool,
+        actor: &UserPermissions,
+        role_id: Uuid,
+        req: &UpdateRoleRequest,
+    ) -> Result<(Role, RoleResponse), PermissionError> {
+        let before = Self::find_role(pool, role_id).await?;
+
+        let display_name = match req.display_name.as_deref() {
+            Some(display_name) => validate_display_name(display_name)?.to_string(),
+            None => before.display_name.clone(),
+        };
+        let description = match req.description.as_deref() {
+            Some(description) => normalize_description(Some(description))?,
+            None => before.description.clone(),
+        };
+        let permissions = match req.permissions.as_ref() {
+            Some(permissions) => {
+                let permissions =
+                    normalize_permissions(permissions).map_err(PermissionError::ValidationError)?;
+                // 新增和移除的权限都需要操作者拥有，防止借助角色扩大或削减他人权限
+                let changed: Vec<String> = permissions
+                    .iter()
+                    .filter(|p| !before.permissions.contains(p))
+                    .chain(
+                        before
+                            .permissions
+                            .iter()
+                            .filter(|p| !permissions.contains(p)),
+                    )
+                    .cloned()
+                    .collect();
+                check_grantable(actor, &changed)?;
+                permissions
+            }
+            None => before.permissions.clone(),
+        };
+
+        // 已按课程分配的角色只能包含可按课程授予的权限
+        if Self::has_course_assignments(pool, role_id).await? {
+            if let Some(name) = first_unscopable(&permissions) {
+                return Err(PermissionError::ValidationError(format!(
+                    "该角色已分配到课程范围，不能添加全站权限 {}",
+                    name
+                )));
+            }
+        }
+
+        let role: Role = sqlx::query_as(
+            r#"
+            UPDATE roles
+            SET display_name = $1, description = $2, permissions = $3, updated_at = NOW()
+            WHERE id = $4
+            RETURNING *
+            "#,
+        )
+        .bind(display_name)
+        .bind(description)
+        .bind(&permissions)
+        .bind(role_id)
+        .fetch_optional(pool)
+        .await?
+        .ok_or_else(|| PermissionError::NotFound("角色不存在".to_string()))?;
+
+        let user_count: i64 = sqlx::query_scalar(
+            "SELECT COUNT(DISTINCT user_id) FROM user_roles WHERE role_id = $1",
+        )
+        .bind(role_id)
+        .fetch_one(pool)
+        .await?;
+
+        Ok((before, RoleResponse::from_role(role, user_count)))
+    }
+
+    /// 删除角色，同时取消所有用户的该角色；内置角色不能删除
+    pub async fn delete_role(
+        pool: &PgPool,
+        actor: &UserPermissions,
+        role_id: Uuid,
+    ) -> Result<Role, PermissionError> {
+        let role = Self::find_role(pool, role_id).await?;
+        if role.is_system {
+            return Err(PermissionError::ValidationError(
+                "内置角色不能删除".to_string(),
+            ));
+        }
+        check_grantable(actor, &role.permissions)?;
+
+        sqlx::query("DELETE FROM roles WHERE id = $1")
+            .bind(role_id)
+            .execute(pool)
+            .await?;
+        Ok(role)
+    }
+
+    /// 获取用户的角色分配
+    pub async fn list_user_roles(
+        pool: &PgPool,
+        user_id: Uuid,
+    ) -> Result<Vec<UserRoleAssignment>, PermissionError> {
+        Self::ensure_user_exists(pool, user_id).await?;
+
+        let assignments = sqlx::query_as::<_, UserRoleAssignment>(&format!(
+            "{} WHERE ur.user_id = $1 ORDER BY ur.created_at ASC",
+            ASSIGNMENT_SELECT_SQL
+        ))
+        .bind(user_id)
+        .fetch_all(pool)
+        .await?;
+        Ok(assignments)
+    }
+
+    /// 为用户分配角色，`course_sn` 不为空时只在该课程范围内生效
+    pub async fn assign_role(
+        pool: &PgPool,
+        actor: &UserPermissions,
+        user_id: Uuid,
+        req: &AssignRoleRequest,
+        granted_by: Uuid,
+    ) -> Result<UserRoleAssignment, PermissionError> {
+        Self::ensure_user_exists(pool, user_id).await?;
+        let role = Self::find_role(pool, req.role_id).await?;
+        check_grantable(actor, &role.permissions)?;
+
+        if let Some(course_sn) = req.course_sn {
+            let course_exists: bool =
+                sqlx::query_scalar("SELECT EXISTS(SELECT 1 FROM courses WHERE sn = $1)")
+                    .bind(course_sn)
+                    .fetch_one(pool)
+                    .await?;
+            if !course_exists {
+                return Err(PermissionError::NotFound("课程不存在".to_string()));
+            }
+            if let Some(name) = first_unscopable(&role.permissions) {
+                return Err(PermissionError::ValidationError(format!(
+                    "角色包含不能按课程授予的权限 {}，只能在全站范围分配",
+                    name
+                )));
+            }
+        }
+
+        let assignment_id: Uuid = sqlx::query_scalar(
+            r#"
+            INSERT INTO user_roles (user_id, role_id, course_sn, granted_by)
+            VALUES ($1, $2, $3, $4)
+            RETURNING id
+            "#,
+        )
+        .bind(user_id)
+        .bind(req.role_id)
+        .bind(req.course_sn)
+        .bind(granted_by)
+        .fetch_one(pool)
+        .await
+        .map_err(|e| match e {
+            sqlx::Error::Database(ref db) if db.is_unique_violation() => {
+                PermissionError::Conflict("用户已拥有该角色".to_string())
+            }
+            e => e.into(),
+        })?;
+
+        Self::find_assignment(pool, user_id, assignment_id).await
+    }
+
+    /// 取消用户的角色分配，返回被取消的分配
+    pub async fn revoke_role(
+        pool: &PgPool,
+        actor: &UserPermissions,
+        user_id: Uuid,
+        assignment_id: Uuid,
+    ) -> Result<UserRoleAssignment, PermissionError> {
+        let assignment = Self::find_assignment(pool, user_id, assignment_id).await?;
+        let role = Self::find_role(pool, assignment.role_id).await?;
+        check_grantable(actor, &role.permissions)?;
+
+        sqlx::query("DELETE FROM user_roles WHERE id = $1 AND user_id = $2")
+            .bind(assignment_id)
+            .bind(user_id)
+            .execute(pool)
+            .await?;
+        Ok(assignment)
+    }
+
+    /// 资源关联的课程
+    pub async fn resource_course_sns(
+        pool: &PgPool,
+        resource_id: Uuid,
+    ) -> Result<Vec<i64>, sqlx::Error> {
+        sqlx::query_scalar("SELECT course_sn FROM resource_courses WHERE resource_id = $1")
+            .bind(resource_id)
+            .fetch_all(pool)
+            .await
+    }
+
+    /// 评论所属资源关联的课程
+    pub async fn comment_course_sns(
+        pool: &PgPool,
+        comment_id: Uuid,
+    ) -> Result<Vec<i64>, sqlx::Error> {
+        sqlx::query_scalar(
+            r#"
+            SELECT rc.course_sn
+            FROM comments c
+            JOIN resource_courses rc ON rc.resource_id = c.resource_id
+            WHERE c.id = $1
+            "#,
+        )
+        .bind(comment_id)
+        .fetch_all(pool)
+        .await
+    }
+
+    async fn find_role(pool: &PgPool, role_id: Uuid) -> Result<Role, PermissionError> {
+        sqlx::query_as("SELECT * FROM roles WHERE id = $1")
+            .bind(role_id)
+            .fetch_optional(pool)
+            .await?
+            .ok_or_else(|| PermissionError::NotFound("角色不存在".to_string()))
+    }
+
+    async fn find_assignment(
+        pool: &PgPool,
+        user_id: Uuid,
+        assignment_id: Uuid,
+    ) -> Result<UserRoleAssignment, PermissionError> {
+        sqlx::query_as::<_, UserRoleAssignment>(&format!(
+            "{} WHERE ur.id = $1 AND ur.user_id = $2",
+            ASSIGNMENT_SELECT_SQL
+        ))
+        .bind(assignment_id)
+        .bind(user_id)
+        .fetch_optional(pool)
+        .await?
+        .ok_or_else(|| PermissionError::NotFound("角色分配不存在".to_string()))
+    }
+
+    async fn has_course_assignments(pool: &PgPool, role_id: Uuid) -> Result<bool, sqlx::Error> {
+        sqlx::query_scalar(
+            "SELECT EXISTS(SELECT 1 FROM user_roles WHERE role_id = $1 AND course_sn IS NOT NULL)",
+        )
+        .bind(role_id)
+        .fetch_one(pool)
+        .await
+    }
+
+    async fn ensure_user_exists(pool: &PgPool, user_id: Uuid) -> Result<(), PermissionError> {
+        let exists: bool = sqlx::query_scalar("SELECT EXISTS(SELECT 1 FROM users WHERE id = $1)")
+            .bind(user_id)
+            .fetch_one(pool)
+            .await?;
+        if !exists {
+            return Err(PermissionError::NotFound("用户不存在".to_string()));
+        }
+        Ok(())
+    }
+}
+
+/// 校验角色标识：2-50 位，小写字母开头，只包含小写字母、数字和下划线
+fn validate_role_name(name: &str) -> Result<(), String> {
+    if name.len() < 2 || name.len() > 50 {
+        return Err("角色标识长度必须在2-50个字符之间".to_string());
+    }
+    if !name.starts_with(|c: char| c.is_ascii_lowercase())
+        || !name
+            .chars()
+            .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_')
+    {
+        return Err("角色标识必须以小写字母开头，只能包含小写字母、数字和下划线".to_string());
+    }
+    if name == RESERVED_ROLE_NAME {
+        return Err(format!("{} 是内置角色，不能作为角色标识", name));
+    }
+    Ok(())
+}
+
+fn validate_display_name(display_name: &str) -> Result<&str, PermissionError> {
+    let display_name = display_name.trim();
+    if display_name.is_empty() || display_name.chars().count() > MAX_ROLE_DISPLAY_NAME_LENGTH {
+        return Err(PermissionError::ValidationError(format!(
+            "角色名称不能为空且不能超过{}个字符",
+            MAX_ROLE_DISPLAY_NAME_LENGTH
+        )));
+    }
+    Ok(display_name)
+}
+
+fn normalize_description(description: Option<&str>) -> Result<Option<String>, PermissionError> {
+    let description = description.map(str::trim).filter(|d| !d.is_empty());
+    if description.is_some_and(|d| d.chars().count() > MAX_ROLE_DESCRIPTION_LENGTH) {
+        return Err(PermissionError::ValidationError(format!(
+            "角色说明不能超过{}个字符",
+            MAX_ROLE_DESCRIPTION_LENGTH
+        )));
+    }
+    Ok(description.map(str::to_string))
+}
+
+/// 校验权限名并去重，按权限列表顺序返回
+fn normalize_permissions(names: &[String]) -> Result<Vec<String>, String> {
+    if let Some(unknown) = names
+        .iter()
+        .find(|name| Permission::from_str(name.trim()).is_none())
+    {
+        return Err(format!("未知权限: {}", unknown));
+    }
+    if names.is_empty() {
+        return Err("角色至少需要包含一个权限".to_string());
+    }
+    Ok(Permission::ALL
+        .into_iter()
+        .filter(|p| names.iter().any(|name| name.trim() == p.as_str()))
+        .map(|p| p.as_str().to_string())
+        .collect())
+}
+
+/// 第一个不能按课程授予的权限
+fn first_unscopable(names: &[String]) -> Option<&str> {
+    names
+        .iter()
+        .find(|name| Permission::from_str(name).is_some_and(|p| !p.is_course_scopable()))
+        .map(String::as_str)
+}
+
+/// 操作者只能授予或撤销自己在全站范围内拥有的权限，防止越权提升
+fn check_grantable(actor: &UserPermissions, names: &[String]) -> Result<(), PermissionError> {
+    match names
+        .iter()
+        .find(|name| Permission::from_str(name).is_some_and(|p| !actor.has(p)))
+    {
+        Some(name) => Err(PermissionError::Forbidden(format!(
+            "不能授予或撤销自己没有的权限 {}",
+            name
+        ))),
+        None => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn names(names: &[&str]) -> Vec<String> {
+        names.iter().map(|name| name.to_string()).collect()
+    }
+
+    #[test]
+    fn test_validate_role_name() {
+        assert!(validate_role_name("moderator").is_ok());
+        assert!(validate_role_name("course_mod_2").is_ok());
+        assert!(validate_role_name("m").is_err());
+        assert!(validate_role_name("Moderator").is_err());
+        assert!(validate_role_name("2nd").is_err());
+        assert!(validate_role_name("content-manager").is_err());
+        assert!(validate_role_name("admin").is_err());
+        assert!(validate_role_name(&"a".repeat(51)).is_err());
+    }
+
+    #[test]
+    fn test_normalize_permissions() {
+        assert_eq!(
+            normalize_permissions(&names(&[
+                "comment.delete",
+                " resource.audit ",
+                "comment.delete"
+            ]))
+            .unwrap(),
+            names(&["resource.audit", "comment.delete"])
+        );
+        assert!(normalize_permissions(&names(&["resource.audit", "resource.all"])).is_err());
+        assert!(normalize_permissions(&[]).is_err());
+    }
+
+    #[test]
+    fn test_first_unscopable() {
+        assert_eq!(
+            first_unscopable(&names(&["resource.audit", "comment.audit"])),
+            None
+        );
+        assert_eq!(
+            first_unscopable(&names(&["resource.audit", "user.real_info.read"])),
+            Some("user.real_info.read")
+        );
+    }
+
+    #[test]
+    fn test_check_grantable() {
+        let mut actor = UserPermissions::default();
+        actor.grant(Permission::RoleManage, None);
+        actor.grant(Permission::ResourceAudit, None);
+        actor.grant(Permission::CommentAudit, Some(1));
+
+        assert!(check_grantable(&actor, &names(&["resource.audit"])).is_ok());
+        // 课程范围的权限不能再授予他人
+        assert!(matches!(
+            check_grantable(&actor, &names(&["resource.audit", "comment.audit"])),
+            Err(PermissionError::Forbidden(_))
+        ));
+        assert!(check_grantable(&actor, &names(&["user.manage"])).is_err());
+        assert!(check_grantable(&UserPermissions::admin(), &names(&["user.manage"])).is_ok());
+    }
+}
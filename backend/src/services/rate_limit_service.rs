@@ -0,0 +1,367 @@
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::config::Config;
+
+/// 内存中的失败记录超过该数量时清理过期记录
+const PRUNE_THRESHOLD: usize = 10_000;
+
+/// 指数退避策略
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BackoffPolicy {
+    /// 开始退避前允许的失败次数
+    pub free_attempts: u32,
+    /// 基础等待时间（秒）
+    pub base_delay_secs: u64,
+    /// 最长等待时间（秒）
+    pub max_delay_secs: u64,
+}
+
+impl BackoffPolicy {
+    /// 按 IP 限流的策略
+    pub fn for_ip(config: &Config) -> Self {
+        BackoffPolicy {
+            free_attempts: config.rate_limit_ip_free_attempts,
+            base_delay_secs: config.rate_limit_backoff_base_secs,
+            max_delay_secs: config.rate_limit_backoff_max_secs,
+        }
+    }
+
+    /// 按用户名限流的策略
+    pub fn for_username(config: &Config) -> Self {
+        BackoffPolicy {
+            free_attempts: config.rate_limit_username_free_attempts,
+            base_delay_secs: config.rate_limit_backoff_base_secs,
+            max_delay_secs: config.rate_limit_backoff_max_secs,
+        }
+    }
+
+    /// 累计失败 `failures` 次后需要等待的秒数
+    ///
+    /// 达到允许次数后等待基础时间，之后每次失败翻倍，不超过最长等待时间
+    pub fn delay_after(&self, failures: u32) -> u64 {
+        if failures < self.free_attempts {
+            return 0;
+        }
+        let exponent = (failures - self.free_attempts).min(32);
+        self.base_delay_secs
+            .saturating_mul(1u64 << exponent)
+            .min(self.max_delay_secs)
+    }
+}
+
+/// 单个键的失败记录
+#[derive(Debug, Clone, Copy)]
+struct AttemptRecord {
+    failures: u32,
+    last_failure: Instant,
+    blocked_until: Option<Instant>,
+}
+
+/// 内存限流器（按 IP 等键记录失败次数）
+///
+/// 多实例部署时各实例分别计数
+pub struct RateLimiter {
+    policy: BackoffPolicy,
+    /// 最后一次失败超过该时间后重新计数
+    window: Duration,
+    entries: Mutex<HashMap<String, AttemptRecord>>,
+}
+
+impl RateLimiter {
+    pub fn new(policy: BackoffPolicy, window: Duration) -> Self {
+        RateLimiter {
+            policy,
+            window,
+            entries: Mutex::new(HashMap::new()),
+        }
+    }
+
+    pub fn from_config(config: &Config) -> Self {
+        Self::new(
+            BackoffPolicy::for_ip(config),
+            Duration::from_secs(config.rate_limit_window_secs),
+        )
+    }
+
+    /// 检查是否处于退避中，返回需要等待的秒数
+    pub fn check(&self, key: &str) -> Option<u64> {
+        self.check_at(key, Instant::now())
+    }
+
+    /// 记录一次失败，返回本次失败后需要等待的秒数（不需要等待时返回 None）
+    pub fn record_failure(&self, key: &str) -> Option<u64> {
+        self.record_failure_at(key, Instant::now())
+    }
+
+    fn check_at(&self, key: &str, now: Instant) -> Option<u64> {
+        let entries = self.entries.lock().unwrap_or_else(|e| e.into_inner());
+        let blocked_until = entries.get(key)?.blocked_until?;
+        remaining_secs(blocked_until, now)
+    }
+
+    fn record_failure_at(&self, key: &str, now: Instant) -> Option<u64> {
+        let mut entries = self.entries.lock().unwrap_or_else(|e| e.into_inner());
+        if entries.len() > PRUNE_THRESHOLD {
+            let window = self.window;
+            entries.retain(|_, record| !is_expired(record, now, window));
+        }
+
+        let record = entries.entry(key.to_string()).or_insert(AttemptRecord {
+            failures: 0,
+            last_failure: now,
+            blocked_until: None,
+        });
+        if is_expired(record, now, self.window) {
+            record.failures = 0;
+            record.blocked_until = None;
+        }
+        record.failures = record.failures.saturating_add(1);
+        record.last_failure = now;
+
+        let delay = self.policy.delay_after(record.failures);
+        if delay == 0 {
+            return None;
+        }
+        record.blocked_until = Some(now + Duration::from_secs(delay));
+        Some(delay)
+    }
+}
+
+/// 失败记录是否已超过保留时间（退避中的记录不会过期）
+fn is_expired(record: &AttemptRecord, now: Instant, window: Duration) -> bool {
+    let still_blocked = record
+        .blocked_until
+        .map(|until| until > now)
+        .unwrap_or(false);
+    !still_blocked && now.saturating_duration_since(record.last_failure) >= window
+}
+
+/// 距离解除退避的秒数（向上取整），已解除时返回 None
+fn remaining_secs(until: Instant, now: Instant) -> Option<u64> {
+    let remaining = until.saturating_duration_since(now);
+    if remaining.is_zero() {
+        return None;
+    }
+    Some(remaining.as_secs() + u64::from(remaining.subsec_nanos() > 0))
+}
+
+/// 登录限制配置
+#[derive(Debug, Clone, Copy)]
+pub struct LoginLimits {
+    /// 按用户名退避的策略
+    pub username_policy: BackoffPolicy,
+    /// 连续失败多少次后锁定账号，0 表示不锁定
+    pub lockout_threshold: i32,
+    /// 锁定时长（秒）
+    pub lockout_secs: u64,
+}
+
+impl LoginLimits {
+    /// 未启用限流时返回 None
+    pub fn from_config(config: &Config) -> Option<Self> {
+        if !config.rate_limit_enabled {
+            return None;
+        }
+        Some(LoginLimits {
+            username_policy: BackoffPolicy::for_username(config),
+            lockout_threshold: config.login_lockout_threshold,
+            lockout_secs: config.login_lockout_secs,
+        })
+    }
+}
+
+/// 用户名维度的登录失败状态
+#[derive(Debug, sqlx::FromRow)]
+struct LoginAttemptState {
+    failed_login_attempts: i32,
+    /// 距锁定解除的秒数，未锁定时为空或不大于 0
+    lock_remaining_secs: Option<i64>,
+    /// 距最近一次失败的秒数
+    secs_since_failure: Option<i64>,
+}
+
+/// 登录失败记录服务（按用户名记录在 users 表中，多实例共享）
+pub struct LoginAttemptService;
+
+impl LoginAttemptService {
+    /// 检查用户名是否被锁定或处于退避中，返回需要等待的秒数
+    ///
+    /// 用户名不存在时不限制（由 IP 限流兜底）
+    pub async fn check(
+        pool: &PgPool,
+        username: &str,
+        limits: &LoginLimits,
+    ) -> Result<Option<u64>, sqlx::Error> {
+        let state: Option<LoginAttemptState> = sqlx::query_as(
+            r#"
+            SELECT failed_login_attempts,
+                   CEIL(EXTRACT(EPOCH FROM (locked_until - NOW())))::BIGINT AS lock_remaining_secs,
+                   FLOOR(EXTRACT(EPOCH FROM (NOW() - last_failed_login_at)))::BIGINT AS secs_since_failure
+            FROM users
+            WHERE username = $1 AND is_active = true
+            "#,
+        )
+        .bind(username)
+        .fetch_optional(pool)
+        .await?;
+
+        Ok(state.and_then(|state| login_retry_after(&state, &limits.username_policy)))
+    }
+
+    /// 记录一次登录失败，达到阈值时锁定账号
+    ///
+    /// 返回 Some(锁定秒数) 表示本次失败触发了锁定；锁定后失败次数重新计数
+    pub async fn record_failure(
+        pool: &PgPool,
+        user_id: Uuid,
+        limits: &LoginLimits,
+    ) -> Result<Option<u64>, sqlx::Error> {
+        let locked: Option<bool> = sqlx::query_scalar(
+            r#"
+            UPDATE users
+            SET failed_login_attempts = CASE
+                    WHEN $2 > 0 AND failed_login_attempts + 1 >= $2 THEN 0
+                    ELSE failed_login_attempts + 1
+                END,
+                locked_until = CASE
+                    WHEN $2 > 0 AND failed_login_attempts + 1 >= $2
+                        THEN NOW() + make_interval(secs => $3)
+                    ELSE locked_until
+                END,
+                last_failed_login_at = NOW()
+            WHERE id = $1
+            RETURNING (failed_login_attempts = 0 AND $2 > 0)
+            "#,
+        )
+        .bind(user_id)
+        .bind(limits.lockout_threshold)
+        .bind(limits.lockout_secs as f64)
+        .fetch_optional(pool)
+        .await?;
+
+        Ok(locked.unwrap_or(false).then_some(limits.lockout_secs))
+    }
+
+    /// 登录成功后清除失败记录
+    pub async fn reset(pool: &PgPool, user_id: Uuid) -> Result<(), sqlx::Error> {
+        sqlx::query(
+            r#"
+            UPDATE users
+            SET failed_login_attempts = 0, last_failed_login_at = NULL, locked_until = NULL
+            WHERE id = $1 AND (failed_login_attempts > 0 OR locked_until IS NOT NULL)
+            "#,
+        )
+        .bind(user_id)
+        .execute(pool)
+        .await?;
+
+        Ok(())
+    }
+}
+
+/// 根据失败状态计算需要等待的秒数
+fn login_retry_after(state: &LoginAttemptState, policy: &BackoffPolicy) -> Option<u64> {
+    if let Some(remaining) = state.lock_remaining_secs.filter(|secs| *secs > 0) {
+        return Some(remaining as u64);
+    }
+
+    let failures = state.failed_login_attempts.max(0) as u32;
+    let delay = policy.delay_after(failures);
+    let elapsed = state.secs_since_failure?.max(0) as u64;
+    (elapsed < delay).then(|| delay - elapsed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn policy() -> BackoffPolicy {
+        BackoffPolicy {
+            free_attempts: 3,
+            base_delay_secs: 2,
+            max_delay_secs: 60,
+        }
+    }
+
+    #[test]
+    fn test_backoff_delay() {
+        let p = policy();
+        assert_eq!(p.delay_after(0), 0);
+        assert_eq!(p.delay_after(2), 0);
+        assert_eq!(p.delay_after(3), 2);
+        assert_eq!(p.delay_after(4), 4);
+        assert_eq!(p.delay_after(6), 16);
+        // 不超过最长等待时间，次数很大时也不会溢出
+        assert_eq!(p.delay_after(8), 60);
+        assert_eq!(p.delay_after(u32::MAX), 60);
+    }
+
+    #[test]
+    fn test_rate_limiter_backoff() {
+        let limiter = RateLimiter::new(policy(), Duration::from_secs(600));
+        let now = Instant::now();
+
+        assert_eq!(limiter.record_failure_at("ip:1", now), None);
+        assert_eq!(limiter.record_failure_at("ip:1", now), None);
+        assert_eq!(limiter.check_at("ip:1", now), None);
+
+        // 第 3 次失败开始退避
+        assert_eq!(limiter.record_failure_at("ip:1", now), Some(2));
+        assert_eq!(limiter.check_at("ip:1", now), Some(2));
+        assert_eq!(
+            limiter.check_at("ip:1", now + Duration::from_millis(1500)),
+            Some(1)
+        );
+        assert_eq!(limiter.check_at("ip:1", now + Duration::from_secs(2)), None);
+
+        // 退避结束后再次失败，等待时间翻倍
+        let later = now + Duration::from_secs(3);
+        assert_eq!(limiter.record_failure_at("ip:1", later), Some(4));
+
+        // 其他键不受影响
+        assert_eq!(limiter.check_at("ip:2", later), None);
+    }
+
+    #[test]
+    fn test_rate_limiter_window_reset() {
+        let limiter = RateLimiter::new(policy(), Duration::from_secs(10));
+        let now = Instant::now();
+        for _ in 0..3 {
+            limiter.record_failure_at("ip:1", now);
+        }
+
+        // 超过保留时间后重新计数
+        let later = now + Duration::from_secs(20);
+        assert_eq!(limiter.check_at("ip:1", later), None);
+        assert_eq!(limiter.record_failure_at("ip:1", later), None);
+    }
+
+    #[test]
+    fn test_login_retry_after() {
+        let p = policy();
+        let state = |attempts: i32, lock: Option<i64>, since: Option<i64>| LoginAttemptState {
+            failed_login_attempts: attempts,
+            lock_remaining_secs: lock,
+            secs_since_failure: since,
+        };
+
+        assert_eq!(login_retry_after(&state(0, None, None), &p), None);
+        // 账号锁定中
+        assert_eq!(
+            login_retry_after(&state(0, Some(120), Some(1)), &p),
+            Some(120)
+        );
+        // 锁定已过期
+        assert_eq!(login_retry_after(&state(0, Some(-5), Some(1000)), &p), None);
+        // 未达到允许次数
+        assert_eq!(login_retry_after(&state(2, None, Some(0)), &p), None);
+        // 退避中：第 4 次失败后需等待 4 秒，已过去 1 秒
+        assert_eq!(login_retry_after(&state(4, None, Some(1)), &p), Some(3));
+        assert_eq!(login_retry_after(&state(4, None, Some(4)), &p), None);
+    }
+}
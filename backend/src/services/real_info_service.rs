@@ -0,0 +1,499 @@
+use aes_gcm::aead::{Aead, KeyInit, Payload};
+use aes_gcm::{Aes256Gcm, Nonce};
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use rand::{rngs::OsRng, RngCore};
+use serde_json::Value;
+use sqlx::PgPool;
+use std::sync::Arc;
+use uuid::Uuid;
+
+use crate::config::Config;
+
+/// 需要加密存储的实名信息字段，其余字段（如来源）保持明文
+pub const REAL_INFO_SENSITIVE_FIELDS: [&str; 4] = ["real_name", "student_id", "major", "grade"];
+
+/// 加密字段值的前缀，完整格式为 `enc:v1:{密钥编号}:{Base64(nonce || 密文)}`
+const ENCRYPTED_PREFIX: &str = "enc:v1:";
+
+const NONCE_LEN: usize = 12;
+
+/// 每批迁移的用户数
+const MIGRATION_BATCH_SIZE: i64 = 200;
+
+/// 实名信息加密错误类型
+#[derive(Debug)]
+pub enum RealInfoError {
+    ConfigError(String),
+    /// 数据使用的密钥未配置
+    UnknownKey(String),
+    /// 密文格式错误或校验失败
+    DecryptFailed(String),
+    EncryptFailed(String),
+    DatabaseError(String),
+}
+
+impl std::fmt::Display for RealInfoError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RealInfoError::ConfigError(msg) => write!(f, "实名信息加密配置错误: {}", msg),
+            RealInfoError::UnknownKey(msg) => write!(f, "未知的加密密钥: {}", msg),
+            RealInfoError::DecryptFailed(msg) => write!(f, "实名信息解密失败: {}", msg),
+            RealInfoError::EncryptFailed(msg) => write!(f, "实名信息加密失败: {}", msg),
+            RealInfoError::DatabaseError(msg) => write!(f, "数据库错误: {}", msg),
+        }
+    }
+}
+
+impl std::error::Error for RealInfoError {}
+
+impl From<sqlx::Error> for RealInfoError {
+    fn from(err: sqlx::Error) -> Self {
+        RealInfoError::DatabaseError(err.to_string())
+    }
+}
+
+/// 实名信息字段加密器，使用 AES-256-GCM
+///
+/// 关联数据为 `{用户ID}:{字段名}`，密文不能被挪用到其他用户或字段
+pub struct RealInfoCipher {
+    /// 第一个密钥用于加密，其余只用于解密
+    keys: Vec<(String, Aes256Gcm)>,
+}
+
+impl RealInfoCipher {
+    /// 解析 `密钥编号:Base64 编码的 32 字节密钥` 列表，未配置时返回 None
+    pub fn from_config(config: &Config) -> Result<Option<Self>, RealInfoError> {
+        if config.real_info_encryption_keys.is_empty() {
+            return Ok(None);
+        }
+
+        let mut keys: Vec<(String, Aes256Gcm)> = Vec::new();
+        for entry in &config.real_info_encryption_keys {
+            let (key_id, encoded) = entry.split_once(':').ok_or_else(|| {
+                RealInfoError::ConfigError("密钥格式应为 密钥编号:Base64密钥".to_string())
+            })?;
+            let key_id = key_id.trim();
+            if key_id.is_empty()
+                || !key_id
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
+            {
+                return Err(RealInfoError::ConfigError(format!(
+                    "密钥编号只能包含字母、数字、下划线和连字符: {:?}",
+                    key_id
+                )));
+            }
+            if keys.iter().any(|(id, _)| id == key_id) {
+                return Err(RealInfoError::ConfigError(format!(
+                    "密钥编号重复: {}",
+                    key_id
+                )));
+            }
+            let key = STANDARD.decode(encoded.trim()).map_err(|_| {
+                RealInfoError::ConfigError(format!("密钥 {} 不是有效的 Base64", key_id))
+            })?;
+            let cipher = Aes256Gcm::new_from_slice(&key).map_err(|_| {
+                RealInfoError::ConfigError(format!("密钥 {} 长度必须为 32 字节", key_id))
+            })?;
+            keys.push((key_id.to_string(), cipher));
+        }
+        Ok(Some(Self { keys }))
+    }
+
+    /// 当前用于加密的密钥编号
+    pub fn current_key_id(&self) -> &str {
+        &self.keys[0].0
+    }
+
+    /// 使用当前密钥加密字段值
+    pub fn encrypt_field(
+        &self,
+        user_id: Uuid,
+        field: &str,
+        plaintext: &str,
+    ) -> Result<String, RealInfoError> {
+        let (key_id, cipher) = &self.keys[0];
+        let mut nonce = [0u8; NONCE_LEN];
+        OsRng.fill_bytes(&mut nonce);
+        let aad = associated_data(user_id, field);
+        let ciphertext = cipher
+            .encrypt(
+                Nonce::from_slice(&nonce),
+                Payload {
+                    msg: plaintext.as_bytes(),
+                    aad: aad.as_bytes(),
+                },
+            )
+            .map_err(|_| RealInfoError::EncryptFailed(field.to_string()))?;
+
+        let mut sealed = nonce.to_vec();
+        sealed.extend_from_slice(&ciphertext);
+        Ok(format!(
+            "{}{}:{}",
+            ENCRYPTED_PREFIX,
+            key_id,
+            STANDARD.encode(sealed)
+        ))
+    }
+
+    /// 解密字段值，按密文中的密钥编号选择密钥
+    pub fn decrypt_field(
+        &self,
+        user_id: Uuid,
+        field: &str,
+        value: &str,
+    ) -> Result<String, RealInfoError> {
+        let (key_id, encoded) = parse_encrypted(value)
+            .ok_or_else(|| RealInfoError::DecryptFailed(format!("{} 密文格式错误", field)))?;
+        let cipher = self
+            .keys
+            .iter()
+            .find(|(id, _)| id == key_id)
+            .map(|(_, cipher)| cipher)
+            .ok_or_else(|| RealInfoError::UnknownKey(key_id.to_string()))?;
+
+        let sealed = STANDARD
+            .decode(encoded)
+            .map_err(|_| RealInfoError::DecryptFailed(format!("{} 密文格式错误", field)))?;
+        if sealed.len() <= NONCE_LEN {
+            return Err(RealInfoError::DecryptFailed(format!(
+                "{} 密文长度错误",
+                field
+            )));
+        }
+        let (nonce, ciphertext) = sealed.split_at(NONCE_LEN);
+        let aad = associated_data(user_id, field);
+        let plaintext = cipher
+            .decrypt(
+                Nonce::from_slice(nonce),
+                Payload {
+                    msg: ciphertext,
+                    aad: aad.as_bytes(),
+                },
+            )
+            .map_err(|_| RealInfoError::DecryptFailed(format!("{} 校验失败", field)))?;
+        String::from_utf8(plaintext)
+            .map_err(|_| RealInfoError::DecryptFailed(format!("{} 不是有效的 UTF-8", field)))
+    }
+}
+
+/// 根据配置创建实名信息加密器，未配置密钥时返回 None
+pub fn create_real_info_cipher(
+    config: &Config,
+) -> Result<Option<Arc<RealInfoCipher>>, RealInfoError> {
+    Ok(RealInfoCipher::from_config(config)?.map(Arc::new))
+}
+
+/// 解密后的实名信息
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct RealInfoFields {
+    pub real_name: Option<String>,
+    pub student_id: Option<String>,
+    pub major: Option<String>,
+    pub grade: Option<String>,
+}
+
+/// 实名信息迁移统计
+#[derive(Debug, Default)]
+pub struct RealInfoMigrationStats {
+    /// 检查的用户数
+    pub scanned: u64,
+    /// 加密或换用当前密钥重新加密的用户数
+    pub updated: u64,
+    /// 无法解密（如密钥已移除）的用户数
+    pub failed: u64,
+}
+
+/// 实名信息服务：加密存储与迁移
+pub struct RealInfoService;
+
+impl RealInfoService {
+    /// 加密实名信息中的敏感字段，未配置密钥时原样返回
+    pub fn seal(
+        cipher: Option<&RealInfoCipher>,
+        user_id: Uuid,
+        mut info: Value,
+    ) -> Result<Value, RealInfoError> {
+        let Some(cipher) = cipher else {
+            return Ok(info);
+        };
+        if let Value::Object(ref mut map) = info {
+            for field in REAL_INFO_SENSITIVE_FIELDS {
+                if let Some(Value::String(plaintext)) = map.get(field) {
+                    if parse_encrypted(plaintext).is_none() {
+                        let sealed = cipher.encrypt_field(user_id, field, plaintext)?;
+                        map.insert(field.to_string(), Value::String(sealed));
+                    }
+                }
+            }
+        }
+        Ok(info)
+    }
+
+    /// 解密实名信息，兼容尚未迁移的明文数据
+    ///
+    /// 只应在管理员查看实名信息时调用，调用方负责记录审计日志
+    pub fn open(
+        cipher: Option<&RealInfoCipher>,
+        user_id: Uuid,
+        info: &Value,
+    ) -> Result<RealInfoFields, RealInfoError> {
+        let field = |name: &str| -> Result<Option<String>, RealInfoError> {
+            let Some(value) = info.get(name).and_then(|v| v.as_str()) else {
+                return Ok(None);
+            };
+            if parse_encrypted(value).is_none() {
+                return Ok(Some(value.to_string()));
+            }
+            let cipher = cipher.ok_or_else(|| {
+                RealInfoError::ConfigError("未配置实名信息加密密钥，无法解密".to_string())
+            })?;
+            cipher.decrypt_field(user_id, name, value).map(Some)
+        };
+
+        Ok(RealInfoFields {
+            real_name: field("real_name")?,
+            student_id: field("student_id")?,
+            major: field("major")?,
+            grade: field("grade")?,
+        })
+    }
+
+    /// 将明文或旧密钥加密的字段用当前密钥重新加密，已是最新时返回 None
+    pub fn reseal(
+        cipher: &RealInfoCipher,
+        user_id: Uuid,
+        info: &Value,
+    ) -> Result<Option<Value>, RealInfoError> {
+        let Value::Object(map) = info else {
+            return Ok(None);
+        };
+
+        let mut updated = map.clone();
+        let mut changed = false;
+        for field in REAL_INFO_SENSITIVE_FIELDS {
+            let Some(Value::String(value)) = map.get(field) else {
+                continue;
+            };
+            let plaintext = match parse_encrypted(value) {
+                Some((key_id, _)) if key_id == cipher.current_key_id() => continue,
+                Some(_) => cipher.decrypt_field(user_id, field, value)?,
+                None => value.clone(),
+            };
+            let sealed = cipher.encrypt_field(user_id, field, &plaintext)?;
+            updated.insert(field.to_string(), Value::String(sealed));
+            changed = true;
+        }
+
+        Ok(changed.then_some(Value::Object(updated)))
+    }
+
+    /// 加密已有的明文实名信息，并将旧密钥加密的数据换用当前密钥
+    ///
+    /// 可重复执行，已使用当前密钥加密的数据会被跳过
+    pub async fn encrypt_existing(
+        pool: &PgPool,
+        cipher: &RealInfoCipher,
+    ) -> Result<RealInfoMigrationStats, RealInfoError> {
+        let mut stats = RealInfoMigrationStats::default();
+        let mut last_id: Option<Uuid> = None;
+
+        loop {
+            let rows: Vec<(Uuid, Value)> = sqlx::query_as(
+                r#"
+                SELECT id, real_info
+                FROM users
+                WHERE real_info IS NOT NULL
+                  AND real_info <> '{}'::jsonb
+                  AND ($1::UUID IS NULL OR id > $1)
+                ORDER BY id
+                LIMIT $2
+                "#,
+            )
+            .bind(last_id)
+            .bind(MIGRATION_BATCH_SIZE)
+            .fetch_all(pool)
+            .await?;
+
+            let Some((id, _)) = rows.last() else {
+                break;
+            };
+            last_id = Some(*id);
+
+            for (user_id, info) in rows {
+                stats.scanned += 1;
+                let sealed = match Self::reseal(cipher, user_id, &info) {
+                    Ok(Some(sealed)) => sealed,
+                    Ok(None) => continue,
+                    Err(e) => {
+                        log::warn!(
+                            "[RealInfo] 无法重新加密实名信息 | user_id={}, error={}",
+                            user_id,
+                            e
+                        );
+                        stats.failed += 1;
+                        continue;
+                    }
+                };
+
+                // 只在数据未被并发修改时更新，被修改的行下次执行时再处理
+                let result =
+                    sqlx::query("UPDATE users SET real_info = $1 WHERE id = $2 AND real_info = $3")
+                        .bind(&sealed)
+                        .bind(user_id)
+                        .bind(&info)
+                        .execute(pool)
+                        .await?;
+                if result.rows_affected() > 0 {
+                    stats.updated += 1;
+                }
+            }
+        }
+
+        Ok(stats)
+    }
+}
+
+fn associated_data(user_id: Uuid, field: &str) -> String {
+    format!("{}:{}", user_id, field)
+}
+
+/// 拆分加密字段值，返回 (密钥编号, Base64 密文)；明文返回 None
+fn parse_encrypted(value: &str) -> Option<(&str, &str)> {
+    value.strip_prefix(ENCRYPTED_PREFIX)?.split_once(':')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cipher(keys: &[&str]) -> RealInfoCipher {
+        let mut config = Config::from_env();
+        config.real_info_encryption_keys = keys.iter().map(|k| k.to_string()).collect();
+        RealInfoCipher::from_config(&config).unwrap().unwrap()
+    }
+
+    fn key(id: &str, byte: u8) -> String {
+        format!("{}:{}", id, STANDARD.encode([byte; 32]))
+    }
+
+    fn plaintext_info() -> Value {
+        serde_json::json!({
+            "real_name": "张三",
+            "student_id": "PB21000001",
+            "major": "计算机科学与技术",
+            "grade": null,
+            "source": "oidc",
+        })
+    }
+
+    #[test]
+    fn test_seal_and_open_round_trip() {
+        let cipher = cipher(&[&key("k1", 1)]);
+        let user_id = Uuid::new_v4();
+        let sealed = RealInfoService::seal(Some(&cipher), user_id, plaintext_info()).unwrap();
+
+        let real_name = sealed["real_name"].as_str().unwrap();
+        assert!(real_name.starts_with("enc:v1:k1:"));
+        assert!(!real_name.contains("张三"));
+        assert_eq!(sealed["grade"], Value::Null);
+        assert_eq!(sealed["source"], "oidc");
+
+        let opened = RealInfoService::open(Some(&cipher), user_id, &sealed).unwrap();
+        assert_eq!(opened.real_name.as_deref(), Some("张三"));
+        assert_eq!(opened.student_id.as_deref(), Some("PB21000001"));
+        assert_eq!(opened.major.as_deref(), Some("计算机科学与技术"));
+        assert_eq!(opened.grade, None);
+    }
+
+    #[test]
+    fn test_ciphertext_bound_to_user_and_field() {
+        let cipher = cipher(&[&key("k1", 1)]);
+        let user_id = Uuid::new_v4();
+        let sealed = RealInfoService::seal(Some(&cipher), user_id, plaintext_info()).unwrap();
+
+        assert!(RealInfoService::open(Some(&cipher), Uuid::new_v4(), &sealed).is_err());
+
+        let mut swapped = sealed.clone();
+        swapped["real_name"] = sealed["student_id"].clone();
+        assert!(RealInfoService::open(Some(&cipher), user_id, &swapped).is_err());
+    }
+
+    #[test]
+    fn test_open_plaintext_and_missing_key() {
+        let user_id = Uuid::new_v4();
+        let opened = RealInfoService::open(None, user_id, &plaintext_info()).unwrap();
+        assert_eq!(opened.real_name.as_deref(), Some("张三"));
+
+        let cipher = cipher(&[&key("k1", 1)]);
+        let sealed = RealInfoService::seal(Some(&cipher), user_id, plaintext_info()).unwrap();
+        assert!(matches!(
+            RealInfoService::open(None, user_id, &sealed),
+            Err(RealInfoError::ConfigError(_))
+        ));
+    }
+
+    #[test]
+    fn test_key_rotation() {
+        let user_id = Uuid::new_v4();
+        let old = cipher(&[&key("k1", 1)]);
+        let sealed = RealInfoService::seal(Some(&old), user_id, plaintext_info()).unwrap();
+
+        // 新密钥在前，旧密钥仍可解密
+        let rotated = cipher(&[&key("k2", 2), &key("k1", 1)]);
+        assert_eq!(
+            RealInfoService::open(Some(&rotated), user_id, &sealed)
+                .unwrap()
+                .real_name
+                .as_deref(),
+            Some("张三")
+        );
+
+        let resealed = RealInfoService::reseal(&rotated, user_id, &sealed)
+            .unwrap()
+            .unwrap();
+        assert!(resealed["real_name"]
+            .as_str()
+            .unwrap()
+            .starts_with("enc:v1:k2:"));
+        assert!(RealInfoService::reseal(&rotated, user_id, &resealed)
+            .unwrap()
+            .is_none());
+
+        // 移除旧密钥后只能解密重新加密的数据
+        let new_only = cipher(&[&key("k2", 2)]);
+        assert!(matches!(
+            RealInfoService::open(Some(&new_only), user_id, &sealed),
+            Err(RealInfoError::UnknownKey(_))
+        ));
+        assert!(RealInfoService::open(Some(&new_only), user_id, &resealed).is_ok());
+
+        // 明文数据同样会被加密
+        let from_plaintext = RealInfoService::reseal(&new_only, user_id, &plaintext_info())
+            .unwrap()
+            .unwrap();
+        assert!(from_plaintext["major"]
+            .as_str()
+            .unwrap()
+            .starts_with("enc:v1:k2:"));
+    }
+
+    #[test]
+    fn test_invalid_key_config() {
+        let mut config = Config::from_env();
+        for keys in [
+            vec!["no-separator".to_string()],
+            vec!["k1:not-base64!".to_string()],
+            vec![format!("k1:{}", STANDARD.encode([1u8; 16]))],
+            vec![key("k1", 1), key("k1", 2)],
+            vec![format!("bad id:{}", STANDARD.encode([1u8; 32]))],
+        ] {
+            config.real_info_encryption_keys = keys;
+            assert!(RealInfoCipher::from_config(&config).is_err());
+        }
+
+        config.real_info_encryption_keys = Vec::new();
+        assert!(RealInfoCipher::from_config(&config).unwrap().is_none());
+    }
+}
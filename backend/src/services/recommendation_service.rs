@@ -0,0 +1,466 @@
+use std::collections::HashMap;
+
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::models::{RecommendationSource, RecommendedResourceItem, RelatedResourceInfo};
+
+/// 下载行为权重
+const DOWNLOAD_WEIGHT: f64 = 1.0;
+/// 点赞行为权重
+const LIKE_WEIGHT: f64 = 2.0;
+/// 收藏行为权重
+const FAVORITE_WEIGHT: f64 = 3.0;
+/// 每个资源保留的相似资源数量
+const TOP_K_SIMILAR: usize = 30;
+/// 单个用户参与相似度计算的最大资源数（按行为权重取前 N 个，避免个别重度用户主导计算量）
+const MAX_ITEMS_PER_USER: usize = 200;
+/// 相似度收缩系数：共同用户越少，相似度打折越多
+const SIMILARITY_SHRINKAGE: f64 = 2.0;
+/// 批量写入相似度时每批的行数
+const INSERT_BATCH_SIZE: usize = 1000;
+/// 推荐数量上限
+const MAX_RECOMMEND_LIMIT: i32 = 50;
+
+/// 用户-资源行为汇总 SQL（$1 为 NULL 时查询全部用户）
+///
+/// 评分权重随总体质量分浮动（1.2 ~ 3.0），未填写总体质量时按中间值计算；
+/// 只统计已审核通过的资源，忽略上传者对自己资源的行为
+fn interactions_sql() -> String {
+    format!(
+        r#"
+        SELECT i.user_id, i.resource_id, SUM(i.weight)::float8 AS weight
+        FROM (
+            SELECT DISTINCT user_id, resource_id, {download}::float8 AS weight
+            FROM download_logs WHERE user_id IS NOT NULL
+            UNION ALL
+            SELECT user_id, resource_id, {like}::float8 FROM likes
+            UNION ALL
+            SELECT DISTINCT f.user_id, fr.resource_id, {favorite}::float8
+            FROM favorite_resources fr
+            INNER JOIN favorites f ON f.id = fr.favorite_id
+            UNION ALL
+            SELECT user_id, resource_id, (1.0 + COALESCE(overall_quality, 5) / 5.0)::float8
+            FROM ratings
+        ) i
+        INNER JOIN resources r ON r.id = i.resource_id
+        WHERE r.audit_status = 'approved'
+          AND r.uploader_id <> i.user_id
+          AND ($1::uuid IS NULL OR i.user_id = $1)
+        GROUP BY i.user_id, i.resource_id
+        "#,
+        download = DOWNLOAD_WEIGHT,
+        like = LIKE_WEIGHT,
+        favorite = FAVORITE_WEIGHT,
+    )
+}
+
+/// 用户-资源下载关系 SQL（用于"下载过该资源的用户还下载了"）
+const DOWNLOADS_SQL: &str = r#"
+    SELECT DISTINCT dl.user_id, dl.resource_id, 1.0::float8 AS weight
+    FROM download_logs dl
+    INNER JOIN resources r ON r.id = dl.resource_id
+    WHERE dl.user_id IS NOT NULL
+      AND r.audit_status = 'approved'
+      AND r.uploader_id <> dl.user_id
+"#;
+
+/// 推荐结果通用列（需 JOIN resources r 与 LEFT JOIN resource_stats rs）
+const RECOMMEND_COLUMNS: &str = r#"
+    r.id, r.title, r.course_name, r.resource_type,
+    COALESCE(rs.downloads, 0) AS downloads,
+    COALESCE(rs.views, 0) AS views,
+    COALESCE(rs.likes, 0) AS likes
+"#;
+
+#[derive(Debug)]
+pub enum RecommendationError {
+    DatabaseError(String),
+}
+
+impl std::fmt::Display for RecommendationError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RecommendationError::DatabaseError(msg) => write!(f, "数据库错误: {}", msg),
+        }
+    }
+}
+
+impl std::error::Error for RecommendationError {}
+
+impl From<sqlx::Error> for RecommendationError {
+    fn from(err: sqlx::Error) -> Self {
+        RecommendationError::DatabaseError(err.to_string())
+    }
+}
+
+/// 相似度类型（对应 resource_similarities.kind）
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SimilarityKind {
+    /// 点赞、收藏、评分、下载的综合行为
+    Interaction,
+    /// 仅下载行为
+    Download,
+}
+
+impl SimilarityKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SimilarityKind::Interaction => "interaction",
+            SimilarityKind::Download => "download",
+        }
+    }
+}
+
+/// 用户对资源的行为强度
+#[derive(Debug, Clone, sqlx::FromRow)]
+pub struct Interaction {
+    pub user_id: Uuid,
+    pub resource_id: Uuid,
+    pub weight: f64,
+}
+
+/// 资源之间的相似度
+#[derive(Debug, Clone, PartialEq)]
+pub struct ItemSimilarity {
+    pub resource_id: Uuid,
+    pub similar_resource_id: Uuid,
+    pub score: f64,
+    pub co_users: i32,
+}
+
+/// 计算资源之间的余弦相似度（基于物品的协同过滤）
+///
+/// 每个资源用"用户 -> 行为强度"向量表示，两资源的相似度为向量余弦值，
+/// 再乘以 co / (co + SIMILARITY_SHRINKAGE) 抑制共同用户过少带来的偶然高分；
+/// 每个资源只保留得分最高的 top_k 个相似资源
+pub fn compute_item_similarities(
+    interactions: &[Interaction],
+    top_k: usize,
+) -> Vec<ItemSimilarity> {
+    let mut by_user: HashMap<Uuid, Vec<(Uuid, f64)>> = HashMap::new();
+    let mut norms: HashMap<Uuid, f64> = HashMap::new();
+    for interaction in interactions.iter().filter(|i| i.weight > 0.0) {
+        by_user
+            .entry(interaction.user_id)
+            .or_default()
+            .push((interaction.resource_id, interaction.weight));
+    }
+
+    let mut pairs: HashMap<(Uuid, Uuid), (f64, i32)> = HashMap::new();
+    for items in by_user.values_mut() {
+        items.sort_by(|a, b| b.1.total_cmp(&a.1).then(a.0.cmp(&b.0)));
+        items.truncate(MAX_ITEMS_PER_USER);
+        for &(resource_id, weight) in items.iter() {
+            *norms.entry(resource_id).or_default() += weight * weight;
+        }
+        for (i, &(a, weight_a)) in items.iter().enumerate() {
+            for &(b, weight_b) in &items[i + 1..] {
+                let key = if a < b { (a, b) } else { (b, a) };
+                let entry = pairs.entry(key).or_default();
+                entry.0 += weight_a * weight_b;
+                entry.1 += 1;
+            }
+        }
+    }
+
+    let mut neighbors: HashMap<Uuid, Vec<ItemSimilarity>> = HashMap::new();
+    for ((a, b), (dot, co_users)) in pairs {
+        let norm = (norms[&a] * norms[&b]).sqrt();
+        if norm <= 0.0 {
+            continue;
+        }
+        let co = co_users as f64;
+        let score = dot / norm * co / (co + SIMILARITY_SHRINKAGE);
+        for (from, to) in [(a, b), (b, a)] {
+            neighbors.entry(from).or_default().push(ItemSimilarity {
+                resource_id: from,
+                similar_resource_id: to,
+                score,
+                co_users,
+            });
+        }
+    }
+
+    let mut result = Vec::new();
+    for (_, mut items) in neighbors {
+        items.sort_by(|a, b| {
+            b.score
+                .total_cmp(&a.score)
+                .then(b.co_users.cmp(&a.co_users))
+                .then(a.similar_resource_id.cmp(&b.similar_resource_id))
+        });
+        items.truncate(top_k);
+        result.extend(items);
+    }
+    result
+}
+
+pub struct RecommendationService;
+
+impl RecommendationService {
+    /// 重新计算并物化资源相似度（由推荐任务定期调用）
+    ///
+    /// 返回写入的综合行为相似度和下载相似度条数
+    pub async fn refresh_similarities(
+        pool: &PgPool,
+    ) -> Result<(usize, usize), RecommendationError> {
+        let interactions: Vec<Interaction> = sqlx::query_as(&interactions_sql())
+            .bind(None::<Uuid>)
+            .fetch_all(pool)
+            .await?;
+        let downloads: Vec<Interaction> = sqlx::query_as(DOWNLOADS_SQL).fetch_all(pool).await?;
+
+        let interaction_similarities = compute_item_similarities(&interactions, TOP_K_SIMILAR);
+        let download_similarities = compute_item_similarities(&downloads, TOP_K_SIMILAR);
+
+        let mut tx = pool.begin().await?;
+        sqlx::query("DELETE FROM resource_similarities")
+            .execute(&mut *tx)
+            .await?;
+        for (kind, similarities) in [
+            (SimilarityKind::Interaction, &interaction_similarities),
+            (SimilarityKind::Download, &download_similarities),
+        ] {
+            for chunk in similarities.chunks(INSERT_BATCH_SIZE) {
+                sqlx::query(
+                    r#"
+                    INSERT INTO resource_similarities
+                        (kind, resource_id, similar_resource_id, score, co_users)
+                    SELECT $1, * FROM UNNEST($2::uuid[], $3::uuid[], $4::float8[], $5::int4[])
+                    "#,
+                )
+                .bind(kind.as_str())
+                .bind(chunk.iter().map(|s| s.resource_id).collect::<Vec<_>>())
+                .bind(
+                    chunk
+                        .iter()
+                        .map(|s| s.similar_resource_id)
+                        .collect::<Vec<_>>(),
+                )
+                .bind(chunk.iter().map(|s| s.score).collect::<Vec<_>>())
+                .bind(chunk.iter().map(|s| s.co_users).collect::<Vec<_>>())
+                .execute(&mut *tx)
+                .await?;
+            }
+        }
+        tx.commit().await?;
+
+        Ok((interaction_similarities.len(), download_similarities.len()))
+    }
+
+    /// 为用户推荐资源
+    ///
+    /// 优先使用协同过滤结果（用户有过行为的资源的相似资源，按行为强度加权求和）；
+    /// 不足时按用户上传或交互过的资源所属课程、教师补充；仍不足时用热门资源兜底。
+    /// 结果排除用户自己上传及已有过行为的资源
+    pub async fn recommend_resources(
+        pool: &PgPool,
+        user_id: Uuid,
+        resource_type: Option<&str>,
+        limit: i32,
+    ) -> Result<Vec<RecommendedResourceItem>, RecommendationError> {
+        let limit = limit.clamp(1, MAX_RECOMMEND_LIMIT);
+        let user_items = interactions_sql();
+
+        let mut items: Vec<RecommendedResourceItem> = sqlx::query_as(&format!(
+            r#"
+            WITH user_items AS ({user_items})
+            SELECT {columns},
+                   SUM(ui.weight * s.score)::float8 AS score,
+                   $4 AS source
+            FROM user_items ui
+            INNER JOIN resource_similarities s
+                ON s.kind = 'interaction' AND s.resource_id = ui.resource_id
+            INNER JOIN resources r ON r.id = s.similar_resource_id
+            LEFT JOIN resource_stats rs ON rs.resource_id = r.id
+            WHERE r.audit_status = 'approved'
+              AND r.uploader_id <> $1
+              AND NOT EXISTS (SELECT 1 FROM user_items x WHERE x.resource_id = r.id)
+              AND ($2::varchar IS NULL OR r.resource_type = $2)
+            GROUP BY r.id, rs.downloads, rs.views, rs.likes
+            ORDER BY score DESC, r.created_at DESC
+            LIMIT $3
+            "#,
+            columns = RECOMMEND_COLUMNS,
+        ))
+        .bind(user_id)
+        .bind(resource_type)
+        .bind(limit as i64)
+        .bind(RecommendationSource::Collaborative.as_str())
+        .fetch_all(pool)
+        .await?;
+
+        // 冷启动：同课程（权重 2）/ 同教师（权重 1）的资源
+        if items.len() < limit as usize {
+            let exclude: Vec<Uuid> = items.iter().map(|i| i.id).collect();
+            let content: Vec<RecommendedResourceItem> = sqlx::query_as(&format!(
+                r#"
+                WITH user_items AS ({user_items}),
+                seeds AS (
+                    SELECT resource_id FROM user_items
+                    UNION
+                    SELECT id FROM resources WHERE uploader_id = $1
+                )
+                SELECT * FROM (
+                    SELECT {columns},
+                           (2 * (SELECT COUNT(DISTINCT rc.course_sn)
+                                 FROM resource_courses rc
+                                 INNER JOIN resource_courses sc ON sc.course_sn = rc.course_sn
+                                 WHERE rc.resource_id = r.id
+                                   AND sc.resource_id IN (SELECT resource_id FROM seeds))
+                            + (SELECT COUNT(DISTINCT rt.teacher_sn)
+                               FROM resource_teachers rt
+                               INNER JOIN resource_teachers st ON st.teacher_sn = rt.teacher_sn
+                               WHERE rt.resource_id = r.id
+                                 AND st.resource_id IN (SELECT resource_id FROM seeds)))::float8 AS score,
+                           $5 AS source
+                    FROM resources r
+                    LEFT JOIN resource_stats rs ON rs.resource_id = r.id
+                    WHERE r.audit_status = 'approved'
+                      AND r.uploader_id <> $1
+                      AND r.id NOT IN (SELECT resource_id FROM seeds)
+                      AND r.id <> ALL($3)
+                      AND ($2::varchar IS NULL OR r.resource_type = $2)
+                ) c
+                WHERE c.score > 0
+                ORDER BY c.score DESC, c.downloads DESC, c.likes DESC
+                LIMIT $4
+                "#,
+                columns = RECOMMEND_COLUMNS,
+            ))
+            .bind(user_id)
+            .bind(resource_type)
+            .bind(&exclude)
+            .bind((limit as usize - items.len()) as i64)
+            .bind(RecommendationSource::Content.as_str())
+            .fetch_all(pool)
+            .await?;
+            items.extend(content);
+        }
+
+        // 兜底：热门资源
+        if items.len() < limit as usize {
+            let exclude: Vec<Uuid> = items.iter().map(|i| i.id).collect();
+            let popular: Vec<RecommendedResourceItem> = sqlx::query_as(&format!(
+                r#"
+                WITH user_items AS ({user_items})
+                SELECT {columns},
+                       (COALESCE(rs.downloads, 0) + 2 * COALESCE(rs.likes, 0)
+                        + 0.1 * COALESCE(rs.views, 0))::float8 AS score,
+                       $5 AS source
+                FROM resources r
+                LEFT JOIN resource_stats rs ON rs.resource_id = r.id
+                WHERE r.audit_status = 'approved'
+                  AND r.uploader_id <> $1
+                  AND r.id NOT IN (SELECT resource_id FROM user_items)
+                  AND r.id <> ALL($3)
+                  AND ($2::varchar IS NULL OR r.resource_type = $2)
+                ORDER BY score DESC, r.created_at DESC
+                LIMIT $4
+                "#,
+                columns = RECOMMEND_COLUMNS,
+            ))
+            .bind(user_id)
+            .bind(resource_type)
+            .bind(&exclude)
+            .bind((limit as usize - items.len()) as i64)
+            .bind(RecommendationSource::Popular.as_str())
+            .fetch_all(pool)
+            .await?;
+            items.extend(popular);
+        }
+
+        Ok(items)
+    }
+
+    /// 获取"下载过该资源的用户还下载了"的资源列表
+    pub async fn get_also_downloaded(
+        pool: &PgPool,
+        resource_id: Uuid,
+        limit: i64,
+    ) -> Result<Vec<RelatedResourceInfo>, RecommendationError> {
+        let resources = sqlx::query_as::<_, RelatedResourceInfo>(
+            r#"
+            SELECT r.id, r.title, r.resource_type, r.category, r.created_at
+            FROM resource_similarities s
+            INNER JOIN resources r ON r.id = s.similar_resource_id
+            WHERE s.kind = $1 AND s.resource_id = $2 AND r.audit_status = 'approved'
+            ORDER BY s.score DESC, s.co_users DESC
+            LIMIT $3
+            "#,
+        )
+        .bind(SimilarityKind::Download.as_str())
+        .bind(resource_id)
+        .bind(limit)
+        .fetch_all(pool)
+        .await?;
+
+        Ok(resources)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn interaction(user: u128, resource: u128, weight: f64) -> Interaction {
+        Interaction {
+            user_id: Uuid::from_u128(user),
+            resource_id: Uuid::from_u128(resource),
+            weight,
+        }
+    }
+
+    fn score_of(similarities: &[ItemSimilarity], from: u128, to: u128) -> Option<f64> {
+        similarities
+            .iter()
+            .find(|s| {
+                s.resource_id == Uuid::from_u128(from)
+                    && s.similar_resource_id == Uuid::from_u128(to)
+            })
+            .map(|s| s.score)
+    }
+
+    #[test]
+    fn test_compute_item_similarities_symmetric_cosine() {
+        // 用户 1、2 都与资源 10、11 交互；资源 12 只有用户 3
+        let interactions = vec![
+            interaction(1, 10, 1.0),
+            interaction(1, 11, 1.0),
+            interaction(2, 10, 1.0),
+            interaction(2, 11, 1.0),
+            interaction(3, 12, 3.0),
+        ];
+        let similarities = compute_item_similarities(&interactions, 10);
+
+        // 余弦为 1，共同用户 2 人，收缩后为 2 / (2 + 2)
+        let score = score_of(&similarities, 10, 11).unwrap();
+        assert!((score - 0.5).abs() < 1e-9);
+        assert_eq!(score_of(&similarities, 11, 10), Some(score));
+        assert!(score_of(&similarities, 10, 12).is_none());
+        assert!(similarities.iter().all(|s| s.co_users == 2));
+    }
+
+    #[test]
+    fn test_compute_item_similarities_ranks_and_truncates() {
+        // 资源 10 与 11 共同用户更多，应排在 12 前面
+        let interactions = vec![
+            interaction(1, 10, 1.0),
+            interaction(1, 11, 2.0),
+            interaction(2, 10, 1.0),
+            interaction(2, 11, 2.0),
+            interaction(3, 10, 1.0),
+            interaction(3, 12, 1.0),
+            interaction(4, 12, 0.0),
+        ];
+        let similarities = compute_item_similarities(&interactions, 1);
+        let neighbors: Vec<_> = similarities
+            .iter()
+            .filter(|s| s.resource_id == Uuid::from_u128(10))
+            .collect();
+        assert_eq!(neighbors.len(), 1);
+        assert_eq!(neighbors[0].similar_resource_id, Uuid::from_u128(11));
+        assert!(compute_item_similarities(&[], 10).is_empty());
+    }
+}
@@ -0,0 +1,585 @@
+use sqlx::PgPool;
+use std::collections::HashMap;
+use std::sync::Arc;
+use uuid::Uuid;
+
+use crate::models::{
+    CreateReportRequest, Report, ReportAction, ReportListQuery, ReportListResponse,
+    ReportQueueResponse, ReportReason, ReportResponse, ReportStatus, ReportTargetDetailResponse,
+    ReportTargetGroup, ReportTargetInfo, ReportTargetType, ResolveReportRequest,
+    ResolveReportResponse,
+};
+use crate::services::{NotificationService, ResourceError, ResourceService, StorageBackend};
+
+/// 举报错误类型
+#[derive(Debug)]
+pub enum ReportError {
+    DatabaseError(String),
+    NotFound(String),
+    ValidationError(String),
+    Conflict(String),
+}
+
+impl std::fmt::Display for ReportError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ReportError::DatabaseError(msg) => write!(f, "数据库错误: {}", msg),
+            ReportError::NotFound(msg) => write!(f, "未找到: {}", msg),
+            ReportError::ValidationError(msg) => write!(f, "验证错误: {}", msg),
+            ReportError::Conflict(msg) => write!(f, "状态冲突: {}", msg),
+        }
+    }
+}
+
+impl std::error::Error for ReportError {}
+
+impl From<sqlx::Error> for ReportError {
+    fn from(err: sqlx::Error) -> Self {
+        ReportError::DatabaseError(err.to_string())
+    }
+}
+
+impl From<ResourceError> for ReportError {
+    fn from(err: ResourceError) -> Self {
+        match err {
+            ResourceError::NotFound(msg) => ReportError::NotFound(msg),
+            e => ReportError::DatabaseError(e.to_string()),
+        }
+    }
+}
+
+/// 举报查询结果（包含举报人用户名）
+#[derive(Debug, sqlx::FromRow)]
+struct ReportRow {
+    #[sqlx(flatten)]
+    report: Report,
+    reporter_name: Option<String>,
+}
+
+impl From<ReportRow> for ReportResponse {
+    fn from(row: ReportRow) -> Self {
+        let report = row.report;
+        ReportResponse {
+            id: report.id,
+            target_type: report.target_type,
+            target_id: report.target_id,
+            reporter_id: report.reporter_id,
+            reporter_name: row.reporter_name,
+            reason: report.reason,
+            description: report.description,
+            status: report.status,
+            action: report.action,
+            handled_by: report.handled_by,
+            resolution_note: report.resolution_note,
+            resolved_at: report.resolved_at,
+            created_at: report.created_at,
+        }
+    }
+}
+
+/// 举报查询字段
+const REPORT_SELECT_SQL: &str = r#"
+    SELECT
+        rp.id, rp.target_type, rp.target_id, rp.reporter_id, rp.reason, rp.description,
+        rp.status, rp.action, rp.handled_by, rp.resolution_note, rp.resolved_at, rp.created_at,
+        u.username AS reporter_name
+    FROM reports rp
+    LEFT JOIN users u ON rp.reporter_id = u.id
+"#;
+
+/// 举报队列按对象聚合的查询结果
+#[derive(Debug, sqlx::FromRow)]
+struct ReportGroupRow {
+    target_type: String,
+    target_id: Uuid,
+    report_count: i64,
+    reason_counts: serde_json::Value,
+    first_reported_at: chrono::NaiveDateTime,
+    last_reported_at: chrono::NaiveDateTime,
+}
+
+/// 被举报对象查询结果
+#[derive(Debug, sqlx::FromRow)]
+struct ReportTargetRow {
+    id: Uuid,
+    #[sqlx(flatten)]
+    info: ReportTargetInfo,
+}
+
+/// 被举报资源的摘要信息
+const RESOURCE_TARGET_SQL: &str = r#"
+    SELECT r.id, r.title, r.id AS resource_id, r.title AS resource_title,
+           r.uploader_id AS owner_id, u.username AS owner_name, r.audit_status,
+           false AS is_deleted
+    FROM resources r
+    LEFT JOIN users u ON r.uploader_id = u.id
+    WHERE r.id = ANY($1)
+"#;
+
+/// 被举报评论的摘要信息
+const COMMENT_TARGET_SQL: &str = r#"
+    SELECT c.id, c.content AS title, c.resource_id, r.title AS resource_title,
+           c.user_id AS owner_id, u.username AS owner_name, c.audit_status, c.is_deleted
+    FROM comments c
+    LEFT JOIN resources r ON c.resource_id = r.id
+    LEFT JOIN users u ON c.user_id = u.id
+    WHERE c.id = ANY($1)
+"#;
+
+/// 举报服务：用户举报资源和评论，管理员按对象集中处理
+pub struct ReportService;
+
+impl ReportService {
+    /// 提交举报
+    pub async fn create_report(
+        pool: &PgPool,
+        reporter_id: Uuid,
+        req: CreateReportRequest,
+    ) -> Result<ReportResponse, ReportError> {
+        let (target_type, reason, description) =
+            req.validate().map_err(ReportError::ValidationError)?;
+
+        // 只能举报公开可见的内容
+        let target = Self::load_target(pool, target_type, req.target_id)
+            .await?
+            .filter(|t| !t.is_deleted && t.audit_status.as_deref() == Some("approved"))
+            .ok_or_else(|| ReportError::NotFound(format!("{}不存在", target_type.label())))?;
+        if target.owner_id == reporter_id {
+            return Err(ReportError::ValidationError(
+                "不能举报自己发布的内容".to_string(),
+            ));
+        }
+
+        let id: Uuid = sqlx::query_scalar(
+            r#"
+            INSERT INTO reports (target_type, target_id, reporter_id, reason, description, status)
+            VALUES ($1, $2, $3, $4, $5, 'pending')
+            RETURNING id
+            "#,
+        )
+        .bind(target_type.as_str())
+        .bind(req.target_id)
+        .bind(reporter_id)
+        .bind(reason.as_str())
+        .bind(&description)
+        .fetch_one(pool)
+        .await
+        .map_err(|e| match e {
+            sqlx::Error::Database(ref db) if db.is_unique_violation() => {
+                ReportError::Conflict("您已举报过该内容，请等待处理".to_string())
+            }
+            e => e.into(),
+        })?;
+
+        log::info!(
+            "[ReportService] 举报已提交: id={}, target_type={}, target_id={}, reporter_id={}, reason={}",
+            id,
+            target_type.as_str(),
+            req.target_id,
+            reporter_id,
+            reason.as_str()
+        );
+
+        let sql = format!("{} WHERE rp.id = $1", REPORT_SELECT_SQL);
+        let row = sqlx::query_as::<_, ReportRow>(&sql)
+            .bind(id)
+            .fetch_one(pool)
+            .await?;
+        Ok(row.into())
+    }
+
+    /// 获取当前用户提交的举报
+    pub async fn get_my_reports(
+        pool: &PgPool,
+        reporter_id: Uuid,
+        query: ReportListQuery,
+    ) -> Result<ReportListResponse, ReportError> {
+        let page = query.page.unwrap_or(1).max(1);
+        let per_page = query.per_page.unwrap_or(20).clamp(1, 100);
+        let offset = (page - 1) * per_page;
+        let status = Self::parse_status(query.status.as_deref())?.map(|s| s.as_str());
+
+        let total = sqlx::query_scalar::<_, i64>(
+            "SELECT COUNT(*) FROM reports rp WHERE rp.reporter_id = $1 AND ($2::varchar IS NULL OR rp.status = $2)",
+        )
+        .bind(reporter_id)
+        .bind(status)
+        .fetch_one(pool)
+        .await?;
+
+        let sql = format!(
+            r#"{}
+            WHERE rp.reporter_id = $1 AND ($2::varchar IS NULL OR rp.status = $2)
+            ORDER BY rp.created_at DESC
+            LIMIT $3 OFFSET $4
+            "#,
+            REPORT_SELECT_SQL
+        );
+        let reports = sqlx::query_as::<_, ReportRow>(&sql)
+            .bind(reporter_id)
+            .bind(status)
+            .bind(per_page)
+            .bind(offset)
+            .fetch_all(pool)
+            .await?
+            .into_iter()
+            .map(ReportResponse::from)
+            .collect();
+
+        Ok(ReportListResponse {
+            reports,
+            total,
+            page,
+            per_page,
+        })
+    }
+
+    /// 获取举报队列（管理员），同一对象的举报合并为一条
+    ///
+    /// 默认只看待处理的举报，被举报次数多的对象排在前面
+    pub async fn get_report_queue(
+        pool: &PgPool,
+        query: ReportListQuery,
+    ) -> Result<ReportQueueResponse, ReportError> {
+        let page = query.page.unwrap_or(1).max(1);
+        let per_page = query.per_page.unwrap_or(20).clamp(1, 100);
+        let offset = (page - 1) * per_page;
+
+        let status = Self::parse_status(query.status.as_deref())?.unwrap_or(ReportStatus::Pending);
+        let target_type = match query.target_type.as_deref() {
+            Some(t) if !t.is_empty() => Some(ReportTargetType::from_str(t).ok_or_else(|| {
+                ReportError::ValidationError(format!("无效的举报对象类型: {}", t))
+            })?),
+            _ => None,
+        };
+        let order = if status == ReportStatus::Pending {
+            "report_count DESC, first_reported_at ASC"
+        } else {
+            "last_reported_at DESC"
+        };
+        let target_type = target_type.map(|t| t.as_str());
+
+        let total = sqlx::query_scalar::<_, i64>(
+            r#"
+            SELECT COUNT(DISTINCT (target_type, target_id)) FROM reports
+            WHERE status = $1 AND ($2::varchar IS NULL OR target_type = $2)
+            "#,
+        )
+        .bind(status.as_str())
+        .bind(target_type)
+        .fetch_one(pool)
+        .await?;
+
+        let sql = format!(
+            r#"
+            SELECT target_type, target_id,
+                   SUM(reason_count)::BIGINT AS report_count,
+                   jsonb_object_agg(reason, reason_count) AS reason_counts,
+                   MIN(first_at) AS first_reported_at,
+                   MAX(last_at) AS last_reported_at
+            FROM (
+                SELECT target_type, target_id, reason, COUNT(*) AS reason_count,
+                       MIN(created_at) AS first_at, MAX(created_at) AS last_at
+                FROM reports
+                WHERE status = $1 AND ($2::varchar IS NULL OR target_type = $2)
+                GROUP BY target_type, target_id, reason
+            ) g
+            GROUP BY target_type, target_id
+            ORDER BY {}
+            LIMIT $3 OFFSET $4
+            "#,
+            order
+        );
+        let rows = sqlx::query_as::<_, ReportGroupRow>(&sql)
+            .bind(status.as_str())
+            .bind(target_type)
+            .bind(per_page)
+            .bind(offset)
+            .fetch_all(pool)
+            .await?;
+
+        let keys: Vec<(ReportTargetType, Uuid)> = rows
+            .iter()
+            .filter_map(|row| {
+                ReportTargetType::from_str(&row.target_type).map(|t| (t, row.target_id))
+            })
+            .collect();
+        let mut targets = Self::load_targets(pool, &keys).await?;
+
+        let targets = rows
+            .into_iter()
+            .map(|row| {
+                let target = ReportTargetType::from_str(&row.target_type)
+                    .and_then(|t| targets.remove(&(t, row.target_id)));
+                ReportTargetGroup {
+                    target_type: row.target_type,
+                    target_id: row.target_id,
+                    target,
+                    report_count: row.report_count,
+                    reason_counts: row.reason_counts,
+                    first_reported_at: row.first_reported_at,
+                    last_reported_at: row.last_reported_at,
+                }
+            })
+            .collect();
+
+        Ok(ReportQueueResponse {
+            targets,
+            total,
+            page,
+            per_page,
+        })
+    }
+
+    /// 获取单个对象收到的全部举报（管理员）
+    pub async fn get_target_reports(
+        pool: &PgPool,
+        target_type: ReportTargetType,
+        target_id: Uuid,
+    ) -> Result<ReportTargetDetailResponse, ReportError> {
+        let sql = format!(
+            "{} WHERE rp.target_type = $1 AND rp.target_id = $2 ORDER BY rp.created_at DESC",
+            REPORT_SELECT_SQL
+        );
+        let reports: Vec<ReportResponse> = sqlx::query_as::<_, ReportRow>(&sql)
+            .bind(target_type.as_str())
+            .bind(target_id)
+            .fetch_all(pool)
+            .await?
+            .into_iter()
+            .map(ReportResponse::from)
+            .collect();
+        if reports.is_empty() {
+            return Err(ReportError::NotFound(format!(
+                "该{}没有举报记录",
+                target_type.label()
+            )));
+        }
+
+        let target = Self::load_target(pool, target_type, target_id).await?;
+
+        Ok(ReportTargetDetailResponse {
+            target_type: target_type.as_str().to_string(),
+            target_id,
+            target,
+            reports,
+        })
+    }
+
+    /// 处理对象的全部待处理举报（管理员），并通知举报人处理结果
+    pub async fn resolve_reports(
+        pool: &PgPool,
+        storage: &Arc<dyn StorageBackend>,
+        target_type: ReportTargetType,
+        target_id: Uuid,
+        handler_id: Uuid,
+        req: ResolveReportRequest,
+    ) -> Result<ResolveReportResponse, ReportError> {
+        let (action, note) = req.validate().map_err(ReportError::ValidationError)?;
+
+        let target = Self::load_target(pool, target_type, target_id).await?;
+        if action != ReportAction::Dismiss && target.as_ref().is_none_or(|t| t.is_deleted) {
+            return Err(ReportError::NotFound(format!(
+                "{}不存在或已被删除",
+                target_type.label()
+            )));
+        }
+
+        let mut tx = pool.begin().await?;
+
+        // 锁定待处理的举报，防止并发处理
+        let pending: Vec<(Uuid, String)> = sqlx::query_as(
+            r#"
+            SELECT reporter_id, reason FROM reports
+            WHERE target_type = $1 AND target_id = $2 AND status = 'pending'
+            ORDER BY created_at
+            FOR UPDATE
+            "#,
+        )
+        .bind(target_type.as_str())
+        .bind(target_id)
+        .fetch_all(&mut *tx)
+        .await?;
+        if pending.is_empty() {
+            return Err(ReportError::Conflict(format!(
+                "该{}没有待处理的举报",
+                target_type.label()
+            )));
+        }
+
+        match (action, target_type) {
+            (ReportAction::Hide, ReportTargetType::Resource) => {
+                // 与管理员驳回资源相同，原因对上传者可见
+                sqlx::query(
+                    r#"
+                    UPDATE resources
+                    SET audit_status = 'rejected', ai_reject_reason = $1, updated_at = NOW()
+                    WHERE id = $2
+                    "#,
+                )
+                .bind(note.as_deref().unwrap_or("因用户举报被隐藏"))
+                .bind(target_id)
+                .execute(&mut *tx)
+                .await?;
+            }
+            (ReportAction::Hide, ReportTargetType::Comment) => {
+                sqlx::query("UPDATE comments SET audit_status = 'rejected' WHERE id = $1")
+                    .bind(target_id)
+                    .execute(&mut *tx)
+                    .await?;
+            }
+            (ReportAction::Delete, ReportTargetType::Comment) => {
+                sqlx::query(
+                    "UPDATE comments SET is_deleted = true, deleted_at = NOW() WHERE id = $1 AND is_deleted = false",
+                )
+                .bind(target_id)
+                .execute(&mut *tx)
+                .await?;
+            }
+            _ => {}
+        }
+
+        let status = action.report_status();
+        sqlx::query(
+            r#"
+            UPDATE reports
+            SET status = $1, action = $2, handled_by = $3, resolution_note = $4, resolved_at = NOW()
+            WHERE target_type = $5 AND target_id = $6 AND status = 'pending'
+            "#,
+        )
+        .bind(status.as_str())
+        .bind(action.as_str())
+        .bind(handler_id)
+        .bind(&note)
+        .bind(target_type.as_str())
+        .bind(target_id)
+        .execute(&mut *tx)
+        .await?;
+
+        // 删除资源涉及存储文件，放在事务提交前执行，失败时举报仍保持待处理
+        if (action, target_type) == (ReportAction::Delete, ReportTargetType::Resource) {
+            ResourceService::force_delete_resource(pool, storage, target_id).await?;
+        }
+
+        tx.commit().await?;
+
+        log::info!(
+            "[ReportService] 举报处理完成: target_type={}, target_id={}, action={}, count={}",
+            target_type.as_str(),
+            target_id,
+            action.as_str(),
+            pending.len()
+        );
+
+        let target_desc = match (&target, target_type) {
+            (Some(t), ReportTargetType::Resource) => format!("资源《{}》", t.title),
+            (Some(t), ReportTargetType::Comment) => format!(
+                "资源《{}》下的评论",
+                t.resource_title.as_deref().unwrap_or_default()
+            ),
+            (None, _) => format!("该{}", target_type.label()),
+        };
+
+        if action == ReportAction::WarnUploader {
+            if let Some(ref t) = target {
+                let mut reasons: Vec<ReportReason> = Vec::new();
+                for reason in pending
+                    .iter()
+                    .filter_map(|(_, r)| ReportReason::from_str(r))
+                {
+                    if !reasons.contains(&reason) {
+                        reasons.push(reason);
+                    }
+                }
+                if let Err(e) = NotificationService::create_report_warning_notification(
+                    pool,
+                    t.owner_id,
+                    t.resource_id,
+                    &target_desc,
+                    &reasons,
+                    note.as_deref(),
+                )
+                .await
+                {
+                    log::warn!("[ReportService] 发送举报警告通知失败: {}", e);
+                }
+            }
+        }
+
+        // 同一举报人只通知一次
+        let mut reporters: Vec<Uuid> = pending.iter().map(|(id, _)| *id).collect();
+        reporters.sort_unstable();
+        reporters.dedup();
+        for reporter_id in reporters {
+            if let Err(e) = NotificationService::create_report_result_notification(
+                pool,
+                reporter_id,
+                &target_desc,
+                action,
+                note.as_deref(),
+            )
+            .await
+            {
+                log::warn!(
+                    "[ReportService] 发送举报结果通知失败: reporter_id={}, error={}",
+                    reporter_id,
+                    e
+                );
+            }
+        }
+
+        Ok(ResolveReportResponse {
+            target_type: target_type.as_str().to_string(),
+            target_id,
+            action: action.as_str().to_string(),
+            resolved_count: pending.len() as i64,
+        })
+    }
+
+    fn parse_status(status: Option<&str>) -> Result<Option<ReportStatus>, ReportError> {
+        match status {
+            Some(s) if !s.is_empty() => ReportStatus::from_str(s)
+                .map(Some)
+                .ok_or_else(|| ReportError::ValidationError(format!("无效的举报状态: {}", s))),
+            _ => Ok(None),
+        }
+    }
+
+    async fn load_target(
+        pool: &PgPool,
+        target_type: ReportTargetType,
+        target_id: Uuid,
+    ) -> Result<Option<ReportTargetInfo>, ReportError> {
+        let mut targets = Self::load_targets(pool, &[(target_type, target_id)]).await?;
+        Ok(targets.remove(&(target_type, target_id)))
+    }
+
+    /// 批量查询被举报对象，已被彻底删除的对象不在结果中
+    async fn load_targets(
+        pool: &PgPool,
+        keys: &[(ReportTargetType, Uuid)],
+    ) -> Result<HashMap<(ReportTargetType, Uuid), ReportTargetInfo>, ReportError> {
+        let mut targets = HashMap::new();
+        for (target_type, sql) in [
+            (ReportTargetType::Resource, RESOURCE_TARGET_SQL),
+            (ReportTargetType::Comment, COMMENT_TARGET_SQL),
+        ] {
+            let ids: Vec<Uuid> = keys
+                .iter()
+                .filter(|(t, _)| *t == target_type)
+                .map(|(_, id)| *id)
+                .collect();
+            if ids.is_empty() {
+                continue;
+            }
+            let rows = sqlx::query_as::<_, ReportTargetRow>(sql)
+                .bind(&ids)
+                .fetch_all(pool)
+                .await?;
+            for row in rows {
+                targets.insert((target_type, row.id), row.info);
+            }
+        }
+        Ok(targets)
+    }
+}
@@ -1,10 +1,22 @@
-use crate::models::{resource::*, CurrentUser};
+use crate::models::{resource::*, CurrentUser, Permission};
 use sqlx::{PgPool, Row};
 use std::sync::Arc;
 use tokio::time::Duration;
 use uuid::Uuid;
 
-use super::{AiService, FileService};
+use super::{AiService, FileService, PermissionService, ResourceVersionService};
+use crate::utils::{build_like_pattern, highlight_snippet, highlight_text, split_search_terms};
+
+/// 资源详情中"下载过该资源的用户还下载了"的展示数量
+const ALSO_DOWNLOADED_LIMIT: i64 = 6;
+/// 热度分中单次浏览的权重
+const TRENDING_VIEW_WEIGHT: f64 = 1.0;
+/// 热度分中单次下载的权重
+const TRENDING_DOWNLOAD_WEIGHT: f64 = 3.0;
+/// 热度分中单次点赞的权重
+const TRENDING_LIKE_WEIGHT: f64 = 5.0;
+/// 热度分中单次收藏的权重
+const TRENDING_FAVORITE_WEIGHT: f64 = 8.0;
 
 #[derive(Debug)]
 pub enum ResourceError {
@@ -53,6 +65,9 @@ impl From<super::storage_service::StorageError> for ResourceError {
             super::storage_service::StorageError::NotFound(msg) => ResourceError::NotFound(msg),
             super::storage_service::StorageError::Io(msg) => ResourceError::FileError(msg),
             super::storage_service::StorageError::Backend(msg) => ResourceError::FileError(msg),
+            err @ super::storage_service::StorageError::RangeNotSatisfiable(_) => {
+                ResourceError::ValidationError(err.to_string())
+            }
         }
     }
 }
@@ -63,6 +78,25 @@ impl From<sqlx::Error> for ResourceError {
     }
 }
 
+/// 下载用的资源文件信息：(file_path, resource_type, title, storage_type, file_hash, updated_at)
+pub type ResourceFileInfo = (
+    String,
+    String,
+    String,
+    Option<String>,
+    Option<String>,
+    chrono::DateTime<chrono::Utc>,
+);
+
+/// 预览用的资源文件信息：(file_path, resource_type, storage_type, updated_at, file_hash)
+pub type ResourcePreviewFileInfo = (
+    String,
+    String,
+    Option<String>,
+    chrono::DateTime<chrono::Utc>,
+    Option<String>,
+);
+
 pub struct ResourceService;
 
 impl ResourceService {
@@ -102,6 +136,7 @@ impl ResourceService {
         pool: &PgPool,
         user: &CurrentUser,
         storage: &Arc<dyn super::StorageBackend>,
+        moderation: &Arc<dyn super::ModerationProvider>,
         request: UploadResourceRequest,
         oss_key: &str,
         metadata: super::StorageFileMetadata,
@@ -138,17 +173,56 @@ impl ResourceService {
             )));
         }
 
-        let ai_result =
-            AiService::audit_resource(&request.title, request.description.as_deref(), None)
-                .await
-                .map_err(|e| ResourceError::AiError(e.to_string()))?;
-        let audit_status = if ai_result.passed {
-            AuditStatus::Approved
-        } else {
-            AuditStatus::Pending
-        };
-
         let resource_id = Uuid::new_v4();
+
+        // 从 OSS 下载文件并计算哈希（带重试机制），用于重复上传检测
+        let file_hash =
+            match Self::compute_hash_from_storage_with_retry(storage, oss_key, resource_id).await {
+                Ok(hash) => {
+                    log::info!(
+                        "[Resource] OSS 回调文件哈希计算成功 | resource_id={}, hash={}",
+                        resource_id,
+                        &hash[..16.min(hash.len())]
+                    );
+                    Some(hash)
+                }
+                Err(e) => {
+                    log::warn!(
+                        "[Resource] OSS 回调计算文件哈希失败 | resource_id={}, error={}",
+                        resource_id,
+                        e
+                    );
+                    // 哈希计算失败时无法确认是否重复，资源转人工审核，哈希由定时任务补算
+                    None
+                }
+            };
+
+        if let Some(ref hash) = file_hash {
+            if let Err(e) =
+                Self::check_duplicate_upload(pool, user, hash, request.allow_duplicate).await
+            {
+                if let Err(cleanup_err) = storage.delete_file(oss_key).await {
+                    log::warn!(
+                        "[Resource] 重复上传清理文件失败 | key={}, error={}",
+                        oss_key,
+                        cleanup_err
+                    );
+                }
+                return Err(e);
+            }
+        }
+
+        let ai_result = AiService::audit_resource(
+            moderation.as_ref(),
+            &request.title,
+            request.description.as_deref(),
+            None,
+        )
+        .await
+        .map_err(|e| ResourceError::AiError(e.to_string()))?;
+        let (audit_status, reject_reason) =
+            Self::callback_audit_outcome(ai_result.passed, ai_result.reason, file_hash.is_some());
+
         let tags_json = request
             .tags
             .as_ref()
@@ -160,6 +234,22 @@ impl ResourceService {
             .await
             .map_err(|e| ResourceError::DatabaseError(format!("开启事务失败: {}", e)))?;
 
+        if let Some(ref hash) = file_hash {
+            if let Err(e) =
+                Self::recheck_duplicate_in_tx(&mut tx, pool, user, hash, request.allow_duplicate)
+                    .await
+            {
+                if let Err(cleanup_err) = storage.delete_file(oss_key).await {
+                    log::warn!(
+                        "[Resource] 重复上传清理文件失败 | key={}, error={}",
+                        oss_key,
+                        cleanup_err
+                    );
+                }
+                return Err(e);
+            }
+        }
+
         let resource: Resource = match sqlx::query_as::<_, Resource>(
             r#"
             INSERT INTO resources (
@@ -181,15 +271,11 @@ impl ResourceService {
         .bind(tags_json)
         .bind(oss_key)
         .bind(None::<String>)
-        .bind(None::<String>)
+        .bind(&file_hash)
         .bind(file_size as i64)
         .bind(ai_result.accuracy_score)
         .bind(audit_status.to_string())
-        .bind(if ai_result.passed {
-            None
-        } else {
-            ai_result.reason.as_deref()
-        })
+        .bind(reject_reason.as_deref())
         .bind(&storage_type)
         .bind(request.description.as_ref())
         .fetch_one(&mut *tx)
@@ -293,41 +379,6 @@ impl ResourceService {
             );
         }
 
-        // 从 OSS 下载文件并计算哈希（带重试机制）
-        let file_hash = Self::compute_hash_from_storage_with_retry(storage, oss_key, resource_id).await;
-        match file_hash {
-            Ok(hash) => {
-                // 更新数据库中的 file_hash
-                if let Err(e) = sqlx::query(
-                    "UPDATE resources SET file_hash = $1 WHERE id = $2"
-                )
-                .bind(&hash)
-                .bind(resource_id)
-                .execute(&mut *tx)
-                .await
-                {
-                    log::warn!(
-                        "[Resource] OSS 回调更新文件哈希失败 | resource_id={}, error={}",
-                        resource_id, e
-                    );
-                    // 哈希更新失败不影响主流程，继续提交事务
-                } else {
-                    log::info!(
-                        "[Resource] OSS 回调文件哈希计算成功 | resource_id={}, hash={}",
-                        resource_id,
-                        &hash[..16.min(hash.len())]
-                    );
-                }
-            }
-            Err(e) => {
-                log::warn!(
-                    "[Resource] OSS 回调计算文件哈希失败 | resource_id={}, error={}",
-                    resource_id, e
-                );
-                // 哈希计算失败不影响主流程，后续定时任务会重新计算
-            }
-        }
-
         if let Err(e) = tx.commit().await {
             if let Err(cleanup_err) = storage.delete_file(oss_key).await {
                 log::warn!(
@@ -344,20 +395,140 @@ impl ResourceService {
             title: resource.title,
             resource_type: resource.resource_type,
             audit_status: resource.audit_status,
-            ai_message: if ai_result.passed {
-                Some("AI 审核通过".to_string())
-            } else {
-                Some("AI 审核未通过，等待人工审核".to_string())
-            },
+            ai_message: Some(
+                if file_hash.is_none() {
+                    "无法完成重复检测，等待人工审核"
+                } else if ai_result.passed {
+                    "AI 审核通过"
+                } else {
+                    "AI 审核未通过，等待人工审核"
+                }
+                .to_string(),
+            ),
             created_at: resource.created_at,
         })
     }
 
+    /// OSS 回调上传的审核结果：(审核状态, 拒绝原因)
+    ///
+    /// 文件哈希计算失败时无法进行重复检测，即使 AI 审核通过也转为人工审核
+    fn callback_audit_outcome(
+        ai_passed: bool,
+        ai_reason: Option<String>,
+        hash_computed: bool,
+    ) -> (AuditStatus, Option<String>) {
+        if !hash_computed {
+            let reason = match ai_reason.filter(|_| !ai_passed) {
+                Some(reason) => format!("{}；文件哈希计算失败，需人工确认是否重复上传", reason),
+                None => "文件哈希计算失败，需人工确认是否重复上传".to_string(),
+            };
+            (AuditStatus::Pending, Some(reason))
+        } else if ai_passed {
+            (AuditStatus::Approved, None)
+        } else {
+            (AuditStatus::Pending, ai_reason)
+        }
+    }
+
+    /// 检查是否已存在相同文件的已通过资源，存在时返回指向该资源的冲突错误
+    ///
+    /// 在保存文件、AI 审核之前调用，尽早拒绝重复上传；入库前还需在事务内
+    /// 调用 `recheck_duplicate_in_tx` 再次确认
+    async fn check_duplicate_upload(
+        pool: &PgPool,
+        user: &CurrentUser,
+        file_hash: &str,
+        allow_duplicate: bool,
+    ) -> Result<(), ResourceError> {
+        let existing = Self::find_approved_duplicate(pool, file_hash).await?;
+        Self::ensure_duplicate_allowed(pool, user, existing, allow_duplicate).await
+    }
+
+    /// 在入库事务内对文件哈希加锁并重新检测重复
+    ///
+    /// 使用事务级 advisory lock 串行化相同哈希的并发上传，锁在事务结束时释放。
+    /// 不使用唯一索引：历史数据中已存在重复文件，且管理员可以强制上传重复资源
+    async fn recheck_duplicate_in_tx(
+        tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+        pool: &PgPool,
+        user: &CurrentUser,
+        file_hash: &str,
+        allow_duplicate: bool,
+    ) -> Result<(), ResourceError> {
+        sqlx::query("SELECT pg_advisory_xact_lock(hashtext($1))")
+            .bind(format!("resource_file_hash:{}", file_hash))
+            .execute(&mut **tx)
+            .await?;
+        let existing = Self::find_approved_duplicate(&mut **tx, file_hash).await?;
+        Self::ensure_duplicate_allowed(pool, user, existing, allow_duplicate).await
+    }
+
+    /// 查询相同哈希中最早的已通过资源
+    async fn find_approved_duplicate<'e>(
+        executor: impl sqlx::PgExecutor<'e>,
+        file_hash: &str,
+    ) -> Result<Option<(Uuid, String)>, ResourceError> {
+        let existing = sqlx::query_as(
+            r#"
+            SELECT id, title FROM resources
+            WHERE file_hash = $1 AND audit_status = 'approved'
+            ORDER BY created_at ASC
+            LIMIT 1
+            "#,
+        )
+        .bind(file_hash)
+        .fetch_optional(executor)
+        .await?;
+        Ok(existing)
+    }
+
+    /// 根据检测结果决定是否允许上传
+    ///
+    /// 具有 resource.manage 权限的用户可以通过 `allow_duplicate` 强制上传
+    async fn ensure_duplicate_allowed(
+        pool: &PgPool,
+        user: &CurrentUser,
+        existing: Option<(Uuid, String)>,
+        allow_duplicate: bool,
+    ) -> Result<(), ResourceError> {
+        let Some((existing_id, existing_title)) = existing else {
+            return Ok(());
+        };
+
+        if !allow_duplicate {
+            log::info!(
+                "[Resource] 检测到重复上传 | user_id={}, existing_id={}",
+                user.id,
+                existing_id
+            );
+            return Err(ResourceError::Conflict(format!(
+                "已存在相同文件的资源《{}》（ID: {}），请勿重复上传",
+                existing_title, existing_id
+            )));
+        }
+
+        let permissions =
+            PermissionService::load_user_permissions(pool, user.id, &user.role).await?;
+        if !permissions.has(Permission::ResourceManage) {
+            return Err(ResourceError::Unauthorized(
+                "只有管理员可以强制上传重复资源".to_string(),
+            ));
+        }
+        log::info!(
+            "[Resource] 管理员强制上传重复资源 | user_id={}, existing_id={}",
+            user.id,
+            existing_id
+        );
+        Ok(())
+    }
+
     /// 上传资源
+    #[allow(clippy::too_many_arguments)]
     pub async fn upload_resource(
         pool: &PgPool,
         user: &CurrentUser,
         storage: &Arc<dyn super::StorageBackend>,
+        moderation: &Arc<dyn super::ModerationProvider>,
         request: UploadResourceRequest,
         file_name: &str,
         file_data: Vec<u8>,
@@ -369,8 +540,13 @@ impl ResourceService {
         // 验证并确定资源类型
         let resource_type = FileService::validate_resource_file(file_name, &file_data, mime_type)?;
 
+        // 重复上传检测
+        let file_hash = FileService::calculate_hash(&file_data);
+        Self::check_duplicate_upload(pool, user, &file_hash, request.allow_duplicate).await?;
+
         // AI 审核
         let ai_result = AiService::audit_resource(
+            moderation.as_ref(),
             &request.title,
             request.description.as_deref(),
             Some(&file_data),
@@ -383,7 +559,6 @@ impl ResourceService {
         let resource_type_str = resource_type.to_string();
         let extension = FileService::get_extension_by_type(&resource_type_str);
         let file_key = format!("resources/{}.{}", resource_id, extension);
-        let file_hash = FileService::calculate_hash(&file_data);
         let file_size = file_data.len() as i64;
         let storage_type = storage.backend_type().as_str().to_string();
 
@@ -423,6 +598,21 @@ impl ResourceService {
             }
         };
 
+        // 事务内加锁重新检测，避免并发上传相同文件时都通过前置检测
+        if let Err(e) =
+            Self::recheck_duplicate_in_tx(&mut tx, pool, user, &file_hash, request.allow_duplicate)
+                .await
+        {
+            if let Err(cleanup_err) = storage.delete_file(&file_path).await {
+                log::error!(
+                    "[Resource] 重复上传清理文件出错 | path={}, error={}",
+                    file_path,
+                    cleanup_err
+                );
+            }
+            return Err(e);
+        }
+
         // 插入资源记录
         log::debug!(
             "[Resource] 准备插入资源记录 | title={}, resource_type={}",
@@ -711,6 +901,23 @@ impl ResourceService {
         })
         .unwrap_or_default();
 
+        // 获取"下载过该资源的用户还下载了"（来自推荐任务物化的下载相似度）
+        let also_downloaded = super::RecommendationService::get_also_downloaded(
+            pool,
+            resource_id,
+            ALSO_DOWNLOADED_LIMIT,
+        )
+        .await
+        .map_err(|e| {
+            log::warn!(
+                "[Resource] 获取相似下载资源失败 | resource_id={}, error={}",
+                resource_id,
+                e
+            );
+            e
+        })
+        .unwrap_or_default();
+
         Ok(ResourceDetailResponse {
             id: resource.id,
             title: resource.title,
@@ -740,6 +947,7 @@ impl ResourceService {
             teachers,
             courses,
             related_resources,
+            also_downloaded,
             storage_type: resource.storage_type.clone().unwrap_or_else(|| "local".to_string()),
         })
     }
@@ -974,6 +1182,8 @@ impl ResourceService {
                     .ok()
                     .flatten()
                     .unwrap_or_else(|| "local".to_string()),
+                search_rank: None,
+                highlight: None,
             });
         }
         Ok(resources)
@@ -988,7 +1198,10 @@ impl ResourceService {
     }
 
     /// 搜索资源
-    /// 使用 QueryBuilder 构建动态查询，避免字符串拼接
+    ///
+    /// 使用 tsvector 全文检索（标题、课程名、标签、描述），并以三元组模糊匹配作为中文等
+    /// 无法分词场景的回退；关键词中的每个词都必须命中检索文本、关联教师名或关联课程名之一。
+    /// 结果按相关度排序，并返回高亮片段。
     pub async fn search_resources(
         pool: &PgPool,
         query: &ResourceSearchQuery,
@@ -997,7 +1210,10 @@ impl ResourceService {
         let per_page = query.get_per_page();
         let offset = (page - 1) * per_page;
 
-        let search_pattern = format!("%{}%", query.q);
+        let keyword = query.q.trim();
+        let terms = split_search_terms(keyword);
+        let term_patterns: Vec<String> = terms.iter().map(|t| build_like_pattern(t)).collect();
+        let keyword_pattern = build_like_pattern(keyword);
 
         // 判断是否需要关联表
         let need_teacher_join = !query.teacher_sns.is_empty();
@@ -1005,12 +1221,9 @@ impl ResourceService {
 
         // 使用 QueryBuilder 构建 COUNT 查询
         let mut count_builder = sqlx::QueryBuilder::new(
-            "SELECT COUNT(DISTINCT r.id) FROM resources r WHERE r.audit_status = 'approved' AND (r.title ILIKE "
+            "SELECT COUNT(DISTINCT r.id) FROM resources r WHERE r.audit_status = 'approved'",
         );
-        count_builder.push_bind(&search_pattern);
-        count_builder.push(" OR r.course_name ILIKE ");
-        count_builder.push_bind(&search_pattern);
-        count_builder.push(")");
+        Self::add_search_match_condition(&mut count_builder, keyword, &term_patterns);
 
         // 添加关联表筛选条件
         if need_teacher_join {
@@ -1041,6 +1254,7 @@ impl ResourceService {
             .map_err(|e| ResourceError::DatabaseError(e.to_string()))?;
 
         // 使用 QueryBuilder 构建搜索查询
+        // 相关度 = 全文检索得分 + 标题/课程名三元组相似度 + 标题包含完整关键词的加成
         let mut search_builder = sqlx::QueryBuilder::new(
             r#"
             SELECT r.*, rs.views, rs.downloads, rs.likes,
@@ -1049,17 +1263,27 @@ impl ResourceService {
                    rs.answer_quality_total, rs.answer_quality_count,
                    rs.format_quality_total, rs.format_quality_count,
                    rs.detail_level_total, rs.detail_level_count,
-                   u.username as uploader_name
+                   u.username as uploader_name,
+                   (
+                       COALESCE(ts_rank_cd(r.search_vector, websearch_to_tsquery(resource_search_config(), "#,
+        );
+        search_builder.push_bind(keyword);
+        search_builder.push("), 32), 0) + word_similarity(");
+        search_builder.push_bind(keyword);
+        search_builder.push(", r.title) + 0.5 * word_similarity(");
+        search_builder.push_bind(keyword);
+        search_builder.push(", COALESCE(r.course_name, '')) + CASE WHEN r.title ILIKE ");
+        search_builder.push_bind(&keyword_pattern);
+        search_builder.push(
+            r#" THEN 1.0 ELSE 0.0 END
+                   )::float8 AS search_rank
             FROM resources r
             LEFT JOIN resource_stats rs ON r.id = rs.resource_id
             LEFT JOIN users u ON r.uploader_id = u.id
-            WHERE r.audit_status = 'approved' AND (r.title ILIKE
+            WHERE r.audit_status = 'approved'
             "#,
         );
-        search_builder.push_bind(&search_pattern);
-        search_builder.push(" OR r.course_name ILIKE ");
-        search_builder.push_bind(&search_pattern);
-        search_builder.push(")");
+        Self::add_search_match_condition(&mut search_builder, keyword, &term_patterns);
 
         // 添加关联表筛选条件
         if need_teacher_join {
@@ -1083,8 +1307,8 @@ impl ResourceService {
             search_builder.push_bind(category);
         }
 
-        // 添加排序和分页
-        search_builder.push(" ORDER BY r.created_at DESC LIMIT ");
+        // 按相关度排序，相关度相同时按创建时间倒序
+        search_builder.push(" ORDER BY search_rank DESC, r.created_at DESC LIMIT ");
         search_builder.push_bind(per_page as i64);
         search_builder.push(" OFFSET ");
         search_builder.push_bind(offset as i64);
@@ -1095,7 +1319,28 @@ impl ResourceService {
             .await
             .map_err(|e| ResourceError::DatabaseError(e.to_string()))?;
 
-        let resources = Self::map_rows_to_resources(rows)?;
+        // 先取出高亮所需的字段，map_rows_to_resources 会消耗 rows
+        let extras: Vec<(Option<f64>, Option<String>)> = rows
+            .iter()
+            .map(|row| {
+                (
+                    row.try_get::<f64, _>("search_rank").ok(),
+                    row.try_get::<Option<String>, _>("description")
+                        .ok()
+                        .flatten(),
+                )
+            })
+            .collect();
+
+        let mut resources = Self::map_rows_to_resources(rows)?;
+        for (item, (rank, description)) in resources.iter_mut().zip(extras) {
+            item.search_rank = rank;
+            item.highlight = Some(Self::build_search_highlight(
+                item,
+                description.as_deref(),
+                &terms,
+            ));
+        }
 
         Ok(ResourceListResponse {
             resources,
@@ -1105,6 +1350,64 @@ impl ResourceService {
         })
     }
 
+    /// 辅助方法：添加搜索匹配条件到 QueryBuilder
+    /// 全文检索命中，或每个检索词都命中检索文本/关联教师名/关联课程名之一
+    fn add_search_match_condition<'a>(
+        builder: &mut sqlx::QueryBuilder<'a, sqlx::Postgres>,
+        keyword: &'a str,
+        term_patterns: &'a [String],
+    ) {
+        builder.push(" AND (r.search_vector @@ websearch_to_tsquery(resource_search_config(), ");
+        builder.push_bind(keyword);
+        builder.push(")");
+
+        if !term_patterns.is_empty() {
+            builder.push(" OR (");
+            for (i, pattern) in term_patterns.iter().enumerate() {
+                if i > 0 {
+                    builder.push(" AND ");
+                }
+                builder.push("(r.search_document ILIKE ");
+                builder.push_bind(pattern);
+                builder.push(" OR EXISTS (SELECT 1 FROM resource_teachers srt JOIN teachers st ON st.sn = srt.teacher_sn WHERE srt.resource_id = r.id AND st.name ILIKE ");
+                builder.push_bind(pattern);
+                builder.push(") OR EXISTS (SELECT 1 FROM resource_courses src JOIN courses sc ON sc.sn = src.course_sn WHERE src.resource_id = r.id AND sc.name ILIKE ");
+                builder.push_bind(pattern);
+                builder.push("))");
+            }
+            builder.push(")");
+        }
+
+        builder.push(")");
+    }
+
+    /// 构建搜索结果的高亮片段（优先描述，其次标签）
+    fn build_search_highlight(
+        item: &ResourceListItem,
+        description: Option<&str>,
+        terms: &[String],
+    ) -> ResourceSearchHighlight {
+        const SNIPPET_MAX_CHARS: usize = 120;
+
+        let snippet = description
+            .and_then(|d| highlight_snippet(d, terms, SNIPPET_MAX_CHARS))
+            .or_else(|| {
+                item.tags
+                    .as_ref()
+                    .and_then(|tags| highlight_text(&tags.join(" "), terms))
+            })
+            .or_else(|| {
+                item.course_name
+                    .as_deref()
+                    .and_then(|c| highlight_text(c, terms))
+            });
+
+        ResourceSearchHighlight {
+            title: highlight_text(&item.title, terms),
+            snippet,
+        }
+    }
+
     /// 删除资源
     /// 返回被删除资源的标题
     pub async fn delete_resource(
@@ -1112,6 +1415,26 @@ impl ResourceService {
         user: &CurrentUser,
         storage: &Arc<dyn super::StorageBackend>,
         resource_id: Uuid,
+    ) -> Result<String, ResourceError> {
+        Self::delete_resource_as(pool, Some(user), storage, resource_id).await
+    }
+
+    /// 管理后台删除资源，不检查上传者，调用方需要已校验 resource.manage 权限
+    /// 返回被删除资源的标题
+    pub async fn force_delete_resource(
+        pool: &PgPool,
+        storage: &Arc<dyn super::StorageBackend>,
+        resource_id: Uuid,
+    ) -> Result<String, ResourceError> {
+        Self::delete_resource_as(pool, None, storage, resource_id).await
+    }
+
+    /// `user` 不为空时只允许上传者或管理员删除
+    async fn delete_resource_as(
+        pool: &PgPool,
+        user: Option<&CurrentUser>,
+        storage: &Arc<dyn super::StorageBackend>,
+        resource_id: Uuid,
     ) -> Result<String, ResourceError> {
         // 获取资源信息
         let resource: Resource =
@@ -1123,10 +1446,12 @@ impl ResourceService {
                 .ok_or_else(|| ResourceError::NotFound(format!("资源 {} 不存在", resource_id)))?;
 
         // 检查权限（上传者或管理员）
-        if resource.uploader_id != user.id && user.role != crate::models::UserRole::Admin {
-            return Err(ResourceError::Unauthorized(
-                "没有权限删除此资源".to_string(),
-            ));
+        if let Some(user) = user {
+            if resource.uploader_id != user.id && user.role != crate::models::UserRole::Admin {
+                return Err(ResourceError::Unauthorized(
+                    "没有权限删除此资源".to_string(),
+                ));
+            }
         }
 
         // 删除文件
@@ -1152,6 +1477,15 @@ impl ResourceService {
             }
         }
 
+        // 删除历史版本文件
+        if let Err(e) = ResourceVersionService::delete_version_files(pool, storage, resource_id).await {
+            log::warn!(
+                "[Resource] 删除资源历史版本文件失败 | resource_id={}, error={}",
+                resource_id,
+                e
+            );
+        }
+
         // 保存资源标题用于返回
         let title = resource.title.clone();
 
@@ -1320,6 +1654,8 @@ impl ResourceService {
                     .ok()
                     .flatten()
                     .unwrap_or_else(|| "local".to_string()),
+                search_rank: None,
+                highlight: None,
             });
         }
 
@@ -1355,20 +1691,41 @@ impl ResourceService {
             .await
             .map_err(|e| ResourceError::DatabaseError(e.to_string()))?;
 
+        // 按天累计浏览量，用于计算时间窗口内的热度；失败只影响热度，不影响浏览计数
+        if let Err(e) = sqlx::query(
+            r#"
+            INSERT INTO resource_daily_views (resource_id, view_date, views)
+            VALUES ($1, CURRENT_DATE, 1)
+            ON CONFLICT (resource_id, view_date)
+            DO UPDATE SET views = resource_daily_views.views + 1
+            "#,
+        )
+        .bind(resource_id)
+        .execute(pool)
+        .await
+        {
+            log::warn!(
+                "[Resource] 记录每日浏览量失败 | resource_id={}, error={}",
+                resource_id,
+                e
+            );
+        }
+
         Ok(())
     }
 
     /// 获取资源文件路径（检查审核状态和权限，用于下载）
-    /// 返回：(file_path, resource_type, title, storage_type)
+    /// 返回：(file_path, resource_type, title, storage_type, file_hash, updated_at)
     /// 只有管理员或上传者可以访问未审核的资源，其他情况（包括游客）只能访问已通过审核的资源
     pub async fn get_resource_file_path(
         pool: &PgPool,
         resource_id: Uuid,
         user: Option<&CurrentUser>,
-    ) -> Result<(String, String, String, Option<String>), ResourceError> {
+    ) -> Result<ResourceFileInfo, ResourceError> {
         // 获取资源信息，包括审核状态和上传者
-        let row: (String, String, String, Option<String>, String, Uuid) = sqlx::query_as(
-            "SELECT file_path, resource_type, title, storage_type, audit_status, uploader_id FROM resources WHERE id = $1"
+        // updated_at 不带时区，按会话时区（与写入时一致）转换为 UTC 后返回
+        let row: (String, String, String, Option<String>, String, Uuid, Option<String>, chrono::DateTime<chrono::Utc>) = sqlx::query_as(
+            "SELECT file_path, resource_type, title, storage_type, audit_status, uploader_id, file_hash, updated_at AT TIME ZONE current_setting('TimeZone') FROM resources WHERE id = $1"
         )
         .bind(resource_id)
         .fetch_optional(pool)
@@ -1389,20 +1746,21 @@ impl ResourceService {
             ));
         }
 
-        Ok((row.0, row.1, row.2, row.3))
+        Ok((row.0, row.1, row.2, row.3, row.6, row.7))
     }
 
     /// 获取资源文件路径（检查审核状态和权限，用于预览）
-    /// 返回：(file_path, resource_type, storage_type, updated_at)
+    /// 返回：(file_path, resource_type, storage_type, updated_at, file_hash)
     /// 只有管理员或上传者可以访问未审核的资源，其他情况（包括游客）只能访问已通过审核的资源
     pub async fn get_resource_file_path_for_preview(
         pool: &PgPool,
         resource_id: Uuid,
         user: Option<&CurrentUser>,
-    ) -> Result<(String, String, Option<String>, chrono::NaiveDateTime), ResourceError> {
+    ) -> Result<ResourcePreviewFileInfo, ResourceError> {
         // 获取资源信息，包括审核状态和上传者
-        let row: (String, String, Option<String>, chrono::NaiveDateTime, String, Uuid) =
-            sqlx::query_as("SELECT file_path, resource_type, storage_type, updated_at, audit_status, uploader_id FROM resources WHERE id = $1")
+        // updated_at 不带时区，按会话时区（与写入时一致）转换为 UTC 后返回
+        let row: (String, String, Option<String>, chrono::DateTime<chrono::Utc>, String, Uuid, Option<String>) =
+            sqlx::query_as("SELECT file_path, resource_type, storage_type, updated_at AT TIME ZONE current_setting('TimeZone'), audit_status, uploader_id, file_hash FROM resources WHERE id = $1")
                 .bind(resource_id)
                 .fetch_optional(pool)
                 .await
@@ -1422,7 +1780,7 @@ impl ResourceService {
             ));
         }
 
-        Ok((row.0, row.1, row.2, row.3))
+        Ok((row.0, row.1, row.2, row.3, row.6))
     }
 
     /// 记录下载日志
@@ -1455,8 +1813,24 @@ impl ResourceService {
         pool: &PgPool,
         user: &CurrentUser,
         storage: &Arc<dyn super::StorageBackend>,
+        moderation: &Arc<dyn super::ModerationProvider>,
+        resource_id: Uuid,
+        content: String,
+    ) -> Result<crate::models::UpdateResourceContentResponse, ResourceError> {
+        Self::save_resource_content(pool, user, storage, moderation, resource_id, content, None)
+            .await
+    }
+
+    /// 保存资源内容并记录新版本
+    /// rollback_from: 由版本回滚触发时为回滚来源的版本序号
+    pub async fn save_resource_content(
+        pool: &PgPool,
+        user: &CurrentUser,
+        storage: &Arc<dyn super::StorageBackend>,
+        moderation: &Arc<dyn super::ModerationProvider>,
         resource_id: Uuid,
         content: String,
+        rollback_from: Option<i32>,
     ) -> Result<crate::models::UpdateResourceContentResponse, ResourceError> {
         // 验证内容长度
         if content.len() > 10 * 1024 * 1024 {
@@ -1489,50 +1863,43 @@ impl ResourceService {
         }
 
         // AI 审核更新后的内容
-        let ai_result =
-            AiService::audit_resource(&resource.title, Some(&content), Some(content.as_bytes()))
-                .await
-                .map_err(|e| ResourceError::AiError(e.to_string()))?;
+        let ai_result = AiService::audit_resource(
+            moderation.as_ref(),
+            &resource.title,
+            Some(&content),
+            Some(content.as_bytes()),
+        )
+        .await
+        .map_err(|e| ResourceError::AiError(e.to_string()))?;
+
+        // 首次编辑前保存原始内容为版本 1，避免被覆盖后无法找回
+        ResourceVersionService::ensure_initial_version(pool, storage, &resource).await?;
 
         // 保存旧的hash用于乐观锁检查
         let old_hash = resource.file_hash.clone();
 
         // 根据资源实际的存储类型选择正确的存储后端写入文件
-        let is_oss = resource.storage_type.as_deref() == Some("oss");
+        let is_oss = super::is_object_storage_type(resource.storage_type.as_deref());
         let content_bytes = content.as_bytes();
         let expected_hash = FileService::calculate_hash(content_bytes);
 
-        if is_oss {
-            // OSS 存储
-            if storage.backend_type() == super::StorageBackendType::Oss {
-                storage
-                    .write_file(
-                        &resource.file_path,
-                        content_bytes.to_vec(),
-                        Some("text/markdown"),
-                    )
-                    .await?;
-            } else {
-                // 当前是 local 模式，但需要写入 OSS 文件
-                let config = crate::config::Config::from_env();
-                match super::create_storage_backend(&config) {
-                    Ok(oss_storage) if oss_storage.backend_type() == super::StorageBackendType::Oss => {
-                        oss_storage
-                            .write_file(
-                                &resource.file_path,
-                                content_bytes.to_vec(),
-                                Some("text/markdown"),
-                            )
-                            .await?;
-                    }
-                    _ => return Err(ResourceError::FileError("无法写入 OSS 资源".to_string())),
-                }
-            }
+        let config = crate::config::Config::from_env();
+        let target_storage =
+            super::storage_for_type(storage, &config, resource.storage_type.as_deref())
+                .map_err(|e| ResourceError::FileError(format!("无法访问存储: {}", e)))?;
+        target_storage
+            .write_file(
+                &resource.file_path,
+                content_bytes.to_vec(),
+                Some("text/markdown"),
+            )
+            .await?;
 
-            // OSS写入后验证：读取文件并验证内容一致性（处理最终一致性）
+        if is_oss {
+            // 对象存储写入后验证：读取文件并验证内容一致性（处理最终一致性）
             // 最多重试5次，使用指数退避
             let verification_result = Self::verify_oss_write_with_retry(
-                storage,
+                &target_storage,
                 &resource.file_path,
                 content_bytes,
                 resource_id,
@@ -1556,32 +1923,6 @@ impl ResourceService {
                     )));
                 }
             }
-        } else {
-            // 本地存储
-            if storage.backend_type() == super::StorageBackendType::Local {
-                storage
-                    .write_file(
-                        &resource.file_path,
-                        content_bytes.to_vec(),
-                        Some("text/markdown"),
-                    )
-                    .await?;
-            } else {
-                // 当前是 OSS 模式，但需要写入本地文件
-                let config = crate::config::Config::from_env();
-                match super::create_local_storage(&config) {
-                    Ok(local_storage) => {
-                        local_storage
-                            .write_file(
-                                &resource.file_path,
-                                content_bytes.to_vec(),
-                                Some("text/markdown"),
-                            )
-                            .await?;
-                    }
-                    Err(e) => return Err(ResourceError::FileError(format!("无法访问本地存储: {}", e))),
-                }
-            }
         }
 
         // 计算新的文件哈希和大小
@@ -1635,10 +1976,37 @@ impl ResourceService {
         .map_err(|e| ResourceError::DatabaseError(e.to_string()))?;
 
         match update_result {
-            Some(updated_at) => Ok(crate::models::UpdateResourceContentResponse {
-                id: resource_id,
-                updated_at,
-            }),
+            Some(updated_at) => {
+                // 将新内容保存为一个版本（版本文件独立存放，不会被后续编辑覆盖）
+                let version_number = match ResourceVersionService::create_version(
+                    pool,
+                    storage,
+                    resource_id,
+                    resource.storage_type.as_deref(),
+                    content_bytes.to_vec(),
+                    Some(user.id),
+                    rollback_from,
+                    None,
+                )
+                .await
+                {
+                    Ok(version_number) => Some(version_number),
+                    Err(e) => {
+                        log::error!(
+                            "[Resource] 保存资源版本失败 | resource_id={}, error={}",
+                            resource_id,
+                            e
+                        );
+                        None
+                    }
+                };
+
+                Ok(crate::models::UpdateResourceContentResponse {
+                    id: resource_id,
+                    version_number,
+                    updated_at,
+                })
+            }
             None => {
                 // 乐观锁失败：资源在编辑期间被其他进程修改
                 log::warn!(
@@ -1676,34 +2044,12 @@ impl ResourceService {
         }
 
         // 根据资源实际的存储类型选择正确的存储后端读取文件
-        let is_oss = resource.storage_type.as_deref() == Some("oss");
-        let content_bytes = if is_oss {
-            // OSS 存储
-            if storage.backend_type() == super::StorageBackendType::Oss {
-                storage.read_file(&resource.file_path).await?
-            } else {
-                // 当前是 local 模式，但需要读取 OSS 文件
-                let config = crate::config::Config::from_env();
-                match super::create_storage_backend(&config) {
-                    Ok(oss_storage) if oss_storage.backend_type() == super::StorageBackendType::Oss => {
-                        oss_storage.read_file(&resource.file_path).await?
-                    }
-                    _ => return Err(ResourceError::FileError("无法读取 OSS 资源".to_string())),
-                }
-            }
-        } else {
-            // 本地存储
-            if storage.backend_type() == super::StorageBackendType::Local {
-                storage.read_file(&resource.file_path).await?
-            } else {
-                // 当前是 OSS 模式，但需要读取本地文件
-                let config = crate::config::Config::from_env();
-                match super::create_local_storage(&config) {
-                    Ok(local_storage) => local_storage.read_file(&resource.file_path).await?,
-                    Err(e) => return Err(ResourceError::FileError(format!("无法访问本地存储: {}", e))),
-                }
-            }
-        };
+        let config = crate::config::Config::from_env();
+        let content_bytes =
+            super::storage_for_type(storage, &config, resource.storage_type.as_deref())
+                .map_err(|e| ResourceError::FileError(format!("无法访问存储: {}", e)))?
+                .read_file(&resource.file_path)
+                .await?;
 
         let content = String::from_utf8(content_bytes)
             .map_err(|e| ResourceError::FileError(format!("文件内容不是有效 UTF-8: {}", e)))?;
@@ -1712,70 +2058,195 @@ impl ResourceService {
     }
 
     /// 获取热门资源列表
-    /// 按浏览量降序排序（主要），下载量次之
-    /// 返回所有资源（包括待审核的），只要浏览量>0或按创建时间排序
+    /// 读取热度任务预计算的时间衰减热度分，只返回已审核通过的资源，可按课程、分类筛选；
+    /// 热度分不足 `limit` 条时（如热度任务尚未运行或窗口内行为较少），
+    /// 按累计浏览量、下载量补足，补足的资源热度分为 0
     pub async fn get_hot_resources(
         pool: &PgPool,
+        window: TrendingWindow,
+        course_sn: Option<i64>,
+        category: Option<&str>,
         limit: i32,
-    ) -> Result<Vec<crate::models::HotResourceItem>, ResourceError> {
-        let limit = limit.max(1).min(20);
+    ) -> Result<Vec<HotResourceItem>, ResourceError> {
+        let limit = limit.clamp(1, 20);
 
-        log::info!("获取热门资源，限制数量: {}", limit);
+        let resources = sqlx::query_as::<_, HotResourceItem>(
+            r#"
+            SELECT
+                r.id,
+                r.title,
+                r.course_name,
+                r.resource_type,
+                COALESCE(rs.downloads, 0) as downloads,
+                COALESCE(rs.views, 0) as views,
+                COALESCE(rs.likes, 0) as likes,
+                ts.score,
+                $5 as source
+            FROM resource_trending_scores ts
+            INNER JOIN resources r ON r.id = ts.resource_id
+            LEFT JOIN resource_stats rs ON r.id = rs.resource_id
+            WHERE ts.time_window = $1
+              AND r.audit_status = 'approved'
+              AND ($2::bigint IS NULL OR EXISTS (
+                  SELECT 1 FROM resource_courses rc
+                  WHERE rc.resource_id = r.id AND rc.course_sn = $2
+              ))
+              AND ($3::varchar IS NULL OR r.category = $3)
+            ORDER BY ts.score DESC, r.created_at DESC
+            LIMIT $4
+            "#,
+        )
+        .bind(window.as_str())
+        .bind(course_sn)
+        .bind(category)
+        .bind(limit as i64)
+        .bind(HotResourceSource::Trending.as_str())
+        .fetch_all(pool)
+        .await
+        .map_err(|e| {
+            log::error!("获取热门资源查询失败: {}", e);
+            ResourceError::DatabaseError(e.to_string())
+        })?;
 
-        // 先检查资源总数
-        let total_count: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM resources")
-            .fetch_one(pool)
-            .await
-            .unwrap_or(0);
-        log::info!("数据库中共有 {} 条资源", total_count);
+        if resources.len() >= limit as usize {
+            return Ok(resources);
+        }
 
-        // 先尝试获取有浏览量的资源
-        let rows = sqlx::query(
+        let fallback = sqlx::query_as::<_, HotResourceItem>(
             r#"
-            SELECT 
+            SELECT
                 r.id,
                 r.title,
                 r.course_name,
                 r.resource_type,
                 COALESCE(rs.downloads, 0) as downloads,
                 COALESCE(rs.views, 0) as views,
-                COALESCE(rs.likes, 0) as likes
+                COALESCE(rs.likes, 0) as likes,
+                0::float8 as score,
+                $4 as source
             FROM resources r
             LEFT JOIN resource_stats rs ON r.id = rs.resource_id
+            WHERE r.audit_status = 'approved'
+              AND ($1::bigint IS NULL OR EXISTS (
+                  SELECT 1 FROM resource_courses rc
+                  WHERE rc.resource_id = r.id AND rc.course_sn = $1
+              ))
+              AND ($2::varchar IS NULL OR r.category = $2)
             ORDER BY COALESCE(rs.views, 0) DESC, COALESCE(rs.downloads, 0) DESC, r.created_at DESC
-            LIMIT $1
+            LIMIT $3
             "#,
         )
+        .bind(course_sn)
+        .bind(category)
         .bind(limit as i64)
+        .bind(HotResourceSource::Stats.as_str())
         .fetch_all(pool)
         .await
         .map_err(|e| {
-            log::error!("获取热门资源查询失败: {}", e);
+            log::error!("获取热门资源兜底查询失败: {}", e);
             ResourceError::DatabaseError(e.to_string())
         })?;
 
-        log::info!("获取到 {} 条热门资源", rows.len());
+        Ok(Self::merge_hot_resources(resources, fallback, limit as usize))
+    }
 
-        let mut resources = Vec::new();
-        for row in rows {
-            resources.push(crate::models::HotResourceItem {
-                id: row
-                    .try_get("id")
-                    .map_err(|e| ResourceError::DatabaseError(e.to_string()))?,
-                title: row
-                    .try_get("title")
-                    .map_err(|e| ResourceError::DatabaseError(e.to_string()))?,
-                course_name: row.try_get("course_name").ok(),
-                resource_type: row
-                    .try_get("resource_type")
-                    .map_err(|e| ResourceError::DatabaseError(e.to_string()))?,
-                downloads: row.try_get::<i32, _>("downloads").unwrap_or(0),
-                views: row.try_get::<i32, _>("views").unwrap_or(0),
-                likes: row.try_get::<i32, _>("likes").unwrap_or(0),
-            });
+    /// 在热度分结果之后追加兜底结果，跳过已出现的资源，总数不超过 `limit`
+    fn merge_hot_resources(
+        mut trending: Vec<HotResourceItem>,
+        fallback: Vec<HotResourceItem>,
+        limit: usize,
+    ) -> Vec<HotResourceItem> {
+        for item in fallback {
+            if trending.len() >= limit {
+                break;
+            }
+            if trending.iter().all(|existing| existing.id != item.id) {
+                trending.push(item);
+            }
         }
+        trending.truncate(limit);
+        trending
+    }
 
-        Ok(resources)
+    /// 重新计算各时间窗口的资源热度分（由热度任务定期调用）
+    ///
+    /// 热度分 = Σ 行为权重 × e^(-λ·距今小时数)，只统计窗口内的浏览、下载、点赞和收藏，
+    /// 每日浏览量按当天中午（不晚于当前时间）计算衰减；
+    /// 同时清理超出最长窗口的每日浏览记录。返回写入的热度分条数
+    pub async fn refresh_trending_scores(pool: &PgPool) -> Result<u64, ResourceError> {
+        let mut tx = pool
+            .begin()
+            .await
+            .map_err(|e| ResourceError::DatabaseError(e.to_string()))?;
+
+        let mut total = 0;
+        for window in TrendingWindow::ALL {
+            sqlx::query("DELETE FROM resource_trending_scores WHERE time_window = $1")
+                .bind(window.as_str())
+                .execute(&mut *tx)
+                .await
+                .map_err(|e| ResourceError::DatabaseError(e.to_string()))?;
+
+            let result = sqlx::query(
+                r#"
+                INSERT INTO resource_trending_scores (time_window, resource_id, score)
+                SELECT $1, e.resource_id,
+                       SUM(e.weight * EXP(-$3::float8 * GREATEST(
+                           EXTRACT(EPOCH FROM (LOCALTIMESTAMP - e.happened_at))::float8 / 3600.0, 0
+                       )))
+                FROM (
+                    SELECT resource_id,
+                           LEAST(view_date + INTERVAL '12 hours', LOCALTIMESTAMP) AS happened_at,
+                           views * $4::float8 AS weight
+                    FROM resource_daily_views
+                    WHERE view_date > CURRENT_DATE - $2::int
+                    UNION ALL
+                    SELECT resource_id, downloaded_at, $5::float8
+                    FROM download_logs
+                    WHERE downloaded_at > LOCALTIMESTAMP - make_interval(days => $2::int)
+                    UNION ALL
+                    SELECT resource_id, created_at, $6::float8
+                    FROM likes
+                    WHERE created_at > LOCALTIMESTAMP - make_interval(days => $2::int)
+                    UNION ALL
+                    SELECT resource_id, added_at, $7::float8
+                    FROM favorite_resources
+                    WHERE added_at > LOCALTIMESTAMP - make_interval(days => $2::int)
+                ) e
+                INNER JOIN resources r ON r.id = e.resource_id
+                WHERE r.audit_status = 'approved'
+                GROUP BY e.resource_id
+                "#,
+            )
+            .bind(window.as_str())
+            .bind(window.days())
+            .bind(window.decay_per_hour())
+            .bind(TRENDING_VIEW_WEIGHT)
+            .bind(TRENDING_DOWNLOAD_WEIGHT)
+            .bind(TRENDING_LIKE_WEIGHT)
+            .bind(TRENDING_FAVORITE_WEIGHT)
+            .execute(&mut *tx)
+            .await
+            .map_err(|e| ResourceError::DatabaseError(e.to_string()))?;
+            total += result.rows_affected();
+        }
+
+        let max_days = TrendingWindow::ALL
+            .iter()
+            .map(|w| w.days())
+            .max()
+            .unwrap_or_default();
+        sqlx::query("DELETE FROM resource_daily_views WHERE view_date <= CURRENT_DATE - $1::int")
+            .bind(max_days)
+            .execute(&mut *tx)
+            .await
+            .map_err(|e| ResourceError::DatabaseError(e.to_string()))?;
+
+        tx.commit()
+            .await
+            .map_err(|e| ResourceError::DatabaseError(e.to_string()))?;
+
+        Ok(total)
     }
 
     /// 获取资源总数
@@ -2152,3 +2623,221 @@ impl ResourceService {
         Err(format!("重试 {} 次后仍然失败: {}", MAX_RETRIES, last_error))
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::UserRole;
+    use crate::test_support::{create_resource, create_user, init_schema};
+
+    async fn current_user(pool: &PgPool, username: &str, role: UserRole) -> CurrentUser {
+        CurrentUser {
+            id: create_user(pool, username).await,
+            username: username.to_string(),
+            role,
+            is_verified: false,
+            session_id: None,
+        }
+    }
+
+    async fn grant_role(pool: &PgPool, user_id: Uuid, role_name: &str) {
+        sqlx::query(
+            "INSERT INTO user_roles (user_id, role_id) SELECT $1, id FROM roles WHERE name = $2",
+        )
+        .bind(user_id)
+        .bind(role_name)
+        .execute(pool)
+        .await
+        .unwrap();
+    }
+
+    #[sqlx::test(migrations = false)]
+    async fn test_duplicate_override_requires_resource_manage(pool: PgPool) {
+        init_schema(&pool).await;
+        let uploader = create_user(&pool, "alice").await;
+        let existing = create_resource(&pool, uploader, "高数笔记").await;
+        sqlx::query("UPDATE resources SET file_hash = 'abc123' WHERE id = $1")
+            .bind(existing)
+            .execute(&pool)
+            .await
+            .unwrap();
+
+        let user = current_user(&pool, "bob", UserRole::User).await;
+        assert!(
+            ResourceService::check_duplicate_upload(&pool, &user, "def456", false)
+                .await
+                .is_ok()
+        );
+        match ResourceService::check_duplicate_upload(&pool, &user, "abc123", false).await {
+            Err(ResourceError::Conflict(msg)) => assert!(msg.contains(&existing.to_string())),
+            other => panic!("期望冲突错误，实际为 {:?}", other),
+        }
+        assert!(matches!(
+            ResourceService::check_duplicate_upload(&pool, &user, "abc123", true).await,
+            Err(ResourceError::Unauthorized(_))
+        ));
+
+        // 没有 resource.manage 的管理角色同样不能强制上传
+        grant_role(&pool, user.id, "moderator").await;
+        assert!(matches!(
+            ResourceService::check_duplicate_upload(&pool, &user, "abc123", true).await,
+            Err(ResourceError::Unauthorized(_))
+        ));
+
+        grant_role(&pool, user.id, "content_manager").await;
+        assert!(
+            ResourceService::check_duplicate_upload(&pool, &user, "abc123", true)
+                .await
+                .is_ok()
+        );
+
+        let admin = current_user(&pool, "admin", UserRole::Admin).await;
+        assert!(
+            ResourceService::check_duplicate_upload(&pool, &admin, "abc123", true)
+                .await
+                .is_ok()
+        );
+    }
+
+    #[sqlx::test(migrations = false)]
+    async fn test_hot_resources_fill_with_stats_after_trending(pool: PgPool) {
+        init_schema(&pool).await;
+        let uploader = create_user(&pool, "alice").await;
+        let old_popular = create_resource(&pool, uploader, "往年热门").await;
+        let old_exam = create_resource(&pool, uploader, "往年试卷").await;
+        let recent_hot = create_resource(&pool, uploader, "本周热门").await;
+        let recent_warm = create_resource(&pool, uploader, "本周新资源").await;
+        let pending = create_resource(&pool, uploader, "待审核").await;
+        sqlx::query("UPDATE resources SET audit_status = 'pending' WHERE id = $1")
+            .bind(pending)
+            .execute(&pool)
+            .await
+            .unwrap();
+
+        // 早期累计的浏览量没有每日记录，不产生热度分
+        for (id, views) in [(old_popular, 50), (old_exam, 30)] {
+            sqlx::query("UPDATE resource_stats SET views = $2 WHERE resource_id = $1")
+                .bind(id)
+                .bind(views)
+                .execute(&pool)
+                .await
+                .unwrap();
+        }
+        for (id, views) in [(recent_hot, 3), (recent_warm, 1), (pending, 5)] {
+            for _ in 0..views {
+                ResourceService::increment_views(&pool, id).await.unwrap();
+            }
+        }
+        ResourceService::refresh_trending_scores(&pool)
+            .await
+            .unwrap();
+
+        let hot = ResourceService::get_hot_resources(&pool, TrendingWindow::Week, None, None, 4)
+            .await
+            .unwrap();
+        let ids: Vec<Uuid> = hot.iter().map(|item| item.id).collect();
+        assert_eq!(ids, vec![recent_hot, recent_warm, old_popular, old_exam]);
+        let sources: Vec<&str> = hot.iter().map(|item| item.source.as_str()).collect();
+        assert_eq!(sources, vec!["trending", "trending", "stats", "stats"]);
+        assert!(hot[0].score > hot[1].score && hot[1].score > 0.0);
+        assert_eq!(hot[2].score, 0.0);
+        assert_eq!(hot[2].views, 50);
+
+        // 热度分足够时不补足
+        let hot = ResourceService::get_hot_resources(&pool, TrendingWindow::Week, None, None, 2)
+            .await
+            .unwrap();
+        assert!(hot.iter().all(|item| item.source == "trending"));
+        assert_eq!(hot.len(), 2);
+
+        // 过滤条件同样作用于补足的资源
+        sqlx::query("UPDATE resources SET category = 'exam' WHERE id = $1")
+            .bind(old_exam)
+            .execute(&pool)
+            .await
+            .unwrap();
+        let hot =
+            ResourceService::get_hot_resources(&pool, TrendingWindow::Week, None, Some("exam"), 10)
+                .await
+                .unwrap();
+        assert_eq!(hot.len(), 1);
+        assert_eq!(hot[0].id, old_exam);
+        assert_eq!(hot[0].source, "stats");
+    }
+
+    #[sqlx::test(migrations = false)]
+    async fn test_file_updated_at_converted_from_session_time_zone(pool: PgPool) {
+        init_schema(&pool).await;
+        let uploader = create_user(&pool, "alice").await;
+        let resource_id: Uuid = sqlx::query_scalar(
+            r#"
+            INSERT INTO resources (title, uploader_id, resource_type, file_path, audit_status, updated_at)
+            VALUES ('高数笔记', $1, 'pdf', 'a.pdf', 'approved', '2024-03-01 16:30:15')
+            RETURNING id
+            "#,
+        )
+        .bind(uploader)
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+
+        // sqlx 连接默认使用 UTC 会话时区，这里模拟东八区会话：16:30:15 对应 UTC 08:30:15
+        let shanghai = sqlx::postgres::PgPoolOptions::new()
+            .after_connect(|conn, _| {
+                Box::pin(async move {
+                    sqlx::query("SET TIME ZONE 'Asia/Shanghai'")
+                        .execute(conn)
+                        .await?;
+                    Ok(())
+                })
+            })
+            .connect_with((*pool.connect_options()).clone())
+            .await
+            .unwrap();
+        let expected = "2024-03-01T08:30:15Z"
+            .parse::<chrono::DateTime<chrono::Utc>>()
+            .unwrap();
+
+        let (.., updated_at) =
+            ResourceService::get_resource_file_path(&shanghai, resource_id, None)
+                .await
+                .unwrap();
+        assert_eq!(updated_at, expected);
+        let (_, _, _, updated_at, _) =
+            ResourceService::get_resource_file_path_for_preview(&shanghai, resource_id, None)
+                .await
+                .unwrap();
+        assert_eq!(updated_at, expected);
+
+        let (.., updated_at) = ResourceService::get_resource_file_path(&pool, resource_id, None)
+            .await
+            .unwrap();
+        assert_eq!(updated_at, expected + chrono::Duration::hours(8));
+    }
+
+    #[test]
+    fn test_callback_audit_outcome_with_hash() {
+        let (status, reason) = ResourceService::callback_audit_outcome(true, None, true);
+        assert_eq!(status, AuditStatus::Approved);
+        assert_eq!(reason, None);
+
+        let (status, reason) =
+            ResourceService::callback_audit_outcome(false, Some("内容违规".to_string()), true);
+        assert_eq!(status, AuditStatus::Pending);
+        assert_eq!(reason.as_deref(), Some("内容违规"));
+    }
+
+    #[test]
+    fn test_callback_audit_outcome_without_hash_goes_to_review() {
+        let (status, reason) = ResourceService::callback_audit_outcome(true, None, false);
+        assert_eq!(status, AuditStatus::Pending);
+        assert!(reason.unwrap().contains("文件哈希计算失败"));
+
+        let (status, reason) =
+            ResourceService::callback_audit_outcome(false, Some("内容违规".to_string()), false);
+        assert_eq!(status, AuditStatus::Pending);
+        let reason = reason.unwrap();
+        assert!(reason.starts_with("内容违规"));
+        assert!(reason.contains("文件哈希计算失败"));
+    }
+}
@@ -0,0 +1,506 @@
+use std::sync::Arc;
+
+use chrono::NaiveDateTime;
+use similar::{ChangeTag, TextDiff};
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::config::Config;
+use crate::models::{
+    CurrentUser, DiffHunk, DiffLine, DiffLineKind, Resource, ResourceVersion,
+    ResourceVersionDiffQuery, ResourceVersionDiffResponse, ResourceVersionListResponse,
+    ResourceVersionResponse, RollbackResourceVersionResponse, UserRole,
+};
+use crate::services::{
+    storage_for_type, FileService, ModerationProvider, ResourceError, ResourceService,
+    StorageBackend,
+};
+
+/// 差异块中变更行前后保留的上下文行数
+const DIFF_CONTEXT_LINES: usize = 3;
+
+/// 版本查询结果（包含编辑者用户名）
+#[derive(Debug, sqlx::FromRow)]
+struct ResourceVersionRow {
+    #[sqlx(flatten)]
+    version: ResourceVersion,
+    editor_name: Option<String>,
+}
+
+pub struct ResourceVersionService;
+
+impl ResourceVersionService {
+    /// 获取资源的版本列表（仅上传者或管理员）
+    pub async fn list_versions(
+        pool: &PgPool,
+        user: &CurrentUser,
+        resource_id: Uuid,
+    ) -> Result<ResourceVersionListResponse, ResourceError> {
+        let resource = Self::get_editable_resource(pool, user, resource_id).await?;
+
+        let rows = sqlx::query_as::<_, ResourceVersionRow>(
+            r#"
+            SELECT v.*, u.username AS editor_name
+            FROM resource_versions v
+            LEFT JOIN users u ON v.editor_id = u.id
+            WHERE v.resource_id = $1
+            ORDER BY v.version_number DESC
+            "#,
+        )
+        .bind(resource_id)
+        .fetch_all(pool)
+        .await?;
+
+        let current_version = rows
+            .first()
+            .filter(|row| row.version.file_hash == resource.file_hash)
+            .map(|row| row.version.version_number);
+
+        let versions = rows
+            .into_iter()
+            .map(|row| ResourceVersionResponse {
+                id: row.version.id,
+                version_number: row.version.version_number,
+                file_hash: row.version.file_hash,
+                file_size: row.version.file_size,
+                editor_id: row.version.editor_id,
+                editor_name: row.editor_name,
+                rollback_from: row.version.rollback_from,
+                is_current: Some(row.version.version_number) == current_version,
+                created_at: row
+                    .version
+                    .created_at
+                    .format("%Y-%m-%dT%H:%M:%S%.3fZ")
+                    .to_string(),
+            })
+            .collect();
+
+        Ok(ResourceVersionListResponse {
+            resource_id,
+            current_version,
+            versions,
+        })
+    }
+
+    /// 获取指定版本（用于下载，仅上传者或管理员）
+    /// 返回：(版本信息, 资源标题)
+    pub async fn get_version_for_download(
+        pool: &PgPool,
+        user: &CurrentUser,
+        resource_id: Uuid,
+        version_number: i32,
+    ) -> Result<(ResourceVersion, String), ResourceError> {
+        let resource = Self::get_editable_resource(pool, user, resource_id).await?;
+        let version = Self::get_version(pool, resource_id, version_number).await?;
+        Ok((version, resource.title))
+    }
+
+    /// 对比两个版本的 Markdown 内容（仅上传者或管理员）
+    pub async fn diff_versions(
+        pool: &PgPool,
+        storage: &Arc<dyn StorageBackend>,
+        user: &CurrentUser,
+        resource_id: Uuid,
+        query: &ResourceVersionDiffQuery,
+    ) -> Result<ResourceVersionDiffResponse, ResourceError> {
+        query.validate().map_err(ResourceError::ValidationError)?;
+        Self::get_editable_resource(pool, user, resource_id).await?;
+
+        let to_number = match query.to {
+            Some(to) => to,
+            None => sqlx::query_scalar::<_, Option<i32>>(
+                "SELECT MAX(version_number) FROM resource_versions WHERE resource_id = $1",
+            )
+            .bind(resource_id)
+            .fetch_one(pool)
+            .await?
+            .ok_or_else(|| ResourceError::NotFound("该资源暂无历史版本".to_string()))?,
+        };
+        if to_number == query.from {
+            return Err(ResourceError::ValidationError(
+                "不能与同一版本进行对比".to_string(),
+            ));
+        }
+
+        let from_version = Self::get_version(pool, resource_id, query.from).await?;
+        let to_version = Self::get_version(pool, resource_id, to_number).await?;
+
+        let old_content = Self::read_version_content(storage, &from_version).await?;
+        let new_content = Self::read_version_content(storage, &to_version).await?;
+
+        let (hunks, additions, deletions) = Self::diff_markdown(&old_content, &new_content);
+
+        Ok(ResourceVersionDiffResponse {
+            resource_id,
+            from_version: from_version.version_number,
+            to_version: to_version.version_number,
+            additions,
+            deletions,
+            hunks,
+        })
+    }
+
+    /// 回滚到指定版本（仅上传者或管理员）
+    ///
+    /// 回滚会以该版本的内容重新走一次在线编辑流程（包括 AI 审核），并生成一个新版本
+    pub async fn rollback_to_version(
+        pool: &PgPool,
+        storage: &Arc<dyn StorageBackend>,
+        moderation: &Arc<dyn ModerationProvider>,
+        user: &CurrentUser,
+        resource_id: Uuid,
+        version_number: i32,
+    ) -> Result<RollbackResourceVersionResponse, ResourceError> {
+        let resource = Self::get_editable_resource(pool, user, resource_id).await?;
+        let version = Self::get_version(pool, resource_id, version_number).await?;
+
+        if version.file_hash.is_some() && version.file_hash == resource.file_hash {
+            return Err(ResourceError::ValidationError(
+                "该版本内容与当前内容一致，无需回滚".to_string(),
+            ));
+        }
+
+        let content = Self::read_version_content(storage, &version).await?;
+
+        let response = ResourceService::save_resource_content(
+            pool,
+            user,
+            storage,
+            moderation,
+            resource_id,
+            content,
+            Some(version_number),
+        )
+        .await?;
+
+        Ok(RollbackResourceVersionResponse {
+            id: resource_id,
+            version_number: response.version_number,
+            rolled_back_from: version_number,
+            updated_at: response.updated_at,
+        })
+    }
+
+    /// 确保资源存在首个版本（保存编辑前的原始内容）
+    ///
+    /// 资源首次被在线编辑前没有任何版本记录，此时将当前文件复制为版本 1，
+    /// 编辑者记为上传者，时间为资源最后更新时间
+    pub async fn ensure_initial_version(
+        pool: &PgPool,
+        storage: &Arc<dyn StorageBackend>,
+        resource: &Resource,
+    ) -> Result<(), ResourceError> {
+        let exists: bool = sqlx::query_scalar(
+            "SELECT EXISTS(SELECT 1 FROM resource_versions WHERE resource_id = $1)",
+        )
+        .bind(resource.id)
+        .fetch_one(pool)
+        .await?;
+
+        if exists {
+            return Ok(());
+        }
+
+        let backend = Self::resolve_storage(storage, resource.storage_type.as_deref())?;
+        let data = backend.read_file(&resource.file_path).await?;
+
+        Self::create_version(
+            pool,
+            storage,
+            resource.id,
+            resource.storage_type.as_deref(),
+            data,
+            Some(resource.uploader_id),
+            None,
+            Some(resource.updated_at),
+        )
+        .await?;
+
+        Ok(())
+    }
+
+    /// 保存一个新版本：将内容写入独立的版本文件并记录版本信息
+    /// 返回新版本序号
+    #[allow(clippy::too_many_arguments)]
+    pub async fn create_version(
+        pool: &PgPool,
+        storage: &Arc<dyn StorageBackend>,
+        resource_id: Uuid,
+        storage_type: Option<&str>,
+        data: Vec<u8>,
+        editor_id: Option<Uuid>,
+        rollback_from: Option<i32>,
+        created_at: Option<NaiveDateTime>,
+    ) -> Result<i32, ResourceError> {
+        let backend = Self::resolve_storage(storage, storage_type)?;
+        let storage_key = Self::version_storage_key(resource_id);
+        let file_hash = FileService::calculate_hash(&data);
+        let file_size = data.len() as i64;
+
+        backend
+            .write_file(&storage_key, data, Some("text/markdown"))
+            .await?;
+
+        let result = sqlx::query_scalar::<_, i32>(
+            r#"
+            INSERT INTO resource_versions (
+                resource_id, version_number, storage_key, storage_type,
+                file_hash, file_size, editor_id, rollback_from, created_at
+            )
+            SELECT $1, COALESCE(MAX(version_number), 0) + 1, $2, $3, $4, $5, $6, $7,
+                   COALESCE($8, CURRENT_TIMESTAMP)
+            FROM resource_versions
+            WHERE resource_id = $1
+            RETURNING version_number
+            "#,
+        )
+        .bind(resource_id)
+        .bind(&storage_key)
+        .bind(storage_type.unwrap_or("local"))
+        .bind(&file_hash)
+        .bind(file_size)
+        .bind(editor_id)
+        .bind(rollback_from)
+        .bind(created_at)
+        .fetch_one(pool)
+        .await;
+
+        match result {
+            Ok(version_number) => Ok(version_number),
+            Err(e) => {
+                // 记录写入失败时清理已写入的版本文件
+                if let Err(cleanup_err) = backend.delete_file(&storage_key).await {
+                    log::warn!(
+                        "[ResourceVersion] 清理版本文件失败 | key={}, error={}",
+                        storage_key,
+                        cleanup_err
+                    );
+                }
+                Err(e.into())
+            }
+        }
+    }
+
+    /// 删除资源的所有版本文件（资源删除时调用，数据库记录随资源级联删除）
+    pub async fn delete_version_files(
+        pool: &PgPool,
+        storage: &Arc<dyn StorageBackend>,
+        resource_id: Uuid,
+    ) -> Result<(), ResourceError> {
+        let versions = sqlx::query_as::<_, ResourceVersion>(
+            "SELECT * FROM resource_versions WHERE resource_id = $1",
+        )
+        .bind(resource_id)
+        .fetch_all(pool)
+        .await?;
+
+        for version in versions {
+            let backend = match Self::resolve_storage(storage, version.storage_type.as_deref()) {
+                Ok(backend) => backend,
+                Err(e) => {
+                    log::warn!(
+                        "[ResourceVersion] 无法访问版本文件存储 | version_id={}, error={}",
+                        version.id,
+                        e
+                    );
+                    continue;
+                }
+            };
+            if let Err(e) = backend.delete_file(&version.storage_key).await {
+                log::warn!(
+                    "[ResourceVersion] 删除版本文件失败 | version_id={}, key={}, error={}",
+                    version.id,
+                    version.storage_key,
+                    e
+                );
+            }
+        }
+
+        Ok(())
+    }
+
+    /// 根据存储类型选择存储后端（当前后端与存储类型不一致时创建对应的后端）
+    pub fn resolve_storage(
+        storage: &Arc<dyn StorageBackend>,
+        storage_type: Option<&str>,
+    ) -> Result<Arc<dyn StorageBackend>, ResourceError> {
+        let config = Config::from_env();
+        storage_for_type(storage, &config, storage_type)
+            .map_err(|e| ResourceError::FileError(format!("无法访问存储: {}", e)))
+    }
+
+    /// 计算两段 Markdown 文本的按行差异
+    /// 返回：(差异块, 新增行数, 删除行数)
+    pub fn diff_markdown(old: &str, new: &str) -> (Vec<DiffHunk>, usize, usize) {
+        let diff = TextDiff::from_lines(old, new);
+        let mut additions = 0;
+        let mut deletions = 0;
+        let mut hunks = Vec::new();
+
+        for group in diff.grouped_ops(DIFF_CONTEXT_LINES) {
+            let (Some(first), Some(last)) = (group.first(), group.last()) else {
+                continue;
+            };
+            let old_range = first.old_range().start..last.old_range().end;
+            let new_range = first.new_range().start..last.new_range().end;
+
+            let mut lines = Vec::new();
+            for op in &group {
+                for change in diff.iter_changes(op) {
+                    let kind = match change.tag() {
+                        ChangeTag::Equal => DiffLineKind::Equal,
+                        ChangeTag::Insert => {
+                            additions += 1;
+                            DiffLineKind::Insert
+                        }
+                        ChangeTag::Delete => {
+                            deletions += 1;
+                            DiffLineKind::Delete
+                        }
+                    };
+                    lines.push(DiffLine {
+                        kind,
+                        old_line: change.old_index().map(|i| i + 1),
+                        new_line: change.new_index().map(|i| i + 1),
+                        content: change
+                            .value()
+                            .trim_end_matches('\n')
+                            .trim_end_matches('\r')
+                            .to_string(),
+                    });
+                }
+            }
+
+            hunks.push(DiffHunk {
+                old_start: old_range.start + 1,
+                old_lines: old_range.len(),
+                new_start: new_range.start + 1,
+                new_lines: new_range.len(),
+                lines,
+            });
+        }
+
+        (hunks, additions, deletions)
+    }
+
+    /// 获取资源并检查编辑权限（上传者或管理员）
+    async fn get_editable_resource(
+        pool: &PgPool,
+        user: &CurrentUser,
+        resource_id: Uuid,
+    ) -> Result<Resource, ResourceError> {
+        let resource = sqlx::query_as::<_, Resource>("SELECT * FROM resources WHERE id = $1")
+            .bind(resource_id)
+            .fetch_optional(pool)
+            .await?
+            .ok_or_else(|| ResourceError::NotFound(format!("资源 {} 不存在", resource_id)))?;
+
+        if resource.uploader_id != user.id && user.role != UserRole::Admin {
+            return Err(ResourceError::Unauthorized(
+                "没有权限操作此资源的历史版本".to_string(),
+            ));
+        }
+
+        Ok(resource)
+    }
+
+    async fn get_version(
+        pool: &PgPool,
+        resource_id: Uuid,
+        version_number: i32,
+    ) -> Result<ResourceVersion, ResourceError> {
+        sqlx::query_as::<_, ResourceVersion>(
+            "SELECT * FROM resource_versions WHERE resource_id = $1 AND version_number = $2",
+        )
+        .bind(resource_id)
+        .bind(version_number)
+        .fetch_optional(pool)
+        .await?
+        .ok_or_else(|| ResourceError::NotFound(format!("版本 {} 不存在", version_number)))
+    }
+
+    async fn read_version_content(
+        storage: &Arc<dyn StorageBackend>,
+        version: &ResourceVersion,
+    ) -> Result<String, ResourceError> {
+        let backend = Self::resolve_storage(storage, version.storage_type.as_deref())?;
+        let data = backend.read_file(&version.storage_key).await?;
+        String::from_utf8(data)
+            .map_err(|_| ResourceError::FileError("版本文件不是有效的 UTF-8 文本".to_string()))
+    }
+
+    fn version_storage_key(resource_id: Uuid) -> String {
+        format!("resources/versions/{}/{}.md", resource_id, Uuid::new_v4())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_diff_markdown_identical() {
+        let (hunks, additions, deletions) =
+            ResourceVersionService::diff_markdown("# 标题\n内容\n", "# 标题\n内容\n");
+        assert!(hunks.is_empty());
+        assert_eq!(additions, 0);
+        assert_eq!(deletions, 0);
+    }
+
+    #[test]
+    fn test_diff_markdown_changes() {
+        let old = "# 高数笔记\n第一章\n极限\n";
+        let new = "# 高数笔记\n第一章\n极限与连续\n第二章\n";
+        let (hunks, additions, deletions) = ResourceVersionService::diff_markdown(old, new);
+
+        assert_eq!(hunks.len(), 1);
+        assert_eq!(additions, 2);
+        assert_eq!(deletions, 1);
+
+        let hunk = &hunks[0];
+        assert_eq!(hunk.old_start, 1);
+        assert_eq!(hunk.old_lines, 3);
+        assert_eq!(hunk.new_start, 1);
+        assert_eq!(hunk.new_lines, 4);
+
+        let deleted: Vec<_> = hunk
+            .lines
+            .iter()
+            .filter(|l| l.kind == DiffLineKind::Delete)
+            .collect();
+        assert_eq!(deleted.len(), 1);
+        assert_eq!(deleted[0].content, "极限");
+        assert_eq!(deleted[0].old_line, Some(3));
+        assert_eq!(deleted[0].new_line, None);
+    }
+
+    #[test]
+    fn test_diff_markdown_context_split() {
+        let old: String = (1..=20).map(|i| format!("第{}行\n", i)).collect();
+        let new = old
+            .replace("第2行\n", "第二行\n")
+            .replace("第19行\n", "第十九行\n");
+        let (hunks, additions, deletions) = ResourceVersionService::diff_markdown(&old, &new);
+
+        // 两处修改相距较远，应拆分为两个差异块
+        assert_eq!(hunks.len(), 2);
+        assert_eq!(additions, 2);
+        assert_eq!(deletions, 2);
+        assert!(hunks[0]
+            .lines
+            .iter()
+            .all(|l| l.old_line.unwrap_or(0) <= 2 + DIFF_CONTEXT_LINES));
+    }
+
+    #[test]
+    fn test_version_storage_key() {
+        let resource_id = Uuid::new_v4();
+        let key = ResourceVersionService::version_storage_key(resource_id);
+        assert!(key.starts_with(&format!("resources/versions/{}/", resource_id)));
+        assert!(key.ends_with(".md"));
+        assert_ne!(
+            key,
+            ResourceVersionService::version_storage_key(resource_id)
+        );
+    }
+}
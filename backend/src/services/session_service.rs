@@ -0,0 +1,372 @@
+use sha2::{Digest, Sha256};
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::models::{SessionClientInfo, SessionRevokeReason, SessionTokenState, UserSession};
+use crate::utils::REFRESH_TOKEN_EXPIRE_DAYS;
+
+/// 已轮换的 Refresh Token 在该时间内重复使用视为并发刷新（如多个标签页同时刷新），不按重放处理
+const ROTATION_GRACE_SECONDS: i64 = 30;
+
+/// Refresh Token 校验结果
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RefreshTokenCheck {
+    /// 会话当前的 Refresh Token
+    Valid,
+    /// 会话已吊销或已过期
+    Inactive,
+    /// 刚刚被轮换掉的 Token，拒绝但不吊销会话
+    RecentlyRotated,
+    /// 已轮换的旧 Token 被重复使用，可能已泄露
+    Reused,
+}
+
+/// 判断 Refresh Token 是否可用于刷新
+pub fn check_refresh_token(state: &SessionTokenState, token_hash: &str) -> RefreshTokenCheck {
+    if !state.is_active {
+        return RefreshTokenCheck::Inactive;
+    }
+    if state.refresh_token_hash == token_hash {
+        return RefreshTokenCheck::Valid;
+    }
+    // 签名有效但哈希不匹配，说明是同一会话此前签发、已被轮换的 Token
+    if state.previous_token_hash.as_deref() == Some(token_hash)
+        && state.seconds_since_rotation < ROTATION_GRACE_SECONDS
+    {
+        return RefreshTokenCheck::RecentlyRotated;
+    }
+    RefreshTokenCheck::Reused
+}
+
+/// 登录会话服务
+pub struct SessionService;
+
+impl SessionService {
+    /// 计算 Refresh Token 的哈希，数据库中只保存哈希
+    pub fn hash_refresh_token(token: &str) -> String {
+        format!("{:x}", Sha256::digest(token.as_bytes()))
+    }
+
+    /// 创建登录会话，`mfa_verified` 表示登录时是否完成了两步验证
+    pub async fn create_session(
+        pool: &PgPool,
+        session_id: Uuid,
+        user_id: Uuid,
+        token_hash: &str,
+        client: &SessionClientInfo,
+        mfa_verified: bool,
+    ) -> Result<(), sqlx::Error> {
+        sqlx::query(
+            r#"
+            INSERT INTO user_sessions
+                (id, user_id, refresh_token_hash, user_agent, ip_address, last_used_at, expires_at,
+                 mfa_verified_at, created_at)
+            VALUES ($1, $2, $3, $4, $5, NOW(), NOW() + make_interval(days => $6),
+                    CASE WHEN $7 THEN NOW() END, NOW())
+            "#,
+        )
+        .bind(session_id)
+        .bind(user_id)
+        .bind(token_hash)
+        .bind(client.user_agent.as_deref().map(truncate_user_agent))
+        .bind(&client.ip_address)
+        .bind(REFRESH_TOKEN_EXPIRE_DAYS as i32)
+        .bind(mfa_verified)
+        .execute(pool)
+        .await?;
+
+        Ok(())
+    }
+
+    /// 轮换会话的 Refresh Token，并顺延会话有效期
+    ///
+    /// `expected_hash` 不为空时只有当前哈希与之相同才会轮换，避免并发刷新互相覆盖。
+    /// 返回 false 表示会话不存在、已失效或已被其他请求轮换。
+    pub async fn rotate_session(
+        pool: &PgPool,
+        session_id: Uuid,
+        user_id: Uuid,
+        expected_hash: Option<&str>,
+        new_hash: &str,
+        client: &SessionClientInfo,
+    ) -> Result<bool, sqlx::Error> {
+        let result = sqlx::query(
+            r#"
+            UPDATE user_sessions
+            SET previous_token_hash = refresh_token_hash,
+                refresh_token_hash = $3,
+                user_agent = COALESCE($4, user_agent),
+                ip_address = COALESCE($5, ip_address),
+                last_used_at = NOW(),
+                expires_at = NOW() + make_interval(days => $6)
+            WHERE id = $1 AND user_id = $2
+              AND revoked_at IS NULL AND expires_at > NOW()
+              AND ($7::VARCHAR IS NULL OR refresh_token_hash = $7)
+            "#,
+        )
+        .bind(session_id)
+        .bind(user_id)
+        .bind(new_hash)
+        .bind(client.user_agent.as_deref().map(truncate_user_agent))
+        .bind(&client.ip_address)
+        .bind(REFRESH_TOKEN_EXPIRE_DAYS as i32)
+        .bind(expected_hash)
+        .execute(pool)
+        .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// 获取刷新 Token 所需的会话状态
+    pub async fn get_token_state(
+        pool: &PgPool,
+        session_id: Uuid,
+    ) -> Result<Option<SessionTokenState>, sqlx::Error> {
+        sqlx::query_as::<_, SessionTokenState>(
+            r#"
+            SELECT user_id, refresh_token_hash, previous_token_hash,
+                   (revoked_at IS NULL AND expires_at > NOW()) AS is_active,
+                   EXTRACT(EPOCH FROM (NOW() - last_used_at))::BIGINT AS seconds_since_rotation
+            FROM user_sessions
+            WHERE id = $1
+            "#,
+        )
+        .bind(session_id)
+        .fetch_optional(pool)
+        .await
+    }
+
+    /// 校验 Access Token 对应的用户和会话是否仍然有效
+    ///
+    /// 用户被禁用、令牌版本已递增或会话已吊销时返回 false。
+    /// 旧版 Token 没有会话ID，只校验用户状态和令牌版本。
+    pub async fn is_access_valid(
+        pool: &PgPool,
+        user_id: Uuid,
+        session_id: Option<Uuid>,
+        token_version: i32,
+    ) -> Result<bool, sqlx::Error> {
+        let valid: Option<bool> = sqlx::query_scalar(
+            r#"
+            SELECT u.is_active = true AND u.token_version = $3
+                   AND ($2::UUID IS NULL OR EXISTS (
+                       SELECT 1 FROM user_sessions s
+                       WHERE s.id = $2 AND s.user_id = u.id
+                         AND s.revoked_at IS NULL AND s.expires_at > NOW()
+                   ))
+            FROM users u
+            WHERE u.id = $1
+            "#,
+        )
+        .bind(user_id)
+        .bind(session_id)
+        .bind(token_version)
+        .fetch_optional(pool)
+        .await?;
+
+        Ok(valid.unwrap_or(false))
+    }
+
+    /// 当前会话是否已完成两步验证
+    ///
+    /// 用户关闭两步验证后，之前通过验证的会话也视为未验证。
+    pub async fn is_mfa_verified(
+        pool: &PgPool,
+        user_id: Uuid,
+        session_id: Option<Uuid>,
+    ) -> Result<bool, sqlx::Error> {
+        let Some(session_id) = session_id else {
+            return Ok(false);
+        };
+        let verified: Option<bool> = sqlx::query_scalar(
+            r#"
+            SELECT u.totp_enabled_at IS NOT NULL AND EXISTS (
+                       SELECT 1 FROM user_sessions s
+                       WHERE s.id = $2 AND s.user_id = u.id AND s.mfa_verified_at IS NOT NULL
+                   )
+            FROM users u
+            WHERE u.id = $1
+            "#,
+        )
+        .bind(user_id)
+        .bind(session_id)
+        .fetch_optional(pool)
+        .await?;
+
+        Ok(verified.unwrap_or(false))
+    }
+
+    /// 将会话标记为已完成两步验证（用户在该会话中启用两步验证后调用）
+    pub async fn mark_mfa_verified(
+        pool: &PgPool,
+        user_id: Uuid,
+        session_id: Uuid,
+    ) -> Result<bool, sqlx::Error> {
+        let result = sqlx::query(
+            r#"
+            UPDATE user_sessions SET mfa_verified_at = NOW()
+            WHERE id = $1 AND user_id = $2 AND revoked_at IS NULL AND expires_at > NOW()
+            "#,
+        )
+        .bind(session_id)
+        .bind(user_id)
+        .execute(pool)
+        .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// 获取用户的有效会话列表
+    pub async fn get_active_sessions(
+        pool: &PgPool,
+        user_id: Uuid,
+    ) -> Result<Vec<UserSession>, sqlx::Error> {
+        sqlx::query_as::<_, UserSession>(
+            r#"
+            SELECT id, user_agent, ip_address, created_at, last_used_at, expires_at
+            FROM user_sessions
+            WHERE user_id = $1 AND revoked_at IS NULL AND expires_at > NOW()
+            ORDER BY last_used_at DESC
+            "#,
+        )
+        .bind(user_id)
+        .fetch_all(pool)
+        .await
+    }
+
+    /// 吊销用户的单个会话，返回 false 表示会话不存在或已失效
+    pub async fn revoke_session(
+        pool: &PgPool,
+        user_id: Uuid,
+        session_id: Uuid,
+        reason: SessionRevokeReason,
+    ) -> Result<bool, sqlx::Error> {
+        let result = sqlx::query(
+            r#"
+            UPDATE user_sessions
+            SET revoked_at = NOW(), revoke_reason = $3
+            WHERE id = $1 AND user_id = $2 AND revoked_at IS NULL AND expires_at > NOW()
+            "#,
+        )
+        .bind(session_id)
+        .bind(user_id)
+        .bind(reason.as_str())
+        .execute(pool)
+        .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// 吊销用户的全部会话，并递增令牌版本使已签发的 Access Token 立即失效
+    ///
+    /// 返回被吊销的会话数量
+    pub async fn revoke_all_sessions(
+        pool: &PgPool,
+        user_id: Uuid,
+        reason: SessionRevokeReason,
+    ) -> Result<u64, sqlx::Error> {
+        let mut tx = pool.begin().await?;
+
+        sqlx::query("UPDATE users SET token_version = token_version + 1 WHERE id = $1")
+            .bind(user_id)
+            .execute(&mut *tx)
+            .await?;
+
+        let result = sqlx::query(
+            r#"
+            UPDATE user_sessions
+            SET revoked_at = NOW(), revoke_reason = $2
+            WHERE user_id = $1 AND revoked_at IS NULL AND expires_at > NOW()
+            "#,
+        )
+        .bind(user_id)
+        .bind(reason.as_str())
+        .execute(&mut *tx)
+        .await?;
+
+        tx.commit().await?;
+        Ok(result.rows_affected())
+    }
+
+    /// 删除过期或吊销超过保留天数的会话记录
+    pub async fn delete_stale_sessions(
+        pool: &PgPool,
+        retention_days: i32,
+    ) -> Result<u64, sqlx::Error> {
+        let result = sqlx::query(
+            r#"
+            DELETE FROM user_sessions
+            WHERE expires_at < NOW() - make_interval(days => $1)
+               OR revoked_at < NOW() - make_interval(days => $1)
+            "#,
+        )
+        .bind(retention_days)
+        .execute(pool)
+        .await?;
+
+        Ok(result.rows_affected())
+    }
+}
+
+/// User-Agent 超过数据库列长度时截断
+fn truncate_user_agent(user_agent: &str) -> String {
+    user_agent.chars().take(500).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn state(current: &str, previous: Option<&str>, seconds: i64) -> SessionTokenState {
+        SessionTokenState {
+            user_id: Uuid::nil(),
+            refresh_token_hash: current.to_string(),
+            previous_token_hash: previous.map(|s| s.to_string()),
+            is_active: true,
+            seconds_since_rotation: seconds,
+        }
+    }
+
+    #[test]
+    fn test_check_current_token() {
+        let s = state("new", Some("old"), 5);
+        assert_eq!(check_refresh_token(&s, "new"), RefreshTokenCheck::Valid);
+    }
+
+    #[test]
+    fn test_check_inactive_session() {
+        let mut s = state("new", None, 5);
+        s.is_active = false;
+        assert_eq!(check_refresh_token(&s, "new"), RefreshTokenCheck::Inactive);
+    }
+
+    #[test]
+    fn test_check_rotated_token() {
+        // 刚轮换的旧 Token 视为并发刷新
+        let s = state("new", Some("old"), 5);
+        assert_eq!(
+            check_refresh_token(&s, "old"),
+            RefreshTokenCheck::RecentlyRotated
+        );
+
+        // 超过宽限期或更早的 Token 视为重放
+        let s = state("new", Some("old"), ROTATION_GRACE_SECONDS);
+        assert_eq!(check_refresh_token(&s, "old"), RefreshTokenCheck::Reused);
+        let s = state("new", Some("old"), 5);
+        assert_eq!(check_refresh_token(&s, "older"), RefreshTokenCheck::Reused);
+    }
+
+    #[test]
+    fn test_hash_refresh_token() {
+        let hash = SessionService::hash_refresh_token("token");
+        assert_eq!(hash.len(), 64);
+        assert_eq!(hash, SessionService::hash_refresh_token("token"));
+        assert_ne!(hash, SessionService::hash_refresh_token("token2"));
+    }
+
+    #[test]
+    fn test_truncate_user_agent() {
+        assert_eq!(truncate_user_agent("curl/8.0"), "curl/8.0");
+        assert_eq!(truncate_user_agent(&"浏".repeat(600)).chars().count(), 500);
+    }
+}
@@ -0,0 +1,541 @@
+use sqlx::PgPool;
+use std::path::Path;
+use std::sync::Arc;
+use uuid::Uuid;
+
+use crate::config::Config;
+use crate::models::{
+    CreateStorageMigrationRequest, StorageMigrationFailure, StorageMigrationItemKind,
+    StorageMigrationJob, StorageMigrationJobListResponse, StorageMigrationJobResponse,
+    StorageMigrationStatus,
+};
+use crate::services::{
+    storage_for_type, FileService, ResourceError, StorageBackend, StorageBackendType,
+};
+
+/// 任务记录中最多保留的失败明细条数
+pub const MAX_RECORDED_FAILURES: usize = 100;
+/// 迁移任务列表最多返回的条数
+const MAX_LIST_JOBS: i64 = 20;
+
+const STORAGE_MIGRATION_JOB_COLUMNS: &str = r#"
+    id, admin_id, source_type, target_type, include_images, delete_source, status,
+    total_items, processed_items, migrated_items, failed_items, bytes_copied, failures,
+    error_message, started_at, completed_at, created_at
+"#;
+
+/// 待迁移的单个文件
+#[derive(Debug, Clone)]
+pub struct StorageMigrationItem {
+    pub kind: StorageMigrationItemKind,
+    pub id: Uuid,
+    pub file_path: String,
+    /// 已记录的文件哈希，为空时从源存储计算
+    pub file_hash: Option<String>,
+    pub file_size: Option<i64>,
+    pub content_type: Option<String>,
+}
+
+/// 迁移进度
+#[derive(Debug, Clone, Default)]
+pub struct StorageMigrationProgress {
+    pub processed_items: usize,
+    pub migrated_items: usize,
+    pub failed_items: usize,
+    pub bytes_copied: u64,
+    pub failures: Vec<StorageMigrationFailure>,
+}
+
+impl StorageMigrationProgress {
+    pub fn record_success(&mut self, bytes: u64) {
+        self.processed_items += 1;
+        self.migrated_items += 1;
+        self.bytes_copied += bytes;
+    }
+
+    /// 记录失败，明细超过上限后只计数
+    pub fn record_failure(&mut self, item: &StorageMigrationItem, error: String) {
+        self.processed_items += 1;
+        self.failed_items += 1;
+        if self.failures.len() < MAX_RECORDED_FAILURES {
+            self.failures.push(StorageMigrationFailure {
+                kind: item.kind,
+                id: item.id,
+                file_path: item.file_path.clone(),
+                error,
+            });
+        }
+    }
+
+    fn failures_json(&self) -> serde_json::Value {
+        serde_json::to_value(&self.failures).unwrap_or_else(|_| serde_json::json!([]))
+    }
+}
+
+pub struct StorageMigrationService;
+
+impl StorageMigrationService {
+    /// 创建存储迁移任务
+    /// 校验源/目标存储均已配置，且同一时间只允许一个迁移任务
+    pub async fn create_job(
+        pool: &PgPool,
+        storage: &Arc<dyn StorageBackend>,
+        config: &Config,
+        admin_id: Uuid,
+        request: &CreateStorageMigrationRequest,
+    ) -> Result<StorageMigrationJob, ResourceError> {
+        let source_type = parse_storage_type(&request.source_type)?;
+        let target_type = parse_storage_type(&request.target_type)?;
+        if source_type == target_type {
+            return Err(ResourceError::ValidationError(
+                "源存储和目标存储不能相同".to_string(),
+            ));
+        }
+
+        for storage_type in [source_type, target_type] {
+            storage_for_type(storage, config, Some(storage_type.as_str()))
+                .map_err(|e| ResourceError::ValidationError(e.to_string()))?;
+        }
+
+        let active_jobs = sqlx::query_scalar::<_, i64>(
+            "SELECT COUNT(*) FROM storage_migration_jobs WHERE status IN ('pending', 'running')",
+        )
+        .fetch_one(pool)
+        .await?;
+        if active_jobs > 0 {
+            return Err(ResourceError::Conflict(
+                "已有进行中的存储迁移任务，请等待其完成".to_string(),
+            ));
+        }
+
+        let total_items = Self::collect_items(pool, source_type.as_str(), request.include_images)
+            .await?
+            .len();
+
+        let job = sqlx::query_as::<_, StorageMigrationJob>(&format!(
+            r#"
+            INSERT INTO storage_migration_jobs
+                (admin_id, source_type, target_type, include_images, delete_source, status, total_items)
+            VALUES ($1, $2, $3, $4, $5, $6, $7)
+            RETURNING {}
+            "#,
+            STORAGE_MIGRATION_JOB_COLUMNS
+        ))
+        .bind(admin_id)
+        .bind(source_type.as_str())
+        .bind(target_type.as_str())
+        .bind(request.include_images)
+        .bind(request.delete_source)
+        .bind(StorageMigrationStatus::Pending.as_str())
+        .bind(total_items as i32)
+        .fetch_one(pool)
+        .await?;
+
+        Ok(job)
+    }
+
+    /// 获取迁移任务详情
+    pub async fn get_job(
+        pool: &PgPool,
+        job_id: Uuid,
+    ) -> Result<StorageMigrationJobResponse, ResourceError> {
+        let job = sqlx::query_as::<_, StorageMigrationJob>(&format!(
+            "SELECT {} FROM storage_migration_jobs WHERE id = $1",
+            STORAGE_MIGRATION_JOB_COLUMNS
+        ))
+        .bind(job_id)
+        .fetch_optional(pool)
+        .await?
+        .ok_or_else(|| ResourceError::NotFound("迁移任务不存在".to_string()))?;
+
+        Ok(job.into())
+    }
+
+    /// 获取最近的迁移任务
+    pub async fn get_jobs(pool: &PgPool) -> Result<StorageMigrationJobListResponse, ResourceError> {
+        let jobs = sqlx::query_as::<_, StorageMigrationJob>(&format!(
+            "SELECT {} FROM storage_migration_jobs ORDER BY created_at DESC LIMIT $1",
+            STORAGE_MIGRATION_JOB_COLUMNS
+        ))
+        .bind(MAX_LIST_JOBS)
+        .fetch_all(pool)
+        .await?;
+
+        Ok(StorageMigrationJobListResponse {
+            jobs: jobs.into_iter().map(Into::into).collect(),
+        })
+    }
+
+    /// 查询源存储中待迁移的文件：资源当前文件、资源历史版本，以及可选的图片
+    pub async fn collect_items(
+        pool: &PgPool,
+        source_type: &str,
+        include_images: bool,
+    ) -> Result<Vec<StorageMigrationItem>, ResourceError> {
+        let mut items = Vec::new();
+
+        let resources = sqlx::query_as::<_, (Uuid, String, Option<String>, Option<i64>, String)>(
+            r#"
+            SELECT id, file_path, file_hash, file_size, resource_type
+            FROM resources
+            WHERE COALESCE(storage_type, 'local') = $1 AND file_path <> ''
+            ORDER BY created_at
+            "#,
+        )
+        .bind(source_type)
+        .fetch_all(pool)
+        .await?;
+        items.extend(resources.into_iter().map(
+            |(id, file_path, file_hash, file_size, resource_type)| StorageMigrationItem {
+                kind: StorageMigrationItemKind::Resource,
+                id,
+                file_path,
+                file_hash,
+                file_size,
+                content_type: Some(FileService::get_mime_type_by_type(&resource_type)),
+            },
+        ));
+
+        let versions = sqlx::query_as::<_, (Uuid, String, Option<String>, Option<i64>, String)>(
+            r#"
+            SELECT v.id, v.storage_key, v.file_hash, v.file_size, r.resource_type
+            FROM resource_versions v
+            JOIN resources r ON r.id = v.resource_id
+            WHERE COALESCE(v.storage_type, 'local') = $1 AND v.storage_key <> ''
+            ORDER BY v.created_at
+            "#,
+        )
+        .bind(source_type)
+        .fetch_all(pool)
+        .await?;
+        items.extend(versions.into_iter().map(
+            |(id, file_path, file_hash, file_size, resource_type)| StorageMigrationItem {
+                kind: StorageMigrationItemKind::ResourceVersion,
+                id,
+                file_path,
+                file_hash,
+                file_size,
+                content_type: Some(FileService::get_mime_type_by_type(&resource_type)),
+            },
+        ));
+
+        if include_images {
+            let images = sqlx::query_as::<_, (Uuid, String, Option<i32>, Option<String>)>(
+                r#"
+                SELECT id, file_path, file_size, mime_type
+                FROM images
+                WHERE COALESCE(storage_type, 'local') = $1 AND file_path <> ''
+                ORDER BY created_at
+                "#,
+            )
+            .bind(source_type)
+            .fetch_all(pool)
+            .await?;
+            items.extend(
+                images
+                    .into_iter()
+                    .map(
+                        |(id, file_path, file_size, mime_type)| StorageMigrationItem {
+                            kind: StorageMigrationItemKind::Image,
+                            id,
+                            file_path,
+                            file_hash: None,
+                            file_size: file_size.map(i64::from),
+                            content_type: mime_type,
+                        },
+                    ),
+            );
+        }
+
+        Ok(items)
+    }
+
+    /// 将文件记录切换到目标存储
+    /// 仅当记录仍指向源文件（且资源哈希未变化）时更新，返回是否更新成功
+    pub async fn switch_storage(
+        pool: &PgPool,
+        item: &StorageMigrationItem,
+        source_type: &str,
+        target_type: &str,
+        new_path: &str,
+        verified_hash: &str,
+    ) -> Result<bool, ResourceError> {
+        let result = match item.kind {
+            StorageMigrationItemKind::Resource => {
+                sqlx::query(
+                    r#"
+                    UPDATE resources SET file_path = $2, storage_type = $3
+                    WHERE id = $1 AND file_path = $4 AND COALESCE(storage_type, 'local') = $5
+                        AND file_hash = $6
+                    "#,
+                )
+                .bind(item.id)
+                .bind(new_path)
+                .bind(target_type)
+                .bind(&item.file_path)
+                .bind(source_type)
+                .bind(verified_hash)
+                .execute(pool)
+                .await?
+            }
+            StorageMigrationItemKind::ResourceVersion => {
+                sqlx::query(
+                    r#"
+                    UPDATE resource_versions
+                    SET storage_key = $2, storage_type = $3, file_hash = COALESCE(file_hash, $6)
+                    WHERE id = $1 AND storage_key = $4 AND COALESCE(storage_type, 'local') = $5
+                    "#,
+                )
+                .bind(item.id)
+                .bind(new_path)
+                .bind(target_type)
+                .bind(&item.file_path)
+                .bind(source_type)
+                .bind(verified_hash)
+                .execute(pool)
+                .await?
+            }
+            StorageMigrationItemKind::Image => {
+                sqlx::query(
+                    r#"
+                    UPDATE images SET file_path = $2, storage_type = $3
+                    WHERE id = $1 AND file_path = $4 AND COALESCE(storage_type, 'local') = $5
+                    "#,
+                )
+                .bind(item.id)
+                .bind(new_path)
+                .bind(target_type)
+                .bind(&item.file_path)
+                .bind(source_type)
+                .execute(pool)
+                .await?
+            }
+        };
+
+        Ok(result.rows_affected() == 1)
+    }
+
+    /// 标记任务开始执行，并以实际扫描到的文件数更新总数
+    pub async fn mark_running(
+        pool: &PgPool,
+        job_id: Uuid,
+        total_items: usize,
+    ) -> Result<(), ResourceError> {
+        sqlx::query(
+            r#"
+            UPDATE storage_migration_jobs
+            SET status = $2, total_items = $3, started_at = CURRENT_TIMESTAMP,
+                updated_at = CURRENT_TIMESTAMP
+            WHERE id = $1
+            "#,
+        )
+        .bind(job_id)
+        .bind(StorageMigrationStatus::Running.as_str())
+        .bind(total_items as i32)
+        .execute(pool)
+        .await?;
+
+        Ok(())
+    }
+
+    /// 更新任务进度
+    pub async fn update_progress(
+        pool: &PgPool,
+        job_id: Uuid,
+        progress: &StorageMigrationProgress,
+    ) -> Result<(), ResourceError> {
+        sqlx::query(
+            r#"
+            UPDATE storage_migration_jobs
+            SET processed_items = $2, migrated_items = $3, failed_items = $4,
+                bytes_copied = $5, failures = $6, updated_at = CURRENT_TIMESTAMP
+            WHERE id = $1
+            "#,
+        )
+        .bind(job_id)
+        .bind(progress.processed_items as i32)
+        .bind(progress.migrated_items as i32)
+        .bind(progress.failed_items as i32)
+        .bind(progress.bytes_copied as i64)
+        .bind(progress.failures_json())
+        .execute(pool)
+        .await?;
+
+        Ok(())
+    }
+
+    /// 标记任务完成
+    pub async fn mark_completed(
+        pool: &PgPool,
+        job_id: Uuid,
+        progress: &StorageMigrationProgress,
+    ) -> Result<(), ResourceError> {
+        Self::update_progress(pool, job_id, progress).await?;
+        sqlx::query(
+            r#"
+            UPDATE storage_migration_jobs
+            SET status = $2, completed_at = CURRENT_TIMESTAMP, updated_at = CURRENT_TIMESTAMP
+            WHERE id = $1
+            "#,
+        )
+        .bind(job_id)
+        .bind(StorageMigrationStatus::Completed.as_str())
+        .execute(pool)
+        .await?;
+
+        Ok(())
+    }
+
+    /// 标记任务失败
+    pub async fn mark_failed(
+        pool: &PgPool,
+        job_id: Uuid,
+        error_message: &str,
+    ) -> Result<(), ResourceError> {
+        sqlx::query(
+            r#"
+            UPDATE storage_migration_jobs
+            SET status = $2, error_message = $3, completed_at = CURRENT_TIMESTAMP,
+                updated_at = CURRENT_TIMESTAMP
+            WHERE id = $1
+            "#,
+        )
+        .bind(job_id)
+        .bind(StorageMigrationStatus::Failed.as_str())
+        .bind(error_message)
+        .execute(pool)
+        .await?;
+
+        Ok(())
+    }
+
+    /// 将未完成的任务标记为失败（服务重启后任务无法继续执行）
+    /// 已迁移的文件记录已切换到目标存储，重新创建任务即可继续迁移剩余文件
+    pub async fn fail_interrupted_jobs(pool: &PgPool) -> Result<u64, ResourceError> {
+        let result = sqlx::query(
+            r#"
+            UPDATE storage_migration_jobs
+            SET status = 'failed', error_message = '服务重启，任务中断',
+                updated_at = CURRENT_TIMESTAMP
+            WHERE status IN ('pending', 'running')
+            "#,
+        )
+        .execute(pool)
+        .await?;
+
+        Ok(result.rows_affected())
+    }
+}
+
+fn parse_storage_type(value: &str) -> Result<StorageBackendType, ResourceError> {
+    StorageBackendType::from_str(value.trim())
+        .ok_or_else(|| ResourceError::ValidationError(format!("不支持的存储类型: {}", value)))
+}
+
+/// 计算文件在目标存储中使用的 key
+/// 本地存储记录的是完整路径，需要去掉上传目录前缀；对象存储的 key 直接沿用
+pub fn migration_key(file_path: &str, source_type: StorageBackendType, local_base: &str) -> String {
+    let file_path = file_path.trim();
+    if source_type == StorageBackendType::Local {
+        let base = Path::new(local_base);
+        let mut bases = vec![local_base.trim_end_matches('/').to_string()];
+        if let Ok(canonical) = base.canonicalize() {
+            bases.push(canonical.to_string_lossy().to_string());
+        }
+        if let Ok(current_dir) = std::env::current_dir() {
+            bases.push(
+                current_dir
+                    .join(local_base.trim_start_matches("./"))
+                    .to_string_lossy()
+                    .to_string(),
+            );
+        }
+
+        for base in bases.iter().filter(|base| !base.is_empty()) {
+            if let Some(rest) = file_path.strip_prefix(base.as_str()) {
+                if rest.starts_with('/') {
+                    return rest.trim_start_matches('/').to_string();
+                }
+            }
+        }
+    }
+
+    file_path
+        .trim_start_matches("./")
+        .trim_start_matches('/')
+        .to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn item() -> StorageMigrationItem {
+        StorageMigrationItem {
+            kind: StorageMigrationItemKind::Image,
+            id: Uuid::nil(),
+            file_path: "images/a.png".to_string(),
+            file_hash: None,
+            file_size: Some(10),
+            content_type: None,
+        }
+    }
+
+    #[test]
+    fn test_migration_key_from_local_path() {
+        let local = StorageBackendType::Local;
+        assert_eq!(
+            migration_key("/data/uploads/resources/a.pdf", local, "/data/uploads"),
+            "resources/a.pdf"
+        );
+        assert_eq!(
+            migration_key("./uploads/images/b.png", local, "./uploads"),
+            "images/b.png"
+        );
+        // 已经是相对 key（如历史版本文件）时直接使用
+        assert_eq!(
+            migration_key("resources/versions/x/1.md", local, "/data/uploads"),
+            "resources/versions/x/1.md"
+        );
+        // 只按完整目录名匹配前缀
+        assert_eq!(
+            migration_key("/data/uploads2/a.pdf", local, "/data/uploads"),
+            "data/uploads2/a.pdf"
+        );
+    }
+
+    #[test]
+    fn test_migration_key_from_object_storage() {
+        assert_eq!(
+            migration_key("resources/a.pdf", StorageBackendType::Oss, "/data/uploads"),
+            "resources/a.pdf"
+        );
+    }
+
+    #[test]
+    fn test_progress_caps_failures() {
+        let mut progress = StorageMigrationProgress::default();
+        progress.record_success(10);
+        for _ in 0..MAX_RECORDED_FAILURES + 4 {
+            progress.record_failure(&item(), "读取失败".to_string());
+        }
+        assert_eq!(progress.processed_items, MAX_RECORDED_FAILURES + 5);
+        assert_eq!(progress.migrated_items, 1);
+        assert_eq!(progress.failed_items, MAX_RECORDED_FAILURES + 4);
+        assert_eq!(progress.bytes_copied, 10);
+        assert_eq!(progress.failures.len(), MAX_RECORDED_FAILURES);
+        assert_eq!(progress.failures_json()[0]["kind"], "image");
+    }
+
+    #[test]
+    fn test_parse_storage_type() {
+        assert_eq!(
+            parse_storage_type(" oss ").unwrap(),
+            StorageBackendType::Oss
+        );
+        assert!(matches!(
+            parse_storage_type("ftp"),
+            Err(ResourceError::ValidationError(_))
+        ));
+    }
+}
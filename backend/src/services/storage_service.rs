@@ -1,20 +1,31 @@
 use std::future::Future;
+use std::io::SeekFrom;
 use std::path::{Path, PathBuf};
 use std::pin::Pin;
 use std::sync::Arc;
 
+use futures_util::{Stream, StreamExt};
 use tokio::fs;
+use tokio::io::{AsyncReadExt, AsyncSeekExt, AsyncWriteExt};
 
 use crate::config::Config;
 
 use super::oss_service::OssStorage;
+use super::s3_service::S3Storage;
 
 pub type StorageFuture<'a, T> = Pin<Box<dyn Future<Output = Result<T, StorageError>> + Send + 'a>>;
 
+/// 存储读写使用的字节流
+pub type StorageByteStream = Pin<Box<dyn Stream<Item = Result<Vec<u8>, StorageError>> + Send>>;
+
+/// 流式读取每次返回的数据块大小 (64KB)
+pub const STORAGE_STREAM_CHUNK_SIZE: usize = 64 * 1024;
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum StorageBackendType {
     Local,
     Oss,
+    S3,
 }
 
 impl StorageBackendType {
@@ -22,8 +33,23 @@ impl StorageBackendType {
         match self {
             Self::Local => "local",
             Self::Oss => "oss",
+            Self::S3 => "s3",
         }
     }
+
+    pub fn from_str(s: &str) -> Option<Self> {
+        match s {
+            "local" => Some(Self::Local),
+            "oss" => Some(Self::Oss),
+            "s3" => Some(Self::S3),
+            _ => None,
+        }
+    }
+
+    /// 是否为对象存储（文件不在本机，可以生成签名直链）
+    pub fn is_object_storage(&self) -> bool {
+        !matches!(self, Self::Local)
+    }
 }
 
 #[derive(Debug)]
@@ -33,6 +59,8 @@ pub enum StorageError {
     NotFound(String),
     Io(String),
     Backend(String),
+    /// 请求的字节范围无法满足，携带文件总大小
+    RangeNotSatisfiable(u64),
 }
 
 impl std::fmt::Display for StorageError {
@@ -43,6 +71,9 @@ impl std::fmt::Display for StorageError {
             StorageError::NotFound(msg) => write!(f, "未找到: {}", msg),
             StorageError::Io(msg) => write!(f, "IO 错误: {}", msg),
             StorageError::Backend(msg) => write!(f, "后端错误: {}", msg),
+            StorageError::RangeNotSatisfiable(total) => {
+                write!(f, "请求范围无效，文件大小为 {} 字节", total)
+            }
         }
     }
 }
@@ -57,6 +88,59 @@ pub struct StorageFileMetadata {
     pub etag: Option<String>,
 }
 
+/// 字节范围（闭区间，语义与 HTTP Range 一致）
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ByteRange {
+    /// 从 start 开始读取到 end（含），end 为 None 时读到文件末尾
+    FromTo(u64, Option<u64>),
+    /// 文件末尾的 n 个字节
+    Suffix(u64),
+}
+
+impl ByteRange {
+    /// 根据文件总大小计算实际读取的区间 (start, end)，end 包含在内
+    pub fn resolve(&self, total_size: u64) -> Result<(u64, u64), StorageError> {
+        if total_size == 0 {
+            return Err(StorageError::RangeNotSatisfiable(total_size));
+        }
+        match *self {
+            ByteRange::FromTo(start, end) => {
+                let end = end.unwrap_or(total_size - 1).min(total_size - 1);
+                if start >= total_size || start > end {
+                    return Err(StorageError::RangeNotSatisfiable(total_size));
+                }
+                Ok((start, end))
+            }
+            ByteRange::Suffix(length) => {
+                if length == 0 {
+                    return Err(StorageError::RangeNotSatisfiable(total_size));
+                }
+                Ok((total_size.saturating_sub(length), total_size - 1))
+            }
+        }
+    }
+
+    /// 转换为 HTTP Range 请求头的值
+    pub fn to_header_value(self) -> String {
+        match self {
+            ByteRange::FromTo(start, Some(end)) => format!("bytes={}-{}", start, end),
+            ByteRange::FromTo(start, None) => format!("bytes={}-", start),
+            ByteRange::Suffix(length) => format!("bytes=-{}", length),
+        }
+    }
+}
+
+/// 流式读取结果
+pub struct StorageReadStream {
+    pub stream: StorageByteStream,
+    /// 本次返回的数据长度
+    pub content_length: Option<u64>,
+    /// 文件总大小
+    pub total_size: Option<u64>,
+    /// 按范围读取时实际返回的区间 (start, end)，end 包含在内
+    pub range: Option<(u64, u64)>,
+}
+
 #[derive(Debug, Clone)]
 pub struct StorageStsCredentials {
     pub access_key_id: String,
@@ -80,6 +164,23 @@ pub trait StorageBackend: Send + Sync {
 
     fn read_file<'a>(&'a self, key: &'a str) -> StorageFuture<'a, Vec<u8>>;
 
+    /// 流式读取文件，range 为 None 时读取整个文件
+    fn read_stream<'a>(
+        &'a self,
+        key: &'a str,
+        range: Option<ByteRange>,
+    ) -> StorageFuture<'a, StorageReadStream>;
+
+    /// 流式写入文件，返回与 save_file 相同的存储路径
+    /// content_length 已知时会校验实际写入的长度
+    fn write_stream<'a>(
+        &'a self,
+        key: &'a str,
+        stream: StorageByteStream,
+        content_length: Option<u64>,
+        content_type: Option<&'a str>,
+    ) -> StorageFuture<'a, String>;
+
     fn write_file<'a>(
         &'a self,
         key: &'a str,
@@ -290,6 +391,113 @@ impl StorageBackend for LocalStorage {
         })
     }
 
+    fn read_stream<'a>(
+        &'a self,
+        key: &'a str,
+        range: Option<ByteRange>,
+    ) -> StorageFuture<'a, StorageReadStream> {
+        Box::pin(async move {
+            let full_path = self.resolve_local_path(key)?;
+
+            let mut file = match fs::File::open(&full_path).await {
+                Ok(file) => file,
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+                    return Err(StorageError::NotFound(format!(
+                        "文件不存在: {}",
+                        full_path.to_string_lossy()
+                    )))
+                }
+                Err(e) => return Err(StorageError::Io(format!("读取文件失败: {}", e))),
+            };
+            let total_size = file
+                .metadata()
+                .await
+                .map_err(|e| StorageError::Io(format!("读取文件元信息失败: {}", e)))?
+                .len();
+
+            let (start, length, resolved) = match range {
+                Some(range) => {
+                    let (start, end) = range.resolve(total_size)?;
+                    (start, end - start + 1, Some((start, end)))
+                }
+                None => (0, total_size, None),
+            };
+            if start > 0 {
+                file.seek(SeekFrom::Start(start))
+                    .await
+                    .map_err(|e| StorageError::Io(format!("定位文件失败: {}", e)))?;
+            }
+
+            Ok(StorageReadStream {
+                stream: file_byte_stream(file, length),
+                content_length: Some(length),
+                total_size: Some(total_size),
+                range: resolved,
+            })
+        })
+    }
+
+    fn write_stream<'a>(
+        &'a self,
+        key: &'a str,
+        mut stream: StorageByteStream,
+        content_length: Option<u64>,
+        _content_type: Option<&'a str>,
+    ) -> StorageFuture<'a, String> {
+        Box::pin(async move {
+            let full_path = self.resolve_local_path(key)?;
+
+            if let Some(parent) = full_path.parent() {
+                fs::create_dir_all(parent)
+                    .await
+                    .map_err(|e| StorageError::Io(format!("创建目录失败: {}", e)))?;
+            }
+
+            // 先写入临时文件，完成后再重命名，避免读到写了一半的文件
+            let mut temp_name = full_path.as_os_str().to_owned();
+            temp_name.push(".part");
+            let temp_path = PathBuf::from(temp_name);
+
+            let result = async {
+                let mut file = fs::File::create(&temp_path)
+                    .await
+                    .map_err(|e| StorageError::Io(format!("写入文件失败: {}", e)))?;
+                let mut written: u64 = 0;
+                while let Some(chunk) = stream.next().await {
+                    let chunk = chunk?;
+                    file.write_all(&chunk)
+                        .await
+                        .map_err(|e| StorageError::Io(format!("写入文件失败: {}", e)))?;
+                    written += chunk.len() as u64;
+                }
+                file.flush()
+                    .await
+                    .map_err(|e| StorageError::Io(format!("写入文件失败: {}", e)))?;
+
+                if let Some(expected) = content_length {
+                    if written != expected {
+                        return Err(StorageError::Io(format!(
+                            "写入长度不一致: 期望 {} 字节，实际 {} 字节",
+                            expected, written
+                        )));
+                    }
+                }
+
+                fs::rename(&temp_path, &full_path)
+                    .await
+                    .map_err(|e| StorageError::Io(format!("写入文件失败: {}", e)))
+            }
+            .await;
+
+            if let Err(e) = result {
+                let _ = fs::remove_file(&temp_path).await;
+                return Err(e);
+            }
+
+            Ok(full_path.to_string_lossy().to_string())
+        })
+    }
+
     fn write_file<'a>(
         &'a self,
         key: &'a str,
@@ -386,12 +594,38 @@ impl StorageBackend for LocalStorage {
     }
 }
 
+/// 将本地文件包装为字节流，最多读取 length 字节
+pub fn file_byte_stream(file: fs::File, length: u64) -> StorageByteStream {
+    let reader = file.take(length);
+    Box::pin(futures_util::stream::unfold(
+        Some(reader),
+        |reader| async move {
+            let mut reader = reader?;
+            let mut buffer = vec![0u8; STORAGE_STREAM_CHUNK_SIZE];
+            match reader.read(&mut buffer).await {
+                Ok(0) => None,
+                Ok(n) => {
+                    buffer.truncate(n);
+                    Some((Ok(buffer), Some(reader)))
+                }
+                // 出错后结束数据流
+                Err(e) => Some((Err(StorageError::Io(format!("读取文件失败: {}", e))), None)),
+            }
+        },
+    ))
+}
+
 pub fn create_storage_backend(config: &Config) -> Result<Arc<dyn StorageBackend>, StorageError> {
     if config.storage_backend == "oss" {
         let storage = OssStorage::from_config(config)?;
         return Ok(Arc::new(storage));
     }
 
+    if config.storage_backend == "s3" {
+        let storage = S3Storage::from_config(config)?;
+        return Ok(Arc::new(storage));
+    }
+
     Ok(Arc::new(LocalStorage::new(
         config.file_upload_path.clone(),
         config.image_base_url.clone(),
@@ -405,3 +639,163 @@ pub fn create_local_storage(config: &Config) -> Result<Arc<dyn StorageBackend>,
         config.image_base_url.clone(),
     )))
 }
+
+/// 文件记录的存储类型是否为对象存储（为空时视为本地存储）
+pub fn is_object_storage_type(storage_type: Option<&str>) -> bool {
+    storage_type
+        .and_then(StorageBackendType::from_str)
+        .is_some_and(|backend_type| backend_type.is_object_storage())
+}
+
+/// 根据文件记录的存储类型选择存储后端
+/// 与当前后端一致时直接复用；否则本地文件使用本地存储，对象存储按配置创建对应的后端
+pub fn storage_for_type(
+    storage: &Arc<dyn StorageBackend>,
+    config: &Config,
+    storage_type: Option<&str>,
+) -> Result<Arc<dyn StorageBackend>, StorageError> {
+    let storage_type = storage_type.unwrap_or("local");
+    if storage.backend_type().as_str() == storage_type {
+        return Ok(storage.clone());
+    }
+
+    match StorageBackendType::from_str(storage_type) {
+        Some(StorageBackendType::Local) => create_local_storage(config),
+        Some(backend_type) => match create_storage_backend(config)? {
+            configured if configured.backend_type() == backend_type => Ok(configured),
+            _ => Err(StorageError::Config(format!(
+                "未配置 {} 存储，无法访问该文件",
+                backend_type.as_str()
+            ))),
+        },
+        None => Err(StorageError::Config(format!(
+            "未知的存储类型: {}",
+            storage_type
+        ))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_storage() -> (LocalStorage, PathBuf) {
+        let dir = std::env::temp_dir().join(format!("storage-test-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        (
+            LocalStorage::new(dir.to_string_lossy().to_string(), String::new()),
+            dir,
+        )
+    }
+
+    async fn read_all(file: StorageReadStream) -> Vec<u8> {
+        let mut stream = file.stream;
+        let mut data = Vec::new();
+        while let Some(chunk) = stream.next().await {
+            data.extend_from_slice(&chunk.unwrap());
+        }
+        data
+    }
+
+    fn bytes_stream(chunks: Vec<&'static [u8]>) -> StorageByteStream {
+        Box::pin(futures_util::stream::iter(
+            chunks.into_iter().map(|chunk| Ok(chunk.to_vec())),
+        ))
+    }
+
+    #[test]
+    fn test_byte_range_resolve() {
+        assert_eq!(ByteRange::FromTo(0, Some(9)).resolve(100).unwrap(), (0, 9));
+        assert_eq!(ByteRange::FromTo(90, None).resolve(100).unwrap(), (90, 99));
+        // 结束位置超出文件大小时截断
+        assert_eq!(
+            ByteRange::FromTo(50, Some(500)).resolve(100).unwrap(),
+            (50, 99)
+        );
+        assert_eq!(ByteRange::Suffix(10).resolve(100).unwrap(), (90, 99));
+        assert_eq!(ByteRange::Suffix(500).resolve(100).unwrap(), (0, 99));
+
+        assert!(matches!(
+            ByteRange::FromTo(100, None).resolve(100),
+            Err(StorageError::RangeNotSatisfiable(100))
+        ));
+        assert!(ByteRange::FromTo(20, Some(10)).resolve(100).is_err());
+        assert!(ByteRange::Suffix(0).resolve(100).is_err());
+        assert!(ByteRange::FromTo(0, None).resolve(0).is_err());
+    }
+
+    #[test]
+    fn test_byte_range_header_value() {
+        assert_eq!(
+            ByteRange::FromTo(0, Some(99)).to_header_value(),
+            "bytes=0-99"
+        );
+        assert_eq!(ByteRange::FromTo(100, None).to_header_value(), "bytes=100-");
+        assert_eq!(ByteRange::Suffix(20).to_header_value(), "bytes=-20");
+    }
+
+    #[tokio::test]
+    async fn test_local_write_and_read_stream() {
+        let (storage, dir) = temp_storage();
+
+        let path = storage
+            .write_stream(
+                "a/b.txt",
+                bytes_stream(vec![b"hello ", b"world"]),
+                Some(11),
+                None,
+            )
+            .await
+            .unwrap();
+        assert!(path.ends_with("b.txt"));
+        assert!(!dir.join("a/b.txt.part").exists());
+
+        let full = storage.read_stream("a/b.txt", None).await.unwrap();
+        assert_eq!(full.content_length, Some(11));
+        assert_eq!(full.total_size, Some(11));
+        assert!(full.range.is_none());
+        assert_eq!(read_all(full).await, b"hello world");
+
+        let partial = storage
+            .read_stream("a/b.txt", Some(ByteRange::FromTo(6, Some(8))))
+            .await
+            .unwrap();
+        assert_eq!(partial.range, Some((6, 8)));
+        assert_eq!(partial.content_length, Some(3));
+        assert_eq!(read_all(partial).await, b"wor");
+
+        let suffix = storage
+            .read_stream("a/b.txt", Some(ByteRange::Suffix(5)))
+            .await
+            .unwrap();
+        assert_eq!(read_all(suffix).await, b"world");
+
+        assert!(matches!(
+            storage
+                .read_stream("a/b.txt", Some(ByteRange::FromTo(11, None)))
+                .await,
+            Err(StorageError::RangeNotSatisfiable(11))
+        ));
+        assert!(matches!(
+            storage.read_stream("missing.txt", None).await,
+            Err(StorageError::NotFound(_))
+        ));
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_local_write_stream_length_mismatch() {
+        let (storage, dir) = temp_storage();
+
+        let result = storage
+            .write_stream("c.txt", bytes_stream(vec![b"abc"]), Some(10), None)
+            .await;
+        assert!(matches!(result, Err(StorageError::Io(_))));
+        // 写入失败时不留下目标文件和临时文件
+        assert!(!dir.join("c.txt").exists());
+        assert!(!dir.join("c.txt.part").exists());
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -0,0 +1,641 @@
+use hmac::{Hmac, Mac};
+use rand::{rngs::OsRng, Rng, RngCore};
+use sha1::Sha1;
+use sha2::{Digest, Sha256};
+use sqlx::{PgConnection, PgPool};
+use uuid::Uuid;
+
+use crate::models::{TotpSetupResponse, TotpStatusResponse};
+use crate::utils::{
+    open_field, percent_encode, reseal_field, seal_field, FieldCipher, FieldCipherError,
+};
+
+/// 验证码位数
+const TOTP_DIGITS: u32 = 6;
+/// 时间步长（秒）
+const TOTP_PERIOD_SECS: i64 = 30;
+/// 前后各允许偏差的时间步数，容忍客户端时钟误差
+const TOTP_SKEW_STEPS: i64 = 1;
+/// 密钥长度（字节），RFC 4226 推荐 160 位
+const TOTP_SECRET_BYTES: usize = 20;
+/// 每次生成的恢复码数量
+const RECOVERY_CODE_COUNT: usize = 10;
+/// 恢复码字符集（去掉容易混淆的 0/1/i/l/o）
+const RECOVERY_CODE_ALPHABET: &[u8] = b"23456789abcdefghjkmnpqrstuvwxyz";
+const BASE32_ALPHABET: &[u8; 32] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZ234567";
+/// 加密密钥时使用的字段名，作为关联数据的一部分
+const TOTP_SECRET_FIELD: &str = "totp_secret";
+/// 每批迁移的用户数
+const MIGRATION_BATCH_SIZE: i64 = 200;
+
+/// 两步验证错误类型
+#[derive(Debug)]
+pub enum TotpError {
+    UserNotFound(String),
+    AlreadyEnabled(String),
+    NotEnabled(String),
+    InvalidCode(String),
+    /// 密钥加密或解密失败
+    EncryptionError(String),
+    DatabaseError(String),
+}
+
+impl std::fmt::Display for TotpError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TotpError::UserNotFound(msg) => write!(f, "用户不存在: {}", msg),
+            TotpError::AlreadyEnabled(msg) => write!(f, "两步验证已启用: {}", msg),
+            TotpError::NotEnabled(msg) => write!(f, "两步验证未启用: {}", msg),
+            TotpError::InvalidCode(msg) => write!(f, "验证码错误: {}", msg),
+            TotpError::EncryptionError(msg) => write!(f, "密钥加密错误: {}", msg),
+            TotpError::DatabaseError(msg) => write!(f, "数据库错误: {}", msg),
+        }
+    }
+}
+
+impl std::error::Error for TotpError {}
+
+impl From<sqlx::Error> for TotpError {
+    fn from(err: sqlx::Error) -> Self {
+        TotpError::DatabaseError(err.to_string())
+    }
+}
+
+impl From<FieldCipherError> for TotpError {
+    fn from(err: FieldCipherError) -> Self {
+        TotpError::EncryptionError(err.to_string())
+    }
+}
+
+/// 两步验证密钥加密迁移统计
+#[derive(Debug, Default)]
+pub struct TotpMigrationStats {
+    /// 检查的用户数
+    pub scanned: u64,
+    /// 加密或换用当前密钥重新加密的用户数
+    pub updated: u64,
+    /// 无法解密（如密钥已移除）的用户数
+    pub failed: u64,
+}
+
+/// 通过两步验证时使用的方式
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MfaMethod {
+    /// 认证器应用生成的验证码
+    Totp,
+    /// 一次性恢复码
+    RecoveryCode,
+}
+
+impl MfaMethod {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            MfaMethod::Totp => "totp",
+            MfaMethod::RecoveryCode => "recovery_code",
+        }
+    }
+}
+
+/// 用户的两步验证状态
+#[derive(Debug, sqlx::FromRow)]
+struct TotpState {
+    /// 数据库中存储的密钥，配置了加密密钥时为密文
+    totp_secret: Option<String>,
+    enabled: bool,
+    totp_last_step: Option<i64>,
+}
+
+/// 两步验证服务（TOTP，RFC 6238）
+pub struct TotpService;
+
+impl TotpService {
+    /// 获取两步验证状态
+    pub async fn get_status(
+        pool: &PgPool,
+        user_id: Uuid,
+        session_id: Option<Uuid>,
+        required: bool,
+    ) -> Result<TotpStatusResponse, TotpError> {
+        let state = Self::load_state(pool, user_id).await?;
+        let recovery_codes_remaining: i64 = sqlx::query_scalar(
+            "SELECT COUNT(*) FROM user_recovery_codes WHERE user_id = $1 AND used_at IS NULL",
+        )
+        .bind(user_id)
+        .fetch_one(pool)
+        .await?;
+        let session_verified =
+            super::SessionService::is_mfa_verified(pool, user_id, session_id).await?;
+
+        Ok(TotpStatusResponse {
+            enabled: state.enabled,
+            required,
+            session_verified,
+            recovery_codes_remaining,
+        })
+    }
+
+    /// 开始启用两步验证：生成新的待确认密钥
+    ///
+    /// 密钥在 `enable` 校验验证码之前不生效，重复调用会覆盖之前未确认的密钥。
+    /// 配置了实名信息加密密钥时密钥加密存储
+    pub async fn begin_setup(
+        pool: &PgPool,
+        cipher: Option<&FieldCipher>,
+        user_id: Uuid,
+        issuer: &str,
+    ) -> Result<TotpSetupResponse, TotpError> {
+        let (username, enabled): (String, bool) = sqlx::query_as(
+            "SELECT username, totp_enabled_at IS NOT NULL FROM users WHERE id = $1 AND is_active = true",
+        )
+        .bind(user_id)
+        .fetch_optional(pool)
+        .await?
+        .ok_or_else(|| TotpError::UserNotFound("用户不存在".to_string()))?;
+
+        if enabled {
+            return Err(TotpError::AlreadyEnabled(
+                "请先关闭两步验证再重新绑定".to_string(),
+            ));
+        }
+
+        let secret = generate_secret();
+        let stored = seal_field(cipher, user_id, TOTP_SECRET_FIELD, &secret)?;
+        sqlx::query(
+            r#"
+            UPDATE users
+            SET totp_secret = $2, totp_last_step = NULL, updated_at = NOW()
+            WHERE id = $1 AND totp_enabled_at IS NULL
+            "#,
+        )
+        .bind(user_id)
+        .bind(&stored)
+        .execute(pool)
+        .await?;
+
+        Ok(TotpSetupResponse {
+            otpauth_uri: otpauth_uri(issuer, &username, &secret),
+            secret,
+        })
+    }
+
+    /// 确认启用两步验证，返回恢复码明文
+    pub async fn enable(
+        pool: &PgPool,
+        cipher: Option<&FieldCipher>,
+        user_id: Uuid,
+        code: &str,
+    ) -> Result<Vec<String>, TotpError> {
+        let state = Self::load_state(pool, user_id).await?;
+        if state.enabled {
+            return Err(TotpError::AlreadyEnabled("两步验证已启用".to_string()));
+        }
+        let stored = state
+            .totp_secret
+            .ok_or_else(|| TotpError::NotEnabled("请先生成两步验证密钥".to_string()))?;
+        let secret = open_field(cipher, user_id, TOTP_SECRET_FIELD, &stored)?;
+        let step = verify_totp(&secret, code, chrono::Utc::now().timestamp(), None)
+            .ok_or_else(|| TotpError::InvalidCode("验证码错误".to_string()))?;
+
+        let mut tx = pool.begin().await?;
+        let result = sqlx::query(
+            r#"
+            UPDATE users
+            SET totp_enabled_at = NOW(), totp_last_step = $3, updated_at = NOW()
+            WHERE id = $1 AND totp_secret = $2 AND totp_enabled_at IS NULL
+            "#,
+        )
+        .bind(user_id)
+        .bind(&stored)
+        .bind(step)
+        .execute(&mut *tx)
+        .await?;
+        if result.rows_affected() == 0 {
+            return Err(TotpError::AlreadyEnabled(
+                "两步验证状态已变化，请刷新后重试".to_string(),
+            ));
+        }
+
+        let codes = replace_recovery_codes(&mut tx, user_id).await?;
+        tx.commit().await?;
+
+        log::info!("两步验证已启用: user_id={}", user_id);
+        Ok(codes)
+    }
+
+    /// 关闭两步验证，同时删除全部恢复码
+    pub async fn disable(
+        pool: &PgPool,
+        cipher: Option<&FieldCipher>,
+        user_id: Uuid,
+        code: &str,
+    ) -> Result<(), TotpError> {
+        Self::verify(pool, cipher, user_id, code)
+            .await?
+            .ok_or_else(|| TotpError::InvalidCode("验证码错误".to_string()))?;
+
+        let mut tx = pool.begin().await?;
+        sqlx::query(
+            r#"
+            UPDATE users
+            SET totp_secret = NULL, totp_enabled_at = NULL, totp_last_step = NULL, updated_at = NOW()
+            WHERE id = $1
+            "#,
+        )
+        .bind(user_id)
+        .execute(&mut *tx)
+        .await?;
+        sqlx::query("DELETE FROM user_recovery_codes WHERE user_id = $1")
+            .bind(user_id)
+            .execute(&mut *tx)
+            .await?;
+        tx.commit().await?;
+
+        log::info!("两步验证已关闭: user_id={}", user_id);
+        Ok(())
+    }
+
+    /// 重新生成恢复码，之前的恢复码全部失效
+    pub async fn regenerate_recovery_codes(
+        pool: &PgPool,
+        cipher: Option<&FieldCipher>,
+        user_id: Uuid,
+        code: &str,
+    ) -> Result<Vec<String>, TotpError> {
+        Self::verify(pool, cipher, user_id, code)
+            .await?
+            .ok_or_else(|| TotpError::InvalidCode("验证码错误".to_string()))?;
+
+        let mut tx = pool.begin().await?;
+        let codes = replace_recovery_codes(&mut tx, user_id).await?;
+        tx.commit().await?;
+
+        log::info!("恢复码已重新生成: user_id={}", user_id);
+        Ok(codes)
+    }
+
+    /// 校验验证码或恢复码，通过时返回使用的方式，未通过返回 None
+    ///
+    /// 验证码的时间步必须晚于上次使用的时间步，同一验证码不能重复使用；
+    /// 恢复码使用后立即作废。
+    pub async fn verify(
+        pool: &PgPool,
+        cipher: Option<&FieldCipher>,
+        user_id: Uuid,
+        code: &str,
+    ) -> Result<Option<MfaMethod>, TotpError> {
+        let state = Self::load_state(pool, user_id).await?;
+        let secret = match state.totp_secret {
+            Some(stored) if state.enabled => {
+                open_field(cipher, user_id, TOTP_SECRET_FIELD, &stored)?
+            }
+            _ => return Err(TotpError::NotEnabled("未启用两步验证".to_string())),
+        };
+
+        let code = code.trim();
+        if is_totp_code(code) {
+            let Some(step) = verify_totp(
+                &secret,
+                code,
+                chrono::Utc::now().timestamp(),
+                state.totp_last_step,
+            ) else {
+                return Ok(None);
+            };
+            // 条件更新，并发请求中同一验证码只有一个能通过
+            let result = sqlx::query(
+                r#"
+                UPDATE users SET totp_last_step = $2
+                WHERE id = $1 AND (totp_last_step IS NULL OR totp_last_step < $2)
+                "#,
+            )
+            .bind(user_id)
+            .bind(step)
+            .execute(pool)
+            .await?;
+            return Ok((result.rows_affected() > 0).then_some(MfaMethod::Totp));
+        }
+
+        let result = sqlx::query(
+            r#"
+            UPDATE user_recovery_codes SET used_at = NOW()
+            WHERE user_id = $1 AND code_hash = $2 AND used_at IS NULL
+            "#,
+        )
+        .bind(user_id)
+        .bind(hash_recovery_code(code))
+        .execute(pool)
+        .await?;
+        if result.rows_affected() > 0 {
+            log::warn!("使用恢复码通过两步验证: user_id={}", user_id);
+            return Ok(Some(MfaMethod::RecoveryCode));
+        }
+        Ok(None)
+    }
+
+    /// 用户是否已启用两步验证
+    pub async fn is_enabled(pool: &PgPool, user_id: Uuid) -> Result<bool, TotpError> {
+        Ok(Self::load_state(pool, user_id).await?.enabled)
+    }
+
+    /// 加密已有的明文两步验证密钥，并将旧密钥加密的数据换用当前密钥
+    ///
+    /// 可重复执行，已使用当前密钥加密的数据会被跳过
+    pub async fn encrypt_existing_secrets(
+        pool: &PgPool,
+        cipher: &FieldCipher,
+    ) -> Result<TotpMigrationStats, TotpError> {
+        let mut stats = TotpMigrationStats::default();
+        let mut last_id: Option<Uuid> = None;
+
+        loop {
+            let rows: Vec<(Uuid, String)> = sqlx::query_as(
+                r#"
+                SELECT id, totp_secret
+                FROM users
+                WHERE totp_secret IS NOT NULL
+                  AND ($1::UUID IS NULL OR id > $1)
+                ORDER BY id
+                LIMIT $2
+                "#,
+            )
+            .bind(last_id)
+            .bind(MIGRATION_BATCH_SIZE)
+            .fetch_all(pool)
+            .await?;
+
+            let Some((id, _)) = rows.last() else {
+                break;
+            };
+            last_id = Some(*id);
+
+            for (user_id, stored) in rows {
+                stats.scanned += 1;
+                let sealed = match reseal_field(cipher, user_id, TOTP_SECRET_FIELD, &stored) {
+                    Ok(Some(sealed)) => sealed,
+                    Ok(None) => continue,
+                    Err(e) => {
+                        log::warn!(
+                            "[Totp] 无法重新加密两步验证密钥 | user_id={}, error={}",
+                            user_id,
+                            e
+                        );
+                        stats.failed += 1;
+                        continue;
+                    }
+                };
+
+                // 只在密钥未被并发修改时更新，被修改的行下次执行时再处理
+                let result = sqlx::query(
+                    "UPDATE users SET totp_secret = $1 WHERE id = $2 AND totp_secret = $3",
+                )
+                .bind(&sealed)
+                .bind(user_id)
+                .bind(&stored)
+                .execute(pool)
+                .await?;
+                if result.rows_affected() > 0 {
+                    stats.updated += 1;
+                }
+            }
+        }
+
+        Ok(stats)
+    }
+
+    async fn load_state(pool: &PgPool, user_id: Uuid) -> Result<TotpState, TotpError> {
+        sqlx::query_as::<_, TotpState>(
+            r#"
+            SELECT totp_secret, totp_enabled_at IS NOT NULL AS enabled, totp_last_step
+            FROM users WHERE id = $1 AND is_active = true
+            "#,
+        )
+        .bind(user_id)
+        .fetch_optional(pool)
+        .await?
+        .ok_or_else(|| TotpError::UserNotFound("用户不存在".to_string()))
+    }
+}
+
+/// 删除旧恢复码并生成新的一组，返回明文
+async fn replace_recovery_codes(
+    conn: &mut PgConnection,
+    user_id: Uuid,
+) -> Result<Vec<String>, sqlx::Error> {
+    sqlx::query("DELETE FROM user_recovery_codes WHERE user_id = $1")
+        .bind(user_id)
+        .execute(&mut *conn)
+        .await?;
+
+    let codes: Vec<String> = (0..RECOVERY_CODE_COUNT)
+        .map(|_| generate_recovery_code())
+        .collect();
+    for code in &codes {
+        sqlx::query(
+            "INSERT INTO user_recovery_codes (id, user_id, code_hash, created_at) VALUES ($1, $2, $3, NOW())",
+        )
+        .bind(Uuid::new_v4())
+        .bind(user_id)
+        .bind(hash_recovery_code(code))
+        .execute(&mut *conn)
+        .await?;
+    }
+    Ok(codes)
+}
+
+/// 生成 Base32 编码的随机密钥
+fn generate_secret() -> String {
+    let mut bytes = [0u8; TOTP_SECRET_BYTES];
+    OsRng.fill_bytes(&mut bytes);
+    base32_encode(&bytes)
+}
+
+/// 生成形如 `abcd-efgh-jkmn` 的恢复码
+fn generate_recovery_code() -> String {
+    let chars: Vec<char> = (0..12)
+        .map(|_| RECOVERY_CODE_ALPHABET[OsRng.gen_range(0..RECOVERY_CODE_ALPHABET.len())] as char)
+        .collect();
+    chars
+        .chunks(4)
+        .map(|chunk| chunk.iter().collect::<String>())
+        .collect::<Vec<_>>()
+        .join("-")
+}
+
+/// 计算恢复码哈希，忽略大小写、空格和连字符
+fn hash_recovery_code(code: &str) -> String {
+    let normalized: String = code
+        .chars()
+        .filter(|c| c.is_ascii_alphanumeric())
+        .map(|c| c.to_ascii_lowercase())
+        .collect();
+    format!("{:x}", Sha256::digest(normalized.as_bytes()))
+}
+
+fn is_totp_code(code: &str) -> bool {
+    code.len() == TOTP_DIGITS as usize && code.bytes().all(|b| b.is_ascii_digit())
+}
+
+/// 生成认证器应用扫码使用的链接
+fn otpauth_uri(issuer: &str, account: &str, secret: &str) -> String {
+    format!(
+        "otpauth://totp/{}:{}?secret={}&issuer={}&algorithm=SHA1&digits={}&period={}",
+        percent_encode(issuer, true),
+        percent_encode(account, true),
+        secret,
+        percent_encode(issuer, true),
+        TOTP_DIGITS,
+        TOTP_PERIOD_SECS
+    )
+}
+
+/// 计算指定时间步的验证码（RFC 4226 HOTP，HMAC-SHA1）
+fn hotp(key: &[u8], step: i64) -> u32 {
+    let mut mac = Hmac::<Sha1>::new_from_slice(key).expect("HMAC 可以接受任意长度的密钥");
+    mac.update(&(step as u64).to_be_bytes());
+    let hash = mac.finalize().into_bytes();
+
+    // 动态截断
+    let offset = (hash[hash.len() - 1] & 0x0f) as usize;
+    let binary = u32::from_be_bytes([
+        hash[offset] & 0x7f,
+        hash[offset + 1],
+        hash[offset + 2],
+        hash[offset + 3],
+    ]);
+    binary % 10u32.pow(TOTP_DIGITS)
+}
+
+/// 校验验证码，返回匹配的时间步；`last_step` 及之前的时间步不再接受
+fn verify_totp(secret: &str, code: &str, now: i64, last_step: Option<i64>) -> Option<i64> {
+    let code = code.trim();
+    if !is_totp_code(code) {
+        return None;
+    }
+    let expected: u32 = code.parse().ok()?;
+    let key = base32_decode(secret)?;
+
+    let current = now.div_euclid(TOTP_PERIOD_SECS);
+    (current - TOTP_SKEW_STEPS..=current + TOTP_SKEW_STEPS)
+        .filter(|step| last_step.is_none_or(|last| *step > last))
+        .find(|step| hotp(&key, *step) == expected)
+}
+
+/// Base32 编码（RFC 4648，不带填充）
+fn base32_encode(bytes: &[u8]) -> String {
+    let mut output = String::with_capacity(bytes.len().div_ceil(5) * 8);
+    let mut buffer: u64 = 0;
+    let mut bits = 0;
+    for &byte in bytes {
+        buffer = (buffer << 8) | byte as u64;
+        bits += 8;
+        while bits >= 5 {
+            bits -= 5;
+            output.push(BASE32_ALPHABET[((buffer >> bits) & 0x1f) as usize] as char);
+        }
+    }
+    if bits > 0 {
+        output.push(BASE32_ALPHABET[((buffer << (5 - bits)) & 0x1f) as usize] as char);
+    }
+    output
+}
+
+/// Base32 解码，忽略大小写、空格和填充
+fn base32_decode(input: &str) -> Option<Vec<u8>> {
+    let mut output = Vec::with_capacity(input.len() * 5 / 8);
+    let mut buffer: u64 = 0;
+    let mut bits = 0;
+    for c in input.chars().filter(|c| !c.is_whitespace() && *c != '=') {
+        let value = BASE32_ALPHABET
+            .iter()
+            .position(|&a| a as char == c.to_ascii_uppercase())? as u64;
+        buffer = (buffer << 5) | value;
+        bits += 5;
+        if bits >= 8 {
+            bits -= 8;
+            output.push(((buffer >> bits) & 0xff) as u8);
+        }
+    }
+    Some(output)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// RFC 6238 附录 B 的 SHA1 测试密钥
+    const RFC_KEY: &[u8] = b"12345678901234567890";
+
+    #[test]
+    fn test_hotp_rfc6238_vectors() {
+        // RFC 给出的是 8 位验证码，取后 6 位
+        assert_eq!(hotp(RFC_KEY, 59 / 30), 287082);
+        assert_eq!(hotp(RFC_KEY, 1111111109 / 30), 81804);
+        assert_eq!(hotp(RFC_KEY, 1234567890 / 30), 5924);
+        assert_eq!(hotp(RFC_KEY, 2000000000 / 30), 279037);
+    }
+
+    #[test]
+    fn test_base32_round_trip() {
+        assert_eq!(base32_encode(b"foobar"), "MZXW6YTBOI");
+        assert_eq!(base32_decode("mzxw6ytboi======"), Some(b"foobar".to_vec()));
+        assert_eq!(base32_decode("MZXW 6YTB OI"), Some(b"foobar".to_vec()));
+        assert_eq!(base32_decode("MZXW1"), None);
+
+        let secret = generate_secret();
+        assert_eq!(secret.len(), 32);
+        assert_eq!(
+            base32_decode(&secret).map(|b| b.len()),
+            Some(TOTP_SECRET_BYTES)
+        );
+    }
+
+    #[test]
+    fn test_verify_totp_window_and_replay() {
+        let secret = base32_encode(RFC_KEY);
+        let now = 1111111109;
+        let step = now / 30;
+        let code = |step: i64| format!("{:06}", hotp(RFC_KEY, step));
+
+        assert_eq!(verify_totp(&secret, &code(step), now, None), Some(step));
+        // 允许前后各一个时间步的时钟误差
+        assert_eq!(
+            verify_totp(&secret, &code(step - 1), now, None),
+            Some(step - 1)
+        );
+        assert_eq!(
+            verify_totp(&secret, &code(step + 1), now, None),
+            Some(step + 1)
+        );
+        assert_eq!(verify_totp(&secret, &code(step + 2), now, None), None);
+        // 已使用的时间步不能再次使用
+        assert_eq!(verify_totp(&secret, &code(step), now, Some(step)), None);
+        assert_eq!(
+            verify_totp(&secret, &code(step + 1), now, Some(step)),
+            Some(step + 1)
+        );
+        assert_eq!(verify_totp(&secret, "12345", now, None), None);
+        assert_eq!(verify_totp(&secret, "abcdef", now, None), None);
+    }
+
+    #[test]
+    fn test_recovery_code_format_and_hash() {
+        let code = generate_recovery_code();
+        assert_eq!(code.len(), 14);
+        assert_eq!(code.matches('-').count(), 2);
+        assert!(!is_totp_code(&code));
+
+        // 输入时忽略大小写、空格和连字符
+        let compact = code.replace('-', " ").to_uppercase();
+        assert_eq!(hash_recovery_code(&code), hash_recovery_code(&compact));
+        assert_ne!(hash_recovery_code(&code), hash_recovery_code("other"));
+    }
+
+    #[test]
+    fn test_otpauth_uri() {
+        let uri = otpauth_uri("Share USTC", "alice", "MZXW6YTBOI");
+        assert_eq!(
+            uri,
+            "otpauth://totp/Share%20USTC:alice?secret=MZXW6YTBOI&issuer=Share%20USTC&algorithm=SHA1&digits=6&period=30"
+        );
+    }
+}
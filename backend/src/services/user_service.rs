@@ -1,7 +1,7 @@
 use crate::models::resource::{ResourceListItem, ResourceStatsResponse};
 use crate::models::{
     LeaderboardQuery, LeaderboardResponse, LeaderboardUser, UpdateProfileRequest, User,
-    UserHomepageQuery, UserHomepageResponse, UserInfo, UserProfileResponse, VerificationRequest,
+    UserHomepageQuery, UserHomepageResponse, UserInfo, UserProfileResponse,
 };
 use sqlx::{PgPool, Row};
 use uuid::Uuid;
@@ -37,7 +37,7 @@ impl UserService {
     /// 获取当前用户信息
     pub async fn get_current_user(pool: &PgPool, user_id: Uuid) -> Result<UserInfo, UserError> {
         let user: User = sqlx::query_as::<_, User>(
-            "SELECT id, sn, username, password_hash, email, role, bio,
+            "SELECT id, sn, username, password_hash, email, email_verified, role, bio,
                     CASE WHEN social_links = '{}'::jsonb THEN NULL ELSE social_links END as social_links,
                     CASE WHEN real_info = '{}'::jsonb THEN NULL ELSE real_info END as real_info,
                     is_verified, is_active, created_at, updated_at
@@ -59,7 +59,7 @@ impl UserService {
     ) -> Result<UserProfileResponse, UserError> {
         // 获取用户基本信息
         let user: User = sqlx::query_as::<_, User>(
-            "SELECT id, sn, username, password_hash, email, role, bio,
+            "SELECT id, sn, username, password_hash, email, email_verified, role, bio,
                     CASE WHEN social_links = '{}'::jsonb THEN NULL ELSE social_links END as social_links,
                     CASE WHEN real_info = '{}'::jsonb THEN NULL ELSE real_info END as real_info,
                     is_verified, is_active, created_at, updated_at
@@ -209,10 +209,12 @@ impl UserService {
             SET username = $1,
                 bio = $2,
                 email = $3,
+                email_verified = CASE WHEN LOWER(email) IS NOT DISTINCT FROM LOWER($3) THEN email_verified ELSE false END,
+                email_verified_at = CASE WHEN LOWER(email) IS NOT DISTINCT FROM LOWER($3) THEN email_verified_at ELSE NULL END,
                 social_links = $4,
                 updated_at = NOW()
             WHERE id = $5 AND is_active = true
-            RETURNING id, sn, username, password_hash, email, role, bio, social_links, real_info, is_verified, is_active, created_at, updated_at
+            RETURNING id, sn, username, password_hash, email, email_verified, role, bio, social_links, real_info, is_verified, is_active, created_at, updated_at
             "#
         )
         .bind(username)
@@ -230,61 +232,6 @@ impl UserService {
         Ok(UserInfo::from(updated_user))
     }
 
-    /// 实名认证
-    pub async fn verify_user(
-        pool: &PgPool,
-        user_id: Uuid,
-        req: VerificationRequest,
-    ) -> Result<UserInfo, UserError> {
-        // 获取当前用户
-        let user: User = sqlx::query_as::<_, User>(
-            "SELECT id, sn, username, password_hash, email, role, bio,
-                    CASE WHEN social_links = '{}'::jsonb THEN NULL ELSE social_links END as social_links,
-                    CASE WHEN real_info = '{}'::jsonb THEN NULL ELSE real_info END as real_info,
-                    is_verified, is_active, created_at, updated_at
-             FROM users WHERE id = $1 AND is_active = true"
-        )
-        .bind(user_id)
-        .fetch_optional(pool)
-        .await
-        .map_err(|e| UserError::DatabaseError(e.to_string()))?
-        .ok_or_else(|| UserError::UserNotFound("用户不存在".to_string()))?;
-
-        // 检查是否已经是实名用户
-        if user.is_verified {
-            return Err(UserError::ValidationError("用户已完成实名认证".to_string()));
-        }
-
-        // 构建实名信息 JSON
-        let real_info = serde_json::json!({
-            "real_name": req.real_name,
-            "student_id": req.student_id,
-            "major": req.major,
-            "grade": req.grade,
-        });
-
-        // 更新用户为实名状态（保持原有角色，只更新 is_verified）
-        let updated_user: User = sqlx::query_as::<_, User>(
-            r#"
-            UPDATE users
-            SET is_verified = true,
-                real_info = $1,
-                updated_at = NOW()
-            WHERE id = $2 AND is_active = true
-            RETURNING id, sn, username, password_hash, email, role, bio, social_links, real_info, is_verified, is_active, created_at, updated_at
-            "#
-        )
-        .bind(real_info)
-        .bind(user_id)
-        .fetch_one(pool)
-        .await
-        .map_err(|e| UserError::DatabaseError(format!("认证失败: {}", e)))?;
-
-        log::info!("用户完成实名认证: {}", updated_user.username);
-
-        Ok(UserInfo::from(updated_user))
-    }
-
     /// 获取用户主页数据（公开接口）
     /// 包含用户基本信息、统计数据和已通过审核的资源列表
     pub async fn get_user_homepage(
@@ -294,7 +241,7 @@ impl UserService {
     ) -> Result<UserHomepageResponse, UserError> {
         // 获取用户基本信息
         let user: User = sqlx::query_as::<_, User>(
-            "SELECT id, sn, username, password_hash, email, role, bio,
+            "SELECT id, sn, username, password_hash, email, email_verified, role, bio,
                     CASE WHEN social_links = '{}'::jsonb THEN NULL ELSE social_links END as social_links,
                     CASE WHEN real_info = '{}'::jsonb THEN NULL ELSE real_info END as real_info,
                     is_verified, is_active, created_at, updated_at
@@ -454,6 +401,8 @@ impl UserService {
                     .ok()
                     .flatten()
                     .unwrap_or_else(|| "local".to_string()),
+                search_rank: None,
+                highlight: None,
             });
         }
 
@@ -489,7 +438,7 @@ impl UserService {
     ) -> Result<(), UserError> {
         // 获取用户当前密码哈希
         let user: crate::models::User = sqlx::query_as::<_, crate::models::User>(
-            "SELECT id, sn, username, password_hash, email, role, bio,
+            "SELECT id, sn, username, password_hash, email, email_verified, role, bio,
                     CASE WHEN social_links = '{}'::jsonb THEN NULL ELSE social_links END as social_links,
                     CASE WHEN real_info = '{}'::jsonb THEN NULL ELSE real_info END as real_info,
                     is_verified, is_active, created_at, updated_at
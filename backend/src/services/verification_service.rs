@@ -0,0 +1,350 @@
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::models::{
+    MyVerificationResponse, ReviewVerificationRequest, VerificationListQuery,
+    VerificationListResponse, VerificationProofImage, VerificationRecord, VerificationRequest,
+    VerificationResponse, VerificationStatus,
+};
+use crate::services::{NotificationService, RealInfoCipher, RealInfoService};
+
+/// 实名认证错误类型
+#[derive(Debug)]
+pub enum VerificationError {
+    DatabaseError(String),
+    NotFound(String),
+    ValidationError(String),
+    Conflict(String),
+}
+
+impl std::fmt::Display for VerificationError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            VerificationError::DatabaseError(msg) => write!(f, "数据库错误: {}", msg),
+            VerificationError::NotFound(msg) => write!(f, "未找到: {}", msg),
+            VerificationError::ValidationError(msg) => write!(f, "验证错误: {}", msg),
+            VerificationError::Conflict(msg) => write!(f, "状态冲突: {}", msg),
+        }
+    }
+}
+
+impl std::error::Error for VerificationError {}
+
+impl From<sqlx::Error> for VerificationError {
+    fn from(err: sqlx::Error) -> Self {
+        VerificationError::DatabaseError(err.to_string())
+    }
+}
+
+/// 申请查询结果（包含申请人用户名）
+#[derive(Debug, sqlx::FromRow)]
+struct VerificationRow {
+    #[sqlx(flatten)]
+    record: VerificationRecord,
+    username: Option<String>,
+}
+
+/// 申请列表查询字段
+const VERIFICATION_SELECT_SQL: &str = r#"
+    SELECT
+        v.id, v.user_id, v.real_info, v.proof_image_id, v.status, v.reviewer_id,
+        v.review_reason, v.reviewed_at, v.created_at,
+        u.username
+    FROM verification_requests v
+    LEFT JOIN users u ON v.user_id = u.id
+"#;
+
+/// 实名认证服务：提交申请与管理员审核
+pub struct VerificationService;
+
+impl VerificationService {
+    /// 提交实名认证申请，等待管理员审核
+    pub async fn submit(
+        pool: &PgPool,
+        cipher: Option<&RealInfoCipher>,
+        user_id: Uuid,
+        req: VerificationRequest,
+        image_base_url: &str,
+    ) -> Result<VerificationResponse, VerificationError> {
+        req.validate().map_err(VerificationError::ValidationError)?;
+
+        let is_verified: bool =
+            sqlx::query_scalar("SELECT is_verified FROM users WHERE id = $1 AND is_active = true")
+                .bind(user_id)
+                .fetch_optional(pool)
+                .await?
+                .ok_or_else(|| VerificationError::NotFound("用户不存在".to_string()))?;
+        if is_verified {
+            return Err(VerificationError::ValidationError(
+                "用户已完成实名认证".to_string(),
+            ));
+        }
+
+        // 证明图片必须是申请人自己在图床上传的图片
+        if let Some(image_id) = req.proof_image_id {
+            let owned: bool = sqlx::query_scalar(
+                "SELECT EXISTS(SELECT 1 FROM images WHERE id = $1 AND uploader_id = $2)",
+            )
+            .bind(image_id)
+            .bind(user_id)
+            .fetch_one(pool)
+            .await?;
+            if !owned {
+                return Err(VerificationError::ValidationError(
+                    "证明图片不存在或不属于当前用户".to_string(),
+                ));
+            }
+        }
+
+        // 配置了密钥时敏感字段加密存储
+        let real_info = RealInfoService::seal(cipher, user_id, req.real_info())
+            .map_err(|e| VerificationError::DatabaseError(e.to_string()))?;
+
+        let id: Uuid = sqlx::query_scalar(
+            r#"
+            INSERT INTO verification_requests (user_id, real_info, proof_image_id, status)
+            VALUES ($1, $2, $3, 'pending')
+            RETURNING id
+            "#,
+        )
+        .bind(user_id)
+        .bind(real_info)
+        .bind(req.proof_image_id)
+        .fetch_one(pool)
+        .await
+        .map_err(|e| match e {
+            sqlx::Error::Database(ref db) if db.is_unique_violation() => {
+                VerificationError::Conflict("您已提交过实名认证申请，请等待审核".to_string())
+            }
+            e => e.into(),
+        })?;
+
+        log::info!(
+            "[VerificationService] 实名认证申请已提交: id={}, user_id={}",
+            id,
+            user_id
+        );
+
+        let (_, response) = Self::get_verification(pool, id, image_base_url).await?;
+        Ok(response)
+    }
+
+    /// 获取当前用户的实名认证状态和最近一次申请
+    pub async fn get_my_verification(
+        pool: &PgPool,
+        user_id: Uuid,
+        image_base_url: &str,
+    ) -> Result<MyVerificationResponse, VerificationError> {
+        let is_verified: bool = sqlx::query_scalar("SELECT is_verified FROM users WHERE id = $1")
+            .bind(user_id)
+            .fetch_optional(pool)
+            .await?
+            .ok_or_else(|| VerificationError::NotFound("用户不存在".to_string()))?;
+
+        let sql = format!(
+            "{} WHERE v.user_id = $1 ORDER BY v.created_at DESC LIMIT 1",
+            VERIFICATION_SELECT_SQL
+        );
+        let latest = sqlx::query_as::<_, VerificationRow>(&sql)
+            .bind(user_id)
+            .fetch_optional(pool)
+            .await?
+            .map(|row| Self::build_response(row.record, row.username, image_base_url));
+
+        Ok(MyVerificationResponse {
+            is_verified,
+            latest,
+        })
+    }
+
+    /// 获取单个申请，同时返回包含实名信息（可能为密文）的原始记录
+    pub async fn get_verification(
+        pool: &PgPool,
+        id: Uuid,
+        image_base_url: &str,
+    ) -> Result<(VerificationRecord, VerificationResponse), VerificationError> {
+        let sql = format!("{} WHERE v.id = $1", VERIFICATION_SELECT_SQL);
+        let row = sqlx::query_as::<_, VerificationRow>(&sql)
+            .bind(id)
+            .fetch_optional(pool)
+            .await?
+            .ok_or_else(|| VerificationError::NotFound("实名认证申请不存在".to_string()))?;
+
+        let response = Self::build_response(row.record.clone(), row.username, image_base_url);
+        Ok((row.record, response))
+    }
+
+    /// 获取申请列表（管理员），待审核的申请按提交时间先后排列
+    pub async fn get_verification_list(
+        pool: &PgPool,
+        query: VerificationListQuery,
+        image_base_url: &str,
+    ) -> Result<VerificationListResponse, VerificationError> {
+        let page = query.page.unwrap_or(1).max(1);
+        let per_page = query.per_page.unwrap_or(20).clamp(1, 100);
+        let offset = (page - 1) * per_page;
+
+        let status = match query.status.as_deref() {
+            Some(s) if !s.is_empty() => Some(VerificationStatus::from_str(s).ok_or_else(|| {
+                VerificationError::ValidationError(format!("无效的申请状态: {}", s))
+            })?),
+            _ => None,
+        };
+        // 审核队列先处理最早提交的申请，其余按时间倒序
+        let order = if status == Some(VerificationStatus::Pending) {
+            "ASC"
+        } else {
+            "DESC"
+        };
+        let status = status.map(|s| s.as_str());
+
+        let total = sqlx::query_scalar::<_, i64>(
+            "SELECT COUNT(*) FROM verification_requests v WHERE ($1::varchar IS NULL OR v.status = $1)",
+        )
+        .bind(status)
+        .fetch_one(pool)
+        .await?;
+
+        let sql = format!(
+            r#"{}
+            WHERE ($1::varchar IS NULL OR v.status = $1)
+            ORDER BY v.created_at {}
+            LIMIT $2 OFFSET $3
+            "#,
+            VERIFICATION_SELECT_SQL, order
+        );
+        let rows = sqlx::query_as::<_, VerificationRow>(&sql)
+            .bind(status)
+            .bind(per_page)
+            .bind(offset)
+            .fetch_all(pool)
+            .await?;
+
+        let verifications = rows
+            .into_iter()
+            .map(|row| Self::build_response(row.record, row.username, image_base_url))
+            .collect();
+
+        Ok(VerificationListResponse {
+            verifications,
+            total,
+            page,
+            per_page,
+        })
+    }
+
+    /// 审核实名认证申请（管理员）
+    ///
+    /// 通过时将申请中的实名信息写入用户并设置实名状态；审核后申请中的实名信息被清空
+    pub async fn review(
+        pool: &PgPool,
+        id: Uuid,
+        reviewer_id: Uuid,
+        req: ReviewVerificationRequest,
+        image_base_url: &str,
+    ) -> Result<VerificationResponse, VerificationError> {
+        let (status, reason) = req.validate().map_err(VerificationError::ValidationError)?;
+
+        let mut tx = pool.begin().await?;
+
+        // 锁定申请记录，防止并发审核
+        let record = sqlx::query_as::<_, VerificationRecord>(
+            r#"
+            SELECT id, user_id, real_info, proof_image_id, status, reviewer_id,
+                   review_reason, reviewed_at, created_at
+            FROM verification_requests
+            WHERE id = $1
+            FOR UPDATE
+            "#,
+        )
+        .bind(id)
+        .fetch_optional(&mut *tx)
+        .await?
+        .ok_or_else(|| VerificationError::NotFound("实名认证申请不存在".to_string()))?;
+
+        if record.status != VerificationStatus::Pending.as_str() {
+            return Err(VerificationError::Conflict("该申请已被处理".to_string()));
+        }
+
+        if status == VerificationStatus::Approved {
+            let result = sqlx::query(
+                r#"
+                UPDATE users
+                SET is_verified = true, real_info = $1, updated_at = NOW()
+                WHERE id = $2 AND is_active = true AND is_verified = false
+                "#,
+            )
+            .bind(&record.real_info)
+            .bind(record.user_id)
+            .execute(&mut *tx)
+            .await?;
+
+            if result.rows_affected() == 0 {
+                return Err(VerificationError::Conflict(
+                    "用户已完成实名认证或已被禁用".to_string(),
+                ));
+            }
+        }
+
+        sqlx::query(
+            r#"
+            UPDATE verification_requests
+            SET status = $1, reviewer_id = $2, review_reason = $3, reviewed_at = NOW(),
+                real_info = '{}'::jsonb
+            WHERE id = $4
+            "#,
+        )
+        .bind(status.as_str())
+        .bind(reviewer_id)
+        .bind(&reason)
+        .bind(id)
+        .execute(&mut *tx)
+        .await?;
+
+        tx.commit().await?;
+
+        log::info!(
+            "[VerificationService] 实名认证审核完成: id={}, user_id={}, status={}",
+            id,
+            record.user_id,
+            status.as_str()
+        );
+
+        // 通知申请人审核结果
+        if let Err(e) = NotificationService::create_verification_result_notification(
+            pool,
+            record.user_id,
+            status == VerificationStatus::Approved,
+            reason.as_deref(),
+        )
+        .await
+        {
+            log::warn!("[VerificationService] 发送实名认证结果通知失败: {}", e);
+        }
+
+        let (_, response) = Self::get_verification(pool, id, image_base_url).await?;
+        Ok(response)
+    }
+
+    fn build_response(
+        record: VerificationRecord,
+        username: Option<String>,
+        image_base_url: &str,
+    ) -> VerificationResponse {
+        let base_url = image_base_url.trim_end_matches('/');
+        VerificationResponse {
+            id: record.id,
+            user_id: record.user_id,
+            username,
+            proof_image: record.proof_image_id.map(|id| VerificationProofImage {
+                id,
+                url: format!("{}/images/{}", base_url, id),
+            }),
+            status: record.status,
+            reviewer_id: record.reviewer_id,
+            review_reason: record.review_reason,
+            reviewed_at: record.reviewed_at,
+            created_at: record.created_at,
+        }
+    }
+}
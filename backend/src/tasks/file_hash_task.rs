@@ -12,7 +12,7 @@ use sqlx::PgPool;
 use tokio::time::interval;
 use uuid::Uuid;
 
-use crate::services::{FileService, StorageBackend, StorageBackendType};
+use crate::services::{FileService, StorageBackend, StorageError};
 use crate::config::Config;
 
 /// 批次大小（每次处理的数量）
@@ -21,8 +21,6 @@ const BATCH_SIZE: i64 = 50;
 const ALERT_THRESHOLD: i64 = 500;
 /// 最大文件大小 (100MB)
 const MAX_FILE_SIZE: i64 = 100 * 1024 * 1024;
-/// 流式计算缓冲区大小 (64KB)
-const STREAM_BUFFER_SIZE: usize = 64 * 1024;
 
 /// 启动文件哈希计算任务
 ///
@@ -274,22 +272,10 @@ async fn try_calculate_hash(
     _file_size: usize,
 ) -> Result<String, String> {
     // 首先尝试使用当前 storage 读取文件
-    match storage.read_file(file_path).await {
-        Ok(data) => {
-            // 大文件使用流式计算
-            if data.len() > 10 * 1024 * 1024 {
-                let mut cursor = std::io::Cursor::new(&data);
-                match FileService::calculate_hash_streaming(&mut cursor, Some(STREAM_BUFFER_SIZE)).await {
-                    Ok(hash) => return Ok(hash),
-                    Err(e) => return Err(format!("流式计算hash失败: {}", e)),
-                }
-            } else {
-                let hash = FileService::calculate_hash(&data);
-                return Ok(hash);
-            }
-        }
+    match hash_from_storage(storage, file_path).await {
+        Ok(hash) => Ok(hash),
         Err(e) => {
-            // 如果是 OSS 存储失败，尝试创建对应的 storage 实例
+            // 资源与当前存储后端不一致时，尝试创建对应的 storage 实例
             let storage_type: Option<String> = sqlx::query_scalar(
                 "SELECT storage_type FROM resources WHERE file_path = $1"
             )
@@ -300,42 +286,14 @@ async fn try_calculate_hash(
             .flatten();
 
             if let Some(st) = storage_type {
-                if st == "oss" && storage.backend_type() != StorageBackendType::Oss {
-                    // 当前是 local 模式，但资源在 OSS
-                    let config = Config::from_env();
-                    match crate::services::create_storage_backend(&config) {
-                        Ok(oss_storage) if oss_storage.backend_type() == StorageBackendType::Oss => {
-                            match oss_storage.read_file(file_path).await {
-                                Ok(data) => {
-                                    let hash = FileService::calculate_hash(&data);
-                                    return Ok(hash);
-                                }
-                                Err(e2) => {
-                                    return Err(format!("OSS 读取失败: {}", e2));
-                                }
-                            }
-                        }
-                        _ => {}
-                    }
-                } else if st == "local" && storage.backend_type() != StorageBackendType::Local {
-                    // 当前是 OSS 模式，但资源在本地
+                if st != storage.backend_type().as_str() {
                     let config = Config::from_env();
-                    match crate::services::create_local_storage(&config) {
-                        Ok(local_storage) => {
-                            match local_storage.read_file(file_path).await {
-                                Ok(data) => {
-                                    let hash = FileService::calculate_hash(&data);
-                                    return Ok(hash);
-                                }
-                                Err(e2) => {
-                                    return Err(format!("本地读取失败: {}", e2));
-                                }
-                            }
-                        }
-                        Err(e) => {
-                            return Err(format!("创建本地存储失败: {}", e));
-                        }
-                    }
+                    let resource_storage =
+                        crate::services::storage_for_type(storage, &config, Some(&st))
+                            .map_err(|e2| format!("无法访问 {} 存储: {}", st, e2))?;
+                    return hash_from_storage(&resource_storage, file_path)
+                        .await
+                        .map_err(|e2| format!("{} 读取失败: {}", st, e2));
                 }
             }
             Err(format!("读取文件失败: {}", e))
@@ -343,49 +301,60 @@ async fn try_calculate_hash(
     }
 }
 
+/// 流式读取存储中的文件并计算哈希
+async fn hash_from_storage(
+    storage: &Arc<dyn StorageBackend>,
+    file_path: &str,
+) -> Result<String, StorageError> {
+    let file = storage.read_stream(file_path, None).await?;
+    FileService::calculate_hash_from_stream(file.stream).await
+}
+
 /// 立即计算指定资源的哈希
 ///
-/// 用于 OSS 上传回调时立即计算哈希
+/// 用于 OSS 上传回调和存储迁移时立即计算哈希
 /// 返回计算出的哈希值
-#[allow(dead_code)]
 pub async fn compute_hash_for_resource(
     storage: &Arc<dyn StorageBackend>,
     file_path: &str,
 ) -> Result<String, String> {
-    // 读取文件内容
-    let data = storage.read_file(file_path).await
-        .map_err(|e| format!("读取文件失败: {}", e))?;
-
-    // 计算哈希
-    let hash = FileService::calculate_hash(&data);
-    Ok(hash)
+    // 流式读取文件并计算哈希
+    hash_from_storage(storage, file_path)
+        .await
+        .map_err(|e| format!("读取文件失败: {}", e))
 }
 
 /// 计算资源哈希并更新数据库（带验证）
 ///
-/// 用于需要确保hash准确性的场景（如Markdown编辑后）
-/// 会验证写入的内容与读取的内容一致
-#[allow(dead_code)]
+/// 用于需要确保hash准确性的场景（如Markdown编辑后、存储迁移后）
+/// 会验证写入的内容或已知的哈希与读取的内容一致，验证失败时不更新数据库
 pub async fn compute_and_verify_hash(
     pool: &PgPool,
     storage: &Arc<dyn StorageBackend>,
     resource_id: Uuid,
     file_path: &str,
     expected_content: Option<&[u8]>,
+    expected_hash: Option<&str>,
 ) -> Result<String, String> {
-    // 读取文件内容
-    let data = storage.read_file(file_path).await
+    // 流式读取文件并计算哈希
+    let hash = hash_from_storage(storage, file_path)
+        .await
         .map_err(|e| format!("读取文件失败: {}", e))?;
 
-    // 如果提供了预期内容，验证一致性
+    // 如果提供了预期内容，通过哈希验证一致性
     if let Some(expected) = expected_content {
-        if data.as_slice() != expected {
+        if FileService::calculate_hash(expected) != hash {
             return Err("文件内容验证失败：写入的内容与读取的内容不一致".to_string());
         }
     }
-
-    // 计算哈希
-    let hash = FileService::calculate_hash(&data);
+    if let Some(expected) = expected_hash {
+        if expected != hash {
+            return Err(format!(
+                "文件哈希验证失败：期望 {}，实际 {}",
+                expected, hash
+            ));
+        }
+    }
 
     // 更新数据库（使用乐观锁）
     let result = sqlx::query(
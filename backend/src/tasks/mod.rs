@@ -1,3 +1,8 @@
 // 后台任务模块
 
 pub mod file_hash_task;
+pub mod pack_job_task;
+pub mod recommendation_task;
+pub mod session_cleanup_task;
+pub mod storage_migration_task;
+pub mod trending_task;
@@ -0,0 +1,360 @@
+/// 收藏夹打包下载任务
+///
+/// 在后台执行收藏夹打包，避免大收藏夹的读取与压缩长时间占用 HTTP worker：
+/// 1. 接口创建任务后立即返回任务 ID，客户端轮询任务进度
+/// 2. ZIP 先写入本机临时文件，完成后上传到存储后端的临时目录
+/// 3. 定时清理过期的打包文件和历史任务记录
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use sqlx::PgPool;
+use tokio::fs;
+use tokio::io::AsyncWriteExt;
+use tokio::sync::Semaphore;
+use tokio::time::interval;
+use uuid::Uuid;
+
+use crate::config::Config;
+use crate::models::PackJob;
+use crate::services::{
+    file_byte_stream, pack_artifact_key, resolve_pack_storage, AuditLogService,
+    FavoritePackSummary, PackAbort, PackFuture, PackJobService, PackOutput, PackTask,
+    StorageBackend, StorageError,
+};
+
+/// 同时执行的打包任务数（超出的任务保持 pending 排队）
+const MAX_CONCURRENT_JOBS: usize = 2;
+/// 进度写入数据库的最小间隔
+const PROGRESS_REPORT_INTERVAL: Duration = Duration::from_secs(1);
+/// 每轮清理的过期任务数量
+const GC_BATCH_SIZE: i64 = 50;
+/// 失败或过期的任务记录保留时间 (7天)
+const FINISHED_JOB_RETENTION_SECS: u64 = 7 * 24 * 60 * 60;
+
+static JOB_PERMITS: Semaphore = Semaphore::const_new(MAX_CONCURRENT_JOBS);
+
+/// 本机临时文件目录
+fn pack_temp_dir() -> PathBuf {
+    std::env::temp_dir().join("shareustc-packs")
+}
+
+/// 在后台执行打包任务
+pub fn spawn_pack_job(
+    pool: PgPool,
+    storage: Arc<dyn StorageBackend>,
+    config: Config,
+    job: PackJob,
+    task: PackTask,
+    ip_address: Option<String>,
+) {
+    tokio::spawn(async move {
+        let _permit = match JOB_PERMITS.acquire().await {
+            Ok(permit) => permit,
+            Err(e) => {
+                log::error!(
+                    "[PackJobTask] 获取执行许可失败 | job_id={}, error={}",
+                    job.id,
+                    e
+                );
+                return;
+            }
+        };
+
+        if let Err(e) = PackJobService::mark_running(&pool, job.id).await {
+            log::warn!(
+                "[PackJobTask] 更新任务状态失败 | job_id={}, error={}",
+                job.id,
+                e
+            );
+        }
+        log::info!(
+            "[PackJobTask] 开始打包 | job_id={}, favorite={}, files={}",
+            job.id,
+            job.favorite_name,
+            task.total_files()
+        );
+
+        let storage_key = pack_artifact_key(job.id);
+        match run_pack_job(&pool, &storage, &job, &task, &storage_key).await {
+            Ok(summary) => {
+                if let Err(e) = PackJobService::mark_completed(
+                    &pool,
+                    job.id,
+                    &summary,
+                    &storage_key,
+                    storage.backend_type(),
+                    config.pack_job_ttl_secs,
+                )
+                .await
+                {
+                    log::error!(
+                        "[PackJobTask] 更新任务状态失败 | job_id={}, error={}",
+                        job.id,
+                        e
+                    );
+                    return;
+                }
+
+                log::info!(
+                    "[PackJobTask] 打包完成 | job_id={}, 文件数={}, 跳过={}, 大小={} bytes",
+                    job.id,
+                    summary.file_count,
+                    summary.skipped.len(),
+                    summary.total_bytes
+                );
+
+                // 记录审计日志
+                if let Some(favorite_id) = job.favorite_id {
+                    if let Err(e) = AuditLogService::log_pack_download(
+                        &pool,
+                        job.user_id,
+                        favorite_id,
+                        &job.favorite_name,
+                        summary.total_bytes as i64,
+                        summary.file_count,
+                        ip_address.as_deref(),
+                    )
+                    .await
+                    {
+                        log::warn!(
+                            "[Audit] 记录打包下载日志失败 | favorite_id={}, error={}",
+                            favorite_id,
+                            e
+                        );
+                    }
+                }
+            }
+            Err(message) => {
+                log::error!(
+                    "[PackJobTask] 打包失败 | job_id={}, error={}",
+                    job.id,
+                    message
+                );
+                if let Err(e) = PackJobService::mark_failed(&pool, job.id, &message).await {
+                    log::warn!(
+                        "[PackJobTask] 更新任务状态失败 | job_id={}, error={}",
+                        job.id,
+                        e
+                    );
+                }
+            }
+        }
+    });
+}
+
+/// 执行打包：写入临时文件后上传到存储后端
+async fn run_pack_job(
+    pool: &PgPool,
+    storage: &Arc<dyn StorageBackend>,
+    job: &PackJob,
+    task: &PackTask,
+    storage_key: &str,
+) -> Result<FavoritePackSummary, String> {
+    let temp_dir = pack_temp_dir();
+    fs::create_dir_all(&temp_dir)
+        .await
+        .map_err(|e| format!("创建临时目录失败: {}", e))?;
+    let temp_path = temp_dir.join(format!("{}.zip", job.id));
+
+    let result = async {
+        let file = fs::File::create(&temp_path)
+            .await
+            .map_err(|e| format!("创建临时文件失败: {}", e))?;
+
+        let mut output = FilePackOutput {
+            file,
+            pool: pool.clone(),
+            job_id: job.id,
+            last_report: Instant::now(),
+        };
+        let mut summary = FavoritePackSummary::default();
+        task.run(&mut output, &mut summary)
+            .await
+            .map_err(|e| e.to_string())?;
+        output
+            .file
+            .flush()
+            .await
+            .map_err(|e| format!("写入临时文件失败: {}", e))?;
+
+        // 流式上传到存储后端
+        let file = fs::File::open(&temp_path)
+            .await
+            .map_err(|e| format!("读取临时文件失败: {}", e))?;
+        storage
+            .write_stream(
+                storage_key,
+                file_byte_stream(file, summary.total_bytes),
+                Some(summary.total_bytes),
+                Some("application/zip"),
+            )
+            .await
+            .map_err(|e| format!("上传打包文件失败: {}", e))?;
+
+        Ok(summary)
+    }
+    .await;
+
+    if let Err(e) = fs::remove_file(&temp_path).await {
+        if e.kind() != std::io::ErrorKind::NotFound {
+            log::warn!(
+                "[PackJobTask] 删除临时文件失败 | path={}, error={}",
+                temp_path.display(),
+                e
+            );
+        }
+    }
+
+    result
+}
+
+/// 输出到临时文件，并定期把进度写入数据库
+struct FilePackOutput {
+    file: fs::File,
+    pool: PgPool,
+    job_id: Uuid,
+    last_report: Instant,
+}
+
+impl PackOutput for FilePackOutput {
+    fn write_chunk(&mut self, chunk: Vec<u8>) -> PackFuture<'_> {
+        Box::pin(async move {
+            self.file
+                .write_all(&chunk)
+                .await
+                .map_err(|e| PackAbort::Output(e.to_string()))
+        })
+    }
+
+    fn report_progress<'a>(&'a mut self, summary: &'a FavoritePackSummary) -> PackFuture<'a> {
+        Box::pin(async move {
+            if self.last_report.elapsed() < PROGRESS_REPORT_INTERVAL
+                && summary.processed_files() < summary.total_files
+            {
+                return Ok(());
+            }
+            self.last_report = Instant::now();
+
+            // 进度更新失败不影响打包
+            if let Err(e) = PackJobService::update_progress(&self.pool, self.job_id, summary).await
+            {
+                log::warn!(
+                    "[PackJobTask] 更新任务进度失败 | job_id={}, error={}",
+                    self.job_id,
+                    e
+                );
+            }
+            Ok(())
+        })
+    }
+}
+
+/// 启动打包任务清理任务
+///
+/// 在服务启动时调用，会：
+/// 1. 将上次运行中断的任务标记为失败，并清理残留的临时文件
+/// 2. 之后定期删除过期的打包文件和历史任务记录
+pub async fn start_pack_job_gc_task(
+    pool: PgPool,
+    storage: Arc<dyn StorageBackend>,
+    config: Config,
+) {
+    tokio::spawn(async move {
+        log::info!("[PackJobTask] 启动打包文件清理任务");
+
+        match PackJobService::fail_interrupted_jobs(&pool).await {
+            Ok(count) if count > 0 => {
+                log::warn!(
+                    "[PackJobTask] 服务重启，{} 个未完成的打包任务已标记为失败",
+                    count
+                );
+            }
+            Ok(_) => {}
+            Err(e) => log::error!("[PackJobTask] 标记中断任务失败 | error={}", e),
+        }
+
+        if let Err(e) = fs::remove_dir_all(pack_temp_dir()).await {
+            if e.kind() != std::io::ErrorKind::NotFound {
+                log::warn!("[PackJobTask] 清理临时目录失败 | error={}", e);
+            }
+        }
+
+        let mut ticker = interval(Duration::from_secs(config.pack_job_gc_interval_secs));
+        loop {
+            ticker.tick().await;
+            cleanup_expired_jobs(&pool, &storage, &config).await;
+        }
+    });
+}
+
+/// 删除过期的打包文件，并清理历史任务记录
+async fn cleanup_expired_jobs(pool: &PgPool, storage: &Arc<dyn StorageBackend>, config: &Config) {
+    let jobs = match PackJobService::get_expired_jobs(pool, GC_BATCH_SIZE).await {
+        Ok(jobs) => jobs,
+        Err(e) => {
+            log::error!("[PackJobTask] 查询过期任务失败 | error={}", e);
+            return;
+        }
+    };
+
+    let mut removed = 0;
+    for job in jobs {
+        let Some(storage_key) = job.storage_key.as_deref() else {
+            continue;
+        };
+        let storage_type = job.storage_type.as_deref().unwrap_or("local");
+
+        // 删除失败的文件保留记录，下一轮重试
+        let job_storage = match resolve_pack_storage(storage, config, storage_type) {
+            Ok(job_storage) => job_storage,
+            Err(e) => {
+                log::warn!(
+                    "[PackJobTask] 无法访问存储 | job_id={}, error={}",
+                    job.id,
+                    e
+                );
+                continue;
+            }
+        };
+        match job_storage.delete_file(storage_key).await {
+            Ok(()) | Err(StorageError::NotFound(_)) => {}
+            Err(e) => {
+                log::warn!(
+                    "[PackJobTask] 删除打包文件失败 | job_id={}, key={}, error={}",
+                    job.id,
+                    storage_key,
+                    e
+                );
+                continue;
+            }
+        }
+
+        if let Err(e) = PackJobService::mark_expired(pool, job.id).await {
+            log::warn!(
+                "[PackJobTask] 更新任务状态失败 | job_id={}, error={}",
+                job.id,
+                e
+            );
+            continue;
+        }
+        removed += 1;
+    }
+
+    let purged = match PackJobService::purge_finished_jobs(pool, FINISHED_JOB_RETENTION_SECS).await
+    {
+        Ok(count) => count,
+        Err(e) => {
+            log::error!("[PackJobTask] 清理历史任务失败 | error={}", e);
+            0
+        }
+    };
+
+    if removed > 0 || purged > 0 {
+        log::info!(
+            "[PackJobTask] 清理完成 | 过期文件={}, 历史记录={}",
+            removed,
+            purged
+        );
+    }
+}
@@ -0,0 +1,35 @@
+/// 资源推荐任务
+///
+/// 定期根据点赞、收藏、评分和下载记录重新计算资源相似度，
+/// 结果写入 resource_similarities 表，供推荐接口和资源详情页读取
+use std::time::Duration;
+
+use sqlx::PgPool;
+use tokio::time::interval;
+
+use crate::services::RecommendationService;
+
+/// 重新计算间隔：1小时
+const REFRESH_INTERVAL: Duration = Duration::from_secs(60 * 60);
+
+/// 启动资源推荐任务
+pub async fn start_recommendation_task(pool: PgPool) {
+    tokio::spawn(async move {
+        log::info!("[RecommendationTask] 启动资源推荐任务");
+
+        let mut ticker = interval(REFRESH_INTERVAL);
+        loop {
+            ticker.tick().await;
+            match RecommendationService::refresh_similarities(&pool).await {
+                Ok((interaction_count, download_count)) => {
+                    log::info!(
+                        "[RecommendationTask] 资源相似度已更新 | interaction={}, download={}",
+                        interaction_count,
+                        download_count
+                    );
+                }
+                Err(e) => log::error!("[RecommendationTask] 更新资源相似度失败 | error={}", e),
+            }
+        }
+    });
+}
@@ -0,0 +1,41 @@
+/// 登录会话清理任务
+///
+/// 定期删除过期或已吊销的会话记录，吊销记录保留一段时间便于排查；
+/// 同时删除过期的单点登录状态
+use std::time::Duration;
+
+use sqlx::PgPool;
+use tokio::time::interval;
+
+use crate::services::{OidcService, SessionService};
+
+/// 清理间隔：1小时
+const CLEANUP_INTERVAL: Duration = Duration::from_secs(60 * 60);
+/// 过期或吊销的会话保留天数
+const STALE_SESSION_RETENTION_DAYS: i32 = 30;
+
+/// 启动登录会话清理任务
+pub async fn start_session_cleanup_task(pool: PgPool) {
+    tokio::spawn(async move {
+        log::info!("[SessionTask] 启动登录会话清理任务");
+
+        let mut ticker = interval(CLEANUP_INTERVAL);
+        loop {
+            ticker.tick().await;
+            match SessionService::delete_stale_sessions(&pool, STALE_SESSION_RETENTION_DAYS).await {
+                Ok(count) if count > 0 => {
+                    log::info!("[SessionTask] 已清理 {} 条失效的登录会话", count);
+                }
+                Ok(_) => {}
+                Err(e) => log::error!("[SessionTask] 清理登录会话失败 | error={}", e),
+            }
+            match OidcService::delete_expired_states(&pool).await {
+                Ok(count) if count > 0 => {
+                    log::info!("[SessionTask] 已清理 {} 条过期的单点登录状态", count);
+                }
+                Ok(_) => {}
+                Err(e) => log::error!("[SessionTask] 清理单点登录状态失败 | error={}", e),
+            }
+        }
+    });
+}
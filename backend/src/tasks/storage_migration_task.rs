@@ -0,0 +1,272 @@
+/// 存储迁移任务
+///
+/// 由管理员触发，在后台把资源文件、历史版本和图片从一个存储后端复制到另一个：
+/// 1. 逐个文件流式复制到目标存储，通过哈希校验确认内容一致
+/// 2. 校验通过后切换数据库中的 file_path / storage_type
+/// 3. 单个文件失败不影响其他文件，失败明细记录在任务中
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use sqlx::PgPool;
+
+use crate::config::Config;
+use crate::models::{StorageMigrationItemKind, StorageMigrationJob};
+use crate::services::{
+    migration_key, storage_for_type, StorageBackend, StorageBackendType, StorageMigrationItem,
+    StorageMigrationProgress, StorageMigrationService,
+};
+use crate::tasks::file_hash_task::{compute_and_verify_hash, compute_hash_for_resource};
+
+/// 进度写入数据库的最小间隔
+const PROGRESS_REPORT_INTERVAL: Duration = Duration::from_secs(1);
+
+/// 在后台执行迁移任务
+pub fn spawn_storage_migration_job(
+    pool: PgPool,
+    storage: Arc<dyn StorageBackend>,
+    config: Config,
+    job: StorageMigrationJob,
+) {
+    tokio::spawn(async move {
+        match run_migration_job(&pool, &storage, &config, &job).await {
+            Ok(progress) => {
+                if let Err(e) =
+                    StorageMigrationService::mark_completed(&pool, job.id, &progress).await
+                {
+                    log::error!(
+                        "[StorageMigration] 更新任务状态失败 | job_id={}, error={}",
+                        job.id,
+                        e
+                    );
+                    return;
+                }
+                log::info!(
+                    "[StorageMigration] 迁移完成 | job_id={}, {}->{}, 成功={}, 失败={}, 大小={} bytes",
+                    job.id,
+                    job.source_type,
+                    job.target_type,
+                    progress.migrated_items,
+                    progress.failed_items,
+                    progress.bytes_copied
+                );
+            }
+            Err(message) => {
+                log::error!(
+                    "[StorageMigration] 迁移失败 | job_id={}, error={}",
+                    job.id,
+                    message
+                );
+                if let Err(e) = StorageMigrationService::mark_failed(&pool, job.id, &message).await
+                {
+                    log::warn!(
+                        "[StorageMigration] 更新任务状态失败 | job_id={}, error={}",
+                        job.id,
+                        e
+                    );
+                }
+            }
+        }
+    });
+}
+
+/// 执行迁移，返回最终进度；只有无法开始迁移时返回错误
+async fn run_migration_job(
+    pool: &PgPool,
+    storage: &Arc<dyn StorageBackend>,
+    config: &Config,
+    job: &StorageMigrationJob,
+) -> Result<StorageMigrationProgress, String> {
+    let source_type = StorageBackendType::from_str(&job.source_type)
+        .ok_or_else(|| format!("不支持的存储类型: {}", job.source_type))?;
+    let source = storage_for_type(storage, config, Some(&job.source_type))
+        .map_err(|e| format!("无法访问源存储: {}", e))?;
+    let target = storage_for_type(storage, config, Some(&job.target_type))
+        .map_err(|e| format!("无法访问目标存储: {}", e))?;
+
+    let items = StorageMigrationService::collect_items(pool, &job.source_type, job.include_images)
+        .await
+        .map_err(|e| format!("查询待迁移文件失败: {}", e))?;
+    StorageMigrationService::mark_running(pool, job.id, items.len())
+        .await
+        .map_err(|e| format!("更新任务状态失败: {}", e))?;
+    log::info!(
+        "[StorageMigration] 开始迁移 | job_id={}, {}->{}, files={}",
+        job.id,
+        job.source_type,
+        job.target_type,
+        items.len()
+    );
+
+    let context = MigrationContext {
+        pool,
+        source: &source,
+        target: &target,
+        job,
+        source_type,
+        config,
+    };
+    let mut progress = StorageMigrationProgress::default();
+    let mut last_report = Instant::now();
+    for item in &items {
+        match migrate_item(&context, item).await {
+            Ok(bytes) => progress.record_success(bytes),
+            Err(message) => {
+                log::warn!(
+                    "[StorageMigration] 文件迁移失败 | job_id={}, kind={:?}, id={}, path={}, error={}",
+                    job.id,
+                    item.kind,
+                    item.id,
+                    item.file_path,
+                    message
+                );
+                progress.record_failure(item, message);
+            }
+        }
+
+        if last_report.elapsed() >= PROGRESS_REPORT_INTERVAL {
+            last_report = Instant::now();
+            // 进度更新失败不影响迁移
+            if let Err(e) = StorageMigrationService::update_progress(pool, job.id, &progress).await
+            {
+                log::warn!(
+                    "[StorageMigration] 更新任务进度失败 | job_id={}, error={}",
+                    job.id,
+                    e
+                );
+            }
+        }
+    }
+
+    Ok(progress)
+}
+
+struct MigrationContext<'a> {
+    pool: &'a PgPool,
+    source: &'a Arc<dyn StorageBackend>,
+    target: &'a Arc<dyn StorageBackend>,
+    job: &'a StorageMigrationJob,
+    source_type: StorageBackendType,
+    config: &'a Config,
+}
+
+/// 迁移单个文件，返回复制的字节数
+async fn migrate_item(
+    ctx: &MigrationContext<'_>,
+    item: &StorageMigrationItem,
+) -> Result<u64, String> {
+    // 源文件哈希：优先使用已记录的哈希
+    let expected_hash = match &item.file_hash {
+        Some(hash) => hash.clone(),
+        None => compute_hash_for_resource(ctx.source, &item.file_path).await?,
+    };
+
+    let file = ctx
+        .source
+        .read_stream(&item.file_path, None)
+        .await
+        .map_err(|e| format!("读取源文件失败: {}", e))?;
+    let content_length = file.content_length;
+    let key = migration_key(
+        &item.file_path,
+        ctx.source_type,
+        &ctx.config.file_upload_path,
+    );
+    let new_path = ctx
+        .target
+        .write_stream(
+            &key,
+            file.stream,
+            content_length,
+            item.content_type.as_deref(),
+        )
+        .await
+        .map_err(|e| format!("写入目标存储失败: {}", e))?;
+
+    let result = verify_and_switch(ctx, item, &new_path, &expected_hash).await;
+    if let Err(message) = result {
+        // 校验或切换失败时清理已复制的文件，记录仍指向源文件
+        if let Err(e) = ctx.target.delete_file(&new_path).await {
+            log::warn!(
+                "[StorageMigration] 清理目标文件失败 | job_id={}, path={}, error={}",
+                ctx.job.id,
+                new_path,
+                e
+            );
+        }
+        return Err(message);
+    }
+
+    if ctx.job.delete_source {
+        if let Err(e) = ctx.source.delete_file(&item.file_path).await {
+            log::warn!(
+                "[StorageMigration] 删除源文件失败 | job_id={}, path={}, error={}",
+                ctx.job.id,
+                item.file_path,
+                e
+            );
+        }
+    }
+
+    Ok(content_length
+        .or_else(|| item.file_size.map(|size| size.max(0) as u64))
+        .unwrap_or(0))
+}
+
+/// 校验目标文件哈希，通过后切换数据库记录
+async fn verify_and_switch(
+    ctx: &MigrationContext<'_>,
+    item: &StorageMigrationItem,
+    new_path: &str,
+    expected_hash: &str,
+) -> Result<(), String> {
+    if item.kind == StorageMigrationItemKind::Resource {
+        // 资源哈希缺失时顺便补全
+        compute_and_verify_hash(
+            ctx.pool,
+            ctx.target,
+            item.id,
+            new_path,
+            None,
+            Some(expected_hash),
+        )
+        .await?;
+    } else {
+        let hash = compute_hash_for_resource(ctx.target, new_path).await?;
+        if hash != expected_hash {
+            return Err(format!(
+                "文件哈希验证失败：期望 {}，实际 {}",
+                expected_hash, hash
+            ));
+        }
+    }
+
+    let switched = StorageMigrationService::switch_storage(
+        ctx.pool,
+        item,
+        &ctx.job.source_type,
+        &ctx.job.target_type,
+        new_path,
+        expected_hash,
+    )
+    .await
+    .map_err(|e| format!("更新文件记录失败: {}", e))?;
+
+    if !switched {
+        return Err("迁移期间文件记录已变化，已跳过".to_string());
+    }
+    Ok(())
+}
+
+/// 将上次运行中断的迁移任务标记为失败
+pub async fn fail_interrupted_migrations(pool: &PgPool) {
+    match StorageMigrationService::fail_interrupted_jobs(pool).await {
+        Ok(count) if count > 0 => {
+            log::warn!(
+                "[StorageMigration] 服务重启，{} 个未完成的迁移任务已标记为失败",
+                count
+            );
+        }
+        Ok(_) => {}
+        Err(e) => log::error!("[StorageMigration] 标记中断任务失败 | error={}", e),
+    }
+}
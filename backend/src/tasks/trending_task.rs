@@ -0,0 +1,29 @@
+/// 资源热度任务
+///
+/// 定期按 day / week / semester 时间窗口重新计算资源热度分，
+/// 结果写入 resource_trending_scores 表，热门资源接口直接读取
+use std::time::Duration;
+
+use sqlx::PgPool;
+use tokio::time::interval;
+
+use crate::services::ResourceService;
+
+/// 重新计算间隔：15分钟
+const REFRESH_INTERVAL: Duration = Duration::from_secs(15 * 60);
+
+/// 启动资源热度任务
+pub async fn start_trending_task(pool: PgPool) {
+    tokio::spawn(async move {
+        log::info!("[TrendingTask] 启动资源热度任务");
+
+        let mut ticker = interval(REFRESH_INTERVAL);
+        loop {
+            ticker.tick().await;
+            match ResourceService::refresh_trending_scores(&pool).await {
+                Ok(count) => log::debug!("[TrendingTask] 资源热度分已更新 | count={}", count),
+                Err(e) => log::error!("[TrendingTask] 更新资源热度分失败 | error={}", e),
+            }
+        }
+    });
+}
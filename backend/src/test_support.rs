@@ -0,0 +1,54 @@
+// 数据库测试辅助函数
+//
+// 配合 `#[sqlx::test(migrations = false)]` 使用：sqlx 为每个测试创建独立的临时数据库，
+// 这里按初始化脚本建表，表结构与线上保持一致
+
+use sqlx::PgPool;
+use uuid::Uuid;
+
+/// 数据库初始化脚本，SQL 位于 `<< 'EOF'` 与 `EOF` 之间
+const INIT_SCRIPT: &str = include_str!("../../scripts/database/db_init_tables.sh");
+
+/// 按初始化脚本创建全部表、索引和触发器
+pub async fn init_schema(pool: &PgPool) {
+    let sql = INIT_SCRIPT
+        .split_once("<< 'EOF'\n")
+        .and_then(|(_, rest)| rest.split_once("\nEOF\n"))
+        .map(|(sql, _)| sql)
+        .expect("初始化脚本中未找到 SQL");
+    sqlx::raw_sql(sql)
+        .execute(pool)
+        .await
+        .expect("执行初始化脚本失败");
+}
+
+/// 创建普通用户，返回用户ID
+pub async fn create_user(pool: &PgPool, username: &str) -> Uuid {
+    sqlx::query_scalar("INSERT INTO users (username, password_hash) VALUES ($1, '') RETURNING id")
+        .bind(username)
+        .fetch_one(pool)
+        .await
+        .expect("创建测试用户失败")
+}
+
+/// 创建已审核通过的资源（含统计行），返回资源ID
+pub async fn create_resource(pool: &PgPool, uploader_id: Uuid, title: &str) -> Uuid {
+    let id: Uuid = sqlx::query_scalar(
+        r#"
+        INSERT INTO resources (title, uploader_id, resource_type, category, audit_status)
+        VALUES ($1, $2, 'pdf', 'lecture', 'approved')
+        RETURNING id
+        "#,
+    )
+    .bind(title)
+    .bind(uploader_id)
+    .fetch_one(pool)
+    .await
+    .expect("创建测试资源失败");
+    sqlx::query("INSERT INTO resource_stats (resource_id) VALUES ($1)")
+        .bind(id)
+        .execute(pool)
+        .await
+        .expect("创建资源统计失败");
+    id
+}
@@ -0,0 +1,291 @@
+// HTTP 条件请求与范围请求处理
+//
+// 用于文件下载和预览接口：
+// - ETag 使用文件哈希，Last-Modified 使用资源更新时间（查询时按数据库会话时区转换为 UTC）
+// - If-None-Match / If-Modified-Since 命中时返回 304
+// - 支持单个字节范围的 Range 请求，配合 If-Range 实现断点续传
+
+use actix_web::http::header::{self, HeaderMap, HeaderValue};
+use actix_web::{HttpResponse, HttpResponseBuilder};
+use chrono::{DateTime, NaiveDateTime, Timelike, Utc};
+
+use crate::services::ByteRange;
+use crate::utils::error_response;
+
+/// HTTP 日期格式（IMF-fixdate）
+const HTTP_DATE_FORMAT: &str = "%a, %d %b %Y %H:%M:%S GMT";
+
+/// 文件的缓存校验信息
+#[derive(Debug, Clone, Default)]
+pub struct FileValidators {
+    /// 强 ETag（带引号的文件哈希），哈希尚未计算时为 None
+    etag: Option<String>,
+    /// 最后修改时间（UTC，精确到秒）
+    last_modified: Option<NaiveDateTime>,
+}
+
+/// 条件请求的判断结果
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConditionalOutcome {
+    /// 客户端缓存仍然有效，返回 304
+    NotModified,
+    /// 返回文件内容，Some 表示只返回请求的字节范围
+    Content(Option<ByteRange>),
+}
+
+impl FileValidators {
+    pub fn new(file_hash: Option<&str>, last_modified: Option<DateTime<Utc>>) -> Self {
+        FileValidators {
+            etag: file_hash
+                .filter(|hash| !hash.is_empty())
+                .map(|hash| format!("\"{}\"", hash)),
+            last_modified: last_modified.and_then(|time| time.naive_utc().with_nanosecond(0)),
+        }
+    }
+
+    /// 写入 Accept-Ranges、ETag 和 Last-Modified 响应头
+    pub fn insert_headers(&self, builder: &mut HttpResponseBuilder) {
+        builder.insert_header((header::ACCEPT_RANGES, "bytes"));
+        if let Some(etag) = &self.etag {
+            builder.insert_header((header::ETAG, etag.as_str()));
+        }
+        if let Some(last_modified) = self.last_modified {
+            builder.insert_header((header::LAST_MODIFIED, format_http_date(last_modified)));
+        }
+    }
+
+    /// 根据请求头判断返回 304、完整内容还是部分内容
+    pub fn evaluate(&self, headers: &HeaderMap) -> ConditionalOutcome {
+        if self.is_not_modified(headers) {
+            return ConditionalOutcome::NotModified;
+        }
+
+        // 语法错误或多段的 Range 直接忽略，返回完整内容
+        let range = header_str(headers, header::RANGE)
+            .and_then(parse_range_header)
+            .filter(|_| self.if_range_matches(headers));
+        ConditionalOutcome::Content(range)
+    }
+
+    fn is_not_modified(&self, headers: &HeaderMap) -> bool {
+        // 存在 If-None-Match 时忽略 If-Modified-Since
+        if let Some(value) = header_str(headers, header::IF_NONE_MATCH) {
+            return value.trim() == "*"
+                || self
+                    .etag
+                    .as_deref()
+                    .map(|etag| etag_list_contains(value, etag))
+                    .unwrap_or(false);
+        }
+
+        match (
+            self.last_modified,
+            header_str(headers, header::IF_MODIFIED_SINCE).and_then(parse_http_date),
+        ) {
+            (Some(last_modified), Some(since)) => last_modified <= since,
+            _ => false,
+        }
+    }
+
+    /// If-Range 校验：文件未变化时才按范围返回，否则返回完整内容
+    fn if_range_matches(&self, headers: &HeaderMap) -> bool {
+        let Some(value) = header_str(headers, header::IF_RANGE) else {
+            return true;
+        };
+        let value = value.trim();
+
+        if value.starts_with('"') || value.starts_with("W/") {
+            // If-Range 使用强比较，弱 ETag 不会匹配
+            return self.etag.as_deref() == Some(value);
+        }
+
+        match (self.last_modified, parse_http_date(value)) {
+            (Some(last_modified), Some(date)) => last_modified == date,
+            _ => false,
+        }
+    }
+}
+
+/// 构建 304 Not Modified 响应
+pub fn not_modified(validators: &FileValidators) -> HttpResponse {
+    let mut builder = HttpResponse::NotModified();
+    validators.insert_headers(&mut builder);
+    builder.finish()
+}
+
+/// 构建 416 Range Not Satisfiable 响应
+pub fn range_not_satisfiable(total_size: u64) -> HttpResponse {
+    let mut response = error_response(
+        416,
+        &format!("请求范围无效，文件大小为 {} 字节", total_size),
+    );
+    if let Ok(value) = HeaderValue::from_str(&format!("bytes */{}", total_size)) {
+        response.headers_mut().insert(header::CONTENT_RANGE, value);
+    }
+    response
+}
+
+/// 解析 Range 请求头，只支持单个字节范围
+pub fn parse_range_header(value: &str) -> Option<ByteRange> {
+    let (unit, spec) = value.trim().split_once('=')?;
+    if !unit.trim().eq_ignore_ascii_case("bytes") || spec.contains(',') {
+        return None;
+    }
+
+    let (start, end) = spec.trim().split_once('-')?;
+    let (start, end) = (start.trim(), end.trim());
+    if start.is_empty() {
+        return end.parse().ok().map(ByteRange::Suffix);
+    }
+
+    let start: u64 = start.parse().ok()?;
+    if end.is_empty() {
+        return Some(ByteRange::FromTo(start, None));
+    }
+    let end: u64 = end.parse().ok()?;
+    if end < start {
+        return None;
+    }
+    Some(ByteRange::FromTo(start, Some(end)))
+}
+
+fn header_str(headers: &HeaderMap, name: header::HeaderName) -> Option<&str> {
+    headers.get(name).and_then(|value| value.to_str().ok())
+}
+
+/// 检查 If-None-Match 列表中是否包含指定 ETag（弱比较）
+fn etag_list_contains(list: &str, etag: &str) -> bool {
+    let etag = etag.trim_start_matches("W/");
+    list.split(',')
+        .map(|tag| tag.trim().trim_start_matches("W/"))
+        .any(|tag| tag == etag)
+}
+
+fn format_http_date(time: NaiveDateTime) -> String {
+    time.format(HTTP_DATE_FORMAT).to_string()
+}
+
+fn parse_http_date(value: &str) -> Option<NaiveDateTime> {
+    NaiveDateTime::parse_from_str(value.trim(), HTTP_DATE_FORMAT).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::NaiveDate;
+
+    fn updated_at() -> NaiveDateTime {
+        NaiveDate::from_ymd_opt(2024, 3, 1)
+            .unwrap()
+            .and_hms_milli_opt(8, 30, 15, 250)
+            .unwrap()
+    }
+
+    fn validators() -> FileValidators {
+        FileValidators::new(Some("abc123"), Some(updated_at().and_utc()))
+    }
+
+    fn headers(pairs: &[(header::HeaderName, &str)]) -> HeaderMap {
+        let mut map = HeaderMap::new();
+        for (name, value) in pairs {
+            map.insert(name.clone(), HeaderValue::from_str(value).unwrap());
+        }
+        map
+    }
+
+    #[test]
+    fn test_parse_range_header() {
+        assert_eq!(
+            parse_range_header("bytes=0-99"),
+            Some(ByteRange::FromTo(0, Some(99)))
+        );
+        assert_eq!(
+            parse_range_header("bytes=100-"),
+            Some(ByteRange::FromTo(100, None))
+        );
+        assert_eq!(parse_range_header("bytes=-20"), Some(ByteRange::Suffix(20)));
+        // 不支持的格式直接忽略
+        assert_eq!(parse_range_header("bytes=0-9,20-29"), None);
+        assert_eq!(parse_range_header("bytes=9-0"), None);
+        assert_eq!(parse_range_header("items=0-9"), None);
+        assert_eq!(parse_range_header("bytes=abc"), None);
+    }
+
+    #[test]
+    fn test_http_date_round_trip() {
+        let formatted = format_http_date(updated_at());
+        assert_eq!(formatted, "Fri, 01 Mar 2024 08:30:15 GMT");
+        assert_eq!(parse_http_date(&formatted), updated_at().with_nanosecond(0));
+    }
+
+    #[test]
+    fn test_if_none_match() {
+        let v = validators();
+        let h = headers(&[(header::IF_NONE_MATCH, "\"other\", W/\"abc123\"")]);
+        assert_eq!(v.evaluate(&h), ConditionalOutcome::NotModified);
+
+        let h = headers(&[(header::IF_NONE_MATCH, "\"other\"")]);
+        assert_eq!(v.evaluate(&h), ConditionalOutcome::Content(None));
+
+        // 存在 If-None-Match 时忽略 If-Modified-Since
+        let h = headers(&[
+            (header::IF_NONE_MATCH, "\"other\""),
+            (header::IF_MODIFIED_SINCE, "Fri, 01 Mar 2024 08:30:15 GMT"),
+        ]);
+        assert_eq!(v.evaluate(&h), ConditionalOutcome::Content(None));
+    }
+
+    #[test]
+    fn test_if_modified_since() {
+        let v = validators();
+        let h = headers(&[(header::IF_MODIFIED_SINCE, "Fri, 01 Mar 2024 08:30:15 GMT")]);
+        assert_eq!(v.evaluate(&h), ConditionalOutcome::NotModified);
+
+        let h = headers(&[(header::IF_MODIFIED_SINCE, "Fri, 01 Mar 2024 08:30:14 GMT")]);
+        assert_eq!(v.evaluate(&h), ConditionalOutcome::Content(None));
+    }
+
+    #[test]
+    fn test_if_range() {
+        let v = validators();
+        let range = Some(ByteRange::FromTo(10, None));
+
+        let h = headers(&[(header::RANGE, "bytes=10-")]);
+        assert_eq!(v.evaluate(&h), ConditionalOutcome::Content(range));
+
+        let h = headers(&[
+            (header::RANGE, "bytes=10-"),
+            (header::IF_RANGE, "\"abc123\""),
+        ]);
+        assert_eq!(v.evaluate(&h), ConditionalOutcome::Content(range));
+
+        let h = headers(&[
+            (header::RANGE, "bytes=10-"),
+            (header::IF_RANGE, "Fri, 01 Mar 2024 08:30:15 GMT"),
+        ]);
+        assert_eq!(v.evaluate(&h), ConditionalOutcome::Content(range));
+
+        // 文件已变化或使用弱 ETag 时返回完整内容
+        for if_range in [
+            "\"changed\"",
+            "W/\"abc123\"",
+            "Thu, 29 Feb 2024 08:30:15 GMT",
+        ] {
+            let h = headers(&[(header::RANGE, "bytes=10-"), (header::IF_RANGE, if_range)]);
+            assert_eq!(v.evaluate(&h), ConditionalOutcome::Content(None));
+        }
+    }
+
+    #[test]
+    fn test_validators_without_hash() {
+        let v = FileValidators::new(None, Some(updated_at().and_utc()));
+        let h = headers(&[(header::IF_NONE_MATCH, "\"abc123\"")]);
+        assert_eq!(v.evaluate(&h), ConditionalOutcome::Content(None));
+
+        let h = headers(&[
+            (header::RANGE, "bytes=0-9"),
+            (header::IF_RANGE, "\"abc123\""),
+        ]);
+        assert_eq!(v.evaluate(&h), ConditionalOutcome::Content(None));
+    }
+}
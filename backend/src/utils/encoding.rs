@@ -0,0 +1,48 @@
+// 编码工具
+
+/// 按 RFC 3986 对字符串进行 percent-encoding，只保留非保留字符（字母数字和 `-_.~`）
+///
+/// `encode_slash` 为 false 时保留 `/`，用于对象存储路径；查询参数、签名串等需要编码 `/`
+pub fn percent_encode(input: &str, encode_slash: bool) -> String {
+    let mut encoded = String::new();
+    for &byte in input.as_bytes() {
+        let is_unreserved =
+            byte.is_ascii_alphanumeric() || matches!(byte, b'-' | b'_' | b'.' | b'~');
+        let is_slash = byte == b'/';
+        if is_unreserved || (!encode_slash && is_slash) {
+            encoded.push(byte as char);
+        } else {
+            encoded.push('%');
+            encoded.push_str(&format!("{:02X}", byte));
+        }
+    }
+    encoded
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_percent_encode_keeps_unreserved() {
+        assert_eq!(percent_encode("abcXYZ019-_.~", true), "abcXYZ019-_.~");
+    }
+
+    #[test]
+    fn test_percent_encode_reserved_and_utf8() {
+        assert_eq!(percent_encode("a b&c=d", true), "a%20b%26c%3Dd");
+        assert_eq!(percent_encode("课", true), "%E8%AF%BE");
+    }
+
+    #[test]
+    fn test_percent_encode_slash() {
+        assert_eq!(
+            percent_encode("resources/a b.pdf", false),
+            "resources/a%20b.pdf"
+        );
+        assert_eq!(
+            percent_encode("resources/a b.pdf", true),
+            "resources%2Fa%20b.pdf"
+        );
+    }
+}
@@ -0,0 +1,332 @@
+// 数据库字段加密工具
+
+use aes_gcm::aead::{Aead, KeyInit, Payload};
+use aes_gcm::{Aes256Gcm, Nonce};
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use rand::{rngs::OsRng, RngCore};
+use uuid::Uuid;
+
+/// 加密字段值的前缀，完整格式为 `enc:v1:{密钥编号}:{Base64(nonce || 密文)}`
+const ENCRYPTED_PREFIX: &str = "enc:v1:";
+
+const NONCE_LEN: usize = 12;
+
+/// 字段加密错误类型
+#[derive(Debug)]
+pub enum FieldCipherError {
+    ConfigError(String),
+    /// 数据使用的密钥未配置
+    UnknownKey(String),
+    /// 数据已加密，但未配置任何密钥
+    MissingKey(String),
+    /// 密文格式错误或校验失败
+    DecryptFailed(String),
+    EncryptFailed(String),
+}
+
+impl std::fmt::Display for FieldCipherError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            FieldCipherError::ConfigError(msg) => write!(f, "加密密钥配置错误: {}", msg),
+            FieldCipherError::UnknownKey(msg) => write!(f, "未知的加密密钥: {}", msg),
+            FieldCipherError::MissingKey(msg) => write!(f, "未配置加密密钥，无法解密: {}", msg),
+            FieldCipherError::DecryptFailed(msg) => write!(f, "字段解密失败: {}", msg),
+            FieldCipherError::EncryptFailed(msg) => write!(f, "字段加密失败: {}", msg),
+        }
+    }
+}
+
+impl std::error::Error for FieldCipherError {}
+
+/// 数据库字段加密器，使用 AES-256-GCM，支持多密钥轮换
+///
+/// 关联数据为 `{所属记录ID}:{字段名}`，密文不能被挪用到其他记录或字段。
+/// 各功能使用独立的密钥配置，轮换一个功能的密钥不影响其他功能
+pub struct FieldCipher {
+    /// 第一个密钥用于加密，其余只用于解密
+    keys: Vec<(String, Aes256Gcm)>,
+}
+
+impl FieldCipher {
+    /// 解析 `密钥编号:Base64 编码的 32 字节密钥` 列表，列表为空时返回 None
+    pub fn from_keys(entries: &[String]) -> Result<Option<Self>, FieldCipherError> {
+        if entries.is_empty() {
+            return Ok(None);
+        }
+
+        let mut keys: Vec<(String, Aes256Gcm)> = Vec::new();
+        for entry in entries {
+            let (key_id, encoded) = entry.split_once(':').ok_or_else(|| {
+                FieldCipherError::ConfigError("密钥格式应为 密钥编号:Base64密钥".to_string())
+            })?;
+            let key_id = key_id.trim();
+            if key_id.is_empty()
+                || !key_id
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
+            {
+                return Err(FieldCipherError::ConfigError(format!(
+                    "密钥编号只能包含字母、数字、下划线和连字符: {:?}",
+                    key_id
+                )));
+            }
+            if keys.iter().any(|(id, _)| id == key_id) {
+                return Err(FieldCipherError::ConfigError(format!(
+                    "密钥编号重复: {}",
+                    key_id
+                )));
+            }
+            let key = STANDARD.decode(encoded.trim()).map_err(|_| {
+                FieldCipherError::ConfigError(format!("密钥 {} 不是有效的 Base64", key_id))
+            })?;
+            let cipher = Aes256Gcm::new_from_slice(&key).map_err(|_| {
+                FieldCipherError::ConfigError(format!("密钥 {} 长度必须为 32 字节", key_id))
+            })?;
+            keys.push((key_id.to_string(), cipher));
+        }
+        Ok(Some(Self { keys }))
+    }
+
+    /// 当前用于加密的密钥编号
+    pub fn current_key_id(&self) -> &str {
+        &self.keys[0].0
+    }
+
+    /// 使用当前密钥加密字段值
+    pub fn encrypt(
+        &self,
+        owner_id: Uuid,
+        field: &str,
+        plaintext: &str,
+    ) -> Result<String, FieldCipherError> {
+        let (key_id, cipher) = &self.keys[0];
+        let mut nonce = [0u8; NONCE_LEN];
+        OsRng.fill_bytes(&mut nonce);
+        let aad = associated_data(owner_id, field);
+        let ciphertext = cipher
+            .encrypt(
+                Nonce::from_slice(&nonce),
+                Payload {
+                    msg: plaintext.as_bytes(),
+                    aad: aad.as_bytes(),
+                },
+            )
+            .map_err(|_| FieldCipherError::EncryptFailed(field.to_string()))?;
+
+        let mut sealed = nonce.to_vec();
+        sealed.extend_from_slice(&ciphertext);
+        Ok(format!(
+            "{}{}:{}",
+            ENCRYPTED_PREFIX,
+            key_id,
+            STANDARD.encode(sealed)
+        ))
+    }
+
+    /// 解密字段值，按密文中的密钥编号选择密钥
+    pub fn decrypt(
+        &self,
+        owner_id: Uuid,
+        field: &str,
+        value: &str,
+    ) -> Result<String, FieldCipherError> {
+        let (key_id, encoded) = parse_encrypted(value)
+            .ok_or_else(|| FieldCipherError::DecryptFailed(format!("{} 密文格式错误", field)))?;
+        let cipher = self
+            .keys
+            .iter()
+            .find(|(id, _)| id == key_id)
+            .map(|(_, cipher)| cipher)
+            .ok_or_else(|| FieldCipherError::UnknownKey(key_id.to_string()))?;
+
+        let sealed = STANDARD
+            .decode(encoded)
+            .map_err(|_| FieldCipherError::DecryptFailed(format!("{} 密文格式错误", field)))?;
+        if sealed.len() <= NONCE_LEN {
+            return Err(FieldCipherError::DecryptFailed(format!(
+                "{} 密文长度错误",
+                field
+            )));
+        }
+        let (nonce, ciphertext) = sealed.split_at(NONCE_LEN);
+        let aad = associated_data(owner_id, field);
+        let plaintext = cipher
+            .decrypt(
+                Nonce::from_slice(nonce),
+                Payload {
+                    msg: ciphertext,
+                    aad: aad.as_bytes(),
+                },
+            )
+            .map_err(|_| FieldCipherError::DecryptFailed(format!("{} 校验失败", field)))?;
+        String::from_utf8(plaintext)
+            .map_err(|_| FieldCipherError::DecryptFailed(format!("{} 不是有效的 UTF-8", field)))
+    }
+}
+
+/// 加密字段值，未配置密钥或已加密时原样返回
+pub fn seal_field(
+    cipher: Option<&FieldCipher>,
+    owner_id: Uuid,
+    field: &str,
+    value: &str,
+) -> Result<String, FieldCipherError> {
+    match cipher {
+        Some(cipher) if parse_encrypted(value).is_none() => cipher.encrypt(owner_id, field, value),
+        _ => Ok(value.to_string()),
+    }
+}
+
+/// 解密字段值，兼容尚未加密的明文数据
+pub fn open_field(
+    cipher: Option<&FieldCipher>,
+    owner_id: Uuid,
+    field: &str,
+    value: &str,
+) -> Result<String, FieldCipherError> {
+    if parse_encrypted(value).is_none() {
+        return Ok(value.to_string());
+    }
+    cipher
+        .ok_or_else(|| FieldCipherError::MissingKey(field.to_string()))?
+        .decrypt(owner_id, field, value)
+}
+
+/// 将明文或旧密钥加密的字段值用当前密钥重新加密，已是最新时返回 None
+pub fn reseal_field(
+    cipher: &FieldCipher,
+    owner_id: Uuid,
+    field: &str,
+    value: &str,
+) -> Result<Option<String>, FieldCipherError> {
+    let plaintext = match parse_encrypted(value) {
+        Some((key_id, _)) if key_id == cipher.current_key_id() => return Ok(None),
+        Some(_) => cipher.decrypt(owner_id, field, value)?,
+        None => value.to_string(),
+    };
+    cipher.encrypt(owner_id, field, &plaintext).map(Some)
+}
+
+fn associated_data(owner_id: Uuid, field: &str) -> String {
+    format!("{}:{}", owner_id, field)
+}
+
+/// 拆分加密字段值，返回 (密钥编号, Base64 密文)；明文返回 None
+fn parse_encrypted(value: &str) -> Option<(&str, &str)> {
+    value.strip_prefix(ENCRYPTED_PREFIX)?.split_once(':')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cipher(keys: &[String]) -> FieldCipher {
+        FieldCipher::from_keys(keys).unwrap().unwrap()
+    }
+
+    fn key(id: &str, byte: u8) -> String {
+        format!("{}:{}", id, STANDARD.encode([byte; 32]))
+    }
+
+    #[test]
+    fn test_seal_and_open_round_trip() {
+        let cipher = cipher(&[key("k1", 1)]);
+        let owner_id = Uuid::new_v4();
+
+        let sealed = seal_field(Some(&cipher), owner_id, "secret", "JBSWY3DP").unwrap();
+        assert!(sealed.starts_with("enc:v1:k1:"));
+        assert!(!sealed.contains("JBSWY3DP"));
+        // 已加密的值不会被重复加密
+        assert_eq!(
+            seal_field(Some(&cipher), owner_id, "secret", &sealed).unwrap(),
+            sealed
+        );
+        assert_eq!(
+            open_field(Some(&cipher), owner_id, "secret", &sealed).unwrap(),
+            "JBSWY3DP"
+        );
+    }
+
+    #[test]
+    fn test_ciphertext_bound_to_owner_and_field() {
+        let cipher = cipher(&[key("k1", 1)]);
+        let owner_id = Uuid::new_v4();
+        let sealed = seal_field(Some(&cipher), owner_id, "secret", "JBSWY3DP").unwrap();
+
+        assert!(open_field(Some(&cipher), Uuid::new_v4(), "secret", &sealed).is_err());
+        assert!(open_field(Some(&cipher), owner_id, "other", &sealed).is_err());
+    }
+
+    #[test]
+    fn test_plaintext_and_missing_key() {
+        let owner_id = Uuid::new_v4();
+        assert_eq!(
+            seal_field(None, owner_id, "secret", "JBSWY3DP").unwrap(),
+            "JBSWY3DP"
+        );
+        assert_eq!(
+            open_field(None, owner_id, "secret", "JBSWY3DP").unwrap(),
+            "JBSWY3DP"
+        );
+
+        let cipher = cipher(&[key("k1", 1)]);
+        let sealed = seal_field(Some(&cipher), owner_id, "secret", "JBSWY3DP").unwrap();
+        assert!(matches!(
+            open_field(None, owner_id, "secret", &sealed),
+            Err(FieldCipherError::MissingKey(_))
+        ));
+    }
+
+    #[test]
+    fn test_key_rotation() {
+        let owner_id = Uuid::new_v4();
+        let old = cipher(&[key("k1", 1)]);
+        let sealed = seal_field(Some(&old), owner_id, "secret", "JBSWY3DP").unwrap();
+
+        // 新密钥在前，旧密钥仍可解密
+        let rotated = cipher(&[key("k2", 2), key("k1", 1)]);
+        assert_eq!(
+            open_field(Some(&rotated), owner_id, "secret", &sealed).unwrap(),
+            "JBSWY3DP"
+        );
+        let resealed = reseal_field(&rotated, owner_id, "secret", &sealed)
+            .unwrap()
+            .unwrap();
+        assert!(resealed.starts_with("enc:v1:k2:"));
+        assert!(reseal_field(&rotated, owner_id, "secret", &resealed)
+            .unwrap()
+            .is_none());
+
+        // 移除旧密钥后只能解密重新加密的数据
+        let new_only = cipher(&[key("k2", 2)]);
+        assert!(matches!(
+            open_field(Some(&new_only), owner_id, "secret", &sealed),
+            Err(FieldCipherError::UnknownKey(_))
+        ));
+        assert_eq!(
+            open_field(Some(&new_only), owner_id, "secret", &resealed).unwrap(),
+            "JBSWY3DP"
+        );
+
+        // 明文数据同样会被加密
+        let from_plaintext = reseal_field(&new_only, owner_id, "secret", "JBSWY3DP")
+            .unwrap()
+            .unwrap();
+        assert!(from_plaintext.starts_with("enc:v1:k2:"));
+    }
+
+    #[test]
+    fn test_invalid_key_config() {
+        for keys in [
+            vec!["no-separator".to_string()],
+            vec!["k1:not-base64!".to_string()],
+            vec![format!("k1:{}", STANDARD.encode([1u8; 16]))],
+            vec![key("k1", 1), key("k1", 2)],
+            vec![format!("bad id:{}", STANDARD.encode([1u8; 32]))],
+        ] {
+            assert!(FieldCipher::from_keys(&keys).is_err());
+        }
+        assert!(FieldCipher::from_keys(&[]).unwrap().is_none());
+    }
+}
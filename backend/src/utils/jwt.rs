@@ -5,8 +5,10 @@ use uuid::Uuid;
 
 /// Access Token 有效期：60分钟（1小时）
 const ACCESS_TOKEN_EXPIRE_MINUTES: i64 = 60;
-/// Refresh Token 有效期：7天
-const REFRESH_TOKEN_EXPIRE_DAYS: i64 = 7;
+/// Refresh Token 有效期：7天（同时也是登录会话的有效期）
+pub const REFRESH_TOKEN_EXPIRE_DAYS: i64 = 7;
+/// 两步验证临时令牌有效期：5分钟
+pub const MFA_TOKEN_EXPIRE_MINUTES: i64 = 5;
 
 /// 生成 Access Token
 ///
@@ -15,6 +17,8 @@ const REFRESH_TOKEN_EXPIRE_DAYS: i64 = 7;
 /// * `username` - 用户名
 /// * `role` - 用户角色
 /// * `is_verified` - 是否实名认证
+/// * `session_id` - 登录会话ID
+/// * `token_version` - 用户令牌版本
 /// * `secret` - JWT密钥
 ///
 /// # Returns
@@ -25,6 +29,8 @@ pub fn generate_access_token(
     username: String,
     role: UserRole,
     is_verified: bool,
+    session_id: Uuid,
+    token_version: i32,
     secret: &str,
 ) -> Result<String, String> {
     let now = Utc::now();
@@ -38,6 +44,9 @@ pub fn generate_access_token(
         exp: exp.timestamp(),
         iat: now.timestamp(),
         token_type: "access".to_string(),
+        sid: Some(session_id),
+        ver: token_version,
+        jti: None,
     };
 
     encode(
@@ -55,6 +64,8 @@ pub fn generate_access_token(
 /// * `username` - 用户名
 /// * `role` - 用户角色
 /// * `is_verified` - 是否实名认证
+/// * `session_id` - 登录会话ID
+/// * `token_version` - 用户令牌版本
 /// * `secret` - JWT密钥
 ///
 /// # Returns
@@ -65,6 +76,8 @@ pub fn generate_refresh_token(
     username: String,
     role: UserRole,
     is_verified: bool,
+    session_id: Uuid,
+    token_version: i32,
     secret: &str,
 ) -> Result<String, String> {
     let now = Utc::now();
@@ -78,6 +91,9 @@ pub fn generate_refresh_token(
         exp: exp.timestamp(),
         iat: now.timestamp(),
         token_type: "refresh".to_string(),
+        sid: Some(session_id),
+        ver: token_version,
+        jti: Some(Uuid::new_v4().to_string()),
     };
 
     encode(
@@ -88,6 +104,49 @@ pub fn generate_refresh_token(
     .map_err(|e| format!("生成Refresh Token失败: {}", e))
 }
 
+/// 生成两步验证临时令牌
+///
+/// 密码校验通过后签发，只能用于提交登录第二步的验证码，不能访问其他接口
+///
+/// # Arguments
+/// * `user_id` - 用户ID
+/// * `username` - 用户名
+/// * `token_version` - 用户令牌版本
+/// * `secret` - JWT密钥
+///
+/// # Returns
+/// * `Ok(String)` - JWT Token
+/// * `Err(String)` - 错误信息
+pub fn generate_mfa_token(
+    user_id: Uuid,
+    username: String,
+    token_version: i32,
+    secret: &str,
+) -> Result<String, String> {
+    let now = Utc::now();
+    let exp = now + Duration::minutes(MFA_TOKEN_EXPIRE_MINUTES);
+
+    let claims = Claims {
+        sub: user_id.to_string(),
+        username,
+        role: String::new(),
+        is_verified: false,
+        exp: exp.timestamp(),
+        iat: now.timestamp(),
+        token_type: "mfa".to_string(),
+        sid: None,
+        ver: token_version,
+        jti: Some(Uuid::new_v4().to_string()),
+    };
+
+    encode(
+        &Header::default(),
+        &claims,
+        &EncodingKey::from_secret(secret.as_bytes()),
+    )
+    .map_err(|e| format!("生成两步验证令牌失败: {}", e))
+}
+
 /// 验证 Token
 ///
 /// # Arguments
@@ -145,6 +204,7 @@ pub fn extract_current_user(claims: Claims) -> Result<CurrentUser, String> {
         username: claims.username,
         role,
         is_verified: claims.is_verified,
+        session_id: claims.sid,
     })
 }
 
@@ -160,9 +220,18 @@ mod tests {
         let username = "testuser".to_string();
         let role = UserRole::User;
 
-        let token =
-            generate_access_token(user_id, username.clone(), role.clone(), false, TEST_SECRET)
-                .expect("生成Token失败");
+        let session_id = Uuid::new_v4();
+
+        let token = generate_access_token(
+            user_id,
+            username.clone(),
+            role.clone(),
+            false,
+            session_id,
+            3,
+            TEST_SECRET,
+        )
+        .expect("生成Token失败");
 
         // 验证Token
         let claims = verify_token(&token, TEST_SECRET, Some("access")).expect("验证Token失败");
@@ -172,6 +241,8 @@ mod tests {
         assert_eq!(claims.role, "user");
         assert_eq!(claims.is_verified, false);
         assert_eq!(claims.token_type, "access");
+        assert_eq!(claims.sid, Some(session_id));
+        assert_eq!(claims.ver, 3);
     }
 
     #[test]
@@ -180,9 +251,18 @@ mod tests {
         let username = "testuser".to_string();
         let role = UserRole::User;
 
-        let token =
-            generate_refresh_token(user_id, username.clone(), role.clone(), false, TEST_SECRET)
-                .expect("生成Token失败");
+        let session_id = Uuid::new_v4();
+
+        let token = generate_refresh_token(
+            user_id,
+            username.clone(),
+            role.clone(),
+            false,
+            session_id,
+            0,
+            TEST_SECRET,
+        )
+        .expect("生成Token失败");
 
         // 验证Token
         let claims = verify_token(&token, TEST_SECRET, Some("refresh")).expect("验证Token失败");
@@ -190,6 +270,35 @@ mod tests {
         assert_eq!(claims.sub, user_id.to_string());
         assert_eq!(claims.username, username);
         assert_eq!(claims.token_type, "refresh");
+        assert_eq!(claims.sid, Some(session_id));
+    }
+
+    #[test]
+    fn test_refresh_tokens_are_unique() {
+        // 同一秒内轮换的 Refresh Token 也必须不同，否则无法识别重放
+        let user_id = Uuid::new_v4();
+        let session_id = Uuid::new_v4();
+        let first = generate_refresh_token(
+            user_id,
+            "testuser".to_string(),
+            UserRole::User,
+            false,
+            session_id,
+            0,
+            TEST_SECRET,
+        )
+        .expect("生成Token失败");
+        let second = generate_refresh_token(
+            user_id,
+            "testuser".to_string(),
+            UserRole::User,
+            false,
+            session_id,
+            0,
+            TEST_SECRET,
+        )
+        .expect("生成Token失败");
+        assert_ne!(first, second);
     }
 
     #[test]
@@ -199,8 +308,16 @@ mod tests {
         let role = UserRole::User;
 
         // 生成Access Token
-        let access_token = generate_access_token(user_id, username, role, false, TEST_SECRET)
-            .expect("生成Token失败");
+        let access_token = generate_access_token(
+            user_id,
+            username,
+            role,
+            false,
+            Uuid::new_v4(),
+            0,
+            TEST_SECRET,
+        )
+        .expect("生成Token失败");
 
         // 尝试用refresh类型验证access token
         let result = verify_token(&access_token, TEST_SECRET, Some("refresh"));
@@ -208,6 +325,18 @@ mod tests {
         assert!(result.unwrap_err().contains("Token类型不匹配"));
     }
 
+    #[test]
+    fn test_mfa_token_cannot_be_used_as_access_token() {
+        let user_id = Uuid::new_v4();
+        let token = generate_mfa_token(user_id, "testuser".to_string(), 2, TEST_SECRET)
+            .expect("生成Token失败");
+
+        let claims = verify_token(&token, TEST_SECRET, Some("mfa")).expect("验证Token失败");
+        assert_eq!(claims.sub, user_id.to_string());
+        assert_eq!(claims.ver, 2);
+        assert!(verify_token(&token, TEST_SECRET, Some("access")).is_err());
+    }
+
     #[test]
     fn test_invalid_token() {
         let result = verify_token("invalid.token.here", TEST_SECRET, None);
@@ -225,6 +354,9 @@ mod tests {
             exp: Utc::now().timestamp() + 3600,
             iat: Utc::now().timestamp(),
             token_type: "access".to_string(),
+            sid: None,
+            ver: 0,
+            jti: None,
         };
 
         let current_user = extract_current_user(claims).expect("提取用户信息失败");
@@ -233,5 +365,31 @@ mod tests {
         assert_eq!(current_user.username, "testuser");
         assert_eq!(current_user.role, UserRole::Admin);
         assert_eq!(current_user.is_verified, true);
+        assert_eq!(current_user.session_id, None);
+    }
+
+    #[test]
+    fn test_legacy_token_without_session() {
+        // 旧版 Token 没有 sid/ver 字段，解析时使用默认值
+        let now = Utc::now().timestamp();
+        let legacy = serde_json::json!({
+            "sub": Uuid::new_v4().to_string(),
+            "username": "testuser",
+            "role": "user",
+            "is_verified": false,
+            "exp": now + 3600,
+            "iat": now,
+            "token_type": "access",
+        });
+        let token = encode(
+            &Header::default(),
+            &legacy,
+            &EncodingKey::from_secret(TEST_SECRET.as_bytes()),
+        )
+        .unwrap();
+
+        let claims = verify_token(&token, TEST_SECRET, Some("access")).expect("验证Token失败");
+        assert_eq!(claims.sid, None);
+        assert_eq!(claims.ver, 0);
     }
 }
@@ -1,9 +1,19 @@
 // 工具函数模块
 
+pub mod conditional;
+pub mod encoding;
+pub mod field_cipher;
 pub mod hash;
 pub mod jwt;
 pub mod response;
+pub mod search;
+pub mod zip_stream;
 
+pub use conditional::*;
+pub use encoding::*;
+pub use field_cipher::*;
 pub use hash::*;
 pub use jwt::*;
 pub use response::*;
+pub use search::*;
+pub use zip_stream::*;
@@ -1,6 +1,10 @@
 // 统一响应处理工具
 
-use actix_web::HttpResponse;
+use actix_web::http::{header, StatusCode};
+use actix_web::{web, HttpResponse, HttpResponseBuilder};
+use futures_util::StreamExt;
+
+use crate::services::StorageReadStream;
 
 /// 对文件名进行 RFC 5987 编码，用于支持中文等非 ASCII 字符
 /// 参考: https://datatracker.ietf.org/doc/html/rfc5987
@@ -49,6 +53,25 @@ pub fn build_content_disposition(filename: &str) -> String {
     }
 }
 
+/// 以流式响应返回存储中的文件
+/// 已知长度时设置 Content-Length，避免使用分块传输；按范围读取时返回 206
+pub fn stream_file(mut builder: HttpResponseBuilder, file: StorageReadStream) -> HttpResponse {
+    if let Some((start, end)) = file.range {
+        let total_size = file
+            .total_size
+            .map(|size| size.to_string())
+            .unwrap_or_else(|| "*".to_string());
+        builder.status(StatusCode::PARTIAL_CONTENT).insert_header((
+            header::CONTENT_RANGE,
+            format!("bytes {}-{}/{}", start, end, total_size),
+        ));
+    }
+    if let Some(content_length) = file.content_length {
+        builder.no_chunking(content_length);
+    }
+    builder.streaming(file.stream.map(|chunk| chunk.map(web::Bytes::from)))
+}
+
 /// 构建错误响应
 pub fn error_response(status: u16, message: &str) -> HttpResponse {
     let error = match status {
@@ -57,7 +80,9 @@ pub fn error_response(status: u16, message: &str) -> HttpResponse {
         403 => "Forbidden",
         404 => "NotFound",
         409 => "Conflict",
+        416 => "RangeNotSatisfiable",
         422 => "UnprocessableEntity",
+        429 => "TooManyRequests",
         500 => "InternalServerError",
         502 => "BadGateway",
         503 => "ServiceUnavailable",
@@ -99,6 +124,18 @@ pub fn conflict(message: &str) -> HttpResponse {
     error_response(409, message)
 }
 
+/// 构建 429 Too Many Requests 错误，并通过 Retry-After 告知需要等待的秒数
+pub fn too_many_requests(message: &str, retry_after_secs: u64) -> HttpResponse {
+    let mut response = error_response(429, message);
+    if let Ok(value) = actix_web::http::header::HeaderValue::from_str(&retry_after_secs.to_string())
+    {
+        response
+            .headers_mut()
+            .insert(actix_web::http::header::RETRY_AFTER, value);
+    }
+    response
+}
+
 /// 快速构建 500 Internal Server Error 错误
 pub fn internal_error(message: &str) -> HttpResponse {
     error_response(500, message)
@@ -197,6 +234,13 @@ mod tests {
             assert_eq!(response.status(), 409);
         }
 
+        #[test]
+        fn test_too_many_requests() {
+            let response = too_many_requests("Slow down", 30);
+            assert_eq!(response.status(), 429);
+            assert_eq!(response.headers().get("Retry-After").unwrap(), "30");
+        }
+
         #[test]
         fn test_internal_error() {
             let response = internal_error("Something went wrong");
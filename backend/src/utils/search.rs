@@ -0,0 +1,228 @@
+/// 搜索关键词最多拆分的词数
+const MAX_SEARCH_TERMS: usize = 8;
+
+/// 高亮起始标签
+pub const HIGHLIGHT_START: &str = "<mark>";
+/// 高亮结束标签
+pub const HIGHLIGHT_END: &str = "</mark>";
+
+/// 将搜索关键词按空白拆分为检索词（去重，最多 8 个）
+pub fn split_search_terms(keyword: &str) -> Vec<String> {
+    let mut terms: Vec<String> = Vec::new();
+    for term in keyword.split_whitespace() {
+        let term = term.to_lowercase();
+        if !terms.contains(&term) {
+            terms.push(term);
+        }
+        if terms.len() >= MAX_SEARCH_TERMS {
+            break;
+        }
+    }
+    terms
+}
+
+/// 转义 LIKE/ILIKE 模式中的通配符，返回 `%keyword%` 形式的模式
+pub fn build_like_pattern(keyword: &str) -> String {
+    let mut escaped = String::with_capacity(keyword.len() + 2);
+    escaped.push('%');
+    for c in keyword.chars() {
+        if matches!(c, '%' | '_' | '\\') {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped.push('%');
+    escaped
+}
+
+/// 转义 HTML 特殊字符
+fn escape_html(c: char, out: &mut String) {
+    match c {
+        '<' => out.push_str("&lt;"),
+        '>' => out.push_str("&gt;"),
+        '&' => out.push_str("&amp;"),
+        '"' => out.push_str("&quot;"),
+        '\'' => out.push_str("&#39;"),
+        _ => out.push(c),
+    }
+}
+
+/// 按字符计算的小写形式（保持与原文字符一一对应）
+fn lower_chars(text: &str) -> Vec<char> {
+    text.chars()
+        .map(|c| c.to_lowercase().next().unwrap_or(c))
+        .collect()
+}
+
+/// 查找所有检索词在文本中的匹配区间（字符下标，已合并重叠区间）
+fn find_matches(text: &[char], terms: &[Vec<char>]) -> Vec<(usize, usize)> {
+    let mut ranges: Vec<(usize, usize)> = Vec::new();
+    for term in terms {
+        if term.is_empty() || term.len() > text.len() {
+            continue;
+        }
+        let mut i = 0;
+        while i + term.len() <= text.len() {
+            if text[i..i + term.len()] == term[..] {
+                ranges.push((i, i + term.len()));
+                i += term.len();
+            } else {
+                i += 1;
+            }
+        }
+    }
+
+    ranges.sort();
+    let mut merged: Vec<(usize, usize)> = Vec::new();
+    for (start, end) in ranges {
+        match merged.last_mut() {
+            Some(last) if start <= last.1 => last.1 = last.1.max(end),
+            _ => merged.push((start, end)),
+        }
+    }
+    merged
+}
+
+/// 渲染 [start, end) 范围内的文本，匹配部分用 `<mark>` 包裹，其余内容做 HTML 转义
+fn render(chars: &[char], matches: &[(usize, usize)], start: usize, end: usize) -> String {
+    let mut out = String::new();
+    let mut pos = start;
+    for &(m_start, m_end) in matches {
+        if m_end <= start || m_start >= end {
+            continue;
+        }
+        let m_start = m_start.max(start);
+        let m_end = m_end.min(end);
+        for &c in &chars[pos..m_start] {
+            escape_html(c, &mut out);
+        }
+        out.push_str(HIGHLIGHT_START);
+        for &c in &chars[m_start..m_end] {
+            escape_html(c, &mut out);
+        }
+        out.push_str(HIGHLIGHT_END);
+        pos = m_end;
+    }
+    for &c in &chars[pos..end] {
+        escape_html(c, &mut out);
+    }
+    out
+}
+
+/// 高亮整段文本中的检索词（用于标题），没有匹配时返回 None
+pub fn highlight_text(text: &str, terms: &[String]) -> Option<String> {
+    let chars: Vec<char> = text.chars().collect();
+    let lower = lower_chars(text);
+    let terms: Vec<Vec<char>> = terms.iter().map(|t| lower_chars(t)).collect();
+    let matches = find_matches(&lower, &terms);
+    if matches.is_empty() {
+        return None;
+    }
+    Some(render(&chars, &matches, 0, chars.len()))
+}
+
+/// 截取包含首个匹配的片段并高亮检索词（用于描述等长文本），没有匹配时返回 None
+///
+/// `max_chars` 为片段的最大字符数，片段被截断时在首尾添加省略号
+pub fn highlight_snippet(text: &str, terms: &[String], max_chars: usize) -> Option<String> {
+    let chars: Vec<char> = text.chars().collect();
+    let lower = lower_chars(text);
+    let terms: Vec<Vec<char>> = terms.iter().map(|t| lower_chars(t)).collect();
+    let matches = find_matches(&lower, &terms);
+    let first = matches.first()?;
+
+    let max_chars = max_chars.max(first.1 - first.0);
+    // 匹配位置前保留约三分之一的上下文
+    let start = first.0.saturating_sub(max_chars / 3);
+    let end = (start + max_chars).min(chars.len());
+    let start = end.saturating_sub(max_chars).min(start);
+
+    let mut snippet = String::new();
+    if start > 0 {
+        snippet.push('…');
+    }
+    snippet.push_str(render(&chars, &matches, start, end).trim());
+    if end < chars.len() {
+        snippet.push('…');
+    }
+    Some(snippet)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn terms(keyword: &str) -> Vec<String> {
+        split_search_terms(keyword)
+    }
+
+    #[test]
+    fn test_split_search_terms() {
+        assert_eq!(terms("  高数  期末 高数 "), vec!["高数", "期末"]);
+        assert_eq!(terms("Linear ALGEBRA"), vec!["linear", "algebra"]);
+        assert!(terms("   ").is_empty());
+        assert_eq!(terms("a b c d e f g h i j").len(), MAX_SEARCH_TERMS);
+    }
+
+    #[test]
+    fn test_build_like_pattern_escapes_wildcards() {
+        assert_eq!(build_like_pattern("高数"), "%高数%");
+        assert_eq!(build_like_pattern("100%_a\\b"), "%100\\%\\_a\\\\b%");
+    }
+
+    #[test]
+    fn test_highlight_text() {
+        assert_eq!(
+            highlight_text("高等数学期末试卷", &terms("期末")).as_deref(),
+            Some("高等数学<mark>期末</mark>试卷")
+        );
+        assert_eq!(
+            highlight_text("Linear Algebra", &terms("algebra linear")).as_deref(),
+            Some("<mark>Linear</mark> <mark>Algebra</mark>")
+        );
+        assert_eq!(highlight_text("高等数学", &terms("物理")), None);
+    }
+
+    #[test]
+    fn test_highlight_text_escapes_html() {
+        assert_eq!(
+            highlight_text("<b>期末</b>", &terms("期末")).as_deref(),
+            Some("&lt;b&gt;<mark>期末</mark>&lt;/b&gt;")
+        );
+    }
+
+    #[test]
+    fn test_highlight_overlapping_terms() {
+        assert_eq!(
+            highlight_text("数学分析", &terms("数学 学分")).as_deref(),
+            Some("<mark>数学分</mark>析")
+        );
+    }
+
+    #[test]
+    fn test_highlight_snippet_truncates() {
+        let text = format!("{}期末考试重点{}", "前".repeat(50), "后".repeat(50));
+        let snippet = highlight_snippet(&text, &terms("重点"), 20).unwrap();
+        assert!(snippet.starts_with('…'));
+        assert!(snippet.ends_with('…'));
+        assert!(snippet.contains("<mark>重点</mark>"));
+        assert_eq!(
+            snippet
+                .replace(HIGHLIGHT_START, "")
+                .replace(HIGHLIGHT_END, "")
+                .trim_matches('…')
+                .chars()
+                .count(),
+            20
+        );
+    }
+
+    #[test]
+    fn test_highlight_snippet_short_text() {
+        assert_eq!(
+            highlight_snippet("期末复习提纲", &terms("复习"), 100).as_deref(),
+            Some("期末<mark>复习</mark>提纲")
+        );
+        assert_eq!(highlight_snippet("期末复习提纲", &terms("物理"), 100), None);
+    }
+}
@@ -0,0 +1,447 @@
+use std::io::Write;
+
+use flate2::write::DeflateEncoder;
+use flate2::Compression;
+
+/// 单个条目的最大大小（未启用 ZIP64 数据描述符，条目大小不能超过 4GB）
+pub const MAX_ZIP_ENTRY_SIZE: u64 = u32::MAX as u64;
+
+const LOCAL_FILE_HEADER_SIGNATURE: u32 = 0x0403_4b50;
+const DATA_DESCRIPTOR_SIGNATURE: u32 = 0x0807_4b50;
+const CENTRAL_DIRECTORY_SIGNATURE: u32 = 0x0201_4b50;
+const ZIP64_END_OF_CENTRAL_DIRECTORY_SIGNATURE: u32 = 0x0606_4b50;
+const ZIP64_END_OF_CENTRAL_DIRECTORY_LOCATOR_SIGNATURE: u32 = 0x0706_4b50;
+const END_OF_CENTRAL_DIRECTORY_SIGNATURE: u32 = 0x0605_4b50;
+
+/// 通用标志位：bit 3 使用数据描述符，bit 11 文件名为 UTF-8
+const GENERAL_PURPOSE_FLAGS: u16 = 0x0808;
+const COMPRESSION_DEFLATED: u16 = 8;
+const VERSION_NEEDED: u16 = 20;
+const VERSION_NEEDED_ZIP64: u16 = 45;
+/// 创建者版本：Unix，规范版本 4.5
+const VERSION_MADE_BY: u16 = (3 << 8) | 45;
+/// 外部属性：普通文件，权限 0644
+const EXTERNAL_ATTRIBUTES: u32 = 0o100644 << 16;
+
+#[derive(Debug)]
+pub enum ZipStreamError {
+    /// 调用顺序错误（如未开始条目就写入数据）
+    InvalidState(String),
+    /// 条目大小超过限制
+    EntryTooLarge(String),
+    Io(std::io::Error),
+}
+
+impl std::fmt::Display for ZipStreamError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ZipStreamError::InvalidState(msg) => write!(f, "ZIP 写入状态错误: {}", msg),
+            ZipStreamError::EntryTooLarge(name) => {
+                write!(f, "ZIP 条目超过 4GB 限制: {}", name)
+            }
+            ZipStreamError::Io(e) => write!(f, "ZIP 压缩失败: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for ZipStreamError {}
+
+impl From<std::io::Error> for ZipStreamError {
+    fn from(err: std::io::Error) -> Self {
+        ZipStreamError::Io(err)
+    }
+}
+
+/// 已写入的条目（用于生成中央目录）
+struct ZipEntry {
+    name: String,
+    crc32: u32,
+    compressed_size: u64,
+    uncompressed_size: u64,
+    header_offset: u64,
+}
+
+/// 正在写入的条目
+struct CurrentEntry {
+    name: String,
+    encoder: DeflateEncoder<Vec<u8>>,
+    hasher: crc32fast::Hasher,
+    compressed_size: u64,
+    uncompressed_size: u64,
+    header_offset: u64,
+}
+
+/// 流式 ZIP 写入器
+///
+/// 不依赖 Seek：每个条目使用数据描述符记录 CRC 与大小，中央目录在最后统一写出，
+/// 因此可以边读取边输出。写入器只负责生成字节，调用方通过 `take_output` 取走
+/// 已生成的数据并发送到 HTTP 响应或写入文件，内存占用只与单次写入的数据块大小有关。
+pub struct ZipStreamWriter {
+    output: Vec<u8>,
+    /// 已生成的总字节数（包括已被取走的部分）
+    written: u64,
+    entries: Vec<ZipEntry>,
+    current: Option<CurrentEntry>,
+    dos_time: u16,
+    dos_date: u16,
+    finished: bool,
+}
+
+impl Default for ZipStreamWriter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ZipStreamWriter {
+    pub fn new() -> Self {
+        let (dos_time, dos_date) = dos_datetime(chrono::Local::now().naive_local());
+        Self {
+            output: Vec::new(),
+            written: 0,
+            entries: Vec::new(),
+            current: None,
+            dos_time,
+            dos_date,
+            finished: false,
+        }
+    }
+
+    /// 已生成的总字节数
+    pub fn bytes_written(&self) -> u64 {
+        self.written
+    }
+
+    /// 取走已生成的数据
+    pub fn take_output(&mut self) -> Vec<u8> {
+        std::mem::take(&mut self.output)
+    }
+
+    /// 开始一个新条目（写出本地文件头）
+    pub fn start_file(&mut self, name: &str) -> Result<(), ZipStreamError> {
+        if self.finished {
+            return Err(ZipStreamError::InvalidState("归档已完成".to_string()));
+        }
+        if self.current.is_some() {
+            return Err(ZipStreamError::InvalidState(format!(
+                "上一个条目尚未结束，无法开始 {}",
+                name
+            )));
+        }
+
+        let header_offset = self.written;
+        let name_bytes = name.as_bytes();
+
+        let mut header = Vec::with_capacity(30 + name_bytes.len());
+        put_u32(&mut header, LOCAL_FILE_HEADER_SIGNATURE);
+        put_u16(&mut header, VERSION_NEEDED);
+        put_u16(&mut header, GENERAL_PURPOSE_FLAGS);
+        put_u16(&mut header, COMPRESSION_DEFLATED);
+        put_u16(&mut header, self.dos_time);
+        put_u16(&mut header, self.dos_date);
+        // CRC 与大小写在数据描述符中
+        put_u32(&mut header, 0);
+        put_u32(&mut header, 0);
+        put_u32(&mut header, 0);
+        put_u16(&mut header, name_bytes.len() as u16);
+        put_u16(&mut header, 0);
+        header.extend_from_slice(name_bytes);
+        self.emit(&header);
+
+        self.current = Some(CurrentEntry {
+            name: name.to_string(),
+            encoder: DeflateEncoder::new(Vec::new(), Compression::default()),
+            hasher: crc32fast::Hasher::new(),
+            compressed_size: 0,
+            uncompressed_size: 0,
+            header_offset,
+        });
+        Ok(())
+    }
+
+    /// 向当前条目写入数据
+    pub fn write_data(&mut self, data: &[u8]) -> Result<(), ZipStreamError> {
+        let current = self
+            .current
+            .as_mut()
+            .ok_or_else(|| ZipStreamError::InvalidState("没有正在写入的条目".to_string()))?;
+
+        current.uncompressed_size += data.len() as u64;
+        if current.uncompressed_size > MAX_ZIP_ENTRY_SIZE {
+            return Err(ZipStreamError::EntryTooLarge(current.name.clone()));
+        }
+        current.hasher.update(data);
+        current.encoder.write_all(data)?;
+
+        let compressed = std::mem::take(current.encoder.get_mut());
+        current.compressed_size += compressed.len() as u64;
+        self.emit(&compressed);
+        Ok(())
+    }
+
+    /// 结束当前条目（写出剩余压缩数据和数据描述符）
+    pub fn finish_file(&mut self) -> Result<(), ZipStreamError> {
+        let current = self
+            .current
+            .take()
+            .ok_or_else(|| ZipStreamError::InvalidState("没有正在写入的条目".to_string()))?;
+
+        let compressed = current.encoder.finish()?;
+        let compressed_size = current.compressed_size + compressed.len() as u64;
+        if compressed_size > MAX_ZIP_ENTRY_SIZE {
+            return Err(ZipStreamError::EntryTooLarge(current.name));
+        }
+        self.emit(&compressed);
+
+        let crc32 = current.hasher.finalize();
+        let mut descriptor = Vec::with_capacity(16);
+        put_u32(&mut descriptor, DATA_DESCRIPTOR_SIGNATURE);
+        put_u32(&mut descriptor, crc32);
+        put_u32(&mut descriptor, compressed_size as u32);
+        put_u32(&mut descriptor, current.uncompressed_size as u32);
+        self.emit(&descriptor);
+
+        self.entries.push(ZipEntry {
+            name: current.name,
+            crc32,
+            compressed_size,
+            uncompressed_size: current.uncompressed_size,
+            header_offset: current.header_offset,
+        });
+        Ok(())
+    }
+
+    /// 写入一个完整的小文件（如清单）
+    pub fn add_file(&mut self, name: &str, data: &[u8]) -> Result<(), ZipStreamError> {
+        self.start_file(name)?;
+        self.write_data(data)?;
+        self.finish_file()
+    }
+
+    /// 写出中央目录，完成归档
+    ///
+    /// 偏移量或条目数超过传统 ZIP 限制时自动写出 ZIP64 结束记录
+    pub fn finish(&mut self) -> Result<(), ZipStreamError> {
+        if self.current.is_some() {
+            return Err(ZipStreamError::InvalidState(
+                "存在未结束的条目，无法完成归档".to_string(),
+            ));
+        }
+        if self.finished {
+            return Err(ZipStreamError::InvalidState("归档已完成".to_string()));
+        }
+
+        let central_directory_offset = self.written;
+        let mut central_directory = Vec::new();
+        for entry in &self.entries {
+            let name_bytes = entry.name.as_bytes();
+            let needs_zip64 = entry.header_offset >= u32::MAX as u64;
+
+            put_u32(&mut central_directory, CENTRAL_DIRECTORY_SIGNATURE);
+            put_u16(&mut central_directory, VERSION_MADE_BY);
+            put_u16(
+                &mut central_directory,
+                if needs_zip64 {
+                    VERSION_NEEDED_ZIP64
+                } else {
+                    VERSION_NEEDED
+                },
+            );
+            put_u16(&mut central_directory, GENERAL_PURPOSE_FLAGS);
+            put_u16(&mut central_directory, COMPRESSION_DEFLATED);
+            put_u16(&mut central_directory, self.dos_time);
+            put_u16(&mut central_directory, self.dos_date);
+            put_u32(&mut central_directory, entry.crc32);
+            put_u32(&mut central_directory, entry.compressed_size as u32);
+            put_u32(&mut central_directory, entry.uncompressed_size as u32);
+            put_u16(&mut central_directory, name_bytes.len() as u16);
+            put_u16(&mut central_directory, if needs_zip64 { 12 } else { 0 });
+            put_u16(&mut central_directory, 0); // 注释长度
+            put_u16(&mut central_directory, 0); // 起始磁盘号
+            put_u16(&mut central_directory, 0); // 内部属性
+            put_u32(&mut central_directory, EXTERNAL_ATTRIBUTES);
+            put_u32(
+                &mut central_directory,
+                if needs_zip64 {
+                    u32::MAX
+                } else {
+                    entry.header_offset as u32
+                },
+            );
+            central_directory.extend_from_slice(name_bytes);
+            if needs_zip64 {
+                // ZIP64 扩展字段，只包含本地文件头偏移量
+                put_u16(&mut central_directory, 0x0001);
+                put_u16(&mut central_directory, 8);
+                put_u64(&mut central_directory, entry.header_offset);
+            }
+        }
+        let central_directory_size = central_directory.len() as u64;
+        self.emit(&central_directory);
+
+        let entry_count = self.entries.len() as u64;
+        let needs_zip64 = entry_count >= u16::MAX as u64
+            || central_directory_offset >= u32::MAX as u64
+            || central_directory_size >= u32::MAX as u64;
+
+        let mut end = Vec::new();
+        if needs_zip64 {
+            let zip64_end_offset = self.written;
+            put_u32(&mut end, ZIP64_END_OF_CENTRAL_DIRECTORY_SIGNATURE);
+            put_u64(&mut end, 44); // 记录剩余部分的长度
+            put_u16(&mut end, VERSION_MADE_BY);
+            put_u16(&mut end, VERSION_NEEDED_ZIP64);
+            put_u32(&mut end, 0);
+            put_u32(&mut end, 0);
+            put_u64(&mut end, entry_count);
+            put_u64(&mut end, entry_count);
+            put_u64(&mut end, central_directory_size);
+            put_u64(&mut end, central_directory_offset);
+
+            put_u32(&mut end, ZIP64_END_OF_CENTRAL_DIRECTORY_LOCATOR_SIGNATURE);
+            put_u32(&mut end, 0);
+            put_u64(&mut end, zip64_end_offset);
+            put_u32(&mut end, 1);
+        }
+
+        put_u32(&mut end, END_OF_CENTRAL_DIRECTORY_SIGNATURE);
+        put_u16(&mut end, 0);
+        put_u16(&mut end, 0);
+        put_u16(&mut end, entry_count.min(u16::MAX as u64) as u16);
+        put_u16(&mut end, entry_count.min(u16::MAX as u64) as u16);
+        put_u32(&mut end, central_directory_size.min(u32::MAX as u64) as u32);
+        put_u32(
+            &mut end,
+            central_directory_offset.min(u32::MAX as u64) as u32,
+        );
+        put_u16(&mut end, 0);
+        self.emit(&end);
+
+        self.finished = true;
+        Ok(())
+    }
+
+    fn emit(&mut self, data: &[u8]) {
+        self.output.extend_from_slice(data);
+        self.written += data.len() as u64;
+    }
+}
+
+fn put_u16(buf: &mut Vec<u8>, value: u16) {
+    buf.extend_from_slice(&value.to_le_bytes());
+}
+
+fn put_u32(buf: &mut Vec<u8>, value: u32) {
+    buf.extend_from_slice(&value.to_le_bytes());
+}
+
+fn put_u64(buf: &mut Vec<u8>, value: u64) {
+    buf.extend_from_slice(&value.to_le_bytes());
+}
+
+/// 转换为 MS-DOS 时间与日期（ZIP 只支持 1980 年以后的时间）
+fn dos_datetime(datetime: chrono::NaiveDateTime) -> (u16, u16) {
+    use chrono::{Datelike, Timelike};
+
+    let year = datetime.year().clamp(1980, 2107) as u16;
+    let time = ((datetime.hour() as u16) << 11)
+        | ((datetime.minute() as u16) << 5)
+        | ((datetime.second() as u16) / 2);
+    let date = ((year - 1980) << 9) | ((datetime.month() as u16) << 5) | datetime.day() as u16;
+    (time, date)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Read;
+
+    fn read_archive(data: Vec<u8>) -> Vec<(String, Vec<u8>)> {
+        let mut archive = zip::ZipArchive::new(std::io::Cursor::new(data)).unwrap();
+        (0..archive.len())
+            .map(|i| {
+                let mut file = archive.by_index(i).unwrap();
+                let mut content = Vec::new();
+                file.read_to_end(&mut content).unwrap();
+                (file.name().to_string(), content)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_stream_archive_readable() {
+        let mut writer = ZipStreamWriter::new();
+        let mut archive = Vec::new();
+
+        writer.start_file("高数笔记.md").unwrap();
+        for _ in 0..100 {
+            writer.write_data("# 极限与连续\n".as_bytes()).unwrap();
+            archive.extend(writer.take_output());
+        }
+        writer.finish_file().unwrap();
+        writer.add_file("empty.txt", b"").unwrap();
+        writer.finish().unwrap();
+        archive.extend(writer.take_output());
+
+        assert_eq!(writer.bytes_written(), archive.len() as u64);
+
+        let files = read_archive(archive);
+        assert_eq!(files.len(), 2);
+        assert_eq!(files[0].0, "高数笔记.md");
+        assert_eq!(files[0].1, "# 极限与连续\n".repeat(100).into_bytes());
+        assert_eq!(files[1].0, "empty.txt");
+        assert!(files[1].1.is_empty());
+    }
+
+    #[test]
+    fn test_empty_archive() {
+        let mut writer = ZipStreamWriter::new();
+        writer.finish().unwrap();
+        assert!(read_archive(writer.take_output()).is_empty());
+    }
+
+    #[test]
+    fn test_invalid_call_order() {
+        let mut writer = ZipStreamWriter::new();
+        assert!(writer.write_data(b"data").is_err());
+        assert!(writer.finish_file().is_err());
+
+        writer.start_file("a.txt").unwrap();
+        assert!(writer.start_file("b.txt").is_err());
+        assert!(writer.finish().is_err());
+
+        writer.finish_file().unwrap();
+        writer.finish().unwrap();
+        assert!(writer.start_file("c.txt").is_err());
+    }
+
+    #[test]
+    fn test_zip64_end_record_for_large_offsets() {
+        let mut writer = ZipStreamWriter::new();
+        // 模拟已输出超过 4GB 的数据
+        writer.written = u32::MAX as u64 + 1;
+        writer.add_file("late.txt", b"content").unwrap();
+        writer.finish().unwrap();
+
+        let output = writer.take_output();
+        let contains = |signature: u32| {
+            output
+                .windows(4)
+                .any(|w| w == signature.to_le_bytes().as_slice())
+        };
+        assert!(contains(ZIP64_END_OF_CENTRAL_DIRECTORY_SIGNATURE));
+        assert!(contains(ZIP64_END_OF_CENTRAL_DIRECTORY_LOCATOR_SIGNATURE));
+        assert!(output.ends_with(&[0, 0]));
+    }
+
+    #[test]
+    fn test_dos_datetime() {
+        let datetime = chrono::NaiveDate::from_ymd_opt(2024, 3, 15)
+            .unwrap()
+            .and_hms_opt(10, 30, 42)
+            .unwrap();
+        let (time, date) = dos_datetime(datetime);
+        assert_eq!(time, (10 << 11) | (30 << 5) | 21);
+        assert_eq!(date, (44 << 9) | (3 << 5) | 15);
+    }
+}
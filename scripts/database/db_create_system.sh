@@ -74,6 +74,9 @@ sudo -u postgres psql -p ${DB_PORT} -d ${DB_NAME} -c "GRANT CREATE ON SCHEMA pub
 # 启用 pgcrypto 扩展
 sudo -u postgres psql -p ${DB_PORT} -d ${DB_NAME} -c "CREATE EXTENSION IF NOT EXISTS pgcrypto;"
 
+# 启用 pg_trgm 扩展（资源全文检索的三元组模糊匹配）
+sudo -u postgres psql -p ${DB_PORT} -d ${DB_NAME} -c "CREATE EXTENSION IF NOT EXISTS pg_trgm;"
+
 echo -e "${GREEN}  权限授予完成${NC}"
 
 echo ""
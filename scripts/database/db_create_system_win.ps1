@@ -156,6 +156,9 @@ try {
     # 启用 pgcrypto 扩展
     & $psqlPath -U $POSTGRES_USER -p $DB_PORT -d $DB_NAME -c "CREATE EXTENSION IF NOT EXISTS pgcrypto;" 2>&1 | Out-Null
 
+    # 启用 pg_trgm 扩展（资源全文检索的三元组模糊匹配）
+    & $psqlPath -U $POSTGRES_USER -p $DB_PORT -d $DB_NAME -c "CREATE EXTENSION IF NOT EXISTS pg_trgm;" 2>&1 | Out-Null
+
     Write-ColorOutput Green "  权限授予完成"
 } catch {
     Write-ColorOutput Red "  错误: 授予权限失败"
@@ -64,6 +64,9 @@ GRANT CREATE ON SCHEMA public TO {db_user};
 -- 启用 pgcrypto 扩展
 CREATE EXTENSION IF NOT EXISTS pgcrypto;
 
+-- 启用 pg_trgm 扩展（资源全文检索的三元组模糊匹配）
+CREATE EXTENSION IF NOT EXISTS pg_trgm;
+
 SELECT '权限授予完成' as status;
 '''
 
@@ -79,6 +82,7 @@ TABLE_CREATION_SQL = '''
 
 -- 启用扩展
 CREATE EXTENSION IF NOT EXISTS "pgcrypto";
+CREATE EXTENSION IF NOT EXISTS "pg_trgm";
 
 -- ============================================
 -- 创建 sn 序列（从1开始自增）
@@ -145,6 +149,53 @@ BEGIN
     IF NOT EXISTS (SELECT 1 FROM information_schema.columns WHERE table_name = 'users' AND column_name = 'updated_at') THEN
         ALTER TABLE users ADD COLUMN updated_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP;
     END IF;
+
+    -- token_version: 令牌版本，递增后该用户此前签发的所有 Token 失效
+    IF NOT EXISTS (SELECT 1 FROM information_schema.columns WHERE table_name = 'users' AND column_name = 'token_version') THEN
+        ALTER TABLE users ADD COLUMN token_version INTEGER NOT NULL DEFAULT 0;
+    END IF;
+
+    -- failed_login_attempts / last_failed_login_at: 连续登录失败次数和最近一次失败时间，用于登录退避
+    IF NOT EXISTS (SELECT 1 FROM information_schema.columns WHERE table_name = 'users' AND column_name = 'failed_login_attempts') THEN
+        ALTER TABLE users ADD COLUMN failed_login_attempts INTEGER NOT NULL DEFAULT 0;
+    END IF;
+
+    IF NOT EXISTS (SELECT 1 FROM information_schema.columns WHERE table_name = 'users' AND column_name = 'last_failed_login_at') THEN
+        ALTER TABLE users ADD COLUMN last_failed_login_at TIMESTAMP;
+    END IF;
+
+    -- locked_until: 连续登录失败过多时临时锁定到该时间
+    IF NOT EXISTS (SELECT 1 FROM information_schema.columns WHERE table_name = 'users' AND column_name = 'locked_until') THEN
+        ALTER TABLE users ADD COLUMN locked_until TIMESTAMP;
+    END IF;
+
+    -- email_verified / email_verified_at: 邮箱是否已通过验证邮件确认，修改邮箱后需要重新验证
+    IF NOT EXISTS (SELECT 1 FROM information_schema.columns WHERE table_name = 'users' AND column_name = 'email_verified') THEN
+        ALTER TABLE users ADD COLUMN email_verified BOOLEAN NOT NULL DEFAULT false;
+    END IF;
+
+    IF NOT EXISTS (SELECT 1 FROM information_schema.columns WHERE table_name = 'users' AND column_name = 'email_verified_at') THEN
+        ALTER TABLE users ADD COLUMN email_verified_at TIMESTAMP;
+    END IF;
+
+    -- totp_secret / totp_enabled_at: 两步验证密钥（Base32）和启用时间，启用前为待确认的密钥
+    -- 配置了 TOTP_ENCRYPTION_KEYS 时密钥加密存储，密文长度超过 Base32 明文
+    IF NOT EXISTS (SELECT 1 FROM information_schema.columns WHERE table_name = 'users' AND column_name = 'totp_secret') THEN
+        ALTER TABLE users ADD COLUMN totp_secret TEXT;
+    END IF;
+
+    IF EXISTS (SELECT 1 FROM information_schema.columns WHERE table_name = 'users' AND column_name = 'totp_secret' AND data_type <> 'text') THEN
+        ALTER TABLE users ALTER COLUMN totp_secret TYPE TEXT;
+    END IF;
+
+    IF NOT EXISTS (SELECT 1 FROM information_schema.columns WHERE table_name = 'users' AND column_name = 'totp_enabled_at') THEN
+        ALTER TABLE users ADD COLUMN totp_enabled_at TIMESTAMP;
+    END IF;
+
+    -- totp_last_step: 最近一次使用的验证码时间步，防止同一验证码重复使用
+    IF NOT EXISTS (SELECT 1 FROM information_schema.columns WHERE table_name = 'users' AND column_name = 'totp_last_step') THEN
+        ALTER TABLE users ADD COLUMN totp_last_step BIGINT;
+    END IF;
 END $$;
 
 -- ============================================
@@ -226,6 +277,16 @@ BEGIN
     IF NOT EXISTS (SELECT 1 FROM information_schema.columns WHERE table_name = 'resources' AND column_name = 'description') THEN
         ALTER TABLE resources ADD COLUMN description TEXT;
     END IF;
+
+    -- 全文检索：加权词向量（由触发器维护）
+    IF NOT EXISTS (SELECT 1 FROM information_schema.columns WHERE table_name = 'resources' AND column_name = 'search_vector') THEN
+        ALTER TABLE resources ADD COLUMN search_vector TSVECTOR;
+    END IF;
+
+    -- 全文检索：拼接后的检索文本，用于三元组模糊匹配（由触发器维护）
+    IF NOT EXISTS (SELECT 1 FROM information_schema.columns WHERE table_name = 'resources' AND column_name = 'search_document') THEN
+        ALTER TABLE resources ADD COLUMN search_document TEXT;
+    END IF;
 END $$;
 
 -- ============================================
@@ -398,9 +459,54 @@ BEGIN
     IF NOT EXISTS (SELECT 1 FROM information_schema.columns WHERE table_name = 'comments' AND column_name = 'audit_status') THEN
         ALTER TABLE comments ADD COLUMN audit_status VARCHAR(20) DEFAULT 'approved';
     END IF;
+
+    IF NOT EXISTS (SELECT 1 FROM information_schema.columns WHERE table_name = 'comments' AND column_name = 'ai_reject_reason') THEN
+        ALTER TABLE comments ADD COLUMN ai_reject_reason TEXT;
+    END IF;
     IF NOT EXISTS (SELECT 1 FROM information_schema.columns WHERE table_name = 'comments' AND column_name = 'updated_at') THEN
         ALTER TABLE comments ADD COLUMN updated_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP;
     END IF;
+
+    -- parent_id: 直接回复的评论；root_id: 所属楼层的顶层评论（顶层评论均为 NULL）
+    IF NOT EXISTS (SELECT 1 FROM information_schema.columns WHERE table_name = 'comments' AND column_name = 'parent_id') THEN
+        ALTER TABLE comments ADD COLUMN parent_id UUID REFERENCES comments(id) ON DELETE CASCADE;
+    END IF;
+
+    IF NOT EXISTS (SELECT 1 FROM information_schema.columns WHERE table_name = 'comments' AND column_name = 'root_id') THEN
+        ALTER TABLE comments ADD COLUMN root_id UUID REFERENCES comments(id) ON DELETE CASCADE;
+    END IF;
+
+    -- is_deleted: 软删除，保留楼层结构，内容不再公开展示
+    IF NOT EXISTS (SELECT 1 FROM information_schema.columns WHERE table_name = 'comments' AND column_name = 'is_deleted') THEN
+        ALTER TABLE comments ADD COLUMN is_deleted BOOLEAN NOT NULL DEFAULT false;
+    END IF;
+
+    IF NOT EXISTS (SELECT 1 FROM information_schema.columns WHERE table_name = 'comments' AND column_name = 'deleted_at') THEN
+        ALTER TABLE comments ADD COLUMN deleted_at TIMESTAMP;
+    END IF;
+
+    -- edited_at: 最近一次编辑时间，未编辑过为 NULL
+    IF NOT EXISTS (SELECT 1 FROM information_schema.columns WHERE table_name = 'comments' AND column_name = 'edited_at') THEN
+        ALTER TABLE comments ADD COLUMN edited_at TIMESTAMP;
+    END IF;
+
+    -- upvotes / downvotes: 赞同和反对票数（由 comment_votes 汇总）
+    IF NOT EXISTS (SELECT 1 FROM information_schema.columns WHERE table_name = 'comments' AND column_name = 'upvotes') THEN
+        ALTER TABLE comments ADD COLUMN upvotes INTEGER NOT NULL DEFAULT 0;
+    END IF;
+
+    IF NOT EXISTS (SELECT 1 FROM information_schema.columns WHERE table_name = 'comments' AND column_name = 'downvotes') THEN
+        ALTER TABLE comments ADD COLUMN downvotes INTEGER NOT NULL DEFAULT 0;
+    END IF;
+
+    -- is_helpful: 资源上传者标记的有帮助评论（置顶）
+    IF NOT EXISTS (SELECT 1 FROM information_schema.columns WHERE table_name = 'comments' AND column_name = 'is_helpful') THEN
+        ALTER TABLE comments ADD COLUMN is_helpful BOOLEAN NOT NULL DEFAULT false;
+    END IF;
+
+    IF NOT EXISTS (SELECT 1 FROM information_schema.columns WHERE table_name = 'comments' AND column_name = 'helpful_at') THEN
+        ALTER TABLE comments ADD COLUMN helpful_at TIMESTAMP;
+    END IF;
 END $$;
 
 -- ============================================
@@ -497,6 +603,10 @@ BEGIN
     IF NOT EXISTS (SELECT 1 FROM information_schema.columns WHERE table_name = 'claims' AND column_name = 'reviewed_at') THEN
         ALTER TABLE claims ADD COLUMN reviewed_at TIMESTAMP;
     END IF;
+
+    IF NOT EXISTS (SELECT 1 FROM information_schema.columns WHERE table_name = 'claims' AND column_name = 'review_comment') THEN
+        ALTER TABLE claims ADD COLUMN review_comment TEXT;
+    END IF;
 END $$;
 
 -- ============================================
@@ -832,130 +942,1074 @@ EXCEPTION
 END $$;
 
 -- ============================================
--- 为现有用户分配 sn（增量更新支持）
+-- 19. 资源版本表（Markdown 在线编辑的历史版本）
 -- ============================================
+CREATE TABLE IF NOT EXISTS resource_versions (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP
+);
+
 DO $$
-DECLARE
-    user_record RECORD;
-    current_sn BIGINT := 1;
 BEGIN
-    FOR user_record IN
-        SELECT id FROM users WHERE sn IS NULL ORDER BY created_at ASC
-    LOOP
-        UPDATE users SET sn = current_sn WHERE id = user_record.id;
-        current_sn := current_sn + 1;
-    END LOOP;
-    IF current_sn > 1 THEN
-        PERFORM setval('user_sn_seq', current_sn - 1, true);
+    IF NOT EXISTS (SELECT 1 FROM information_schema.columns WHERE table_name = 'resource_versions' AND column_name = 'resource_id') THEN
+        IF EXISTS (SELECT 1 FROM resource_versions LIMIT 1) THEN
+            ALTER TABLE resource_versions ADD COLUMN resource_id UUID REFERENCES resources(id) ON DELETE CASCADE;
+        ELSE
+            ALTER TABLE resource_versions ADD COLUMN resource_id UUID NOT NULL REFERENCES resources(id) ON DELETE CASCADE DEFAULT '00000000-0000-0000-0000-000000000000';
+        END IF;
+    END IF;
+
+    -- version_number: 资源内的版本序号（从 1 开始递增）
+    IF NOT EXISTS (SELECT 1 FROM information_schema.columns WHERE table_name = 'resource_versions' AND column_name = 'version_number') THEN
+        ALTER TABLE resource_versions ADD COLUMN version_number INTEGER NOT NULL DEFAULT 1;
+    END IF;
+
+    -- storage_key: 该版本文件在存储后端中的 key（与资源当前文件分开存放，不会被覆盖）
+    IF NOT EXISTS (SELECT 1 FROM information_schema.columns WHERE table_name = 'resource_versions' AND column_name = 'storage_key') THEN
+        ALTER TABLE resource_versions ADD COLUMN storage_key VARCHAR(500) NOT NULL DEFAULT '';
+    END IF;
+
+    IF NOT EXISTS (SELECT 1 FROM information_schema.columns WHERE table_name = 'resource_versions' AND column_name = 'storage_type') THEN
+        ALTER TABLE resource_versions ADD COLUMN storage_type VARCHAR(20) DEFAULT 'local';
+    END IF;
+
+    IF NOT EXISTS (SELECT 1 FROM information_schema.columns WHERE table_name = 'resource_versions' AND column_name = 'file_hash') THEN
+        ALTER TABLE resource_versions ADD COLUMN file_hash VARCHAR(64);
+    END IF;
+
+    IF NOT EXISTS (SELECT 1 FROM information_schema.columns WHERE table_name = 'resource_versions' AND column_name = 'file_size') THEN
+        ALTER TABLE resource_versions ADD COLUMN file_size BIGINT;
+    END IF;
+
+    -- editor_id: 产生该版本内容的用户（首个版本为上传者）
+    IF NOT EXISTS (SELECT 1 FROM information_schema.columns WHERE table_name = 'resource_versions' AND column_name = 'editor_id') THEN
+        ALTER TABLE resource_versions ADD COLUMN editor_id UUID REFERENCES users(id) ON DELETE SET NULL;
+    END IF;
+
+    -- rollback_from: 若该版本由回滚产生，记录回滚来源的版本序号
+    IF NOT EXISTS (SELECT 1 FROM information_schema.columns WHERE table_name = 'resource_versions' AND column_name = 'rollback_from') THEN
+        ALTER TABLE resource_versions ADD COLUMN rollback_from INTEGER;
+    END IF;
+END $$;
+
+-- 添加唯一约束：同一资源的版本序号不重复
+DO $$
+BEGIN
+    IF NOT EXISTS (
+        SELECT 1 FROM pg_constraint
+        WHERE conname = 'resource_versions_resource_version_key' AND conrelid = 'resource_versions'::regclass
+    ) THEN
+        ALTER TABLE resource_versions ADD CONSTRAINT resource_versions_resource_version_key UNIQUE (resource_id, version_number);
     END IF;
+EXCEPTION
+    WHEN unique_violation THEN
+        RAISE NOTICE '无法添加唯一约束：存在重复数据';
 END $$;
 
 -- ============================================
--- 创建索引
+-- 20. 打包下载任务表（收藏夹异步打包）
 -- ============================================
-CREATE INDEX IF NOT EXISTS idx_users_role ON users(role);
-CREATE INDEX IF NOT EXISTS idx_users_is_verified ON users(is_verified);
-CREATE INDEX IF NOT EXISTS idx_users_sn ON users(sn);
-CREATE INDEX IF NOT EXISTS idx_resources_uploader ON resources(uploader_id);
-CREATE INDEX IF NOT EXISTS idx_resources_author ON resources(author_id);
-CREATE INDEX IF NOT EXISTS idx_resources_course ON resources(course_name);
-CREATE INDEX IF NOT EXISTS idx_resources_type ON resources(resource_type);
-CREATE INDEX IF NOT EXISTS idx_resources_category ON resources(category);
-CREATE INDEX IF NOT EXISTS idx_resources_audit_status ON resources(audit_status);
-CREATE INDEX IF NOT EXISTS idx_resources_tags ON resources USING GIN(tags);
-CREATE INDEX IF NOT EXISTS idx_resources_created_at ON resources(created_at DESC);
-CREATE INDEX IF NOT EXISTS idx_resources_storage_type ON resources(storage_type);
-CREATE INDEX IF NOT EXISTS idx_ratings_resource ON ratings(resource_id);
-CREATE INDEX IF NOT EXISTS idx_ratings_user ON ratings(user_id);
-CREATE INDEX IF NOT EXISTS idx_likes_user ON likes(user_id);
-CREATE INDEX IF NOT EXISTS idx_comments_resource ON comments(resource_id);
-CREATE INDEX IF NOT EXISTS idx_comments_user ON comments(user_id);
-CREATE INDEX IF NOT EXISTS idx_comments_created_at ON comments(created_at DESC);
-CREATE INDEX IF NOT EXISTS idx_favorites_user ON favorites(user_id);
-CREATE INDEX IF NOT EXISTS idx_fav_res_resource ON favorite_resources(resource_id);
-CREATE INDEX IF NOT EXISTS idx_claims_resource ON claims(resource_id);
-CREATE INDEX IF NOT EXISTS idx_claims_applicant ON claims(applicant_id);
-CREATE INDEX IF NOT EXISTS idx_claims_status ON claims(status);
-CREATE INDEX IF NOT EXISTS idx_notifications_recipient ON notifications(recipient_id);
-CREATE INDEX IF NOT EXISTS idx_notifications_priority ON notifications(priority);
-CREATE INDEX IF NOT EXISTS idx_notifications_is_read ON notifications(is_read);
-CREATE INDEX IF NOT EXISTS idx_notifications_created_at ON notifications(created_at DESC);
-CREATE INDEX IF NOT EXISTS idx_notification_reads_notification ON notification_reads(notification_id);
-CREATE INDEX IF NOT EXISTS idx_notification_reads_user ON notification_reads(user_id);
-CREATE INDEX IF NOT EXISTS idx_notification_reads_unique ON notification_reads(notification_id, user_id);
-CREATE INDEX IF NOT EXISTS idx_audit_logs_user ON audit_logs(user_id);
-CREATE INDEX IF NOT EXISTS idx_audit_logs_action ON audit_logs(action);
-CREATE INDEX IF NOT EXISTS idx_audit_logs_created_at ON audit_logs(created_at DESC);
-CREATE INDEX IF NOT EXISTS idx_download_logs_resource ON download_logs(resource_id);
-CREATE INDEX IF NOT EXISTS idx_download_logs_user ON download_logs(user_id);
-CREATE INDEX IF NOT EXISTS idx_download_logs_time ON download_logs(downloaded_at DESC);
-CREATE INDEX IF NOT EXISTS idx_images_uploader ON images(uploader_id);
-CREATE INDEX IF NOT EXISTS idx_images_storage_type ON images(storage_type);
-CREATE INDEX IF NOT EXISTS idx_teachers_sn ON teachers(sn);
-CREATE INDEX IF NOT EXISTS idx_teachers_department ON teachers(department);
-CREATE INDEX IF NOT EXISTS idx_teachers_is_active ON teachers(is_active);
-CREATE INDEX IF NOT EXISTS idx_courses_sn ON courses(sn);
-CREATE INDEX IF NOT EXISTS idx_courses_semester ON courses(semester);
-CREATE INDEX IF NOT EXISTS idx_courses_is_active ON courses(is_active);
-CREATE INDEX IF NOT EXISTS idx_resource_teachers_resource ON resource_teachers(resource_id);
-CREATE INDEX IF NOT EXISTS idx_resource_teachers_teacher ON resource_teachers(teacher_sn);
-CREATE INDEX IF NOT EXISTS idx_resource_courses_resource ON resource_courses(resource_id);
-CREATE INDEX IF NOT EXISTS idx_resource_courses_course ON resource_courses(course_sn);
+CREATE TABLE IF NOT EXISTS pack_jobs (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP
+);
 
--- 资源关联表索引
-CREATE INDEX IF NOT EXISTS idx_resource_relations_source ON resource_relations(source_resource_id);
-CREATE INDEX IF NOT EXISTS idx_resource_relations_target ON resource_relations(target_resource_id);
+DO $$
+BEGIN
+    IF NOT EXISTS (SELECT 1 FROM information_schema.columns WHERE table_name = 'pack_jobs' AND column_name = 'user_id') THEN
+        IF EXISTS (SELECT 1 FROM pack_jobs LIMIT 1) THEN
+            ALTER TABLE pack_jobs ADD COLUMN user_id UUID REFERENCES users(id) ON DELETE CASCADE;
+        ELSE
+            ALTER TABLE pack_jobs ADD COLUMN user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE DEFAULT '00000000-0000-0000-0000-000000000000';
+        END IF;
+    END IF;
 
--- 资源统计表索引
-CREATE INDEX IF NOT EXISTS idx_resource_stats_resource ON resource_stats(resource_id);
+    -- favorite_id: 收藏夹被删除后保留任务记录，已生成的文件仍可下载至过期
+    IF NOT EXISTS (SELECT 1 FROM information_schema.columns WHERE table_name = 'pack_jobs' AND column_name = 'favorite_id') THEN
+        ALTER TABLE pack_jobs ADD COLUMN favorite_id UUID REFERENCES favorites(id) ON DELETE SET NULL;
+    END IF;
+
+    IF NOT EXISTS (SELECT 1 FROM information_schema.columns WHERE table_name = 'pack_jobs' AND column_name = 'favorite_name') THEN
+        ALTER TABLE pack_jobs ADD COLUMN favorite_name VARCHAR(100) NOT NULL DEFAULT '';
+    END IF;
+
+    -- status: pending（排队）, running（打包中）, completed（已完成）, failed（失败）, expired（文件已清理）
+    IF NOT EXISTS (SELECT 1 FROM information_schema.columns WHERE table_name = 'pack_jobs' AND column_name = 'status') THEN
+        ALTER TABLE pack_jobs ADD COLUMN status VARCHAR(20) NOT NULL DEFAULT 'pending';
+    END IF;
+
+    IF NOT EXISTS (SELECT 1 FROM information_schema.columns WHERE table_name = 'pack_jobs' AND column_name = 'total_files') THEN
+        ALTER TABLE pack_jobs ADD COLUMN total_files INTEGER NOT NULL DEFAULT 0;
+    END IF;
+
+    IF NOT EXISTS (SELECT 1 FROM information_schema.columns WHERE table_name = 'pack_jobs' AND column_name = 'processed_files') THEN
+        ALTER TABLE pack_jobs ADD COLUMN processed_files INTEGER NOT NULL DEFAULT 0;
+    END IF;
+
+    IF NOT EXISTS (SELECT 1 FROM information_schema.columns WHERE table_name = 'pack_jobs' AND column_name = 'skipped_files') THEN
+        ALTER TABLE pack_jobs ADD COLUMN skipped_files INTEGER NOT NULL DEFAULT 0;
+    END IF;
+
+    IF NOT EXISTS (SELECT 1 FROM information_schema.columns WHERE table_name = 'pack_jobs' AND column_name = 'bytes_written') THEN
+        ALTER TABLE pack_jobs ADD COLUMN bytes_written BIGINT NOT NULL DEFAULT 0;
+    END IF;
+
+    -- storage_key: 生成的 ZIP 在存储后端中的临时 key
+    IF NOT EXISTS (SELECT 1 FROM information_schema.columns WHERE table_name = 'pack_jobs' AND column_name = 'storage_key') THEN
+        ALTER TABLE pack_jobs ADD COLUMN storage_key VARCHAR(500);
+    END IF;
+
+    IF NOT EXISTS (SELECT 1 FROM information_schema.columns WHERE table_name = 'pack_jobs' AND column_name = 'storage_type') THEN
+        ALTER TABLE pack_jobs ADD COLUMN storage_type VARCHAR(20);
+    END IF;
+
+    IF NOT EXISTS (SELECT 1 FROM information_schema.columns WHERE table_name = 'pack_jobs' AND column_name = 'file_name') THEN
+        ALTER TABLE pack_jobs ADD COLUMN file_name VARCHAR(255);
+    END IF;
+
+    IF NOT EXISTS (SELECT 1 FROM information_schema.columns WHERE table_name = 'pack_jobs' AND column_name = 'error_message') THEN
+        ALTER TABLE pack_jobs ADD COLUMN error_message TEXT;
+    END IF;
+
+    IF NOT EXISTS (SELECT 1 FROM information_schema.columns WHERE table_name = 'pack_jobs' AND column_name = 'completed_at') THEN
+        ALTER TABLE pack_jobs ADD COLUMN completed_at TIMESTAMP;
+    END IF;
+
+    -- expires_at: 生成的文件过期时间，过期后由后台任务清理
+    IF NOT EXISTS (SELECT 1 FROM information_schema.columns WHERE table_name = 'pack_jobs' AND column_name = 'expires_at') THEN
+        ALTER TABLE pack_jobs ADD COLUMN expires_at TIMESTAMP;
+    END IF;
+
+    IF NOT EXISTS (SELECT 1 FROM information_schema.columns WHERE table_name = 'pack_jobs' AND column_name = 'updated_at') THEN
+        ALTER TABLE pack_jobs ADD COLUMN updated_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP;
+    END IF;
+END $$;
+
+-- 添加检查约束：任务状态
+DO $$
+BEGIN
+    IF NOT EXISTS (
+        SELECT 1 FROM pg_constraint
+        WHERE conname = 'pack_jobs_status_check' AND conrelid = 'pack_jobs'::regclass
+    ) THEN
+        ALTER TABLE pack_jobs ADD CONSTRAINT pack_jobs_status_check
+            CHECK (status IN ('pending', 'running', 'completed', 'failed', 'expired'));
+    END IF;
+END $$;
 
 -- ============================================
--- 创建触发器
+-- 21. 存储迁移任务表（管理员在存储后端之间迁移文件）
 -- ============================================
-CREATE OR REPLACE FUNCTION update_updated_at_column()
-RETURNS TRIGGER AS $$
+CREATE TABLE IF NOT EXISTS storage_migration_jobs (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP
+);
+
+DO $$
 BEGIN
-    NEW.updated_at = CURRENT_TIMESTAMP;
-    RETURN NEW;
-END;
-$$
-language 'plpgsql';
+    IF NOT EXISTS (SELECT 1 FROM information_schema.columns WHERE table_name = 'storage_migration_jobs' AND column_name = 'admin_id') THEN
+        IF EXISTS (SELECT 1 FROM storage_migration_jobs LIMIT 1) THEN
+            ALTER TABLE storage_migration_jobs ADD COLUMN admin_id UUID REFERENCES users(id) ON DELETE CASCADE;
+        ELSE
+            ALTER TABLE storage_migration_jobs ADD COLUMN admin_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE DEFAULT '00000000-0000-0000-0000-000000000000';
+        END IF;
+    END IF;
 
-DROP TRIGGER IF EXISTS update_users_updated_at ON users;
-CREATE TRIGGER update_users_updated_at
-    BEFORE UPDATE ON users
-    FOR EACH ROW
-    EXECUTE FUNCTION update_updated_at_column();
+    -- source_type / target_type: 迁移的源存储和目标存储（local / oss / s3）
+    IF NOT EXISTS (SELECT 1 FROM information_schema.columns WHERE table_name = 'storage_migration_jobs' AND column_name = 'source_type') THEN
+        ALTER TABLE storage_migration_jobs ADD COLUMN source_type VARCHAR(20) NOT NULL DEFAULT 'local';
+    END IF;
 
-DROP TRIGGER IF EXISTS update_resources_updated_at ON resources;
-CREATE TRIGGER update_resources_updated_at
-    BEFORE UPDATE ON resources
-    FOR EACH ROW
-    EXECUTE FUNCTION update_updated_at_column();
+    IF NOT EXISTS (SELECT 1 FROM information_schema.columns WHERE table_name = 'storage_migration_jobs' AND column_name = 'target_type') THEN
+        ALTER TABLE storage_migration_jobs ADD COLUMN target_type VARCHAR(20) NOT NULL DEFAULT 'oss';
+    END IF;
 
-DROP TRIGGER IF EXISTS update_ratings_updated_at ON ratings;
-CREATE TRIGGER update_ratings_updated_at
-    BEFORE UPDATE ON ratings
-    FOR EACH ROW
-    EXECUTE FUNCTION update_updated_at_column();
+    IF NOT EXISTS (SELECT 1 FROM information_schema.columns WHERE table_name = 'storage_migration_jobs' AND column_name = 'include_images') THEN
+        ALTER TABLE storage_migration_jobs ADD COLUMN include_images BOOLEAN NOT NULL DEFAULT TRUE;
+    END IF;
 
-DROP TRIGGER IF EXISTS update_comments_updated_at ON comments;
-CREATE TRIGGER update_comments_updated_at
-    BEFORE UPDATE ON comments
-    FOR EACH ROW
-    EXECUTE FUNCTION update_updated_at_column();
+    -- delete_source: 迁移成功后是否删除源文件
+    IF NOT EXISTS (SELECT 1 FROM information_schema.columns WHERE table_name = 'storage_migration_jobs' AND column_name = 'delete_source') THEN
+        ALTER TABLE storage_migration_jobs ADD COLUMN delete_source BOOLEAN NOT NULL DEFAULT FALSE;
+    END IF;
 
-DROP TRIGGER IF EXISTS update_teachers_updated_at ON teachers;
-CREATE TRIGGER update_teachers_updated_at
-    BEFORE UPDATE ON teachers
-    FOR EACH ROW
-    EXECUTE FUNCTION update_updated_at_column();
+    -- status: pending（排队）, running（迁移中）, completed（已完成）, failed（失败）
+    IF NOT EXISTS (SELECT 1 FROM information_schema.columns WHERE table_name = 'storage_migration_jobs' AND column_name = 'status') THEN
+        ALTER TABLE storage_migration_jobs ADD COLUMN status VARCHAR(20) NOT NULL DEFAULT 'pending';
+    END IF;
 
-DROP TRIGGER IF EXISTS update_courses_updated_at ON courses;
-CREATE TRIGGER update_courses_updated_at
-    BEFORE UPDATE ON courses
-    FOR EACH ROW
-    EXECUTE FUNCTION update_updated_at_column();
+    IF NOT EXISTS (SELECT 1 FROM information_schema.columns WHERE table_name = 'storage_migration_jobs' AND column_name = 'total_items') THEN
+        ALTER TABLE storage_migration_jobs ADD COLUMN total_items INTEGER NOT NULL DEFAULT 0;
+    END IF;
+
+    IF NOT EXISTS (SELECT 1 FROM information_schema.columns WHERE table_name = 'storage_migration_jobs' AND column_name = 'processed_items') THEN
+        ALTER TABLE storage_migration_jobs ADD COLUMN processed_items INTEGER NOT NULL DEFAULT 0;
+    END IF;
+
+    IF NOT EXISTS (SELECT 1 FROM information_schema.columns WHERE table_name = 'storage_migration_jobs' AND column_name = 'migrated_items') THEN
+        ALTER TABLE storage_migration_jobs ADD COLUMN migrated_items INTEGER NOT NULL DEFAULT 0;
+    END IF;
+
+    IF NOT EXISTS (SELECT 1 FROM information_schema.columns WHERE table_name = 'storage_migration_jobs' AND column_name = 'failed_items') THEN
+        ALTER TABLE storage_migration_jobs ADD COLUMN failed_items INTEGER NOT NULL DEFAULT 0;
+    END IF;
+
+    IF NOT EXISTS (SELECT 1 FROM information_schema.columns WHERE table_name = 'storage_migration_jobs' AND column_name = 'bytes_copied') THEN
+        ALTER TABLE storage_migration_jobs ADD COLUMN bytes_copied BIGINT NOT NULL DEFAULT 0;
+    END IF;
+
+    -- failures: 迁移失败的文件明细 (JSONB 数组，最多保留 100 条)
+    IF NOT EXISTS (SELECT 1 FROM information_schema.columns WHERE table_name = 'storage_migration_jobs' AND column_name = 'failures') THEN
+        ALTER TABLE storage_migration_jobs ADD COLUMN failures JSONB NOT NULL DEFAULT '[]';
+    END IF;
+
+    IF NOT EXISTS (SELECT 1 FROM information_schema.columns WHERE table_name = 'storage_migration_jobs' AND column_name = 'error_message') THEN
+        ALTER TABLE storage_migration_jobs ADD COLUMN error_message TEXT;
+    END IF;
+
+    IF NOT EXISTS (SELECT 1 FROM information_schema.columns WHERE table_name = 'storage_migration_jobs' AND column_name = 'started_at') THEN
+        ALTER TABLE storage_migration_jobs ADD COLUMN started_at TIMESTAMP;
+    END IF;
+
+    IF NOT EXISTS (SELECT 1 FROM information_schema.columns WHERE table_name = 'storage_migration_jobs' AND column_name = 'completed_at') THEN
+        ALTER TABLE storage_migration_jobs ADD COLUMN completed_at TIMESTAMP;
+    END IF;
+
+    IF NOT EXISTS (SELECT 1 FROM information_schema.columns WHERE table_name = 'storage_migration_jobs' AND column_name = 'updated_at') THEN
+        ALTER TABLE storage_migration_jobs ADD COLUMN updated_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP;
+    END IF;
+END $$;
+
+-- 添加检查约束：迁移任务状态
+DO $$
+BEGIN
+    IF NOT EXISTS (
+        SELECT 1 FROM pg_constraint
+        WHERE conname = 'storage_migration_jobs_status_check' AND conrelid = 'storage_migration_jobs'::regclass
+    ) THEN
+        ALTER TABLE storage_migration_jobs ADD CONSTRAINT storage_migration_jobs_status_check
+            CHECK (status IN ('pending', 'running', 'completed', 'failed'));
+    END IF;
+END $$;
+
+-- ============================================
+-- 22. 登录会话表（Refresh Token 轮换与吊销）
+-- ============================================
+CREATE TABLE IF NOT EXISTS user_sessions (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP
+);
+
+DO $$
+BEGIN
+    IF NOT EXISTS (SELECT 1 FROM information_schema.columns WHERE table_name = 'user_sessions' AND column_name = 'user_id') THEN
+        IF EXISTS (SELECT 1 FROM user_sessions LIMIT 1) THEN
+            ALTER TABLE user_sessions ADD COLUMN user_id UUID REFERENCES users(id) ON DELETE CASCADE;
+        ELSE
+            ALTER TABLE user_sessions ADD COLUMN user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE DEFAULT '00000000-0000-0000-0000-000000000000';
+        END IF;
+    END IF;
+
+    -- refresh_token_hash: 当前有效 Refresh Token 的 SHA-256，轮换后更新
+    IF NOT EXISTS (SELECT 1 FROM information_schema.columns WHERE table_name = 'user_sessions' AND column_name = 'refresh_token_hash') THEN
+        ALTER TABLE user_sessions ADD COLUMN refresh_token_hash VARCHAR(64) NOT NULL DEFAULT '';
+    END IF;
+
+    -- previous_token_hash: 上一个 Refresh Token 的哈希，用于识别重放
+    IF NOT EXISTS (SELECT 1 FROM information_schema.columns WHERE table_name = 'user_sessions' AND column_name = 'previous_token_hash') THEN
+        ALTER TABLE user_sessions ADD COLUMN previous_token_hash VARCHAR(64);
+    END IF;
+
+    IF NOT EXISTS (SELECT 1 FROM information_schema.columns WHERE table_name = 'user_sessions' AND column_name = 'user_agent') THEN
+        ALTER TABLE user_sessions ADD COLUMN user_agent VARCHAR(500);
+    END IF;
+
+    IF NOT EXISTS (SELECT 1 FROM information_schema.columns WHERE table_name = 'user_sessions' AND column_name = 'ip_address') THEN
+        ALTER TABLE user_sessions ADD COLUMN ip_address VARCHAR(64);
+    END IF;
+
+    IF NOT EXISTS (SELECT 1 FROM information_schema.columns WHERE table_name = 'user_sessions' AND column_name = 'last_used_at') THEN
+        ALTER TABLE user_sessions ADD COLUMN last_used_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP;
+    END IF;
+
+    IF NOT EXISTS (SELECT 1 FROM information_schema.columns WHERE table_name = 'user_sessions' AND column_name = 'expires_at') THEN
+        ALTER TABLE user_sessions ADD COLUMN expires_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP;
+    END IF;
+
+    -- revoked_at / revoke_reason: 吊销时间和原因（logout / revoked / revoke_all / reuse_detected / user_disabled / password_reset）
+    IF NOT EXISTS (SELECT 1 FROM information_schema.columns WHERE table_name = 'user_sessions' AND column_name = 'revoked_at') THEN
+        ALTER TABLE user_sessions ADD COLUMN revoked_at TIMESTAMP;
+    END IF;
+
+    IF NOT EXISTS (SELECT 1 FROM information_schema.columns WHERE table_name = 'user_sessions' AND column_name = 'revoke_reason') THEN
+        ALTER TABLE user_sessions ADD COLUMN revoke_reason VARCHAR(50);
+    END IF;
+
+    -- mfa_verified_at: 登录时完成两步验证的时间，为空表示该会话未经过两步验证
+    IF NOT EXISTS (SELECT 1 FROM information_schema.columns WHERE table_name = 'user_sessions' AND column_name = 'mfa_verified_at') THEN
+        ALTER TABLE user_sessions ADD COLUMN mfa_verified_at TIMESTAMP;
+    END IF;
+END $$;
+
+-- ============================================
+-- 23. 邮件令牌表（邮箱验证与密码重置的一次性令牌）
+-- ============================================
+CREATE TABLE IF NOT EXISTS email_tokens (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP
+);
+
+DO $$
+BEGIN
+    IF NOT EXISTS (SELECT 1 FROM information_schema.columns WHERE table_name = 'email_tokens' AND column_name = 'user_id') THEN
+        IF EXISTS (SELECT 1 FROM email_tokens LIMIT 1) THEN
+            ALTER TABLE email_tokens ADD COLUMN user_id UUID REFERENCES users(id) ON DELETE CASCADE;
+        ELSE
+            ALTER TABLE email_tokens ADD COLUMN user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE DEFAULT '00000000-0000-0000-0000-000000000000';
+        END IF;
+    END IF;
+
+    -- purpose: verify_email / reset_password
+    IF NOT EXISTS (SELECT 1 FROM information_schema.columns WHERE table_name = 'email_tokens' AND column_name = 'purpose') THEN
+        ALTER TABLE email_tokens ADD COLUMN purpose VARCHAR(20) NOT NULL DEFAULT 'verify_email';
+    END IF;
+
+    -- email: 令牌发送到的邮箱，邮箱变更后旧令牌失效
+    IF NOT EXISTS (SELECT 1 FROM information_schema.columns WHERE table_name = 'email_tokens' AND column_name = 'email') THEN
+        ALTER TABLE email_tokens ADD COLUMN email VARCHAR(255) NOT NULL DEFAULT '';
+    END IF;
+
+    IF NOT EXISTS (SELECT 1 FROM information_schema.columns WHERE table_name = 'email_tokens' AND column_name = 'expires_at') THEN
+        ALTER TABLE email_tokens ADD COLUMN expires_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP;
+    END IF;
+
+    -- used_at: 令牌使用（或被新令牌取代）的时间，非空表示已失效
+    IF NOT EXISTS (SELECT 1 FROM information_schema.columns WHERE table_name = 'email_tokens' AND column_name = 'used_at') THEN
+        ALTER TABLE email_tokens ADD COLUMN used_at TIMESTAMP;
+    END IF;
+END $$;
+
+-- ============================================
+-- 24. 两步验证恢复码表
+-- ============================================
+CREATE TABLE IF NOT EXISTS user_recovery_codes (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP
+);
+
+DO $$
+BEGIN
+    IF NOT EXISTS (SELECT 1 FROM information_schema.columns WHERE table_name = 'user_recovery_codes' AND column_name = 'user_id') THEN
+        IF EXISTS (SELECT 1 FROM user_recovery_codes LIMIT 1) THEN
+            ALTER TABLE user_recovery_codes ADD COLUMN user_id UUID REFERENCES users(id) ON DELETE CASCADE;
+        ELSE
+            ALTER TABLE user_recovery_codes ADD COLUMN user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE DEFAULT '00000000-0000-0000-0000-000000000000';
+        END IF;
+    END IF;
+
+    -- code_hash: 恢复码的 SHA-256，明文只在生成时返回一次
+    IF NOT EXISTS (SELECT 1 FROM information_schema.columns WHERE table_name = 'user_recovery_codes' AND column_name = 'code_hash') THEN
+        ALTER TABLE user_recovery_codes ADD COLUMN code_hash VARCHAR(64) NOT NULL DEFAULT '';
+    END IF;
+
+    -- used_at: 恢复码使用时间，每个恢复码只能使用一次
+    IF NOT EXISTS (SELECT 1 FROM information_schema.columns WHERE table_name = 'user_recovery_codes' AND column_name = 'used_at') THEN
+        ALTER TABLE user_recovery_codes ADD COLUMN used_at TIMESTAMP;
+    END IF;
+END $$;
+
+-- ============================================
+-- 25. 外部身份绑定表（OIDC 单点登录）
+-- ============================================
+CREATE TABLE IF NOT EXISTS user_identities (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP
+);
+
+DO $$
+BEGIN
+    IF NOT EXISTS (SELECT 1 FROM information_schema.columns WHERE table_name = 'user_identities' AND column_name = 'user_id') THEN
+        IF EXISTS (SELECT 1 FROM user_identities LIMIT 1) THEN
+            ALTER TABLE user_identities ADD COLUMN user_id UUID REFERENCES users(id) ON DELETE CASCADE;
+        ELSE
+            ALTER TABLE user_identities ADD COLUMN user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE DEFAULT '00000000-0000-0000-0000-000000000000';
+        END IF;
+    END IF;
+
+    -- provider: 身份提供方的 Issuer
+    IF NOT EXISTS (SELECT 1 FROM information_schema.columns WHERE table_name = 'user_identities' AND column_name = 'provider') THEN
+        ALTER TABLE user_identities ADD COLUMN provider VARCHAR(255) NOT NULL DEFAULT '';
+    END IF;
+
+    -- subject: 身份提供方中的用户唯一标识（sub 声明）
+    IF NOT EXISTS (SELECT 1 FROM information_schema.columns WHERE table_name = 'user_identities' AND column_name = 'subject') THEN
+        ALTER TABLE user_identities ADD COLUMN subject VARCHAR(255) NOT NULL DEFAULT '';
+    END IF;
+
+    IF NOT EXISTS (SELECT 1 FROM information_schema.columns WHERE table_name = 'user_identities' AND column_name = 'email') THEN
+        ALTER TABLE user_identities ADD COLUMN email VARCHAR(255);
+    END IF;
+
+    -- claims: 最近一次登录时身份提供方返回的声明
+    IF NOT EXISTS (SELECT 1 FROM information_schema.columns WHERE table_name = 'user_identities' AND column_name = 'claims') THEN
+        ALTER TABLE user_identities ADD COLUMN claims JSONB NOT NULL DEFAULT '{}';
+    END IF;
+
+    IF NOT EXISTS (SELECT 1 FROM information_schema.columns WHERE table_name = 'user_identities' AND column_name = 'last_login_at') THEN
+        ALTER TABLE user_identities ADD COLUMN last_login_at TIMESTAMP;
+    END IF;
+END $$;
+
+-- 同一外部身份只能绑定一个账号
+DO $$
+BEGIN
+    IF NOT EXISTS (
+        SELECT 1 FROM pg_constraint
+        WHERE conname = 'user_identities_provider_subject_key' AND conrelid = 'user_identities'::regclass
+    ) THEN
+        ALTER TABLE user_identities ADD CONSTRAINT user_identities_provider_subject_key UNIQUE (provider, subject);
+    END IF;
+EXCEPTION
+    WHEN unique_violation THEN
+        RAISE NOTICE '无法添加唯一约束：存在重复数据 (provider, subject)';
+END $$;
+
+-- ============================================
+-- 26. OIDC 登录状态表
+-- ============================================
+CREATE TABLE IF NOT EXISTS oidc_login_states (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP
+);
+
+DO $$
+BEGIN
+    -- state_hash: state 参数的 SHA-256，每个 state 只能使用一次
+    IF NOT EXISTS (SELECT 1 FROM information_schema.columns WHERE table_name = 'oidc_login_states' AND column_name = 'state_hash') THEN
+        ALTER TABLE oidc_login_states ADD COLUMN state_hash VARCHAR(64) NOT NULL DEFAULT '';
+    END IF;
+
+    -- code_verifier: PKCE 校验码
+    IF NOT EXISTS (SELECT 1 FROM information_schema.columns WHERE table_name = 'oidc_login_states' AND column_name = 'code_verifier') THEN
+        ALTER TABLE oidc_login_states ADD COLUMN code_verifier VARCHAR(128) NOT NULL DEFAULT '';
+    END IF;
+
+    IF NOT EXISTS (SELECT 1 FROM information_schema.columns WHERE table_name = 'oidc_login_states' AND column_name = 'nonce') THEN
+        ALTER TABLE oidc_login_states ADD COLUMN nonce VARCHAR(128) NOT NULL DEFAULT '';
+    END IF;
+
+    -- link_user_id: 已登录用户绑定外部身份时的用户ID，为空表示登录
+    IF NOT EXISTS (SELECT 1 FROM information_schema.columns WHERE table_name = 'oidc_login_states' AND column_name = 'link_user_id') THEN
+        ALTER TABLE oidc_login_states ADD COLUMN link_user_id UUID REFERENCES users(id) ON DELETE CASCADE;
+    END IF;
+
+    -- redirect_path: 登录完成后跳转的前端路径
+    IF NOT EXISTS (SELECT 1 FROM information_schema.columns WHERE table_name = 'oidc_login_states' AND column_name = 'redirect_path') THEN
+        ALTER TABLE oidc_login_states ADD COLUMN redirect_path VARCHAR(500) NOT NULL DEFAULT '/';
+    END IF;
+
+    IF NOT EXISTS (SELECT 1 FROM information_schema.columns WHERE table_name = 'oidc_login_states' AND column_name = 'expires_at') THEN
+        ALTER TABLE oidc_login_states ADD COLUMN expires_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP;
+    END IF;
+END $$;
+
+-- ============================================
+-- 27. 角色表（管理后台权限分组）
+-- ============================================
+CREATE TABLE IF NOT EXISTS roles (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP
+);
+
+DO $$
+BEGIN
+    -- name: 角色标识，如 moderator
+    IF NOT EXISTS (SELECT 1 FROM information_schema.columns WHERE table_name = 'roles' AND column_name = 'name') THEN
+        ALTER TABLE roles ADD COLUMN name VARCHAR(50) NOT NULL DEFAULT '';
+    END IF;
+
+    IF NOT EXISTS (SELECT 1 FROM information_schema.columns WHERE table_name = 'roles' AND column_name = 'display_name') THEN
+        ALTER TABLE roles ADD COLUMN display_name VARCHAR(100) NOT NULL DEFAULT '';
+    END IF;
+
+    IF NOT EXISTS (SELECT 1 FROM information_schema.columns WHERE table_name = 'roles' AND column_name = 'description') THEN
+        ALTER TABLE roles ADD COLUMN description TEXT;
+    END IF;
+
+    -- permissions: 权限名列表，如 resource.audit、comment.delete
+    IF NOT EXISTS (SELECT 1 FROM information_schema.columns WHERE table_name = 'roles' AND column_name = 'permissions') THEN
+        ALTER TABLE roles ADD COLUMN permissions TEXT[] NOT NULL DEFAULT '{}';
+    END IF;
+
+    -- is_system: 内置角色，不能删除
+    IF NOT EXISTS (SELECT 1 FROM information_schema.columns WHERE table_name = 'roles' AND column_name = 'is_system') THEN
+        ALTER TABLE roles ADD COLUMN is_system BOOLEAN NOT NULL DEFAULT FALSE;
+    END IF;
+
+    IF NOT EXISTS (SELECT 1 FROM information_schema.columns WHERE table_name = 'roles' AND column_name = 'updated_at') THEN
+        ALTER TABLE roles ADD COLUMN updated_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP;
+    END IF;
+END $$;
+
+DO $$
+BEGIN
+    IF NOT EXISTS (
+        SELECT 1 FROM pg_constraint
+        WHERE conname = 'roles_name_key' AND conrelid = 'roles'::regclass
+    ) THEN
+        ALTER TABLE roles ADD CONSTRAINT roles_name_key UNIQUE (name);
+    END IF;
+EXCEPTION
+    WHEN unique_violation THEN
+        RAISE NOTICE '无法添加唯一约束：存在重复数据 (name)';
+END $$;
+
+-- 内置角色（已存在时不覆盖管理员的修改）
+INSERT INTO roles (name, display_name, description, permissions, is_system)
+VALUES
+    ('moderator', '内容审核员', '审核资源和评论，处理用户举报，可按课程分配为课程版主',
+        ARRAY['resource.audit', 'comment.audit', 'comment.delete', 'report.review'], TRUE),
+    ('content_manager', '内容管理员', '管理资源、评论、课程、教师和资源申领，处理用户举报',
+        ARRAY['resource.audit', 'resource.manage', 'comment.audit', 'comment.delete', 'teacher.manage', 'course.manage', 'claim.review', 'report.review'], TRUE),
+    ('user_manager', '用户管理员', '管理用户状态，审核和查看实名信息，查看审计日志',
+        ARRAY['user.read', 'user.manage', 'user.real_info.read', 'verification.review', 'audit_log.read'], TRUE)
+ON CONFLICT (name) DO NOTHING;
+
+-- ============================================
+-- 28. 用户角色分配表
+-- ============================================
+CREATE TABLE IF NOT EXISTS user_roles (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP
+);
+
+DO $$
+BEGIN
+    IF NOT EXISTS (SELECT 1 FROM information_schema.columns WHERE table_name = 'user_roles' AND column_name = 'user_id') THEN
+        IF EXISTS (SELECT 1 FROM user_roles LIMIT 1) THEN
+            ALTER TABLE user_roles ADD COLUMN user_id UUID REFERENCES users(id) ON DELETE CASCADE;
+        ELSE
+            ALTER TABLE user_roles ADD COLUMN user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE DEFAULT '00000000-0000-0000-0000-000000000000';
+        END IF;
+    END IF;
+
+    IF NOT EXISTS (SELECT 1 FROM information_schema.columns WHERE table_name = 'user_roles' AND column_name = 'role_id') THEN
+        IF EXISTS (SELECT 1 FROM user_roles LIMIT 1) THEN
+            ALTER TABLE user_roles ADD COLUMN role_id UUID REFERENCES roles(id) ON DELETE CASCADE;
+        ELSE
+            ALTER TABLE user_roles ADD COLUMN role_id UUID NOT NULL REFERENCES roles(id) ON DELETE CASCADE DEFAULT '00000000-0000-0000-0000-000000000000';
+        END IF;
+    END IF;
+
+    -- course_sn: 只在该课程范围内生效（课程版主），为空表示全站
+    IF NOT EXISTS (SELECT 1 FROM information_schema.columns WHERE table_name = 'user_roles' AND column_name = 'course_sn') THEN
+        ALTER TABLE user_roles ADD COLUMN course_sn BIGINT REFERENCES courses(sn) ON DELETE CASCADE;
+    END IF;
+
+    -- granted_by: 分配该角色的管理员
+    IF NOT EXISTS (SELECT 1 FROM information_schema.columns WHERE table_name = 'user_roles' AND column_name = 'granted_by') THEN
+        ALTER TABLE user_roles ADD COLUMN granted_by UUID REFERENCES users(id) ON DELETE SET NULL;
+    END IF;
+END $$;
+
+-- ============================================
+-- 29. 实名认证申请表
+-- ============================================
+CREATE TABLE IF NOT EXISTS verification_requests (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP
+);
+
+DO $$
+BEGIN
+    IF NOT EXISTS (SELECT 1 FROM information_schema.columns WHERE table_name = 'verification_requests' AND column_name = 'user_id') THEN
+        IF EXISTS (SELECT 1 FROM verification_requests LIMIT 1) THEN
+            ALTER TABLE verification_requests ADD COLUMN user_id UUID REFERENCES users(id) ON DELETE CASCADE;
+        ELSE
+            ALTER TABLE verification_requests ADD COLUMN user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE DEFAULT '00000000-0000-0000-0000-000000000000';
+        END IF;
+    END IF;
+
+    -- real_info: 提交的实名信息（与 users.real_info 格式相同），审核后清空
+    IF NOT EXISTS (SELECT 1 FROM information_schema.columns WHERE table_name = 'verification_requests' AND column_name = 'real_info') THEN
+        ALTER TABLE verification_requests ADD COLUMN real_info JSONB NOT NULL DEFAULT '{}';
+    END IF;
+
+    -- proof_image_id: 证明材料（如学生证照片，来自图床）
+    IF NOT EXISTS (SELECT 1 FROM information_schema.columns WHERE table_name = 'verification_requests' AND column_name = 'proof_image_id') THEN
+        ALTER TABLE verification_requests ADD COLUMN proof_image_id UUID REFERENCES images(id) ON DELETE SET NULL;
+    END IF;
+
+    -- status: pending / approved / rejected
+    IF NOT EXISTS (SELECT 1 FROM information_schema.columns WHERE table_name = 'verification_requests' AND column_name = 'status') THEN
+        ALTER TABLE verification_requests ADD COLUMN status VARCHAR(20) NOT NULL DEFAULT 'pending';
+    END IF;
+
+    IF NOT EXISTS (SELECT 1 FROM information_schema.columns WHERE table_name = 'verification_requests' AND column_name = 'reviewer_id') THEN
+        ALTER TABLE verification_requests ADD COLUMN reviewer_id UUID REFERENCES users(id) ON DELETE SET NULL;
+    END IF;
+
+    IF NOT EXISTS (SELECT 1 FROM information_schema.columns WHERE table_name = 'verification_requests' AND column_name = 'review_reason') THEN
+        ALTER TABLE verification_requests ADD COLUMN review_reason TEXT;
+    END IF;
+
+    IF NOT EXISTS (SELECT 1 FROM information_schema.columns WHERE table_name = 'verification_requests' AND column_name = 'reviewed_at') THEN
+        ALTER TABLE verification_requests ADD COLUMN reviewed_at TIMESTAMP;
+    END IF;
+END $$;
+
+-- ============================================
+-- 30. 评论编辑历史表
+-- ============================================
+CREATE TABLE IF NOT EXISTS comment_edits (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP
+);
+
+DO $$
+BEGIN
+    IF NOT EXISTS (SELECT 1 FROM information_schema.columns WHERE table_name = 'comment_edits' AND column_name = 'comment_id') THEN
+        IF EXISTS (SELECT 1 FROM comment_edits LIMIT 1) THEN
+            ALTER TABLE comment_edits ADD COLUMN comment_id UUID REFERENCES comments(id) ON DELETE CASCADE;
+        ELSE
+            ALTER TABLE comment_edits ADD COLUMN comment_id UUID NOT NULL REFERENCES comments(id) ON DELETE CASCADE DEFAULT '00000000-0000-0000-0000-000000000000';
+        END IF;
+    END IF;
+
+    -- content: 编辑前的评论内容
+    IF NOT EXISTS (SELECT 1 FROM information_schema.columns WHERE table_name = 'comment_edits' AND column_name = 'content') THEN
+        ALTER TABLE comment_edits ADD COLUMN content TEXT NOT NULL DEFAULT '';
+    END IF;
+END $$;
+
+-- ============================================
+-- 31. 评论投票表
+-- ============================================
+CREATE TABLE IF NOT EXISTS comment_votes (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP
+);
+
+DO $$
+BEGIN
+    IF NOT EXISTS (SELECT 1 FROM information_schema.columns WHERE table_name = 'comment_votes' AND column_name = 'comment_id') THEN
+        IF EXISTS (SELECT 1 FROM comment_votes LIMIT 1) THEN
+            ALTER TABLE comment_votes ADD COLUMN comment_id UUID REFERENCES comments(id) ON DELETE CASCADE;
+        ELSE
+            ALTER TABLE comment_votes ADD COLUMN comment_id UUID NOT NULL REFERENCES comments(id) ON DELETE CASCADE DEFAULT '00000000-0000-0000-0000-000000000000';
+        END IF;
+    END IF;
+
+    IF NOT EXISTS (SELECT 1 FROM information_schema.columns WHERE table_name = 'comment_votes' AND column_name = 'user_id') THEN
+        IF EXISTS (SELECT 1 FROM comment_votes LIMIT 1) THEN
+            ALTER TABLE comment_votes ADD COLUMN user_id UUID REFERENCES users(id) ON DELETE CASCADE;
+        ELSE
+            ALTER TABLE comment_votes ADD COLUMN user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE DEFAULT '00000000-0000-0000-0000-000000000000';
+        END IF;
+    END IF;
+
+    -- value: 1 赞同，-1 反对
+    IF NOT EXISTS (SELECT 1 FROM information_schema.columns WHERE table_name = 'comment_votes' AND column_name = 'value') THEN
+        ALTER TABLE comment_votes ADD COLUMN value SMALLINT NOT NULL DEFAULT 1 CHECK (value IN (-1, 1));
+    END IF;
+END $$;
+
+-- ============================================
+-- 32. 举报表
+-- ============================================
+CREATE TABLE IF NOT EXISTS reports (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP
+);
+
+DO $$
+BEGIN
+    -- target_type: resource / comment
+    IF NOT EXISTS (SELECT 1 FROM information_schema.columns WHERE table_name = 'reports' AND column_name = 'target_type') THEN
+        ALTER TABLE reports ADD COLUMN target_type VARCHAR(20) NOT NULL DEFAULT 'resource';
+    END IF;
+
+    -- target_id: 被举报的资源或评论（对象删除后保留举报记录）
+    IF NOT EXISTS (SELECT 1 FROM information_schema.columns WHERE table_name = 'reports' AND column_name = 'target_id') THEN
+        ALTER TABLE reports ADD COLUMN target_id UUID NOT NULL DEFAULT '00000000-0000-0000-0000-000000000000';
+    END IF;
+
+    IF NOT EXISTS (SELECT 1 FROM information_schema.columns WHERE table_name = 'reports' AND column_name = 'reporter_id') THEN
+        IF EXISTS (SELECT 1 FROM reports LIMIT 1) THEN
+            ALTER TABLE reports ADD COLUMN reporter_id UUID REFERENCES users(id) ON DELETE CASCADE;
+        ELSE
+            ALTER TABLE reports ADD COLUMN reporter_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE DEFAULT '00000000-0000-0000-0000-000000000000';
+        END IF;
+    END IF;
+
+    -- reason: copyright / incorrect / abusive / spam / other
+    IF NOT EXISTS (SELECT 1 FROM information_schema.columns WHERE table_name = 'reports' AND column_name = 'reason') THEN
+        ALTER TABLE reports ADD COLUMN reason VARCHAR(20) NOT NULL DEFAULT 'other';
+    END IF;
+
+    IF NOT EXISTS (SELECT 1 FROM information_schema.columns WHERE table_name = 'reports' AND column_name = 'description') THEN
+        ALTER TABLE reports ADD COLUMN description TEXT;
+    END IF;
+
+    -- status: pending / resolved / dismissed
+    IF NOT EXISTS (SELECT 1 FROM information_schema.columns WHERE table_name = 'reports' AND column_name = 'status') THEN
+        ALTER TABLE reports ADD COLUMN status VARCHAR(20) NOT NULL DEFAULT 'pending';
+    END IF;
+
+    -- action: dismiss / hide / delete / warn_uploader
+    IF NOT EXISTS (SELECT 1 FROM information_schema.columns WHERE table_name = 'reports' AND column_name = 'action') THEN
+        ALTER TABLE reports ADD COLUMN action VARCHAR(20);
+    END IF;
+
+    IF NOT EXISTS (SELECT 1 FROM information_schema.columns WHERE table_name = 'reports' AND column_name = 'handled_by') THEN
+        ALTER TABLE reports ADD COLUMN handled_by UUID REFERENCES users(id) ON DELETE SET NULL;
+    END IF;
+
+    IF NOT EXISTS (SELECT 1 FROM information_schema.columns WHERE table_name = 'reports' AND column_name = 'resolution_note') THEN
+        ALTER TABLE reports ADD COLUMN resolution_note TEXT;
+    END IF;
+
+    IF NOT EXISTS (SELECT 1 FROM information_schema.columns WHERE table_name = 'reports' AND column_name = 'resolved_at') THEN
+        ALTER TABLE reports ADD COLUMN resolved_at TIMESTAMP;
+    END IF;
+END $$;
+
+-- ============================================
+-- 33. 资源相似度表（推荐任务定期重建的物化结果）
+-- ============================================
+CREATE TABLE IF NOT EXISTS resource_similarities (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP
+);
+
+DO $$
+BEGIN
+    -- kind: interaction（点赞/收藏/评分/下载综合行为）/ download（仅下载行为）
+    IF NOT EXISTS (SELECT 1 FROM information_schema.columns WHERE table_name = 'resource_similarities' AND column_name = 'kind') THEN
+        ALTER TABLE resource_similarities ADD COLUMN kind VARCHAR(20) NOT NULL DEFAULT 'interaction';
+    END IF;
+
+    IF NOT EXISTS (SELECT 1 FROM information_schema.columns WHERE table_name = 'resource_similarities' AND column_name = 'resource_id') THEN
+        IF EXISTS (SELECT 1 FROM resource_similarities LIMIT 1) THEN
+            ALTER TABLE resource_similarities ADD COLUMN resource_id UUID REFERENCES resources(id) ON DELETE CASCADE;
+        ELSE
+            ALTER TABLE resource_similarities ADD COLUMN resource_id UUID NOT NULL REFERENCES resources(id) ON DELETE CASCADE DEFAULT '00000000-0000-0000-0000-000000000000';
+        END IF;
+    END IF;
+
+    IF NOT EXISTS (SELECT 1 FROM information_schema.columns WHERE table_name = 'resource_similarities' AND column_name = 'similar_resource_id') THEN
+        IF EXISTS (SELECT 1 FROM resource_similarities LIMIT 1) THEN
+            ALTER TABLE resource_similarities ADD COLUMN similar_resource_id UUID REFERENCES resources(id) ON DELETE CASCADE;
+        ELSE
+            ALTER TABLE resource_similarities ADD COLUMN similar_resource_id UUID NOT NULL REFERENCES resources(id) ON DELETE CASCADE DEFAULT '00000000-0000-0000-0000-000000000000';
+        END IF;
+    END IF;
+
+    -- score: 余弦相似度（已按共同用户数收缩）
+    IF NOT EXISTS (SELECT 1 FROM information_schema.columns WHERE table_name = 'resource_similarities' AND column_name = 'score') THEN
+        ALTER TABLE resource_similarities ADD COLUMN score DOUBLE PRECISION NOT NULL DEFAULT 0;
+    END IF;
+
+    -- co_users: 同时与两个资源有交互的用户数
+    IF NOT EXISTS (SELECT 1 FROM information_schema.columns WHERE table_name = 'resource_similarities' AND column_name = 'co_users') THEN
+        ALTER TABLE resource_similarities ADD COLUMN co_users INTEGER NOT NULL DEFAULT 0;
+    END IF;
+END $$;
+
+-- ============================================
+-- 34. 资源每日浏览量表（用于计算时间窗口内的热度）
+-- ============================================
+CREATE TABLE IF NOT EXISTS resource_daily_views (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP
+);
+
+DO $$
+BEGIN
+    IF NOT EXISTS (SELECT 1 FROM information_schema.columns WHERE table_name = 'resource_daily_views' AND column_name = 'resource_id') THEN
+        IF EXISTS (SELECT 1 FROM resource_daily_views LIMIT 1) THEN
+            ALTER TABLE resource_daily_views ADD COLUMN resource_id UUID REFERENCES resources(id) ON DELETE CASCADE;
+        ELSE
+            ALTER TABLE resource_daily_views ADD COLUMN resource_id UUID NOT NULL REFERENCES resources(id) ON DELETE CASCADE DEFAULT '00000000-0000-0000-0000-000000000000';
+        END IF;
+    END IF;
+
+    IF NOT EXISTS (SELECT 1 FROM information_schema.columns WHERE table_name = 'resource_daily_views' AND column_name = 'view_date') THEN
+        ALTER TABLE resource_daily_views ADD COLUMN view_date DATE NOT NULL DEFAULT CURRENT_DATE;
+    END IF;
+
+    IF NOT EXISTS (SELECT 1 FROM information_schema.columns WHERE table_name = 'resource_daily_views' AND column_name = 'views') THEN
+        ALTER TABLE resource_daily_views ADD COLUMN views INTEGER NOT NULL DEFAULT 0;
+    END IF;
+END $$;
+
+-- ============================================
+-- 35. 资源热度分表（热度任务定期重建的物化结果）
+-- ============================================
+CREATE TABLE IF NOT EXISTS resource_trending_scores (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP
+);
+
+DO $$
+BEGIN
+    -- time_window: day / week / semester
+    IF NOT EXISTS (SELECT 1 FROM information_schema.columns WHERE table_name = 'resource_trending_scores' AND column_name = 'time_window') THEN
+        ALTER TABLE resource_trending_scores ADD COLUMN time_window VARCHAR(20) NOT NULL DEFAULT 'week';
+    END IF;
+
+    IF NOT EXISTS (SELECT 1 FROM information_schema.columns WHERE table_name = 'resource_trending_scores' AND column_name = 'resource_id') THEN
+        IF EXISTS (SELECT 1 FROM resource_trending_scores LIMIT 1) THEN
+            ALTER TABLE resource_trending_scores ADD COLUMN resource_id UUID REFERENCES resources(id) ON DELETE CASCADE;
+        ELSE
+            ALTER TABLE resource_trending_scores ADD COLUMN resource_id UUID NOT NULL REFERENCES resources(id) ON DELETE CASCADE DEFAULT '00000000-0000-0000-0000-000000000000';
+        END IF;
+    END IF;
+
+    -- score: 窗口内浏览、下载、点赞、收藏按时间指数衰减后的加权和
+    IF NOT EXISTS (SELECT 1 FROM information_schema.columns WHERE table_name = 'resource_trending_scores' AND column_name = 'score') THEN
+        ALTER TABLE resource_trending_scores ADD COLUMN score DOUBLE PRECISION NOT NULL DEFAULT 0;
+    END IF;
+END $$;
+
+-- ============================================
+-- 为现有用户分配 sn（增量更新支持）
+-- ============================================
+DO $$
+DECLARE
+    user_record RECORD;
+    current_sn BIGINT := 1;
+BEGIN
+    FOR user_record IN
+        SELECT id FROM users WHERE sn IS NULL ORDER BY created_at ASC
+    LOOP
+        UPDATE users SET sn = current_sn WHERE id = user_record.id;
+        current_sn := current_sn + 1;
+    END LOOP;
+    IF current_sn > 1 THEN
+        PERFORM setval('user_sn_seq', current_sn - 1, true);
+    END IF;
+END $$;
+
+-- ============================================
+-- 创建索引
+-- ============================================
+CREATE INDEX IF NOT EXISTS idx_users_role ON users(role);
+CREATE INDEX IF NOT EXISTS idx_users_is_verified ON users(is_verified);
+CREATE INDEX IF NOT EXISTS idx_users_sn ON users(sn);
+CREATE INDEX IF NOT EXISTS idx_resources_uploader ON resources(uploader_id);
+CREATE INDEX IF NOT EXISTS idx_resources_author ON resources(author_id);
+CREATE INDEX IF NOT EXISTS idx_resources_course ON resources(course_name);
+CREATE INDEX IF NOT EXISTS idx_resources_type ON resources(resource_type);
+CREATE INDEX IF NOT EXISTS idx_resources_category ON resources(category);
+CREATE INDEX IF NOT EXISTS idx_resources_audit_status ON resources(audit_status);
+CREATE INDEX IF NOT EXISTS idx_resources_tags ON resources USING GIN(tags);
+CREATE INDEX IF NOT EXISTS idx_resources_created_at ON resources(created_at DESC);
+CREATE INDEX IF NOT EXISTS idx_resources_storage_type ON resources(storage_type);
+CREATE INDEX IF NOT EXISTS idx_resources_file_hash ON resources(file_hash) WHERE file_hash IS NOT NULL;
+CREATE INDEX IF NOT EXISTS idx_resources_search_vector ON resources USING GIN(search_vector);
+CREATE INDEX IF NOT EXISTS idx_resources_search_document_trgm ON resources USING GIN(search_document gin_trgm_ops);
+CREATE INDEX IF NOT EXISTS idx_ratings_resource ON ratings(resource_id);
+CREATE INDEX IF NOT EXISTS idx_ratings_user ON ratings(user_id);
+CREATE INDEX IF NOT EXISTS idx_likes_user ON likes(user_id);
+CREATE INDEX IF NOT EXISTS idx_comments_resource ON comments(resource_id);
+CREATE INDEX IF NOT EXISTS idx_comments_user ON comments(user_id);
+CREATE INDEX IF NOT EXISTS idx_comments_created_at ON comments(created_at DESC);
+CREATE INDEX IF NOT EXISTS idx_comments_root ON comments(root_id, created_at);
+
+-- 评论编辑历史索引
+CREATE INDEX IF NOT EXISTS idx_comment_edits_comment ON comment_edits(comment_id, created_at DESC);
+
+-- 评论投票索引（每个用户对每条评论只能投一票）
+CREATE UNIQUE INDEX IF NOT EXISTS idx_comment_votes_unique ON comment_votes(comment_id, user_id);
+CREATE INDEX IF NOT EXISTS idx_comment_votes_user ON comment_votes(user_id);
+
+-- 举报索引（同一用户对同一对象只能有一条待处理举报）
+CREATE UNIQUE INDEX IF NOT EXISTS idx_reports_pending ON reports(reporter_id, target_type, target_id) WHERE status = 'pending';
+CREATE INDEX IF NOT EXISTS idx_reports_target ON reports(target_type, target_id, status);
+CREATE INDEX IF NOT EXISTS idx_reports_status ON reports(status, created_at);
+CREATE INDEX IF NOT EXISTS idx_reports_reporter ON reports(reporter_id, created_at DESC);
+CREATE UNIQUE INDEX IF NOT EXISTS idx_resource_similarities_pair ON resource_similarities(kind, resource_id, similar_resource_id);
+CREATE INDEX IF NOT EXISTS idx_resource_similarities_score ON resource_similarities(kind, resource_id, score DESC);
+CREATE UNIQUE INDEX IF NOT EXISTS idx_resource_daily_views_date ON resource_daily_views(resource_id, view_date);
+CREATE INDEX IF NOT EXISTS idx_resource_daily_views_view_date ON resource_daily_views(view_date);
+CREATE UNIQUE INDEX IF NOT EXISTS idx_resource_trending_scores_resource ON resource_trending_scores(time_window, resource_id);
+CREATE INDEX IF NOT EXISTS idx_resource_trending_scores_score ON resource_trending_scores(time_window, score DESC);
+CREATE INDEX IF NOT EXISTS idx_favorites_user ON favorites(user_id);
+CREATE INDEX IF NOT EXISTS idx_fav_res_resource ON favorite_resources(resource_id);
+CREATE INDEX IF NOT EXISTS idx_claims_resource ON claims(resource_id);
+CREATE INDEX IF NOT EXISTS idx_claims_applicant ON claims(applicant_id);
+CREATE INDEX IF NOT EXISTS idx_claims_status ON claims(status);
+CREATE UNIQUE INDEX IF NOT EXISTS idx_claims_pending ON claims(resource_id, applicant_id) WHERE status = 'pending';
+CREATE INDEX IF NOT EXISTS idx_notifications_recipient ON notifications(recipient_id);
+CREATE INDEX IF NOT EXISTS idx_notifications_priority ON notifications(priority);
+CREATE INDEX IF NOT EXISTS idx_notifications_is_read ON notifications(is_read);
+CREATE INDEX IF NOT EXISTS idx_notifications_created_at ON notifications(created_at DESC);
+CREATE INDEX IF NOT EXISTS idx_notification_reads_notification ON notification_reads(notification_id);
+CREATE INDEX IF NOT EXISTS idx_notification_reads_user ON notification_reads(user_id);
+CREATE INDEX IF NOT EXISTS idx_notification_reads_unique ON notification_reads(notification_id, user_id);
+CREATE INDEX IF NOT EXISTS idx_audit_logs_user ON audit_logs(user_id);
+CREATE INDEX IF NOT EXISTS idx_audit_logs_action ON audit_logs(action);
+CREATE INDEX IF NOT EXISTS idx_audit_logs_created_at ON audit_logs(created_at DESC);
+CREATE INDEX IF NOT EXISTS idx_download_logs_resource ON download_logs(resource_id);
+CREATE INDEX IF NOT EXISTS idx_download_logs_user ON download_logs(user_id);
+CREATE INDEX IF NOT EXISTS idx_download_logs_time ON download_logs(downloaded_at DESC);
+CREATE INDEX IF NOT EXISTS idx_images_uploader ON images(uploader_id);
+CREATE INDEX IF NOT EXISTS idx_images_storage_type ON images(storage_type);
+CREATE INDEX IF NOT EXISTS idx_teachers_sn ON teachers(sn);
+CREATE INDEX IF NOT EXISTS idx_teachers_department ON teachers(department);
+CREATE INDEX IF NOT EXISTS idx_teachers_is_active ON teachers(is_active);
+CREATE INDEX IF NOT EXISTS idx_teachers_name_trgm ON teachers USING GIN(name gin_trgm_ops);
+CREATE INDEX IF NOT EXISTS idx_courses_sn ON courses(sn);
+CREATE INDEX IF NOT EXISTS idx_courses_semester ON courses(semester);
+CREATE INDEX IF NOT EXISTS idx_courses_is_active ON courses(is_active);
+CREATE INDEX IF NOT EXISTS idx_courses_name_trgm ON courses USING GIN(name gin_trgm_ops);
+CREATE INDEX IF NOT EXISTS idx_resource_teachers_resource ON resource_teachers(resource_id);
+CREATE INDEX IF NOT EXISTS idx_resource_teachers_teacher ON resource_teachers(teacher_sn);
+CREATE INDEX IF NOT EXISTS idx_resource_courses_resource ON resource_courses(resource_id);
+CREATE INDEX IF NOT EXISTS idx_resource_courses_course ON resource_courses(course_sn);
+
+-- 资源关联表索引
+CREATE INDEX IF NOT EXISTS idx_resource_relations_source ON resource_relations(source_resource_id);
+CREATE INDEX IF NOT EXISTS idx_resource_relations_target ON resource_relations(target_resource_id);
+
+-- 资源版本表索引
+CREATE INDEX IF NOT EXISTS idx_resource_versions_resource ON resource_versions(resource_id, version_number DESC);
+
+-- 打包下载任务表索引
+CREATE INDEX IF NOT EXISTS idx_pack_jobs_user ON pack_jobs(user_id, created_at DESC);
+CREATE INDEX IF NOT EXISTS idx_pack_jobs_status_expires ON pack_jobs(status, expires_at);
+
+-- 存储迁移任务表索引
+CREATE INDEX IF NOT EXISTS idx_storage_migration_jobs_created ON storage_migration_jobs(created_at DESC);
+
+-- 登录会话表索引
+CREATE INDEX IF NOT EXISTS idx_user_sessions_user ON user_sessions(user_id, last_used_at DESC);
+CREATE INDEX IF NOT EXISTS idx_user_sessions_expires ON user_sessions(expires_at);
+
+-- 邮件令牌表索引
+CREATE INDEX IF NOT EXISTS idx_email_tokens_user_purpose ON email_tokens(user_id, purpose);
+CREATE INDEX IF NOT EXISTS idx_email_tokens_expires ON email_tokens(expires_at);
+
+-- 两步验证恢复码表索引
+CREATE INDEX IF NOT EXISTS idx_user_recovery_codes_user ON user_recovery_codes(user_id);
+
+-- 外部身份绑定表索引
+CREATE INDEX IF NOT EXISTS idx_user_identities_user ON user_identities(user_id);
+
+-- OIDC 登录状态表索引
+CREATE UNIQUE INDEX IF NOT EXISTS idx_oidc_login_states_state_hash ON oidc_login_states(state_hash);
+CREATE INDEX IF NOT EXISTS idx_oidc_login_states_expires ON oidc_login_states(expires_at);
+
+-- 用户角色分配表索引（同一角色在同一范围内只能分配一次）
+CREATE UNIQUE INDEX IF NOT EXISTS idx_user_roles_unique ON user_roles(user_id, role_id, COALESCE(course_sn, 0));
+CREATE INDEX IF NOT EXISTS idx_user_roles_role ON user_roles(role_id);
+
+-- 实名认证申请表索引（每个用户最多一个待审核申请）
+CREATE UNIQUE INDEX IF NOT EXISTS idx_verification_requests_pending ON verification_requests(user_id) WHERE status = 'pending';
+CREATE INDEX IF NOT EXISTS idx_verification_requests_user ON verification_requests(user_id, created_at DESC);
+CREATE INDEX IF NOT EXISTS idx_verification_requests_status ON verification_requests(status, created_at);
+
+-- 资源统计表索引
+CREATE INDEX IF NOT EXISTS idx_resource_stats_resource ON resource_stats(resource_id);
+
+-- ============================================
+-- 创建触发器
+-- ============================================
+CREATE OR REPLACE FUNCTION update_updated_at_column()
+RETURNS TRIGGER AS $$
+BEGIN
+    NEW.updated_at = CURRENT_TIMESTAMP;
+    RETURN NEW;
+END;
+$$
+language 'plpgsql';
+
+DROP TRIGGER IF EXISTS update_users_updated_at ON users;
+CREATE TRIGGER update_users_updated_at
+    BEFORE UPDATE ON users
+    FOR EACH ROW
+    EXECUTE FUNCTION update_updated_at_column();
+
+DROP TRIGGER IF EXISTS update_resources_updated_at ON resources;
+CREATE TRIGGER update_resources_updated_at
+    BEFORE UPDATE ON resources
+    FOR EACH ROW
+    EXECUTE FUNCTION update_updated_at_column();
+
+DROP TRIGGER IF EXISTS update_ratings_updated_at ON ratings;
+CREATE TRIGGER update_ratings_updated_at
+    BEFORE UPDATE ON ratings
+    FOR EACH ROW
+    EXECUTE FUNCTION update_updated_at_column();
+
+DROP TRIGGER IF EXISTS update_comments_updated_at ON comments;
+CREATE TRIGGER update_comments_updated_at
+    BEFORE UPDATE ON comments
+    FOR EACH ROW
+    EXECUTE FUNCTION update_updated_at_column();
+
+DROP TRIGGER IF EXISTS update_teachers_updated_at ON teachers;
+CREATE TRIGGER update_teachers_updated_at
+    BEFORE UPDATE ON teachers
+    FOR EACH ROW
+    EXECUTE FUNCTION update_updated_at_column();
+
+DROP TRIGGER IF EXISTS update_courses_updated_at ON courses;
+CREATE TRIGGER update_courses_updated_at
+    BEFORE UPDATE ON courses
+    FOR EACH ROW
+    EXECUTE FUNCTION update_updated_at_column();
+
+-- 资源全文检索触发器
+-- 优先使用中文分词配置（如 zhparser 创建的 chinese 配置），否则退回 simple 配置
+CREATE OR REPLACE FUNCTION resource_search_config()
+RETURNS regconfig AS $$
+    SELECT COALESCE(
+        (SELECT oid::regconfig FROM pg_ts_config WHERE cfgname = 'chinese' LIMIT 1),
+        'simple'::regconfig
+    );
+$$ LANGUAGE sql STABLE;
+
+CREATE OR REPLACE FUNCTION resource_tags_text(tags JSONB)
+RETURNS TEXT AS $$
+    SELECT COALESCE(string_agg(value, ' '), '')
+    FROM jsonb_array_elements_text(
+        CASE WHEN jsonb_typeof(tags) = 'array' THEN tags ELSE '[]'::jsonb END
+    );
+$$ LANGUAGE sql IMMUTABLE;
+
+CREATE OR REPLACE FUNCTION update_resource_search_fields()
+RETURNS TRIGGER AS $$
+DECLARE
+    cfg regconfig := resource_search_config();
+    tags_text TEXT := resource_tags_text(NEW.tags);
+BEGIN
+    NEW.search_vector :=
+        setweight(to_tsvector(cfg, COALESCE(NEW.title, '')), 'A') ||
+        setweight(to_tsvector(cfg, COALESCE(NEW.course_name, '')), 'A') ||
+        setweight(to_tsvector(cfg, tags_text), 'B') ||
+        setweight(to_tsvector(cfg, COALESCE(NEW.description, '')), 'C');
+    NEW.search_document := concat_ws(' ', NEW.title, NEW.course_name, tags_text, NEW.description);
+    RETURN NEW;
+END;
+$$ language 'plpgsql';
+
+DROP TRIGGER IF EXISTS update_resources_search_fields ON resources;
+CREATE TRIGGER update_resources_search_fields
+    BEFORE INSERT OR UPDATE OF title, course_name, tags, description ON resources
+    FOR EACH ROW
+    EXECUTE FUNCTION update_resource_search_fields();
+
+-- 为已有资源回填检索字段（不修改 updated_at）
+DO $$
+BEGIN
+    IF EXISTS (SELECT 1 FROM resources WHERE search_vector IS NULL) THEN
+        ALTER TABLE resources DISABLE TRIGGER update_resources_updated_at;
+        UPDATE resources SET title = title WHERE search_vector IS NULL;
+        ALTER TABLE resources ENABLE TRIGGER update_resources_updated_at;
+    END IF;
+END $$;
 
 -- ============================================
 -- 验证
@@ -1241,6 +2295,23 @@ def main():
     print("  - resource_teachers (资源教师关联表)")
     print("  - resource_courses (资源课程关联表)")
     print("  - resource_relations (资源关联表)")
+    print("  - resource_versions (资源版本表)")
+    print("  - pack_jobs (打包下载任务表)")
+    print("  - storage_migration_jobs (存储迁移任务表)")
+    print("  - user_sessions (登录会话表)")
+    print("  - email_tokens (邮件令牌表)")
+    print("  - user_recovery_codes (两步验证恢复码表)")
+    print("  - user_identities (外部身份绑定表)")
+    print("  - oidc_login_states (OIDC 登录状态表)")
+    print("  - roles (角色表)")
+    print("  - user_roles (用户角色分配表)")
+    print("  - verification_requests (实名认证申请表)")
+    print("  - comment_edits (评论编辑历史表)")
+    print("  - comment_votes (评论投票表)")
+    print("  - reports (举报表)")
+    print("  - resource_similarities (资源相似度表)")
+    print("  - resource_daily_views (资源每日浏览量表)")
+    print("  - resource_trending_scores (资源热度分表)")
     print()
     print("索引: 42+")
     print("触发器: 6 (自动更新 updated_at)")